@@ -1,7 +1,7 @@
 use crate::icrc1::transfer::BlockIndex;
 
 use super::{
-    blocks::{BlockRange, GetBlocksRequest},
+    blocks::{BlockRange, GetBlocksRequest, GetBlocksResult},
     transactions::{GetTransactionsRequest, TransactionRange},
 };
 use candid::{CandidType, Deserialize, Nat, Principal};
@@ -74,8 +74,10 @@ impl<Input: CandidType, Output: CandidType> CandidType for QueryArchiveFn<Input,
     fn _ty() -> candid::types::Type {
         candid::types::Type::Func(candid::types::Function {
             modes: vec![candid::parser::types::FuncMode::Query],
-            args: vec![Input::_ty()],
-            rets: vec![Output::_ty()],
+            // NB. We use `ty` instead of `_ty` to support recursive types,
+            // e.g., the ICRC-3 callback returning the type containing it.
+            args: vec![Input::ty()],
+            rets: vec![Output::ty()],
         })
     }
 
@@ -95,3 +97,24 @@ pub struct ArchiveInfo {
 }
 pub type QueryBlockArchiveFn = QueryArchiveFn<GetBlocksRequest, BlockRange>;
 pub type QueryTxArchiveFn = QueryArchiveFn<GetTransactionsRequest, TransactionRange>;
+pub type ICRC3QueryBlockArchiveFn = QueryArchiveFn<Vec<GetBlocksRequest>, GetBlocksResult>;
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GetArchivesArgs {
+    // The last archive seen by the client.
+    // The ledger returns archives coming after this one if set,
+    // otherwise it returns the first archives.
+    pub from: Option<Principal>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ICRC3ArchiveInfo {
+    // The id of the archive.
+    pub canister_id: Principal,
+    // The first block in the archive.
+    pub start: Nat,
+    // The last block in the archive.
+    pub end: Nat,
+}
+
+pub type GetArchivesResult = Vec<ICRC3ArchiveInfo>;
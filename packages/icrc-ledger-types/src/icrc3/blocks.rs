@@ -1,5 +1,5 @@
 use crate::icrc3::archive::ArchivedRange;
-use crate::icrc3::archive::QueryBlockArchiveFn;
+use crate::icrc3::archive::{ICRC3QueryBlockArchiveFn, QueryBlockArchiveFn};
 use crate::{icrc::generic_value::Value, icrc1::transfer::BlockIndex};
 use candid::{CandidType, Deserialize, Nat};
 use serde_bytes::ByteBuf;
@@ -52,3 +52,37 @@ pub struct DataCertificate {
     pub certificate: Option<serde_bytes::ByteBuf>,
     pub hash_tree: serde_bytes::ByteBuf,
 }
+
+#[derive(Debug, CandidType, Clone, Deserialize, PartialEq, Eq)]
+pub struct ICRC3DataCertificate {
+    //  See https://internetcomputer.org/docs/current/references/ic-interface-spec#certification
+    pub certificate: serde_bytes::ByteBuf,
+    // CBOR encoded hash_tree
+    pub hash_tree: serde_bytes::ByteBuf,
+}
+
+#[derive(Debug, CandidType, Clone, Deserialize, PartialEq, Eq)]
+pub struct BlockWithId {
+    pub id: Nat,
+    pub block: GenericBlock,
+}
+
+#[derive(Debug, CandidType, Clone, Deserialize, PartialEq, Eq)]
+pub struct ArchivedBlocks {
+    pub args: Vec<GetBlocksRequest>,
+    pub callback: ICRC3QueryBlockArchiveFn,
+}
+
+#[derive(Debug, CandidType, Clone, Deserialize, PartialEq, Eq)]
+pub struct GetBlocksResult {
+    // Total number of blocks in the block log.
+    pub log_length: Nat,
+    pub blocks: Vec<BlockWithId>,
+    pub archived_blocks: Vec<ArchivedBlocks>,
+}
+
+#[derive(Debug, CandidType, Clone, Deserialize, PartialEq, Eq)]
+pub struct SupportedBlockType {
+    pub block_type: String,
+    pub url: String,
+}
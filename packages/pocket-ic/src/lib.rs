@@ -9,10 +9,19 @@ use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_bytes::ByteBuf;
 use std::fmt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::{Duration, Instant, SystemTime};
 
+pub mod nonblocking;
+pub use nonblocking::PocketIcAsync;
+
+/// The names of the secp256k1 ECDSA keys that are available on every subnet.
+/// `sign_with_ecdsa` requests for these keys are answered in the next tick
+/// with signatures by a fixed test key, so that they are deterministic and
+/// verify against the public key returned by `ecdsa_public_key`.
+pub const ECDSA_TEST_KEY_NAMES: [&str; 3] = ["dfx_test_key", "test_key_1", "key_1"];
+
 const LOCALHOST: &str = "127.0.0.1";
 const POCKET_IC_BIN_PATH: &str = "../../target/debug/pocket-ic-backend";
 
@@ -52,6 +61,25 @@ fn get_service_port(lock_file: PathBuf, bin_path: PathBuf) -> u16 {
     }
 }
 
+fn start_or_reuse_daemon() -> Url {
+    // use the parent process ID, so that we have one PocketIC per `cargo test` invocation
+    let ppid = std::os::unix::process::parent_id();
+    let lock_file = std::env::temp_dir().join(format!("pocket_ic.{}.port", ppid));
+    let port = get_service_port(lock_file, PathBuf::from(POCKET_IC_BIN_PATH));
+    let url = Url::parse(&format!("http://{}:{}/", LOCALHOST, port)).expect("Failed to parse url.");
+    println!("Found PocketIC server running on {}", &url);
+    url
+}
+
+// The endpoint through which all communication with the given IC instance goes.
+fn instance_url(daemon_url: &Url, instance_id: &InstanceId) -> Url {
+    daemon_url
+        .join("instance/")
+        .unwrap()
+        .join(instance_id)
+        .unwrap()
+}
+
 // ======================================================================================================
 // Code borrowed from https://github.com/dfinity/test-state-machine-client/blob/main/src/lib.rs
 // The StateMachine struct is renamed to `PocketIc` and given new interface.
@@ -66,21 +94,44 @@ pub struct PocketIc {
 }
 
 impl PocketIc {
+    /// Creates a new IC instance with a single system subnet.
     pub fn new() -> Self {
+        Self::create_instance("instance", None::<&SubnetConfigSet>)
+    }
+
+    /// Creates a new IC instance with the given subnets, which share a routing
+    /// table and exchange cross-subnet messages on every tick.
+    pub fn from_config(config: SubnetConfigSet) -> Self {
+        Self::create_instance("instance", Some(&config))
+    }
+
+    /// Creates a new IC instance from a checkpoint written by
+    /// [PocketIc::checkpoint]. The checkpoint is not modified, so any number
+    /// of instances can be created from it.
+    pub fn from_checkpoint(checkpoint_dir: impl AsRef<Path>) -> Self {
+        Self::create_instance(
+            "instance/from_checkpoint",
+            Some(&CheckpointArg {
+                checkpoint_dir: checkpoint_dir.as_ref().to_path_buf(),
+            }),
+        )
+    }
+
+    fn create_instance<B: Serialize>(endpoint: &str, body: Option<&B>) -> Self {
         let reqwest_client = reqwest::blocking::Client::new();
-        let daemon_url = Self::start_or_reuse_daemon();
-        let instance_id = reqwest_client
-            .post(daemon_url.join("instance").unwrap())
-            .send()
-            .expect("Failed to get result")
-            .text()
-            .expect("Failed to get text");
+        let daemon_url = start_or_reuse_daemon();
+        let mut request = reqwest_client.post(daemon_url.join(endpoint).unwrap());
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let response = request.send().expect("Failed to get result");
+        let status = response.status();
+        let instance_id = response.text().expect("Failed to get text");
+        if !status.is_success() {
+            panic!("Failed to create instance: {}", instance_id);
+        }
         println!("Created new instance with id {}", instance_id);
-        let instance_url = daemon_url
-            .join("instance/")
-            .unwrap()
-            .join(&instance_id)
-            .unwrap();
+        let instance_url = instance_url(&daemon_url, &instance_id);
 
         Self {
             instance_id,
@@ -90,17 +141,6 @@ impl PocketIc {
         }
     }
 
-    fn start_or_reuse_daemon() -> Url {
-        // use the parent process ID, so that we have one PocketIC per `cargo test` invocation
-        let ppid = std::os::unix::process::parent_id();
-        let lock_file = std::env::temp_dir().join(format!("pocket_ic.{}.port", ppid));
-        let port = get_service_port(lock_file, PathBuf::from(POCKET_IC_BIN_PATH));
-        let url =
-            Url::parse(&format!("http://{}:{}/", LOCALHOST, port)).expect("Failed to parse url.");
-        println!("Found PocketIC server running on {}", &url);
-        url
-    }
-
     pub fn list_instances(&self) -> Vec<InstanceId> {
         let url = self.daemon_url.join("instance").unwrap();
         let response = reqwest::blocking::Client::new()
@@ -129,10 +169,30 @@ impl PocketIc {
         sender: Principal,
         method: &str,
         arg: Vec<u8>,
+    ) -> Result<WasmResult, UserError> {
+        self.update_call_with_effective_principal(
+            canister_id,
+            RawEffectivePrincipal::None,
+            sender,
+            method,
+            arg,
+        )
+    }
+
+    /// Executes an update call, routing calls to the management canister to
+    /// the subnet given by the effective principal.
+    pub fn update_call_with_effective_principal(
+        &self,
+        canister_id: Principal,
+        effective_principal: RawEffectivePrincipal,
+        sender: Principal,
+        method: &str,
+        arg: Vec<u8>,
     ) -> Result<WasmResult, UserError> {
         self.call_state_machine(Request::CanisterUpdateCall(CanisterCall {
             sender: sender.as_slice().to_vec(),
             canister_id: canister_id.as_slice().to_vec(),
+            effective_principal,
             method: method.to_string(),
             arg,
         }))
@@ -148,15 +208,102 @@ impl PocketIc {
         self.call_state_machine(Request::CanisterQueryCall(CanisterCall {
             sender: sender.as_slice().to_vec(),
             canister_id: canister_id.as_slice().to_vec(),
+            effective_principal: RawEffectivePrincipal::None,
             method: method.to_string(),
             arg,
         }))
     }
 
+    /// Submits an update call without waiting for its result, which can be
+    /// retrieved later with [PocketIc::await_call]. The call is inducted and
+    /// starts executing in a new round, so that several calls can be in
+    /// flight at the same time, e.g., to test interleavings of messages or
+    /// deterministic time slicing.
+    pub fn submit_call(
+        &self,
+        canister_id: Principal,
+        sender: Principal,
+        method: &str,
+        arg: Vec<u8>,
+    ) -> Result<RawMessageId, UserError> {
+        self.submit_call_with_effective_principal(
+            canister_id,
+            RawEffectivePrincipal::None,
+            sender,
+            method,
+            arg,
+        )
+    }
+
+    /// Like [PocketIc::submit_call], but routes calls to the management
+    /// canister to the subnet given by the effective principal.
+    pub fn submit_call_with_effective_principal(
+        &self,
+        canister_id: Principal,
+        effective_principal: RawEffectivePrincipal,
+        sender: Principal,
+        method: &str,
+        arg: Vec<u8>,
+    ) -> Result<RawMessageId, UserError> {
+        self.call_state_machine(Request::SubmitCall(CanisterCall {
+            sender: sender.as_slice().to_vec(),
+            canister_id: canister_id.as_slice().to_vec(),
+            effective_principal,
+            method: method.to_string(),
+            arg,
+        }))
+    }
+
+    /// Ticks the instance until the result of a call submitted with
+    /// [PocketIc::submit_call] is known and returns it.
+    pub fn await_call(&self, message_id: RawMessageId) -> Result<WasmResult, UserError> {
+        self.call_state_machine(Request::AwaitCall(message_id))
+    }
+
+    /// Returns the canister HTTP requests (HTTPS outcalls) of all subnets
+    /// that have not been answered yet. Outcalls are never performed by
+    /// PocketIC, but have to be answered with
+    /// [PocketIc::mock_canister_http_response].
+    pub fn get_canister_http(&self) -> Vec<CanisterHttpRequest> {
+        self.call_state_machine(Request::GetCanisterHttp)
+    }
+
+    /// Answers a pending canister HTTP request. A reply is passed through
+    /// the transform function of the request, if any, before it is delivered
+    /// to the canister in a new round.
+    pub fn mock_canister_http_response(
+        &self,
+        mock_canister_http_response: MockCanisterHttpResponse,
+    ) -> Result<(), String> {
+        self.call_state_machine(Request::MockCanisterHttp(mock_canister_http_response))
+    }
+
     pub fn root_key(&self) -> Vec<u8> {
         self.call_state_machine(Request::RootKey)
     }
 
+    /// Returns the URL under which this instance serves the public
+    /// `/api/v2` HTTP interface, e.g., for use with an `ic-agent`.
+    /// Requests are validated against the instance time, so it should be
+    /// set close to the current time (see [PocketIc::set_time]) before
+    /// sending signed requests with a real ingress expiry.
+    pub fn url(&self) -> Url {
+        Url::parse(&format!("{}/", self.instance_url)).expect("Failed to parse url.")
+    }
+
+    /// Returns the subnets of this instance and their canister ranges.
+    pub fn topology(&self) -> Topology {
+        self.call_state_machine(Request::Topology)
+    }
+
+    /// Returns the subnet that hosts the given canister ID according to the
+    /// routing table.
+    pub fn get_subnet(&self, canister_id: Principal) -> Option<Principal> {
+        let subnet_id: Option<RawSubnetId> =
+            self.call_state_machine(Request::GetSubnet(RawCanisterId::from(canister_id)));
+        subnet_id.map(|subnet_id| Principal::from_slice(&subnet_id.subnet_id))
+    }
+
     pub fn create_canister(&self, sender: Option<Principal>) -> CanisterId {
         let CanisterIdRecord { canister_id } = call_candid_as(
             self,
@@ -187,6 +334,28 @@ impl PocketIc {
         canister_id
     }
 
+    /// Creates a canister on the given subnet.
+    pub fn create_canister_on_subnet(
+        &self,
+        sender: Option<Principal>,
+        settings: Option<CanisterSettings>,
+        subnet_id: Principal,
+    ) -> CanisterId {
+        let CanisterIdRecord { canister_id } =
+            with_candid((CreateCanisterArgument { settings },), |bytes| {
+                self.update_call_with_effective_principal(
+                    Principal::management_canister(),
+                    RawEffectivePrincipal::SubnetId(subnet_id.as_slice().to_vec()),
+                    sender.unwrap_or(Principal::anonymous()),
+                    "create_canister",
+                    bytes,
+                )
+            })
+            .map(|(x,)| x)
+            .unwrap();
+        canister_id
+    }
+
     pub fn install_canister(
         &self,
         canister_id: CanisterId,
@@ -313,7 +482,18 @@ impl PocketIc {
         self.call_state_machine(Request::Tick)
     }
 
-    pub fn run_until_completion(&self, max_ticks: u64) {
+    /// Writes a checkpoint of the replicated state of all subnets to
+    /// `checkpoint_dir`, which must not exist or be empty. The directory must
+    /// be accessible to the PocketIC server.
+    pub fn checkpoint(&self, checkpoint_dir: impl AsRef<Path>) -> Result<(), String> {
+        self.call_state_machine(Request::Checkpoint(CheckpointArg {
+            checkpoint_dir: checkpoint_dir.as_ref().to_path_buf(),
+        }))
+    }
+
+    /// Ticks until no subnet has any messages left to process or deliver.
+    /// Returns an error if this is not the case after `max_ticks` ticks.
+    pub fn run_until_completion(&self, max_ticks: u64) -> Result<(), String> {
         self.call_state_machine(Request::RunUntilCompletion(RunUntilCompletionArg {
             max_ticks,
         }))
@@ -334,6 +514,35 @@ impl PocketIc {
         self.call_state_machine(Request::CyclesBalance(RawCanisterId::from(canister_id)))
     }
 
+    /// Returns the instruction profile of the latest execution of the given
+    /// entry point of a canister in the folded stack format, which can be
+    /// turned into a flamegraph by `flamegraph.pl` or `inferno-flamegraph`.
+    /// Entry points are named like `canister_update transfer`,
+    /// `canister_heartbeat` or `canister_post_upgrade`; responses are profiled
+    /// as `callback`. Profiles are only recorded by instances created with
+    /// [SubnetConfigSet::canister_profiling].
+    pub fn execution_profile(&self, canister_id: Principal, entry_point: &str) -> Option<String> {
+        self.call_state_machine(Request::GetExecutionProfile(ExecutionProfileArg {
+            canister_id: RawCanisterId::from(canister_id),
+            entry_point: entry_point.to_string(),
+        }))
+    }
+
+    /// Executes an update call and returns its result together with the
+    /// instruction profile of the called method, see
+    /// [PocketIc::execution_profile].
+    pub fn update_call_with_profile(
+        &self,
+        canister_id: Principal,
+        sender: Principal,
+        method: &str,
+        arg: Vec<u8>,
+    ) -> (Result<WasmResult, UserError>, Option<String>) {
+        let result = self.update_call(canister_id, sender, method, arg);
+        let profile = self.execution_profile(canister_id, &format!("canister_update {}", method));
+        (result, profile)
+    }
+
     pub fn add_cycles(&self, canister_id: Principal, amount: u128) -> u128 {
         self.call_state_machine(Request::AddCycles(AddCyclesArg {
             canister_id: canister_id.as_slice().to_vec(),
@@ -373,11 +582,17 @@ impl Default for PocketIc {
 #[derive(Serialize, Deserialize)]
 pub enum Request {
     RootKey,
+    Topology,
+    GetSubnet(RawCanisterId),
     Time,
     SetTime(SystemTime),
     AdvanceTime(Duration),
     CanisterUpdateCall(CanisterCall),
     CanisterQueryCall(CanisterCall),
+    SubmitCall(CanisterCall),
+    AwaitCall(RawMessageId),
+    GetCanisterHttp,
+    MockCanisterHttp(MockCanisterHttpResponse),
     CanisterExists(RawCanisterId),
     CyclesBalance(RawCanisterId),
     AddCycles(AddCyclesArg),
@@ -385,7 +600,16 @@ pub enum Request {
     ReadStableMemory(RawCanisterId),
     Tick,
     RunUntilCompletion(RunUntilCompletionArg),
+    Checkpoint(CheckpointArg),
     VerifyCanisterSig(VerifyCanisterSigArg),
+    GetExecutionProfile(ExecutionProfileArg),
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExecutionProfileArg {
+    pub canister_id: RawCanisterId,
+    // e.g. `canister_update transfer`, `canister_heartbeat` or `callback`
+    pub entry_point: String,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -406,6 +630,12 @@ pub struct RunUntilCompletionArg {
     pub max_ticks: u64,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct CheckpointArg {
+    // a directory on the machine running the PocketIC server
+    pub checkpoint_dir: PathBuf,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct AddCyclesArg {
     // raw bytes of the principal
@@ -422,7 +652,7 @@ pub struct SetStableMemoryArg {
     pub data: ByteBuf,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RawCanisterId {
     // raw bytes of the principal
     #[serde(with = "base64")]
@@ -437,17 +667,180 @@ impl From<Principal> for RawCanisterId {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RawSubnetId {
+    // raw bytes of the principal
+    #[serde(with = "base64")]
+    pub subnet_id: Vec<u8>,
+}
+
+impl From<Principal> for RawSubnetId {
+    fn from(principal: Principal) -> Self {
+        Self {
+            subnet_id: principal.as_slice().to_vec(),
+        }
+    }
+}
+
+/// Determines the subnet that a call to the management canister is routed to,
+/// unless the call's arguments already name the target canister.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RawEffectivePrincipal {
+    #[default]
+    None,
+    SubnetId(#[serde(with = "base64")] Vec<u8>),
+    CanisterId(#[serde(with = "base64")] Vec<u8>),
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct CanisterCall {
     #[serde(with = "base64")]
     pub sender: Vec<u8>,
     #[serde(with = "base64")]
     pub canister_id: Vec<u8>,
+    #[serde(default)]
+    pub effective_principal: RawEffectivePrincipal,
     pub method: String,
     #[serde(with = "base64")]
     pub arg: Vec<u8>,
 }
 
+/// Identifies an ingress message submitted with [PocketIc::submit_call].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RawMessageId {
+    // the subnet to which the message was submitted
+    pub subnet_id: RawSubnetId,
+    #[serde(with = "base64")]
+    pub message_id: Vec<u8>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CanisterHttpMethod {
+    GET,
+    POST,
+    HEAD,
+    PUT,
+    DELETE,
+    PATCH,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CanisterHttpHeader {
+    pub name: String,
+    pub value: String,
+}
+
+/// A pending canister HTTP request, see [PocketIc::get_canister_http].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CanisterHttpRequest {
+    // the subnet on which the request was made
+    pub subnet_id: RawSubnetId,
+    pub request_id: u64,
+    pub http_method: CanisterHttpMethod,
+    pub url: String,
+    pub headers: Vec<CanisterHttpHeader>,
+    #[serde(with = "base64")]
+    pub body: Vec<u8>,
+    pub max_response_bytes: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CanisterHttpReply {
+    pub status: u16,
+    pub headers: Vec<CanisterHttpHeader>,
+    #[serde(with = "base64")]
+    pub body: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CanisterHttpReject {
+    // one of the reject codes of the IC interface specification, e.g., 2 for SYS_TRANSIENT
+    pub reject_code: u64,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CanisterHttpResponse {
+    CanisterHttpReply(CanisterHttpReply),
+    CanisterHttpReject(CanisterHttpReject),
+}
+
+/// The response to a pending canister HTTP request, see
+/// [PocketIc::mock_canister_http_response].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MockCanisterHttpResponse {
+    pub subnet_id: RawSubnetId,
+    pub request_id: u64,
+    pub response: CanisterHttpResponse,
+}
+
+/// The kind of a subnet of a PocketIC instance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SubnetKind {
+    /// The NNS subnet, which is assigned the canister ranges of the NNS
+    /// canisters.
+    NNS,
+    System,
+    Application,
+}
+
+/// Specifies the subnets of a PocketIC instance.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubnetConfigSet {
+    pub nns: bool,
+    pub system: bool,
+    pub application: usize,
+    /// Records an instruction profile of every canister execution, see
+    /// [PocketIc::execution_profile]. Profiling slows down execution
+    /// considerably.
+    #[serde(default)]
+    pub canister_profiling: bool,
+}
+
+impl SubnetConfigSet {
+    /// Returns the kinds of the configured subnets in the order in which they
+    /// are assigned canister ranges.
+    pub fn subnet_kinds(&self) -> Vec<SubnetKind> {
+        let mut kinds = vec![];
+        if self.nns {
+            kinds.push(SubnetKind::NNS);
+        }
+        if self.system {
+            kinds.push(SubnetKind::System);
+        }
+        kinds.extend(std::iter::repeat(SubnetKind::Application).take(self.application));
+        kinds
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanisterIdRange {
+    pub start: RawCanisterId,
+    pub end: RawCanisterId,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubnetTopology {
+    pub subnet_id: RawSubnetId,
+    pub kind: SubnetKind,
+    pub canister_ranges: Vec<CanisterIdRange>,
+}
+
+/// The subnets of a PocketIC instance.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Topology(pub Vec<SubnetTopology>);
+
+impl Topology {
+    /// Returns the IDs of the subnets of the given kind.
+    pub fn subnet_ids(&self, kind: SubnetKind) -> Vec<Principal> {
+        self.0
+            .iter()
+            .filter(|subnet| subnet.kind == kind)
+            .map(|subnet| Principal::from_slice(&subnet.subnet_id.subnet_id))
+            .collect()
+    }
+}
+
 /// Call a canister candid query method, anonymous.
 pub fn query_candid<Input, Output>(
     env: &PocketIc,
@@ -613,7 +1006,16 @@ where
     Output: for<'a> ArgumentDecoder<'a>,
 {
     let in_bytes = encode_args(input).expect("failed to encode args");
-    match f(in_bytes) {
+    decode_candid_result(f(in_bytes))
+}
+
+// Decodes the candid reply of a call, or turns a reject or user error into a
+// `CallError`.
+fn decode_candid_result<Output>(result: Result<WasmResult, UserError>) -> Result<Output, CallError>
+where
+    Output: for<'a> ArgumentDecoder<'a>,
+{
+    match result {
         Ok(WasmResult::Reply(out_bytes)) => Ok(decode_args(&out_bytes).unwrap_or_else(|e| {
             panic!(
                 "Failed to decode response as candid type {}:\nerror: {}\nbytes: {:?}\nutf8: {}",
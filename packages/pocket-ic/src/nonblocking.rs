@@ -0,0 +1,592 @@
+//! An asynchronous client for PocketIC, which can be used from within a tokio
+//! runtime (where the blocking [crate::PocketIc] panics). Its API mirrors the
+//! one of [crate::PocketIc].
+
+use crate::{
+    decode_candid_result, instance_url, start_or_reuse_daemon, AddCyclesArg, CallError,
+    CanisterCall, CanisterHttpRequest, CheckpointArg, ExecutionProfileArg, InstanceId,
+    MockCanisterHttpResponse, RawCanisterId, RawEffectivePrincipal, RawMessageId, RawSubnetId,
+    Request, RunUntilCompletionArg, SetStableMemoryArg, SubnetConfigSet, Topology, UserError,
+    VerifyCanisterSigArg, WasmResult,
+};
+use candid::utils::{ArgumentDecoder, ArgumentEncoder};
+use candid::{encode_args, Principal};
+use ic_cdk::api::management_canister::main::{
+    CanisterId, CanisterIdRecord, CanisterInstallMode, CanisterSettings, CreateCanisterArgument,
+    InstallCodeArgument,
+};
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_bytes::ByteBuf;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+pub struct PocketIcAsync {
+    pub instance_id: InstanceId,
+    // The PocketIC server's base address.
+    daemon_url: Url,
+    // The PocketIC server's base address plus "/instance/<instance_id>".
+    // All communication with this IC instance goes through this endpoint.
+    instance_url: Url,
+    reqwest_client: reqwest::Client,
+}
+
+impl PocketIcAsync {
+    /// Creates a new IC instance with a single system subnet.
+    pub async fn new() -> Self {
+        Self::create_instance("instance", None::<&SubnetConfigSet>).await
+    }
+
+    /// Creates a new IC instance with the given subnets, which share a routing
+    /// table and exchange cross-subnet messages on every tick.
+    pub async fn from_config(config: SubnetConfigSet) -> Self {
+        Self::create_instance("instance", Some(&config)).await
+    }
+
+    /// Creates a new IC instance from a checkpoint written by
+    /// [PocketIcAsync::checkpoint].
+    pub async fn from_checkpoint(checkpoint_dir: impl AsRef<Path>) -> Self {
+        Self::create_instance(
+            "instance/from_checkpoint",
+            Some(&CheckpointArg {
+                checkpoint_dir: checkpoint_dir.as_ref().to_path_buf(),
+            }),
+        )
+        .await
+    }
+
+    async fn create_instance<B: Serialize>(endpoint: &str, body: Option<&B>) -> Self {
+        let reqwest_client = reqwest::Client::new();
+        // Starting the server only takes a few milliseconds, so it is fine to
+        // do it synchronously.
+        let daemon_url = start_or_reuse_daemon();
+        let mut request = reqwest_client.post(daemon_url.join(endpoint).unwrap());
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let response = request.send().await.expect("Failed to get result");
+        let status = response.status();
+        let instance_id = response.text().await.expect("Failed to get text");
+        if !status.is_success() {
+            panic!("Failed to create instance: {}", instance_id);
+        }
+        println!("Created new instance with id {}", instance_id);
+        let instance_url = instance_url(&daemon_url, &instance_id);
+
+        Self {
+            instance_id,
+            daemon_url,
+            instance_url,
+            reqwest_client,
+        }
+    }
+
+    pub async fn list_instances(&self) -> Vec<InstanceId> {
+        let url = self.daemon_url.join("instance").unwrap();
+        let response = self
+            .reqwest_client
+            .get(url)
+            .send()
+            .await
+            .expect("Failed to get result")
+            .text()
+            .await
+            .expect("Failed to get text");
+        response.split(", ").map(String::from).collect()
+    }
+
+    pub async fn send_request(&self, request: Request) -> String {
+        self.reqwest_client
+            .post(self.instance_url.clone())
+            .json(&request)
+            .send()
+            .await
+            .expect("Failed to get result")
+            .text()
+            .await
+            .expect("Failed to get text")
+    }
+    // ------------------------------------------------------------------
+
+    pub async fn update_call(
+        &self,
+        canister_id: Principal,
+        sender: Principal,
+        method: &str,
+        arg: Vec<u8>,
+    ) -> Result<WasmResult, UserError> {
+        self.update_call_with_effective_principal(
+            canister_id,
+            RawEffectivePrincipal::None,
+            sender,
+            method,
+            arg,
+        )
+        .await
+    }
+
+    /// Executes an update call, routing calls to the management canister to
+    /// the subnet given by the effective principal.
+    pub async fn update_call_with_effective_principal(
+        &self,
+        canister_id: Principal,
+        effective_principal: RawEffectivePrincipal,
+        sender: Principal,
+        method: &str,
+        arg: Vec<u8>,
+    ) -> Result<WasmResult, UserError> {
+        self.call_state_machine(Request::CanisterUpdateCall(CanisterCall {
+            sender: sender.as_slice().to_vec(),
+            canister_id: canister_id.as_slice().to_vec(),
+            effective_principal,
+            method: method.to_string(),
+            arg,
+        }))
+        .await
+    }
+
+    pub async fn query_call(
+        &self,
+        canister_id: Principal,
+        sender: Principal,
+        method: &str,
+        arg: Vec<u8>,
+    ) -> Result<WasmResult, UserError> {
+        self.call_state_machine(Request::CanisterQueryCall(CanisterCall {
+            sender: sender.as_slice().to_vec(),
+            canister_id: canister_id.as_slice().to_vec(),
+            effective_principal: RawEffectivePrincipal::None,
+            method: method.to_string(),
+            arg,
+        }))
+        .await
+    }
+
+    /// Submits an update call without waiting for its result, which can be
+    /// retrieved later with [PocketIcAsync::await_call].
+    pub async fn submit_call(
+        &self,
+        canister_id: Principal,
+        sender: Principal,
+        method: &str,
+        arg: Vec<u8>,
+    ) -> Result<RawMessageId, UserError> {
+        self.submit_call_with_effective_principal(
+            canister_id,
+            RawEffectivePrincipal::None,
+            sender,
+            method,
+            arg,
+        )
+        .await
+    }
+
+    /// Like [PocketIcAsync::submit_call], but routes calls to the management
+    /// canister to the subnet given by the effective principal.
+    pub async fn submit_call_with_effective_principal(
+        &self,
+        canister_id: Principal,
+        effective_principal: RawEffectivePrincipal,
+        sender: Principal,
+        method: &str,
+        arg: Vec<u8>,
+    ) -> Result<RawMessageId, UserError> {
+        self.call_state_machine(Request::SubmitCall(CanisterCall {
+            sender: sender.as_slice().to_vec(),
+            canister_id: canister_id.as_slice().to_vec(),
+            effective_principal,
+            method: method.to_string(),
+            arg,
+        }))
+        .await
+    }
+
+    /// Ticks the instance until the result of a call submitted with
+    /// [PocketIcAsync::submit_call] is known and returns it.
+    pub async fn await_call(&self, message_id: RawMessageId) -> Result<WasmResult, UserError> {
+        self.call_state_machine(Request::AwaitCall(message_id))
+            .await
+    }
+
+    /// Returns the canister HTTP requests of all subnets that have not been
+    /// answered yet, see [crate::PocketIc::get_canister_http].
+    pub async fn get_canister_http(&self) -> Vec<CanisterHttpRequest> {
+        self.call_state_machine(Request::GetCanisterHttp).await
+    }
+
+    /// Answers a pending canister HTTP request, see
+    /// [crate::PocketIc::mock_canister_http_response].
+    pub async fn mock_canister_http_response(
+        &self,
+        mock_canister_http_response: MockCanisterHttpResponse,
+    ) -> Result<(), String> {
+        self.call_state_machine(Request::MockCanisterHttp(mock_canister_http_response))
+            .await
+    }
+
+    pub async fn root_key(&self) -> Vec<u8> {
+        self.call_state_machine(Request::RootKey).await
+    }
+
+    /// Returns the URL under which this instance serves the public
+    /// `/api/v2` HTTP interface, see [crate::PocketIc::url].
+    pub fn url(&self) -> Url {
+        Url::parse(&format!("{}/", self.instance_url)).expect("Failed to parse url.")
+    }
+
+    /// Returns the subnets of this instance and their canister ranges.
+    pub async fn topology(&self) -> Topology {
+        self.call_state_machine(Request::Topology).await
+    }
+
+    /// Returns the subnet that hosts the given canister ID according to the
+    /// routing table.
+    pub async fn get_subnet(&self, canister_id: Principal) -> Option<Principal> {
+        let subnet_id: Option<RawSubnetId> = self
+            .call_state_machine(Request::GetSubnet(RawCanisterId::from(canister_id)))
+            .await;
+        subnet_id.map(|subnet_id| Principal::from_slice(&subnet_id.subnet_id))
+    }
+
+    pub async fn create_canister(&self, sender: Option<Principal>) -> CanisterId {
+        self.create_canister_with_settings(None, sender).await
+    }
+
+    pub async fn create_canister_with_settings(
+        &self,
+        settings: Option<CanisterSettings>,
+        sender: Option<Principal>,
+    ) -> CanisterId {
+        let CanisterIdRecord { canister_id } = call_candid_as(
+            self,
+            Principal::management_canister(),
+            sender.unwrap_or(Principal::anonymous()),
+            "create_canister",
+            (CreateCanisterArgument { settings },),
+        )
+        .await
+        .map(|(x,)| x)
+        .unwrap();
+        canister_id
+    }
+
+    /// Creates a canister on the given subnet.
+    pub async fn create_canister_on_subnet(
+        &self,
+        sender: Option<Principal>,
+        settings: Option<CanisterSettings>,
+        subnet_id: Principal,
+    ) -> CanisterId {
+        let in_bytes =
+            encode_args((CreateCanisterArgument { settings },)).expect("failed to encode args");
+        let result = self
+            .update_call_with_effective_principal(
+                Principal::management_canister(),
+                RawEffectivePrincipal::SubnetId(subnet_id.as_slice().to_vec()),
+                sender.unwrap_or(Principal::anonymous()),
+                "create_canister",
+                in_bytes,
+            )
+            .await;
+        let (CanisterIdRecord { canister_id },) = decode_candid_result(result).unwrap();
+        canister_id
+    }
+
+    pub async fn install_canister(
+        &self,
+        canister_id: CanisterId,
+        wasm_module: Vec<u8>,
+        arg: Vec<u8>,
+        sender: Option<Principal>,
+    ) {
+        self.install_code(
+            CanisterInstallMode::Install,
+            canister_id,
+            wasm_module,
+            arg,
+            sender,
+        )
+        .await
+        .unwrap();
+    }
+
+    pub async fn upgrade_canister(
+        &self,
+        canister_id: CanisterId,
+        wasm_module: Vec<u8>,
+        arg: Vec<u8>,
+        sender: Option<Principal>,
+    ) -> Result<(), CallError> {
+        self.install_code(
+            CanisterInstallMode::Upgrade,
+            canister_id,
+            wasm_module,
+            arg,
+            sender,
+        )
+        .await
+    }
+
+    pub async fn reinstall_canister(
+        &self,
+        canister_id: CanisterId,
+        wasm_module: Vec<u8>,
+        arg: Vec<u8>,
+        sender: Option<Principal>,
+    ) -> Result<(), CallError> {
+        self.install_code(
+            CanisterInstallMode::Reinstall,
+            canister_id,
+            wasm_module,
+            arg,
+            sender,
+        )
+        .await
+    }
+
+    async fn install_code(
+        &self,
+        mode: CanisterInstallMode,
+        canister_id: CanisterId,
+        wasm_module: Vec<u8>,
+        arg: Vec<u8>,
+        sender: Option<Principal>,
+    ) -> Result<(), CallError> {
+        call_candid_as::<(InstallCodeArgument,), ()>(
+            self,
+            Principal::management_canister(),
+            sender.unwrap_or(Principal::anonymous()),
+            "install_code",
+            (InstallCodeArgument {
+                mode,
+                canister_id,
+                wasm_module,
+                arg,
+            },),
+        )
+        .await
+    }
+
+    pub async fn start_canister(
+        &self,
+        canister_id: CanisterId,
+        sender: Option<Principal>,
+    ) -> Result<(), CallError> {
+        self.call_management_canister("start_canister", canister_id, sender)
+            .await
+    }
+
+    pub async fn stop_canister(
+        &self,
+        canister_id: CanisterId,
+        sender: Option<Principal>,
+    ) -> Result<(), CallError> {
+        self.call_management_canister("stop_canister", canister_id, sender)
+            .await
+    }
+
+    pub async fn delete_canister(
+        &self,
+        canister_id: CanisterId,
+        sender: Option<Principal>,
+    ) -> Result<(), CallError> {
+        self.call_management_canister("delete_canister", canister_id, sender)
+            .await
+    }
+
+    async fn call_management_canister(
+        &self,
+        method: &str,
+        canister_id: CanisterId,
+        sender: Option<Principal>,
+    ) -> Result<(), CallError> {
+        call_candid_as::<(CanisterIdRecord,), ()>(
+            self,
+            Principal::management_canister(),
+            sender.unwrap_or(Principal::anonymous()),
+            method,
+            (CanisterIdRecord { canister_id },),
+        )
+        .await
+    }
+
+    pub async fn canister_exists(&self, canister_id: Principal) -> bool {
+        self.call_state_machine(Request::CanisterExists(RawCanisterId::from(canister_id)))
+            .await
+    }
+
+    pub async fn time(&self) -> SystemTime {
+        self.call_state_machine(Request::Time).await
+    }
+
+    pub async fn set_time(&self, time: SystemTime) {
+        self.call_state_machine(Request::SetTime(time)).await
+    }
+
+    pub async fn advance_time(&self, duration: Duration) {
+        self.call_state_machine(Request::AdvanceTime(duration))
+            .await
+    }
+
+    pub async fn tick(&self) {
+        self.call_state_machine(Request::Tick).await
+    }
+
+    /// Writes a checkpoint of the replicated state of all subnets to
+    /// `checkpoint_dir`, see [crate::PocketIc::checkpoint].
+    pub async fn checkpoint(&self, checkpoint_dir: impl AsRef<Path>) -> Result<(), String> {
+        self.call_state_machine(Request::Checkpoint(CheckpointArg {
+            checkpoint_dir: checkpoint_dir.as_ref().to_path_buf(),
+        }))
+        .await
+    }
+
+    /// Ticks until no subnet has any messages left to process or deliver.
+    /// Returns an error if this is not the case after `max_ticks` ticks.
+    pub async fn run_until_completion(&self, max_ticks: u64) -> Result<(), String> {
+        self.call_state_machine(Request::RunUntilCompletion(RunUntilCompletionArg {
+            max_ticks,
+        }))
+        .await
+    }
+
+    pub async fn stable_memory(&self, canister_id: Principal) -> Vec<u8> {
+        self.call_state_machine(Request::ReadStableMemory(RawCanisterId::from(canister_id)))
+            .await
+    }
+
+    pub async fn set_stable_memory(&self, canister_id: Principal, data: ByteBuf) {
+        self.call_state_machine(Request::SetStableMemory(SetStableMemoryArg {
+            canister_id: canister_id.as_slice().to_vec(),
+            data,
+        }))
+        .await
+    }
+
+    pub async fn cycle_balance(&self, canister_id: Principal) -> u128 {
+        self.call_state_machine(Request::CyclesBalance(RawCanisterId::from(canister_id)))
+            .await
+    }
+
+    /// Returns the instruction profile of the latest execution of an entry
+    /// point of a canister, see [crate::PocketIc::execution_profile].
+    pub async fn execution_profile(
+        &self,
+        canister_id: Principal,
+        entry_point: &str,
+    ) -> Option<String> {
+        self.call_state_machine(Request::GetExecutionProfile(ExecutionProfileArg {
+            canister_id: RawCanisterId::from(canister_id),
+            entry_point: entry_point.to_string(),
+        }))
+        .await
+    }
+
+    /// Executes an update call and returns its result together with its
+    /// instruction profile, see [crate::PocketIc::update_call_with_profile].
+    pub async fn update_call_with_profile(
+        &self,
+        canister_id: Principal,
+        sender: Principal,
+        method: &str,
+        arg: Vec<u8>,
+    ) -> (Result<WasmResult, UserError>, Option<String>) {
+        let result = self.update_call(canister_id, sender, method, arg).await;
+        let profile = self
+            .execution_profile(canister_id, &format!("canister_update {}", method))
+            .await;
+        (result, profile)
+    }
+
+    pub async fn add_cycles(&self, canister_id: Principal, amount: u128) -> u128 {
+        self.call_state_machine(Request::AddCycles(AddCyclesArg {
+            canister_id: canister_id.as_slice().to_vec(),
+            amount,
+        }))
+        .await
+    }
+
+    /// Verifies a canister signature. Returns Ok(()) if the signature is valid.
+    /// On error, returns a string describing the error.
+    pub async fn verify_canister_signature(
+        &self,
+        msg: Vec<u8>,
+        sig: Vec<u8>,
+        pubkey: Vec<u8>,
+        root_pubkey: Vec<u8>,
+    ) -> Result<(), String> {
+        self.call_state_machine(Request::VerifyCanisterSig(VerifyCanisterSigArg {
+            msg,
+            sig,
+            pubkey,
+            root_pubkey,
+        }))
+        .await
+    }
+
+    async fn call_state_machine<T: DeserializeOwned>(&self, request: Request) -> T {
+        let res = self.send_request(request).await;
+        serde_json::from_str(&res).expect("Failed to decode json")
+    }
+}
+
+/// Call a canister candid query method, anonymous.
+pub async fn query_candid<Input, Output>(
+    env: &PocketIcAsync,
+    canister_id: Principal,
+    method: &str,
+    input: Input,
+) -> Result<Output, CallError>
+where
+    Input: ArgumentEncoder,
+    Output: for<'a> ArgumentDecoder<'a>,
+{
+    query_candid_as(env, canister_id, Principal::anonymous(), method, input).await
+}
+
+/// Call a canister candid query method, authenticated.
+pub async fn query_candid_as<Input, Output>(
+    env: &PocketIcAsync,
+    canister_id: Principal,
+    sender: Principal,
+    method: &str,
+    input: Input,
+) -> Result<Output, CallError>
+where
+    Input: ArgumentEncoder,
+    Output: for<'a> ArgumentDecoder<'a>,
+{
+    let in_bytes = encode_args(input).expect("failed to encode args");
+    decode_candid_result(env.query_call(canister_id, sender, method, in_bytes).await)
+}
+
+/// Call a canister candid method, authenticated.
+pub async fn call_candid_as<Input, Output>(
+    env: &PocketIcAsync,
+    canister_id: Principal,
+    sender: Principal,
+    method: &str,
+    input: Input,
+) -> Result<Output, CallError>
+where
+    Input: ArgumentEncoder,
+    Output: for<'a> ArgumentDecoder<'a>,
+{
+    let in_bytes = encode_args(input).expect("failed to encode args");
+    decode_candid_result(env.update_call(canister_id, sender, method, in_bytes).await)
+}
+
+/// Call a canister candid method, anonymous.
+pub async fn call_candid<Input, Output>(
+    env: &PocketIcAsync,
+    canister_id: Principal,
+    method: &str,
+    input: Input,
+) -> Result<Output, CallError>
+where
+    Input: ArgumentEncoder,
+    Output: for<'a> ArgumentDecoder<'a>,
+{
+    call_candid_as(env, canister_id, Principal::anonymous(), method, input).await
+}
@@ -1,5 +1,5 @@
 use candid::{encode_one, Principal};
-use pocket_ic::{PocketIc, WasmResult};
+use pocket_ic::{PocketIc, PocketIcAsync, SubnetConfigSet, SubnetKind, WasmResult};
 
 // tests in one file may run concurrently
 // test sets from different files run in sequence
@@ -17,6 +17,149 @@ fn test_2() {
     println!("===== Test 2 end   =====");
 }
 
+#[test]
+fn test_multi_subnet_instance() {
+    let counter_wasm = std::fs::read("./tests/counter.wasm").expect("Failed to load counter.wasm.");
+    let ic = PocketIc::from_config(SubnetConfigSet {
+        nns: true,
+        system: false,
+        application: 2,
+        ..Default::default()
+    });
+    let topology = ic.topology();
+    assert_eq!(topology.subnet_ids(SubnetKind::NNS).len(), 1);
+    let app_subnets = topology.subnet_ids(SubnetKind::Application);
+    assert_eq!(app_subnets.len(), 2);
+
+    let controller = Principal::anonymous();
+    let can_id = ic.create_canister_on_subnet(Some(controller), None, app_subnets[1]);
+    assert_eq!(ic.get_subnet(can_id), Some(app_subnets[1]));
+    ic.add_cycles(can_id, 1_000_000_000_000_000_000);
+    // Calls to the management canister are routed by the target canister.
+    ic.install_canister(can_id, counter_wasm, vec![], Some(controller));
+
+    let reply = call_counter_can(&ic, can_id, controller, "write");
+    assert!(reply == WasmResult::Reply(vec![1, 0, 0, 0]));
+}
+
+#[test]
+fn test_checkpoint_and_restore() {
+    let counter_wasm = std::fs::read("./tests/counter.wasm").expect("Failed to load counter.wasm.");
+    let ic = PocketIc::from_config(SubnetConfigSet {
+        nns: false,
+        system: true,
+        application: 1,
+        ..Default::default()
+    });
+    let controller = Principal::anonymous();
+    let can_id = ic.create_canister(Some(controller));
+    ic.add_cycles(can_id, 1_000_000_000_000_000_000);
+    ic.install_canister(can_id, counter_wasm, vec![], Some(controller));
+    call_counter_can(&ic, can_id, controller, "write");
+
+    let checkpoint_dir = tempfile::tempdir().unwrap();
+    ic.checkpoint(checkpoint_dir.path())
+        .expect("Failed to write checkpoint");
+    call_counter_can(&ic, can_id, controller, "write");
+
+    let restored = PocketIc::from_checkpoint(checkpoint_dir.path());
+    assert_eq!(restored.topology(), ic.topology());
+    let reply = call_counter_can(&restored, can_id, controller, "read");
+    assert!(reply == WasmResult::Reply(vec![1, 0, 0, 0]));
+    let reply = call_counter_can(&ic, can_id, controller, "read");
+    assert!(reply == WasmResult::Reply(vec![2, 0, 0, 0]));
+}
+
+#[test]
+fn test_api_v2_status() {
+    let ic = PocketIc::new();
+    let url = ic.url().join("api/v2/status").unwrap();
+    let response = reqwest::blocking::get(url).expect("Failed to get status");
+    assert!(response.status().is_success());
+    assert_eq!(
+        response.headers()[reqwest::header::CONTENT_TYPE],
+        "application/cbor"
+    );
+    assert!(!response.bytes().unwrap().is_empty());
+}
+
+#[test]
+fn test_submit_and_await_calls() {
+    let counter_wasm = std::fs::read("./tests/counter.wasm").expect("Failed to load counter.wasm.");
+    let ic = PocketIc::new();
+    let controller = Principal::anonymous();
+    let can_id = ic.create_canister(Some(controller));
+    ic.add_cycles(can_id, 1_000_000_000_000_000_000);
+    ic.install_canister(can_id, counter_wasm, vec![], Some(controller));
+
+    let first = ic
+        .submit_call(can_id, controller, "write", encode_one(()).unwrap())
+        .unwrap();
+    let second = ic
+        .submit_call(can_id, controller, "write", encode_one(()).unwrap())
+        .unwrap();
+    assert_eq!(
+        ic.await_call(second).unwrap(),
+        WasmResult::Reply(vec![2, 0, 0, 0])
+    );
+    assert_eq!(
+        ic.await_call(first).unwrap(),
+        WasmResult::Reply(vec![1, 0, 0, 0])
+    );
+}
+
+#[tokio::test]
+async fn test_async_client() {
+    let counter_wasm = std::fs::read("./tests/counter.wasm").expect("Failed to load counter.wasm.");
+    let ic = PocketIcAsync::new().await;
+    let controller = Principal::anonymous();
+    let can_id = ic.create_canister(Some(controller)).await;
+    ic.add_cycles(can_id, 1_000_000_000_000_000_000).await;
+    ic.install_canister(can_id, counter_wasm, vec![], Some(controller))
+        .await;
+
+    let mut message_ids = vec![];
+    for _ in 0..3 {
+        let message_id = ic
+            .submit_call(can_id, controller, "write", encode_one(()).unwrap())
+            .await
+            .unwrap();
+        message_ids.push(message_id);
+    }
+    for message_id in message_ids {
+        ic.await_call(message_id).await.unwrap();
+    }
+    let reply = ic
+        .query_call(can_id, controller, "read", encode_one(()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(reply, WasmResult::Reply(vec![3, 0, 0, 0]));
+}
+
+#[test]
+fn test_execution_profile() {
+    let counter_wasm = std::fs::read("./tests/counter.wasm").expect("Failed to load counter.wasm.");
+    let ic = PocketIc::from_config(SubnetConfigSet {
+        system: true,
+        canister_profiling: true,
+        ..Default::default()
+    });
+    let controller = Principal::anonymous();
+    let can_id = ic.create_canister(Some(controller));
+    ic.add_cycles(can_id, 1_000_000_000_000_000_000);
+    ic.install_canister(can_id, counter_wasm, vec![], Some(controller));
+
+    let (reply, profile) =
+        ic.update_call_with_profile(can_id, controller, "write", encode_one(()).unwrap());
+    assert_eq!(reply.unwrap(), WasmResult::Reply(vec![1, 0, 0, 0]));
+    let profile = profile.expect("No profile recorded");
+    assert!(!profile.is_empty());
+    for line in profile.lines() {
+        assert!(line.starts_with("canister_update write"), "{}", line);
+    }
+    assert_eq!(ic.execution_profile(can_id, "canister_update read"), None);
+}
+
 fn test_counter_canister() {
     let counter_wasm = std::fs::read("./tests/counter.wasm").expect("Failed to load counter.wasm.");
     let ic = PocketIc::new();
@@ -218,6 +218,7 @@ mod tests {
             CanisterTimer::Inactive,
             0,
             BTreeSet::from([controller]),
+            None,
         )
     }
 
@@ -727,6 +727,9 @@ mod tests {
                 //          4A                      # bytes(10)
                 //             00000000000000140101 # "\x00\x00\x00\x00\x00\x00\x00\x14\x01\x01"
                 E::VisitBlob(hex::decode("d9d9f781824a000000000000000b01014a00000000000000140101").unwrap()),
+                edge("node"),
+                E::StartSubtree,
+                E::EndSubtree, // nodes
                 edge("public_key"),
                 E::VisitBlob(vec![5, 6, 7, 8]),
                 E::EndSubtree, // subnet
@@ -70,6 +70,10 @@ pub struct FeatureFlags {
     /// Track dirty pages with a write barrier instead of the signal handler.
     pub write_barrier: FlagStatus,
     pub wasm_native_stable_memory: FlagStatus,
+    /// Record the instructions spent per function and call stack of each
+    /// execution. Meant for profiling canisters in tests only, since it slows
+    /// down execution considerably.
+    pub canister_profiling: FlagStatus,
 }
 
 impl FeatureFlags {
@@ -78,6 +82,7 @@ impl FeatureFlags {
             rate_limiting_of_debug_prints: FlagStatus::Enabled,
             write_barrier: FlagStatus::Disabled,
             wasm_native_stable_memory: FlagStatus::Enabled,
+            canister_profiling: FlagStatus::Disabled,
         }
     }
 }
@@ -5,7 +5,7 @@ use ic_types::{
     Cycles, NumBytes, NumInstructions, MAX_STABLE_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES,
 };
 use serde::{Deserialize, Serialize};
-use std::{str::FromStr, time::Duration};
+use std::{path::PathBuf, str::FromStr, time::Duration};
 
 const MIB: u64 = 1024 * 1024;
 const GIB: u64 = MIB * 1024;
@@ -110,9 +110,19 @@ pub const BITCOIN_MAINNET_CANISTER_ID: &str = "ghsi2-tqaaa-aaaan-aaaca-cai";
 // TODO(EXC-1298): Uninstall this canister once the bitcoin mainnet canister is live.
 const BITCOIN_MAINNET_SOFT_LAUNCH_CANISTER_ID: &str = "gsvzx-syaaa-aaaan-aaabq-cai";
 
+/// The maximum number of snapshots a single canister can keep.
+pub const MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER: usize = 1;
+
 /// The capacity of the Wasm compilation cache.
 pub const MAX_COMPILATION_CACHE_SIZE: NumBytes = NumBytes::new(10 * GIB);
 
+/// The capacity of the on-disk tier of the Wasm compilation cache.
+pub const MAX_DISK_COMPILATION_CACHE_SIZE: NumBytes = NumBytes::new(20 * GIB);
+
+/// The number of block heights over which query statistics are collected
+/// before they are aggregated.
+pub const QUERY_STATS_EPOCH_LENGTH: u64 = 2000;
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default)]
 pub struct Config {
@@ -163,6 +173,9 @@ pub struct Config {
     /// Maximum number of controllers a canister can have.
     pub max_controllers: usize,
 
+    /// Maximum number of snapshots a canister can have at the same time.
+    pub max_number_of_snapshots_per_canister: usize,
+
     /// Indicates whether canisters sandboxing is enabled or not.
     pub canister_sandboxing_flag: FlagStatus,
 
@@ -220,6 +233,21 @@ pub struct Config {
 
     /// The capacity of the Wasm compilation cache.
     pub max_compilation_cache_size: NumBytes,
+
+    /// The directory in which compiled Wasm modules are persisted across
+    /// restarts. The on-disk tier of the compilation cache is disabled if no
+    /// directory is configured.
+    pub compilation_cache_dir: Option<PathBuf>,
+
+    /// The capacity of the on-disk tier of the Wasm compilation cache.
+    pub max_disk_compilation_cache_size: NumBytes,
+
+    /// Indicates whether query statistics are collected and aggregated.
+    pub query_stats_aggregation: FlagStatus,
+
+    /// The number of block heights over which query statistics are collected
+    /// before they are aggregated.
+    pub query_stats_epoch_length: u64,
 }
 
 impl Default for Config {
@@ -254,6 +282,7 @@ impl Default for Config {
             // Maximum number of controllers allowed in a request (specified in the public
             // Spec).
             max_controllers: 10,
+            max_number_of_snapshots_per_canister: MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER,
             canister_sandboxing_flag: FlagStatus::Enabled,
             query_execution_threads_total: QUERY_EXECUTION_THREADS_TOTAL,
             query_scheduling_time_slice_per_canister: QUERY_SCHEDULING_TIME_SLICE_PER_CANISTER,
@@ -284,6 +313,10 @@ impl Default for Config {
             query_caching: FlagStatus::Enabled,
             query_cache_capacity: QUERY_CACHE_CAPACITY,
             max_compilation_cache_size: MAX_COMPILATION_CACHE_SIZE,
+            compilation_cache_dir: None,
+            max_disk_compilation_cache_size: MAX_DISK_COMPILATION_CACHE_SIZE,
+            query_stats_aggregation: FlagStatus::Disabled,
+            query_stats_epoch_length: QUERY_STATS_EPOCH_LENGTH,
         }
     }
 }
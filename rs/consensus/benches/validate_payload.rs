@@ -22,6 +22,7 @@ use ic_ic00_types::IC_00;
 use ic_ingress_manager::IngressManager;
 use ic_interfaces::{
     artifact_pool::MutablePool,
+    batch_payload::ProposalContext,
     consensus::{PayloadBuilder, PayloadValidationError},
     consensus_pool::{ChangeAction, ChangeSet, ConsensusPool},
     time_source::TimeSource,
@@ -32,6 +33,7 @@ use ic_interfaces_state_manager_mocks::MockStateManager;
 use ic_logger::replica_logger::no_op_logger;
 use ic_metrics::MetricsRegistry;
 use ic_protobuf::types::v1 as pb;
+use ic_query_stats::test_utils::FakeQueryStatsPayloadBuilder;
 use ic_registry_subnet_type::SubnetType;
 use ic_state_manager::StateManagerImpl;
 use ic_test_utilities::{
@@ -153,11 +155,13 @@ where
 
         let payload_builder = Arc::new(PayloadBuilderImpl::new(
             subnet_test_id(0),
+            node_test_id(0),
             registry_client,
             ingress_manager,
             Arc::new(FakeXNetPayloadBuilder::new()),
             Arc::new(FakeSelfValidatingPayloadBuilder::new()),
             Arc::new(FakeCanisterHttpPayloadBuilder::new()),
+            Arc::new(FakeQueryStatsPayloadBuilder::new()),
             metrics_registry,
             no_op_logger(),
         ));
@@ -308,7 +312,10 @@ fn validate_payload(
         Height::from(CERTIFIED_HEIGHT + 1),
         payload,
         &past_payloads,
-        &validation_context,
+        &ProposalContext {
+            proposer: node_test_id(0),
+            validation_context: &validation_context,
+        },
     )
 }
 
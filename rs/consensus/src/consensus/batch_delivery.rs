@@ -155,6 +155,10 @@ pub fn deliver_batches(
                     messages: batch_messages,
                     randomness,
                     ecdsa_subnet_public_keys: ecdsa_subnet_public_key.into_iter().collect(),
+                    // No Schnorr or vetKD keys are configured in the registry
+                    // yet, so consensus does not deliver any.
+                    schnorr_subnet_public_keys: BTreeMap::new(),
+                    vetkd_subnet_public_keys: BTreeMap::new(),
                     registry_version: block.context.registry_version,
                     time: block.context.time,
                     consensus_responses,
@@ -3,7 +3,7 @@ use crate::consensus::metrics::{
 };
 use ic_consensus_utils::pool_reader::filter_past_payloads;
 use ic_interfaces::{
-    batch_payload::{BatchPayloadBuilder, PastPayload},
+    batch_payload::{BatchPayloadBuilder, PastPayload, ProposalContext},
     consensus::PayloadValidationError,
     ingress_manager::IngressSelector,
     messaging::XNetPayloadBuilder,
@@ -11,7 +11,7 @@ use ic_interfaces::{
 };
 use ic_logger::{error, ReplicaLogger};
 use ic_types::{
-    batch::{BatchPayload, IngressPayload, SelfValidatingPayload, ValidationContext, XNetPayload},
+    batch::{BatchPayload, IngressPayload, SelfValidatingPayload, XNetPayload},
     consensus::Payload,
     CountBytes, Height, NumBytes, Time,
 };
@@ -25,7 +25,7 @@ use std::sync::Arc;
 /// [`build_payload`](BatchPayloadSectionBuilder::build_payload)
 /// succeeds when passed into
 /// [`validate_payload`](BatchPayloadSectionBuilder::validate_payload),
-/// given the same arguments for [`ValidationContext`] and `past_payloads`,
+/// given the same arguments for [`ProposalContext`] and `past_payloads`,
 /// and that the following constraints are satisfied:
 ///
 /// - Payload size returned by [`build_payload`](BatchPayloadSectionBuilder::build_payload)
@@ -41,13 +41,14 @@ pub(crate) enum BatchPayloadSectionBuilder {
     XNet(Arc<dyn XNetPayloadBuilder>),
     SelfValidating(Arc<dyn SelfValidatingPayloadBuilder>),
     CanisterHttp(Arc<dyn BatchPayloadBuilder>),
+    QueryStats(Arc<dyn BatchPayloadBuilder>),
 }
 
 impl BatchPayloadSectionBuilder {
     /// Called to build the payload.
     ///
     /// # Arguments:
-    /// - `validation_context`: The [`ValidationContext`], under which the payload must be valid.
+    /// - `proposal_context`: The [`ProposalContext`], under which the payload must be valid.
     /// - `max_size`: The maximum size in [`NumBytes`], that the payload section has available in the current block.
     /// - `past_payloads`: All [`BatchPayload`]s from the certified height to the tip.
     /// - `logger`: Access to a [`ReplicaLogger`]
@@ -58,12 +59,13 @@ impl BatchPayloadSectionBuilder {
         &self,
         payload: &mut BatchPayload,
         height: Height,
-        validation_context: &ValidationContext,
+        proposal_context: &ProposalContext,
         max_size: NumBytes,
         past_payloads: &[(Height, Time, Payload)],
         metrics: &PayloadBuilderMetrics,
         logger: &ReplicaLogger,
     ) -> NumBytes {
+        let validation_context = proposal_context.validation_context;
         match self {
             Self::Ingress(builder) => {
                 let past_payloads = builder.filter_past_payloads(past_payloads, validation_context);
@@ -177,7 +179,7 @@ impl BatchPayloadSectionBuilder {
                     height,
                     &canister_http,
                     &past_payloads,
-                    validation_context,
+                    proposal_context,
                 ) {
                     Ok(()) => {
                         payload.canister_http = canister_http;
@@ -197,6 +199,45 @@ impl BatchPayloadSectionBuilder {
                     }
                 }
             }
+            Self::QueryStats(builder) => {
+                let past_payloads: Vec<PastPayload> =
+                    filter_past_payloads(past_payloads, |_, _, payload| {
+                        if payload.is_summary() {
+                            None
+                        } else {
+                            Some(&payload.as_ref().as_data().batch.query_stats)
+                        }
+                    });
+
+                let query_stats =
+                    builder.build_payload(height, max_size, &past_payloads, validation_context);
+                let size = NumBytes::new(query_stats.len() as u64);
+
+                // Check validation as safety measure
+                match builder.validate_payload(
+                    height,
+                    &query_stats,
+                    &past_payloads,
+                    proposal_context,
+                ) {
+                    Ok(()) => {
+                        payload.query_stats = query_stats;
+                        size
+                    }
+                    Err(err) => {
+                        error!(
+                            logger,
+                            "QueryStats payload did not pass validation, this is a bug, {:?} @{}",
+                            err,
+                            CRITICAL_ERROR_VALIDATION_NOT_PASSED
+                        );
+
+                        metrics.critical_error_validation_not_passed.inc();
+                        payload.query_stats = vec![];
+                        NumBytes::new(0)
+                    }
+                }
+            }
         }
     }
 
@@ -204,7 +245,7 @@ impl BatchPayloadSectionBuilder {
     ///
     /// # Argument:
     /// - `payload`: The payload to verify.
-    /// - `validation_context`: The [`ValidationContext`], under which to validate the payload.
+    /// - `proposal_context`: The [`ProposalContext`], under which to validate the payload.
     /// - `past_payloads`: All [`Payload`]s from the certified height to the tip.
     ///
     /// # Returns:
@@ -214,9 +255,10 @@ impl BatchPayloadSectionBuilder {
         &self,
         height: Height,
         payload: &BatchPayload,
-        validation_context: &ValidationContext,
+        proposal_context: &ProposalContext,
         past_payloads: &[(Height, Time, Payload)],
     ) -> Result<NumBytes, PayloadValidationError> {
+        let validation_context = proposal_context.validation_context;
         match self {
             Self::Ingress(builder) => {
                 let past_payloads = builder.filter_past_payloads(past_payloads, validation_context);
@@ -257,11 +299,30 @@ impl BatchPayloadSectionBuilder {
                     height,
                     &payload.canister_http,
                     &past_payloads,
-                    validation_context,
+                    proposal_context,
                 )?;
 
                 Ok(NumBytes::new(payload.canister_http.len() as u64))
             }
+            Self::QueryStats(builder) => {
+                let past_payloads: Vec<PastPayload> =
+                    filter_past_payloads(past_payloads, |_, _, payload| {
+                        if payload.is_summary() {
+                            None
+                        } else {
+                            Some(&payload.as_ref().as_data().batch.query_stats)
+                        }
+                    });
+
+                builder.validate_payload(
+                    height,
+                    &payload.query_stats,
+                    &past_payloads,
+                    proposal_context,
+                )?;
+
+                Ok(NumBytes::new(payload.query_stats.len() as u64))
+            }
         }
     }
 }
@@ -1,6 +1,6 @@
 use crate::consensus::payload_builder::test::make_test_payload_impl;
 use ic_consensus_mocks::{dependencies_with_subnet_params, Dependencies};
-use ic_interfaces::consensus::PayloadBuilder;
+use ic_interfaces::{batch_payload::ProposalContext, consensus::PayloadBuilder};
 use ic_test_utilities::{
     consensus::fake::Fake,
     mock_time,
@@ -83,7 +83,15 @@ fn proptest_round(
 
         let wrapped_payload = wrap_batch_payload(0, payload);
         payload_builder
-            .validate_payload(Height::from(0), &wrapped_payload, &[], &context)
+            .validate_payload(
+                Height::from(0),
+                &wrapped_payload,
+                &[],
+                &ProposalContext {
+                    proposer: node_test_id(0),
+                    validation_context: &context,
+                },
+            )
             .unwrap();
 
         // Check that no critical errors occured during the run.
@@ -20,6 +20,7 @@ use ic_consensus_utils::{
     RoundRobin,
 };
 use ic_interfaces::{
+    batch_payload::ProposalContext,
     consensus::{PayloadBuilder, PayloadPermanentError, PayloadTransientError},
     consensus_pool::*,
     dkg::DkgPool,
@@ -997,6 +998,7 @@ impl Validator {
         self.verify_artifact(pool_reader, proposal)?;
 
         // Ensure registry_version, certified_height and time are non-decreasing.
+        let proposer = proposal.signature.signer;
         let proposal = proposal.as_ref();
         if !proposal.context.greater_or_equal(&parent.context) {
             return Err(PermanentError::DecreasingValidationContext.into());
@@ -1030,12 +1032,16 @@ impl Validator {
             parent.clone(),
         );
 
+        let proposal_context = ProposalContext {
+            proposer,
+            validation_context: &proposal.context,
+        };
         self.payload_builder
             .validate_payload(
                 proposal.height,
                 &proposal.payload,
                 &payloads,
-                &proposal.context,
+                &proposal_context,
             )
             .map_err(|err| {
                 err.map(
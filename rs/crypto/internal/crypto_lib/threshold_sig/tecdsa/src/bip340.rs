@@ -0,0 +1,368 @@
+use crate::DerivationPath;
+use crate::*;
+
+/// The BIP340 tagged hash function
+///
+/// This is SHA-256(SHA-256(tag) || SHA-256(tag) || input)
+fn bip340_tagged_hash(tag: &'static str, inputs: &[&[u8]]) -> [u8; 32] {
+    let tag_hash = ic_crypto_sha2::Sha256::hash(tag.as_bytes());
+
+    let mut sha256 = ic_crypto_sha2::Sha256::new();
+    sha256.write(&tag_hash);
+    sha256.write(&tag_hash);
+    for input in inputs {
+        sha256.write(input);
+    }
+    sha256.finish()
+}
+
+/// Return true if the affine y coordinate of the point is even
+///
+/// The SEC1 compressed encoding of a point is 0x02 followed by the x
+/// coordinate if y is even, and 0x03 followed by the x coordinate otherwise.
+fn point_has_even_y(pt: &EccPoint) -> ThresholdEcdsaResult<bool> {
+    if pt.is_infinity()? {
+        return Err(ThresholdEcdsaError::InvalidPoint);
+    }
+    Ok(pt.serialize()[0] == 0x02)
+}
+
+/// Return the BIP340 "x-only" encoding of a point
+fn point_x_only_bytes(pt: &EccPoint) -> Vec<u8> {
+    pt.serialize()[1..].to_vec()
+}
+
+/// Compute the BIP340 challenge e = int(hash_challenge(R.x || P.x || m)) mod n
+fn bip340_challenge(
+    presig: &EccPoint,
+    public_key: &EccPoint,
+    message: &[u8],
+) -> ThresholdEcdsaResult<EccScalar> {
+    let e = bip340_tagged_hash(
+        "BIP0340/challenge",
+        &[
+            &point_x_only_bytes(presig),
+            &point_x_only_bytes(public_key),
+            message,
+        ],
+    );
+    EccScalar::from_bytes_wide(EccCurveType::K256, &e)
+}
+
+struct RerandomizedPresignature {
+    derived_key: EccPoint,
+    key_tweak: EccScalar,
+    randomizer: EccScalar,
+    randomized_pre_sig: EccPoint,
+}
+
+impl RerandomizedPresignature {
+    fn compute(
+        message: &[u8],
+        randomness: &Randomness,
+        derivation_path: &DerivationPath,
+        key_transcript: &IDkgTranscriptInternal,
+        presig_transcript: &IDkgTranscriptInternal,
+    ) -> ThresholdEcdsaResult<Self> {
+        let pre_sig = match &presig_transcript.combined_commitment {
+            CombinedCommitment::ByInterpolation(PolynomialCommitment::Simple(c)) => {
+                c.constant_term()
+            }
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        let curve_type = EccCurveType::K256;
+
+        if pre_sig.curve_type() != curve_type
+            || key_transcript.constant_term().curve_type() != curve_type
+        {
+            return Err(ThresholdEcdsaError::CurveMismatch);
+        }
+
+        let (key_tweak, _chain_key) =
+            derivation_path.derive_tweak(&key_transcript.constant_term())?;
+
+        let mut ro = ro::RandomOracle::new("ic-crypto-tschnorr-bip340-rerandomize-presig");
+        ro.add_bytestring("randomness", &randomness.get())?;
+        ro.add_bytestring("message", message)?;
+        ro.add_point("pre_sig", &pre_sig)?;
+        ro.add_scalar("key_tweak", &key_tweak)?;
+        let randomizer = ro.output_scalar(curve_type)?;
+
+        let randomized_pre_sig = pre_sig.add_points(&EccPoint::mul_by_g(&randomizer)?)?;
+
+        let derived_key = key_transcript
+            .constant_term()
+            .add_points(&EccPoint::mul_by_g(&key_tweak)?)?;
+
+        Ok(Self {
+            derived_key,
+            key_tweak,
+            randomizer,
+            randomized_pre_sig,
+        })
+    }
+}
+
+/// A signature share of a threshold BIP340 Schnorr signature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThresholdBip340SignatureShareInternal {
+    s: EccScalar,
+}
+
+impl ThresholdBip340SignatureShareInternal {
+    /// Create a new BIP340 signature share
+    ///
+    /// The key_transcript and presig_transcript must both be unmasked
+    /// transcripts on secp256k1; key_opening and presig_opening are our
+    /// openings of the commitments in these transcripts.
+    pub fn new(
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        key_transcript: &IDkgTranscriptInternal,
+        key_opening: &CommitmentOpening,
+        presig_transcript: &IDkgTranscriptInternal,
+        presig_opening: &CommitmentOpening,
+    ) -> ThresholdEcdsaResult<Self> {
+        let rerandomized = RerandomizedPresignature::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        let key_opening = match key_opening {
+            CommitmentOpening::Simple(s) => s,
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        let presig_opening = match presig_opening {
+            CommitmentOpening::Simple(s) => s,
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        // Adding the same value to each share shifts the shared secret by that value
+        let tweaked_x = key_opening.add(&rerandomized.key_tweak)?;
+        let rerandomized_k = presig_opening.add(&rerandomized.randomizer)?;
+
+        // BIP340 requires that both the public key and the presignature have
+        // even y coordinates; if not, we instead use the negation of the secret
+        let tweaked_x = if point_has_even_y(&rerandomized.derived_key)? {
+            tweaked_x
+        } else {
+            tweaked_x.negate()
+        };
+
+        let rerandomized_k = if point_has_even_y(&rerandomized.randomized_pre_sig)? {
+            rerandomized_k
+        } else {
+            rerandomized_k.negate()
+        };
+
+        let e = bip340_challenge(
+            &rerandomized.randomized_pre_sig,
+            &rerandomized.derived_key,
+            message,
+        )?;
+
+        let s = rerandomized_k.add(&e.mul(&tweaked_x)?)?;
+
+        Ok(Self { s })
+    }
+
+    /// Verify a BIP340 signature share
+    ///
+    /// This checks that the share is consistent with the commitments of the
+    /// key and presignature transcripts at the index of the signer.
+    pub fn verify(
+        &self,
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        signer_index: NodeIndex,
+        key_transcript: &IDkgTranscriptInternal,
+        presig_transcript: &IDkgTranscriptInternal,
+    ) -> ThresholdEcdsaResult<()> {
+        let rerandomized = RerandomizedPresignature::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        let e = bip340_challenge(
+            &rerandomized.randomized_pre_sig,
+            &rerandomized.derived_key,
+            message,
+        )?;
+
+        let tweaked_x_j = key_transcript
+            .evaluate_at(signer_index)?
+            .add_points(&EccPoint::mul_by_g(&rerandomized.key_tweak)?)?;
+        let rerandomized_k_j = presig_transcript
+            .evaluate_at(signer_index)?
+            .add_points(&EccPoint::mul_by_g(&rerandomized.randomizer)?)?;
+
+        let tweaked_x_j = if point_has_even_y(&rerandomized.derived_key)? {
+            tweaked_x_j
+        } else {
+            tweaked_x_j.negate()
+        };
+
+        let rerandomized_k_j = if point_has_even_y(&rerandomized.randomized_pre_sig)? {
+            rerandomized_k_j
+        } else {
+            rerandomized_k_j.negate()
+        };
+
+        let expected = rerandomized_k_j.add_points(&tweaked_x_j.scalar_mul(&e)?)?;
+
+        if EccPoint::mul_by_g(&self.s)? != expected {
+            return Err(ThresholdEcdsaError::InvalidSignatureShare);
+        }
+
+        Ok(())
+    }
+}
+
+/// A combined threshold BIP340 Schnorr signature
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdBip340CombinedSignatureInternal {
+    r: EccPoint,
+    s: EccScalar,
+}
+
+impl ThresholdBip340CombinedSignatureInternal {
+    /// Serialize the signature in the 64 byte format specified by BIP340
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut sig = point_x_only_bytes(&self.r);
+        sig.extend_from_slice(&self.s.serialize());
+        sig
+    }
+
+    /// Deserialize a signature in the 64 byte format specified by BIP340
+    pub fn deserialize(bytes: &[u8]) -> ThresholdEcdsaSerializationResult<Self> {
+        let curve_type = EccCurveType::K256;
+        let flen = curve_type.field_bytes();
+        let slen = curve_type.scalar_bytes();
+
+        if bytes.len() != flen + slen {
+            return Err(ThresholdEcdsaSerializationError(
+                "Bad signature length".to_string(),
+            ));
+        }
+
+        // BIP340 signatures encode R as only its x coordinate, with an even y
+        let mut r_bytes = Vec::with_capacity(1 + flen);
+        r_bytes.push(0x02);
+        r_bytes.extend_from_slice(&bytes[..flen]);
+
+        let r = EccPoint::deserialize(curve_type, &r_bytes)
+            .map_err(|e| ThresholdEcdsaSerializationError(format!("Invalid r: {:?}", e)))?;
+
+        let s = EccScalar::deserialize(curve_type, &bytes[flen..])
+            .map_err(|e| ThresholdEcdsaSerializationError(format!("Invalid s: {:?}", e)))?;
+
+        Ok(Self { r, s })
+    }
+
+    /// Combine sufficient signature shares into a BIP340 signature
+    ///
+    /// The signature shares must be verified prior to use, and there must
+    /// be at least reconstruction_threshold many of them.
+    pub fn new(
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        key_transcript: &IDkgTranscriptInternal,
+        presig_transcript: &IDkgTranscriptInternal,
+        reconstruction_threshold: NumberOfNodes,
+        sig_shares: &BTreeMap<NodeIndex, ThresholdBip340SignatureShareInternal>,
+    ) -> ThresholdEcdsaResult<Self> {
+        let reconstruction_threshold = reconstruction_threshold.get() as usize;
+        if sig_shares.len() < reconstruction_threshold {
+            return Err(ThresholdEcdsaError::InsufficientDealings);
+        }
+
+        let rerandomized = RerandomizedPresignature::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        let mut x_values = Vec::with_capacity(reconstruction_threshold);
+        let mut samples = Vec::with_capacity(reconstruction_threshold);
+
+        for (index, sig_share) in sig_shares.iter().take(reconstruction_threshold) {
+            x_values.push(*index);
+            samples.push(sig_share.s.clone());
+        }
+
+        let coefficients = LagrangeCoefficients::at_zero(EccCurveType::K256, &x_values)?;
+        let s = coefficients.interpolate_scalar(&samples)?;
+
+        let r = if point_has_even_y(&rerandomized.randomized_pre_sig)? {
+            rerandomized.randomized_pre_sig
+        } else {
+            rerandomized.randomized_pre_sig.negate()
+        };
+
+        Ok(Self { r, s })
+    }
+
+    /// Verify a threshold BIP340 signature
+    ///
+    /// This not only verifies the BIP340 signature equation but also that
+    /// the signature was generated with a particular presignature transcript.
+    pub fn verify(
+        &self,
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        presig_transcript: &IDkgTranscriptInternal,
+        key_transcript: &IDkgTranscriptInternal,
+    ) -> ThresholdEcdsaResult<()> {
+        if self.s.is_zero() || !point_has_even_y(&self.r)? {
+            return Err(ThresholdEcdsaError::InvalidSignature);
+        }
+
+        let rerandomized = RerandomizedPresignature::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        if point_x_only_bytes(&self.r) != point_x_only_bytes(&rerandomized.randomized_pre_sig) {
+            return Err(ThresholdEcdsaError::InvalidSignature);
+        }
+
+        let public_key = if point_has_even_y(&rerandomized.derived_key)? {
+            rerandomized.derived_key
+        } else {
+            rerandomized.derived_key.negate()
+        };
+
+        let e = bip340_challenge(&self.r, &public_key, message)?;
+
+        // Check that s*G - e*P == R
+        let rp = EccPoint::mul_2_points(
+            &EccPoint::generator_g(EccCurveType::K256),
+            &self.s,
+            &public_key,
+            &e.negate(),
+        )?;
+
+        if rp != self.r {
+            return Err(ThresholdEcdsaError::InvalidSignature);
+        }
+
+        Ok(())
+    }
+}
@@ -69,6 +69,12 @@
 //! * Generation and verification of signature shares
 //! * Generation and verification of combined signatures
 //!
+//! File: `bip340.rs`
+//!
+//! * Generation and verification of BIP340 Schnorr signature shares and
+//!   combined signatures, using an unmasked key transcript and an unmasked
+//!   pre-signature transcript on secp256k1
+//!
 //! ## Protocol: Multi-encryption gadget (MEGa)
 //!
 //! File: `mega.rs`
@@ -251,6 +257,7 @@ pub struct ThresholdEcdsaSerializationError(pub String);
 pub type ThresholdEcdsaSerializationResult<T> =
     std::result::Result<T, ThresholdEcdsaSerializationError>;
 
+pub mod bip340;
 mod complaints;
 mod dealings;
 mod fe;
@@ -274,6 +281,7 @@ pub use crate::poly::*;
 pub use crate::transcript::*;
 
 pub use crate::key_derivation::{DerivationIndex, DerivationPath};
+pub use bip340::{ThresholdBip340CombinedSignatureInternal, ThresholdBip340SignatureShareInternal};
 pub use sign::{ThresholdEcdsaCombinedSigInternal, ThresholdEcdsaSigShareInternal};
 
 /// Create MEGa encryption keypair
@@ -321,7 +329,9 @@ pub fn create_dealing(
     seed: Seed,
 ) -> Result<IDkgDealingInternal, IdkgCreateDealingInternalError> {
     let curve = match algorithm_id {
-        AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(EccCurveType::K256),
+        AlgorithmId::ThresholdEcdsaSecp256k1 | AlgorithmId::ThresholdSchnorrBip340 => {
+            Ok(EccCurveType::K256)
+        }
         _ => Err(IdkgCreateDealingInternalError::UnsupportedAlgorithm),
     }?;
 
@@ -372,7 +382,9 @@ pub fn create_transcript(
     operation_mode: &IDkgTranscriptOperationInternal,
 ) -> Result<IDkgTranscriptInternal, IDkgCreateTranscriptInternalError> {
     let curve = match algorithm_id {
-        AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(EccCurveType::K256),
+        AlgorithmId::ThresholdEcdsaSecp256k1 | AlgorithmId::ThresholdSchnorrBip340 => {
+            Ok(EccCurveType::K256)
+        }
         _ => Err(IDkgCreateTranscriptInternalError::UnsupportedAlgorithm),
     }?;
 
@@ -569,7 +581,9 @@ pub fn publicly_verify_dealing(
     associated_data: &[u8],
 ) -> Result<(), IDkgVerifyDealingInternalError> {
     let curve = match algorithm_id {
-        AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(EccCurveType::K256),
+        AlgorithmId::ThresholdEcdsaSecp256k1 | AlgorithmId::ThresholdSchnorrBip340 => {
+            Ok(EccCurveType::K256)
+        }
         _ => Err(IDkgVerifyDealingInternalError::UnsupportedAlgorithm),
     }?;
 
@@ -601,7 +615,9 @@ pub fn privately_verify_dealing(
     recipient_index: NodeIndex,
 ) -> Result<(), IDkgVerifyDealingInternalError> {
     let curve = match algorithm_id {
-        AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(EccCurveType::K256),
+        AlgorithmId::ThresholdEcdsaSecp256k1 | AlgorithmId::ThresholdSchnorrBip340 => {
+            Ok(EccCurveType::K256)
+        }
         _ => Err(IDkgVerifyDealingInternalError::UnsupportedAlgorithm),
     }?;
 
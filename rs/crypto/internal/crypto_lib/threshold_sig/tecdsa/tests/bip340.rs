@@ -0,0 +1,236 @@
+use ic_crypto_internal_threshold_sig_ecdsa::*;
+use ic_crypto_test_utils_reproducible_rng::reproducible_rng;
+use ic_types::*;
+use rand::Rng;
+use std::collections::BTreeMap;
+
+mod test_utils;
+
+use crate::test_utils::*;
+
+struct Bip340SignatureProtocol {
+    key: ProtocolRound,
+    presig: ProtocolRound,
+    threshold: usize,
+    nodes: usize,
+}
+
+impl Bip340SignatureProtocol {
+    fn new(nodes: usize, threshold: usize, seed: Seed) -> Result<Self, ThresholdEcdsaError> {
+        let setup = ProtocolSetup::new(EccCurveType::K256, nodes, threshold, seed)?;
+
+        let key = ProtocolRound::random(&setup, nodes, 0)?;
+        let key = ProtocolRound::reshare_of_masked(&setup, &key, nodes, 0)?;
+        let presig = ProtocolRound::random(&setup, nodes, 0)?;
+        let presig = ProtocolRound::reshare_of_masked(&setup, &presig, nodes, 0)?;
+
+        Ok(Self {
+            key,
+            presig,
+            threshold,
+            nodes,
+        })
+    }
+
+    fn generate_shares(
+        &self,
+        path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+    ) -> Result<BTreeMap<NodeIndex, ThresholdBip340SignatureShareInternal>, ThresholdEcdsaError>
+    {
+        let mut shares = BTreeMap::new();
+
+        for node_index in 0..self.nodes {
+            let share = ThresholdBip340SignatureShareInternal::new(
+                path,
+                message,
+                randomness,
+                &self.key.transcript,
+                &self.key.openings[node_index],
+                &self.presig.transcript,
+                &self.presig.openings[node_index],
+            )?;
+
+            share.verify(
+                path,
+                message,
+                randomness,
+                node_index as NodeIndex,
+                &self.key.transcript,
+                &self.presig.transcript,
+            )?;
+
+            shares.insert(node_index as NodeIndex, share);
+        }
+
+        Ok(shares)
+    }
+
+    fn derived_public_key(&self, path: &DerivationPath) -> Result<Vec<u8>, ThresholdEcdsaError> {
+        let master_public_key = self.key.transcript.constant_term();
+        let (key_tweak, _chain_key) = path.derive_tweak(&master_public_key)?;
+        let public_key = master_public_key.add_points(&EccPoint::mul_by_g(&key_tweak)?)?;
+        // BIP340 public keys are encoded as only the x coordinate
+        Ok(public_key.serialize()[1..].to_vec())
+    }
+}
+
+/// Verifies a BIP340 signature as described in the specification, without
+/// relying on the transcripts used to create it.
+fn bip340_spec_verify(
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> Result<bool, ThresholdEcdsaError> {
+    // lift_x: the point with the given x coordinate and an even y coordinate
+    let p = EccPoint::deserialize(EccCurveType::K256, &[&[0x02], public_key].concat())?;
+    let (r, s) = signature.split_at(32);
+    let s = EccScalar::deserialize(EccCurveType::K256, s)
+        .map_err(|_| ThresholdEcdsaError::InvalidScalar)?;
+
+    let tag_hash = ic_crypto_sha2::Sha256::hash(b"BIP0340/challenge");
+    let mut hasher = ic_crypto_sha2::Sha256::new();
+    for input in [&tag_hash[..], &tag_hash[..], r, public_key, message] {
+        hasher.write(input);
+    }
+    let e = EccScalar::from_bytes_wide(EccCurveType::K256, &hasher.finish())?;
+
+    let expected_r = EccPoint::mul_by_g(&s)?.sub_points(&p.scalar_mul(&e)?)?;
+    if expected_r.is_infinity()? {
+        return Ok(false);
+    }
+    let expected_r = expected_r.serialize();
+    Ok(expected_r[0] == 0x02 && &expected_r[1..] == r)
+}
+
+#[test]
+fn should_basic_bip340_signing_protocol_work() -> Result<(), ThresholdEcdsaError> {
+    let rng = &mut reproducible_rng();
+
+    let nodes = 5;
+    let threshold = 2;
+
+    let protocol = Bip340SignatureProtocol::new(nodes, threshold, Seed::from_rng(rng))?;
+
+    for _trial in 0..10 {
+        let message = rng.gen::<[u8; 32]>();
+        let randomness = Randomness::from(rng.gen::<[u8; 32]>());
+        let path = DerivationPath::new_bip32(&[rng.gen::<u32>(), rng.gen::<u32>()]);
+
+        let shares = protocol.generate_shares(&path, &message, randomness)?;
+
+        let sig = ThresholdBip340CombinedSignatureInternal::new(
+            &path,
+            &message,
+            randomness,
+            &protocol.key.transcript,
+            &protocol.presig.transcript,
+            NumberOfNodes::from(protocol.threshold as u32),
+            &shares,
+        )?;
+
+        sig.verify(
+            &path,
+            &message,
+            randomness,
+            &protocol.presig.transcript,
+            &protocol.key.transcript,
+        )?;
+
+        let sig_bytes = sig.serialize();
+        assert_eq!(sig_bytes.len(), 64);
+        assert_eq!(
+            ThresholdBip340CombinedSignatureInternal::deserialize(&sig_bytes)
+                .expect("Failed to deserialize signature"),
+            sig
+        );
+
+        // Check the signature with the verification algorithm of the BIP340 spec
+        let pk = protocol.derived_public_key(&path)?;
+        assert!(bip340_spec_verify(&pk, &message, &sig_bytes)?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_reject_invalid_bip340_shares_and_signatures() -> Result<(), ThresholdEcdsaError> {
+    let rng = &mut reproducible_rng();
+
+    let protocol = Bip340SignatureProtocol::new(4, 2, Seed::from_rng(rng))?;
+
+    let message = rng.gen::<[u8; 32]>();
+    let randomness = Randomness::from(rng.gen::<[u8; 32]>());
+    let path = DerivationPath::new_bip32(&[1, 2, 3]);
+
+    let shares = protocol.generate_shares(&path, &message, randomness)?;
+
+    // A share created by one node does not verify as a share of another node
+    assert_eq!(
+        shares[&0].verify(
+            &path,
+            &message,
+            randomness,
+            1,
+            &protocol.key.transcript,
+            &protocol.presig.transcript,
+        ),
+        Err(ThresholdEcdsaError::InvalidSignatureShare)
+    );
+
+    // Too few shares cannot be combined
+    let mut insufficient_shares = shares.clone();
+    insufficient_shares.retain(|index, _| *index == 0);
+    assert_eq!(
+        ThresholdBip340CombinedSignatureInternal::new(
+            &path,
+            &message,
+            randomness,
+            &protocol.key.transcript,
+            &protocol.presig.transcript,
+            NumberOfNodes::from(protocol.threshold as u32),
+            &insufficient_shares,
+        )
+        .map(|_| ()),
+        Err(ThresholdEcdsaError::InsufficientDealings)
+    );
+
+    let sig = ThresholdBip340CombinedSignatureInternal::new(
+        &path,
+        &message,
+        randomness,
+        &protocol.key.transcript,
+        &protocol.presig.transcript,
+        NumberOfNodes::from(protocol.threshold as u32),
+        &shares,
+    )?;
+
+    // The signature does not verify for a different message
+    let mut wrong_message = message;
+    wrong_message[0] ^= 1;
+    assert_eq!(
+        sig.verify(
+            &path,
+            &wrong_message,
+            randomness,
+            &protocol.presig.transcript,
+            &protocol.key.transcript,
+        ),
+        Err(ThresholdEcdsaError::InvalidSignature)
+    );
+
+    // The signature does not verify for a different derivation path
+    assert_eq!(
+        sig.verify(
+            &DerivationPath::new_bip32(&[1, 2, 4]),
+            &message,
+            randomness,
+            &protocol.presig.transcript,
+            &protocol.key.transcript,
+        ),
+        Err(ThresholdEcdsaError::InvalidSignature)
+    );
+
+    Ok(())
+}
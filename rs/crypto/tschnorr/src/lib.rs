@@ -0,0 +1,259 @@
+//! Public API for threshold Schnorr signatures.
+//!
+//! Threshold Schnorr keys are either BIP340 keys on secp256k1 or Ed25519
+//! keys. BIP340 keys are derived exactly like threshold ECDSA keys. Ed25519
+//! keys are derived additively as well: every index of the derivation path
+//! adds an offset, hashed from the parent key, the chain code and the index,
+//! to the parent key.
+use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use ic_crypto_internal_threshold_sig_ecdsa::{DerivationPath, EccCurveType, EccPoint, EccScalar};
+use ic_crypto_sha2::{DomainSeparationContext, Sha256, Sha512};
+use ic_types::crypto::canister_threshold_sig::{ExtendedDerivationPath, MasterEcdsaPublicKey};
+use ic_types::crypto::AlgorithmId;
+use std::fmt;
+
+pub mod test_utils;
+#[cfg(test)]
+mod tests;
+
+const ED25519_OFFSET_DOMAIN: &str = "ic-tschnorr-ed25519-derivation-offset";
+const ED25519_CHAIN_CODE_DOMAIN: &str = "ic-tschnorr-ed25519-derivation-chain-code";
+
+/// Errors that can occur when deriving threshold Schnorr public keys.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ThresholdSchnorrDerivePublicKeyError {
+    /// The master public key is not a threshold Schnorr key.
+    UnsupportedAlgorithm(AlgorithmId),
+    /// The master public key is not a valid point of its curve.
+    InvalidMasterPublicKey,
+    /// The derivation path cannot be used for key derivation.
+    InvalidDerivationPath(String),
+}
+
+impl fmt::Display for ThresholdSchnorrDerivePublicKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedAlgorithm(algorithm_id) => {
+                write!(
+                    f,
+                    "unsupported threshold Schnorr algorithm {:?}",
+                    algorithm_id
+                )
+            }
+            Self::InvalidMasterPublicKey => {
+                write!(f, "invalid threshold Schnorr master public key")
+            }
+            Self::InvalidDerivationPath(err) => write!(f, "invalid derivation path: {}", err),
+        }
+    }
+}
+
+/// A threshold Schnorr public key derived for a canister.
+///
+/// BIP340 keys are SEC1 compressed secp256k1 points, Ed25519 keys are
+/// compressed Edwards points.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SchnorrPublicKey {
+    pub algorithm_id: AlgorithmId,
+    pub public_key: Vec<u8>,
+    pub chain_code: Vec<u8>,
+}
+
+/// Derives the threshold Schnorr public key from the specified
+/// `master_public_key` for the given `extended_derivation_path`.
+pub fn derive_tschnorr_public_key(
+    master_public_key: &MasterEcdsaPublicKey,
+    extended_derivation_path: &ExtendedDerivationPath,
+) -> Result<SchnorrPublicKey, ThresholdSchnorrDerivePublicKeyError> {
+    match master_public_key.algorithm_id {
+        AlgorithmId::ThresholdSchnorrBip340 => {
+            derive_bip340_key(&master_public_key.public_key, extended_derivation_path)
+                .map(|(public_key, _offset)| public_key)
+        }
+        AlgorithmId::ThresholdEd25519 => {
+            derive_ed25519_key(&master_public_key.public_key, extended_derivation_path)
+                .map(|(public_key, _offset)| public_key)
+        }
+        algorithm_id => Err(ThresholdSchnorrDerivePublicKeyError::UnsupportedAlgorithm(
+            algorithm_id,
+        )),
+    }
+}
+
+/// Derives a BIP340 key from the SEC1 compressed `master_public_key`.
+///
+/// Returns the derived public key together with the offset that was added
+/// to the master key, i.e. the derived secret key is the master secret key
+/// plus the offset.
+pub fn derive_bip340_key(
+    master_public_key: &[u8],
+    extended_derivation_path: &ExtendedDerivationPath,
+) -> Result<(SchnorrPublicKey, EccScalar), ThresholdSchnorrDerivePublicKeyError> {
+    let master_public_key = EccPoint::deserialize(EccCurveType::K256, master_public_key)
+        .ok()
+        .filter(|point| matches!(point.is_infinity(), Ok(false)))
+        .ok_or(ThresholdSchnorrDerivePublicKeyError::InvalidMasterPublicKey)?;
+    let (offset, chain_code) = DerivationPath::from(extended_derivation_path)
+        .derive_tweak(&master_public_key)
+        .map_err(|err| {
+            ThresholdSchnorrDerivePublicKeyError::InvalidDerivationPath(format!("{:?}", err))
+        })?;
+    let public_key = EccPoint::mul_by_g(&offset)
+        .and_then(|offset_g| offset_g.add_points(&master_public_key))
+        .map_err(|_| ThresholdSchnorrDerivePublicKeyError::InvalidMasterPublicKey)?;
+    Ok((
+        SchnorrPublicKey {
+            algorithm_id: AlgorithmId::ThresholdSchnorrBip340,
+            public_key: public_key.serialize(),
+            chain_code,
+        },
+        offset,
+    ))
+}
+
+/// Derives an Ed25519 key from the compressed `master_public_key`.
+///
+/// Returns the derived public key together with the offset that was added
+/// to the master key, i.e. the derived secret scalar is the master secret
+/// scalar plus the offset.
+pub fn derive_ed25519_key(
+    master_public_key: &[u8],
+    extended_derivation_path: &ExtendedDerivationPath,
+) -> Result<(SchnorrPublicKey, Scalar), ThresholdSchnorrDerivePublicKeyError> {
+    let mut public_key = <[u8; 32]>::try_from(master_public_key)
+        .ok()
+        .and_then(|bytes| CompressedEdwardsY(bytes).decompress())
+        .filter(|point| !point.is_small_order())
+        .ok_or(ThresholdSchnorrDerivePublicKeyError::InvalidMasterPublicKey)?;
+
+    let path = DerivationPath::from(extended_derivation_path);
+    if path.len() > DerivationPath::MAXIMUM_DERIVATION_PATH_LENGTH {
+        return Err(ThresholdSchnorrDerivePublicKeyError::InvalidDerivationPath(
+            format!(
+                "Derivation path len {} larger than allowed maximum of {}",
+                path.len(),
+                DerivationPath::MAXIMUM_DERIVATION_PATH_LENGTH
+            ),
+        ));
+    }
+
+    let mut chain_code = [0u8; 32];
+    let mut offset = Scalar::zero();
+    for index in path.path() {
+        let (next_public_key, next_chain_code, next_offset) =
+            ed25519_ckd(&public_key, &chain_code, &index.0);
+        public_key = next_public_key;
+        chain_code = next_chain_code;
+        offset += next_offset;
+    }
+
+    Ok((
+        SchnorrPublicKey {
+            algorithm_id: AlgorithmId::ThresholdEd25519,
+            public_key: public_key.compress().to_bytes().to_vec(),
+            chain_code: chain_code.to_vec(),
+        },
+        offset,
+    ))
+}
+
+/// Derives the child of `public_key` for a single derivation `index`.
+fn ed25519_ckd(
+    public_key: &EdwardsPoint,
+    chain_code: &[u8; 32],
+    index: &[u8],
+) -> (EdwardsPoint, [u8; 32], Scalar) {
+    let hash = |domain: &str| {
+        let mut sha512 = Sha512::new_with_context(&DomainSeparationContext::new(domain));
+        sha512.write(chain_code);
+        sha512.write(&public_key.compress().to_bytes());
+        sha512.write(&(index.len() as u64).to_be_bytes());
+        sha512.write(index);
+        sha512.finish()
+    };
+    let offset = Scalar::from_bytes_mod_order_wide(&hash(ED25519_OFFSET_DOMAIN));
+    let mut next_chain_code = [0u8; 32];
+    next_chain_code.copy_from_slice(&hash(ED25519_CHAIN_CODE_DOMAIN)[..32]);
+    (
+        public_key + &offset * &ED25519_BASEPOINT_TABLE,
+        next_chain_code,
+        offset,
+    )
+}
+
+/// Verifies a Schnorr `signature` on `message` under `public_key`.
+///
+/// BIP340 public keys are expected in SEC1 compressed form, as returned by
+/// `derive_tschnorr_public_key`; only their x coordinate is used.
+pub fn verify_tschnorr_signature(
+    algorithm_id: AlgorithmId,
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> bool {
+    match algorithm_id {
+        AlgorithmId::ThresholdSchnorrBip340 => {
+            verify_bip340_signature(public_key, message, signature).is_some()
+        }
+        AlgorithmId::ThresholdEd25519 => verify_ed25519_signature(public_key, message, signature),
+        _ => false,
+    }
+}
+
+fn verify_bip340_signature(public_key: &[u8], message: &[u8], signature: &[u8]) -> Option<()> {
+    if signature.len() != 64 {
+        return None;
+    }
+    let public_key = EccPoint::deserialize(EccCurveType::K256, public_key).ok()?;
+    if public_key.is_infinity().ok()? {
+        return None;
+    }
+    // BIP340 public keys are x-only, which denotes the point with even y.
+    let public_key = if public_key.serialize()[0] == 0x02 {
+        public_key
+    } else {
+        public_key.negate()
+    };
+    let r =
+        EccPoint::deserialize(EccCurveType::K256, &[&[0x02], &signature[..32]].concat()).ok()?;
+    let s = EccScalar::deserialize(EccCurveType::K256, &signature[32..]).ok()?;
+    let e = bip340_challenge(&signature[..32], &public_key.serialize()[1..], message)?;
+
+    // Check that s*G - e*P == R
+    let rp = EccPoint::mul_2_points(
+        &EccPoint::generator_g(EccCurveType::K256),
+        &s,
+        &public_key,
+        &e.negate(),
+    )
+    .ok()?;
+    (rp.serialize() == r.serialize()).then_some(())
+}
+
+/// Computes the BIP340 challenge e = int(hash_challenge(R.x || P.x || m)) mod n.
+///
+/// The tagged hash is SHA-256(SHA-256(tag) || SHA-256(tag) || input).
+pub fn bip340_challenge(r_x: &[u8], public_key_x: &[u8], message: &[u8]) -> Option<EccScalar> {
+    let tag_hash = Sha256::hash(b"BIP0340/challenge");
+    let mut sha256 = Sha256::new();
+    sha256.write(&tag_hash);
+    sha256.write(&tag_hash);
+    sha256.write(r_x);
+    sha256.write(public_key_x);
+    sha256.write(message);
+    EccScalar::from_bytes_wide(EccCurveType::K256, &sha256.finish()).ok()
+}
+
+fn verify_ed25519_signature(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    let (Ok(public_key), Ok(signature)) = (
+        <[u8; 32]>::try_from(public_key),
+        <[u8; 64]>::try_from(signature),
+    ) else {
+        return false;
+    };
+    ed25519_consensus::VerificationKey::try_from(public_key)
+        .and_then(|key| key.verify(&ed25519_consensus::Signature::from(signature), message))
+        .is_ok()
+}
@@ -0,0 +1,147 @@
+//! Signing with threshold Schnorr keys whose secret key is known in the
+//! clear.
+//!
+//! This is only intended for testing, e.g. to answer Schnorr signature
+//! requests with a fixed test key, and should not be called in production
+//! code.
+use super::*;
+
+/// Signs `message` with the secp256k1 `secret_key` according to BIP340.
+///
+/// The nonce is derived deterministically from the secret key and the
+/// message instead of from auxiliary randomness.
+pub fn sign_bip340(secret_key: &EccScalar, message: &[u8]) -> Vec<u8> {
+    let public_key = EccPoint::mul_by_g(secret_key).expect("failed to compute public key");
+    // BIP340 public keys are x-only, which denotes the point with even y.
+    let secret_key = if public_key.serialize()[0] == 0x02 {
+        secret_key.clone()
+    } else {
+        secret_key.negate()
+    };
+    let public_key_x = public_key.serialize()[1..].to_vec();
+
+    let mut sha256 = Sha256::new_with_context(&DomainSeparationContext::new(
+        "ic-tschnorr-bip340-test-nonce",
+    ));
+    sha256.write(&secret_key.serialize());
+    sha256.write(message);
+    let nonce = EccScalar::from_bytes_wide(EccCurveType::K256, &sha256.finish())
+        .expect("failed to compute nonce");
+    let r = EccPoint::mul_by_g(&nonce).expect("failed to compute nonce commitment");
+    let nonce = if r.serialize()[0] == 0x02 {
+        nonce
+    } else {
+        nonce.negate()
+    };
+    let r_x = r.serialize()[1..].to_vec();
+
+    let e = bip340_challenge(&r_x, &public_key_x, message).expect("failed to compute challenge");
+    let s = e
+        .mul(&secret_key)
+        .and_then(|e_d| e_d.add(&nonce))
+        .expect("failed to compute signature");
+    [r_x, s.serialize()].concat()
+}
+
+/// Signs `message` with the Ed25519 secret scalar `secret_key`.
+///
+/// Derived Ed25519 keys have no seed, so the nonce is derived from the
+/// secret scalar and the message instead of from the hashed seed.
+pub fn sign_ed25519(secret_key: &Scalar, message: &[u8]) -> Vec<u8> {
+    let public_key = (secret_key * &ED25519_BASEPOINT_TABLE).compress();
+
+    let mut sha512 = Sha512::new_with_context(&DomainSeparationContext::new(
+        "ic-tschnorr-ed25519-test-nonce",
+    ));
+    sha512.write(secret_key.as_bytes());
+    sha512.write(message);
+    let nonce = Scalar::from_bytes_mod_order_wide(&sha512.finish());
+    let r = (&nonce * &ED25519_BASEPOINT_TABLE).compress();
+
+    let mut sha512 = Sha512::new();
+    sha512.write(r.as_bytes());
+    sha512.write(public_key.as_bytes());
+    sha512.write(message);
+    let k = Scalar::from_bytes_mod_order_wide(&sha512.finish());
+    let s = nonce + k * secret_key;
+    [r.as_bytes().as_slice(), s.as_bytes().as_slice()].concat()
+}
+
+/// A threshold Schnorr master key whose secret key is known in the clear,
+/// so that signatures for keys derived from it can be computed directly.
+#[derive(Clone)]
+pub struct MasterSchnorrTestKey {
+    secret_key: MasterSchnorrSecretKey,
+}
+
+#[derive(Clone)]
+enum MasterSchnorrSecretKey {
+    Bip340(EccScalar),
+    Ed25519(Scalar),
+}
+
+impl MasterSchnorrTestKey {
+    /// Creates the master key of `algorithm_id` whose secret key is derived
+    /// from `seed`.
+    ///
+    /// # Panics
+    /// If `algorithm_id` is not a threshold Schnorr algorithm.
+    pub fn new(algorithm_id: AlgorithmId, seed: &[u8]) -> Self {
+        let mut sha512 =
+            Sha512::new_with_context(&DomainSeparationContext::new("ic-tschnorr-test-master-key"));
+        sha512.write(seed);
+        let digest = sha512.finish();
+        let secret_key = match algorithm_id {
+            AlgorithmId::ThresholdSchnorrBip340 => MasterSchnorrSecretKey::Bip340(
+                EccScalar::from_bytes_wide(EccCurveType::K256, &digest)
+                    .expect("failed to compute secret key"),
+            ),
+            AlgorithmId::ThresholdEd25519 => {
+                MasterSchnorrSecretKey::Ed25519(Scalar::from_bytes_mod_order_wide(&digest))
+            }
+            algorithm_id => panic!("unsupported threshold Schnorr algorithm {:?}", algorithm_id),
+        };
+        Self { secret_key }
+    }
+
+    /// Returns the master public key of this key.
+    pub fn public_key(&self) -> MasterEcdsaPublicKey {
+        match &self.secret_key {
+            MasterSchnorrSecretKey::Bip340(secret_key) => MasterEcdsaPublicKey {
+                algorithm_id: AlgorithmId::ThresholdSchnorrBip340,
+                public_key: EccPoint::mul_by_g(secret_key)
+                    .expect("failed to compute public key")
+                    .serialize(),
+            },
+            MasterSchnorrSecretKey::Ed25519(secret_key) => MasterEcdsaPublicKey {
+                algorithm_id: AlgorithmId::ThresholdEd25519,
+                public_key: (secret_key * &ED25519_BASEPOINT_TABLE)
+                    .compress()
+                    .to_bytes()
+                    .to_vec(),
+            },
+        }
+    }
+
+    /// Signs `message` with the key derived for `extended_derivation_path`.
+    pub fn sign(
+        &self,
+        extended_derivation_path: &ExtendedDerivationPath,
+        message: &[u8],
+    ) -> Result<Vec<u8>, ThresholdSchnorrDerivePublicKeyError> {
+        let master_public_key = self.public_key().public_key;
+        match &self.secret_key {
+            MasterSchnorrSecretKey::Bip340(secret_key) => {
+                let (_, offset) = derive_bip340_key(&master_public_key, extended_derivation_path)?;
+                let secret_key = secret_key
+                    .add(&offset)
+                    .expect("failed to compute derived secret key");
+                Ok(sign_bip340(&secret_key, message))
+            }
+            MasterSchnorrSecretKey::Ed25519(secret_key) => {
+                let (_, offset) = derive_ed25519_key(&master_public_key, extended_derivation_path)?;
+                Ok(sign_ed25519(&(secret_key + offset), message))
+            }
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use super::test_utils::{sign_bip340, sign_ed25519, MasterSchnorrTestKey};
+use super::*;
+use ic_types::PrincipalId;
+
+fn path(caller: u64, derivation_path: Vec<Vec<u8>>) -> ExtendedDerivationPath {
+    ExtendedDerivationPath {
+        caller: PrincipalId::new_user_test_id(caller),
+        derivation_path,
+    }
+}
+
+fn bip340_master_key(master_secret_key: &EccScalar) -> MasterEcdsaPublicKey {
+    MasterEcdsaPublicKey {
+        algorithm_id: AlgorithmId::ThresholdSchnorrBip340,
+        public_key: EccPoint::mul_by_g(master_secret_key).unwrap().serialize(),
+    }
+}
+
+fn ed25519_master_key(master_secret_key: &Scalar) -> MasterEcdsaPublicKey {
+    MasterEcdsaPublicKey {
+        algorithm_id: AlgorithmId::ThresholdEd25519,
+        public_key: (master_secret_key * &ED25519_BASEPOINT_TABLE)
+            .compress()
+            .to_bytes()
+            .to_vec(),
+    }
+}
+
+#[test]
+fn should_derive_bip340_key_matching_the_offset_secret_key() {
+    let master_secret_key = EccScalar::from_u64(EccCurveType::K256, 0x5eed_0001);
+    let master_public_key = bip340_master_key(&master_secret_key);
+    let path = path(1, vec![b"key".to_vec(), vec![1, 2, 3]]);
+
+    let (derived_key, offset) = derive_bip340_key(&master_public_key.public_key, &path).unwrap();
+
+    let derived_secret_key = master_secret_key.add(&offset).unwrap();
+    assert_eq!(
+        derived_key.public_key,
+        EccPoint::mul_by_g(&derived_secret_key).unwrap().serialize()
+    );
+    assert_eq!(
+        derive_tschnorr_public_key(&master_public_key, &path).unwrap(),
+        derived_key
+    );
+}
+
+#[test]
+fn should_derive_ed25519_key_matching_the_offset_secret_key() {
+    let master_secret_key = Scalar::from(0x5eed_0002_u64);
+    let master_public_key = ed25519_master_key(&master_secret_key);
+    let path = path(1, vec![b"key".to_vec(), vec![1, 2, 3]]);
+
+    let (derived_key, offset) = derive_ed25519_key(&master_public_key.public_key, &path).unwrap();
+
+    let derived_secret_key = master_secret_key + offset;
+    assert_eq!(
+        derived_key.public_key,
+        (&derived_secret_key * &ED25519_BASEPOINT_TABLE)
+            .compress()
+            .to_bytes()
+            .to_vec()
+    );
+    assert_eq!(derived_key.chain_code.len(), 32);
+    assert_eq!(
+        derive_tschnorr_public_key(&master_public_key, &path).unwrap(),
+        derived_key
+    );
+}
+
+#[test]
+fn should_derive_different_keys_for_different_callers_and_paths() {
+    let master_keys = [
+        bip340_master_key(&EccScalar::from_u64(EccCurveType::K256, 0x5eed_0003)),
+        ed25519_master_key(&Scalar::from(0x5eed_0004_u64)),
+    ];
+    for master_public_key in master_keys {
+        let derive = |path| derive_tschnorr_public_key(&master_public_key, &path).unwrap();
+
+        let key_1 = derive(path(1, vec![vec![1]]));
+        let key_2 = derive(path(2, vec![vec![1]]));
+        let key_3 = derive(path(1, vec![vec![2]]));
+
+        assert_ne!(key_1.public_key, key_2.public_key);
+        assert_ne!(key_1.public_key, key_3.public_key);
+        assert_ne!(key_1.public_key, master_public_key.public_key);
+    }
+}
+
+#[test]
+fn should_reject_invalid_master_keys() {
+    let ecdsa_key = MasterEcdsaPublicKey {
+        algorithm_id: AlgorithmId::EcdsaSecp256k1,
+        public_key: bip340_master_key(&EccScalar::one(EccCurveType::K256)).public_key,
+    };
+    assert_eq!(
+        derive_tschnorr_public_key(&ecdsa_key, &path(1, vec![])),
+        Err(ThresholdSchnorrDerivePublicKeyError::UnsupportedAlgorithm(
+            AlgorithmId::EcdsaSecp256k1
+        ))
+    );
+
+    for algorithm_id in [
+        AlgorithmId::ThresholdSchnorrBip340,
+        AlgorithmId::ThresholdEd25519,
+    ] {
+        let invalid_key = MasterEcdsaPublicKey {
+            algorithm_id,
+            public_key: vec![0; 33],
+        };
+        assert_eq!(
+            derive_tschnorr_public_key(&invalid_key, &path(1, vec![])),
+            Err(ThresholdSchnorrDerivePublicKeyError::InvalidMasterPublicKey)
+        );
+    }
+}
+
+#[test]
+fn should_verify_bip340_test_vector() {
+    // Test vector 0 of the BIP340 specification.
+    let public_key =
+        hex::decode("02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9").unwrap();
+    let signature = hex::decode(
+        "E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA8215\
+         25F66A4A85EA8B71E482A74F382D2CE5EBEEE8FDB2172F477DF4900D310536C0",
+    )
+    .unwrap();
+    let message = [0u8; 32];
+
+    assert!(verify_tschnorr_signature(
+        AlgorithmId::ThresholdSchnorrBip340,
+        &public_key,
+        &message,
+        &signature
+    ));
+    assert!(!verify_tschnorr_signature(
+        AlgorithmId::ThresholdSchnorrBip340,
+        &public_key,
+        &[1u8; 32],
+        &signature
+    ));
+}
+
+#[test]
+fn should_verify_signatures_of_derived_bip340_keys() {
+    let master_secret_key = EccScalar::from_u64(EccCurveType::K256, 0x5eed_0005);
+    let master_public_key = bip340_master_key(&master_secret_key);
+
+    for caller in 1..10 {
+        let (derived_key, offset) =
+            derive_bip340_key(&master_public_key.public_key, &path(caller, vec![])).unwrap();
+        let secret_key = master_secret_key.add(&offset).unwrap();
+        let signature = sign_bip340(&secret_key, b"message");
+
+        assert_eq!(signature.len(), 64);
+        assert!(verify_tschnorr_signature(
+            AlgorithmId::ThresholdSchnorrBip340,
+            &derived_key.public_key,
+            b"message",
+            &signature
+        ));
+        assert!(!verify_tschnorr_signature(
+            AlgorithmId::ThresholdSchnorrBip340,
+            &derived_key.public_key,
+            b"other message",
+            &signature
+        ));
+    }
+}
+
+#[test]
+fn should_verify_signatures_of_derived_ed25519_keys() {
+    let master_secret_key = Scalar::from(0x5eed_0006_u64);
+    let master_public_key = ed25519_master_key(&master_secret_key);
+
+    for caller in 1..10 {
+        let (derived_key, offset) =
+            derive_ed25519_key(&master_public_key.public_key, &path(caller, vec![])).unwrap();
+        let signature = sign_ed25519(&(master_secret_key + offset), b"message");
+
+        assert_eq!(signature.len(), 64);
+        assert!(verify_tschnorr_signature(
+            AlgorithmId::ThresholdEd25519,
+            &derived_key.public_key,
+            b"message",
+            &signature
+        ));
+        assert!(!verify_tschnorr_signature(
+            AlgorithmId::ThresholdEd25519,
+            &derived_key.public_key,
+            b"other message",
+            &signature
+        ));
+    }
+}
+
+#[test]
+fn should_sign_with_derived_keys_of_master_test_key() {
+    for algorithm_id in [
+        AlgorithmId::ThresholdSchnorrBip340,
+        AlgorithmId::ThresholdEd25519,
+    ] {
+        let master_key = MasterSchnorrTestKey::new(algorithm_id, b"seed");
+        let path = path(1, vec![b"key".to_vec()]);
+
+        let derived_key = derive_tschnorr_public_key(&master_key.public_key(), &path).unwrap();
+        let signature = master_key.sign(&path, b"message").unwrap();
+
+        assert!(verify_tschnorr_signature(
+            algorithm_id,
+            &derived_key.public_key,
+            b"message",
+            &signature
+        ));
+    }
+}
@@ -0,0 +1,59 @@
+//! Public API for verifiably encrypted threshold key derivation (vetKD).
+//!
+//! vetKD keys are backed by the subnet's NI-DKG threshold key: the vetKD
+//! master public key of a subnet is a BLS12-381 threshold public key, from
+//! which the keys of individual canisters are derived.
+use ic_crypto_internal_bls12_381_vetkd::{
+    DerivationPath, DerivedPublicKey, G2Affine, TransportPublicKey,
+};
+use ic_types::crypto::threshold_sig::ThresholdSigPublicKey;
+use ic_types::CanisterId;
+use std::fmt;
+
+#[cfg(test)]
+mod tests;
+
+/// Errors that can occur when validating or deriving vetKD keys.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VetKdArgumentError {
+    /// The master public key is not a valid BLS12-381 G2 point.
+    InvalidMasterPublicKey,
+    /// The transport public key is not a valid BLS12-381 G1 point.
+    InvalidTransportPublicKey,
+}
+
+impl fmt::Display for VetKdArgumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMasterPublicKey => write!(f, "invalid vetKD master public key"),
+            Self::InvalidTransportPublicKey => write!(f, "invalid vetKD transport public key"),
+        }
+    }
+}
+
+/// Derives the vetKD public key of `canister_id` for the given
+/// `derivation_path` from the specified `master_public_key`.
+///
+/// Returns the serialized (compressed) BLS12-381 G2 point.
+pub fn derive_vetkd_public_key(
+    master_public_key: &ThresholdSigPublicKey,
+    canister_id: &CanisterId,
+    derivation_path: &[Vec<u8>],
+) -> Result<Vec<u8>, VetKdArgumentError> {
+    let master_public_key = G2Affine::deserialize(&master_public_key.into_bytes())
+        .map_err(|_| VetKdArgumentError::InvalidMasterPublicKey)?;
+    let derivation_path = DerivationPath::new(canister_id.get_ref().as_slice(), derivation_path);
+    Ok(
+        DerivedPublicKey::compute_derived_key(&master_public_key, &derivation_path)
+            .serialize()
+            .to_vec(),
+    )
+}
+
+/// Checks that `transport_public_key` is a valid vetKD transport public key,
+/// i.e. a serialized BLS12-381 G1 point.
+pub fn verify_transport_public_key(transport_public_key: &[u8]) -> Result<(), VetKdArgumentError> {
+    TransportPublicKey::deserialize(transport_public_key)
+        .map(|_| ())
+        .map_err(|_| VetKdArgumentError::InvalidTransportPublicKey)
+}
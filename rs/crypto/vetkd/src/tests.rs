@@ -0,0 +1,66 @@
+use super::*;
+use ic_crypto_internal_bls12_381_vetkd::{G1Affine, Scalar};
+use ic_crypto_internal_types::sign::threshold_sig::public_key::bls12_381::PublicKeyBytes;
+use ic_types::PrincipalId;
+
+fn master_public_key(master_secret_key: &Scalar) -> ThresholdSigPublicKey {
+    let public_key = G2Affine::from(G2Affine::generator() * master_secret_key);
+    ThresholdSigPublicKey::from(PublicKeyBytes(public_key.serialize()))
+}
+
+#[test]
+fn should_derive_vetkd_public_key_from_master_key() {
+    let master_secret_key = Scalar::from_u64(0x5eed_0001);
+    let master_public_key = master_public_key(&master_secret_key);
+    let canister_id = CanisterId::new(PrincipalId::new_user_test_id(1)).unwrap();
+    let path = vec![b"key".to_vec(), vec![1, 2, 3]];
+
+    let derived_key = derive_vetkd_public_key(&master_public_key, &canister_id, &path).unwrap();
+
+    let expected = DerivedPublicKey::compute_derived_key(
+        &G2Affine::from(G2Affine::generator() * &master_secret_key),
+        &DerivationPath::new(canister_id.get_ref().as_slice(), &path),
+    );
+    assert_eq!(derived_key, expected.serialize().to_vec());
+}
+
+#[test]
+fn should_derive_different_keys_for_different_callers_and_paths() {
+    let master_public_key = master_public_key(&Scalar::from_u64(0x5eed_0002));
+    let canister_1 = CanisterId::new(PrincipalId::new_user_test_id(1)).unwrap();
+    let canister_2 = CanisterId::new(PrincipalId::new_user_test_id(2)).unwrap();
+
+    let key_1 = derive_vetkd_public_key(&master_public_key, &canister_1, &[vec![1]]).unwrap();
+    let key_2 = derive_vetkd_public_key(&master_public_key, &canister_2, &[vec![1]]).unwrap();
+    let key_3 = derive_vetkd_public_key(&master_public_key, &canister_1, &[vec![2]]).unwrap();
+
+    assert_ne!(key_1, key_2);
+    assert_ne!(key_1, key_3);
+}
+
+#[test]
+fn should_reject_invalid_master_public_key() {
+    let master_public_key = ThresholdSigPublicKey::from(PublicKeyBytes([0; PublicKeyBytes::SIZE]));
+    let canister_id = CanisterId::new(PrincipalId::new_user_test_id(1)).unwrap();
+
+    assert_eq!(
+        derive_vetkd_public_key(&master_public_key, &canister_id, &[]),
+        Err(VetKdArgumentError::InvalidMasterPublicKey)
+    );
+}
+
+#[test]
+fn should_verify_transport_public_key() {
+    assert_eq!(
+        verify_transport_public_key(&G1Affine::generator().serialize()),
+        Ok(())
+    );
+    assert_eq!(
+        verify_transport_public_key(&[0; G1Affine::BYTES]),
+        Err(VetKdArgumentError::InvalidTransportPublicKey)
+    );
+    assert_eq!(
+        verify_transport_public_key(&[1, 2, 3]),
+        Err(VetKdArgumentError::InvalidTransportPublicKey)
+    );
+}
@@ -924,6 +924,29 @@ impl CyclesAccountManager {
             + self.config.http_response_per_byte_fee * response_size)
             * (subnet_size as u64)
     }
+
+    /// Returns the fee for a non-replicated HTTP request.
+    ///
+    /// The request is performed by a single replica and no response shares
+    /// are gossiped, so the baseline and request fees are charged once.
+    /// The response is still included in a block and processed by every
+    /// replica, so its fee scales with the subnet size.
+    pub fn http_request_fee_non_replicated(
+        &self,
+        request_size: NumBytes,
+        response_size_limit: Option<NumBytes>,
+        subnet_size: usize,
+    ) -> Cycles {
+        let response_size = match response_size_limit {
+            Some(response_size) => response_size.get(),
+            // Defaults to maximum response size.
+            None => MAX_CANISTER_HTTP_RESPONSE_BYTES,
+        };
+
+        self.config.http_request_linear_baseline_fee
+            + self.config.http_request_per_byte_fee * request_size.get()
+            + self.config.http_response_per_byte_fee * response_size * (subnet_size as u64)
+    }
 }
 
 /// Encapsulates the payer and cost of inducting an ingress messages.
@@ -1035,4 +1058,32 @@ mod tests {
             Cycles::from(1_605_046_800u64) * subnet_size
         );
     }
+
+    #[test]
+    fn http_request_fee_non_replicated_is_cheaper() {
+        let reference_subnet_size: u64 = 13;
+        let request_size = NumBytes::from(17);
+        let response_size = NumBytes::from(1_000);
+        let cycles_account_manager = create_cycles_account_manager(reference_subnet_size as usize);
+
+        assert_eq!(
+            cycles_account_manager.http_request_fee_non_replicated(
+                request_size,
+                Some(response_size),
+                reference_subnet_size as usize,
+            ),
+            Cycles::from(3_006_800u64) + Cycles::from(800_000u64) * reference_subnet_size
+        );
+        assert!(
+            cycles_account_manager.http_request_fee_non_replicated(
+                request_size,
+                Some(response_size),
+                reference_subnet_size as usize,
+            ) < cycles_account_manager.http_request_fee(
+                request_size,
+                Some(response_size),
+                reference_subnet_size as usize,
+            )
+        );
+    }
 }
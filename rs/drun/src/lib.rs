@@ -41,9 +41,11 @@ use ic_types::{
 };
 use rand::distributions::{Distribution, Uniform};
 use slog::{Drain, Logger};
+use std::cell::Cell;
 use std::collections::BTreeMap;
 use std::fs::OpenOptions;
 use std::path::PathBuf;
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 use std::{thread::sleep, time::Duration};
 
@@ -64,20 +66,28 @@ pub struct DrunOptions {
     pub subnet_type: SubnetType,
 }
 
-/// Deliver a single message to the Message Routing layer
+/// Deliver a single message to the Message Routing layer and return its result
 fn deliver_message(
     msg: SignedIngress,
     message_routing: &dyn MessageRouting,
     ingress_hist_reader: &dyn IngressHistoryReader,
     extra_batches: u64,
-) {
+    time_offset: Duration,
+) -> Result<WasmResult, UserError> {
     let message_id = msg.id();
 
-    let _ = execute_ingress_message(message_routing, msg, &message_id, ingress_hist_reader);
+    let result = execute_ingress_message(
+        message_routing,
+        msg,
+        &message_id,
+        ingress_hist_reader,
+        time_offset,
+    );
     // print result after waiting, to not interleave the result
     // with debug.print messages from subsequent calls. revise after DFN-1269.
-    wait_extra_batches(message_routing, extra_batches);
+    wait_extra_batches(message_routing, extra_batches, time_offset);
     print_ingress_result(&message_id, ingress_hist_reader);
+    result
 }
 
 fn setup_logger(log_file: PathBuf) -> Logger {
@@ -182,7 +192,10 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
         subnet_id,
     };
 
-    let mut msg_stream = msg_stream_from_file(&msg_filename)?;
+    // The offset by which `advance-time` has moved the time of batches (and
+    // the expiry of ingress messages) beyond the wall-clock time.
+    let time_offset = Rc::new(Cell::new(Duration::ZERO));
+    let mut msg_stream = msg_stream_from_file(&msg_filename, Rc::clone(&time_offset))?;
     let log = match log_file {
         Some(log_file) => setup_logger(log_file),
         None => slog::Logger::root(slog::Discard, slog::o!()),
@@ -249,49 +262,57 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
         MaliciousFlags::default(),
     );
 
+    let mut last_result: Option<Result<WasmResult, UserError>> = None;
+    let mut failed_expectations = 0;
     msg_stream.try_for_each(|parse_result| {
         parse_result.map(|msg| match msg {
-            Message::Install(msg) => {
-                deliver_message(
+            Message::Install(msg) | Message::Ingress(msg) | Message::Create(msg) => {
+                last_result = Some(deliver_message(
                     msg,
                     &message_routing,
                     ingress_hist_reader.as_ref(),
                     extra_batches,
-                );
+                    time_offset.get(),
+                ));
             }
 
             Message::Query(q) => {
                 // NOTE: Data certificates aren't supported in drun yet.
                 // To support them, we'd need to do something similar to
                 // http_handler::get_latest_certified_state_and_data_certificate
-                print_query_result(query_handler.query(
-                    q,
-                    state_manager.get_latest_state().take(),
-                    Vec::new(),
-                ));
+                let result =
+                    query_handler.query(q, state_manager.get_latest_state().take(), Vec::new());
+                print_query_result(&result);
+                last_result = Some(result);
             }
 
-            Message::Ingress(msg) => {
-                deliver_message(
-                    msg,
-                    &message_routing,
-                    ingress_hist_reader.as_ref(),
-                    extra_batches,
-                );
+            Message::AdvanceTime(duration) => {
+                time_offset.set(time_offset.get() + duration);
+                // Deliver a batch so that the new time is observed by
+                // canisters (e.g. by their timers) before the next message.
+                wait_extra_batches(&message_routing, 1, time_offset.get());
             }
-            Message::Create(msg) => {
-                deliver_message(
-                    msg,
-                    &message_routing,
-                    ingress_hist_reader.as_ref(),
-                    extra_batches,
-                );
+
+            Message::Expect(expectation) => {
+                let check = match &last_result {
+                    Some(result) => expectation.check(result),
+                    None => Err("no message to check the expectation against".to_string()),
+                };
+                if let Err(mismatch) = check {
+                    println!("Expectation failed: {}", mismatch);
+                    failed_expectations += 1;
+                }
             }
         })
-    })
+    })?;
+
+    if failed_expectations > 0 {
+        return Err(format!("{} expectation(s) failed", failed_expectations));
+    }
+    Ok(())
 }
 
-fn print_query_result(res: Result<WasmResult, UserError>) {
+fn print_query_result(res: &Result<WasmResult, UserError>) {
     match res {
         Ok(payload) => {
             print!("Ok: ");
@@ -310,7 +331,7 @@ fn print_ingress_result(message_id: &MessageId, ingress_hist_reader: &dyn Ingres
             ..
         } => {
             print!("Completed: ");
-            print_wasm_result(result)
+            print_wasm_result(&result)
         }
         IngressStatus::Known {
             state: IngressState::Failed(error),
@@ -320,7 +341,7 @@ fn print_ingress_result(message_id: &MessageId, ingress_hist_reader: &dyn Ingres
     };
 }
 
-fn print_wasm_result(wasm_result: WasmResult) {
+fn print_wasm_result(wasm_result: &WasmResult) {
     match wasm_result {
         WasmResult::Reply(v) => println!("Reply: 0x{}", encode(v)),
         WasmResult::Reject(e) => println!("Reject: {}", e),
@@ -334,7 +355,11 @@ fn get_random_seed() -> [u8; 32] {
     seed.try_into().unwrap()
 }
 
-fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -> Batch {
+fn build_batch(
+    message_routing: &dyn MessageRouting,
+    msgs: Vec<SignedIngress>,
+    time_offset: Duration,
+) -> Batch {
     Batch {
         batch_number: message_routing.expected_batch_height(),
         requires_full_state_hash: !msgs.is_empty(),
@@ -344,8 +369,10 @@ fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -
         },
         randomness: Randomness::from(get_random_seed()),
         ecdsa_subnet_public_keys: BTreeMap::new(),
+        schnorr_subnet_public_keys: BTreeMap::new(),
+        vetkd_subnet_public_keys: BTreeMap::new(),
         registry_version: RegistryVersion::from(1),
-        time: time::current_time(),
+        time: time::current_time() + time_offset,
         consensus_responses: vec![],
     }
 }
@@ -358,8 +385,9 @@ fn execute_ingress_message(
     msg: SignedIngress,
     msg_id: &MessageId,
     ingress_history: &dyn IngressHistoryReader,
+    time_offset: Duration,
 ) -> Result<WasmResult, UserError> {
-    let mut batch = build_batch(message_routing, vec![msg]);
+    let mut batch = build_batch(message_routing, vec![msg], time_offset);
     for _ in 0..MAX_BATCHES_UNTIL_RESPONSE {
         // In the first batch we try to send the ingress message itself. If it fails, we
         // repeat with the same batch.
@@ -370,7 +398,7 @@ fn execute_ingress_message(
         // potential inter-canister messages that the ingress message may have
         // triggered.
         if message_routing.deliver_batch(batch.clone()).is_ok() {
-            batch = build_batch(message_routing, vec![])
+            batch = build_batch(message_routing, vec![], time_offset)
         }
         sleep(WAIT_PER_BATCH);
 
@@ -416,10 +444,14 @@ fn execute_ingress_message(
 ///
 /// This is a temporary measure until DFN-1269 is resolved. In that ticket, we
 /// will actually try to wait until all messages have been executed.
-fn wait_extra_batches(message_routing: &dyn MessageRouting, extra_batches: u64) {
+fn wait_extra_batches(
+    message_routing: &dyn MessageRouting,
+    extra_batches: u64,
+    time_offset: Duration,
+) {
     for _ in 0..extra_batches {
         loop {
-            let batch = build_batch(message_routing, vec![]);
+            let batch = build_batch(message_routing, vec![], time_offset);
             let ok = message_routing.deliver_batch(batch).is_ok();
             sleep(WAIT_PER_BATCH);
             if ok {
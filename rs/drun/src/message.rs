@@ -1,20 +1,33 @@
+//! Parsing of drun message files. See `README.adoc` for the file format.
+
 use super::CanisterId;
 
+use candid::{IDLArgs, TypeEnv};
 use hex::decode;
-use ic_ic00_types::{self as ic00, CanisterInstallMode, Payload};
+use ic_error_types::{RejectCode, UserError};
+use ic_ic00_types::{
+    self as ic00, CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgsBuilder, Payload,
+    UpdateSettingsArgs,
+};
+use ic_test_utilities::types::messages::SignedIngressBuilder;
 use ic_types::{
+    ingress::WasmResult,
     messages::{SignedIngress, UserQuery},
     time::expiry_time_from_now,
-    PrincipalId, UserId,
+    PrincipalId, Time, UserId,
 };
+use regex::Regex;
 
 use std::{
+    cell::Cell,
     convert::TryFrom,
     fmt,
     fs::File,
     io::{self, Read},
-    str::Chars,
+    rc::Rc,
+    str::{Chars, FromStr},
     string::FromUtf8Error,
+    time::Duration,
 };
 
 #[derive(Debug, PartialEq)]
@@ -23,6 +36,127 @@ pub(crate) enum Message {
     Query(UserQuery),
     Install(SignedIngress),
     Create(SignedIngress),
+    AdvanceTime(Duration),
+    Expect(Expectation),
+}
+
+/// An assertion on the result of the preceding message.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Expectation {
+    Reply(Vec<u8>),
+    /// A reply whose Candid value equals the given Candid text. Numbers in
+    /// the text take the types of the actual reply.
+    CandidReply(String),
+    /// A reject, with the given reject code and a message matching the given
+    /// regular expression, if specified.
+    Reject {
+        code: Option<RejectCode>,
+        message: Option<String>,
+    },
+}
+
+impl Expectation {
+    /// Returns a description of the mismatch if `result` does not meet this
+    /// expectation.
+    pub(crate) fn check(&self, result: &Result<WasmResult, UserError>) -> Result<(), String> {
+        let (actual_code, actual_message) = match (self, result) {
+            (Expectation::Reply(expected), Ok(WasmResult::Reply(actual))) => {
+                if expected == actual {
+                    return Ok(());
+                }
+                return Err(format!(
+                    "expected reply 0x{}, got reply 0x{}",
+                    hex::encode(expected),
+                    hex::encode(actual)
+                ));
+            }
+            (Expectation::CandidReply(expected), Ok(WasmResult::Reply(actual))) => {
+                return check_candid_reply(expected, actual);
+            }
+            (
+                Expectation::Reply(_) | Expectation::CandidReply(_),
+                Ok(WasmResult::Reject(message)),
+            ) => {
+                return Err(format!(
+                    "expected reply {}, got reject: {}",
+                    self.expected_reply(),
+                    message
+                ));
+            }
+            (Expectation::Reply(_) | Expectation::CandidReply(_), Err(err)) => {
+                return Err(format!(
+                    "expected reply {}, got error: {}",
+                    self.expected_reply(),
+                    err
+                ));
+            }
+            (Expectation::Reject { .. }, Ok(WasmResult::Reply(actual))) => {
+                return Err(format!(
+                    "expected reject, got reply 0x{}",
+                    hex::encode(actual)
+                ));
+            }
+            (Expectation::Reject { .. }, Ok(WasmResult::Reject(message))) => {
+                (RejectCode::CanisterReject, message.as_str())
+            }
+            (Expectation::Reject { .. }, Err(err)) => (err.reject_code(), err.description()),
+        };
+        if let Expectation::Reject { code, message } = self {
+            if let Some(code) = code {
+                if *code != actual_code {
+                    return Err(format!(
+                        "expected reject code {}, got reject code {}: {}",
+                        *code as u64, actual_code as u64, actual_message
+                    ));
+                }
+            }
+            if let Some(message) = message {
+                // The regex has been validated when parsing the expectation.
+                if !Regex::new(message).unwrap().is_match(actual_message) {
+                    return Err(format!(
+                        "expected reject message matching {}, got: {}",
+                        message, actual_message
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn expected_reply(&self) -> String {
+        match self {
+            Expectation::Reply(expected) => format!("0x{}", hex::encode(expected)),
+            Expectation::CandidReply(expected) => expected.clone(),
+            Expectation::Reject { .. } => unreachable!("a reject expectation has no reply"),
+        }
+    }
+}
+
+/// Compares the Candid value of the `actual` reply with the `expected` Candid
+/// text. The expected values are annotated with the types of the actual
+/// values, so that e.g. `(42)` matches a reply of type `nat8`.
+fn check_candid_reply(expected: &str, actual: &[u8]) -> Result<(), String> {
+    let actual_args = IDLArgs::from_bytes(actual).map_err(|e| {
+        format!(
+            "expected reply {}, got non-Candid reply 0x{}: {}",
+            expected,
+            hex::encode(actual),
+            e
+        )
+    })?;
+    let types: Vec<_> = actual_args.args.iter().map(|v| v.value_ty()).collect();
+    // The Candid text has been validated when parsing the expectation.
+    let expected_args =
+        IDLArgs::from_str(expected)
+            .unwrap()
+            .annotate_types(true, &TypeEnv::new(), &types);
+    match expected_args {
+        Ok(expected_args) if expected_args == actual_args => Ok(()),
+        _ => Err(format!(
+            "expected reply {}, got reply {}",
+            expected, actual_args
+        )),
+    }
 }
 
 #[derive(Debug)]
@@ -123,8 +257,12 @@ impl<R: Read> Iterator for LineIterator<R> {
     }
 }
 
+/// Returns the messages in the given file. The ingress expiry of each message
+/// is derived from the current time plus `time_offset` at the time the message
+/// is parsed, so that messages do not expire after `advance-time`.
 pub(crate) fn msg_stream_from_file(
     filename: &str,
+    time_offset: Rc<Cell<Duration>>,
 ) -> Result<impl Iterator<Item = Result<Message, String>>, String> {
     let f = File::open(filename).map_err(|e| e.to_string())?;
     let line_iterator = LineIterator::new(f);
@@ -136,23 +274,23 @@ pub(crate) fn msg_stream_from_file(
             Ok(s) => !s.is_empty() && !s.starts_with('#'),
             _ => true,
         })
-        .map(|(i, line)| match line {
+        .map(move |(i, line)| match line {
             Ok(line) => {
-                parse_message(&line, i as u64).map_err(|e| format!("Line {}: {}", i + 1, e))
+                let expiry_time = expiry_time_from_now() + time_offset.get();
+                parse_message(&line, i as u64, expiry_time)
+                    .map_err(|e| format!("Line {}: {}", i + 1, e))
             }
             Err(e) => Err(format!("Error while reading line {}: {}", i, e)),
         }))
 }
 
-fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
+fn parse_message(s: &str, nonce: u64, expiry_time: Time) -> Result<Message, String> {
     let s = s.trim_end();
     let tokens: Vec<&str> = s.splitn(4, char::is_whitespace).collect();
 
     match &tokens[..] {
         [] => Err("Too few arguments.".to_string()),
         ["ingress", canister_id, method_name, payload] => {
-            use ic_test_utilities::types::messages::SignedIngressBuilder;
-
             let canister_id = parse_canister_id(canister_id)?;
             let method_name = validate_method_name(method_name)?;
             let method_payload = parse_octet_string(payload)?;
@@ -164,6 +302,7 @@ fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
                 .method_name(method_name)
                 .method_payload(method_payload)
                 .nonce(nonce)
+                .expiry_time(expiry_time)
                 .build();
             Ok(Message::Ingress(signed_ingress))
         }
@@ -172,19 +311,71 @@ fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
             receiver: parse_canister_id(canister_id)?,
             method_name: validate_method_name(method_name)?,
             method_payload: parse_octet_string(payload)?,
-            ingress_expiry: expiry_time_from_now().as_nanos_since_unix_epoch(),
+            ingress_expiry: expiry_time.as_nanos_since_unix_epoch(),
             nonce: Some(nonce.to_le_bytes().to_vec()),
         })),
-        ["create"] => parse_create(nonce),
-        ["install", canister_id, wasm_file, payload] => {
-            parse_install(nonce, canister_id, payload, wasm_file, "install")
-        }
-        ["reinstall", canister_id, wasm_file, payload] => {
-            parse_install(nonce, canister_id, payload, wasm_file, "reinstall")
-        }
-        ["upgrade", canister_id, wasm_file, payload] => {
-            parse_install(nonce, canister_id, payload, wasm_file, "upgrade")
+        ["create"] => parse_create(nonce, expiry_time),
+        ["install", canister_id, wasm_file, payload] => parse_install(
+            nonce,
+            expiry_time,
+            canister_id,
+            payload,
+            wasm_file,
+            "install",
+        ),
+        ["reinstall", canister_id, wasm_file, payload] => parse_install(
+            nonce,
+            expiry_time,
+            canister_id,
+            payload,
+            wasm_file,
+            "reinstall",
+        ),
+        ["upgrade", canister_id, wasm_file, payload] => parse_install(
+            nonce,
+            expiry_time,
+            canister_id,
+            payload,
+            wasm_file,
+            "upgrade",
+        ),
+        ["stop", canister_id] => Ok(Message::Ingress(management_canister_ingress(
+            nonce,
+            expiry_time,
+            ic00::Method::StopCanister,
+            CanisterIdRecord::from(parse_canister_id(canister_id)?).encode(),
+        ))),
+        ["start", canister_id] => Ok(Message::Ingress(management_canister_ingress(
+            nonce,
+            expiry_time,
+            ic00::Method::StartCanister,
+            CanisterIdRecord::from(parse_canister_id(canister_id)?).encode(),
+        ))),
+        ["controller", canister_id, controllers @ ..] if !controllers.is_empty() => {
+            let controllers = controllers
+                .iter()
+                .flat_map(|controllers| controllers.split_whitespace())
+                .map(|controller| {
+                    PrincipalId::from_str(controller).map_err(|err| {
+                        format!(
+                            "Failed to convert {} to principal id with {}",
+                            controller, err
+                        )
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let settings = CanisterSettingsArgsBuilder::new()
+                .with_controllers(controllers)
+                .build();
+            Ok(Message::Ingress(management_canister_ingress(
+                nonce,
+                expiry_time,
+                ic00::Method::UpdateSettings,
+                UpdateSettingsArgs::new(parse_canister_id(canister_id)?, settings).encode(),
+            )))
         }
+        ["advance-time", duration] => Ok(Message::AdvanceTime(parse_duration(duration)?)),
+        ["expect", ..] => parse_expectation(s),
         _ => Err(format!(
             "Failed to parse line {}, don't have a pattern to match this with",
             s
@@ -209,28 +400,25 @@ fn parse_canister_id(canister_id: &str) -> Result<CanisterId, String> {
     }
 }
 
-fn parse_create(nonce: u64) -> Result<Message, String> {
-    use ic_test_utilities::types::messages::SignedIngressBuilder;
-
-    let signed_ingress = SignedIngressBuilder::new()
-        .method_name(ic00::Method::ProvisionalCreateCanisterWithCycles)
-        .canister_id(ic00::IC_00)
-        .method_payload(ic00::ProvisionalCreateCanisterWithCyclesArgs::new(None, None).encode())
-        .nonce(nonce)
-        .build();
+fn parse_create(nonce: u64, expiry_time: Time) -> Result<Message, String> {
+    let signed_ingress = management_canister_ingress(
+        nonce,
+        expiry_time,
+        ic00::Method::ProvisionalCreateCanisterWithCycles,
+        ic00::ProvisionalCreateCanisterWithCyclesArgs::new(None, None).encode(),
+    );
 
     Ok(Message::Create(signed_ingress))
 }
 
 fn parse_install(
     nonce: u64,
+    expiry_time: Time,
     canister_id: &str,
     payload: &str,
     wasm_file: &str,
     mode: &str,
 ) -> Result<Message, String> {
-    use ic_test_utilities::types::messages::SignedIngressBuilder;
-
     let mut wasm_data = Vec::new();
     let mut wasm_file = File::open(wasm_file)
         .map_err(|e| format!("Could not open wasm file: {} - Error: {}", wasm_file, e))?;
@@ -241,26 +429,104 @@ fn parse_install(
     let canister_id = parse_canister_id(canister_id)?;
     let payload = parse_octet_string(payload)?;
 
-    let signed_ingress = SignedIngressBuilder::new()
+    let signed_ingress = management_canister_ingress(
+        nonce,
+        expiry_time,
+        ic00::Method::InstallCode,
+        ic00::InstallCodeArgs::new(
+            CanisterInstallMode::try_from(mode.to_string()).unwrap(),
+            canister_id,
+            wasm_data,
+            payload,
+            None,
+            Some(8 * 1024 * 1024 * 1024), // drun users dont care about memory limits
+            None,
+        )
+        .encode(),
+    );
+    Ok(Message::Install(signed_ingress))
+}
+
+fn management_canister_ingress(
+    nonce: u64,
+    expiry_time: Time,
+    method: ic00::Method,
+    payload: Vec<u8>,
+) -> SignedIngress {
+    SignedIngressBuilder::new()
         // `source` should become a self-authenticating id according
         // to https://sdk.dfinity.org/docs/interface-spec/index.html#id-classes
         .canister_id(ic00::IC_00)
-        .method_name(ic00::Method::InstallCode)
-        .method_payload(
-            ic00::InstallCodeArgs::new(
-                CanisterInstallMode::try_from(mode.to_string()).unwrap(),
-                canister_id,
-                wasm_data,
-                payload,
-                None,
-                Some(8 * 1024 * 1024 * 1024), // drun users dont care about memory limits
-                None,
-            )
-            .encode(),
-        )
+        .method_name(method)
+        .method_payload(payload)
         .nonce(nonce)
-        .build();
-    Ok(Message::Install(signed_ingress))
+        .expiry_time(expiry_time)
+        .build()
+}
+
+fn parse_expectation(s: &str) -> Result<Message, String> {
+    let tokens: Vec<&str> = s.splitn(3, char::is_whitespace).collect();
+    let expectation = match &tokens[..] {
+        ["expect", "reply", payload] if payload.starts_with('(') => {
+            parse_candid(payload)?;
+            Expectation::CandidReply(payload.to_string())
+        }
+        ["expect", "reply", payload] => Expectation::Reply(parse_octet_string(payload)?),
+        ["expect", "reject"] => Expectation::Reject {
+            code: None,
+            message: None,
+        },
+        ["expect", "reject", rest] => {
+            let rest = rest.trim_start();
+            // A leading number is the reject code, the rest is the regex.
+            let (code, message) = match rest.split_once(char::is_whitespace) {
+                Some((code, message)) if code.parse::<u64>().is_ok() => (Some(code), message),
+                None if rest.parse::<u64>().is_ok() => (Some(rest), ""),
+                _ => (None, rest),
+            };
+            let code = code
+                .map(|code| {
+                    RejectCode::try_from(code.parse::<u64>().unwrap())
+                        .map_err(|_| format!("Illegal reject code {}.", code))
+                })
+                .transpose()?;
+            let message = match message.trim() {
+                "" => None,
+                message => {
+                    Regex::new(message)
+                        .map_err(|e| format!("Illegal regex {}: {}", message, e))?;
+                    Some(message.to_string())
+                }
+            };
+            Expectation::Reject { code, message }
+        }
+        _ => {
+            return Err(format!(
+                "Failed to parse expectation {}, expected `expect reply <payload>` or `expect reject [<reject_code>] [<regex>]`",
+                s
+            ))
+        }
+    };
+    Ok(Message::Expect(expectation))
+}
+
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let unit_start = s
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| format!("Missing unit in duration {}.", s))?;
+    let (amount, unit) = s.split_at(unit_start);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|e| format!("Illegal duration {}: {}", s, e))?;
+    match unit {
+        "ns" => Ok(Duration::from_nanos(amount)),
+        "us" => Ok(Duration::from_micros(amount)),
+        "ms" => Ok(Duration::from_millis(amount)),
+        "s" => Ok(Duration::from_secs(amount)),
+        "m" => Ok(Duration::from_secs(amount * 60)),
+        "h" => Ok(Duration::from_secs(amount * 60 * 60)),
+        _ => Err(format!("Illegal unit in duration {}.", s)),
+    }
 }
 
 fn validate_method_name(method_name: &str) -> Result<String, String> {
@@ -286,11 +552,19 @@ fn validate_method_name(method_name: &str) -> Result<String, String> {
 fn parse_octet_string(input_str: &str) -> Result<Vec<u8>, String> {
     if input_str.starts_with('"') {
         parse_quoted(input_str)
+    } else if input_str.starts_with('(') {
+        parse_candid(input_str)
     } else {
         parse_hex(input_str)
     }
 }
 
+fn parse_candid(candid_str: &str) -> Result<Vec<u8>, String> {
+    IDLArgs::from_str(candid_str)
+        .and_then(|args| args.to_bytes())
+        .map_err(|e| format!("Illegal Candid arguments {}: {}", candid_str, e))
+}
+
 fn parse_quoted(quoted_str: &str) -> Result<Vec<u8>, String> {
     if !quoted_str.is_ascii() {
         return Err(String::from("Only ASCII strings are allowed."));
@@ -383,7 +657,7 @@ mod tests {
             "ingress {} write \"payload \\x0a\\b00010001\"",
             APP_CANISTER_URL
         );
-        let parsed_message = parse_message(s, 0).unwrap();
+        let parsed_message = parse_message(s, 0, expiry_time_from_now()).unwrap();
         let expiry_time = match &parsed_message {
             Message::Ingress(signed_ingress) => signed_ingress.expiry_time(),
             _ => panic!(
@@ -406,7 +680,7 @@ mod tests {
     #[test]
     fn test_parse_message_hex_payload_succeeds() {
         let s = &format!("ingress {} write 0x010203", APP_CANISTER_URL);
-        let parsed_message = parse_message(s, 0).unwrap();
+        let parsed_message = parse_message(s, 0, expiry_time_from_now()).unwrap();
         let expiry_time = match &parsed_message {
             Message::Ingress(signed_ingress) => signed_ingress.expiry_time(),
             _ => panic!(
@@ -427,7 +701,7 @@ mod tests {
 
         let s = &format!("query {} read 0x010203", APP_CANISTER_URL);
         let nonce: u64 = 0;
-        let parsed_message = parse_message(s, 0).unwrap();
+        let parsed_message = parse_message(s, 0, expiry_time_from_now()).unwrap();
         let ingress_expiry = match &parsed_message {
             Message::Query(query) => query.ingress_expiry,
             _ => panic!(
@@ -449,25 +723,129 @@ mod tests {
     #[test]
     fn test_parse_message_invalid_escapes_fails() {
         let s = &format!("query {} read \"\\xzz\"", APP_CANISTER_URL);
-        assert!(parse_message(s, 0).is_err());
+        assert!(parse_message(s, 0, expiry_time_from_now()).is_err());
 
         let s = &format!("query {} read \"\\b01\"", APP_CANISTER_URL);
-        assert!(parse_message(s, 0).is_err());
+        assert!(parse_message(s, 0, expiry_time_from_now()).is_err());
 
         let s = &format!("query {} read \"\\x1\"", APP_CANISTER_URL);
-        assert!(parse_message(s, 0).is_err());
+        assert!(parse_message(s, 0, expiry_time_from_now()).is_err());
 
         let s = &format!("query {} read \"\\b2\"", APP_CANISTER_URL);
-        assert!(parse_message(s, 0).is_err());
+        assert!(parse_message(s, 0, expiry_time_from_now()).is_err());
+    }
+
+    #[test]
+    fn test_parse_message_candid_payload_succeeds() {
+        let s = &format!("ingress {} greet (\"world\", 42 : nat8)", APP_CANISTER_URL);
+        match parse_message(s, 0, expiry_time_from_now()).unwrap() {
+            Message::Ingress(signed_ingress) => assert_eq!(
+                signed_ingress.content().arg(),
+                candid::encode_args(("world", 42u8)).unwrap()
+            ),
+            msg => panic!("parse_message() returned an unexpected message: {:?}", msg),
+        }
+
+        let s = &format!("ingress {} greet (\"world\"", APP_CANISTER_URL);
+        assert!(parse_message(s, 0, expiry_time_from_now()).is_err());
+    }
+
+    #[test]
+    fn test_parse_advance_time() {
+        assert_eq!(
+            parse_message("advance-time 10s", 0, expiry_time_from_now()),
+            Ok(Message::AdvanceTime(Duration::from_secs(10)))
+        );
+        assert_eq!(
+            parse_message("advance-time 5m", 0, expiry_time_from_now()),
+            Ok(Message::AdvanceTime(Duration::from_secs(300)))
+        );
+        assert!(parse_message("advance-time 10", 0, expiry_time_from_now()).is_err());
+        assert!(parse_message("advance-time 10d", 0, expiry_time_from_now()).is_err());
+    }
+
+    #[test]
+    fn test_parse_expectations() {
+        assert_eq!(
+            parse_message("expect reply 0x0102", 0, expiry_time_from_now()),
+            Ok(Message::Expect(Expectation::Reply(vec![1, 2])))
+        );
+        assert_eq!(
+            parse_message("expect reject", 0, expiry_time_from_now()),
+            Ok(Message::Expect(Expectation::Reject {
+                code: None,
+                message: None,
+            }))
+        );
+        assert_eq!(
+            parse_message("expect reject 4 trapped: .*", 0, expiry_time_from_now()),
+            Ok(Message::Expect(Expectation::Reject {
+                code: Some(RejectCode::CanisterReject),
+                message: Some("trapped: .*".to_string()),
+            }))
+        );
+        assert_eq!(
+            parse_message("expect reject out of cycles", 0, expiry_time_from_now()),
+            Ok(Message::Expect(Expectation::Reject {
+                code: None,
+                message: Some("out of cycles".to_string()),
+            }))
+        );
+        assert_eq!(
+            parse_message("expect reply (42)", 0, expiry_time_from_now()),
+            Ok(Message::Expect(Expectation::CandidReply(
+                "(42)".to_string()
+            )))
+        );
+        assert!(parse_message("expect reply (42", 0, expiry_time_from_now()).is_err());
+        assert!(parse_message("expect reject 42", 0, expiry_time_from_now()).is_err());
+        assert!(parse_message("expect reject (", 0, expiry_time_from_now()).is_err());
+        assert!(parse_message("expect nothing", 0, expiry_time_from_now()).is_err());
+    }
+
+    #[test]
+    fn test_check_expectation() {
+        let reply = Ok(WasmResult::Reply(vec![1, 2]));
+        let reject = Ok(WasmResult::Reject("no thanks".to_string()));
+        assert!(Expectation::Reply(vec![1, 2]).check(&reply).is_ok());
+        assert!(Expectation::Reply(vec![1]).check(&reply).is_err());
+        assert!(Expectation::Reply(vec![1, 2]).check(&reject).is_err());
+
+        // The numbers of a Candid expectation take the types of the reply.
+        let candid_reply = Ok(WasmResult::Reply(
+            candid::encode_args(("hi", 42u8, 7u64)).unwrap(),
+        ));
+        let expectation = Expectation::CandidReply("(\"hi\", 42, 7)".to_string());
+        assert!(expectation.check(&candid_reply).is_ok());
+        assert!(Expectation::CandidReply("(\"hi\", 42, 8)".to_string())
+            .check(&candid_reply)
+            .is_err());
+        assert!(Expectation::CandidReply("(42)".to_string())
+            .check(&candid_reply)
+            .is_err());
+        assert!(expectation.check(&reply).is_err());
+        assert!(expectation.check(&reject).is_err());
+
+        let expectation = Expectation::Reject {
+            code: Some(RejectCode::CanisterReject),
+            message: Some("^no".to_string()),
+        };
+        assert!(expectation.check(&reject).is_ok());
+        assert!(expectation.check(&reply).is_err());
+        let err = Err(UserError::new(
+            ic_error_types::ErrorCode::CanisterNotFound,
+            "no such canister",
+        ));
+        assert!(expectation.check(&err).is_err());
     }
 
     #[test]
     fn test_illegal_method_name_must_fail() {
         let s = &format!("query {} 0read \"\\xzz\"", APP_CANISTER_URL);
-        assert!(parse_message(s, 0).is_err());
+        assert!(parse_message(s, 0, expiry_time_from_now()).is_err());
 
         let s = &format!("query {} üread \"\\xzz\"", APP_CANISTER_URL);
-        assert!(parse_message(s, 0).is_err());
+        assert!(parse_message(s, 0, expiry_time_from_now()).is_err());
     }
 
     #[test]
@@ -1,21 +1,39 @@
+mod disk;
+
 use std::sync::{Arc, Mutex};
 
 use crate::SerializedModule;
+pub use disk::DiskCompilationCache;
 use ic_interfaces::execution_environment::HypervisorResult;
-use ic_types::NumBytes;
+use ic_types::{CountBytes, NumBytes};
 use ic_utils_lru_cache::LruCache;
 use ic_wasm_types::{CanisterModule, WasmHash};
 
 /// Stores the serialized modules of wasm code that has already been compiled so
 /// that it can be used again without recompiling.
+///
+/// The in-memory cache can optionally be backed by a [`DiskCompilationCache`]
+/// that keeps successfully compiled modules across restarts.
 pub struct CompilationCache {
     cache: Mutex<LruCache<WasmHash, HypervisorResult<Arc<SerializedModule>>>>,
+    capacity: NumBytes,
+    disk_cache: Option<DiskCompilationCache>,
 }
 
 impl CompilationCache {
     pub fn new(capacity: NumBytes) -> Self {
         Self {
             cache: Mutex::new(LruCache::new(capacity)),
+            capacity,
+            disk_cache: None,
+        }
+    }
+
+    /// Creates a cache whose entries are also persisted in the given disk cache.
+    pub fn new_with_disk_cache(capacity: NumBytes, disk_cache: DiskCompilationCache) -> Self {
+        Self {
+            disk_cache: Some(disk_cache),
+            ..Self::new(capacity)
         }
     }
 
@@ -24,25 +42,148 @@ impl CompilationCache {
         canister_module: &CanisterModule,
         serialized_module: HypervisorResult<Arc<SerializedModule>>,
     ) {
+        let wasm_hash = WasmHash::from(canister_module);
+        if let (Some(disk_cache), Ok(serialized_module)) = (&self.disk_cache, &serialized_module) {
+            disk_cache.insert(&wasm_hash, serialized_module);
+        }
         self.cache
             .lock()
             .unwrap()
-            .push(WasmHash::from(canister_module), serialized_module);
+            .push(wasm_hash, serialized_module);
     }
 
     pub fn get(
         &self,
         canister_module: &CanisterModule,
     ) -> Option<HypervisorResult<Arc<SerializedModule>>> {
+        let wasm_hash = WasmHash::from(canister_module);
+        let cached = self
+            .cache
+            .lock()
+            .unwrap()
+            .get(&wasm_hash)
+            .map(|o| o.as_ref().map(Arc::clone).map_err(|e| e.clone()));
+        if cached.is_some() {
+            return cached;
+        }
+
+        let serialized_module = Arc::new(self.disk_cache.as_ref()?.get(&wasm_hash)?);
         self.cache
             .lock()
             .unwrap()
-            .get(&WasmHash::from(canister_module))
-            .map(|o| o.as_ref().map(Arc::clone).map_err(|e| e.clone()))
+            .push(wasm_hash, Ok(Arc::clone(&serialized_module)));
+        Some(Ok(serialized_module))
+    }
+
+    /// Loads the most recently used modules of the disk cache into memory, as
+    /// many as fit into the in-memory cache. Returns the number of loaded
+    /// modules.
+    pub fn warm_up(&self) -> usize {
+        let disk_cache = match &self.disk_cache {
+            Some(disk_cache) => disk_cache,
+            None => return 0,
+        };
+
+        let mut total_size = 0;
+        let wasm_hashes: Vec<_> = disk_cache
+            .entries()
+            .into_iter()
+            .take_while(|(wasm_hash, size)| {
+                total_size += wasm_hash.count_bytes() as u64 + size.get();
+                total_size <= self.capacity.get()
+            })
+            .map(|(wasm_hash, _)| wasm_hash)
+            .collect();
+
+        // Load the least recently used modules first, so that the in-memory
+        // cache ends up with the same order as the disk cache.
+        let mut loaded = 0;
+        for wasm_hash in wasm_hashes.into_iter().rev() {
+            if let Some(serialized_module) = disk_cache.get(&wasm_hash) {
+                self.cache
+                    .lock()
+                    .unwrap()
+                    .push(wasm_hash, Ok(Arc::new(serialized_module)));
+                loaded += 1;
+            }
+        }
+        loaded
     }
 
     #[doc(hidden)]
     pub fn clear_for_testing(&self) {
-        self.cache.lock().unwrap().clear()
+        self.cache.lock().unwrap().clear();
+        if let Some(disk_cache) = &self.disk_cache {
+            disk_cache.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{wasm_utils::compile, WasmtimeEmbedder};
+    use ic_config::embedders::Config as EmbeddersConfig;
+    use ic_logger::replica_logger::no_op_logger;
+    use ic_wasm_types::BinaryEncodedWasm;
+
+    const CAPACITY: NumBytes = NumBytes::new(1 << 30);
+
+    fn compiled_module() -> (CanisterModule, Arc<SerializedModule>) {
+        let wasm = wat::parse_str(r#"(module (func (export "canister_query go")))"#).unwrap();
+        let canister_module = CanisterModule::new(wasm.clone());
+        let embedder = WasmtimeEmbedder::new(EmbeddersConfig::default(), no_op_logger());
+        let (_, result) = compile(&embedder, &BinaryEncodedWasm::new(wasm));
+        (canister_module, Arc::new(result.unwrap().1))
+    }
+
+    fn disk_cache(dir: &std::path::Path) -> DiskCompilationCache {
+        DiskCompilationCache::new(
+            dir.to_path_buf(),
+            CAPACITY,
+            &EmbeddersConfig::default(),
+            no_op_logger(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn modules_are_served_from_disk_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let (canister_module, serialized_module) = compiled_module();
+
+        let cache = CompilationCache::new_with_disk_cache(CAPACITY, disk_cache(dir.path()));
+        cache.insert(&canister_module, Ok(Arc::clone(&serialized_module)));
+        drop(cache);
+
+        let cache = CompilationCache::new_with_disk_cache(CAPACITY, disk_cache(dir.path()));
+        let cached = cache.get(&canister_module).unwrap().unwrap();
+        assert_eq!(cached.bytes.as_slice(), serialized_module.bytes.as_slice());
+    }
+
+    #[test]
+    fn warm_up_loads_modules_into_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        let (canister_module, serialized_module) = compiled_module();
+        disk_cache(dir.path()).insert(&WasmHash::from(&canister_module), &serialized_module);
+
+        let cache = CompilationCache::new_with_disk_cache(CAPACITY, disk_cache(dir.path()));
+        assert_eq!(cache.warm_up(), 1);
+        // The module is served from memory even if the disk entry is gone.
+        std::fs::remove_dir_all(dir.path()).unwrap();
+        assert!(cache.get(&canister_module).unwrap().is_ok());
+    }
+
+    #[test]
+    fn compilation_errors_are_not_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let (canister_module, _) = compiled_module();
+        let cache = CompilationCache::new_with_disk_cache(CAPACITY, disk_cache(dir.path()));
+        cache.insert(
+            &canister_module,
+            Err(ic_interfaces::execution_environment::HypervisorError::WasmModuleNotFound),
+        );
+        assert!(cache.get(&canister_module).unwrap().is_err());
+        assert!(disk_cache(dir.path()).entries().is_empty());
     }
 }
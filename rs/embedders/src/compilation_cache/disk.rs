@@ -0,0 +1,493 @@
+//! The persistent tier of the [`CompilationCache`](super::CompilationCache).
+//!
+//! Every cache entry is stored in a separate file named after the hash of the
+//! Wasm module and the cache salt. The salt is derived from the embedder
+//! config, the wasmtime version and the replica version, so that modules
+//! compiled by a different replica or with a different config are never
+//! reused. Each file starts with a header containing the salt, the Wasm hash
+//! and a checksum of the serialized module, which is verified before the module
+//! is handed out again.
+
+use std::{
+    ffi::OsStr,
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::SystemTime,
+};
+
+use crate::SerializedModule;
+use ic_config::embedders::Config as EmbeddersConfig;
+use ic_crypto_sha2::Sha256;
+use ic_logger::{warn, ReplicaLogger};
+use ic_types::{replica_version::REPLICA_BINARY_HASH, CountBytes, NumBytes, ReplicaVersion};
+use ic_utils_lru_cache::LruCache;
+use ic_wasm_types::WasmHash;
+
+/// The version of wasmtime used to compile the cached modules. This has to be
+/// kept in sync with the `wasmtime` dependency of this crate.
+const WASMTIME_VERSION: &str = "10.0.1";
+
+/// Identifies the files written by the disk compilation cache.
+const MAGIC: &[u8; 8] = b"ICWASMCC";
+
+/// The version of the file format. It has to be bumped on every incompatible
+/// change of the header or the encoding of `SerializedModule`.
+const FORMAT_VERSION: u32 = 1;
+
+const ENTRY_EXTENSION: &str = "module";
+const TMP_EXTENSION: &str = "tmp";
+
+const HASH_LEN: usize = 32;
+
+/// Magic, format version, salt, Wasm hash, payload length and checksum.
+const HEADER_LEN: usize = MAGIC.len() + 4 + HASH_LEN + HASH_LEN + 8 + HASH_LEN;
+
+/// The header preceding the serialized module in every cache file.
+#[derive(Debug, PartialEq, Eq)]
+struct EntryHeader {
+    salt: [u8; HASH_LEN],
+    wasm_hash: [u8; HASH_LEN],
+    payload_len: u64,
+    checksum: [u8; HASH_LEN],
+}
+
+impl EntryHeader {
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.salt);
+        bytes.extend_from_slice(&self.wasm_hash);
+        bytes.extend_from_slice(&self.payload_len.to_le_bytes());
+        bytes.extend_from_slice(&self.checksum);
+        bytes
+    }
+
+    /// Decodes a header, returning `None` if the bytes were not written by the
+    /// current version of the disk cache.
+    fn decode(bytes: &[u8; HEADER_LEN]) -> Option<Self> {
+        let (magic, rest) = bytes.split_at(MAGIC.len());
+        let (version, rest) = rest.split_at(4);
+        if magic != MAGIC || version != FORMAT_VERSION.to_le_bytes() {
+            return None;
+        }
+        let (salt, rest) = rest.split_at(HASH_LEN);
+        let (wasm_hash, rest) = rest.split_at(HASH_LEN);
+        let (payload_len, checksum) = rest.split_at(8);
+        Some(Self {
+            salt: salt.try_into().unwrap(),
+            wasm_hash: wasm_hash.try_into().unwrap(),
+            payload_len: u64::from_le_bytes(payload_len.try_into().unwrap()),
+            checksum: checksum.try_into().unwrap(),
+        })
+    }
+}
+
+/// The size of a cache file, used to bound the size of the cache directory.
+struct EntrySize(usize);
+
+impl CountBytes for EntrySize {
+    fn count_bytes(&self) -> usize {
+        self.0
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Computes the salt that is mixed into the file names of the cache entries.
+///
+/// Modules are only valid for the exact combination of embedder config,
+/// wasmtime version and replica code that produced them, as the latter
+/// determines the instrumentation.
+fn cache_salt(embedder_config: &EmbeddersConfig) -> [u8; HASH_LEN] {
+    let config = bincode::serialize(embedder_config).expect("Failed to serialize embedder config");
+    let replica_version = ReplicaVersion::default();
+    let binary_hash = REPLICA_BINARY_HASH.get().map(String::as_str).unwrap_or("");
+
+    let mut hasher = Sha256::new();
+    for field in [
+        WASMTIME_VERSION.as_bytes(),
+        replica_version.as_ref().as_bytes(),
+        binary_hash.as_bytes(),
+        config.as_slice(),
+    ] {
+        hasher.write(&(field.len() as u64).to_le_bytes());
+        hasher.write(field);
+    }
+    hasher.finish()
+}
+
+/// Stores serialized modules in a directory, evicting the least-recently used
+/// entries once the total size of the files exceeds the capacity.
+///
+/// Only successfully compiled modules are persisted. All I/O errors are logged
+/// and treated as cache misses, since the modules can always be recompiled.
+pub struct DiskCompilationCache {
+    dir: PathBuf,
+    salt: [u8; HASH_LEN],
+    index: Mutex<LruCache<WasmHash, EntrySize>>,
+    next_tmp_id: AtomicU64,
+    log: ReplicaLogger,
+}
+
+impl DiskCompilationCache {
+    /// Opens the cache in the given directory, creating the directory if
+    /// needed. Entries written for a different salt, corrupted entries and
+    /// leftovers of interrupted writes are removed.
+    pub fn new(
+        dir: PathBuf,
+        capacity: NumBytes,
+        embedder_config: &EmbeddersConfig,
+        log: ReplicaLogger,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let cache = Self {
+            dir,
+            salt: cache_salt(embedder_config),
+            index: Mutex::new(LruCache::new(capacity)),
+            next_tmp_id: AtomicU64::new(0),
+            log,
+        };
+        cache.load_index()?;
+        Ok(cache)
+    }
+
+    fn load_index(&self) -> io::Result<()> {
+        let mut entries: Vec<(SystemTime, WasmHash, usize)> = vec![];
+        for dir_entry in fs::read_dir(&self.dir)? {
+            let path = dir_entry?.path();
+            let extension = path.extension().and_then(OsStr::to_str);
+            if extension == Some(TMP_EXTENSION) {
+                self.remove_file(&path);
+            } else if extension == Some(ENTRY_EXTENSION) {
+                match self.read_entry_metadata(&path) {
+                    Ok(entry) => entries.push(entry),
+                    Err(_) => self.remove_file(&path),
+                }
+            }
+        }
+
+        // Index the entries from the oldest to the newest, so that the most
+        // recently written ones survive if the capacity was reduced.
+        entries.sort();
+        let mut index = self.index.lock().unwrap();
+        for (_, wasm_hash, size) in entries {
+            for (evicted, _) in index.push(wasm_hash, EntrySize(size)) {
+                self.remove_file(&self.entry_path(&evicted));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads and validates the header of an entry without reading the module.
+    fn read_entry_metadata(&self, path: &Path) -> io::Result<(SystemTime, WasmHash, usize)> {
+        let mut file = fs::File::open(path)?;
+        let metadata = file.metadata()?;
+        let mut header = [0; HEADER_LEN];
+        file.read_exact(&mut header)?;
+        let header = EntryHeader::decode(&header)
+            .ok_or_else(|| invalid_data("unknown file format".to_string()))?;
+        let wasm_hash = WasmHash::from(header.wasm_hash);
+        if header.salt != self.salt
+            || path != self.entry_path(&wasm_hash)
+            || metadata.len() != HEADER_LEN as u64 + header.payload_len
+        {
+            return Err(invalid_data("stale or truncated entry".to_string()));
+        }
+        Ok((metadata.modified()?, wasm_hash, metadata.len() as usize))
+    }
+
+    fn entry_path(&self, wasm_hash: &WasmHash) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.write(&self.salt);
+        hasher.write(&wasm_hash.to_slice());
+        self.dir
+            .join(hex::encode(hasher.finish()))
+            .with_extension(ENTRY_EXTENSION)
+    }
+
+    fn remove_file(&self, path: &Path) {
+        if let Err(err) = fs::remove_file(path) {
+            if err.kind() != io::ErrorKind::NotFound {
+                warn!(
+                    self.log,
+                    "Failed to remove compilation cache file {}: {}",
+                    path.display(),
+                    err
+                );
+            }
+        }
+    }
+
+    /// Returns the module stored for the given Wasm hash. Entries that fail
+    /// the integrity checks are removed.
+    pub fn get(&self, wasm_hash: &WasmHash) -> Option<SerializedModule> {
+        self.index.lock().unwrap().get(wasm_hash)?;
+        match self.read_entry(wasm_hash) {
+            Ok(serialized_module) => Some(serialized_module),
+            Err(err) => {
+                warn!(
+                    self.log,
+                    "Dropping compilation cache entry for {}: {}",
+                    hex::encode(wasm_hash.to_slice()),
+                    err
+                );
+                self.remove(wasm_hash);
+                None
+            }
+        }
+    }
+
+    fn read_entry(&self, wasm_hash: &WasmHash) -> io::Result<SerializedModule> {
+        let bytes = fs::read(self.entry_path(wasm_hash))?;
+        if bytes.len() < HEADER_LEN {
+            return Err(invalid_data("truncated header".to_string()));
+        }
+        let (header, payload) = bytes.split_at(HEADER_LEN);
+        let header = EntryHeader::decode(header.try_into().unwrap())
+            .ok_or_else(|| invalid_data("unknown file format".to_string()))?;
+        if header.salt != self.salt || header.wasm_hash != wasm_hash.to_slice() {
+            return Err(invalid_data("header does not match the entry".to_string()));
+        }
+        if header.payload_len != payload.len() as u64 {
+            return Err(invalid_data(format!(
+                "expected {} bytes of payload, found {}",
+                header.payload_len,
+                payload.len()
+            )));
+        }
+        if header.checksum != Sha256::hash(payload) {
+            return Err(invalid_data("checksum mismatch".to_string()));
+        }
+        bincode::deserialize(payload).map_err(|err| invalid_data(err.to_string()))
+    }
+
+    /// Persists the module for the given Wasm hash unless it is already
+    /// stored, evicting other entries as needed.
+    pub fn insert(&self, wasm_hash: &WasmHash, serialized_module: &SerializedModule) {
+        if self.index.lock().unwrap().get(wasm_hash).is_some() {
+            return;
+        }
+        let size = match self.write_entry(wasm_hash, serialized_module) {
+            Ok(size) => size,
+            Err(err) => {
+                warn!(
+                    self.log,
+                    "Failed to persist compilation cache entry for {}: {}",
+                    hex::encode(wasm_hash.to_slice()),
+                    err
+                );
+                return;
+            }
+        };
+
+        let mut index = self.index.lock().unwrap();
+        let evicted = index.push(wasm_hash.clone(), EntrySize(size));
+        // A concurrent insertion of the same module replaces the index entry
+        // but shares the file, which must be kept in that case.
+        let inserted = index.get(wasm_hash).is_some();
+        for (evicted, _) in evicted {
+            if evicted != *wasm_hash || !inserted {
+                self.remove_file(&self.entry_path(&evicted));
+            }
+        }
+    }
+
+    /// Writes the entry to a temporary file first and then moves it into
+    /// place, so that readers never observe partially written entries.
+    fn write_entry(
+        &self,
+        wasm_hash: &WasmHash,
+        serialized_module: &SerializedModule,
+    ) -> io::Result<usize> {
+        let payload = bincode::serialize(serialized_module)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        let header = EntryHeader {
+            salt: self.salt,
+            wasm_hash: wasm_hash.to_slice(),
+            payload_len: payload.len() as u64,
+            checksum: Sha256::hash(&payload),
+        };
+        let mut bytes = header.encode();
+        bytes.extend_from_slice(&payload);
+
+        let tmp_id = self.next_tmp_id.fetch_add(1, Ordering::Relaxed);
+        let tmp_path = self
+            .dir
+            .join(tmp_id.to_string())
+            .with_extension(TMP_EXTENSION);
+        fs::write(&tmp_path, &bytes)
+            .and_then(|()| fs::rename(&tmp_path, self.entry_path(wasm_hash)))
+            .map_err(|err| {
+                self.remove_file(&tmp_path);
+                err
+            })?;
+        Ok(bytes.len())
+    }
+
+    /// Removes the entry for the given Wasm hash.
+    pub fn remove(&self, wasm_hash: &WasmHash) {
+        if self.index.lock().unwrap().pop(wasm_hash).is_some() {
+            self.remove_file(&self.entry_path(wasm_hash));
+        }
+    }
+
+    /// Returns the Wasm hashes and file sizes of all entries, ordered from the
+    /// most-recently used to the least-recently used.
+    pub fn entries(&self) -> Vec<(WasmHash, NumBytes)> {
+        self.index
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(wasm_hash, size)| (wasm_hash.clone(), NumBytes::from(size.0 as u64)))
+            .collect()
+    }
+
+    /// Removes all entries.
+    pub fn clear(&self) {
+        let entries = self.entries();
+        for (wasm_hash, _) in entries {
+            self.remove(&wasm_hash);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wasm_utils::compile;
+    use crate::WasmtimeEmbedder;
+    use ic_logger::replica_logger::no_op_logger;
+    use ic_wasm_types::{BinaryEncodedWasm, CanisterModule};
+    use std::io::Write;
+
+    fn canister_module(function_count: usize) -> CanisterModule {
+        let functions = "(func)".repeat(function_count);
+        let wat = format!(
+            r#"(module {} (func (export "canister_update go")))"#,
+            functions
+        );
+        CanisterModule::new(wat::parse_str(wat).unwrap())
+    }
+
+    fn serialized_module(canister_module: &CanisterModule) -> SerializedModule {
+        let embedder = WasmtimeEmbedder::new(EmbeddersConfig::default(), no_op_logger());
+        let wasm = BinaryEncodedWasm::new(canister_module.as_slice().to_vec());
+        compile(&embedder, &wasm).1.unwrap().1
+    }
+
+    fn open(dir: &Path, capacity: u64) -> DiskCompilationCache {
+        DiskCompilationCache::new(
+            dir.to_path_buf(),
+            NumBytes::from(capacity),
+            &EmbeddersConfig::default(),
+            no_op_logger(),
+        )
+        .unwrap()
+    }
+
+    fn assert_same_module(a: &SerializedModule, b: &SerializedModule) {
+        assert_eq!(a.bytes.as_slice(), b.bytes.as_slice());
+        assert_eq!(a.compilation_cost, b.compilation_cost);
+    }
+
+    #[test]
+    fn entries_survive_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let module = canister_module(1);
+        let wasm_hash = WasmHash::from(&module);
+        let serialized = serialized_module(&module);
+
+        open(dir.path(), 1 << 30).insert(&wasm_hash, &serialized);
+
+        let cache = open(dir.path(), 1 << 30);
+        assert_eq!(cache.entries().len(), 1);
+        assert_same_module(&cache.get(&wasm_hash).unwrap(), &serialized);
+    }
+
+    #[test]
+    fn corrupted_entries_are_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let module = canister_module(1);
+        let wasm_hash = WasmHash::from(&module);
+        let cache = open(dir.path(), 1 << 30);
+        cache.insert(&wasm_hash, &serialized_module(&module));
+
+        // Flip a byte in the serialized module.
+        let path = cache.entry_path(&wasm_hash);
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        fs::write(&path, bytes).unwrap();
+
+        assert!(cache.get(&wasm_hash).is_none());
+        assert!(cache.entries().is_empty());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn entries_with_different_salt_are_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let module = canister_module(1);
+        let wasm_hash = WasmHash::from(&module);
+        open(dir.path(), 1 << 30).insert(&wasm_hash, &serialized_module(&module));
+
+        let other_config = EmbeddersConfig {
+            max_globals: EmbeddersConfig::default().max_globals + 1,
+            ..EmbeddersConfig::default()
+        };
+        let cache = DiskCompilationCache::new(
+            dir.path().to_path_buf(),
+            NumBytes::from(1 << 30),
+            &other_config,
+            no_op_logger(),
+        )
+        .unwrap();
+        assert!(cache.get(&wasm_hash).is_none());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn leftover_temporary_files_are_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let tmp_path = dir.path().join("0").with_extension(TMP_EXTENSION);
+        fs::File::create(&tmp_path)
+            .unwrap()
+            .write_all(b"partial")
+            .unwrap();
+        open(dir.path(), 1 << 30);
+        assert!(!tmp_path.exists());
+    }
+
+    #[test]
+    fn least_recently_used_entries_are_evicted() {
+        let dir = tempfile::tempdir().unwrap();
+        let modules: Vec<_> = (1..=3).rev().map(canister_module).collect();
+        let hashes: Vec<_> = modules.iter().map(WasmHash::from).collect();
+        let serialized: Vec<_> = modules.iter().map(serialized_module).collect();
+        let entry_size = |i: usize| {
+            (HEADER_LEN + bincode::serialize(&serialized[i]).unwrap().len()) as u64
+                + hashes[i].count_bytes() as u64
+        };
+
+        // The entries get smaller, so there is room for the first two only.
+        let cache = open(dir.path(), entry_size(0) + entry_size(1));
+        cache.insert(&hashes[0], &serialized[0]);
+        cache.insert(&hashes[1], &serialized[1]);
+        // Mark the first entry as recently used.
+        assert!(cache.get(&hashes[0]).is_some());
+        cache.insert(&hashes[2], &serialized[2]);
+
+        assert!(cache.get(&hashes[0]).is_some());
+        assert!(cache.get(&hashes[1]).is_none());
+        assert!(!cache.entry_path(&hashes[1]).exists());
+        assert!(cache.entries().len() <= 2);
+    }
+}
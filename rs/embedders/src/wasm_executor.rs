@@ -440,6 +440,14 @@ impl WasmExecutorImpl {
     }
 }
 
+/// Returns the name of the profiled entry point, e.g. `canister_update foo`.
+fn entry_point_name(func_ref: &FuncRef) -> String {
+    match func_ref {
+        FuncRef::Method(method) => method.to_string(),
+        FuncRef::UpdateClosure(_) | FuncRef::QueryClosure(_) => "callback".to_string(),
+    }
+}
+
 /// A helper function that returns a Wasm execution result with an error.
 pub fn wasm_execution_error(
     err: HypervisorError,
@@ -456,6 +464,8 @@ pub fn wasm_execution_error(
             allocated_bytes: NumBytes::from(0),
             allocated_message_bytes: NumBytes::from(0),
             instance_stats: InstanceStats::default(),
+            canister_log: Default::default(),
+            profile: None,
         },
         None,
     )
@@ -617,6 +627,8 @@ pub fn process(
                     allocated_bytes: NumBytes::from(0),
                     allocated_message_bytes: NumBytes::from(0),
                     instance_stats: InstanceStats::default(),
+                    canister_log: Default::default(),
+                    profile: None,
                 },
                 None,
                 Err(system_api),
@@ -627,6 +639,12 @@ pub fn process(
     // Set the instruction limit for the first slice.
     instance.set_instruction_counter(first_slice_instruction_limit.get() as i64);
 
+    let entry_point = instance
+        .store_data()
+        .profiler
+        .is_some()
+        .then(|| entry_point_name(&func_ref));
+
     // Execute Wasm code until it finishes or exceeds the message instruction
     // limit. With deterministic time slicing, this call may execute multiple
     // slices before it returns.
@@ -657,6 +675,13 @@ pub fn process(
         .store_data_mut()
         .system_api
         .take_execution_result(run_result.as_ref().err());
+    let canister_log = instance.store_data_mut().system_api.take_canister_log();
+    let profile = match (instance.store_data_mut().profiler.as_mut(), entry_point) {
+        (Some(profiler), Some(entry_point)) => {
+            Some(profiler.take_profile(entry_point, message_instructions_executed.get()))
+        }
+        _ => None,
+    };
 
     let wasm_heap_size_after = instance.heap_size(CanisterMemoryType::Heap);
     let wasm_heap_limit =
@@ -738,6 +763,8 @@ pub fn process(
             allocated_bytes,
             allocated_message_bytes,
             instance_stats,
+            canister_log,
+            profile,
         },
         wasm_state_changes,
         Ok(instance),
@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     time::Instant,
 };
 
@@ -209,6 +209,7 @@ fn validate_and_instrument(
         config.feature_flags.wasm_native_stable_memory,
         config.subnet_type,
         config.dirty_page_overhead,
+        config.feature_flags.canister_profiling,
     )?;
     Ok((wasm_validation_details, instrumentation_output))
 }
@@ -260,3 +261,47 @@ pub fn compile(
     };
     (EmbedderCache::new(cache), result)
 }
+
+/// Returns the function names from the `name` custom section of the given
+/// (decoded) Wasm module, indexed by function index. Used to render
+/// [`ic_types::execution_profile::ExecutionProfile`]s. A missing or malformed
+/// `name` section results in no or only some of the names.
+pub fn function_names(wasm: &[u8]) -> BTreeMap<u32, String> {
+    const FUNCTION_NAMES_SUBSECTION: u8 = 1;
+
+    fn read_function_names(
+        data: &[u8],
+        names: &mut BTreeMap<u32, String>,
+    ) -> wasmparser::Result<()> {
+        let mut reader = wasmparser::BinaryReader::new(data);
+        while !reader.eof() {
+            let subsection_id = reader.read_u8()?;
+            let size = reader.read_var_u32()?;
+            let subsection = reader.read_bytes(size as usize)?;
+            if subsection_id != FUNCTION_NAMES_SUBSECTION {
+                continue;
+            }
+            let mut reader = wasmparser::BinaryReader::new(subsection);
+            let count = reader.read_var_u32()?;
+            for _ in 0..count {
+                let function_index = reader.read_var_u32()?;
+                let name = reader.read_string()?;
+                names.insert(function_index, name.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    let mut names = BTreeMap::new();
+    for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+        match payload {
+            Ok(wasmparser::Payload::CustomSection(reader)) if reader.name() == "name" => {
+                // Keep the names read before an error.
+                let _ = read_function_names(reader.data(), &mut names);
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+    names
+}
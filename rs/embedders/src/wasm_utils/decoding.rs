@@ -3,7 +3,10 @@ use std::io::Read;
 use std::sync::Arc;
 
 /// Maximum size of a WebAssembly module.
-pub const MAX_WASM_MODULE_SIZE_BYTES: usize = 30 * 1024 * 1024;
+///
+/// Modules larger than the ingress message size limit can be installed by
+/// uploading them in chunks with `upload_chunk` and `install_chunked_code`.
+pub const MAX_WASM_MODULE_SIZE_BYTES: usize = 100 * 1024 * 1024;
 
 fn make_module_too_large_error() -> WasmValidationError {
     WasmValidationError::DecodingError(format!(
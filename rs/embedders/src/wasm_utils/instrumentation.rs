@@ -109,6 +109,22 @@
 //! (memory (export "stable_memory_bytemap") i32 (i64.const STABLE_BYTEMAP_SIZE) (i64.const STABLE_BYTEMAP_SIZE))
 //! ```
 //!
+//! # Canister profiling
+//!
+//! If canister profiling is enabled, two more System API functions are
+//! inserted after all other injected imports:
+//!
+//! ```wasm
+//! (import "__" "profile_enter" (func ((param i32))))
+//! (import "__" "profile_exit" (func ((param i32))))
+//! ```
+//!
+//! Every function of the canister module calls `profile_enter` with its index
+//! in the original module before executing its body, and `profile_exit` with
+//! the same index before returning. These calls are inserted after metering,
+//! so they do not consume instructions. The embedder uses them to attribute
+//! the executed instructions to call stacks.
+//!
 
 use super::system_api_replacements::replacement_functions;
 use super::validation::API_VERSION_IC0;
@@ -154,6 +170,15 @@ impl InjectedImports {
     }
 }
 
+// The number of function imports injected for canister profiling. They follow
+// the imports listed in `InjectedImports`.
+fn profiling_imports_count(canister_profiling: FlagStatus) -> usize {
+    match canister_profiling {
+        FlagStatus::Enabled => 2,
+        FlagStatus::Disabled => 0,
+    }
+}
+
 // Gets the cost of an instruction.
 fn instruction_to_cost(i: &Operator) -> u64 {
     match i {
@@ -175,6 +200,8 @@ const UPDATE_MEMORY_FUN_NAME: &str = "update_available_memory";
 const TRY_GROW_STABLE_MEMORY_FUN_NAME: &str = "try_grow_stable_memory";
 const INTERNAL_TRAP_FUN_NAME: &str = "internal_trap";
 const STABLE_READ_FIRST_ACCESS_NAME: &str = "stable_read_first_access";
+const PROFILE_ENTER_FUN_NAME: &str = "profile_enter";
+const PROFILE_EXIT_FUN_NAME: &str = "profile_exit";
 const TABLE_STR: &str = "table";
 pub(crate) const INSTRUCTIONS_COUNTER_GLOBAL_NAME: &str = "canister counter_instructions";
 pub(crate) const DIRTY_PAGES_COUNTER_GLOBAL_NAME: &str = "canister counter_dirty_pages";
@@ -269,7 +296,11 @@ fn mutate_function_indices(module: &mut Module, f: impl Fn(u32) -> u32) {
 /// added as the last imports, we'd need to increment only non imported
 /// functions, since imported functions precede all others in the function index
 /// space, but this would be error-prone).
-fn inject_helper_functions(mut module: Module, wasm_native_stable_memory: FlagStatus) -> Module {
+fn inject_helper_functions(
+    mut module: Module,
+    wasm_native_stable_memory: FlagStatus,
+    canister_profiling: FlagStatus,
+) -> Module {
     // insert types
     let ooi_type = Type::Func(FuncType::new([], []));
     let uam_type = Type::Func(FuncType::new(
@@ -293,9 +324,10 @@ fn inject_helper_functions(mut module: Module, wasm_native_stable_memory: FlagSt
         ty: TypeRef::Func(uam_type_idx),
     };
 
+    let injected_imports_count = InjectedImports::count(wasm_native_stable_memory)
+        + profiling_imports_count(canister_profiling);
     let mut old_imports = module.imports;
-    module.imports =
-        Vec::with_capacity(old_imports.len() + InjectedImports::count(wasm_native_stable_memory));
+    module.imports = Vec::with_capacity(old_imports.len() + injected_imports_count);
     module.imports.push(ooi_imp);
     module.imports.push(uam_imp);
 
@@ -334,10 +366,22 @@ fn inject_helper_functions(mut module: Module, wasm_native_stable_memory: FlagSt
         module.imports.push(fr_imp);
     }
 
+    if canister_profiling == FlagStatus::Enabled {
+        let profile_type = Type::Func(FuncType::new([ValType::I32], []));
+        let profile_type_idx = add_type(&mut module, profile_type);
+        for name in [PROFILE_ENTER_FUN_NAME, PROFILE_EXIT_FUN_NAME] {
+            module.imports.push(Import {
+                module: INSTRUMENTED_FUN_MODULE,
+                name,
+                ty: TypeRef::Func(profile_type_idx),
+            });
+        }
+    }
+
     module.imports.append(&mut old_imports);
 
-    // now increment all function references by InjectedImports::Count
-    let cnt = InjectedImports::count(wasm_native_stable_memory) as u32;
+    // now increment all function references by the number of injected imports
+    let cnt = injected_imports_count as u32;
     mutate_function_indices(&mut module, |i| i + cnt);
 
     debug_assert!(
@@ -389,9 +433,10 @@ pub(super) fn instrument(
     wasm_native_stable_memory: FlagStatus,
     subnet_type: SubnetType,
     dirty_page_overhead: NumInstructions,
+    canister_profiling: FlagStatus,
 ) -> Result<InstrumentationOutput, WasmInstrumentationError> {
     let stable_memory_index;
-    let mut module = inject_helper_functions(module, wasm_native_stable_memory);
+    let mut module = inject_helper_functions(module, wasm_native_stable_memory, canister_profiling);
     module = export_table(module);
     (module, stable_memory_index) =
         update_memories(module, write_barrier, wasm_native_stable_memory);
@@ -451,6 +496,29 @@ pub(super) fn instrument(
         inject_metering(&mut func_body.instructions, &special_indices);
     }
 
+    // inject profiling hooks after metering, so that they are not metered
+    if canister_profiling == FlagStatus::Enabled {
+        let profile_enter_fn = InjectedImports::count(wasm_native_stable_memory) as u32;
+        let num_original_imported_functions = num_imported_functions
+            - InjectedImports::count(wasm_native_stable_memory)
+            - profiling_imports_count(canister_profiling);
+        let body_block_types: Vec<_> = (0..module.code_sections.len())
+            .map(|i| {
+                let Type::Func(func_type) = module.types[module.functions[i] as usize].clone();
+                body_block_type(&mut module.types, func_type.results())
+            })
+            .collect();
+        for (i, func_body) in module.code_sections.iter_mut().enumerate() {
+            inject_profiling(
+                &mut func_body.instructions,
+                (num_original_imported_functions + i) as u32,
+                body_block_types[i],
+                profile_enter_fn,
+                profile_enter_fn + 1,
+            );
+        }
+    }
+
     // Collect all the function types of the locally defined functions inside the
     // module.
     //
@@ -946,6 +1014,74 @@ fn inject_metering(code: &mut Vec<Operator>, export_data_module: &SpecialIndices
     *orig_elems = elems;
 }
 
+// Returns the type of a block that takes no parameters and leaves the given
+// results on the stack. A block with multiple results needs a function type,
+// which is added to `types` unless it already exists.
+fn body_block_type(types: &mut Vec<Type>, results: &[ValType]) -> BlockType {
+    match results {
+        [] => BlockType::Empty,
+        [result] => BlockType::Type(*result),
+        _ => {
+            let block_type = Type::Func(FuncType::new([], results.iter().copied()));
+            let index = match types.iter().position(|ty| *ty == block_type) {
+                Some(index) => index,
+                None => {
+                    types.push(block_type);
+                    types.len() - 1
+                }
+            };
+            BlockType::FuncType(index as u32)
+        }
+    }
+}
+
+// Calls `profile_enter` at the beginning of the function body and
+// `profile_exit` before every return from it. `function_index` is the index
+// of the function in the original module.
+//
+// The original body is wrapped in a block of type `body_block_type`, which
+// has the results of the function. A branch to the label of the function body
+// then ends the block instead of returning, so it still reaches the
+// `profile_exit` after the block.
+fn inject_profiling(
+    code: &mut Vec<Operator>,
+    function_index: u32,
+    body_block_type: BlockType,
+    profile_enter_fn: u32,
+    profile_exit_fn: u32,
+) {
+    use Operator::*;
+    let index = I32Const {
+        value: function_index as i32,
+    };
+    let exit = [
+        index.clone(),
+        Call {
+            function_index: profile_exit_fn,
+        },
+    ];
+    let mut elems = Vec::with_capacity(code.len() + 7);
+    elems.extend_from_slice(&[
+        index.clone(),
+        Call {
+            function_index: profile_enter_fn,
+        },
+        Block {
+            blockty: body_block_type,
+        },
+    ]);
+    for op in code.drain(..) {
+        if matches!(op, Return | ReturnCall { .. } | ReturnCallIndirect { .. }) {
+            elems.extend_from_slice(&exit);
+        }
+        elems.push(op);
+    }
+    // The last `End` of the original body now ends the block.
+    elems.extend_from_slice(&exit);
+    elems.push(End);
+    *code = elems;
+}
+
 // This function adds mem barrier writes, assuming that arguments
 // of the original store operation are on the stack
 fn write_barrier_instructions<'a>(
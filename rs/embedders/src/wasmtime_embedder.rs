@@ -1,4 +1,5 @@
 pub mod host_memory;
+mod profiler;
 mod signal_stack;
 mod system_api;
 pub mod system_api_complexity;
@@ -33,6 +34,7 @@ use ic_types::{
 };
 use ic_wasm_types::{BinaryEncodedWasm, WasmEngineError};
 use memory_tracker::{DirtyPageTracking, PageBitmap, SigsegvMemoryTracker};
+use profiler::Profiler;
 use signal_stack::WasmtimeSignalStack;
 
 use crate::wasm_utils::instrumentation::{
@@ -324,6 +326,8 @@ impl WasmtimeEmbedder {
             StoreData {
                 system_api,
                 num_instructions_global: None,
+                profiler: (self.config.feature_flags.canister_profiling == FlagStatus::Enabled)
+                    .then(Profiler::default),
             },
         );
 
@@ -638,6 +642,8 @@ fn sigsegv_memory_tracker<S>(
 pub struct StoreData<S> {
     pub system_api: S,
     pub num_instructions_global: Option<wasmtime::Global>,
+    /// Set if canister profiling is enabled.
+    pub profiler: Option<Profiler>,
 }
 
 pub struct PageAccessResults {
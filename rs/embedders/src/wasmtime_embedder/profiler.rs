@@ -0,0 +1,89 @@
+//! Attributes the instructions executed by a canister to call stacks, based
+//! on the `profile_enter` and `profile_exit` calls that the instrumentation
+//! injects if canister profiling is enabled.
+
+use ic_types::execution_profile::ExecutionProfile;
+
+/// Tracks the call stack of an execution and records the instructions
+/// executed between two consecutive profiling events for the call stack
+/// that was active in between.
+#[derive(Default)]
+pub struct Profiler {
+    stack: Vec<u32>,
+    instructions_at_last_event: u64,
+    profile: ExecutionProfile,
+}
+
+impl Profiler {
+    /// Called when the function with the given index is entered.
+    pub fn enter(&mut self, function_index: u32, instructions_executed: u64) {
+        self.record(instructions_executed);
+        self.stack.push(function_index);
+    }
+
+    /// Called when the function with the given index returns.
+    pub fn exit(&mut self, function_index: u32, instructions_executed: u64) {
+        self.record(instructions_executed);
+        // Also drop the frames above the exited function, if any. This can
+        // only happen if the injected calls are out of sync.
+        while let Some(top) = self.stack.pop() {
+            if top == function_index {
+                break;
+            }
+        }
+    }
+
+    /// Returns the profile recorded so far and resets the profiler. The
+    /// instructions executed since the last event are recorded for the current
+    /// call stack, which is not empty if the execution trapped.
+    pub fn take_profile(
+        &mut self,
+        entry_point: String,
+        instructions_executed: u64,
+    ) -> ExecutionProfile {
+        self.record(instructions_executed);
+        let recorded = std::mem::take(self);
+        let mut profile = ExecutionProfile::new(entry_point);
+        for (stack, instructions) in recorded.profile.stacks() {
+            profile.record(stack, *instructions);
+        }
+        profile
+    }
+
+    fn record(&mut self, instructions_executed: u64) {
+        let instructions = instructions_executed.saturating_sub(self.instructions_at_last_event);
+        self.instructions_at_last_event =
+            self.instructions_at_last_event.max(instructions_executed);
+        self.profile.record(&self.stack, instructions);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn instructions_are_attributed_to_the_innermost_function() {
+        let mut profiler = Profiler::default();
+        profiler.enter(1, 0);
+        profiler.enter(2, 10);
+        profiler.exit(2, 25);
+        profiler.enter(2, 30);
+        profiler.enter(3, 31);
+        // Function 3 traps.
+        let profile = profiler.take_profile("canister_update go".to_string(), 40);
+
+        assert_eq!(profile.entry_point(), "canister_update go");
+        assert_eq!(
+            profile.stacks(),
+            &BTreeMap::from([(vec![1], 15), (vec![1, 2], 16), (vec![1, 2, 3], 9)])
+        );
+
+        // The profiler starts from scratch afterwards.
+        profiler.enter(1, 0);
+        profiler.exit(1, 5);
+        let profile = profiler.take_profile("canister_heartbeat".to_string(), 5);
+        assert_eq!(profile.stacks(), &BTreeMap::from([(vec![1], 5)]));
+    }
+}
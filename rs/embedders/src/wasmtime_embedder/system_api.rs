@@ -13,6 +13,7 @@ use ic_sys::PAGE_SIZE;
 use ic_types::{CanisterId, Cycles, NumBytes, NumInstructions, NumPages, Time};
 
 use wasmtime::{AsContextMut, Caller, Global, Linker, Store, Val};
+use wasmtime_environ::WASM_PAGE_SIZE;
 
 use crate::InternalErrorCode;
 use std::convert::TryFrom;
@@ -332,6 +333,21 @@ fn observe_execution_complexity<S: SystemApi>(
     Ok(())
 }
 
+/// Returns the number of instructions executed by the current message so far.
+fn message_instructions_executed<S: SystemApi>(
+    log: &ReplicaLogger,
+    canister_id: CanisterId,
+    caller: &mut Caller<'_, StoreData<S>>,
+) -> Result<u64, anyhow::Error> {
+    let num_instructions_global = get_num_instructions_global(caller, log, canister_id)?;
+    let instruction_counter = load_value(&num_instructions_global, caller, log, canister_id)?;
+    Ok(caller
+        .data()
+        .system_api
+        .message_instructions_executed(instruction_counter)
+        .get())
+}
+
 /// A helper to pass wasmtime counters to the System API
 fn ic0_performance_counter_helper<S: SystemApi>(
     log: &ReplicaLogger,
@@ -719,6 +735,12 @@ pub(crate) fn syscalls<S: SystemApi>(
                     NumInstructions::from(0),
                     stable_memory_dirty_page_limit,
                 )?;
+                // The message is always recorded in the canister log, which is
+                // bounded in size, even if printing it is rate limited.
+                with_memory_and_system_api(&mut caller, |system_api, memory| {
+                    system_api.save_log_message(offset as u32, length as u32, memory);
+                    Ok(())
+                })?;
                 match (
                     caller.data().system_api.subnet_type(),
                     feature_flags.rate_limiting_of_debug_prints,
@@ -1314,6 +1336,36 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
+    if feature_flags.canister_profiling == FlagStatus::Enabled {
+        linker
+            .func_wrap("__", "profile_enter", {
+                let log = log.clone();
+                move |mut caller: Caller<'_, StoreData<S>>, function_index: i32| -> Result<(), _> {
+                    let instructions =
+                        message_instructions_executed(&log, canister_id, &mut caller)?;
+                    if let Some(profiler) = caller.data_mut().profiler.as_mut() {
+                        profiler.enter(function_index as u32, instructions);
+                    }
+                    Ok(())
+                }
+            })
+            .unwrap();
+
+        linker
+            .func_wrap("__", "profile_exit", {
+                let log = log.clone();
+                move |mut caller: Caller<'_, StoreData<S>>, function_index: i32| -> Result<(), _> {
+                    let instructions =
+                        message_instructions_executed(&log, canister_id, &mut caller)?;
+                    if let Some(profiler) = caller.data_mut().profiler.as_mut() {
+                        profiler.exit(function_index as u32, instructions);
+                    }
+                    Ok(())
+                }
+            })
+            .unwrap();
+    }
+
     linker
         .func_wrap("__", "update_available_memory", {
             move |mut caller: Caller<'_, StoreData<S>>,
@@ -1321,11 +1373,21 @@ pub(crate) fn syscalls<S: SystemApi>(
                   additional_elements: i32,
                   element_size: i32| {
                 with_system_api(&mut caller, |s| {
-                    s.update_available_memory(
-                        native_memory_grow_res as i64,
-                        additional_elements as u32 as u64,
-                        element_size as u32 as u64,
-                    )
+                    // The instrumentation passes the Wasm page size as the
+                    // element size for `memory.grow` and a smaller one for
+                    // `table.grow`.
+                    if element_size as u32 == WASM_PAGE_SIZE {
+                        s.try_grow_wasm_memory(
+                            native_memory_grow_res as i64,
+                            additional_elements as u32 as u64,
+                        )
+                    } else {
+                        s.update_available_memory(
+                            native_memory_grow_res as i64,
+                            additional_elements as u32 as u64,
+                            element_size as u32 as u64,
+                        )
+                    }
                 })
                 .map(|()| native_memory_grow_res)
                 .map_err(|e| process_err(&mut caller, e))
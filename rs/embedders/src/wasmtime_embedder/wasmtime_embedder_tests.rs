@@ -80,6 +80,7 @@ fn test_wasmtime_system_api() {
         StoreData {
             system_api,
             num_instructions_global: None,
+            profiler: None,
         },
     );
 
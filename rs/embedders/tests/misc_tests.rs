@@ -132,7 +132,7 @@ fn compressed_test_contents(name: &str) -> Vec<u8> {
 #[test]
 #[should_panic(expected = "too large")]
 fn test_decode_large_compressed_module() {
-    // Try decoding 12MB of zeros
+    // Try decoding 101MB of zeros
     decode_wasm(Arc::new(compressed_test_contents("zeros.gz"))).unwrap();
 }
 
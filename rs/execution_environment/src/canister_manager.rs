@@ -13,8 +13,9 @@ use ic_config::flag_status::FlagStatus;
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallMode, CanisterStatusResultV2,
-    CanisterStatusType, InstallCodeArgs, Method as Ic00Method,
+    CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallMode, CanisterSnapshotResponse,
+    CanisterStatusResultV2, CanisterStatusType, ChunkHash, InstallChunkedCodeArgs, InstallCodeArgs,
+    ListCanisterSnapshotsResponse, Method as Ic00Method, StoredChunksReply, UploadChunkReply,
 };
 use ic_interfaces::execution_environment::{
     CanisterOutOfCyclesError, HypervisorError, IngressHistoryWriter, SubnetAvailableMemory,
@@ -23,10 +24,13 @@ use ic_interfaces::messages::CanisterCall;
 use ic_logger::{error, fatal, info, ReplicaLogger};
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
-use ic_replicated_state::canister_state::system_state::CyclesUseCase;
+use ic_replicated_state::canister_state::system_state::{
+    CyclesUseCase, WasmChunkHash, WasmChunkStore,
+};
+use ic_replicated_state::page_map::PageAllocatorFileDescriptor;
 use ic_replicated_state::{
-    CallOrigin, CanisterState, CanisterStatus, NetworkTopology, ReplicatedState, SchedulerState,
-    SystemState,
+    CallOrigin, CanisterSnapshot, CanisterState, CanisterStatus, Memory, NetworkTopology,
+    ReplicatedState, SchedulerState, SnapshotId, SystemState,
 };
 use ic_system_api::ExecutionParameters;
 use ic_types::messages::{MessageId, SignedIngressContent};
@@ -101,6 +105,7 @@ pub(crate) struct CanisterMgrConfig {
     pub(crate) own_subnet_type: SubnetType,
     pub(crate) max_controllers: usize,
     pub(crate) rate_limiting_of_instructions: FlagStatus,
+    pub(crate) max_number_of_snapshots_per_canister: usize,
 }
 
 impl CanisterMgrConfig {
@@ -115,6 +120,7 @@ impl CanisterMgrConfig {
         compute_capacity: usize,
         rate_limiting_of_instructions: FlagStatus,
         allocatable_capacity_in_percent: usize,
+        max_number_of_snapshots_per_canister: usize,
     ) -> Self {
         Self {
             subnet_memory_capacity,
@@ -126,6 +132,7 @@ impl CanisterMgrConfig {
             compute_capacity: (compute_capacity * allocatable_capacity_in_percent.min(100) / 100)
                 as u64,
             rate_limiting_of_instructions,
+            max_number_of_snapshots_per_canister,
         }
     }
 }
@@ -314,6 +321,10 @@ impl CanisterManager {
             | Ok(Ic00Method::ECDSAPublicKey)
             | Ok(Ic00Method::SetupInitialDKG)
             | Ok(Ic00Method::SignWithECDSA)
+            | Ok(Ic00Method::SchnorrPublicKey)
+            | Ok(Ic00Method::SignWithSchnorr)
+            | Ok(Ic00Method::VetKdPublicKey)
+            | Ok(Ic00Method::VetKdDeriveEncryptedKey)
             | Ok(Ic00Method::ComputeInitialEcdsaDealings)
             // "DepositCycles" can be called by anyone however as ingress message
             // cannot carry cycles, it does not make sense to allow them from users.
@@ -331,6 +342,12 @@ impl CanisterManager {
                 format!("Only canisters can call ic00 method {}", method_name),
             )),
 
+            // Canister logs can only be fetched with a query call.
+            Ok(Ic00Method::FetchCanisterLogs) => Err(UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                format!("{} API is only accessible in non-replicated mode", method_name),
+            )),
+
 
             // These methods are only valid if they are sent by the controller
             // of the canister. We assume that the canister always wants to
@@ -342,7 +359,15 @@ impl CanisterManager {
             | Ok(Ic00Method::DeleteCanister) |
             Ok(Ic00Method::UpdateSettings)|
             Ok(Ic00Method::InstallCode) |
-            Ok(Ic00Method::SetController) => {
+            Ok(Ic00Method::SetController) |
+            Ok(Ic00Method::TakeCanisterSnapshot) |
+            Ok(Ic00Method::LoadCanisterSnapshot) |
+            Ok(Ic00Method::ListCanisterSnapshots) |
+            Ok(Ic00Method::DeleteCanisterSnapshot) |
+            Ok(Ic00Method::UploadChunk) |
+            Ok(Ic00Method::StoredChunks) |
+            Ok(Ic00Method::ClearChunkStore) |
+            Ok(Ic00Method::InstallChunkedCode) => {
                 match effective_canister_id {
                     Some(canister_id) => {
                         let canister = state.canister_state(&canister_id).ok_or_else(|| UserError::new(
@@ -452,6 +477,14 @@ impl CanisterManager {
         if let Some(freezing_threshold) = settings.freezing_threshold() {
             canister.system_state.freeze_threshold = freezing_threshold;
         }
+        if let Some(log_visibility) = settings.log_visibility() {
+            canister.system_state.log_visibility = log_visibility;
+        }
+        if let Some(wasm_memory_limit) = settings.wasm_memory_limit() {
+            // A zero limit removes the limit.
+            canister.system_state.wasm_memory_limit =
+                (wasm_memory_limit.get() > 0).then_some(wasm_memory_limit);
+        }
     }
 
     /// Tries to apply the requested settings on the canister identified by
@@ -805,6 +838,7 @@ impl CanisterManager {
             canister,
             time,
             AddCanisterChangeToHistory::Yes(origin),
+            self.hypervisor.fd_factory(),
         );
         crate::util::process_responses(
             rejects,
@@ -950,6 +984,7 @@ impl CanisterManager {
         let compute_allocation = canister.scheduler_state.compute_allocation;
         let memory_allocation = canister.memory_allocation();
         let freeze_threshold = canister.system_state.freeze_threshold;
+        let query_stats = &canister.scheduler_state.total_query_stats;
 
         Ok(CanisterStatusResultV2::new(
             canister.status(),
@@ -964,6 +999,10 @@ impl CanisterManager {
             compute_allocation.as_percent(),
             Some(memory_allocation.bytes().get()),
             freeze_threshold.get(),
+            canister
+                .system_state
+                .wasm_memory_limit
+                .map(|limit| limit.get()),
             self.cycles_account_manager
                 .idle_cycles_burned_rate(
                     memory_allocation,
@@ -972,6 +1011,10 @@ impl CanisterManager {
                     subnet_size,
                 )
                 .get(),
+            query_stats.num_calls,
+            query_stats.num_instructions,
+            query_stats.ingress_payload_size,
+            query_stats.egress_payload_size,
         ))
     }
 
@@ -1047,6 +1090,10 @@ impl CanisterManager {
 
         // Take out the canister from `ReplicatedState`.
         let canister_to_delete = state.take_canister_state(&canister_id_to_delete).unwrap();
+        // Snapshots of a deleted canister cannot be loaded anymore.
+        state
+            .canister_snapshots
+            .delete_snapshots(canister_id_to_delete);
         // Leftover cycles in the balance are considered `consumed`.
         let leftover_cycles = NominalCycles::from(canister_to_delete.system_state.balance());
         let consumed_cycles_by_canister_to_delete = leftover_cycles
@@ -1207,6 +1254,7 @@ impl CanisterManager {
             cycles,
             self.config.default_freeze_threshold,
         );
+        system_state.wasm_chunk_store = WasmChunkStore::new(self.hypervisor.fd_factory());
 
         system_state.remove_cycles(creation_fee, CyclesUseCase::CanisterCreation);
         let scheduler_state = SchedulerState::new(state.metadata.batch_time);
@@ -1281,6 +1329,406 @@ impl CanisterManager {
         Ok(())
     }
 
+    /// Takes a snapshot of the canister's current state and stores it in
+    /// `ReplicatedState`.
+    ///
+    /// Only the controllers of the canister can take a snapshot. If
+    /// `replace_snapshot` is provided, the new snapshot replaces the given
+    /// existing snapshot of the canister; otherwise, the number of snapshots
+    /// the canister already has must be below the per-canister limit.
+    ///
+    /// The size of the snapshot counts towards the memory usage of the
+    /// canister.
+    pub(crate) fn take_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        replace_snapshot: Option<Vec<u8>>,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<CanisterSnapshotResponse, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        let replaced_snapshot = match replace_snapshot {
+            Some(snapshot_id) => {
+                let snapshot_id =
+                    self.validate_snapshot_exists(state, canister_id, &snapshot_id)?;
+                Some(snapshot_id)
+            }
+            None => {
+                if state.canister_snapshots.count_by_canister(&canister_id)
+                    >= self.config.max_number_of_snapshots_per_canister
+                {
+                    return Err(CanisterManagerError::CanisterSnapshotLimitExceeded {
+                        canister_id,
+                        limit: self.config.max_number_of_snapshots_per_canister,
+                    });
+                }
+                None
+            }
+        };
+        let replaced_size = replaced_snapshot
+            .and_then(|snapshot_id| state.canister_snapshots.get(snapshot_id))
+            .map_or(NumBytes::from(0), |snapshot| snapshot.size());
+
+        let snapshot = CanisterSnapshot::from_canister(canister, state.time()).ok_or(
+            CanisterManagerError::Hypervisor(canister_id, HypervisorError::WasmModuleNotFound),
+        )?;
+        let old_usage = canister.memory_usage();
+        let new_usage = old_usage - replaced_size.min(old_usage) + snapshot.size();
+        self.validate_memory_usage_change(canister, old_usage, new_usage, round_limits)?;
+
+        if let Some(snapshot_id) = replaced_snapshot {
+            state.canister_snapshots.remove(snapshot_id);
+        }
+        let snapshot_size = snapshot.size();
+        let taken_at_timestamp = snapshot.taken_at_timestamp();
+        // The canister was validated to exist above.
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        let snapshot_id =
+            SnapshotId::new(canister_id, canister.system_state.new_local_snapshot_id());
+        canister.system_state.snapshots_memory_usage = canister.system_state.snapshots_memory_usage
+            - replaced_size.min(canister.system_state.snapshots_memory_usage)
+            + snapshot_size;
+        state
+            .canister_snapshots
+            .push(snapshot_id, Arc::new(snapshot));
+
+        Ok(CanisterSnapshotResponse::new(
+            snapshot_id.to_vec(),
+            taken_at_timestamp.as_nanos_since_unix_epoch(),
+            snapshot_size.get(),
+        ))
+    }
+
+    /// Restores the canister to the state captured by the given snapshot.
+    ///
+    /// Only the controllers of the canister can load a snapshot and only
+    /// snapshots taken of the same canister can be loaded. The Wasm module,
+    /// the Wasm and stable memories, the exported globals, the certified
+    /// data and the global timer are replaced with the ones of the snapshot.
+    pub(crate) fn load_canister_snapshot(
+        &self,
+        origin: CanisterChangeOrigin,
+        canister_id: CanisterId,
+        snapshot_id: &[u8],
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<(), CanisterManagerError> {
+        let sender = origin.origin();
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+        let snapshot_id = self.validate_snapshot_exists(state, canister_id, snapshot_id)?;
+        // The snapshot was validated to exist above.
+        let snapshot = Arc::clone(state.canister_snapshots.get(snapshot_id).unwrap());
+
+        // The Wasm module is copied into memory, so that the new execution
+        // state does not refer to the files of the snapshot.
+        let (_, result) = self.hypervisor.create_execution_state(
+            CanisterModule::new(snapshot.wasm_binary().as_slice().to_vec()),
+            "NOT_USED".into(),
+            canister_id,
+            round_limits,
+            CompilationCostHandling::CountFullAmount,
+        );
+        let mut execution_state = result.map_err(|err| (canister_id, err))?;
+        execution_state.wasm_memory = Memory::new(
+            snapshot.wasm_memory().page_map.clone(),
+            snapshot.wasm_memory().size,
+        );
+        execution_state.stable_memory = Memory::new(
+            snapshot.stable_memory().page_map.clone(),
+            snapshot.stable_memory().size,
+        );
+        execution_state.exported_globals = snapshot.exported_globals().clone();
+
+        let mut new_canister = canister.clone();
+        let old_usage = canister.memory_usage();
+        new_canister.execution_state = Some(execution_state);
+        new_canister.system_state.certified_data = snapshot.certified_data().clone();
+        new_canister.system_state.global_timer = snapshot.global_timer();
+        new_canister.system_state.canister_version += 1;
+        new_canister.system_state.add_canister_change(
+            state.time(),
+            origin,
+            CanisterChangeDetails::load_snapshot(
+                snapshot.canister_version(),
+                snapshot_id.to_vec(),
+                snapshot.taken_at_timestamp().as_nanos_since_unix_epoch(),
+            ),
+        );
+        let new_usage = new_canister.memory_usage();
+        self.validate_memory_usage_change(canister, old_usage, new_usage, round_limits)?;
+
+        state.put_canister_state(new_canister);
+        state
+            .canister_snapshots
+            .add_restore_operation(canister_id, snapshot_id);
+        Ok(())
+    }
+
+    /// Returns the snapshots of the given canister.
+    pub(crate) fn list_canister_snapshots(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &ReplicatedState,
+    ) -> Result<ListCanisterSnapshotsResponse, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        Ok(ListCanisterSnapshotsResponse(
+            state
+                .canister_snapshots
+                .list_snapshots(canister_id)
+                .into_iter()
+                .map(|(snapshot_id, snapshot)| {
+                    CanisterSnapshotResponse::new(
+                        snapshot_id.to_vec(),
+                        snapshot.taken_at_timestamp().as_nanos_since_unix_epoch(),
+                        snapshot.size().get(),
+                    )
+                })
+                .collect(),
+        ))
+    }
+
+    /// Deletes the given snapshot of the canister and releases the memory it
+    /// was using.
+    pub(crate) fn delete_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        snapshot_id: &[u8],
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<(), CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+        let snapshot_id = self.validate_snapshot_exists(state, canister_id, snapshot_id)?;
+
+        // The snapshot was validated to exist above.
+        let snapshot = state.canister_snapshots.remove(snapshot_id).unwrap();
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        let old_usage = canister.memory_usage();
+        canister.system_state.snapshots_memory_usage = canister
+            .system_state
+            .snapshots_memory_usage
+            .get()
+            .saturating_sub(snapshot.size().get())
+            .into();
+        let new_usage = canister.memory_usage();
+        self.release_memory_usage(canister, old_usage, new_usage, round_limits);
+        Ok(())
+    }
+
+    /// Uploads a chunk to the Wasm chunk store of the canister and returns
+    /// the hash of the chunk.
+    ///
+    /// Only the controllers of the canister can upload chunks. The chunk
+    /// counts towards the memory usage of the canister.
+    pub(crate) fn upload_chunk(
+        &self,
+        sender: PrincipalId,
+        canister: &mut CanisterState,
+        chunk: Vec<u8>,
+        round_limits: &mut RoundLimits,
+    ) -> Result<UploadChunkReply, CanisterManagerError> {
+        validate_controller(canister, &sender)?;
+
+        canister
+            .system_state
+            .wasm_chunk_store
+            .can_insert_chunk(&chunk)
+            .map_err(|message| CanisterManagerError::WasmChunkStoreError { message })?;
+
+        let old_usage = canister.memory_usage();
+        let mut wasm_chunk_store = canister.system_state.wasm_chunk_store.clone();
+        let hash = wasm_chunk_store.insert_chunk(&chunk);
+        let new_usage =
+            old_usage - canister.wasm_chunk_store_memory_usage() + wasm_chunk_store.memory_usage();
+        self.validate_memory_usage_change(canister, old_usage, new_usage, round_limits)?;
+        canister.system_state.wasm_chunk_store = wasm_chunk_store;
+
+        Ok(UploadChunkReply {
+            hash: hash.to_vec(),
+        })
+    }
+
+    /// Removes all chunks from the Wasm chunk store of the canister.
+    pub(crate) fn clear_chunk_store(
+        &self,
+        sender: PrincipalId,
+        canister: &mut CanisterState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<(), CanisterManagerError> {
+        validate_controller(canister, &sender)?;
+
+        let old_usage = canister.memory_usage();
+        canister.system_state.wasm_chunk_store = WasmChunkStore::new(self.hypervisor.fd_factory());
+        let new_usage = canister.memory_usage();
+        self.release_memory_usage(canister, old_usage, new_usage, round_limits);
+        Ok(())
+    }
+
+    /// Returns the hashes of all chunks in the Wasm chunk store of the
+    /// canister.
+    pub(crate) fn stored_chunks(
+        &self,
+        sender: PrincipalId,
+        canister: &CanisterState,
+    ) -> Result<StoredChunksReply, CanisterManagerError> {
+        validate_controller(canister, &sender)?;
+
+        Ok(StoredChunksReply(
+            canister
+                .system_state
+                .wasm_chunk_store
+                .keys()
+                .map(|hash| ChunkHash {
+                    hash: hash.to_vec(),
+                })
+                .collect(),
+        ))
+    }
+
+    /// Reassembles the Wasm module of an `install_chunked_code` call from the
+    /// chunk store of the store canister and turns the call into the
+    /// equivalent `install_code` arguments.
+    ///
+    /// The sender must control the store canister, which has to be on this
+    /// subnet. The concatenated chunks must hash to the declared module hash.
+    pub(crate) fn install_chunked_code_args_to_install_code_args(
+        &self,
+        sender: PrincipalId,
+        args: InstallChunkedCodeArgs,
+        state: &ReplicatedState,
+    ) -> Result<InstallCodeArgs, CanisterManagerError> {
+        let store_canister = self.validate_canister_exists(state, args.get_store_canister())?;
+        validate_controller(store_canister, &sender)?;
+
+        let wasm_chunk_store = &store_canister.system_state.wasm_chunk_store;
+        let mut wasm_module = Vec::new();
+        for chunk_hash in args.chunk_hashes_list.iter() {
+            let chunk = <WasmChunkHash>::try_from(chunk_hash.hash.as_slice())
+                .ok()
+                .and_then(|hash| wasm_chunk_store.get_chunk(&hash))
+                .ok_or_else(|| CanisterManagerError::WasmChunkStoreError {
+                    message: format!(
+                        "Chunk with hash {:?} is not present in the chunk store of canister {}.",
+                        chunk_hash.hash,
+                        store_canister.canister_id()
+                    ),
+                })?;
+            wasm_module.extend_from_slice(&chunk);
+        }
+
+        let module_hash = ic_crypto_sha2::Sha256::hash(&wasm_module);
+        if module_hash[..] != args.wasm_module_hash[..] {
+            return Err(CanisterManagerError::WasmChunkStoreError {
+                message: format!(
+                    "Wasm module hash {:?} does not match the declared hash {:?}.",
+                    module_hash, args.wasm_module_hash
+                ),
+            });
+        }
+
+        Ok(InstallCodeArgs {
+            mode: args.mode,
+            canister_id: args.target_canister,
+            wasm_module,
+            arg: args.arg,
+            compute_allocation: None,
+            memory_allocation: None,
+            query_allocation: None,
+            sender_canister_version: args.sender_canister_version,
+        })
+    }
+
+    /// Checks that the snapshot with the given raw ID exists and belongs to
+    /// the given canister.
+    fn validate_snapshot_exists(
+        &self,
+        state: &ReplicatedState,
+        canister_id: CanisterId,
+        snapshot_id: &[u8],
+    ) -> Result<SnapshotId, CanisterManagerError> {
+        let snapshot_id = SnapshotId::try_from(snapshot_id)
+            .map_err(|message| CanisterManagerError::InvalidSnapshotId { message })?;
+        if snapshot_id.get_canister_id() != canister_id
+            || state.canister_snapshots.get(snapshot_id).is_none()
+        {
+            return Err(CanisterManagerError::CanisterSnapshotNotFound {
+                canister_id,
+                snapshot_id,
+            });
+        }
+        Ok(snapshot_id)
+    }
+
+    /// Checks that the canister can go from `old_usage` to `new_usage` bytes
+    /// of memory and reserves the difference in the subnet available memory
+    /// if the canister does not have a memory allocation.
+    fn validate_memory_usage_change(
+        &self,
+        canister: &CanisterState,
+        old_usage: NumBytes,
+        new_usage: NumBytes,
+        round_limits: &mut RoundLimits,
+    ) -> Result<(), CanisterManagerError> {
+        match canister.memory_allocation() {
+            MemoryAllocation::Reserved(bytes) => {
+                if new_usage > bytes {
+                    return Err(CanisterManagerError::NotEnoughMemoryAllocationGiven {
+                        memory_allocation_given: canister.memory_allocation(),
+                        memory_usage_needed: new_usage,
+                    });
+                }
+            }
+            MemoryAllocation::BestEffort => {
+                if new_usage > old_usage {
+                    let requested = new_usage - old_usage;
+                    round_limits
+                        .subnet_available_memory
+                        .try_decrement(requested, NumBytes::from(0), NumBytes::from(0))
+                        .map_err(
+                            |_| CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
+                                requested,
+                                available: NumBytes::from(
+                                    round_limits
+                                        .subnet_available_memory
+                                        .get_execution_memory()
+                                        .max(0) as u64,
+                                ),
+                            },
+                        )?;
+                } else {
+                    self.release_memory_usage(canister, old_usage, new_usage, round_limits);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the memory freed by a canister without memory allocation to
+    /// the subnet available memory.
+    fn release_memory_usage(
+        &self,
+        canister: &CanisterState,
+        old_usage: NumBytes,
+        new_usage: NumBytes,
+        round_limits: &mut RoundLimits,
+    ) {
+        if canister.memory_allocation() == MemoryAllocation::BestEffort && old_usage > new_usage {
+            round_limits.subnet_available_memory.increment(
+                old_usage - new_usage,
+                NumBytes::from(0),
+                NumBytes::from(0),
+            );
+        }
+    }
+
     fn validate_canister_is_stopped(
         &self,
         canister: &CanisterState,
@@ -1394,6 +1842,20 @@ pub(crate) enum CanisterManagerError {
         available: Cycles,
         threshold: Cycles,
     },
+    InvalidSnapshotId {
+        message: String,
+    },
+    CanisterSnapshotNotFound {
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+    },
+    CanisterSnapshotLimitExceeded {
+        canister_id: CanisterId,
+        limit: usize,
+    },
+    WasmChunkStoreError {
+        message: String,
+    },
 }
 
 impl From<CanisterManagerError> for UserError {
@@ -1593,6 +2055,33 @@ impl From<CanisterManagerError> for UserError {
                          threshold - available)
                 )
             }
+            InvalidSnapshotId { message } => {
+                Self::new(
+                    ErrorCode::InvalidManagementPayload,
+                    format!("Invalid snapshot ID: {}", message),
+                )
+            }
+            CanisterSnapshotNotFound { canister_id, snapshot_id } => {
+                Self::new(
+                    ErrorCode::CanisterSnapshotNotFound,
+                    format!("Could not find the snapshot ID {} for canister {}.", snapshot_id, canister_id),
+                )
+            }
+            CanisterSnapshotLimitExceeded { canister_id, limit } => {
+                Self::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!(
+                        "Canister {} has reached the maximum number of snapshots allowed: {}. Delete a snapshot or replace an existing one instead.",
+                        canister_id, limit
+                    ),
+                )
+            }
+            WasmChunkStoreError { message } => {
+                Self::new(
+                    ErrorCode::CanisterContractViolation,
+                    format!("Error from Wasm chunk store: {}", message),
+                )
+            }
         }
     }
 }
@@ -1621,6 +2110,7 @@ pub fn uninstall_canister(
     canister: &mut CanisterState,
     time: Time,
     add_canister_change: AddCanisterChangeToHistory,
+    fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
 ) -> Vec<Response> {
     // Drop the canister's execution state.
     canister.execution_state = None;
@@ -1628,6 +2118,12 @@ pub fn uninstall_canister(
     // Drop its certified data.
     canister.system_state.certified_data = Vec::new();
 
+    // Drop its Wasm chunk store.
+    canister.system_state.wasm_chunk_store = WasmChunkStore::new(fd_factory);
+
+    // Drop its log records.
+    canister.system_state.canister_log.clear();
+
     // Deactivate global timer.
     canister.system_state.global_timer = CanisterTimer::Inactive;
     // Increment canister version.
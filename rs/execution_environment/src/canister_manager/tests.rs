@@ -21,8 +21,12 @@ use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, UserError};
 use ic_ic00_types::{
     CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterIdRecord,
-    CanisterInstallMode, CanisterSettingsArgsBuilder, CanisterStatusType, CreateCanisterArgs,
-    EmptyBlob, InstallCodeArgs, Method, Payload, UpdateSettingsArgs,
+    CanisterInstallMode, CanisterSettingsArgsBuilder, CanisterSnapshotResponse, CanisterStatusType,
+    ClearChunkStoreArgs, CreateCanisterArgs, DeleteCanisterSnapshotArgs, EmptyBlob,
+    InstallChunkedCodeArgs, InstallCodeArgs, ListCanisterSnapshotArgs,
+    ListCanisterSnapshotsResponse, LoadCanisterSnapshotArgs, Method, Payload, StoredChunksArgs,
+    StoredChunksReply, TakeCanisterSnapshotArgs, UpdateSettingsArgs, UploadChunkArgs,
+    UploadChunkReply,
 };
 use ic_interfaces::{
     execution_environment::{
@@ -84,6 +88,7 @@ const MAX_NUM_INSTRUCTIONS: NumInstructions = NumInstructions::new(5_000_000_000
 const DEFAULT_PROVISIONAL_BALANCE: Cycles = Cycles::new(100_000_000_000_000);
 const MEMORY_CAPACITY: NumBytes = NumBytes::new(8 * 1024 * 1024 * 1024); // 8GiB
 const MAX_CONTROLLERS: usize = 10;
+const MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER: usize = 1;
 const WASM_PAGE_SIZE_IN_BYTES: u64 = 64 * 1024; // 64KiB
 const MAX_NUMBER_OF_CANISTERS: u64 = 0;
 // The simplest valid WASM binary: "(module)"
@@ -276,6 +281,7 @@ fn canister_manager_config(
         100,
         rate_limiting_of_instructions,
         100,
+        MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER,
     )
 }
 
@@ -2922,6 +2928,7 @@ fn uninstall_canister_doesnt_respond_to_responded_call_contexts() {
                 .build(),
             mock_time(),
             AddCanisterChangeToHistory::No,
+            Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
         ),
         Vec::new()
     );
@@ -2946,6 +2953,7 @@ fn uninstall_canister_responds_to_unresponded_call_contexts() {
                 .build(),
             mock_time(),
             AddCanisterChangeToHistory::No,
+            Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
         )[0],
         Response::Ingress(IngressResponse {
             message_id: message_test_id(456),
@@ -5566,3 +5574,280 @@ fn system_subnet_does_not_check_for_freezing_threshold_on_allocation_changes() {
     test.canister_update_allocations_settings(canister_id, Some(0), Some(0))
         .unwrap();
 }
+
+fn take_canister_snapshot(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+    replace_snapshot: Option<Vec<u8>>,
+) -> Result<CanisterSnapshotResponse, UserError> {
+    let args = TakeCanisterSnapshotArgs::new(canister_id, replace_snapshot);
+    test.subnet_message(Method::TakeCanisterSnapshot, args.encode())
+        .map(|result| match result {
+            WasmResult::Reply(data) => Decode!(&data, CanisterSnapshotResponse).unwrap(),
+            WasmResult::Reject(reason) => panic!("Unexpected reject: {}", reason),
+        })
+}
+
+#[test]
+fn load_canister_snapshot_restores_stable_memory() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    test.ingress(
+        canister_id,
+        "update",
+        wasm().stable_grow(1).stable_write(0, b"a").reply().build(),
+    )
+    .unwrap();
+
+    let snapshot = take_canister_snapshot(&mut test, canister_id, None).unwrap();
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .snapshots_memory_usage,
+        NumBytes::from(snapshot.total_size)
+    );
+
+    test.ingress(
+        canister_id,
+        "update",
+        wasm().stable_write(0, b"b").reply().build(),
+    )
+    .unwrap();
+    let version_before_load = test
+        .canister_state(canister_id)
+        .system_state
+        .canister_version;
+
+    let args = LoadCanisterSnapshotArgs::new(canister_id, snapshot.snapshot_id().to_vec(), None);
+    test.subnet_message(Method::LoadCanisterSnapshot, args.encode())
+        .unwrap();
+
+    let reply = get_reply(test.ingress(
+        canister_id,
+        "query",
+        wasm().stable_read(0, 1).append_and_reply().build(),
+    ));
+    assert_eq!(reply, b"a".to_vec());
+    assert!(
+        test.canister_state(canister_id)
+            .system_state
+            .canister_version
+            > version_before_load
+    );
+}
+
+#[test]
+fn load_canister_snapshot_adds_canister_change() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    let snapshot_canister_version = test
+        .canister_state(canister_id)
+        .system_state
+        .canister_version;
+    let snapshot = take_canister_snapshot(&mut test, canister_id, None).unwrap();
+
+    let args = LoadCanisterSnapshotArgs::new(canister_id, snapshot.snapshot_id().to_vec(), None);
+    test.subnet_message(Method::LoadCanisterSnapshot, args.encode())
+        .unwrap();
+
+    let timestamp_nanos = test.state().time().as_nanos_since_unix_epoch();
+    let system_state = &test.canister_state(canister_id).system_state;
+    let history = system_state.get_canister_history();
+    let last_change = history.get_changes(1).next().unwrap();
+    assert_eq!(
+        **last_change,
+        CanisterChange::new(
+            timestamp_nanos,
+            system_state.canister_version,
+            CanisterChangeOrigin::from_user(test.user_id().get()),
+            CanisterChangeDetails::load_snapshot(
+                snapshot_canister_version,
+                snapshot.snapshot_id().to_vec(),
+                snapshot.taken_at_timestamp,
+            ),
+        )
+    );
+}
+
+#[test]
+fn take_canister_snapshot_fails_when_limit_is_reached() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+
+    let snapshot = take_canister_snapshot(&mut test, canister_id, None).unwrap();
+    let err = take_canister_snapshot(&mut test, canister_id, None).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterRejectedMessage);
+
+    // Replacing the existing snapshot is allowed.
+    let new_snapshot = take_canister_snapshot(
+        &mut test,
+        canister_id,
+        Some(snapshot.snapshot_id().to_vec()),
+    )
+    .unwrap();
+    assert_ne!(new_snapshot.snapshot_id(), snapshot.snapshot_id());
+
+    let args = ListCanisterSnapshotArgs::new(canister_id);
+    let snapshots = Decode!(
+        &get_reply(test.subnet_message(Method::ListCanisterSnapshots, args.encode())),
+        ListCanisterSnapshotsResponse
+    )
+    .unwrap();
+    assert_eq!(snapshots, ListCanisterSnapshotsResponse(vec![new_snapshot]));
+}
+
+#[test]
+fn delete_canister_snapshot_releases_memory() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    let memory_usage_before = test.canister_state(canister_id).memory_usage();
+
+    let snapshot = take_canister_snapshot(&mut test, canister_id, None).unwrap();
+    assert_eq!(
+        test.canister_state(canister_id).memory_usage(),
+        memory_usage_before + NumBytes::from(snapshot.total_size)
+    );
+
+    let args = DeleteCanisterSnapshotArgs::new(canister_id, snapshot.snapshot_id().to_vec());
+    test.subnet_message(Method::DeleteCanisterSnapshot, args.encode())
+        .unwrap();
+    assert_eq!(
+        test.canister_state(canister_id).memory_usage(),
+        memory_usage_before
+    );
+    assert!(test.state().canister_snapshots.is_empty());
+
+    // The snapshot cannot be deleted twice.
+    let err = test
+        .subnet_message(Method::DeleteCanisterSnapshot, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterSnapshotNotFound);
+}
+
+#[test]
+fn canister_snapshots_can_only_be_managed_by_controllers() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    let snapshot = take_canister_snapshot(&mut test, canister_id, None).unwrap();
+
+    test.set_user_id(user_test_id(42));
+    let err = take_canister_snapshot(&mut test, canister_id, None).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterInvalidController);
+
+    let args = LoadCanisterSnapshotArgs::new(canister_id, snapshot.snapshot_id().to_vec(), None);
+    let err = test
+        .subnet_message(Method::LoadCanisterSnapshot, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterInvalidController);
+}
+
+fn upload_chunk(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+    chunk: Vec<u8>,
+) -> Result<Vec<u8>, UserError> {
+    let args = UploadChunkArgs::new(canister_id, chunk);
+    test.subnet_message(Method::UploadChunk, args.encode())
+        .map(|result| match result {
+            WasmResult::Reply(data) => Decode!(&data, UploadChunkReply).unwrap().hash,
+            WasmResult::Reject(reason) => panic!("Unexpected reject: {}", reason),
+        })
+}
+
+#[test]
+fn install_chunked_code_installs_the_reassembled_module() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000_000));
+
+    let wasm_module = UNIVERSAL_CANISTER_WASM.to_vec();
+    let chunk_hashes: Vec<Vec<u8>> = wasm_module
+        .chunks(wasm_module.len() / 3 + 1)
+        .map(|chunk| upload_chunk(&mut test, canister_id, chunk.to_vec()).unwrap())
+        .collect();
+    assert_eq!(chunk_hashes.len(), 3);
+
+    let args = StoredChunksArgs::new(canister_id);
+    let stored_chunks = Decode!(
+        &get_reply(test.subnet_message(Method::StoredChunks, args.encode())),
+        StoredChunksReply
+    )
+    .unwrap();
+    let mut expected_hashes = chunk_hashes.clone();
+    expected_hashes.sort();
+    assert_eq!(
+        stored_chunks
+            .0
+            .into_iter()
+            .map(|chunk_hash| chunk_hash.hash)
+            .collect::<Vec<_>>(),
+        expected_hashes
+    );
+
+    let args = InstallChunkedCodeArgs::new(
+        CanisterInstallMode::Install,
+        canister_id,
+        None,
+        chunk_hashes,
+        ic_crypto_sha2::Sha256::hash(&wasm_module).to_vec(),
+        vec![],
+    );
+    test.subnet_message(Method::InstallChunkedCode, args.encode())
+        .unwrap();
+
+    let result = test
+        .ingress(canister_id, "update", wasm().reply_data(b"hi").build())
+        .unwrap();
+    assert_eq!(result, WasmResult::Reply(b"hi".to_vec()));
+}
+
+#[test]
+fn install_chunked_code_fails_on_wasm_module_hash_mismatch() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000_000));
+
+    let wasm_module = UNIVERSAL_CANISTER_WASM.to_vec();
+    let hash = upload_chunk(&mut test, canister_id, wasm_module).unwrap();
+
+    let args = InstallChunkedCodeArgs::new(
+        CanisterInstallMode::Install,
+        canister_id,
+        None,
+        vec![hash],
+        vec![0; 32],
+        vec![],
+    );
+    let err = test
+        .subnet_message(Method::InstallChunkedCode, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert!(test.canister_state(canister_id).execution_state.is_none());
+}
+
+#[test]
+fn wasm_chunk_store_is_charged_as_memory_and_released_on_clear() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000_000));
+    let memory_usage_before = test.canister_state(canister_id).memory_usage();
+
+    upload_chunk(&mut test, canister_id, vec![1; 1000]).unwrap();
+    // Uploading the same chunk again does not charge for it twice.
+    upload_chunk(&mut test, canister_id, vec![1; 1000]).unwrap();
+    assert_eq!(
+        test.canister_state(canister_id).memory_usage(),
+        memory_usage_before + NumBytes::from(1000)
+    );
+
+    let controller = test.user_id();
+    test.set_user_id(user_test_id(42));
+    let err = upload_chunk(&mut test, canister_id, vec![2; 10]).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterInvalidController);
+
+    test.set_user_id(controller);
+    let args = ClearChunkStoreArgs::new(canister_id);
+    test.subnet_message(Method::ClearChunkStore, args.encode())
+        .unwrap();
+    assert_eq!(
+        test.canister_state(canister_id).memory_usage(),
+        memory_usage_before
+    );
+}
@@ -445,6 +445,14 @@ pub fn apply_canister_state_changes(
     subnet_id: SubnetId,
     log: &ReplicaLogger,
 ) {
+    // Log records are kept even if the execution failed, since traps are
+    // usually what the canister developer wants to debug.
+    system_state
+        .canister_log
+        .append_delta(&mut output.canister_log);
+    if let Some(profile) = output.profile.take() {
+        system_state.add_execution_profile(profile);
+    }
     if let Some(CanisterStateChanges {
         globals,
         wasm_memory,
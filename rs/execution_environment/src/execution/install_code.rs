@@ -521,7 +521,7 @@ impl InstallCodeHelper {
     pub fn handle_wasm_execution(
         &mut self,
         canister_state_changes: Option<CanisterStateChanges>,
-        output: WasmExecutionOutput,
+        mut output: WasmExecutionOutput,
         original: &OriginalContext,
         round: &RoundContext,
     ) -> Result<(), CanisterManagerError> {
@@ -534,6 +534,14 @@ impl InstallCodeHelper {
             .instruction_limits
             .update(output.num_instructions_left);
 
+        self.canister
+            .system_state
+            .canister_log
+            .append_delta(&mut output.canister_log);
+        if let Some(profile) = output.profile.take() {
+            self.canister.system_state.add_execution_profile(profile);
+        }
+
         match output.wasm_result {
             Ok(None) => {}
             Ok(Some(_response)) => {
@@ -22,15 +22,21 @@ use ic_config::execution_environment::Config as ExecutionConfig;
 use ic_config::flag_status::FlagStatus;
 use ic_constants::{LOG_CANISTER_OPERATION_CYCLES_THRESHOLD, SMALL_APP_SUBNET_MAX_SIZE};
 use ic_crypto_tecdsa::derive_tecdsa_public_key;
+use ic_crypto_tschnorr::derive_tschnorr_public_key;
+use ic_crypto_vetkd::{derive_vetkd_public_key, verify_transport_public_key};
 use ic_cycles_account_manager::{CyclesAccountManager, IngressInductionCost};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
     CanisterChangeOrigin, CanisterHttpRequestArgs, CanisterIdRecord, CanisterInfoRequest,
-    CanisterInfoResponse, CanisterSettingsArgs, ComputeInitialEcdsaDealingsArgs,
-    CreateCanisterArgs, ECDSAPublicKeyArgs, ECDSAPublicKeyResponse, EcdsaKeyId, EmptyBlob,
-    InstallCodeArgs, Method as Ic00Method, Payload as Ic00Payload,
-    ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs, SetControllerArgs,
-    SetupInitialDKGArgs, SignWithECDSAArgs, UninstallCodeArgs, UpdateSettingsArgs, IC_00,
+    CanisterInfoResponse, CanisterSettingsArgs, ClearChunkStoreArgs,
+    ComputeInitialEcdsaDealingsArgs, CreateCanisterArgs, DeleteCanisterSnapshotArgs,
+    ECDSAPublicKeyArgs, ECDSAPublicKeyResponse, EcdsaKeyId, EmptyBlob, InstallChunkedCodeArgs,
+    InstallCodeArgs, ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs, Method as Ic00Method,
+    Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs,
+    SchnorrKeyId, SchnorrPublicKeyArgs, SchnorrPublicKeyResponse, SetControllerArgs,
+    SetupInitialDKGArgs, SignWithECDSAArgs, SignWithSchnorrArgs, StoredChunksArgs,
+    TakeCanisterSnapshotArgs, UninstallCodeArgs, UpdateSettingsArgs, UploadChunkArgs,
+    VetKdDeriveEncryptedKeyArgs, VetKdKeyId, VetKdPublicKeyArgs, VetKdPublicKeyResult, IC_00,
 };
 use ic_interfaces::{
     execution_environment::{
@@ -53,15 +59,16 @@ use ic_replicated_state::{
 };
 use ic_replicated_state::{
     metadata_state::subnet_call_context_manager::{
-        EcdsaDealingsContext, SetupInitialDkgContext, SignWithEcdsaContext,
+        EcdsaDealingsContext, SetupInitialDkgContext, SignWithEcdsaContext, SignWithSchnorrContext,
+        VetKdContext,
     },
     CanisterState, NetworkTopology, ReplicatedState,
 };
 use ic_system_api::{ExecutionParameters, InstructionLimits};
 use ic_types::{
-    canister_http::CanisterHttpRequestContext,
+    canister_http::{CanisterHttpRequestContext, Replication},
     crypto::canister_threshold_sig::{ExtendedDerivationPath, MasterEcdsaPublicKey},
-    crypto::threshold_sig::ni_dkg::NiDkgTargetId,
+    crypto::threshold_sig::{ni_dkg::NiDkgTargetId, ThresholdSigPublicKey},
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{
         extract_effective_canister_id, AnonymousQuery, Payload, RejectContext, Request, Response,
@@ -69,8 +76,8 @@ use ic_types::{
     },
     methods::SystemMethod,
     nominal_cycles::NominalCycles,
-    CanisterId, CpuComplexity, Cycles, LongExecutionMode, NumBytes, NumInstructions, SubnetId,
-    Time,
+    CanisterId, CpuComplexity, Cycles, LongExecutionMode, NodeId, NumBytes, NumInstructions,
+    SubnetId, Time,
 };
 use ic_types::{messages::MessageId, methods::WasmMethod};
 use ic_wasm_types::WasmHash;
@@ -306,6 +313,7 @@ impl ExecutionEnvironment {
             compute_capacity,
             config.rate_limiting_of_instructions,
             config.allocatable_compute_capacity_in_percent,
+            config.max_number_of_snapshots_per_canister,
         );
         let canister_manager = CanisterManager::new(
             Arc::clone(&hypervisor),
@@ -355,6 +363,8 @@ impl ExecutionEnvironment {
         instruction_limits: InstructionLimits,
         rng: &mut dyn RngCore,
         ecdsa_subnet_public_keys: &BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+        schnorr_subnet_public_keys: &BTreeMap<SchnorrKeyId, MasterEcdsaPublicKey>,
+        vetkd_subnet_public_keys: &BTreeMap<VetKdKeyId, ThresholdSigPublicKey>,
         registry_settings: &RegistryExecutionSettings,
         round_limits: &mut RoundLimits,
     ) -> (ReplicatedState, Option<NumInstructions>) {
@@ -422,7 +432,7 @@ impl ExecutionEnvironment {
         }
 
         let result = match method {
-            Ok(Ic00Method::InstallCode) => {
+            Ok(Ic00Method::InstallCode) | Ok(Ic00Method::InstallChunkedCode) => {
                 // Tail call is needed for deterministic time slicing here to
                 // properly handle the case of a paused execution.
                 return self.execute_install_code(
@@ -735,58 +745,92 @@ impl ExecutionEnvironment {
                     CanisterCall::Request(request) => {
                         match CanisterHttpRequestArgs::decode(payload) {
                             Err(err) => Some((Err(err), msg.take_cycles())),
-                            Ok(args) => match CanisterHttpRequestContext::try_from((
-                                state.time(),
-                                request.as_ref(),
-                                args,
-                            )) {
-                                Err(err) => Some((Err(err.into()), msg.take_cycles())),
-                                Ok(mut canister_http_request_context) => {
-                                    let http_request_fee =
-                                        self.cycles_account_manager.http_request_fee(
-                                            canister_http_request_context.variable_parts_size(),
-                                            canister_http_request_context.max_response_bytes,
-                                            registry_settings.subnet_size,
-                                        );
-                                    if request.payment < http_request_fee {
-                                        let err = Err(UserError::new(
+                            Ok(args) => {
+                                let non_replicated = args.is_non_replicated();
+                                match CanisterHttpRequestContext::try_from((
+                                    state.time(),
+                                    request.as_ref(),
+                                    args,
+                                )) {
+                                    Err(err) => Some((Err(err.into()), msg.take_cycles())),
+                                    Ok(mut canister_http_request_context) => {
+                                        let http_request_fee = if non_replicated {
+                                            self.cycles_account_manager
+                                                .http_request_fee_non_replicated(
+                                                    canister_http_request_context
+                                                        .variable_parts_size(),
+                                                    canister_http_request_context
+                                                        .max_response_bytes,
+                                                    registry_settings.subnet_size,
+                                                )
+                                        } else {
+                                            self.cycles_account_manager.http_request_fee(
+                                                canister_http_request_context.variable_parts_size(),
+                                                canister_http_request_context.max_response_bytes,
+                                                registry_settings.subnet_size,
+                                            )
+                                        };
+                                        let designated_node = if non_replicated {
+                                            designate_non_replicated_node(&state, rng)
+                                        } else {
+                                            None
+                                        };
+                                        if non_replicated && designated_node.is_none() {
+                                            let err = Err(UserError::new(
+                                            ErrorCode::CanisterRejectedMessage,
+                                            format!(
+                                                "http_request: no node on subnet {} is available to perform a non-replicated request.",
+                                                state.metadata.own_subnet_id
+                                            ),
+                                        ));
+                                            Some((err, msg.take_cycles()))
+                                        } else if request.payment < http_request_fee {
+                                            let err = Err(UserError::new(
                                                         ErrorCode::CanisterRejectedMessage,
                                                         format!(
                                                             "http_request request sent with {} cycles, but {} cycles are required.",
                                                             request.payment, http_request_fee
                                                         ),
                                                     ));
-                                        Some((err, msg.take_cycles()))
-                                    } else {
-                                        canister_http_request_context.request.payment -=
-                                            http_request_fee;
-                                        let http_fee = NominalCycles::from(http_request_fee);
-                                        state
-                                            .metadata
-                                            .subnet_metrics
-                                            .consumed_cycles_http_outcalls += http_fee;
-                                        state
-                                            .metadata
-                                            .subnet_metrics
-                                            .observe_consumed_cycles_with_use_case(
-                                                CyclesUseCase::HTTPOutcalls,
-                                                http_fee,
+                                            Some((err, msg.take_cycles()))
+                                        } else {
+                                            if let Some(node_id) = designated_node {
+                                                canister_http_request_context.replication =
+                                                    Replication::NonReplicated(node_id);
+                                            }
+                                            canister_http_request_context.request.payment -=
+                                                http_request_fee;
+                                            let http_fee = NominalCycles::from(http_request_fee);
+                                            state
+                                                .metadata
+                                                .subnet_metrics
+                                                .consumed_cycles_http_outcalls += http_fee;
+                                            state
+                                                .metadata
+                                                .subnet_metrics
+                                                .observe_consumed_cycles_with_use_case(
+                                                    CyclesUseCase::HTTPOutcalls,
+                                                    http_fee,
+                                                );
+                                            state
+                                                .metadata
+                                                .subnet_call_context_manager
+                                                .push_context(
+                                                    SubnetCallContext::CanisterHttpRequest(
+                                                        canister_http_request_context,
+                                                    ),
+                                                );
+                                            self.metrics.observe_message_with_label(
+                                                &request.method_name,
+                                                timer.elapsed(),
+                                                SUBMITTED_OUTCOME_LABEL.into(),
+                                                SUCCESS_STATUS_LABEL.into(),
                                             );
-                                        state.metadata.subnet_call_context_manager.push_context(
-                                            SubnetCallContext::CanisterHttpRequest(
-                                                canister_http_request_context,
-                                            ),
-                                        );
-                                        self.metrics.observe_message_with_label(
-                                            &request.method_name,
-                                            timer.elapsed(),
-                                            SUBMITTED_OUTCOME_LABEL.into(),
-                                            SUCCESS_STATUS_LABEL.into(),
-                                        );
-                                        None
+                                            None
+                                        }
                                     }
                                 }
-                            },
+                            }
                         }
                     }
 
@@ -848,6 +892,106 @@ impl ExecutionEnvironment {
                 }
             }
 
+            Ok(Ic00Method::SchnorrPublicKey) => {
+                let cycles = msg.take_cycles();
+                match &msg {
+                    CanisterCall::Request(request) => {
+                        let res = SchnorrPublicKeyArgs::decode(payload).and_then(|args| {
+                            let master_public_key = get_master_schnorr_public_key(
+                                schnorr_subnet_public_keys,
+                                self.own_subnet_id,
+                                &args.key_id,
+                            )?;
+                            self.get_schnorr_public_key(
+                                master_public_key,
+                                args.canister_id.unwrap_or(request.sender),
+                                args.derivation_path.get(),
+                            )
+                            .map(|res| res.encode())
+                        });
+                        Some((res, cycles))
+                    }
+                    CanisterCall::Ingress(_) => {
+                        self.reject_unexpected_ingress(Ic00Method::SchnorrPublicKey)
+                    }
+                }
+            }
+
+            Ok(Ic00Method::SignWithSchnorr) => match &msg {
+                CanisterCall::Request(request) => match SignWithSchnorrArgs::decode(payload) {
+                    Err(err) => Some((Err(err), msg.take_cycles())),
+                    Ok(args) => match get_master_schnorr_public_key(
+                        schnorr_subnet_public_keys,
+                        self.own_subnet_id,
+                        &args.key_id,
+                    ) {
+                        Err(err) => Some((Err(err), msg.take_cycles())),
+                        Ok(_) => self
+                            .sign_with_schnorr(
+                                (**request).clone(),
+                                args,
+                                registry_settings.max_ecdsa_queue_size,
+                                &mut state,
+                            )
+                            .map_or_else(|err| Some((Err(err), msg.take_cycles())), |()| None),
+                    },
+                },
+                CanisterCall::Ingress(_) => {
+                    self.reject_unexpected_ingress(Ic00Method::SignWithSchnorr)
+                }
+            },
+
+            Ok(Ic00Method::VetKdPublicKey) => {
+                let cycles = msg.take_cycles();
+                match &msg {
+                    CanisterCall::Request(request) => {
+                        let res = VetKdPublicKeyArgs::decode(payload).and_then(|args| {
+                            let master_public_key = get_master_vetkd_public_key(
+                                vetkd_subnet_public_keys,
+                                self.own_subnet_id,
+                                &args.key_id,
+                            )?;
+                            self.get_vetkd_public_key(
+                                master_public_key,
+                                args.canister_id.unwrap_or(request.sender),
+                                args.derivation_path.get(),
+                            )
+                            .map(|res| res.encode())
+                        });
+                        Some((res, cycles))
+                    }
+                    CanisterCall::Ingress(_) => {
+                        self.reject_unexpected_ingress(Ic00Method::VetKdPublicKey)
+                    }
+                }
+            }
+
+            Ok(Ic00Method::VetKdDeriveEncryptedKey) => match &msg {
+                CanisterCall::Request(request) => {
+                    match VetKdDeriveEncryptedKeyArgs::decode(payload) {
+                        Err(err) => Some((Err(err), msg.take_cycles())),
+                        Ok(args) => match get_master_vetkd_public_key(
+                            vetkd_subnet_public_keys,
+                            self.own_subnet_id,
+                            &args.key_id,
+                        ) {
+                            Err(err) => Some((Err(err), msg.take_cycles())),
+                            Ok(_) => self
+                                .vetkd_derive_encrypted_key(
+                                    (**request).clone(),
+                                    args,
+                                    registry_settings.max_ecdsa_queue_size,
+                                    &mut state,
+                                )
+                                .map_or_else(|err| Some((Err(err), msg.take_cycles())), |()| None),
+                        },
+                    }
+                }
+                CanisterCall::Ingress(_) => {
+                    self.reject_unexpected_ingress(Ic00Method::VetKdDeriveEncryptedKey)
+                }
+            },
+
             Ok(Ic00Method::ComputeInitialEcdsaDealings) => {
                 let cycles = msg.take_cycles();
                 match &msg {
@@ -875,6 +1019,123 @@ impl ExecutionEnvironment {
                 }
             }
 
+            Ok(Ic00Method::TakeCanisterSnapshot) => {
+                let res = match TakeCanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .take_canister_snapshot(
+                            *msg.sender(),
+                            args.get_canister_id(),
+                            args.replace_snapshot(),
+                            &mut state,
+                            round_limits,
+                        )
+                        .map(|response| response.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::LoadCanisterSnapshot) => {
+                let res = match LoadCanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .load_canister_snapshot(
+                            msg.canister_change_origin(args.get_sender_canister_version()),
+                            args.get_canister_id(),
+                            args.snapshot_id(),
+                            &mut state,
+                            round_limits,
+                        )
+                        .map(|()| EmptyBlob.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::ListCanisterSnapshots) => {
+                let res = match ListCanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .list_canister_snapshots(*msg.sender(), args.get_canister_id(), &state)
+                        .map(|response| response.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::DeleteCanisterSnapshot) => {
+                let res = match DeleteCanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self
+                        .canister_manager
+                        .delete_canister_snapshot(
+                            *msg.sender(),
+                            args.get_canister_id(),
+                            args.snapshot_id(),
+                            &mut state,
+                            round_limits,
+                        )
+                        .map(|()| EmptyBlob.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::UploadChunk) => {
+                let res = match UploadChunkArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self.upload_chunk(
+                        *msg.sender(),
+                        args.get_canister_id(),
+                        args.chunk,
+                        &mut state,
+                        round_limits,
+                    ),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::ClearChunkStore) => {
+                let res = match ClearChunkStoreArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => self.clear_chunk_store(
+                        *msg.sender(),
+                        args.get_canister_id(),
+                        &mut state,
+                        round_limits,
+                    ),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::StoredChunks) => {
+                let res = match StoredChunksArgs::decode(payload) {
+                    Err(err) => Err(err),
+                    Ok(args) => get_canister(args.get_canister_id(), &state).and_then(|canister| {
+                        self.canister_manager
+                            .stored_chunks(*msg.sender(), canister)
+                            .map(|reply| reply.encode())
+                            .map_err(|err| err.into())
+                    }),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::FetchCanisterLogs) => Some((
+                Err(UserError::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!(
+                        "{} API is only accessible in non-replicated mode",
+                        Ic00Method::FetchCanisterLogs
+                    ),
+                )),
+                msg.take_cycles(),
+            )),
+
             Ok(Ic00Method::ProvisionalCreateCanisterWithCycles) => {
                 let res = match ProvisionalCreateCanisterWithCyclesArgs::decode(payload) {
                     Err(err) => Err(err),
@@ -1278,6 +1539,35 @@ impl ExecutionEnvironment {
             .map_err(|err| err.into())
     }
 
+    fn upload_chunk(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        chunk: Vec<u8>,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<Vec<u8>, UserError> {
+        let canister = get_canister_mut(canister_id, state)?;
+        self.canister_manager
+            .upload_chunk(sender, canister, chunk, round_limits)
+            .map(|reply| reply.encode())
+            .map_err(|err| err.into())
+    }
+
+    fn clear_chunk_store(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<Vec<u8>, UserError> {
+        let canister = get_canister_mut(canister_id, state)?;
+        self.canister_manager
+            .clear_chunk_store(sender, canister, round_limits)
+            .map(|()| EmptyBlob.encode())
+            .map_err(|err| err.into())
+    }
+
     fn start_canister(
         &self,
         canister_id: CanisterId,
@@ -1860,6 +2150,117 @@ impl ExecutionEnvironment {
         Ok(())
     }
 
+    fn get_schnorr_public_key(
+        &self,
+        master_public_key: &MasterEcdsaPublicKey,
+        canister_id: CanisterId,
+        derivation_path: Vec<Vec<u8>>,
+    ) -> Result<SchnorrPublicKeyResponse, UserError> {
+        let path = ExtendedDerivationPath {
+            caller: canister_id.get(),
+            derivation_path,
+        };
+        derive_tschnorr_public_key(master_public_key, &path)
+            .map_err(|err| UserError::new(ErrorCode::CanisterRejectedMessage, format!("{}", err)))
+            .map(|res| SchnorrPublicKeyResponse {
+                public_key: res.public_key,
+                chain_code: res.chain_code,
+            })
+    }
+
+    /// Records a Schnorr signature request in the subnet call context manager.
+    /// Consensus combines the signature shares of the nodes and delivers the
+    /// signature as the response.
+    fn sign_with_schnorr(
+        &self,
+        request: Request,
+        args: SignWithSchnorrArgs,
+        max_queue_size: u32,
+        state: &mut ReplicatedState,
+    ) -> Result<(), UserError> {
+        // Schnorr signature requests are bounded by the same queue size as
+        // ECDSA signature requests.
+        if state
+            .metadata
+            .subnet_call_context_manager
+            .sign_with_schnorr_contexts
+            .len()
+            >= max_queue_size as usize
+        {
+            return Err(UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                "sign_with_schnorr request could not be handled, the Schnorr signature queue is full."
+                    .to_string(),
+            ));
+        }
+
+        state.metadata.subnet_call_context_manager.push_context(
+            SubnetCallContext::SignWithSchnorr(SignWithSchnorrContext {
+                request,
+                key_id: args.key_id,
+                message: args.message,
+                derivation_path: args.derivation_path.get(),
+                batch_time: state.metadata.batch_time,
+            }),
+        );
+        Ok(())
+    }
+
+    fn get_vetkd_public_key(
+        &self,
+        master_public_key: &ThresholdSigPublicKey,
+        canister_id: CanisterId,
+        derivation_path: Vec<Vec<u8>>,
+    ) -> Result<VetKdPublicKeyResult, UserError> {
+        derive_vetkd_public_key(master_public_key, &canister_id, &derivation_path)
+            .map_err(|err| UserError::new(ErrorCode::CanisterRejectedMessage, format!("{}", err)))
+            .map(|public_key| VetKdPublicKeyResult { public_key })
+    }
+
+    /// Records a vetKD request in the subnet call context manager. Consensus
+    /// aggregates the encrypted key shares of the nodes and delivers the
+    /// combined encrypted key as the response.
+    fn vetkd_derive_encrypted_key(
+        &self,
+        request: Request,
+        args: VetKdDeriveEncryptedKeyArgs,
+        max_queue_size: u32,
+        state: &mut ReplicatedState,
+    ) -> Result<(), UserError> {
+        verify_transport_public_key(&args.encryption_public_key).map_err(|err| {
+            UserError::new(ErrorCode::CanisterRejectedMessage, format!("{}", err))
+        })?;
+
+        // vetKD requests are bounded by the same queue size as ECDSA signature
+        // requests, as both are answered by threshold protocols run by consensus.
+        if state
+            .metadata
+            .subnet_call_context_manager
+            .vetkd_contexts
+            .len()
+            >= max_queue_size as usize
+        {
+            return Err(UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                "vetkd_derive_encrypted_key request could not be handled, the vetKD request queue is full."
+                    .to_string(),
+            ));
+        }
+
+        state
+            .metadata
+            .subnet_call_context_manager
+            .push_context(SubnetCallContext::VetKd(VetKdContext {
+                request,
+                key_id: args.key_id,
+                derivation_path: args.public_key_derivation_path.get(),
+                derivation_id: args.derivation_id,
+                encryption_public_key: args.encryption_public_key,
+                batch_time: state.metadata.batch_time,
+            }));
+        Ok(())
+    }
+
     fn compute_initial_ecdsa_dealings(
         &self,
         state: &mut ReplicatedState,
@@ -1886,7 +2287,8 @@ impl ExecutionEnvironment {
     /// exceeds the given slice limit.
     ///
     /// Precondition:
-    /// - The given message is an `install_code` message.
+    /// - The given message is an `install_code` or `install_chunked_code`
+    ///   message.
     /// - The canister does not have any paused execution in its task queue.
     ///
     /// Postcondition:
@@ -1906,10 +2308,21 @@ impl ExecutionEnvironment {
         // A helper function to make error handling more compact using `?`.
         fn decode_input_and_take_canister(
             msg: &CanisterCall,
+            canister_manager: &CanisterManager,
             state: &mut ReplicatedState,
         ) -> Result<(InstallCodeContext, CanisterState), UserError> {
             let payload = msg.method_payload();
-            let args = InstallCodeArgs::decode(payload)?;
+            let args = match Ic00Method::from_str(msg.method_name()) {
+                Ok(Ic00Method::InstallChunkedCode) => {
+                    let chunked_args = InstallChunkedCodeArgs::decode(payload)?;
+                    canister_manager.install_chunked_code_args_to_install_code_args(
+                        *msg.sender(),
+                        chunked_args,
+                        state,
+                    )?
+                }
+                _ => InstallCodeArgs::decode(payload)?,
+            };
             let install_context = InstallCodeContext::try_from((
                 msg.canister_change_origin(args.get_sender_canister_version()),
                 args,
@@ -1925,16 +2338,16 @@ impl ExecutionEnvironment {
         // Start logging execution time for `install_code`.
         let timer = Timer::start();
 
-        let (install_context, old_canister) = match decode_input_and_take_canister(&msg, &mut state)
-        {
-            Ok(result) => result,
-            Err(err) => {
-                let refund = msg.take_cycles();
-                let state =
-                    self.finish_subnet_message_execution(state, msg, Err(err), refund, timer);
-                return (state, Some(NumInstructions::from(0)));
-            }
-        };
+        let (install_context, old_canister) =
+            match decode_input_and_take_canister(&msg, &self.canister_manager, &mut state) {
+                Ok(result) => result,
+                Err(err) => {
+                    let refund = msg.take_cycles();
+                    let state =
+                        self.finish_subnet_message_execution(state, msg, Err(err), refund, timer);
+                    return (state, Some(NumInstructions::from(0)));
+                }
+            };
 
         // Check the precondition.
         match old_canister.next_execution() {
@@ -2521,6 +2934,52 @@ pub fn execute_canister(
     )
 }
 
+/// Picks the node of the own subnet that performs a non-replicated HTTP
+/// request. Returns `None` if the own subnet has no nodes in the topology.
+fn designate_non_replicated_node(state: &ReplicatedState, rng: &mut dyn RngCore) -> Option<NodeId> {
+    let nodes = &state
+        .metadata
+        .network_topology
+        .subnets
+        .get(&state.metadata.own_subnet_id)?
+        .nodes;
+    if nodes.is_empty() {
+        return None;
+    }
+    nodes
+        .iter()
+        .nth((rng.next_u64() % nodes.len() as u64) as usize)
+        .copied()
+}
+
+fn get_master_schnorr_public_key<'a>(
+    schnorr_subnet_public_keys: &'a BTreeMap<SchnorrKeyId, MasterEcdsaPublicKey>,
+    subnet_id: SubnetId,
+    key_id: &SchnorrKeyId,
+) -> Result<&'a MasterEcdsaPublicKey, UserError> {
+    match schnorr_subnet_public_keys.get(key_id) {
+        None => Err(UserError::new(
+            ErrorCode::CanisterRejectedMessage,
+            format!("Subnet {} does not hold Schnorr key {}.", subnet_id, key_id),
+        )),
+        Some(master_key) => Ok(master_key),
+    }
+}
+
+fn get_master_vetkd_public_key<'a>(
+    vetkd_subnet_public_keys: &'a BTreeMap<VetKdKeyId, ThresholdSigPublicKey>,
+    subnet_id: SubnetId,
+    key_id: &VetKdKeyId,
+) -> Result<&'a ThresholdSigPublicKey, UserError> {
+    match vetkd_subnet_public_keys.get(key_id) {
+        None => Err(UserError::new(
+            ErrorCode::CanisterRejectedMessage,
+            format!("Subnet {} does not hold vetKD key {}.", subnet_id, key_id),
+        )),
+        Some(master_key) => Ok(master_key),
+    }
+}
+
 fn get_master_ecdsa_public_key<'a>(
     ecdsa_subnet_public_keys: &'a BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
     subnet_id: SubnetId,
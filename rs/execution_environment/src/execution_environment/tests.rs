@@ -8,8 +8,9 @@ use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
     self as ic00, CanisterChange, CanisterHttpRequestArgs, CanisterIdRecord,
     CanisterStatusResultV2, CanisterStatusType, DerivationPath, EcdsaCurve, EcdsaKeyId, EmptyBlob,
-    HttpMethod, Method, Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs,
-    ProvisionalTopUpCanisterArgs, TransformContext, TransformFunc, IC_00,
+    HttpMethod, HttpOutcallReplication, Method, Payload as Ic00Payload,
+    ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs, SchnorrAlgorithm,
+    SchnorrKeyId, TransformContext, TransformFunc, VetKdCurve, VetKdKeyId, IC_00,
 };
 use ic_registry_routing_table::canister_id_into_u64;
 use ic_registry_routing_table::CanisterIdRange;
@@ -17,16 +18,18 @@ use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
     canister_state::{DEFAULT_QUEUE_CAPACITY, WASM_PAGE_SIZE_IN_BYTES},
     testing::{CanisterQueuesTesting, SystemStateTesting},
-    CanisterStatus, SystemState,
+    CanisterStatus, SystemState, TotalQueryStats,
 };
 use ic_test_utilities::{assert_utils::assert_balance_equals, mock_time};
 use ic_test_utilities_execution_environment::{
-    assert_empty_reply, check_ingress_status, get_reply, ExecutionTest, ExecutionTestBuilder,
+    assert_empty_reply, check_ingress_status, get_reply, schnorr_master_public_key,
+    vetkd_master_public_key, ExecutionTest, ExecutionTestBuilder,
 };
 use ic_test_utilities_metrics::{fetch_histogram_vec_count, metric_vec};
 use ic_types::canister_http::Transform;
+use ic_types::crypto::canister_threshold_sig::ExtendedDerivationPath;
 use ic_types::{
-    canister_http::CanisterHttpMethod,
+    canister_http::{CanisterHttpMethod, Replication},
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{
         CallbackId, Payload, RejectContext, RequestOrResponse, Response, MAX_RESPONSE_COUNT_BYTES,
@@ -669,6 +672,37 @@ fn get_running_canister_status_from_another_canister() {
     );
 }
 
+#[test]
+fn get_canister_status_reports_query_stats() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let controller = test.universal_canister().unwrap();
+    let canister = test.universal_canister().unwrap();
+    test.canister_state_mut(canister)
+        .scheduler_state
+        .total_query_stats = TotalQueryStats {
+        num_calls: 3,
+        num_instructions: 30_000,
+        ingress_payload_size: 300,
+        egress_payload_size: 3_000,
+    };
+    let canister_status_args = Encode!(&CanisterIdRecord::from(canister)).unwrap();
+    let get_canister_status = wasm()
+        .call_simple(
+            ic00::IC_00,
+            Method::CanisterStatus,
+            call_args().other_side(canister_status_args),
+        )
+        .build();
+    test.set_controller(canister, controller.get()).unwrap();
+    let result = test.ingress(controller, "update", get_canister_status);
+    let reply = get_reply(result);
+    let csr = CanisterStatusResultV2::decode(&reply).unwrap();
+    assert_eq!(csr.query_num_calls_total(), 3);
+    assert_eq!(csr.query_num_instructions_total(), 30_000);
+    assert_eq!(csr.query_request_payload_bytes_total(), 300);
+    assert_eq!(csr.query_response_payload_bytes_total(), 3_000);
+}
+
 #[test]
 fn get_canister_status_from_another_canister_when_memory_low() {
     let mut test = ExecutionTestBuilder::new().build();
@@ -1446,6 +1480,7 @@ fn execute_canister_http_request() {
             }),
             context: transform_context.clone(),
         }),
+        replication: None,
     };
 
     // Create request to HTTP_REQUEST method.
@@ -1500,6 +1535,64 @@ fn execute_canister_http_request() {
     );
 }
 
+#[test]
+fn execute_non_replicated_canister_http_request() {
+    let own_subnet = subnet_test_id(1);
+    let caller_canister = canister_test_id(10);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet)
+        .with_caller(own_subnet, caller_canister)
+        .build();
+    test.state_mut().metadata.own_subnet_features.http_requests = true;
+
+    let response_size_limit = 1000u64;
+    let args = CanisterHttpRequestArgs {
+        url: "https://".to_string(),
+        max_response_bytes: Some(response_size_limit),
+        headers: Vec::new(),
+        body: Some(vec![1, 2, 3]),
+        method: HttpMethod::PUT,
+        transform: None,
+        replication: Some(HttpOutcallReplication::NonReplicated),
+    };
+
+    let payment = Cycles::new(1_000_000_000);
+    test.inject_call_to_ic00(Method::HttpRequest, args.encode(), payment);
+    test.execute_all();
+
+    let http_request_context = test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .canister_http_request_contexts
+        .get(&CallbackId::from(0))
+        .unwrap()
+        .clone();
+    assert_eq!(http_request_context.http_method, CanisterHttpMethod::PUT);
+
+    // The designated node must be a member of the own subnet.
+    let own_subnet_nodes = &test
+        .state()
+        .metadata
+        .network_topology
+        .subnets
+        .get(&own_subnet)
+        .unwrap()
+        .nodes;
+    match http_request_context.replication {
+        Replication::NonReplicated(node_id) => assert!(own_subnet_nodes.contains(&node_id)),
+        Replication::FullyReplicated => panic!("Expected a non-replicated request"),
+    }
+
+    // A non-replicated request is charged the non-replicated fee, which is
+    // lower than the fee of the same request in replicated mode.
+    let request_size = http_request_context.variable_parts_size();
+    let fee = test
+        .http_request_fee_non_replicated(request_size, Some(NumBytes::from(response_size_limit)));
+    assert_eq!(http_request_context.request.payment, payment - fee);
+    assert!(fee < test.http_request_fee(request_size, Some(NumBytes::from(response_size_limit))));
+}
+
 #[test]
 fn execute_canister_http_request_disabled() {
     let own_subnet = subnet_test_id(1);
@@ -1525,6 +1618,7 @@ fn execute_canister_http_request_disabled() {
             }),
             context: vec![0, 1, 2],
         }),
+        replication: None,
     };
 
     // Create request to HTTP_REQUEST method.
@@ -1945,6 +2039,347 @@ fn ecdsa_signature_queue_fills_up() {
     );
 }
 
+fn make_vetkd_key(name: &str) -> VetKdKeyId {
+    VetKdKeyId {
+        curve: VetKdCurve::Bls12_381_G2,
+        name: name.to_string(),
+    }
+}
+
+/// The BLS12-381 G1 generator, a valid vetKD transport public key.
+const VETKD_TRANSPORT_PUBLIC_KEY: &str = "97f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb";
+
+#[test]
+fn vetkd_public_key_returns_derived_key() {
+    let vetkd_key = make_vetkd_key("test_key");
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_vetkd_key(vetkd_key.clone())
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let args = ic00::VetKdPublicKeyArgs {
+        canister_id: None,
+        derivation_path: DerivationPath::new(vec![b"path".to_vec()]),
+        key_id: vetkd_key,
+    };
+    let run = wasm()
+        .call_simple(
+            ic00::IC_00,
+            Method::VetKdPublicKey,
+            call_args()
+                .other_side(args.encode())
+                .on_reject(wasm().reject_message().reject()),
+        )
+        .build();
+
+    let result = get_reply(test.ingress(canister_id, "update", run));
+    let expected = ic_crypto_vetkd::derive_vetkd_public_key(
+        &vetkd_master_public_key(),
+        &canister_id,
+        &[b"path".to_vec()],
+    )
+    .unwrap();
+    assert_eq!(
+        ic00::VetKdPublicKeyResult::decode(&result)
+            .unwrap()
+            .public_key,
+        expected
+    );
+}
+
+#[test]
+fn vetkd_public_key_with_unknown_key_rejected() {
+    let correct_key = make_vetkd_key("correct_key");
+    let wrong_key = make_vetkd_key("wrong_key");
+    let own_subnet = subnet_test_id(1);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet)
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_vetkd_key(correct_key)
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let args = ic00::VetKdPublicKeyArgs {
+        canister_id: None,
+        derivation_path: DerivationPath::new(vec![]),
+        key_id: wrong_key.clone(),
+    };
+    let run = wasm()
+        .call_simple(
+            ic00::IC_00,
+            Method::VetKdPublicKey,
+            call_args()
+                .other_side(args.encode())
+                .on_reject(wasm().reject_message().reject()),
+        )
+        .build();
+
+    let result = test.ingress(canister_id, "update", run).unwrap();
+    assert_eq!(
+        WasmResult::Reject(format!(
+            "Subnet {} does not hold vetKD key {}.",
+            own_subnet, wrong_key
+        )),
+        result
+    );
+}
+
+#[test]
+fn vetkd_derive_encrypted_key_creates_context() {
+    let vetkd_key = make_vetkd_key("test_key");
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_vetkd_key(vetkd_key.clone())
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let args = ic00::VetKdDeriveEncryptedKeyArgs {
+        derivation_id: b"id".to_vec(),
+        public_key_derivation_path: DerivationPath::new(vec![b"path".to_vec()]),
+        key_id: vetkd_key.clone(),
+        encryption_public_key: hex::decode(VETKD_TRANSPORT_PUBLIC_KEY).unwrap(),
+    };
+    let run = wasm()
+        .call_simple(
+            ic00::IC_00,
+            Method::VetKdDeriveEncryptedKey,
+            call_args()
+                .other_side(args.encode())
+                .on_reject(wasm().reject_message().reject()),
+        )
+        .build();
+
+    let (_, ingress_status) = test.ingress_raw(canister_id, "update", run);
+    assert_eq!(
+        ingress_status,
+        IngressStatus::Known {
+            receiver: canister_id.get(),
+            user_id: test.user_id(),
+            time: test.time(),
+            state: IngressState::Processing,
+        }
+    );
+    let contexts = &test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .vetkd_contexts;
+    assert_eq!(contexts.len(), 1);
+    let context = contexts.values().next().unwrap();
+    assert_eq!(context.request.sender, canister_id);
+    assert_eq!(context.key_id, vetkd_key);
+    assert_eq!(context.derivation_path, vec![b"path".to_vec()]);
+    assert_eq!(context.derivation_id, b"id".to_vec());
+}
+
+#[test]
+fn vetkd_derive_encrypted_key_with_invalid_transport_key_rejected() {
+    let vetkd_key = make_vetkd_key("test_key");
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_vetkd_key(vetkd_key.clone())
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let args = ic00::VetKdDeriveEncryptedKeyArgs {
+        derivation_id: vec![],
+        public_key_derivation_path: DerivationPath::new(vec![]),
+        key_id: vetkd_key,
+        encryption_public_key: vec![1; 48],
+    };
+    let run = wasm()
+        .call_simple(
+            ic00::IC_00,
+            Method::VetKdDeriveEncryptedKey,
+            call_args()
+                .other_side(args.encode())
+                .on_reject(wasm().reject_message().reject()),
+        )
+        .build();
+
+    let result = test.ingress(canister_id, "update", run).unwrap();
+    assert_eq!(
+        WasmResult::Reject("invalid vetKD transport public key".to_string()),
+        result
+    );
+    assert!(test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .vetkd_contexts
+        .is_empty());
+}
+
+fn make_schnorr_key(algorithm: SchnorrAlgorithm, name: &str) -> SchnorrKeyId {
+    SchnorrKeyId {
+        algorithm,
+        name: name.to_string(),
+    }
+}
+
+#[test]
+fn schnorr_public_key_returns_derived_key() {
+    for algorithm in [SchnorrAlgorithm::Bip340Secp256k1, SchnorrAlgorithm::Ed25519] {
+        let schnorr_key = make_schnorr_key(algorithm, "test_key");
+        let mut test = ExecutionTestBuilder::new()
+            .with_own_subnet_id(subnet_test_id(1))
+            .with_nns_subnet_id(subnet_test_id(2))
+            .with_schnorr_key(schnorr_key.clone())
+            .build();
+        let canister_id = test.universal_canister().unwrap();
+        let args = ic00::SchnorrPublicKeyArgs {
+            canister_id: None,
+            derivation_path: DerivationPath::new(vec![b"path".to_vec()]),
+            key_id: schnorr_key,
+        };
+        let run = wasm()
+            .call_simple(
+                ic00::IC_00,
+                Method::SchnorrPublicKey,
+                call_args()
+                    .other_side(args.encode())
+                    .on_reject(wasm().reject_message().reject()),
+            )
+            .build();
+
+        let result = get_reply(test.ingress(canister_id, "update", run));
+        let expected = ic_crypto_tschnorr::derive_tschnorr_public_key(
+            &schnorr_master_public_key(algorithm),
+            &ExtendedDerivationPath {
+                caller: canister_id.get(),
+                derivation_path: vec![b"path".to_vec()],
+            },
+        )
+        .unwrap();
+        let response = ic00::SchnorrPublicKeyResponse::decode(&result).unwrap();
+        assert_eq!(response.public_key, expected.public_key);
+        assert_eq!(response.chain_code, expected.chain_code);
+    }
+}
+
+#[test]
+fn schnorr_public_key_with_unknown_key_rejected() {
+    let correct_key = make_schnorr_key(SchnorrAlgorithm::Ed25519, "correct_key");
+    let wrong_key = make_schnorr_key(SchnorrAlgorithm::Ed25519, "wrong_key");
+    let own_subnet = subnet_test_id(1);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet)
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_schnorr_key(correct_key)
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let args = ic00::SchnorrPublicKeyArgs {
+        canister_id: None,
+        derivation_path: DerivationPath::new(vec![]),
+        key_id: wrong_key.clone(),
+    };
+    let run = wasm()
+        .call_simple(
+            ic00::IC_00,
+            Method::SchnorrPublicKey,
+            call_args()
+                .other_side(args.encode())
+                .on_reject(wasm().reject_message().reject()),
+        )
+        .build();
+
+    let result = test.ingress(canister_id, "update", run).unwrap();
+    assert_eq!(
+        WasmResult::Reject(format!(
+            "Subnet {} does not hold Schnorr key {}.",
+            own_subnet, wrong_key
+        )),
+        result
+    );
+}
+
+#[test]
+fn sign_with_schnorr_creates_context() {
+    let schnorr_key = make_schnorr_key(SchnorrAlgorithm::Bip340Secp256k1, "test_key");
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_schnorr_key(schnorr_key.clone())
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let args = ic00::SignWithSchnorrArgs {
+        message: b"message".to_vec(),
+        derivation_path: DerivationPath::new(vec![b"path".to_vec()]),
+        key_id: schnorr_key.clone(),
+    };
+    let run = wasm()
+        .call_simple(
+            ic00::IC_00,
+            Method::SignWithSchnorr,
+            call_args()
+                .other_side(args.encode())
+                .on_reject(wasm().reject_message().reject()),
+        )
+        .build();
+
+    let (_, ingress_status) = test.ingress_raw(canister_id, "update", run);
+    assert_eq!(
+        ingress_status,
+        IngressStatus::Known {
+            receiver: canister_id.get(),
+            user_id: test.user_id(),
+            time: test.time(),
+            state: IngressState::Processing,
+        }
+    );
+    let contexts = &test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .sign_with_schnorr_contexts;
+    assert_eq!(contexts.len(), 1);
+    let context = contexts.values().next().unwrap();
+    assert_eq!(context.request.sender, canister_id);
+    assert_eq!(context.key_id, schnorr_key);
+    assert_eq!(context.message, b"message".to_vec());
+    assert_eq!(context.derivation_path, vec![b"path".to_vec()]);
+}
+
+#[test]
+fn sign_with_schnorr_with_unknown_key_rejected() {
+    let own_subnet = subnet_test_id(1);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet)
+        .with_nns_subnet_id(subnet_test_id(2))
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let schnorr_key = make_schnorr_key(SchnorrAlgorithm::Bip340Secp256k1, "test_key");
+    let args = ic00::SignWithSchnorrArgs {
+        message: vec![],
+        derivation_path: DerivationPath::new(vec![]),
+        key_id: schnorr_key.clone(),
+    };
+    let run = wasm()
+        .call_simple(
+            ic00::IC_00,
+            Method::SignWithSchnorr,
+            call_args()
+                .other_side(args.encode())
+                .on_reject(wasm().reject_message().reject()),
+        )
+        .build();
+
+    let result = test.ingress(canister_id, "update", run).unwrap();
+    assert_eq!(
+        WasmResult::Reject(format!(
+            "Subnet {} does not hold Schnorr key {}.",
+            own_subnet, schnorr_key
+        )),
+        result
+    );
+    assert!(test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .sign_with_schnorr_contexts
+        .is_empty());
+}
+
 #[test]
 fn canister_output_queue_does_not_overflow_when_calling_ic00() {
     let own_subnet = subnet_test_id(1);
@@ -301,5 +301,6 @@ fn dashboard_label_value_from(code: ErrorCode) -> &'static str {
         InsufficientCyclesInComputeAllocation => "Canister does not have enough cycles to increase its compute allocation",
         InsufficientCyclesInMemoryAllocation => "Canister does not have enough cycles to increase its memory allocation",
         InsufficientCyclesInMemoryGrow => "Canister does not have enough cycles to grow memory",
+        CanisterWasmMemoryLimitExceeded => "Canister exceeded its Wasm memory limit",
     }
 }
@@ -1,13 +1,14 @@
 use ic_canister_sandbox_replica_controller::sandboxed_execution_controller::SandboxedExecutionController;
+use ic_config::embedders::Config as EmbeddersConfig;
 use ic_config::execution_environment::{Config, MAX_COMPILATION_CACHE_SIZE};
 use ic_config::flag_status::FlagStatus;
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_embedders::wasm_executor::{WasmExecutionResult, WasmExecutor};
 use ic_embedders::wasm_utils::decoding::decoded_wasm_size;
 use ic_embedders::{wasm_executor::WasmExecutorImpl, WasmExecutionInput, WasmtimeEmbedder};
-use ic_embedders::{CompilationCache, CompilationResult};
+use ic_embedders::{CompilationCache, CompilationResult, DiskCompilationCache};
 use ic_interfaces::execution_environment::{HypervisorResult, WasmExecutionOutput};
-use ic_logger::ReplicaLogger;
+use ic_logger::{info, warn, ReplicaLogger};
 use ic_metrics::buckets::decimal_buckets_with_zero;
 use ic_metrics::{buckets::exponential_buckets, MetricsRegistry};
 use ic_registry_subnet_type::SubnetType;
@@ -22,11 +23,64 @@ use std::{path::PathBuf, sync::Arc};
 
 use crate::execution::common::{apply_canister_state_changes, update_round_limits};
 use crate::execution_environment::{as_round_instructions, CompilationCostHandling, RoundLimits};
-use ic_replicated_state::page_map::PageAllocatorFileDescriptor;
+use ic_replicated_state::page_map::{
+    PageAllocatorFileDescriptor, TestPageAllocatorFileDescriptorImpl,
+};
 
 #[cfg(test)]
 mod tests;
 
+/// Creates the Wasm compilation cache. If a cache directory is configured, the
+/// cache is backed by a disk tier, which is loaded into memory in the
+/// background so that startup is not delayed.
+fn new_compilation_cache(
+    config: &Config,
+    embedder_config: &EmbeddersConfig,
+    log: &ReplicaLogger,
+) -> Arc<CompilationCache> {
+    let dir = match &config.compilation_cache_dir {
+        Some(dir) => dir.clone(),
+        None => return Arc::new(CompilationCache::new(config.max_compilation_cache_size)),
+    };
+    let disk_cache = match DiskCompilationCache::new(
+        dir.clone(),
+        config.max_disk_compilation_cache_size,
+        embedder_config,
+        log.clone(),
+    ) {
+        Ok(disk_cache) => disk_cache,
+        Err(err) => {
+            warn!(
+                log,
+                "Failed to open the compilation cache in {}: {}",
+                dir.display(),
+                err
+            );
+            return Arc::new(CompilationCache::new(config.max_compilation_cache_size));
+        }
+    };
+
+    let compilation_cache = Arc::new(CompilationCache::new_with_disk_cache(
+        config.max_compilation_cache_size,
+        disk_cache,
+    ));
+    let cache = Arc::clone(&compilation_cache);
+    let log = log.clone();
+    std::thread::Builder::new()
+        .name("CompilationCacheWarmUp".to_string())
+        .spawn(move || {
+            let loaded = cache.warm_up();
+            info!(
+                log,
+                "Loaded {} compiled modules from {}",
+                loaded,
+                dir.display()
+            );
+        })
+        .expect("Failed to spawn the compilation cache warm-up thread");
+    compilation_cache
+}
+
 #[doc(hidden)] // pub for usage in tests
 pub struct HypervisorMetrics {
     accessed_pages: Histogram,
@@ -125,6 +179,7 @@ pub struct Hypervisor {
     deterministic_time_slicing: FlagStatus,
     cost_to_compile_wasm_instruction: NumInstructions,
     dirty_page_overhead: NumInstructions,
+    fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
 }
 
 impl Hypervisor {
@@ -136,6 +191,12 @@ impl Hypervisor {
         self.own_subnet_type
     }
 
+    /// Returns the factory of file descriptors backing the page maps that
+    /// execution creates.
+    pub(crate) fn fd_factory(&self) -> Arc<dyn PageAllocatorFileDescriptor> {
+        Arc::clone(&self.fd_factory)
+    }
+
     pub fn create_execution_state(
         &self,
         canister_module: CanisterModule,
@@ -198,6 +259,7 @@ impl Hypervisor {
         let mut embedder_config = config.embedders_config.clone();
         embedder_config.subnet_type = own_subnet_type;
         embedder_config.dirty_page_overhead = dirty_page_overhead;
+        let compilation_cache = new_compilation_cache(&config, &embedder_config, &log);
 
         let wasm_executor: Arc<dyn WasmExecutor> = match config.canister_sandboxing_flag {
             FlagStatus::Enabled => {
@@ -228,12 +290,13 @@ impl Hypervisor {
             own_subnet_type,
             log,
             cycles_account_manager,
-            compilation_cache: Arc::new(CompilationCache::new(config.max_compilation_cache_size)),
+            compilation_cache,
             deterministic_time_slicing: config.deterministic_time_slicing,
             cost_to_compile_wasm_instruction: config
                 .embedders_config
                 .cost_to_compile_wasm_instruction,
             dirty_page_overhead,
+            fd_factory,
         }
     }
 
@@ -260,6 +323,7 @@ impl Hypervisor {
             deterministic_time_slicing,
             cost_to_compile_wasm_instruction,
             dirty_page_overhead,
+            fd_factory: Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
         }
     }
 
@@ -2349,6 +2349,45 @@ fn subnet_available_memory_is_respected_by_memory_grow() {
     assert_eq!(ErrorCode::CanisterOutOfMemory, err.code());
 }
 
+#[test]
+fn wasm_memory_limit_is_enforced_in_updates_only() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func $grow
+                (if (i32.eq (memory.grow (i32.const 10)) (i32.const -1))
+                    (then (unreachable))
+                )
+                (call $msg_reply)
+            )
+            (func (export "canister_update grow") (call $grow))
+            (func (export "canister_query grow_query") (call $grow))
+            (func (export "canister_pre_upgrade")
+                (drop (memory.grow (i32.const 10)))
+            )
+            (memory 1 40)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    test.set_wasm_memory_limit(canister_id, NumBytes::new(5 * WASM_PAGE_SIZE as u64))
+        .unwrap();
+
+    let err = test.ingress(canister_id, "grow", vec![]).unwrap_err();
+    assert_eq!(ErrorCode::CanisterWasmMemoryLimitExceeded, err.code());
+
+    // Queries and upgrades are not affected by the limit.
+    let result = test.non_replicated_query(canister_id, "grow_query", vec![]);
+    assert_empty_reply(result);
+    test.upgrade_canister(canister_id, wat::parse_str(wat).unwrap())
+        .unwrap();
+
+    // A zero limit removes the limit.
+    test.set_wasm_memory_limit(canister_id, NumBytes::new(0))
+        .unwrap();
+    let result = test.ingress(canister_id, "grow", vec![]);
+    assert_empty_reply(result);
+}
+
 #[test]
 fn subnet_available_memory_is_updated() {
     let mut test = ExecutionTestBuilder::new().build();
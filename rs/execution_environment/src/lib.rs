@@ -33,6 +33,7 @@ use ic_interfaces::execution_environment::{
 use ic_interfaces_state_manager::StateReader;
 use ic_logger::ReplicaLogger;
 use ic_metrics::MetricsRegistry;
+use ic_query_stats::QueryStatsPayloadBuilderParams;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::page_map::PageAllocatorFileDescriptor;
 use ic_replicated_state::{CallOrigin, NetworkTopology, ReplicatedState};
@@ -84,6 +85,7 @@ pub struct ExecutionServices {
     pub async_query_handler: QueryExecutionService,
     pub anonymous_query_handler: AnonymousQueryService,
     pub scheduler: Box<dyn Scheduler<State = ReplicatedState>>,
+    pub query_stats_payload_builder: QueryStatsPayloadBuilderParams,
 }
 
 impl ExecutionServices {
@@ -131,6 +133,9 @@ impl ExecutionServices {
             config.clone(),
             Arc::clone(&cycles_account_manager),
         ));
+        let (query_stats_collector, query_stats_payload_builder) =
+            ic_query_stats::init_query_stats(logger.clone(), &config, metrics_registry);
+
         let sync_query_handler = Arc::new(InternalHttpQueryHandler::new(
             logger.clone(),
             hypervisor,
@@ -139,6 +144,7 @@ impl ExecutionServices {
             metrics_registry,
             scheduler_config.max_instructions_per_message_without_dts,
             Arc::clone(&cycles_account_manager),
+            query_stats_collector.clone(),
         ));
 
         // If this is not a system or verified subnet we can double the
@@ -165,6 +171,7 @@ impl ExecutionServices {
             Arc::clone(&sync_query_handler) as Arc<_>,
             query_scheduler.clone(),
             Arc::clone(&state_reader),
+            query_stats_collector,
         );
         let ingress_filter = IngressFilter::new_service(
             query_scheduler.clone(),
@@ -189,6 +196,7 @@ impl ExecutionServices {
             config.rate_limiting_of_heap_delta,
             config.rate_limiting_of_instructions,
             config.deterministic_time_slicing,
+            Arc::clone(&fd_factory),
         ));
 
         Self {
@@ -199,6 +207,7 @@ impl ExecutionServices {
             async_query_handler,
             anonymous_query_handler,
             scheduler,
+            query_stats_payload_builder,
         }
     }
 
@@ -18,10 +18,15 @@ use ic_config::flag_status::FlagStatus;
 use ic_crypto_tree_hash::{flatmap, Label, LabeledTree, LabeledTree::SubTree};
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, RejectCode, UserError};
+use ic_ic00_types::{
+    FetchCanisterLogsRequest, FetchCanisterLogsResponse, LogVisibility, Method as Ic00Method,
+    Payload,
+};
 use ic_interfaces::execution_environment::{QueryExecutionService, QueryHandler};
 use ic_interfaces_state_manager::StateReader;
 use ic_logger::ReplicaLogger;
 use ic_metrics::MetricsRegistry;
+use ic_query_stats::QueryStatsCollector;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::ReplicatedState;
 use ic_types::{
@@ -30,7 +35,7 @@ use ic_types::{
         Blob, Certificate, CertificateDelegation, HttpQueryResponse, HttpQueryResponseReply,
         UserQuery,
     },
-    CanisterId, NumInstructions,
+    CanisterId, NumInstructions, PrincipalId,
 };
 use serde::Serialize;
 use std::{
@@ -99,6 +104,7 @@ pub struct InternalHttpQueryHandler {
     max_instructions_per_query: NumInstructions,
     cycles_account_manager: Arc<CyclesAccountManager>,
     query_cache: query_cache::QueryCache,
+    query_stats_collector: QueryStatsCollector,
 }
 
 #[derive(Clone)]
@@ -107,6 +113,7 @@ pub(crate) struct HttpQueryHandler {
     internal: Arc<dyn QueryHandler<State = ReplicatedState>>,
     state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
     query_scheduler: QueryScheduler,
+    query_stats_collector: QueryStatsCollector,
 }
 
 impl InternalHttpQueryHandler {
@@ -118,6 +125,7 @@ impl InternalHttpQueryHandler {
         metrics_registry: &MetricsRegistry,
         max_instructions_per_query: NumInstructions,
         cycles_account_manager: Arc<CyclesAccountManager>,
+        query_stats_collector: QueryStatsCollector,
     ) -> Self {
         let query_cache_capacity = config.query_cache_capacity;
         Self {
@@ -129,6 +137,7 @@ impl InternalHttpQueryHandler {
             max_instructions_per_query,
             cycles_account_manager,
             query_cache: query_cache::QueryCache::new(metrics_registry, query_cache_capacity),
+            query_stats_collector,
         }
     }
 }
@@ -144,6 +153,14 @@ impl QueryHandler for InternalHttpQueryHandler {
     ) -> Result<WasmResult, UserError> {
         let measurement_scope = MeasurementScope::root(&self.metrics.query);
 
+        // Canister logs are served by the management canister directly from
+        // the state, without executing any canister code.
+        if query.receiver == CanisterId::ic_00()
+            && query.method_name == Ic00Method::FetchCanisterLogs.to_string()
+        {
+            return fetch_canister_logs(query.source.get(), state.as_ref(), &query.method_payload);
+        }
+
         // Check the query cache first (if the query caching is enabled).
         // If a valid cache entry found, the result will be immediately returned.
         // Otherwise, the key and the env will be kept for the `insert` below.
@@ -190,6 +207,11 @@ impl QueryHandler for InternalHttpQueryHandler {
             &measurement_scope,
         );
 
+        for (canister_id, stats) in context.query_stats() {
+            self.query_stats_collector
+                .register_query_statistics(*canister_id, stats);
+        }
+
         // Add the query execution result to the query cache  (if the query caching is enabled).
         if self.config.query_caching == FlagStatus::Enabled {
             if let (Some(key), Some(env)) = (cache_entry_key, cache_entry_env) {
@@ -201,16 +223,59 @@ impl QueryHandler for InternalHttpQueryHandler {
     }
 }
 
+fn fetch_canister_logs(
+    sender: PrincipalId,
+    state: &ReplicatedState,
+    payload: &[u8],
+) -> Result<WasmResult, UserError> {
+    let args = FetchCanisterLogsRequest::decode(payload)?;
+    let canister_id = args.get_canister_id();
+    let canister = state.canister_state(&canister_id).ok_or_else(|| {
+        UserError::new(
+            ErrorCode::CanisterNotFound,
+            format!("Canister {} not found.", canister_id),
+        )
+    })?;
+
+    match canister.system_state.log_visibility {
+        LogVisibility::Public => {}
+        LogVisibility::Controllers if canister.controllers().contains(&sender) => {}
+        LogVisibility::Controllers => {
+            return Err(UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                format!(
+                    "Caller {} is not allowed to query ic00 method {}",
+                    sender,
+                    Ic00Method::FetchCanisterLogs
+                ),
+            ));
+        }
+    }
+
+    let response = FetchCanisterLogsResponse {
+        canister_log_records: canister
+            .system_state
+            .canister_log
+            .records()
+            .iter()
+            .cloned()
+            .collect(),
+    };
+    Ok(WasmResult::Reply(response.encode()))
+}
+
 impl HttpQueryHandler {
     pub(crate) fn new_service(
         internal: Arc<dyn QueryHandler<State = ReplicatedState>>,
         query_scheduler: QueryScheduler,
         state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+        query_stats_collector: QueryStatsCollector,
     ) -> QueryExecutionService {
         BoxCloneService::new(Self {
             internal,
             state_reader,
             query_scheduler,
+            query_stats_collector,
         })
     }
 }
@@ -244,6 +309,7 @@ impl Service<(UserQuery, Option<CertificateDelegation>)> for HttpQueryHandler {
     ) -> Self::Future {
         let internal = Arc::clone(&self.internal);
         let state_reader = Arc::clone(&self.state_reader);
+        let query_stats_collector = self.query_stats_collector.clone();
         let (tx, rx) = oneshot::channel();
         let canister_id = query.receiver;
         self.query_scheduler.push(canister_id, move || {
@@ -252,6 +318,7 @@ impl Service<(UserQuery, Option<CertificateDelegation>)> for HttpQueryHandler {
                 // We managed to upgrade the weak pointer, so the query was not cancelled.
                 // Canceling the query after this point will have no effect: the query will
                 // be executed anyway. That is fine because the execution will take O(ms).
+                query_stats_collector.set_epoch_from_height(state_reader.latest_certified_height());
                 let result = match get_latest_certified_state_and_data_certificate(
                     state_reader,
                     certificate_delegation,
@@ -21,6 +21,7 @@ use ic_replicated_state::{
 };
 use ic_system_api::{ApiType, ExecutionParameters, InstructionLimits};
 use ic_types::{
+    batch::QueryStats,
     ingress::WasmResult,
     messages::{Payload, RejectContext, Request, RequestOrResponse, Response, UserQuery},
     methods::WasmMethod,
@@ -31,7 +32,12 @@ use ic_types::{
     NumSlices,
 };
 use prometheus::IntCounter;
-use std::{collections::VecDeque, sync::Arc, time::Duration, time::Instant};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Arc,
+    time::Duration,
+    time::Instant,
+};
 
 use super::query_call_graph::evaluate_query_call_graph;
 
@@ -95,6 +101,8 @@ pub(super) struct QueryContext<'a> {
     query_context_time_limit: Duration,
     query_critical_error: &'a IntCounter,
     subnet_memory_capacity: NumBytes,
+    // Statistics of the executed queries and callbacks, by canister.
+    query_stats: BTreeMap<CanisterId, QueryStats>,
 }
 
 impl<'a> QueryContext<'a> {
@@ -144,9 +152,16 @@ impl<'a> QueryContext<'a> {
             query_context_time_limit: max_query_call_walltime,
             query_critical_error,
             subnet_memory_capacity,
+            query_stats: BTreeMap::new(),
         }
     }
 
+    /// Returns the statistics of all queries and callbacks executed in this
+    /// context, by canister.
+    pub(super) fn query_stats(&self) -> &BTreeMap<CanisterId, QueryStats> {
+        &self.query_stats
+    }
+
     /// Executes the given query sent by an end user.
     ///
     /// - If it produces a response return the response.
@@ -400,6 +415,20 @@ impl<'a> QueryContext<'a> {
             NumSlices::from(1),
             NumMessages::from(1),
         );
+        let egress_payload_size = match &result {
+            Ok(Some(WasmResult::Reply(payload))) => payload.len(),
+            Ok(Some(WasmResult::Reject(message))) => message.len(),
+            Ok(None) | Err(_) => 0,
+        };
+        self.query_stats
+            .entry(canister.canister_id())
+            .or_default()
+            .saturating_accumulate(&QueryStats {
+                num_calls: 1,
+                num_instructions: instructions_executed.get(),
+                ingress_payload_size: method_payload.len() as u64,
+                egress_payload_size: egress_payload_size as u64,
+            });
         (canister, result)
     }
 
@@ -549,6 +578,13 @@ impl<'a> QueryContext<'a> {
             NumSlices::from(1),
             NumMessages::from(1),
         );
+        self.query_stats
+            .entry(canister_id)
+            .or_default()
+            .saturating_accumulate(&QueryStats {
+                num_instructions: instructions_executed.get(),
+                ..QueryStats::default()
+            });
         Ok((canister, call_origin, action))
     }
 
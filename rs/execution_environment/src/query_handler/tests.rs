@@ -2,6 +2,9 @@ use crate::InternalHttpQueryHandler;
 use ic_base_types::NumSeconds;
 use ic_config::execution_environment::INSTRUCTION_OVERHEAD_PER_QUERY_CALL;
 use ic_error_types::{ErrorCode, UserError};
+use ic_ic00_types::{
+    FetchCanisterLogsRequest, FetchCanisterLogsResponse, LogVisibility, Method, Payload,
+};
 use ic_interfaces::messages::CanisterTask;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::canister_state::system_state::CyclesUseCase;
@@ -11,7 +14,8 @@ use ic_test_utilities::{
 };
 use ic_test_utilities_execution_environment::{ExecutionTest, ExecutionTestBuilder};
 use ic_types::{
-    ingress::WasmResult, messages::UserQuery, time, CountBytes, Cycles, NumInstructions,
+    ingress::WasmResult, messages::UserQuery, time, CanisterId, CountBytes, Cycles,
+    NumInstructions, UserId,
 };
 use std::{sync::Arc, time::Duration};
 
@@ -1652,6 +1656,128 @@ fn queries_to_frozen_canisters_are_rejected() {
     assert!(result.is_ok());
 }
 
+fn fetch_canister_logs(
+    test: &ExecutionTest,
+    source: UserId,
+    canister_id: CanisterId,
+) -> Result<WasmResult, UserError> {
+    test.query(
+        UserQuery {
+            source,
+            receiver: CanisterId::ic_00(),
+            method_name: Method::FetchCanisterLogs.to_string(),
+            method_payload: FetchCanisterLogsRequest::new(canister_id).encode(),
+            ingress_expiry: 0,
+            nonce: None,
+        },
+        Arc::new(test.state().clone()),
+        vec![],
+    )
+}
+
+fn log_contents(result: Result<WasmResult, UserError>) -> Vec<Vec<u8>> {
+    match result.unwrap() {
+        WasmResult::Reply(bytes) => FetchCanisterLogsResponse::decode(&bytes)
+            .unwrap()
+            .canister_log_records
+            .into_iter()
+            .map(|record| record.content)
+            .collect(),
+        WasmResult::Reject(msg) => panic!("Unexpected reject: {}", msg),
+    }
+}
+
+#[test]
+fn fetch_canister_logs_returns_debug_prints_and_traps() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister = test.universal_canister().unwrap();
+
+    test.ingress(
+        canister,
+        "update",
+        wasm().debug_print(b"hello").reply().build(),
+    )
+    .unwrap();
+    test.ingress(
+        canister,
+        "update",
+        wasm()
+            .debug_print(b"about to trap")
+            .trap_with_blob(b"boom")
+            .build(),
+    )
+    .unwrap_err();
+
+    let contents = log_contents(fetch_canister_logs(&test, test.user_id(), canister));
+    assert_eq!(
+        contents,
+        vec![
+            b"hello".to_vec(),
+            b"about to trap".to_vec(),
+            b"[TRAP]: boom".to_vec()
+        ]
+    );
+}
+
+#[test]
+fn fetch_canister_logs_records_wasm_traps() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister = test
+        .canister_from_wat(
+            r#"(module
+                (func (export "canister_update trap") unreachable)
+                (memory 1)
+            )"#,
+        )
+        .unwrap();
+
+    test.ingress(canister, "trap", vec![]).unwrap_err();
+
+    let contents = log_contents(fetch_canister_logs(&test, test.user_id(), canister));
+    assert_eq!(
+        contents,
+        vec![format!("[TRAP]: Canister {} trapped: unreachable", canister).into_bytes()]
+    );
+}
+
+#[test]
+fn fetch_canister_logs_respects_log_visibility() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister = test.universal_canister().unwrap();
+    test.ingress(
+        canister,
+        "update",
+        wasm().debug_print(b"hello").reply().build(),
+    )
+    .unwrap();
+
+    // By default only controllers can fetch the logs.
+    let result = fetch_canister_logs(&test, user_test_id(42), canister);
+    assert_eq!(
+        result.unwrap_err().code(),
+        ErrorCode::CanisterRejectedMessage
+    );
+
+    test.set_log_visibility(canister, LogVisibility::Public)
+        .unwrap();
+    let contents = log_contents(fetch_canister_logs(&test, user_test_id(42), canister));
+    assert_eq!(contents, vec![b"hello".to_vec()]);
+}
+
+#[test]
+fn fetch_canister_logs_is_rejected_in_replicated_mode() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister = test.universal_canister().unwrap();
+    let result = test.subnet_message(
+        Method::FetchCanisterLogs,
+        FetchCanisterLogsRequest::new(canister).encode(),
+    );
+    assert_eq!(
+        result.unwrap_err().code(),
+        ErrorCode::CanisterRejectedMessage
+    );
+}
+
 const COMPOSITE_QUERY_WAT: &str = r#"
         (module
             (import "ic0" "msg_reply" (func $msg_reply))
@@ -12,7 +12,9 @@ use ic_config::subnet_config::SchedulerConfig;
 use ic_crypto_prng::{Csprng, RandomnessPurpose::ExecutionThread};
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, UserError};
-use ic_ic00_types::{CanisterStatusType, EcdsaKeyId, Method as Ic00Method};
+use ic_ic00_types::{
+    CanisterStatusType, EcdsaKeyId, Method as Ic00Method, SchnorrKeyId, VetKdKeyId,
+};
 use ic_interfaces::execution_environment::{
     ExecutionComplexity, ExecutionRoundType, RegistryExecutionSettings,
 };
@@ -26,12 +28,13 @@ use ic_replicated_state::{
     canister_state::{
         execution_state::NextScheduledMethod, system_state::CyclesUseCase, NextExecution,
     },
+    page_map::PageAllocatorFileDescriptor,
     testing::ReplicatedStateTesting,
     CanisterState, CanisterStatus, ExecutionTask, InputQueueType, NetworkTopology, ReplicatedState,
 };
 use ic_system_api::InstructionLimits;
 use ic_types::{
-    crypto::canister_threshold_sig::MasterEcdsaPublicKey,
+    crypto::{canister_threshold_sig::MasterEcdsaPublicKey, threshold_sig::ThresholdSigPublicKey},
     ingress::{IngressState, IngressStatus},
     messages::{Ingress, MessageId},
     AccumulatedPriority, CanisterId, ComputeAllocation, Cycles, ExecutionRound, LongExecutionMode,
@@ -115,6 +118,7 @@ pub(crate) struct SchedulerImpl {
     rate_limiting_of_heap_delta: FlagStatus,
     rate_limiting_of_instructions: FlagStatus,
     deterministic_time_slicing: FlagStatus,
+    fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
 }
 
 impl SchedulerImpl {
@@ -371,6 +375,7 @@ impl SchedulerImpl {
         rate_limiting_of_heap_delta: FlagStatus,
         rate_limiting_of_instructions: FlagStatus,
         deterministic_time_slicing: FlagStatus,
+        fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
     ) -> Self {
         let scheduler_cores = config.scheduler_cores as u32;
         Self {
@@ -385,6 +390,7 @@ impl SchedulerImpl {
             rate_limiting_of_heap_delta,
             rate_limiting_of_instructions,
             deterministic_time_slicing,
+            fd_factory,
         }
     }
 
@@ -454,6 +460,8 @@ impl SchedulerImpl {
         long_running_canister_ids: BTreeSet<CanisterId>,
         registry_settings: &RegistryExecutionSettings,
         ecdsa_subnet_public_keys: &BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+        schnorr_subnet_public_keys: &BTreeMap<SchnorrKeyId, MasterEcdsaPublicKey>,
+        vetkd_subnet_public_keys: &BTreeMap<VetKdKeyId, ThresholdSigPublicKey>,
     ) -> ReplicatedState {
         loop {
             let mut available_subnet_messages = false;
@@ -486,6 +494,8 @@ impl SchedulerImpl {
                     instruction_limits,
                     csprng,
                     ecdsa_subnet_public_keys,
+                    schnorr_subnet_public_keys,
+                    vetkd_subnet_public_keys,
                     registry_settings,
                     round_limits,
                 );
@@ -1009,6 +1019,7 @@ impl SchedulerImpl {
                         canister,
                         state_time,
                         AddCanisterChangeToHistory::No,
+                        Arc::clone(&self.fd_factory),
                     ));
                     canister.scheduler_state.compute_allocation = ComputeAllocation::zero();
                     canister.system_state.memory_allocation = MemoryAllocation::BestEffort;
@@ -1288,6 +1299,8 @@ impl Scheduler for SchedulerImpl {
         mut state: ReplicatedState,
         randomness: Randomness,
         ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+        schnorr_subnet_public_keys: BTreeMap<SchnorrKeyId, MasterEcdsaPublicKey>,
+        vetkd_subnet_public_keys: BTreeMap<VetKdKeyId, ThresholdSigPublicKey>,
         current_round: ExecutionRound,
         current_round_type: ExecutionRoundType,
         registry_settings: &RegistryExecutionSettings,
@@ -1429,6 +1442,8 @@ impl Scheduler for SchedulerImpl {
                     instruction_limits,
                     &mut csprng,
                     &ecdsa_subnet_public_keys,
+                    &schnorr_subnet_public_keys,
+                    &vetkd_subnet_public_keys,
                     registry_settings,
                     &mut round_limits,
                 );
@@ -1474,6 +1489,8 @@ impl Scheduler for SchedulerImpl {
                 long_running_canister_ids,
                 registry_settings,
                 &ecdsa_subnet_public_keys,
+                &schnorr_subnet_public_keys,
+                &vetkd_subnet_public_keys,
             );
 
             // Reset the round limit after executing all subnet messages.
@@ -2093,7 +2110,9 @@ fn can_execute_msg(
         };
 
         // Only one install code message allowed at a time.
-        if let Some(Ic00Method::InstallCode) = maybe_instal_code_method {
+        if let Some(Ic00Method::InstallCode) | Some(Ic00Method::InstallChunkedCode) =
+            maybe_instal_code_method
+        {
             return false;
         }
     }
@@ -2138,6 +2157,10 @@ fn get_instructions_limits_for_subnet_message(
             | HttpRequest
             | SetupInitialDKG
             | SignWithECDSA
+            | SchnorrPublicKey
+            | SignWithSchnorr
+            | VetKdPublicKey
+            | VetKdDeriveEncryptedKey
             | ComputeInitialEcdsaDealings
             | StartCanister
             | StopCanister
@@ -2150,8 +2173,16 @@ fn get_instructions_limits_for_subnet_message(
             | BitcoinGetCurrentFeePercentiles
             | BitcoinGetSuccessors
             | ProvisionalCreateCanisterWithCycles
-            | ProvisionalTopUpCanister => default_limits,
-            InstallCode => InstructionLimits::new(
+            | ProvisionalTopUpCanister
+            | TakeCanisterSnapshot
+            | LoadCanisterSnapshot
+            | ListCanisterSnapshots
+            | DeleteCanisterSnapshot
+            | UploadChunk
+            | StoredChunks
+            | ClearChunkStore
+            | FetchCanisterLogs => default_limits,
+            InstallCode | InstallChunkedCode => InstructionLimits::new(
                 dts,
                 config.max_instructions_per_install_code,
                 config.max_instructions_per_install_code_slice,
@@ -32,6 +32,7 @@ use ic_registry_routing_table::{CanisterIdRange, RoutingTable};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
     canister_state::execution_state::{self, WasmMetadata},
+    page_map::TestPageAllocatorFileDescriptorImpl,
     testing::{CanisterQueuesTesting, ReplicatedStateTesting},
     CanisterState, ExecutionState, ExportedFunctions, InputQueueType, Memory, ReplicatedState,
 };
@@ -469,6 +470,8 @@ impl SchedulerTest {
             state,
             Randomness::from([0; 32]),
             self.ecdsa_subnet_public_keys.clone(),
+            BTreeMap::new(),
+            BTreeMap::new(),
             self.round,
             round_type,
             self.registry_settings(),
@@ -525,6 +528,8 @@ impl SchedulerTest {
             long_running_canister_ids,
             self.registry_settings(),
             &BTreeMap::new(),
+            &BTreeMap::new(),
+            &BTreeMap::new(),
         )
     }
 
@@ -818,6 +823,7 @@ impl SchedulerTestBuilder {
             rate_limiting_of_heap_delta,
             rate_limiting_of_instructions,
             deterministic_time_slicing,
+            Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
         );
         SchedulerTest {
             state: Some(state),
@@ -1082,6 +1088,8 @@ impl TestWasmExecutorCore {
                 allocated_bytes: NumBytes::from(0),
                 allocated_message_bytes: NumBytes::from(0),
                 instance_stats: InstanceStats::default(),
+                canister_log: Default::default(),
+                profile: None,
             };
             self.schedule
                 .push((self.round, canister_id, instructions_to_execute));
@@ -1122,6 +1130,8 @@ impl TestWasmExecutorCore {
             allocated_message_bytes: NumBytes::from(0),
             num_instructions_left: instructions_left,
             instance_stats,
+            canister_log: Default::default(),
+            profile: None,
         };
         self.schedule
             .push((self.round, canister_id, instructions_to_execute));
@@ -3136,6 +3136,7 @@ fn consumed_cycles_http_outcalls_are_added_to_consumed_cycles_total() {
             }),
             context: transform_context,
         }),
+        replication: None,
     };
 
     // Create request to `HttpRequest` method.
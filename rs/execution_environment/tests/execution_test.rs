@@ -4,11 +4,16 @@ use ic_config::{
     subnet_config::{CyclesAccountManagerConfig, SubnetConfig},
 };
 use ic_ic00_types::{
-    CanisterIdRecord, CanisterSettingsArgsBuilder, CanisterStatusResultV2, Method, Payload, IC_00,
+    CanisterIdRecord, CanisterSettingsArgsBuilder, CanisterStatusResultV2, DerivationPath, Method,
+    Payload, SchnorrAlgorithm, SchnorrKeyId, SchnorrPublicKeyArgs, SchnorrPublicKeyResponse,
+    SignWithSchnorrArgs, SignWithSchnorrReply, VetKdCurve, VetKdDeriveEncryptedKeyArgs,
+    VetKdDeriveEncryptedKeyResult, VetKdKeyId, VetKdPublicKeyArgs, VetKdPublicKeyResult, IC_00,
 };
 use ic_registry_subnet_type::SubnetType;
-use ic_state_machine_tests::{ErrorCode, StateMachine, StateMachineConfig, UserError};
-use ic_types::{ingress::WasmResult, Cycles, NumBytes};
+use ic_state_machine_tests::{
+    ErrorCode, StateMachine, StateMachineBuilder, StateMachineConfig, UserError,
+};
+use ic_types::{crypto::AlgorithmId, ingress::WasmResult, Cycles, NumBytes, PrincipalId, Time};
 use ic_universal_canister::{call_args, wasm, UNIVERSAL_CANISTER_WASM};
 use std::{convert::TryInto, sync::Arc, time::Duration};
 
@@ -211,6 +216,38 @@ fn test_canister_upgrade_restart() {
     assert_eq!(to_int(val), 0);
 }
 
+/// Checks that a state machine started from an exported checkpoint sees the
+/// canister state at the time of the export, and that the exported checkpoint
+/// can be used more than once.
+#[test]
+fn test_canister_state_restored_from_exported_checkpoint() {
+    let env = StateMachine::new();
+    let canister_id = env.install_canister_wat(TEST_CANISTER, vec![], None);
+    env.execute_ingress(canister_id, "inc", vec![]).unwrap();
+
+    let checkpoint_dir = tempfile::tempdir().unwrap();
+    env.checkpoint_to(checkpoint_dir.path()).unwrap();
+    env.execute_ingress(canister_id, "inc", vec![]).unwrap();
+    let val = env.query(canister_id, "read", vec![]).unwrap().bytes();
+    assert_eq!(to_int(val), 2);
+
+    for _ in 0..2 {
+        let restored = StateMachineBuilder::new()
+            .with_checkpoint_dir(checkpoint_dir.path())
+            .unwrap()
+            .with_time(Time::try_from(env.time()).unwrap())
+            .with_nonce(env.nonce())
+            .build();
+        let val = restored.query(canister_id, "read", vec![]).unwrap().bytes();
+        assert_eq!(to_int(val), 1);
+        restored
+            .execute_ingress(canister_id, "inc", vec![])
+            .unwrap();
+        let val = restored.query(canister_id, "read", vec![]).unwrap().bytes();
+        assert_eq!(to_int(val), 2);
+    }
+}
+
 /// Tests that if you delete a canister, it stays deleted after a restart
 #[test]
 fn test_canister_delete_restart() {
@@ -1259,3 +1296,298 @@ fn canister_with_reserved_balance_is_not_frozen_too_early() {
         WasmResult::Reject(err) => unreachable!("Unexpected reject: {:?}", err),
     }
 }
+
+#[test]
+fn vetkd_requests_are_answered_by_state_machine() {
+    let key_id = VetKdKeyId {
+        curve: VetKdCurve::Bls12_381_G2,
+        name: "test_key".to_string(),
+    };
+    let env = StateMachineBuilder::new()
+        .with_vetkd_key(key_id.clone())
+        .build();
+    let canister_id = env
+        .install_canister_with_cycles(
+            UNIVERSAL_CANISTER_WASM.into(),
+            vec![],
+            None,
+            INITIAL_CYCLES_BALANCE,
+        )
+        .unwrap();
+
+    let public_key_args = VetKdPublicKeyArgs {
+        canister_id: None,
+        derivation_path: DerivationPath::new(vec![b"path".to_vec()]),
+        key_id: key_id.clone(),
+    };
+    let result = env
+        .execute_ingress(
+            canister_id,
+            "update",
+            wasm()
+                .call_simple(
+                    IC_00,
+                    Method::VetKdPublicKey,
+                    call_args().other_side(public_key_args.encode()),
+                )
+                .build(),
+        )
+        .unwrap();
+    let expected = env
+        .vetkd_public_key(&key_id, canister_id, vec![b"path".to_vec()])
+        .unwrap();
+    match result {
+        WasmResult::Reply(bytes) => {
+            assert_eq!(
+                VetKdPublicKeyResult::decode(&bytes).unwrap().public_key,
+                expected.public_key
+            )
+        }
+        WasmResult::Reject(err) => unreachable!("Unexpected reject: {:?}", err),
+    }
+
+    // The BLS12-381 G1 generator serves as the transport public key.
+    let derive_args = VetKdDeriveEncryptedKeyArgs {
+        derivation_id: b"id".to_vec(),
+        public_key_derivation_path: DerivationPath::new(vec![b"path".to_vec()]),
+        key_id,
+        encryption_public_key: hex::decode(
+            "97f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb",
+        )
+        .unwrap(),
+    };
+    let result = env
+        .execute_ingress(
+            canister_id,
+            "update",
+            wasm()
+                .call_simple(
+                    IC_00,
+                    Method::VetKdDeriveEncryptedKey,
+                    call_args().other_side(derive_args.encode()),
+                )
+                .build(),
+        )
+        .unwrap();
+    match result {
+        WasmResult::Reply(bytes) => {
+            let reply = VetKdDeriveEncryptedKeyResult::decode(&bytes).unwrap();
+            assert_eq!(reply.encrypted_key.len(), 192);
+        }
+        WasmResult::Reject(err) => unreachable!("Unexpected reject: {:?}", err),
+    }
+    assert!(env.vetkd_contexts().is_empty());
+}
+
+#[test]
+fn schnorr_requests_are_answered_by_state_machine() {
+    for (algorithm, algorithm_id) in [
+        (
+            SchnorrAlgorithm::Bip340Secp256k1,
+            AlgorithmId::ThresholdSchnorrBip340,
+        ),
+        (SchnorrAlgorithm::Ed25519, AlgorithmId::ThresholdEd25519),
+    ] {
+        let key_id = SchnorrKeyId {
+            algorithm,
+            name: "test_key".to_string(),
+        };
+        let env = StateMachineBuilder::new()
+            .with_schnorr_key(key_id.clone())
+            .build();
+        let canister_id = env
+            .install_canister_with_cycles(
+                UNIVERSAL_CANISTER_WASM.into(),
+                vec![],
+                None,
+                INITIAL_CYCLES_BALANCE,
+            )
+            .unwrap();
+
+        let public_key_args = SchnorrPublicKeyArgs {
+            canister_id: None,
+            derivation_path: DerivationPath::new(vec![b"path".to_vec()]),
+            key_id: key_id.clone(),
+        };
+        let result = env
+            .execute_ingress(
+                canister_id,
+                "update",
+                wasm()
+                    .call_simple(
+                        IC_00,
+                        Method::SchnorrPublicKey,
+                        call_args().other_side(public_key_args.encode()),
+                    )
+                    .build(),
+            )
+            .unwrap();
+        let public_key = match result {
+            WasmResult::Reply(bytes) => SchnorrPublicKeyResponse::decode(&bytes).unwrap(),
+            WasmResult::Reject(err) => unreachable!("Unexpected reject: {:?}", err),
+        };
+        let expected = env
+            .schnorr_public_key(&key_id, canister_id, vec![b"path".to_vec()])
+            .unwrap();
+        assert_eq!(public_key.public_key, expected.public_key);
+        assert_eq!(public_key.chain_code, expected.chain_code);
+
+        let sign_args = SignWithSchnorrArgs {
+            message: b"message".to_vec(),
+            derivation_path: DerivationPath::new(vec![b"path".to_vec()]),
+            key_id,
+        };
+        let result = env
+            .execute_ingress(
+                canister_id,
+                "update",
+                wasm()
+                    .call_simple(
+                        IC_00,
+                        Method::SignWithSchnorr,
+                        call_args().other_side(sign_args.encode()),
+                    )
+                    .build(),
+            )
+            .unwrap();
+        match result {
+            WasmResult::Reply(bytes) => {
+                let reply = SignWithSchnorrReply::decode(&bytes).unwrap();
+                assert!(ic_crypto_tschnorr::verify_tschnorr_signature(
+                    algorithm_id,
+                    &public_key.public_key,
+                    b"message",
+                    &reply.signature
+                ));
+            }
+            WasmResult::Reject(err) => unreachable!("Unexpected reject: {:?}", err),
+        }
+        assert!(env.sign_with_schnorr_contexts().is_empty());
+    }
+}
+
+#[test]
+fn sign_with_schnorr_with_unknown_key_is_rejected_by_state_machine() {
+    let env = StateMachine::new();
+    let canister_id = env
+        .install_canister_with_cycles(
+            UNIVERSAL_CANISTER_WASM.into(),
+            vec![],
+            None,
+            INITIAL_CYCLES_BALANCE,
+        )
+        .unwrap();
+    let key_id = SchnorrKeyId {
+        algorithm: SchnorrAlgorithm::Ed25519,
+        name: "unknown_key".to_string(),
+    };
+    let sign_args = SignWithSchnorrArgs {
+        message: vec![],
+        derivation_path: DerivationPath::new(vec![]),
+        key_id: key_id.clone(),
+    };
+    let result = env
+        .execute_ingress(
+            canister_id,
+            "update",
+            wasm()
+                .call_simple(
+                    IC_00,
+                    Method::SignWithSchnorr,
+                    call_args()
+                        .other_side(sign_args.encode())
+                        .on_reject(wasm().reject_message().reject()),
+                )
+                .build(),
+        )
+        .unwrap();
+    assert_eq!(
+        result,
+        WasmResult::Reject(format!(
+            "Subnet {} does not hold Schnorr key {}.",
+            PrincipalId::new_subnet_test_id(1),
+            key_id
+        ))
+    );
+}
+
+#[test]
+fn execution_profile_records_instructions_per_call_stack() {
+    let env = StateMachineBuilder::new().with_canister_profiling().build();
+    let canister_id = env.install_canister_wat(
+        r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func $inner (param $n i32) (result i32)
+                (local $i i32)
+                (loop $loop
+                    (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                    (br_if $loop (i32.lt_u (local.get $i) (local.get $n))))
+                (local.get $i))
+            (func $work
+                (drop (call $inner (i32.const 100)))
+                (drop (call $inner (i32.const 1000)))
+                (call $msg_reply))
+            (export "canister_update work" (func $work)))"#,
+        vec![],
+        None,
+    );
+
+    let (result, profile) =
+        env.execute_ingress_with_profile(PrincipalId::new_anonymous(), canister_id, "work", vec![]);
+    assert_eq!(result.unwrap(), WasmResult::Reply(vec![]));
+    let profile = profile.expect("no profile recorded");
+    assert_eq!(profile.entry_point(), "canister_update work");
+    // Both calls of `inner` share a call stack and dominate the profile.
+    let inner = *profile.stacks().get(&vec![2, 1]).unwrap();
+    let work = *profile.stacks().get(&vec![2]).unwrap();
+    assert!(inner > work, "inner: {}, work: {}", inner, work);
+
+    let folded = env.folded_stacks(canister_id, &profile);
+    assert!(folded.contains(&format!("canister_update work;work;inner {}\n", inner)));
+    assert!(folded.contains(&format!("canister_update work;work {}\n", work)));
+}
+
+#[test]
+fn execution_profile_is_not_corrupted_by_branches_out_of_the_function_body() {
+    let env = StateMachineBuilder::new().with_canister_profiling().build();
+    let canister_id = env.install_canister_wat(
+        r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            ;; Returns through `br 0` at depth 0, which targets the label of
+            ;; the function body.
+            (func $early (result i32)
+                (br 0 (i32.const 1)))
+            (func $work
+                (local $i i32)
+                (drop (call $early))
+                (loop $loop
+                    (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                    (br_if $loop (i32.lt_u (local.get $i) (i32.const 1000))))
+                (call $msg_reply))
+            (export "canister_update work" (func $work)))"#,
+        vec![],
+        None,
+    );
+
+    let (result, profile) =
+        env.execute_ingress_with_profile(PrincipalId::new_anonymous(), canister_id, "work", vec![]);
+    assert_eq!(result.unwrap(), WasmResult::Reply(vec![]));
+    let profile = profile.expect("no profile recorded");
+    // The loop after the call of `early` is attributed to `work` alone.
+    let early = *profile.stacks().get(&vec![2, 1]).unwrap();
+    let work = *profile.stacks().get(&vec![2]).unwrap();
+    assert!(work > early, "work: {}, early: {}", work, early);
+}
+
+#[test]
+fn execution_profile_is_not_recorded_by_default() {
+    let env = StateMachine::new();
+    let canister_id = env.install_canister_wat(TEST_CANISTER, vec![], None);
+    env.execute_ingress(canister_id, "inc", vec![]).unwrap();
+    assert_eq!(
+        env.execution_profile(canister_id, "canister_update inc"),
+        None
+    );
+}
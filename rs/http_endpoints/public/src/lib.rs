@@ -52,7 +52,7 @@ use ic_crypto_utils_threshold_sig_der::parse_threshold_sig_key_from_der;
 use ic_interfaces::{
     artifact_pool::UnvalidatedArtifact,
     consensus_pool::ConsensusPoolCache,
-    crypto::IngressSigVerifier,
+    crypto::{BasicSigner, IngressSigVerifier},
     execution_environment::{IngressFilterService, QueryExecutionService},
     ingress_pool::IngressPoolThrottler,
     time_source::TimeSource,
@@ -72,7 +72,8 @@ use ic_types::{
     malicious_flags::MaliciousFlags,
     messages::{
         Blob, Certificate, CertificateDelegation, HttpReadState, HttpReadStateContent,
-        HttpReadStateResponse, HttpRequestEnvelope, ReplicaHealthStatus, SignedIngress,
+        HttpReadStateResponse, HttpRequestEnvelope, QueryResponseHash, ReplicaHealthStatus,
+        SignedIngress,
     },
     time::expiry_time_from_now,
     CanisterId, NodeId, SubnetId,
@@ -244,6 +245,7 @@ pub fn start_server(
     registry_client: Arc<dyn RegistryClient>,
     tls_handshake: Arc<dyn TlsHandshake + Send + Sync>,
     ingress_verifier: Arc<dyn IngressSigVerifier + Send + Sync>,
+    query_signer: Arc<dyn BasicSigner<QueryResponseHash> + Send + Sync>,
     node_id: NodeId,
     subnet_id: SubnetId,
     nns_subnet_id: SubnetId,
@@ -278,7 +280,7 @@ pub fn start_server(
         metrics.clone(),
         node_id,
         subnet_id,
-        time_source,
+        Arc::clone(&time_source),
         Arc::clone(&registry_client),
         ValidatorExecutor::new(
             Arc::clone(&registry_client),
@@ -303,6 +305,9 @@ pub fn start_server(
             log.clone(),
         ),
         Arc::clone(&registry_client),
+        node_id,
+        query_signer,
+        time_source,
         query_execution_service,
     );
     let read_state_service = ReadStateService::new_service(
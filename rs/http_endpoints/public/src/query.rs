@@ -13,12 +13,19 @@ use futures_util::FutureExt;
 use http::Request;
 use hyper::{Body, Response, StatusCode};
 use ic_config::http_handler::Config;
-use ic_interfaces::execution_environment::QueryExecutionService;
+use ic_ic00_types::{FetchCanisterLogsRequest, Method as Ic00Method, Payload};
+use ic_interfaces::{
+    crypto::BasicSigner, execution_environment::QueryExecutionService, time_source::TimeSource,
+};
 use ic_interfaces_registry::RegistryClient;
 use ic_logger::{error, ReplicaLogger};
-use ic_types::messages::{
-    CertificateDelegation, HasCanisterId, HttpQueryContent, HttpRequest, HttpRequestEnvelope,
-    SignedRequestBytes, UserQuery,
+use ic_types::{
+    messages::{
+        Blob, CertificateDelegation, HasCanisterId, HttpQueryContent, HttpRequest,
+        HttpRequestEnvelope, HttpSignedQueryResponse, NodeSignature, QueryResponseHash,
+        SignedRequestBytes, UserQuery,
+    },
+    CanisterId, NodeId,
 };
 use std::convert::{Infallible, TryFrom};
 use std::future::Future;
@@ -35,6 +42,9 @@ pub(crate) struct QueryService {
     delegation_from_nns: Arc<RwLock<Option<CertificateDelegation>>>,
     validator_executor: ValidatorExecutor<UserQuery>,
     registry_client: Arc<dyn RegistryClient>,
+    node_id: NodeId,
+    query_signer: Arc<dyn BasicSigner<QueryResponseHash> + Send + Sync>,
+    time_source: Arc<dyn TimeSource>,
     query_execution_service: QueryExecutionService,
 }
 
@@ -48,6 +58,9 @@ impl QueryService {
         delegation_from_nns: Arc<RwLock<Option<CertificateDelegation>>>,
         validator_executor: ValidatorExecutor<UserQuery>,
         registry_client: Arc<dyn RegistryClient>,
+        node_id: NodeId,
+        query_signer: Arc<dyn BasicSigner<QueryResponseHash> + Send + Sync>,
+        time_source: Arc<dyn TimeSource>,
         query_execution_service: QueryExecutionService,
     ) -> EndpointService {
         let base_service = BoxCloneService::new(
@@ -62,6 +75,9 @@ impl QueryService {
                     delegation_from_nns,
                     validator_executor,
                     registry_client,
+                    node_id,
+                    query_signer,
+                    time_source,
                     query_execution_service,
                 }),
         );
@@ -138,13 +154,28 @@ impl Service<Request<Vec<u8>>> for QueryService {
             }
         };
 
-        // Reject requests where `canister_id` != `effective_canister_id`. In comparison to update
-        // requests we don't need to check for the mgmt canister since all mgmt canister calls are updated calls.
+        // Reject requests where `canister_id` != `effective_canister_id`. The only query
+        // method of the mgmt canister is `fetch_canister_logs`, for which the canister whose
+        // logs are fetched has to match the `effective_canister_id`.
         // This needs to be enforced because boundary nodes block access based on the `effective_canister_id`
         // in the url and the replica processes the request based on the `canister_id`.
         // If this is not enforced, a blocked canisters can still be accessed by specifying
         // a non-blocked `effective_canister_id` and a blocked `canister_id`.
-        let canister_id = request.content().canister_id();
+        let mut canister_id = request.content().canister_id();
+        if canister_id == CanisterId::ic_00()
+            && request.content().method_name == Ic00Method::FetchCanisterLogs.to_string()
+        {
+            match FetchCanisterLogsRequest::decode(&request.content().method_payload) {
+                Ok(args) => canister_id = args.get_canister_id(),
+                Err(e) => {
+                    let res = make_plaintext_response(
+                        StatusCode::BAD_REQUEST,
+                        format!("Malformed request: {}", e),
+                    );
+                    return Box::pin(async move { Ok(res) });
+                }
+            }
+        }
         if canister_id != effective_canister_id {
             let res = make_plaintext_response(
                 StatusCode::BAD_REQUEST,
@@ -184,6 +215,10 @@ impl Service<Request<Vec<u8>>> for QueryService {
         let registry_version = self.registry_client.get_latest_version();
         let validator_executor = self.validator_executor.clone();
         let response_body_size_bytes_metric = self.metrics.response_body_size_bytes.clone();
+        let log = self.log.clone();
+        let node_id = self.node_id;
+        let query_signer = Arc::clone(&self.query_signer);
+        let time_source = Arc::clone(&self.time_source);
         async move {
             let get_authorized_canisters_fut =
                 validator_executor.validate_request(request.clone(), registry_version);
@@ -200,17 +235,46 @@ impl Service<Request<Vec<u8>>> for QueryService {
                     return Ok(res);
                 }
             };
-            old_query_execution_service
+            let request_id = request.id();
+            let response = old_query_execution_service
                 .call((request.take_content(), delegation_from_nns))
-                .map(|result| {
-                    let v = result?;
-                    let (resp, body_size) = cbor_response(&v);
-                    response_body_size_bytes_metric
-                        .with_label_values(&[ApiReqType::Query.into()])
-                        .observe(body_size as f64);
-                    Ok(resp)
-                })
-                .await
+                .await?;
+
+            // Sign the response with the node signing key, so that clients can
+            // verify it against the node public keys in the certified state.
+            let timestamp = time_source.get_relative_time();
+            let response_hash = QueryResponseHash::new(&response, &request_id, timestamp);
+            let signature = match tokio::task::spawn_blocking(move || {
+                query_signer.sign_basic(&response_hash, node_id, registry_version)
+            })
+            .await
+            .map_err(|err| err.to_string())
+            .and_then(|result| result.map_err(|err| err.to_string()))
+            {
+                Ok(signature) => signature,
+                Err(err) => {
+                    error!(log, "Failed to sign the query response: {}", err);
+                    let res = make_plaintext_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Failed to sign the query response.".to_string(),
+                    );
+                    return Ok(res);
+                }
+            };
+
+            let signed_response = HttpSignedQueryResponse {
+                response,
+                signatures: vec![NodeSignature {
+                    timestamp: timestamp.as_nanos_since_unix_epoch(),
+                    signature: Blob(signature.get().0),
+                    identity: Blob(node_id.get().into_vec()),
+                }],
+            };
+            let (resp, body_size) = cbor_response(&signed_response);
+            response_body_size_bytes_metric
+                .with_label_values(&[ApiReqType::Query.into()])
+                .observe(body_size as f64);
+            Ok(resp)
         }
         .boxed()
     }
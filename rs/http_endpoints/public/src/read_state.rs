@@ -275,6 +275,9 @@ fn verify_paths(
             }
             [b"subnet"] => {}
             [b"subnet", _subnet_id, b"public_key" | b"canister_ranges"] => {}
+            [b"subnet", _subnet_id, b"node"] => {}
+            [b"subnet", _subnet_id, b"node", _node_id] => {}
+            [b"subnet", _subnet_id, b"node", _node_id, b"public_key"] => {}
             [b"request_status", request_id]
             | [b"request_status", request_id, b"status" | b"reply" | b"reject_code" | b"reject_message" | b"error_code"] =>
             {
@@ -407,11 +410,11 @@ mod test {
     use ic_crypto_tree_hash::{Digest, Label, MixedHashTree, Path};
     use ic_metrics::MetricsRegistry;
     use ic_registry_subnet_type::SubnetType;
-    use ic_replicated_state::{CanisterQueues, ReplicatedState, SystemMetadata};
+    use ic_replicated_state::{CanisterQueues, CanisterSnapshots, ReplicatedState, SystemMetadata};
     use ic_test_utilities::{
         mock_time,
         state::insert_dummy_canister,
-        types::ids::{canister_test_id, subnet_test_id, user_test_id},
+        types::ids::{canister_test_id, node_test_id, subnet_test_id, user_test_id},
     };
     use ic_validator::CanisterIdSet;
     use std::collections::BTreeMap;
@@ -533,6 +536,7 @@ mod test {
             BTreeMap::new(),
             metadata,
             CanisterQueues::default(),
+            CanisterSnapshots::default(),
         );
         assert_eq!(
             verify_paths(
@@ -567,6 +571,23 @@ mod test {
             ),
             Ok(())
         );
+        assert_eq!(
+            verify_paths(
+                &state,
+                &user_test_id(1),
+                &[Path::new(vec![
+                    Label::from("subnet"),
+                    subnet_id.get().into_vec().into(),
+                    Label::from("node"),
+                    node_test_id(1).get().into_vec().into(),
+                    Label::from("public_key")
+                ])],
+                &CanisterIdSet::all(),
+                canister_test_id(1),
+                &HttpHandlerMetrics::new(&MetricsRegistry::default())
+            ),
+            Ok(())
+        );
         assert!(verify_paths(
             &state,
             &user_test_id(1),
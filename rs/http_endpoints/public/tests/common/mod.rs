@@ -35,10 +35,12 @@ use ic_registry_keys::{
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_routing_table::{CanisterMigrations, RoutingTable};
 use ic_registry_subnet_type::SubnetType;
-use ic_replicated_state::{CanisterQueues, NetworkTopology, ReplicatedState, SystemMetadata};
+use ic_replicated_state::{
+    CanisterQueues, CanisterSnapshots, NetworkTopology, ReplicatedState, SystemMetadata,
+};
 use ic_test_utilities::{
     consensus::MockConsensusCache,
-    crypto::temp_crypto_component_with_fake_registry,
+    crypto::{temp_crypto_component_with_fake_registry, CryptoReturningOk},
     mock_time,
     state::ReplicatedStateBuilder,
     types::ids::{node_test_id, subnet_test_id},
@@ -201,6 +203,7 @@ pub fn default_get_latest_state() -> Labeled<Arc<ReplicatedState>> {
             BTreeMap::new(),
             metadata,
             CanisterQueues::default(),
+            CanisterSnapshots::default(),
         )),
     )
 }
@@ -427,6 +430,7 @@ pub fn start_http_endpoint(
         registry_client,
         tls_handshake,
         sig_verifier,
+        Arc::new(CryptoReturningOk::default()),
         node_id,
         subnet_id,
         nns_subnet_id,
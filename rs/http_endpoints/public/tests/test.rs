@@ -27,7 +27,11 @@ use ic_protobuf::registry::crypto::v1::{
     AlgorithmId as AlgorithmIdProto, PublicKey as PublicKeyProto,
 };
 use ic_registry_keys::make_crypto_threshold_signing_pubkey_key;
-use ic_test_utilities::{consensus::MockConsensusCache, mock_time, types::ids::subnet_test_id};
+use ic_test_utilities::{
+    consensus::MockConsensusCache,
+    mock_time,
+    types::ids::{node_test_id, subnet_test_id},
+};
 use ic_types::{
     batch::{BatchPayload, ValidationContext},
     consensus::{dkg::Dealings, Block, Payload, Rank},
@@ -291,6 +295,91 @@ fn test_unauthorized_query() {
     });
 }
 
+/// Test that query responses carry a signature of the node that executed the query.
+#[test]
+fn test_query_response_is_signed_by_node() {
+    let rt = Runtime::new().unwrap();
+    let addr = get_free_localhost_socket_addr();
+    let config = Config {
+        listen_addr: addr,
+        ..Default::default()
+    };
+
+    let mock_state_manager = basic_state_manager_mock();
+    let mock_consensus_cache = basic_consensus_pool_cache();
+    let mock_registry_client = basic_registry_client();
+
+    let (_, _, mut query_handler) = start_http_endpoint(
+        rt.handle().clone(),
+        config,
+        Arc::new(mock_state_manager),
+        Arc::new(mock_consensus_cache),
+        Arc::new(mock_registry_client),
+        Arc::new(Pprof),
+    );
+
+    let agent = Agent::builder()
+        .with_transport(ReqwestHttpReplicaV2Transport::create(format!("http://{}", addr)).unwrap())
+        .build()
+        .unwrap();
+
+    rt.spawn(async move {
+        loop {
+            let (_, resp) = query_handler.next_request().await.unwrap();
+            resp.send_response(HttpQueryResponse::Replied {
+                reply: HttpQueryResponseReply {
+                    arg: Blob("success".into()),
+                },
+            })
+        }
+    });
+
+    let canister = Principal::from_text("223xb-saaaa-aaaaf-arlqa-cai").unwrap();
+    let query = QueryBuilder::new(&agent, canister, "test".to_string())
+        .with_effective_canister_id(canister)
+        .with_arg(Vec::new())
+        .sign()
+        .unwrap();
+
+    let response = rt.block_on(async {
+        wait_for_status_healthy(&agent).await.unwrap();
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri(format!(
+                "http://{}/api/v2/canister/{}/query",
+                addr, canister
+            ))
+            .header("Content-Type", "application/cbor")
+            .body(Body::from(query.signed_query))
+            .expect("request builder");
+        let response = Client::new().request(req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        hyper::body::to_bytes(response.into_body()).await.unwrap()
+    });
+
+    let response: serde_cbor::Value = serde_cbor::from_slice(&response).unwrap();
+    let serde_cbor::Value::Map(response) = response else {
+        panic!("Expected a map, got {:?}", response);
+    };
+    let text = |text: &str| serde_cbor::Value::Text(text.to_string());
+    assert_eq!(response.get(&text("status")), Some(&text("replied")));
+    match response.get(&text("signatures")) {
+        Some(serde_cbor::Value::Array(signatures)) => {
+            assert_eq!(signatures.len(), 1);
+            let serde_cbor::Value::Map(signature) = &signatures[0] else {
+                panic!("Expected a map, got {:?}", signatures[0]);
+            };
+            assert_eq!(
+                signature.get(&text("identity")),
+                Some(&serde_cbor::Value::Bytes(node_test_id(1).get().into_vec()))
+            );
+            assert!(signature.contains_key(&text("timestamp")));
+            assert!(signature.contains_key(&text("signature")));
+        }
+        other => panic!("Expected an array of signatures, got {:?}", other),
+    }
+}
+
 // Test that that http endpoint rejects calls with mismatch between canister id an effective canister id.
 #[test]
 fn test_unauthorized_call() {
@@ -102,6 +102,9 @@ impl CanisterHttpService for CanisterHttp {
                 HttpMethod::Get => Ok(Method::GET),
                 HttpMethod::Post => Ok(Method::POST),
                 HttpMethod::Head => Ok(Method::HEAD),
+                HttpMethod::Put => Ok(Method::PUT),
+                HttpMethod::Delete => Ok(Method::DELETE),
+                HttpMethod::Patch => Ok(Method::PATCH),
                 _ => {
                     self.metrics
                         .request_errors
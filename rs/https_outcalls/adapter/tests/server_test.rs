@@ -85,6 +85,11 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             .and(warp::body::json())
             .map(|req: u64| Response::builder().body(req.to_string()));
 
+        let basic_put = warp::put()
+            .and(warp::path("put"))
+            .and(warp::body::json())
+            .map(|req: u64| Response::builder().body(req.to_string()));
+
         let basic_get = warp::get()
             .and(warp::path("get"))
             .map(|| warp::reply::json(&"Hello"));
@@ -116,6 +121,7 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             .map(|| warp::reply::reply());
 
         let routes = basic_post
+            .or(basic_put)
             .or(basic_get)
             .or(basic_head)
             .or(get_response_size)
@@ -206,6 +212,30 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
         assert_eq!(String::from_utf8_lossy(&http_response.content), "420");
     }
 
+    #[tokio::test]
+    async fn test_canister_http_server_put() {
+        let server_config = Config {
+            ..Default::default()
+        };
+
+        let url = start_server(CERT_INIT.get_or_init(generate_certs));
+        let mut client = spawn_grpc_server(server_config);
+
+        let request = tonic::Request::new(CanisterHttpSendRequest {
+            url: format!("https://{}/put", &url),
+            headers: Vec::new(),
+            method: HttpMethod::Put as i32,
+            body: "420".to_string().as_bytes().to_vec(),
+            max_response_size_bytes: 512,
+            socks_proxy_allowed: false,
+        });
+
+        let response = client.canister_http_send(request).await;
+        let http_response = response.unwrap().into_inner();
+        assert_eq!(http_response.status, StatusCode::OK.as_u16() as u32);
+        assert_eq!(String::from_utf8_lossy(&http_response.content), "420");
+    }
+
     #[tokio::test]
     async fn test_canister_http_server_head() {
         let server_config = Config {
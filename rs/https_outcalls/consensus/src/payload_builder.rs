@@ -12,7 +12,7 @@ use ic_consensus_utils::{
 };
 use ic_error_types::RejectCode;
 use ic_interfaces::{
-    batch_payload::{BatchPayloadBuilder, IntoMessages, PastPayload},
+    batch_payload::{BatchPayloadBuilder, IntoMessages, PastPayload, ProposalContext},
     canister_http::{
         CanisterHttpPayloadValidationError, CanisterHttpPermanentValidationError, CanisterHttpPool,
         CanisterHttpTransientValidationError,
@@ -32,7 +32,7 @@ use ic_types::{
     canister_http::{
         CanisterHttpResponse, CanisterHttpResponseContent, CanisterHttpResponseDivergence,
         CanisterHttpResponseMetadata, CanisterHttpResponseProof, CanisterHttpResponseWithConsensus,
-        CANISTER_HTTP_MAX_RESPONSES_PER_BLOCK, CANISTER_HTTP_TIMEOUT_INTERVAL,
+        Replication, CANISTER_HTTP_MAX_RESPONSES_PER_BLOCK, CANISTER_HTTP_TIMEOUT_INTERVAL,
     },
     consensus::Committee,
     crypto::Signed,
@@ -42,7 +42,7 @@ use ic_types::{
     CanisterId, CountBytes, Cycles, Height, NodeId, NumBytes, RegistryVersion, SubnetId,
 };
 use std::{
-    collections::{BTreeSet, HashSet},
+    collections::{BTreeMap, BTreeSet, HashSet},
     mem::size_of,
     sync::{Arc, RwLock},
 };
@@ -211,10 +211,11 @@ impl CanisterHttpPayloadBuilderImpl {
         // timed out metadata share and we would pick it up to generate a
         // time out response. Instead, we scan the state metadata for timed
         // out requests and generate time out responses based on that
-        if let Ok(state) = self
+        let state = self
             .state_reader
             .get_state_at(validation_context.certified_height)
-        {
+            .ok();
+        if let Some(state) = &state {
             // Iterate over all outstanding canister http requests
             for (callback_id, request) in state
                 .get_ref()
@@ -239,6 +240,23 @@ impl CanisterHttpPayloadBuilderImpl {
             }
         }
 
+        // Non-replicated requests only need the share of the designated node.
+        let designated_nodes: BTreeMap<CallbackId, NodeId> = state
+            .iter()
+            .flat_map(|state| {
+                state
+                    .get_ref()
+                    .metadata
+                    .subnet_call_context_manager
+                    .canister_http_request_contexts
+                    .iter()
+            })
+            .filter_map(|(callback_id, context)| match context.replication {
+                Replication::NonReplicated(node_id) => Some((*callback_id, node_id)),
+                Replication::FullyReplicated => None,
+            })
+            .collect();
+
         // Since aggegating the signatures is expensive, we don't want to do the
         // size checks after aggregation. Also we don't want to hold the lock on
         // the pool while aggregating. Therefore, we pick the candidates for the
@@ -276,7 +294,26 @@ impl CanisterHttpPayloadBuilderImpl {
 
             let candidates_and_divergences = response_candidates_by_callback_id
                 .into_iter()
-                .filter_map(|(_, grouped_shares)| {
+                .filter_map(|(callback_id, grouped_shares)| {
+                    if let Some(designated_node) = designated_nodes.get(&callback_id) {
+                        // For a non-replicated request, the response signed by
+                        // the designated node is included on its own.
+                        unique_responses_count += grouped_shares.len() as i64;
+                        return grouped_shares.iter().find_map(|(metadata, shares)| {
+                            let share = shares
+                                .iter()
+                                .find(|share| share.signature.signer == *designated_node)?;
+                            pool_access
+                                .get_response_content_by_hash(&metadata.content_hash)
+                                .map(|content| {
+                                    CandidateOrDivergence::Candidate((
+                                        metadata.clone(),
+                                        BTreeSet::from([share.signature.clone()]),
+                                        content,
+                                    ))
+                                })
+                        });
+                    }
                     if let Some((metadata, shares)) = grouped_shares.iter().find(|(_, shares)| {
                         unique_responses_count += 1;
                         let signers: BTreeSet<_> =
@@ -504,7 +541,21 @@ impl CanisterHttpPayloadBuilderImpl {
                     },
                 ));
             }
-            if valid_signers.len() < threshold {
+            if let Some(Replication::NonReplicated(designated_node)) = http_contexts
+                .get(&response.content.id)
+                .map(|context| &context.replication)
+            {
+                // A response to a non-replicated request only needs to be
+                // signed by the designated node.
+                if !valid_signers.contains(designated_node) {
+                    return Err(CanisterHttpPayloadValidationError::Permanent(
+                        CanisterHttpPermanentValidationError::NotSignedByDesignatedNode {
+                            designated_node: *designated_node,
+                            signers: valid_signers,
+                        },
+                    ));
+                }
+            } else if valid_signers.len() < threshold {
                 return Err(CanisterHttpPayloadValidationError::Permanent(
                     CanisterHttpPermanentValidationError::NotEnoughSigners {
                         committee,
@@ -617,7 +668,7 @@ impl BatchPayloadBuilder for CanisterHttpPayloadBuilderImpl {
         height: Height,
         payload: &[u8],
         past_payloads: &[PastPayload],
-        context: &ValidationContext,
+        proposal_context: &ProposalContext,
     ) -> Result<(), PayloadValidationError> {
         let _time = self
             .metrics
@@ -647,16 +698,21 @@ impl BatchPayloadBuilder for CanisterHttpPayloadBuilderImpl {
                 CanisterHttpPermanentValidationError::DecodeError(e),
             ))
         })?;
-        self.validate_canister_http_payload_impl(height, &payload, context, delivered_ids)
-            .map(|_| ())
-            .map_err(|err| match err {
-                ValidationError::Permanent(err) => ValidationError::Permanent(
-                    PayloadPermanentError::CanisterHttpPayloadValidationError(err),
-                ),
-                ValidationError::Transient(err) => ValidationError::Transient(
-                    PayloadTransientError::CanisterHttpPayloadValidationError(err),
-                ),
-            })
+        self.validate_canister_http_payload_impl(
+            height,
+            &payload,
+            proposal_context.validation_context,
+            delivered_ids,
+        )
+        .map(|_| ())
+        .map_err(|err| match err {
+            ValidationError::Permanent(err) => ValidationError::Permanent(
+                PayloadPermanentError::CanisterHttpPayloadValidationError(err),
+            ),
+            ValidationError::Transient(err) => ValidationError::Transient(
+                PayloadTransientError::CanisterHttpPayloadValidationError(err),
+            ),
+        })
     }
 }
 
@@ -10,7 +10,7 @@ use ic_artifact_pool::canister_http_pool::CanisterHttpPoolImpl;
 use ic_consensus_mocks::{dependencies_with_subnet_params, Dependencies};
 use ic_interfaces::{
     artifact_pool::{MutablePool, UnvalidatedArtifact},
-    batch_payload::{BatchPayloadBuilder, PastPayload},
+    batch_payload::{BatchPayloadBuilder, PastPayload, ProposalContext},
     canister_http::{
         CanisterHttpChangeAction, CanisterHttpChangeSet, CanisterHttpPermanentValidationError,
         CanisterHttpTransientValidationError,
@@ -37,7 +37,7 @@ use ic_types::{
     canister_http::{
         CanisterHttpMethod, CanisterHttpRequestContext, CanisterHttpResponse,
         CanisterHttpResponseContent, CanisterHttpResponseDivergence, CanisterHttpResponseMetadata,
-        CanisterHttpResponseShare, CanisterHttpResponseWithConsensus,
+        CanisterHttpResponseShare, CanisterHttpResponseWithConsensus, Replication,
         CANISTER_HTTP_MAX_RESPONSES_PER_BLOCK, CANISTER_HTTP_TIMEOUT_INTERVAL,
     },
     consensus::get_faults_tolerated,
@@ -93,7 +93,7 @@ fn single_request_test() {
             assert_eq!(parsed_payload.responses[0].content, response);
 
             assert!(payload_builder
-                .validate_payload(Height::new(1), &payload, &[], &context)
+                .validate_payload(Height::new(1), &payload, &[], &proposal_context(&context))
                 .is_ok());
         });
 
@@ -229,7 +229,7 @@ fn multiple_payload_test() {
                     Height::new(1),
                     &payload,
                     &past_payloads,
-                    &validation_context,
+                    &proposal_context(&validation_context),
                 )
                 .unwrap();
 
@@ -324,6 +324,7 @@ fn timeout_priority() {
                     transform: None,
                     // this is the important one
                     time: mock_time(),
+                    replication: Replication::FullyReplicated,
                 };
                 init_state
                     .metadata
@@ -440,6 +441,95 @@ fn divergence_response_inclusion_test() {
     });
 }
 
+/// Check that the response to a non-replicated request is included with the
+/// share of the designated node alone, and that a proof without it is rejected.
+#[test]
+fn non_replicated_request_test() {
+    let designated_node = node_test_id(2);
+    let mut init_state = ic_test_utilities::state::get_initial_state(0, 0);
+    init_state
+        .metadata
+        .subnet_call_context_manager
+        .canister_http_request_contexts
+        .insert(
+            CallbackId::new(0),
+            CanisterHttpRequestContext {
+                request: RequestBuilder::default().build(),
+                url: String::new(),
+                max_response_bytes: None,
+                headers: vec![],
+                body: None,
+                http_method: CanisterHttpMethod::POST,
+                transform: None,
+                time: mock_time(),
+                replication: Replication::NonReplicated(designated_node),
+            },
+        );
+    let init_state = Arc::new(init_state);
+    let context = default_validation_context();
+
+    test_config_with_http_feature(4, |mut payload_builder, canister_http_pool| {
+        let state_manager = Arc::new(RefMockStateManager::default());
+        state_manager
+            .get_mut()
+            .expect_get_state_at()
+            .return_const(Ok(ic_interfaces_state_manager::Labeled::new(
+                Height::new(0),
+                init_state.clone(),
+            )));
+        payload_builder.state_reader = state_manager;
+
+        let (response, metadata) = test_response_and_metadata(0);
+        {
+            // Only the designated node performs the request
+            let mut pool_access = canister_http_pool.write().unwrap();
+            add_own_share_to_pool(
+                pool_access.deref_mut(),
+                &metadata_to_share(2, &metadata),
+                &response,
+            );
+        }
+
+        let payload = payload_builder.build_payload(
+            Height::new(1),
+            NumBytes::new(4 * 1024 * 1024),
+            &[],
+            &context,
+        );
+
+        let parsed_payload = bytes_to_payload(&payload).expect("Failed to parse the payload");
+        assert_eq!(parsed_payload.num_responses(), 1);
+        assert_eq!(parsed_payload.responses[0].content, response);
+        assert!(payload_builder
+            .validate_payload(Height::new(1), &payload, &[], &proposal_context(&context))
+            .is_ok());
+
+        // A proof that is not signed by the designated node must not validate
+        let payload = CanisterHttpPayload {
+            responses: vec![response_and_metadata_to_proof(&response, &metadata)],
+            timeouts: vec![],
+            divergence_responses: vec![],
+        };
+        let payload = payload_to_bytes(&payload, NumBytes::new(4 * 1024 * 1024));
+        match payload_builder.validate_payload(
+            Height::new(1),
+            &payload,
+            &[],
+            &proposal_context(&context),
+        ) {
+            Err(ValidationError::Permanent(
+                PayloadPermanentError::CanisterHttpPayloadValidationError(
+                    CanisterHttpPermanentValidationError::NotSignedByDesignatedNode {
+                        designated_node: node,
+                        ..
+                    },
+                ),
+            )) if node == designated_node => (),
+            x => panic!("Expected NotSignedByDesignatedNode, got {:?}", x),
+        }
+    });
+}
+
 /// Submit a very large number of valid responses, then check that the
 /// payload builder does not process all of them but only CANISTER_HTTP_RESPONSES_PER_BLOCK
 #[test]
@@ -476,7 +566,12 @@ fn max_responses() {
 
         //  Make sure the response is not contained in the payload
         payload_builder
-            .validate_payload(Height::new(1), &payload, &[], &validation_context)
+            .validate_payload(
+                Height::new(1),
+                &payload,
+                &[],
+                &proposal_context(&validation_context),
+            )
             .unwrap();
     })
 }
@@ -640,7 +735,7 @@ fn duplicate_validation() {
             Height::from(1),
             &payload,
             &past_payloads,
-            &default_validation_context(),
+            &proposal_context(&default_validation_context()),
         );
 
         match validation_result {
@@ -687,7 +782,7 @@ fn divergence_response_validation_test() {
                 Height::from(1),
                 &payload,
                 &[],
-                &default_validation_context(),
+                &proposal_context(&default_validation_context()),
             );
 
             assert!(validation_result.is_ok());
@@ -707,7 +802,7 @@ fn divergence_response_validation_test() {
                 Height::from(1),
                 &payload,
                 &[],
-                &default_validation_context(),
+                &proposal_context(&default_validation_context()),
             );
 
             match validation_result {
@@ -744,7 +839,7 @@ fn divergence_response_validation_test() {
                 Height::from(1),
                 &payload,
                 &[],
-                &default_validation_context(),
+                &proposal_context(&default_validation_context()),
             );
 
             match validation_result {
@@ -951,6 +1046,14 @@ pub(crate) fn default_validation_context() -> ValidationContext {
     }
 }
 
+/// Wraps the validation context into the context of a block proposed by node 0
+pub(crate) fn proposal_context(validation_context: &ValidationContext) -> ProposalContext<'_> {
+    ProposalContext {
+        proposer: node_test_id(0),
+        validation_context,
+    }
+}
+
 /// Mocks up a test environment and test response and metadata. Lets the caller modify them and
 /// then runs validation on it and returns the validation result.
 ///
@@ -974,6 +1077,11 @@ where
         };
 
         let payload = payload_to_bytes(&payload, NumBytes::new(4 * 1024 * 1024));
-        payload_builder.validate_payload(Height::from(1), &payload, &[], validation_context)
+        payload_builder.validate_payload(
+            Height::from(1),
+            &payload,
+            &[],
+            &proposal_context(validation_context),
+        )
     })
 }
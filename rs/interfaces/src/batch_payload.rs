@@ -1,7 +1,7 @@
 use crate::{consensus::PayloadValidationError, validation::ValidationResult};
 use ic_base_types::NumBytes;
 use ic_types::{
-    batch::ValidationContext, consensus::BlockPayload, crypto::CryptoHashOf, Height, Time,
+    batch::ValidationContext, consensus::BlockPayload, crypto::CryptoHashOf, Height, NodeId, Time,
 };
 use prost::{bytes::BufMut, DecodeError, Message};
 
@@ -28,6 +28,14 @@ pub struct PastPayload<'a> {
     pub payload: &'a [u8],
 }
 
+/// The context of the block proposal that a payload is validated in.
+pub struct ProposalContext<'a> {
+    /// The node that proposed the block, i.e. the block maker.
+    pub proposer: NodeId,
+    /// The [`ValidationContext`] of the block.
+    pub validation_context: &'a ValidationContext,
+}
+
 /// Indicates that this component can build batch payloads.
 ///
 /// A batch payload has the following properties:
@@ -65,7 +73,7 @@ pub trait BatchPayloadBuilder: Send + Sync {
     /// - `payload`: The payload to validate
     /// - `past_payloads`: A collection of past payloads. Allows the payload builder
     ///     to deduplicate messages
-    /// - `context`: [`ValidationContext`] under which to validate the payload
+    /// - `proposal_context`: [`ProposalContext`] of the block that contains the payload
     ///
     /// # Returns
     ///
@@ -76,7 +84,7 @@ pub trait BatchPayloadBuilder: Send + Sync {
         height: Height,
         payload: &[u8],
         past_payloads: &[PastPayload],
-        context: &ValidationContext,
+        proposal_context: &ProposalContext,
     ) -> ValidationResult<PayloadValidationError>;
 }
 
@@ -4,12 +4,14 @@ mod errors;
 pub use errors::{CanisterOutOfCyclesError, HypervisorError, TrapCode};
 use ic_base_types::NumBytes;
 use ic_error_types::UserError;
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, SchnorrKeyId, VetKdKeyId};
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
 use ic_sys::{PageBytes, PageIndex};
 use ic_types::{
-    crypto::canister_threshold_sig::MasterEcdsaPublicKey,
+    canister_log::CanisterLog,
+    crypto::{canister_threshold_sig::MasterEcdsaPublicKey, threshold_sig::ThresholdSigPublicKey},
+    execution_profile::ExecutionProfile,
     ingress::{IngressStatus, WasmResult},
     messages::{
         AnonymousQuery, AnonymousQueryResponse, CertificateDelegation, HttpQueryResponse,
@@ -468,6 +470,11 @@ pub trait SystemApi {
     /// Returns the subnet type the replica runs on.
     fn subnet_type(&self) -> SubnetType;
 
+    /// Saves the bytes at `heap[src..src+size]` as a record in the canister
+    /// log. Invalid memory ranges are logged as an error message instead of
+    /// trapping.
+    fn save_log_message(&mut self, src: u32, size: u32, heap: &[u8]);
+
     /// Returns the message instruction limit, which is the total instruction
     /// limit for all slices combined.
     fn message_instruction_limit(&self) -> NumInstructions;
@@ -811,6 +818,16 @@ pub trait SystemApi {
         element_size: u64,
     ) -> HypervisorResult<()>;
 
+    /// This system call is not part of the public spec. It's called after a
+    /// native `memory.grow` has been called to check whether the new size of
+    /// the Wasm memory is within the `wasm_memory_limit` of the canister and
+    /// whether there's enough available memory left.
+    fn try_grow_wasm_memory(
+        &mut self,
+        native_memory_grow_res: i64,
+        additional_wasm_pages: u64,
+    ) -> HypervisorResult<()>;
+
     /// Attempts to allocate memory before calling stable grow. Will also check
     /// that the current size if valid for the stable memory API being used and
     /// the resulting size doesn't exceed the maximum stable memory limit.
@@ -1042,6 +1059,8 @@ pub trait Scheduler: Send {
         state: Self::State,
         randomness: Randomness,
         ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+        schnorr_subnet_public_keys: BTreeMap<SchnorrKeyId, MasterEcdsaPublicKey>,
+        vetkd_subnet_public_keys: BTreeMap<VetKdKeyId, ThresholdSigPublicKey>,
         current_round: ExecutionRound,
         current_round_type: ExecutionRoundType,
         registry_settings: &RegistryExecutionSettings,
@@ -1055,6 +1074,12 @@ pub struct WasmExecutionOutput {
     pub allocated_bytes: NumBytes,
     pub allocated_message_bytes: NumBytes,
     pub instance_stats: InstanceStats,
+    /// Log records written during the execution, including the message of an
+    /// explicit trap.
+    pub canister_log: CanisterLog,
+    /// The instruction profile of the execution if canister profiling is
+    /// enabled.
+    pub profile: Option<ExecutionProfile>,
 }
 
 impl fmt::Display for WasmExecutionOutput {
@@ -140,6 +140,12 @@ pub enum HypervisorError {
         available: Cycles,
         threshold: Cycles,
     },
+    /// The canister attempted to grow its Wasm memory beyond the
+    /// `wasm_memory_limit` canister setting.
+    WasmMemoryLimitExceeded {
+        bytes: NumBytes,
+        limit: NumBytes,
+    },
 }
 
 impl From<WasmInstrumentationError> for HypervisorError {
@@ -333,6 +339,15 @@ impl HypervisorError {
                      bytes,
                      threshold - available)
             ),
+            Self::WasmMemoryLimitExceeded { bytes, limit } => UserError::new(
+                E::CanisterWasmMemoryLimitExceeded,
+                format!(
+                    "Canister {} exceeded its Wasm memory limit of {} bytes by attempting \
+                     to grow its Wasm memory to {} bytes. The limit can be changed via the \
+                     `wasm_memory_limit` canister setting.",
+                    canister_id, limit.get(), bytes.get()
+                ),
+            ),
         }
     }
 
@@ -366,6 +381,7 @@ impl HypervisorError {
             HypervisorError::InsufficientCyclesInMemoryGrow { .. } => {
                 "InsufficientCyclesInMemoryGrow"
             }
+            HypervisorError::WasmMemoryLimitExceeded { .. } => "WasmMemoryLimitExceeded",
         }
     }
 }
@@ -0,0 +1,37 @@
+//! Query statistics related public interfaces.
+use crate::validation::ValidationError;
+use ic_protobuf::proxy::ProxyDecodeError;
+use ic_types::{batch::QueryStatsEpoch, CanisterId, Height, NodeId};
+
+/// A permanent error that can occur during validation of a `QueryStatsPayload`
+#[derive(Debug)]
+pub enum QueryStatsPermanentValidationError {
+    /// The payload could not be deserialized
+    DeserializationFailed(ProxyDecodeError),
+    /// The payload reports statistics for an epoch that is not the one
+    /// expected under the validation context
+    EpochMismatch {
+        expected: Option<QueryStatsEpoch>,
+        received: QueryStatsEpoch,
+    },
+    /// The payload contains statistics for a canister that have already
+    /// been reported by the same node in this epoch
+    DuplicateCanisterId(CanisterId),
+    /// The payload claims to be proposed by a node other than the maker of
+    /// the block that contains it
+    InvalidProposer { expected: NodeId, received: NodeId },
+    /// The proposer of the payload is not a member of the subnet in the
+    /// certified state
+    ProposerNotSubnetMember(NodeId),
+}
+
+/// A transient error that can occur during validation of a `QueryStatsPayload`
+#[derive(Debug)]
+pub enum QueryStatsTransientValidationError {
+    /// The state at the certified height of the validation context was not
+    /// available at the time of validation
+    StateUnavailable(Height),
+}
+
+pub type QueryStatsPayloadValidationError =
+    ValidationError<QueryStatsPermanentValidationError, QueryStatsTransientValidationError>;
@@ -744,6 +744,8 @@ fn try_read_registry_succeeds_with_fully_specified_registry_records() {
             messages: BatchMessages::default(),
             randomness: Randomness::new([123; 32]),
             ecdsa_subnet_public_keys: BTreeMap::default(),
+            schnorr_subnet_public_keys: BTreeMap::default(),
+            vetkd_subnet_public_keys: BTreeMap::default(),
             registry_version: fixture.registry.get_latest_version(),
             time: Time::from_nanos_since_unix_epoch(0),
             consensus_responses: Vec::new(),
@@ -3,7 +3,7 @@ use crate::{
     routing::demux::MockDemux, routing::stream_builder::MockStreamBuilder,
     state_machine::StateMachineImpl,
 };
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, SchnorrKeyId, VetKdKeyId};
 use ic_interfaces::execution_environment::Scheduler;
 use ic_interfaces_state_manager::StateManager;
 use ic_metrics::MetricsRegistry;
@@ -17,7 +17,10 @@ use ic_test_utilities::{
 use ic_test_utilities_execution_environment::test_registry_settings;
 use ic_test_utilities_logger::with_test_replica_logger;
 use ic_types::messages::SignedIngress;
-use ic_types::{batch::BatchMessages, crypto::canister_threshold_sig::MasterEcdsaPublicKey};
+use ic_types::{
+    batch::BatchMessages,
+    crypto::{canister_threshold_sig::MasterEcdsaPublicKey, threshold_sig::ThresholdSigPublicKey},
+};
 use ic_types::{Height, PrincipalId, SubnetId};
 use mockall::{mock, predicate::*, Sequence};
 use std::collections::{BTreeMap, BTreeSet};
@@ -31,6 +34,8 @@ mock! {
             state: ic_replicated_state::ReplicatedState,
             randomness: ic_types::Randomness,
             ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+            schnorr_subnet_public_keys: BTreeMap<SchnorrKeyId, MasterEcdsaPublicKey>,
+            vetkd_subnet_public_keys: BTreeMap<VetKdKeyId, ThresholdSigPublicKey>,
             current_round: ExecutionRound,
             current_round_type: ExecutionRoundType,
             registry_settings: &RegistryExecutionSettings,
@@ -84,11 +89,13 @@ fn test_fixture(provided_batch: &Batch) -> StateMachineTestFixture {
             always(),
             eq(provided_batch.randomness),
             eq(provided_batch.ecdsa_subnet_public_keys.clone()),
+            eq(provided_batch.schnorr_subnet_public_keys.clone()),
+            eq(provided_batch.vetkd_subnet_public_keys.clone()),
             eq(round),
             eq(round_type),
             eq(test_registry_settings()),
         )
-        .returning(|state, _, _, _, _, _| state);
+        .returning(|state, _, _, _, _, _, _, _| state);
 
     let mut stream_builder = Box::new(MockStreamBuilder::new());
     stream_builder
@@ -0,0 +1,353 @@
+//! The public HTTP interface of a PocketIC instance: the CBOR-encoded
+//! `/api/v2` endpoints of the IC interface specification, so that agents can
+//! talk to an instance as if it was a replica.
+//!
+//! Requests are validated like on a replica, including their signatures and
+//! ingress expiry, against the root key and the current time of the instance.
+//! Update calls are executed to completion before the call is acknowledged,
+//! so their status can be read with the next `read_state` request.
+
+use crate::instance::Instance;
+use axum::http::StatusCode;
+use ic_crypto::threshold_sig_public_key_to_der;
+use ic_crypto_tree_hash::Path;
+use ic_error_types::{ErrorCode, RejectCode};
+use ic_replicated_state::{canister_state::execution_state::CustomSectionType, ReplicatedState};
+use ic_state_machine_tests::WasmResult;
+use ic_types::{
+    messages::{
+        Blob, HttpQueryContent, HttpQueryResponse, HttpQueryResponseReply, HttpReadStateContent,
+        HttpReadStateResponse, HttpRequest, HttpRequestContent, HttpRequestEnvelope,
+        HttpRequestError, HttpSignedQueryResponse, HttpStatusResponse, MessageId, ReadState,
+        ReplicaHealthStatus, SignedIngress, SignedRequestBytes, UserQuery,
+        EXPECTED_MESSAGE_ID_LENGTH,
+    },
+    CanisterId, Time, UserId,
+};
+use ic_validator_ingress_message::{HttpRequestVerifier, IngressMessageVerifier, TimeProvider};
+use pocket_ic::RawEffectivePrincipal;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+/// The version of the IC interface specification that is implemented.
+const IC_API_VERSION: &str = "0.18.0";
+
+/// An HTTP status code and a plain text message explaining the error.
+pub type HttpError = (StatusCode, String);
+
+/// Handles `/api/v2/status`. Returns the CBOR-encoded status, which includes
+/// the root key of the instance.
+pub fn status(instance: &Instance) -> Vec<u8> {
+    let root_key = threshold_sig_public_key_to_der(instance.root_subnet().root_key())
+        .expect("Failed to encode the root key");
+    into_cbor(&HttpStatusResponse {
+        ic_api_version: IC_API_VERSION.to_string(),
+        root_key: Some(Blob(root_key)),
+        impl_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        impl_hash: None,
+        replica_health_status: Some(ReplicaHealthStatus::Healthy),
+        certified_height: None,
+    })
+}
+
+/// Handles `/api/v2/canister/<effective_canister_id>/call`.
+///
+/// The message is executed to completion (or until the instance gives up
+/// waiting for it) before this function returns.
+pub fn call(
+    instance: &Instance,
+    effective_canister_id: CanisterId,
+    body: Vec<u8>,
+) -> Result<(), HttpError> {
+    let msg = SignedIngress::try_from(SignedRequestBytes::from(body)).map_err(|err| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Could not parse body as call request: {}", err),
+        )
+    })?;
+    validate_request(instance, msg.as_ref())?;
+
+    let canister_id = msg.canister_id();
+    if canister_id != CanisterId::ic_00() && canister_id != effective_canister_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Specified CanisterId {} does not match effective canister id in URL {}",
+                canister_id, effective_canister_id
+            ),
+        ));
+    }
+
+    let content = msg.content();
+    let state_machine = instance
+        .route_update_call(
+            content.sender().get(),
+            canister_id,
+            content.method_name(),
+            content.arg(),
+            &RawEffectivePrincipal::CanisterId(effective_canister_id.get().to_vec()),
+        )
+        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?;
+    let msg_id = state_machine.submit_signed_ingress(msg);
+    instance.await_ingress(state_machine, &msg_id);
+    Ok(())
+}
+
+/// Handles `/api/v2/canister/<effective_canister_id>/query`. Returns the
+/// CBOR-encoded response.
+///
+/// Unlike a replica, an instance has no node signing keys, so the response
+/// does not carry any node signatures.
+pub fn query(
+    instance: &Instance,
+    effective_canister_id: CanisterId,
+    body: Vec<u8>,
+) -> Result<Vec<u8>, HttpError> {
+    let request = parse_request::<HttpQueryContent, UserQuery>(body, "query")?;
+    validate_request(instance, &request)?;
+
+    let query = request.take_content();
+    if query.receiver != effective_canister_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Specified CanisterId {} does not match effective canister id in URL {}",
+                query.receiver, effective_canister_id
+            ),
+        ));
+    }
+
+    let result = instance.subnet_of_or_default(query.receiver).query_as(
+        query.source.get(),
+        query.receiver,
+        query.method_name,
+        query.method_payload,
+    );
+    let response = match result {
+        Ok(WasmResult::Reply(arg)) => HttpQueryResponse::Replied {
+            reply: HttpQueryResponseReply { arg: Blob(arg) },
+        },
+        Ok(WasmResult::Reject(message)) => HttpQueryResponse::Rejected {
+            error_code: ErrorCode::CanisterRejectedMessage.to_string(),
+            reject_code: RejectCode::CanisterReject as u64,
+            reject_message: message,
+        },
+        Err(user_error) => HttpQueryResponse::Rejected {
+            error_code: user_error.code().to_string(),
+            reject_code: user_error.reject_code() as u64,
+            reject_message: user_error.to_string(),
+        },
+    };
+    Ok(into_cbor(&HttpSignedQueryResponse {
+        response,
+        signatures: vec![],
+    }))
+}
+
+/// Handles `/api/v2/canister/<effective_canister_id>/read_state`. Returns the
+/// CBOR-encoded certificate, signed with the key of the subnet that hosts the
+/// effective canister ID.
+pub fn read_state(
+    instance: &Instance,
+    effective_canister_id: CanisterId,
+    body: Vec<u8>,
+) -> Result<Vec<u8>, HttpError> {
+    let request = parse_request::<HttpReadStateContent, ReadState>(body, "read request")?;
+    validate_request(instance, &request)?;
+
+    let state_machine = instance.subnet_of_or_default(effective_canister_id);
+    let read_state = request.take_content();
+    verify_paths(
+        &state_machine.get_latest_state(),
+        &read_state.source,
+        &read_state.paths,
+        effective_canister_id,
+    )?;
+    let certificate = state_machine
+        .read_state(&read_state.paths)
+        .map_err(|err| (StatusCode::SERVICE_UNAVAILABLE, err))?;
+    Ok(into_cbor(&HttpReadStateResponse {
+        certificate: Blob(into_cbor(&certificate)),
+    }))
+}
+
+fn parse_request<E, C>(body: Vec<u8>, kind: &str) -> Result<HttpRequest<C>, HttpError>
+where
+    for<'a> E: Deserialize<'a>,
+    HttpRequest<C>: TryFrom<HttpRequestEnvelope<E>, Error = HttpRequestError>,
+{
+    let envelope =
+        HttpRequestEnvelope::<E>::try_from(&SignedRequestBytes::from(body)).map_err(|err| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Could not parse body as {}: {}", kind, err),
+            )
+        })?;
+    HttpRequest::<C>::try_from(envelope).map_err(|err| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Malformed request: {:?}", err),
+        )
+    })
+}
+
+/// Validates the signature, delegations and ingress expiry of the request
+/// against the root key and the current time of the instance.
+fn validate_request<C: HttpRequestContent>(
+    instance: &Instance,
+    request: &HttpRequest<C>,
+) -> Result<(), HttpError>
+where
+    IngressMessageVerifier: HttpRequestVerifier<C>,
+{
+    let current_time = Time::try_from(instance.time()).expect("Invalid instance time");
+    let verifier = IngressMessageVerifier::builder()
+        .with_root_of_trust(instance.root_subnet().root_key())
+        .with_time_provider(TimeProvider::Constant(current_time))
+        .build();
+    verifier.validate_request(request).map_err(|err| {
+        (
+            StatusCode::FORBIDDEN,
+            format!("Failed to authenticate request {}: {}", request.id(), err),
+        )
+    })
+}
+
+/// Verifies that `user` may read the requested paths, following the rules of
+/// a replica's `read_state` endpoint.
+fn verify_paths(
+    state: &ReplicatedState,
+    user: &UserId,
+    paths: &[Path],
+    effective_canister_id: CanisterId,
+) -> Result<(), HttpError> {
+    let mut request_status_id: Option<MessageId> = None;
+
+    let paths: Vec<Vec<&[u8]>> = paths
+        .iter()
+        .map(|path| path.iter().map(|label| label.as_bytes()).collect())
+        .collect();
+
+    for path in paths {
+        match path.as_slice() {
+            [b"time"] => {}
+            [b"canister", canister_id, b"controller" | b"controllers" | b"module_hash"] => {
+                verify_canister_id(canister_id, effective_canister_id)?;
+            }
+            [b"canister", canister_id, b"metadata", name] => {
+                let canister_id = verify_canister_id(canister_id, effective_canister_id)?;
+                let name = String::from_utf8(name.to_vec()).map_err(|err| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        format!("Could not parse the custom section name: {}.", err),
+                    )
+                })?;
+                can_read_canister_metadata(user, &canister_id, &name, state)?;
+            }
+            [b"subnet"] => {}
+            [b"subnet", _subnet_id, b"public_key" | b"canister_ranges"] => {}
+            [b"subnet", _subnet_id, b"node"] => {}
+            [b"subnet", _subnet_id, b"node", _node_id] => {}
+            [b"subnet", _subnet_id, b"node", _node_id, b"public_key"] => {}
+            [b"request_status", request_id]
+            | [b"request_status", request_id, b"status" | b"reply" | b"reject_code" | b"reject_message" | b"error_code"] =>
+            {
+                let message_id = MessageId::try_from(*request_id).map_err(|_| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        format!(
+                            "Request IDs must be {} bytes in length.",
+                            EXPECTED_MESSAGE_ID_LENGTH
+                        ),
+                    )
+                })?;
+                if request_status_id.is_some_and(|id| id != message_id) {
+                    return Err((
+                        StatusCode::BAD_REQUEST,
+                        "Can only request a single request ID in request_status paths.".to_string(),
+                    ));
+                }
+                // Verify that the request was signed by the same user.
+                if let Some(ingress_user_id) = state.get_ingress_status(&message_id).user_id() {
+                    if ingress_user_id != *user {
+                        return Err((
+                            StatusCode::FORBIDDEN,
+                            "Request IDs must be for requests signed by the caller.".to_string(),
+                        ));
+                    }
+                }
+                request_status_id = Some(message_id);
+            }
+            _ => {
+                return Err((StatusCode::NOT_FOUND, "Invalid path requested.".to_string()));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn verify_canister_id(
+    canister_id: &[u8],
+    effective_canister_id: CanisterId,
+) -> Result<CanisterId, HttpError> {
+    let canister_id = CanisterId::try_from(canister_id).map_err(|err| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Could not parse Canister ID: {}.", err),
+        )
+    })?;
+    if canister_id != effective_canister_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Effective canister id in URL {} does not match requested canister id: {}.",
+                effective_canister_id, canister_id
+            ),
+        ));
+    }
+    Ok(canister_id)
+}
+
+fn can_read_canister_metadata(
+    user: &UserId,
+    canister_id: &CanisterId,
+    custom_section_name: &str,
+    state: &ReplicatedState,
+) -> Result<(), HttpError> {
+    let canister = match state.canister_states.get(canister_id) {
+        Some(canister) => canister,
+        None => return Ok(()),
+    };
+    let custom_section = match canister
+        .execution_state
+        .as_ref()
+        .and_then(|execution_state| {
+            execution_state
+                .metadata
+                .get_custom_section(custom_section_name)
+        }) {
+        Some(section) => section,
+        None => return Ok(()),
+    };
+
+    // Only the controllers can request private custom sections.
+    if custom_section.visibility() == CustomSectionType::Private
+        && !canister.system_state.controllers.contains(&user.get())
+    {
+        return Err((
+            StatusCode::FORBIDDEN,
+            format!(
+                "Custom section {:.100} can only be requested by the controllers of the canister.",
+                custom_section_name
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn into_cbor<R: Serialize>(r: &R) -> Vec<u8> {
+    let mut ser = serde_cbor::Serializer::new(Vec::new());
+    ser.self_describe().expect("Could not write magic tag.");
+    r.serialize(&mut ser).expect("Serialization failed.");
+    ser.into_inner()
+}
@@ -0,0 +1,1111 @@
+//! A PocketIC instance consists of one or more subnets, each of which is backed
+//! by its own `StateMachine`. All subnets share the same routing table, so that
+//! canisters can call canisters on other subnets: on every tick, the XNet
+//! streams produced by each subnet are inducted into their destination subnets.
+
+use ic_config::{execution_environment, subnet_config::SubnetConfig};
+use ic_error_types::RejectCode;
+use ic_ic00_types::{
+    CanisterHttpResponsePayload, EcdsaCurve, EcdsaKeyId, HttpHeader, Payload, TransformArgs,
+};
+use ic_registry_routing_table::{routing_table_insert_subnet, RoutingTable};
+use ic_registry_subnet_type::SubnetType;
+use ic_replicated_state::ReplicatedState;
+use ic_state_machine_tests::{
+    CanisterHttpRequestContext, ErrorCode, MessageId, PayloadBuilder, StateMachine,
+    StateMachineBuilder, StateMachineConfig, UserError, WasmResult,
+};
+use ic_types::{
+    batch::XNetPayload,
+    canister_http::{self, MAX_CANISTER_HTTP_RESPONSE_BYTES},
+    ingress::{IngressState, IngressStatus},
+    messages::{
+        extract_effective_canister_id, Blob, CallbackId, HttpCanisterUpdate, RejectContext,
+        SignedIngressContent,
+    },
+    xnet::StreamIndex,
+    CanisterId, Cycles, PrincipalId, SubnetId, Time,
+};
+use pocket_ic::{
+    CanisterHttpHeader, CanisterHttpMethod, CanisterHttpReply, CanisterHttpRequest,
+    CanisterHttpResponse, CanisterIdRange, RawCanisterId, RawEffectivePrincipal, RawMessageId,
+    RawSubnetId, SubnetConfigSet, SubnetKind, SubnetTopology, Topology, ECDSA_TEST_KEY_NAMES,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// The maximum number of ticks to wait for the result of an ingress message.
+const MAX_TICKS: usize = 100;
+
+/// The file in a checkpoint directory that describes the checkpointed subnets.
+const CHECKPOINT_METADATA_FILE: &str = "instance.json";
+
+struct Subnet {
+    kind: SubnetKind,
+    state_machine: StateMachine,
+}
+
+/// Describes a subnet whose state has been written to a checkpoint directory.
+#[derive(Serialize, Deserialize)]
+struct SubnetCheckpoint {
+    subnet_id: RawSubnetId,
+    kind: SubnetKind,
+    nonce: u64,
+    time: SystemTime,
+    #[serde(default)]
+    canister_profiling: bool,
+}
+
+pub struct Instance {
+    /// The subnets in the order in which they were assigned canister ranges.
+    subnets: Vec<Subnet>,
+    routing_table: RoutingTable,
+    /// Whether the subnets record instruction profiles of canister executions.
+    canister_profiling: bool,
+}
+
+impl Instance {
+    /// Creates an instance with the given subnets.
+    ///
+    /// The subnets are assigned consecutive canister ranges in the order
+    /// NNS, system, application, so that the NNS subnet hosts the canister
+    /// IDs of the NNS canisters.
+    pub fn new(config: SubnetConfigSet) -> Result<Self, String> {
+        let kinds = config.subnet_kinds();
+        if kinds.is_empty() {
+            return Err("An instance needs at least one subnet.".to_string());
+        }
+
+        let subnet_ids: Vec<_> = (1..=kinds.len() as u64)
+            .map(|i| SubnetId::from(PrincipalId::new_subnet_test_id(i)))
+            .collect();
+        let routing_table = routing_table(&subnet_ids);
+        // The NNS subnet (or the only system subnet) acts as the root subnet.
+        let root_subnet_id = subnet_ids[0];
+
+        let subnets = kinds
+            .into_iter()
+            .zip(subnet_ids)
+            .map(|(kind, subnet_id)| Subnet {
+                kind,
+                state_machine: subnet_builder(
+                    kind,
+                    subnet_id,
+                    root_subnet_id,
+                    &routing_table,
+                    config.canister_profiling,
+                )
+                .build(),
+            })
+            .collect();
+
+        Ok(Self {
+            subnets,
+            routing_table,
+            canister_profiling: config.canister_profiling,
+        })
+    }
+
+    /// Creates an instance from a checkpoint written by [Instance::checkpoint].
+    /// The checkpoint directory is left untouched, so that it can be used to
+    /// create any number of instances.
+    pub fn from_checkpoint(checkpoint_dir: &Path) -> Result<Self, String> {
+        let metadata_path = checkpoint_dir.join(CHECKPOINT_METADATA_FILE);
+        let metadata = std::fs::read(&metadata_path).map_err(|err| {
+            format!(
+                "Failed to read checkpoint metadata {}: {}",
+                metadata_path.display(),
+                err
+            )
+        })?;
+        let metadata: Vec<SubnetCheckpoint> = serde_json::from_slice(&metadata)
+            .map_err(|err| format!("Failed to parse checkpoint metadata: {}", err))?;
+        if metadata.is_empty() {
+            return Err("The checkpoint does not contain any subnet.".to_string());
+        }
+
+        let subnet_ids = metadata
+            .iter()
+            .map(|subnet| {
+                PrincipalId::try_from(subnet.subnet_id.subnet_id.as_slice())
+                    .map(SubnetId::from)
+                    .map_err(|err| format!("Invalid subnet ID in checkpoint metadata: {}", err))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let routing_table = routing_table(&subnet_ids);
+        let root_subnet_id = subnet_ids[0];
+        let canister_profiling = metadata[0].canister_profiling;
+
+        let subnets = metadata
+            .into_iter()
+            .zip(subnet_ids)
+            .map(|(subnet, subnet_id)| {
+                let state_machine = subnet_builder(
+                    subnet.kind,
+                    subnet_id,
+                    root_subnet_id,
+                    &routing_table,
+                    canister_profiling,
+                )
+                .with_checkpoint_dir(&checkpoint_dir.join(subnet_id.to_string()))?
+                .with_nonce(subnet.nonce)
+                .with_time(
+                    Time::try_from(subnet.time)
+                        .map_err(|err| format!("Invalid time in checkpoint metadata: {:?}", err))?,
+                )
+                .build();
+                Ok(Subnet {
+                    kind: subnet.kind,
+                    state_machine,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self {
+            subnets,
+            routing_table,
+            canister_profiling,
+        })
+    }
+
+    /// Writes a checkpoint of the replicated state of every subnet to
+    /// `checkpoint_dir`, which must not exist or be empty. Every subnet gets
+    /// its own state root in the `state_layout` format, next to a metadata
+    /// file describing the subnets.
+    pub fn checkpoint(&self, checkpoint_dir: &Path) -> Result<(), String> {
+        std::fs::create_dir_all(checkpoint_dir).map_err(|err| {
+            format!(
+                "Failed to create checkpoint directory {}: {}",
+                checkpoint_dir.display(),
+                err
+            )
+        })?;
+        let is_empty = checkpoint_dir
+            .read_dir()
+            .map_err(|err| {
+                format!(
+                    "Failed to read checkpoint directory {}: {}",
+                    checkpoint_dir.display(),
+                    err
+                )
+            })?
+            .next()
+            .is_none();
+        if !is_empty {
+            return Err(format!(
+                "Checkpoint directory {} is not empty",
+                checkpoint_dir.display()
+            ));
+        }
+
+        let mut metadata = vec![];
+        for subnet in &self.subnets {
+            let subnet_id = subnet.state_machine.get_subnet_id();
+            subnet
+                .state_machine
+                .checkpoint_to(&checkpoint_dir.join(subnet_id.to_string()))?;
+            metadata.push(SubnetCheckpoint {
+                subnet_id: RawSubnetId {
+                    subnet_id: subnet_id.get().to_vec(),
+                },
+                kind: subnet.kind,
+                nonce: subnet.state_machine.nonce(),
+                time: subnet.state_machine.time(),
+                canister_profiling: self.canister_profiling,
+            });
+        }
+        let metadata = serde_json::to_vec_pretty(&metadata)
+            .map_err(|err| format!("Failed to serialize checkpoint metadata: {}", err))?;
+        std::fs::write(checkpoint_dir.join(CHECKPOINT_METADATA_FILE), metadata)
+            .map_err(|err| format!("Failed to write checkpoint metadata: {}", err))
+    }
+
+    /// Returns the state machines of all subnets.
+    pub fn state_machines(&self) -> impl Iterator<Item = &StateMachine> {
+        self.subnets.iter().map(|subnet| &subnet.state_machine)
+    }
+
+    /// Returns the root subnet, whose key is the root key of the instance.
+    pub fn root_subnet(&self) -> &StateMachine {
+        &self.subnets[0].state_machine
+    }
+
+    /// Returns the subnet on which canisters are created unless another
+    /// subnet is requested: the first application subnet if there is one.
+    pub fn default_subnet(&self) -> &StateMachine {
+        self.subnets
+            .iter()
+            .find(|subnet| subnet.kind == SubnetKind::Application)
+            .map(|subnet| &subnet.state_machine)
+            .unwrap_or_else(|| self.root_subnet())
+    }
+
+    pub fn subnet(&self, subnet_id: SubnetId) -> Option<&StateMachine> {
+        self.state_machines()
+            .find(|state_machine| state_machine.get_subnet_id() == subnet_id)
+    }
+
+    /// Returns the subnet that hosts the given canister ID according to the
+    /// routing table.
+    pub fn subnet_of(&self, canister_id: CanisterId) -> Option<&StateMachine> {
+        self.routing_table
+            .route(canister_id.get())
+            .and_then(|subnet_id| self.subnet(subnet_id))
+    }
+
+    /// Like `subnet_of`, but falls back to the default subnet for canister IDs
+    /// that are not routed to any subnet.
+    pub fn subnet_of_or_default(&self, canister_id: CanisterId) -> &StateMachine {
+        self.subnet_of(canister_id)
+            .unwrap_or_else(|| self.default_subnet())
+    }
+
+    pub fn topology(&self) -> Topology {
+        Topology(
+            self.subnets
+                .iter()
+                .map(|subnet| {
+                    let subnet_id = subnet.state_machine.get_subnet_id();
+                    SubnetTopology {
+                        subnet_id: RawSubnetId {
+                            subnet_id: subnet_id.get().to_vec(),
+                        },
+                        kind: subnet.kind,
+                        canister_ranges: self
+                            .routing_table
+                            .ranges(subnet_id)
+                            .iter()
+                            .map(|range| CanisterIdRange {
+                                start: RawCanisterId {
+                                    canister_id: range.start.get().to_vec(),
+                                },
+                                end: RawCanisterId {
+                                    canister_id: range.end.get().to_vec(),
+                                },
+                            })
+                            .collect(),
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Determines the subnet that an update call is executed on.
+    ///
+    /// Calls to canisters are routed to the subnet hosting the canister. Calls
+    /// to the management canister are routed to the subnet hosting the
+    /// canister named in their arguments if there is one, and otherwise to the
+    /// subnet given by the effective principal or to the default subnet.
+    pub fn route_update_call(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        method: &str,
+        arg: &[u8],
+        effective_principal: &RawEffectivePrincipal,
+    ) -> Result<&StateMachine, UserError> {
+        if canister_id != CanisterId::ic_00() {
+            return self.subnet_of(canister_id).ok_or_else(|| {
+                UserError::new(
+                    ErrorCode::CanisterNotFound,
+                    format!("Canister {} is not hosted by any subnet", canister_id),
+                )
+            });
+        }
+
+        let content = SignedIngressContent::try_from(HttpCanisterUpdate {
+            canister_id: Blob(canister_id.get().to_vec()),
+            method_name: method.to_string(),
+            arg: Blob(arg.to_vec()),
+            sender: Blob(sender.to_vec()),
+            ingress_expiry: 0,
+            nonce: None,
+        });
+        // Malformed arguments are rejected by the management canister.
+        if let Ok(Ok(Some(target))) = content.map(|content| {
+            extract_effective_canister_id(&content, self.default_subnet().get_subnet_id())
+        }) {
+            return Ok(self.subnet_of_or_default(target));
+        }
+
+        match effective_principal {
+            RawEffectivePrincipal::None => Ok(self.default_subnet()),
+            RawEffectivePrincipal::SubnetId(subnet_id) => {
+                let subnet_id = PrincipalId::try_from(subnet_id.as_slice())
+                    .map(SubnetId::from)
+                    .map_err(|err| {
+                        UserError::new(
+                            ErrorCode::SubnetNotFound,
+                            format!("Invalid subnet ID: {}", err),
+                        )
+                    })?;
+                self.subnet(subnet_id).ok_or_else(|| {
+                    UserError::new(
+                        ErrorCode::SubnetNotFound,
+                        format!("Subnet {} not found", subnet_id),
+                    )
+                })
+            }
+            RawEffectivePrincipal::CanisterId(canister_id) => {
+                let canister_id = CanisterId::try_from(canister_id.as_slice()).map_err(|err| {
+                    UserError::new(
+                        ErrorCode::CanisterNotFound,
+                        format!("Invalid canister ID: {}", err),
+                    )
+                })?;
+                Ok(self.subnet_of_or_default(canister_id))
+            }
+        }
+    }
+
+    /// Submits an ingress message to the given subnet and ticks the whole
+    /// instance until its result is known, so that the message may make calls
+    /// to canisters on other subnets.
+    pub fn execute_ingress(
+        &self,
+        state_machine: &StateMachine,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        method: String,
+        arg: Vec<u8>,
+    ) -> Result<WasmResult, UserError> {
+        let msg_id = state_machine.send_ingress(sender, canister_id, method, arg);
+        self.await_call(state_machine, &msg_id)
+    }
+
+    /// Ticks the whole instance until the result of the ingress message with
+    /// the given ID, which was submitted to the given subnet, is known.
+    /// Returns an `IngressMessageTimeout` error if the result is not known
+    /// after `MAX_TICKS` ticks.
+    pub fn await_call(
+        &self,
+        state_machine: &StateMachine,
+        msg_id: &MessageId,
+    ) -> Result<WasmResult, UserError> {
+        match self.await_ingress(state_machine, msg_id) {
+            IngressStatus::Known {
+                state: IngressState::Completed(result),
+                ..
+            } => Ok(result),
+            IngressStatus::Known {
+                state: IngressState::Failed(error),
+                ..
+            } => Err(error),
+            _ => Err(UserError::new(
+                ErrorCode::IngressMessageTimeout,
+                format!(
+                    "Did not get answer to ingress {} after {} ticks",
+                    msg_id, MAX_TICKS
+                ),
+            )),
+        }
+    }
+
+    /// Like [`Instance::await_call`], but takes the message ID as returned to
+    /// clients. Returns an error if the subnet or the message is not known.
+    pub fn await_raw_call(&self, raw_msg_id: &RawMessageId) -> Result<WasmResult, UserError> {
+        let subnet_id = PrincipalId::try_from(raw_msg_id.subnet_id.subnet_id.as_slice())
+            .map(SubnetId::from)
+            .map_err(|err| {
+                UserError::new(
+                    ErrorCode::SubnetNotFound,
+                    format!("Invalid subnet ID: {}", err),
+                )
+            })?;
+        let state_machine = self.subnet(subnet_id).ok_or_else(|| {
+            UserError::new(
+                ErrorCode::SubnetNotFound,
+                format!("Subnet {} not found", subnet_id),
+            )
+        })?;
+        let msg_id = MessageId::try_from(raw_msg_id.message_id.as_slice()).map_err(|err| {
+            UserError::new(
+                ErrorCode::IngressMessageTimeout,
+                format!("Invalid message ID: {}", err),
+            )
+        })?;
+        // A message that was never submitted would otherwise only be reported
+        // after `MAX_TICKS` ticks.
+        if state_machine.ingress_status(&msg_id) == IngressStatus::Unknown {
+            return Err(UserError::new(
+                ErrorCode::IngressMessageTimeout,
+                format!("Message {} is not known to subnet {}", msg_id, subnet_id),
+            ));
+        }
+        self.await_call(state_machine, &msg_id)
+    }
+
+    /// Ticks the whole instance until the ingress message with the given ID,
+    /// which was submitted to the given subnet, is completed or failed, or
+    /// until `MAX_TICKS` ticks have passed. Returns the last known status.
+    pub fn await_ingress(&self, state_machine: &StateMachine, msg_id: &MessageId) -> IngressStatus {
+        for _ in 0..MAX_TICKS {
+            let status = state_machine.ingress_status(msg_id);
+            match status {
+                IngressStatus::Known {
+                    state: IngressState::Completed(_) | IngressState::Failed(_),
+                    ..
+                } => return status,
+                _ => self.tick(),
+            }
+        }
+        state_machine.ingress_status(msg_id)
+    }
+
+    /// Executes a round on every subnet and then inducts the XNet messages
+    /// produced by each subnet into their destination subnets.
+    pub fn tick(&self) {
+        for state_machine in self.state_machines() {
+            state_machine.tick();
+        }
+        self.deliver_xnet_messages();
+    }
+
+    fn deliver_xnet_messages(&self) {
+        for destination in self.state_machines() {
+            let destination_state = destination.get_latest_state();
+            let mut stream_slices = BTreeMap::new();
+            for source in self.state_machines() {
+                if source.get_subnet_id() == destination.get_subnet_id() {
+                    continue;
+                }
+                let source_state = source.get_latest_state();
+                if let Some(begin) = pending_stream_begin(
+                    &source_state,
+                    source.get_subnet_id(),
+                    &destination_state,
+                    destination.get_subnet_id(),
+                ) {
+                    let xnet_payload = source
+                        .generate_xnet_payload(
+                            destination.get_subnet_id(),
+                            Some(begin),
+                            Some(begin),
+                            None,
+                            None,
+                        )
+                        .expect("Failed to generate an XNet payload");
+                    stream_slices.extend(xnet_payload.stream_slices);
+                }
+            }
+            if !stream_slices.is_empty() {
+                destination.execute_block_with_xnet_payload(XNetPayload { stream_slices });
+            }
+        }
+    }
+
+    /// Ticks until no subnet has any messages left to process or deliver.
+    /// Returns an error if this is not the case after `max_ticks` ticks.
+    pub fn run_until_completion(&self, max_ticks: usize) -> Result<(), String> {
+        for _ in 0..max_ticks {
+            if self.is_idle() {
+                return Ok(());
+            }
+            self.tick();
+        }
+        if self.is_idle() {
+            Ok(())
+        } else {
+            Err(format!(
+                "The instance did not reach completion after {} ticks",
+                max_ticks
+            ))
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        let states: Vec<_> = self
+            .state_machines()
+            .map(|state_machine| {
+                (
+                    state_machine.get_subnet_id(),
+                    state_machine.get_latest_state(),
+                )
+            })
+            .collect();
+        states.iter().all(|(subnet_id, state)| {
+            !state
+                .canisters_iter()
+                .any(|canister| canister.has_input() || canister.has_output())
+                && !state.subnet_queues().has_input()
+                && !state.subnet_queues().has_output()
+                && states.iter().all(|(other_id, other_state)| {
+                    other_id == subnet_id
+                        || pending_stream_begin(state, *subnet_id, other_state, *other_id).is_none()
+                })
+        })
+    }
+
+    /// Returns the canister HTTP requests of all subnets that have not been
+    /// answered yet.
+    pub fn canister_http_requests(&self) -> Vec<CanisterHttpRequest> {
+        self.state_machines()
+            .flat_map(|state_machine| {
+                let subnet_id = RawSubnetId {
+                    subnet_id: state_machine.get_subnet_id().get().to_vec(),
+                };
+                state_machine
+                    .canister_http_request_contexts()
+                    .into_iter()
+                    .map(move |(request_id, context)| CanisterHttpRequest {
+                        subnet_id: subnet_id.clone(),
+                        request_id: request_id.get(),
+                        http_method: match context.http_method {
+                            canister_http::CanisterHttpMethod::GET => CanisterHttpMethod::GET,
+                            canister_http::CanisterHttpMethod::POST => CanisterHttpMethod::POST,
+                            canister_http::CanisterHttpMethod::HEAD => CanisterHttpMethod::HEAD,
+                            canister_http::CanisterHttpMethod::PUT => CanisterHttpMethod::PUT,
+                            canister_http::CanisterHttpMethod::DELETE => CanisterHttpMethod::DELETE,
+                            canister_http::CanisterHttpMethod::PATCH => CanisterHttpMethod::PATCH,
+                        },
+                        url: context.url,
+                        headers: context
+                            .headers
+                            .into_iter()
+                            .map(|header| CanisterHttpHeader {
+                                name: header.name,
+                                value: header.value,
+                            })
+                            .collect(),
+                        body: context.body.unwrap_or_default(),
+                        max_response_bytes: context.max_response_bytes.map(|bytes| bytes.get()),
+                    })
+            })
+            .collect()
+    }
+
+    /// Answers the pending canister HTTP request with the given ID on the
+    /// given subnet in a new round on that subnet. As on a replica, a reply
+    /// is rejected if it exceeds the maximum response size of the request and
+    /// is passed through the transform function of the request, if any.
+    pub fn mock_canister_http_response(
+        &self,
+        subnet_id: SubnetId,
+        request_id: CallbackId,
+        response: CanisterHttpResponse,
+    ) -> Result<(), String> {
+        let state_machine = self
+            .subnet(subnet_id)
+            .ok_or_else(|| format!("Subnet {} not found", subnet_id))?;
+        let context = state_machine
+            .canister_http_request_contexts()
+            .remove(&request_id)
+            .ok_or_else(|| {
+                format!(
+                    "No pending canister HTTP request with ID {} on subnet {}",
+                    request_id, subnet_id
+                )
+            })?;
+        let payload = match response {
+            CanisterHttpResponse::CanisterHttpReply(reply) => {
+                match transform_canister_http_reply(state_machine, &context, reply) {
+                    Ok(reply) => PayloadBuilder::new().http_response(request_id, &reply),
+                    Err(reject) => PayloadBuilder::new().http_reject(request_id, reject),
+                }
+            }
+            CanisterHttpResponse::CanisterHttpReject(reject) => {
+                let reject_code = RejectCode::try_from(reject.reject_code)
+                    .map_err(|_| format!("Invalid reject code {}", reject.reject_code))?;
+                PayloadBuilder::new()
+                    .http_reject(request_id, RejectContext::new(reject_code, reject.message))
+            }
+        };
+        state_machine.execute_payload(payload);
+        Ok(())
+    }
+
+    pub fn time(&self) -> SystemTime {
+        self.root_subnet().time()
+    }
+
+    pub fn set_time(&self, time: SystemTime) {
+        for state_machine in self.state_machines() {
+            state_machine.set_time(time);
+        }
+    }
+
+    pub fn advance_time(&self, amount: Duration) {
+        for state_machine in self.state_machines() {
+            state_machine.advance_time(amount);
+        }
+    }
+}
+
+/// Returns a routing table that assigns consecutive canister ranges to the
+/// given subnets.
+fn routing_table(subnet_ids: &[SubnetId]) -> RoutingTable {
+    let mut routing_table = RoutingTable::new();
+    for subnet_id in subnet_ids {
+        routing_table_insert_subnet(&mut routing_table, *subnet_id)
+            .expect("Failed to update the routing table");
+    }
+    routing_table
+}
+
+fn subnet_builder(
+    kind: SubnetKind,
+    subnet_id: SubnetId,
+    root_subnet_id: SubnetId,
+    routing_table: &RoutingTable,
+    canister_profiling: bool,
+) -> StateMachineBuilder {
+    let subnet_type = match kind {
+        SubnetKind::NNS | SubnetKind::System => SubnetType::System,
+        SubnetKind::Application => SubnetType::Application,
+    };
+    let hypervisor_config = execution_environment::Config {
+        default_provisional_cycles_balance: Cycles::new(0),
+        ..Default::default()
+    };
+    let config = StateMachineConfig::new(SubnetConfig::new(subnet_type), hypervisor_config);
+    let builder = StateMachineBuilder::new()
+        .with_config(Some(config))
+        .with_subnet_type(subnet_type)
+        .with_subnet_id(subnet_id)
+        .with_nns_subnet_id(root_subnet_id)
+        .with_routing_table(routing_table.clone());
+    let builder = if canister_profiling {
+        builder.with_canister_profiling()
+    } else {
+        builder
+    };
+    ECDSA_TEST_KEY_NAMES.iter().fold(builder, |builder, name| {
+        builder.with_ecdsa_key(EcdsaKeyId {
+            curve: EcdsaCurve::Secp256k1,
+            name: name.to_string(),
+        })
+    })
+}
+
+/// Turns a mocked reply to a canister HTTP request into the response that is
+/// delivered to the canister, or into a reject if the reply is too large or
+/// the transform function fails.
+fn transform_canister_http_reply(
+    state_machine: &StateMachine,
+    context: &CanisterHttpRequestContext,
+    reply: CanisterHttpReply,
+) -> Result<CanisterHttpResponsePayload, RejectContext> {
+    let max_response_bytes = context
+        .max_response_bytes
+        .map(|bytes| bytes.get())
+        .unwrap_or(MAX_CANISTER_HTTP_RESPONSE_BYTES);
+    if reply.body.len() as u64 > max_response_bytes {
+        return Err(RejectContext::new(
+            RejectCode::SysFatal,
+            format!(
+                "Http body exceeds size limit of {} bytes.",
+                max_response_bytes
+            ),
+        ));
+    }
+    let response = CanisterHttpResponsePayload {
+        status: reply.status as u128,
+        headers: reply
+            .headers
+            .into_iter()
+            .map(|header| HttpHeader {
+                name: header.name,
+                value: header.value,
+            })
+            .collect(),
+        body: reply.body,
+    };
+    let transform = match &context.transform {
+        Some(transform) => transform,
+        None => return Ok(response),
+    };
+    let transform_args = TransformArgs {
+        response,
+        context: transform.context.clone(),
+    };
+    // The transform function is called with an anonymous query, as on a replica.
+    match state_machine.query_as(
+        PrincipalId::new_anonymous(),
+        context.request.sender,
+        &transform.method_name,
+        transform_args.encode(),
+    ) {
+        Ok(WasmResult::Reply(bytes)) => {
+            CanisterHttpResponsePayload::decode(&bytes).map_err(|err| {
+                RejectContext::new(
+                    RejectCode::SysFatal,
+                    format!("Failed to decode the transformed http response: {}", err),
+                )
+            })
+        }
+        Ok(WasmResult::Reject(message)) => {
+            Err(RejectContext::new(RejectCode::CanisterReject, message))
+        }
+        Err(err) => Err(RejectContext::new(
+            err.reject_code(),
+            err.description().to_string(),
+        )),
+    }
+}
+
+/// Returns the index from which the stream from `source` to `destination` has
+/// to be inducted into `destination`, if the stream contains messages that
+/// `destination` has not inducted yet or signals that `destination` has not
+/// seen yet.
+fn pending_stream_begin(
+    source_state: &ReplicatedState,
+    source_id: SubnetId,
+    destination_state: &ReplicatedState,
+    destination_id: SubnetId,
+) -> Option<StreamIndex> {
+    let stream = source_state.get_stream(&destination_id)?;
+    let reverse_stream = destination_state.get_stream(&source_id);
+    // The index of the next message that `destination` expects from `source`.
+    let begin = reverse_stream
+        .map(|reverse_stream| reverse_stream.signals_end())
+        .unwrap_or_default();
+    let has_new_messages = stream.messages_end() > begin;
+    let has_new_signals = reverse_stream
+        .map(|reverse_stream| reverse_stream.messages_begin() < stream.signals_end())
+        .unwrap_or(false);
+    (has_new_messages || has_new_signals).then_some(begin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_ic00_types::{CanisterHttpRequestArgs, CanisterIdRecord, HttpMethod};
+    use ic_universal_canister::{call_args, wasm, UNIVERSAL_CANISTER_WASM};
+    use pocket_ic::CanisterHttpReject;
+
+    fn install_universal_canister(state_machine: &StateMachine) -> CanisterId {
+        state_machine
+            .install_canister_with_cycles(
+                UNIVERSAL_CANISTER_WASM.to_vec(),
+                vec![],
+                None,
+                Cycles::new(100_000_000_000_000),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn canisters_on_different_subnets_can_call_each_other() {
+        let instance = Instance::new(SubnetConfigSet {
+            nns: true,
+            system: false,
+            application: 2,
+            ..Default::default()
+        })
+        .unwrap();
+        let subnets: Vec<_> = instance.state_machines().collect();
+        let caller = install_universal_canister(subnets[1]);
+        let callee = install_universal_canister(subnets[2]);
+        assert_eq!(
+            instance.subnet_of(callee).unwrap().get_subnet_id(),
+            subnets[2].get_subnet_id()
+        );
+
+        let payload = wasm()
+            .call_simple(
+                callee.get(),
+                "update",
+                call_args().other_side(wasm().reply_data(b"pong")),
+            )
+            .build();
+        let result = instance.execute_ingress(
+            instance.subnet_of(caller).unwrap(),
+            PrincipalId::new_anonymous(),
+            caller,
+            "update".to_string(),
+            payload,
+        );
+        assert_eq!(result, Ok(WasmResult::Reply(b"pong".to_vec())));
+        assert_eq!(instance.run_until_completion(10), Ok(()));
+
+        // The call to the other subnet is still pending without any ticks.
+        let payload = wasm()
+            .call_simple(
+                callee.get(),
+                "update",
+                call_args().other_side(wasm().reply_data(b"pong")),
+            )
+            .build();
+        instance.subnet_of(caller).unwrap().send_ingress(
+            PrincipalId::new_anonymous(),
+            caller,
+            "update",
+            payload,
+        );
+        assert_eq!(
+            instance.run_until_completion(0),
+            Err("The instance did not reach completion after 0 ticks".to_string())
+        );
+        assert_eq!(instance.run_until_completion(10), Ok(()));
+    }
+
+    #[test]
+    fn management_canister_calls_are_routed_by_effective_canister_id() {
+        let instance = Instance::new(SubnetConfigSet {
+            nns: false,
+            system: true,
+            application: 1,
+            ..Default::default()
+        })
+        .unwrap();
+        let system_subnet = instance.root_subnet();
+        let canister_id = system_subnet.create_canister(None);
+
+        let arg = CanisterIdRecord::from(canister_id).encode();
+        let subnet = instance
+            .route_update_call(
+                PrincipalId::new_anonymous(),
+                CanisterId::ic_00(),
+                "canister_status",
+                &arg,
+                &RawEffectivePrincipal::None,
+            )
+            .unwrap();
+        assert_eq!(subnet.get_subnet_id(), system_subnet.get_subnet_id());
+
+        let subnet = instance
+            .route_update_call(
+                PrincipalId::new_anonymous(),
+                CanisterId::ic_00(),
+                "provisional_create_canister_with_cycles",
+                &[],
+                &RawEffectivePrincipal::None,
+            )
+            .unwrap();
+        assert_eq!(
+            subnet.get_subnet_id(),
+            instance.default_subnet().get_subnet_id()
+        );
+        assert_ne!(subnet.get_subnet_id(), system_subnet.get_subnet_id());
+    }
+
+    #[test]
+    fn await_raw_call_rejects_invalid_message_ids() {
+        let instance = Instance::new(SubnetConfigSet {
+            application: 1,
+            ..Default::default()
+        })
+        .unwrap();
+        let state_machine = instance.default_subnet();
+        let canister_id = install_universal_canister(state_machine);
+        let msg_id = state_machine.send_ingress(
+            PrincipalId::new_anonymous(),
+            canister_id,
+            "update",
+            wasm().reply_data(b"done").build(),
+        );
+        let subnet_id = RawSubnetId {
+            subnet_id: state_machine.get_subnet_id().get().to_vec(),
+        };
+        let await_raw_call = |subnet_id: &RawSubnetId, message_id: Vec<u8>| {
+            instance.await_raw_call(&RawMessageId {
+                subnet_id: subnet_id.clone(),
+                message_id,
+            })
+        };
+
+        let err = await_raw_call(
+            &RawSubnetId {
+                subnet_id: vec![0xff; 40],
+            },
+            msg_id.as_bytes().to_vec(),
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), ErrorCode::SubnetNotFound);
+
+        let err = await_raw_call(
+            &RawSubnetId {
+                subnet_id: PrincipalId::new_user_test_id(1).to_vec(),
+            },
+            msg_id.as_bytes().to_vec(),
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), ErrorCode::SubnetNotFound);
+
+        let err = await_raw_call(&subnet_id, vec![1, 2, 3]).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::IngressMessageTimeout);
+
+        let err = await_raw_call(&subnet_id, vec![0; 32]).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::IngressMessageTimeout);
+
+        assert_eq!(
+            await_raw_call(&subnet_id, msg_id.as_bytes().to_vec()),
+            Ok(WasmResult::Reply(b"done".to_vec()))
+        );
+    }
+
+    #[test]
+    fn instances_can_be_restored_from_a_checkpoint() {
+        let instance = Instance::new(SubnetConfigSet {
+            nns: false,
+            system: true,
+            application: 1,
+            ..Default::default()
+        })
+        .unwrap();
+        let canister_id = install_universal_canister(instance.default_subnet());
+        let set_global_data = |instance: &Instance, data: &[u8]| {
+            instance
+                .execute_ingress(
+                    instance.subnet_of(canister_id).unwrap(),
+                    PrincipalId::new_anonymous(),
+                    canister_id,
+                    "update".to_string(),
+                    wasm().set_global_data(data).reply().build(),
+                )
+                .unwrap();
+        };
+        let get_global_data = |instance: &Instance| {
+            instance
+                .subnet_of(canister_id)
+                .unwrap()
+                .query(
+                    canister_id,
+                    "query",
+                    wasm().get_global_data().append_and_reply().build(),
+                )
+                .unwrap()
+        };
+        set_global_data(&instance, b"checkpointed");
+
+        let checkpoint_dir = tempfile::tempdir().unwrap();
+        instance.checkpoint(checkpoint_dir.path()).unwrap();
+        set_global_data(&instance, b"discarded");
+        // Checkpoints are only written to empty directories.
+        assert!(instance.checkpoint(checkpoint_dir.path()).is_err());
+
+        for _ in 0..2 {
+            let restored = Instance::from_checkpoint(checkpoint_dir.path()).unwrap();
+            assert_eq!(restored.topology(), instance.topology());
+            assert_eq!(restored.time(), instance.time());
+            assert_eq!(
+                get_global_data(&restored),
+                WasmResult::Reply(b"checkpointed".to_vec())
+            );
+            set_global_data(&restored, b"restored");
+            assert_eq!(
+                get_global_data(&restored),
+                WasmResult::Reply(b"restored".to_vec())
+            );
+        }
+    }
+
+    #[test]
+    fn canister_http_requests_can_be_mocked() {
+        let instance = Instance::new(SubnetConfigSet {
+            nns: false,
+            system: true,
+            application: 1,
+            ..Default::default()
+        })
+        .unwrap();
+        let state_machine = instance.default_subnet();
+        let canister_id = install_universal_canister(state_machine);
+        let send_http_request = || {
+            let arg = CanisterHttpRequestArgs {
+                url: "https://example.com".to_string(),
+                max_response_bytes: Some(1_000),
+                headers: vec![],
+                body: None,
+                method: HttpMethod::GET,
+                transform: None,
+                replication: None,
+            };
+            let payload = wasm()
+                .call_with_cycles(
+                    CanisterId::ic_00(),
+                    "http_request",
+                    call_args().other_side(arg.encode()),
+                    Cycles::new(1_000_000_000_000),
+                )
+                .build();
+            let msg_id = state_machine.send_ingress(
+                PrincipalId::new_anonymous(),
+                canister_id,
+                "update",
+                payload,
+            );
+            instance.tick();
+            let requests = instance.canister_http_requests();
+            assert_eq!(requests.len(), 1);
+            assert_eq!(requests[0].url, "https://example.com");
+            assert_eq!(requests[0].max_response_bytes, Some(1_000));
+            (msg_id, CallbackId::from(requests[0].request_id))
+        };
+
+        let (msg_id, request_id) = send_http_request();
+        let reply = CanisterHttpReply {
+            status: 200,
+            headers: vec![],
+            body: b"mocked".to_vec(),
+        };
+        instance
+            .mock_canister_http_response(
+                state_machine.get_subnet_id(),
+                request_id,
+                CanisterHttpResponse::CanisterHttpReply(reply),
+            )
+            .unwrap();
+        let response = match instance.await_call(state_machine, &msg_id) {
+            Ok(WasmResult::Reply(bytes)) => CanisterHttpResponsePayload::decode(&bytes).unwrap(),
+            result => panic!("Unexpected result {:?}", result),
+        };
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"mocked".to_vec());
+        assert!(instance.canister_http_requests().is_empty());
+
+        // The universal canister replies with the reject code of a rejected call.
+        let (msg_id, request_id) = send_http_request();
+        let reject = CanisterHttpReject {
+            reject_code: RejectCode::SysTransient as u64,
+            message: "mocked".to_string(),
+        };
+        instance
+            .mock_canister_http_response(
+                state_machine.get_subnet_id(),
+                request_id,
+                CanisterHttpResponse::CanisterHttpReject(reject),
+            )
+            .unwrap();
+        assert_eq!(
+            instance.await_call(state_machine, &msg_id),
+            Ok(WasmResult::Reply(vec![2, 0, 0, 0]))
+        );
+
+        // Replies that exceed the maximum response size are rejected.
+        let (msg_id, request_id) = send_http_request();
+        let reply = CanisterHttpReply {
+            status: 200,
+            headers: vec![],
+            body: vec![0; 1_001],
+        };
+        instance
+            .mock_canister_http_response(
+                state_machine.get_subnet_id(),
+                request_id,
+                CanisterHttpResponse::CanisterHttpReply(reply),
+            )
+            .unwrap();
+        assert_eq!(
+            instance.await_call(state_machine, &msg_id),
+            Ok(WasmResult::Reply(vec![1, 0, 0, 0]))
+        );
+        assert!(instance
+            .mock_canister_http_response(
+                state_machine.get_subnet_id(),
+                request_id,
+                CanisterHttpResponse::CanisterHttpReject(CanisterHttpReject {
+                    reject_code: RejectCode::SysTransient as u64,
+                    message: "already answered".to_string(),
+                }),
+            )
+            .is_err());
+
+        // A request that is never answered times out.
+        let (msg_id, _) = send_http_request();
+        let err = instance.await_call(state_machine, &msg_id).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::IngressMessageTimeout);
+    }
+}
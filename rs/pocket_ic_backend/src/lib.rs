@@ -1 +1,2 @@
-
+pub mod http_interface;
+pub mod instance;
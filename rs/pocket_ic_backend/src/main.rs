@@ -1,23 +1,28 @@
+use axum::body::Bytes;
 use axum::extract::FromRef;
 use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
 use axum::routing::post;
 use axum::{extract::Path, http::StatusCode, routing::get, Router, Server};
 use clap::Parser;
 use file_lock::{FileLock, FileOptions};
-use ic_config::execution_environment;
-use ic_config::subnet_config::SubnetConfig;
 use ic_crypto::threshold_sig_public_key_to_der;
 use ic_crypto_iccsa::types::SignatureBytes;
 use ic_crypto_iccsa::{public_key_bytes_from_der, verify};
 use ic_crypto_utils_threshold_sig_der::parse_threshold_sig_key_from_der;
-use ic_registry_subnet_type::SubnetType;
-use ic_state_machine_tests::{StateMachine, StateMachineBuilder, StateMachineConfig};
-use ic_types::{CanisterId, Cycles, PrincipalId};
+use ic_types::{messages::CallbackId, CanisterId, PrincipalId, SubnetId};
 use itertools::Itertools;
-use pocket_ic::{CanisterCall, RawCanisterId, Request, Request::*};
+use pocket_ic::{
+    CanisterCall, CheckpointArg, RawCanisterId, RawEffectivePrincipal, RawMessageId, RawSubnetId,
+    Request, Request::*, SubnetConfigSet,
+};
+use pocket_ic_backend::http_interface::{self, HttpError};
+use pocket_ic_backend::instance::Instance;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::io::Write;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::RwLock;
@@ -27,12 +32,14 @@ const TTL_SEC: u64 = 30;
 
 type InstanceId = String;
 // The shared, mutable state of the PocketIC process.
-// In essence, a Map<InstanceId, StateMachine>, but due to shared mutability, some extra layers are needed.
+// In essence, a Map<InstanceId, Instance>, but due to shared mutability, some extra layers are needed.
 //
 // The outer RwLock is for concurrent read access to the Map (such as calls to different instances),
 // and exclusive write access (when a new instance is created or destroyed).
-// The inner RwLock should allow safe concurrent calls to the same instance. TODO: Confirm this.
-type InstanceMap = Arc<RwLock<HashMap<InstanceId, RwLock<StateMachine>>>>;
+// The inner RwLock allows concurrent read-only calls to the same instance (such as queries),
+// while calls that modify the instance (such as update calls or ticks) get exclusive access.
+// The work itself runs on tokio's blocking threads, so that it does not stall the server.
+type InstanceMap = Arc<RwLock<HashMap<InstanceId, Arc<RwLock<Instance>>>>>;
 
 #[derive(Clone)]
 struct AppState {
@@ -86,13 +93,36 @@ async fn main() {
         .route("/instance", get(list_instances))
         //
         // Create a new IC instance. Returns an InstanceId.
-        // Body is currently ignored.
+        // Body optionally contains a SubnetConfigSet; defaults to a single system subnet.
         .route("/instance", post(create_instance))
         //
+        // Create a new IC instance from a checkpoint. Returns an InstanceId.
+        // Body contains a CheckpointArg.
+        .route(
+            "/instance/from_checkpoint",
+            post(create_instance_from_checkpoint),
+        )
+        //
         // Call the specified IC instance.
         // Body contains a Request.
         // Returns the IC's Response.
         .route("/instance/:id", post(call_instance))
+        //
+        // The public HTTP interface of the specified IC instance, as served by
+        // a replica. Bodies and responses are CBOR-encoded.
+        .route("/instance/:id/api/v2/status", get(api_v2_status))
+        .route(
+            "/instance/:id/api/v2/canister/:effective_canister_id/call",
+            post(api_v2_call),
+        )
+        .route(
+            "/instance/:id/api/v2/canister/:effective_canister_id/query",
+            post(api_v2_query),
+        )
+        .route(
+            "/instance/:id/api/v2/canister/:effective_canister_id/read_state",
+            post(api_v2_read_state),
+        )
         .with_state(app_state.clone());
 
     // bind to port 0; the OS will give a specific port; communicate that to parent process via stdout
@@ -125,14 +155,39 @@ async fn status() -> StatusCode {
 
 /// Create a new IC instance.
 /// The new InstanceId will be returned
-async fn create_instance(State(inst_map): State<InstanceMap>) -> String {
+async fn create_instance(
+    State(inst_map): State<InstanceMap>,
+    config: Option<axum::extract::Json<SubnetConfigSet>>,
+) -> Result<String, (StatusCode, String)> {
+    let config = config.map(|config| config.0).unwrap_or(SubnetConfigSet {
+        system: true,
+        ..Default::default()
+    });
     let instance_id = rand_string(6);
-    let sm = tokio::task::spawn_blocking(|| create_state_machine())
+    let instance = tokio::task::spawn_blocking(move || Instance::new(config))
         .await
-        .expect("Failed to launch a state machine");
+        .expect("Failed to launch a state machine")
+        .map_err(|err| (StatusCode::BAD_REQUEST, err))?;
     let mut guard = inst_map.write().await;
-    guard.insert(instance_id.clone(), RwLock::new(sm));
-    instance_id
+    guard.insert(instance_id.clone(), Arc::new(RwLock::new(instance)));
+    Ok(instance_id)
+}
+
+/// Create a new IC instance from a checkpoint written by a `Checkpoint` request.
+/// The new InstanceId will be returned
+async fn create_instance_from_checkpoint(
+    State(inst_map): State<InstanceMap>,
+    axum::extract::Json(arg): axum::extract::Json<CheckpointArg>,
+) -> Result<String, (StatusCode, String)> {
+    let instance_id = rand_string(6);
+    let instance =
+        tokio::task::spawn_blocking(move || Instance::from_checkpoint(&arg.checkpoint_dir))
+            .await
+            .expect("Failed to launch a state machine")
+            .map_err(|err| (StatusCode::BAD_REQUEST, err))?;
+    let mut guard = inst_map.write().await;
+    guard.insert(instance_id.clone(), Arc::new(RwLock::new(instance)));
+    Ok(instance_id)
 }
 
 async fn list_instances(State(inst_map): State<InstanceMap>) -> String {
@@ -147,24 +202,131 @@ async fn call_instance(
     axum::extract::Json(request): axum::extract::Json<Request>,
 ) -> String {
     // println!("call_instance {} with request: {}", id, serde_json::to_string(&request).unwrap_or("Failed to decode json".to_owned()));
-    let guard_map = inst_map.read().await;
-    if let Some(rw_lock) = guard_map.get(&id) {
-        let guard_sm = rw_lock.write().await;
-        call_sm(&guard_sm, request)
-    } else {
-        // id not found in map; return error
-        // TODO: Result Type for this call
-        format!("Id {} was not found in instance map.", id)
-    }
+    let read_only = is_read_only(&request);
+    with_instance(&inst_map, &id, read_only, move |instance| {
+        Ok(call_instance_request(instance, request))
+    })
+    .await
+    // id not found in map; return error
+    // TODO: Result Type for this call
+    .unwrap_or_else(|(_, err)| err)
 }
 
-fn create_state_machine() -> StateMachine {
-    let hypervisor_config = execution_environment::Config {
-        default_provisional_cycles_balance: Cycles::new(0),
-        ..Default::default()
+async fn api_v2_status(
+    State(inst_map): State<InstanceMap>,
+    Path(id): Path<InstanceId>,
+) -> Result<impl IntoResponse, HttpError> {
+    let status = with_instance(&inst_map, &id, true, |instance| {
+        Ok(http_interface::status(instance))
+    })
+    .await?;
+    Ok(cbor_response(status))
+}
+
+async fn api_v2_call(
+    State(inst_map): State<InstanceMap>,
+    Path((id, effective_canister_id)): Path<(InstanceId, String)>,
+    body: Bytes,
+) -> Result<StatusCode, HttpError> {
+    let effective_canister_id = parse_effective_canister_id(&effective_canister_id)?;
+    with_instance(&inst_map, &id, false, move |instance| {
+        http_interface::call(instance, effective_canister_id, body.to_vec())
+    })
+    .await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn api_v2_query(
+    State(inst_map): State<InstanceMap>,
+    Path((id, effective_canister_id)): Path<(InstanceId, String)>,
+    body: Bytes,
+) -> Result<impl IntoResponse, HttpError> {
+    let effective_canister_id = parse_effective_canister_id(&effective_canister_id)?;
+    let response = with_instance(&inst_map, &id, true, move |instance| {
+        http_interface::query(instance, effective_canister_id, body.to_vec())
+    })
+    .await?;
+    Ok(cbor_response(response))
+}
+
+async fn api_v2_read_state(
+    State(inst_map): State<InstanceMap>,
+    Path((id, effective_canister_id)): Path<(InstanceId, String)>,
+    body: Bytes,
+) -> Result<impl IntoResponse, HttpError> {
+    let effective_canister_id = parse_effective_canister_id(&effective_canister_id)?;
+    let response = with_instance(&inst_map, &id, true, move |instance| {
+        http_interface::read_state(instance, effective_canister_id, body.to_vec())
+    })
+    .await?;
+    Ok(cbor_response(response))
+}
+
+// Runs `f` on a blocking thread on the IC instance with the given InstanceId.
+// Unless the call is read-only, it gets exclusive access to the instance.
+async fn with_instance<T: Send + 'static>(
+    inst_map: &InstanceMap,
+    id: &InstanceId,
+    read_only: bool,
+    f: impl FnOnce(&Instance) -> Result<T, HttpError> + Send + 'static,
+) -> Result<T, HttpError> {
+    let instance = inst_map.read().await.get(id).cloned().ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Id {} was not found in instance map.", id),
+        )
+    })?;
+    let result = if read_only {
+        let guard_instance = instance.read_owned().await;
+        tokio::task::spawn_blocking(move || f(&guard_instance)).await
+    } else {
+        let guard_instance = instance.write_owned().await;
+        tokio::task::spawn_blocking(move || f(&guard_instance)).await
     };
-    let config = StateMachineConfig::new(SubnetConfig::new(SubnetType::System), hypervisor_config);
-    StateMachineBuilder::new().with_config(Some(config)).build()
+    result.map_err(|err| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Call to instance {} failed: {}", id, err),
+        )
+    })?
+}
+
+// Whether the request leaves the instance unchanged, so that it can be served
+// concurrently with other such requests.
+fn is_read_only(request: &Request) -> bool {
+    matches!(
+        request,
+        RootKey
+            | Topology
+            | GetSubnet(_)
+            | GetCanisterHttp
+            | Time
+            | CanisterQueryCall(_)
+            | CanisterExists(_)
+            | CyclesBalance(_)
+            | ReadStableMemory(_)
+            | VerifyCanisterSig(_)
+            | GetExecutionProfile(_)
+    )
+}
+
+fn parse_effective_canister_id(effective_canister_id: &str) -> Result<CanisterId, HttpError> {
+    PrincipalId::from_str(effective_canister_id)
+        .map_err(|err| err.to_string())
+        .and_then(|principal| CanisterId::try_from(principal).map_err(|err| err.to_string()))
+        .map_err(|err| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Could not parse effective canister ID {}: {}",
+                    effective_canister_id, err
+                ),
+            )
+        })
+}
+
+fn cbor_response(body: Vec<u8>) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/cbor")], body)
 }
 
 fn rand_string(len: usize) -> String {
@@ -182,51 +344,141 @@ fn rand_string(len: usize) -> String {
 // ===================================================================================
 // Code borrowed from rs/state_machine_tests/src/main.rs
 
-fn call_sm(sm: &StateMachine, data: Request) -> String {
+fn call_instance_request(instance: &Instance, data: Request) -> String {
     match data {
-        RootKey => to_json_str(threshold_sig_public_key_to_der(sm.root_key()).unwrap()),
-        Time => to_json_str(sm.time()),
+        RootKey => {
+            to_json_str(threshold_sig_public_key_to_der(instance.root_subnet().root_key()).unwrap())
+        }
+        Topology => to_json_str(instance.topology()),
+        GetSubnet(canister_id) => to_json_str(instance.subnet_of(to_canister_id(canister_id)).map(
+            |sm| RawSubnetId {
+                subnet_id: sm.get_subnet_id().get().to_vec(),
+            },
+        )),
+        Time => to_json_str(instance.time()),
         SetTime(time) => {
-            sm.set_time(time);
+            instance.set_time(time);
             to_json_str(())
         }
         AdvanceTime(amount) => {
-            sm.advance_time(amount);
+            instance.advance_time(amount);
             to_json_str(())
         }
         CanisterUpdateCall(call) => {
-            let mut call = ParsedCanisterCall::from(call);
-            if call.canister_id == CanisterId::ic_00() && call.method == "create_canister" {
-                call.method = "provisional_create_canister_with_cycles".to_string();
-            }
-            let result =
-                sm.execute_ingress_as(call.sender, call.canister_id, call.method, call.arg);
+            let (call, effective_principal) = parse_update_call(call);
+            let result = instance
+                .route_update_call(
+                    call.sender,
+                    call.canister_id,
+                    &call.method,
+                    &call.arg,
+                    &effective_principal,
+                )
+                .and_then(|sm| {
+                    instance.execute_ingress(
+                        sm,
+                        call.sender,
+                        call.canister_id,
+                        call.method,
+                        call.arg,
+                    )
+                });
+            to_json_str(result)
+        }
+        SubmitCall(call) => {
+            let (call, effective_principal) = parse_update_call(call);
+            let result = instance
+                .route_update_call(
+                    call.sender,
+                    call.canister_id,
+                    &call.method,
+                    &call.arg,
+                    &effective_principal,
+                )
+                .map(|sm| {
+                    let msg_id =
+                        sm.send_ingress(call.sender, call.canister_id, call.method, call.arg);
+                    RawMessageId {
+                        subnet_id: RawSubnetId {
+                            subnet_id: sm.get_subnet_id().get().to_vec(),
+                        },
+                        message_id: msg_id.as_bytes().to_vec(),
+                    }
+                });
             to_json_str(result)
         }
+        AwaitCall(raw_msg_id) => to_json_str(instance.await_raw_call(&raw_msg_id)),
         CanisterQueryCall(call) => {
             let call = ParsedCanisterCall::from(call);
+            let sm = instance.subnet_of_or_default(call.canister_id);
             let result = sm.query_as(call.sender, call.canister_id, call.method, call.arg);
             to_json_str(result)
         }
-        CanisterExists(canister_id) => to_json_str(sm.canister_exists(to_canister_id(canister_id))),
+        CanisterExists(canister_id) => {
+            let canister_id = to_canister_id(canister_id);
+            to_json_str(
+                instance
+                    .state_machines()
+                    .any(|sm| sm.canister_exists(canister_id)),
+            )
+        }
         SetStableMemory(arg) => {
             let canister_id = CanisterId::try_from(arg.canister_id).expect("invalid canister id");
-            sm.set_stable_memory(canister_id, arg.data.as_ref());
+            instance
+                .subnet_of_or_default(canister_id)
+                .set_stable_memory(canister_id, arg.data.as_ref());
             to_json_str(())
         }
-        ReadStableMemory(canister_id) => to_json_str(sm.stable_memory(to_canister_id(canister_id))),
-        CyclesBalance(canister_id) => to_json_str(sm.cycle_balance(to_canister_id(canister_id))),
-        AddCycles(arg) => to_json_str(sm.add_cycles(
-            CanisterId::try_from(arg.canister_id).expect("invalid canister id"),
-            arg.amount,
-        )),
+        ReadStableMemory(canister_id) => {
+            let canister_id = to_canister_id(canister_id);
+            to_json_str(
+                instance
+                    .subnet_of_or_default(canister_id)
+                    .stable_memory(canister_id),
+            )
+        }
+        GetExecutionProfile(arg) => {
+            let canister_id = to_canister_id(arg.canister_id);
+            let sm = instance.subnet_of_or_default(canister_id);
+            to_json_str(
+                sm.execution_profile(canister_id, &arg.entry_point)
+                    .map(|profile| sm.folded_stacks(canister_id, &profile)),
+            )
+        }
+        CyclesBalance(canister_id) => {
+            let canister_id = to_canister_id(canister_id);
+            to_json_str(
+                instance
+                    .subnet_of_or_default(canister_id)
+                    .cycle_balance(canister_id),
+            )
+        }
+        AddCycles(arg) => {
+            let canister_id = CanisterId::try_from(arg.canister_id).expect("invalid canister id");
+            to_json_str(
+                instance
+                    .subnet_of_or_default(canister_id)
+                    .add_cycles(canister_id, arg.amount),
+            )
+        }
+        GetCanisterHttp => to_json_str(instance.canister_http_requests()),
+        MockCanisterHttp(mock) => {
+            let subnet_id = PrincipalId::try_from(mock.subnet_id.subnet_id)
+                .map(SubnetId::from)
+                .expect("invalid subnet id");
+            to_json_str(instance.mock_canister_http_response(
+                subnet_id,
+                CallbackId::from(mock.request_id),
+                mock.response,
+            ))
+        }
+        Checkpoint(arg) => to_json_str(instance.checkpoint(&arg.checkpoint_dir)),
         Tick => {
-            sm.tick();
+            instance.tick();
             to_json_str(())
         }
         RunUntilCompletion(arg) => {
-            sm.run_until_completion(arg.max_ticks as usize);
-            to_json_str(())
+            to_json_str(instance.run_until_completion(arg.max_ticks as usize))
         }
         VerifyCanisterSig(arg) => {
             type VerificationResult = Result<(), String>;
@@ -267,6 +519,17 @@ fn to_canister_id(raw_id: RawCanisterId) -> CanisterId {
     CanisterId::try_from(raw_id.canister_id).expect("invalid canister id")
 }
 
+// Parses an update call. Calls to `create_canister` are turned into calls to
+// `provisional_create_canister_with_cycles`, so that they need not pay cycles.
+fn parse_update_call(call: CanisterCall) -> (ParsedCanisterCall, RawEffectivePrincipal) {
+    let effective_principal = call.effective_principal.clone();
+    let mut call = ParsedCanisterCall::from(call);
+    if call.canister_id == CanisterId::ic_00() && call.method == "create_canister" {
+        call.method = "provisional_create_canister_with_cycles".to_string();
+    }
+    (call, effective_principal)
+}
+
 struct ParsedCanisterCall {
     sender: PrincipalId,
     canister_id: CanisterId,
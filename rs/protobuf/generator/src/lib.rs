@@ -366,6 +366,7 @@ fn build_types_proto(def: &Path, out: &Path) {
         def.join("types/v1/consensus.proto"),
         def.join("types/v1/ecdsa.proto"),
         def.join("types/v1/signature.proto"),
+        def.join("types/v1/query_stats.proto"),
     ];
     compile_protos(config, def, &files);
 }
@@ -375,6 +375,16 @@ pub struct ConsumedCyclesByUseCase {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterLogRecord {
+    #[prost(uint64, tag = "1")]
+    pub idx: u64,
+    #[prost(uint64, tag = "2")]
+    pub timestamp_nanos: u64,
+    #[prost(bytes = "vec", tag = "3")]
+    pub content: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CanisterChangeFromUser {
     #[prost(message, optional, tag = "1")]
     pub user_id: ::core::option::Option<super::super::super::types::v1::PrincipalId>,
@@ -415,6 +425,16 @@ pub struct CanisterControllersChange {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterLoadSnapshot {
+    #[prost(uint64, tag = "1")]
+    pub canister_version: u64,
+    #[prost(bytes = "vec", tag = "2")]
+    pub snapshot_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(uint64, tag = "3")]
+    pub taken_at_timestamp: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CanisterChange {
     #[prost(uint64, tag = "1")]
     pub timestamp_nanos: u64,
@@ -422,7 +442,7 @@ pub struct CanisterChange {
     pub canister_version: u64,
     #[prost(oneof = "canister_change::ChangeOrigin", tags = "3, 4")]
     pub change_origin: ::core::option::Option<canister_change::ChangeOrigin>,
-    #[prost(oneof = "canister_change::ChangeDetails", tags = "5, 6, 7, 8")]
+    #[prost(oneof = "canister_change::ChangeDetails", tags = "5, 6, 7, 8, 9")]
     pub change_details: ::core::option::Option<canister_change::ChangeDetails>,
 }
 /// Nested message and enum types in `CanisterChange`.
@@ -446,6 +466,8 @@ pub mod canister_change {
         CanisterCodeDeployment(super::CanisterCodeDeployment),
         #[prost(message, tag = "8")]
         CanisterControllersChange(super::CanisterControllersChange),
+        #[prost(message, tag = "9")]
+        CanisterLoadSnapshot(super::CanisterLoadSnapshot),
     }
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -526,6 +548,31 @@ pub struct CanisterStateBits {
     /// Resource reservation cycles.
     #[prost(message, optional, tag = "38")]
     pub reserved_balance: ::core::option::Option<super::super::queues::v1::Cycles>,
+    /// The local ID to be assigned to the next snapshot of this canister.
+    #[prost(uint64, tag = "39")]
+    pub next_snapshot_id: u64,
+    /// The total size of the snapshots taken of this canister, in bytes.
+    #[prost(uint64, tag = "40")]
+    pub snapshots_memory_usage: u64,
+    /// Who is allowed to fetch the canister's logs.
+    #[prost(enumeration = "LogVisibility", tag = "42")]
+    pub log_visibility: i32,
+    /// The records in the canister's log buffer.
+    #[prost(message, repeated, tag = "43")]
+    pub canister_log_records: ::prost::alloc::vec::Vec<CanisterLogRecord>,
+    /// The index to be assigned to the next canister log record.
+    #[prost(uint64, tag = "44")]
+    pub next_canister_log_record_idx: u64,
+    /// The limit on the canister's Wasm memory size, in bytes.
+    #[prost(uint64, optional, tag = "45")]
+    pub wasm_memory_limit: ::core::option::Option<u64>,
+    /// Query statistics aggregated through consensus.
+    #[prost(message, optional, tag = "46")]
+    pub total_query_stats: ::core::option::Option<TotalQueryStats>,
+    /// The chunks in the canister's Wasm chunk store. The chunks themselves are
+    /// stored in a separate page map file.
+    #[prost(message, optional, tag = "47")]
+    pub wasm_chunk_store_metadata: ::core::option::Option<WasmChunkStoreMetadata>,
     #[prost(oneof = "canister_state_bits::CanisterStatus", tags = "11, 12, 13")]
     pub canister_status: ::core::option::Option<canister_state_bits::CanisterStatus>,
 }
@@ -542,6 +589,71 @@ pub mod canister_state_bits {
         Stopped(super::CanisterStatusStopped),
     }
 }
+/// The location of a chunk in the page map of a Wasm chunk store.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WasmChunkData {
+    /// The SHA-256 hash of the chunk.
+    #[prost(bytes = "vec", tag = "1")]
+    pub hash: ::prost::alloc::vec::Vec<u8>,
+    /// The chunk is stored at offset `index * CHUNK_SIZE`.
+    #[prost(uint64, tag = "2")]
+    pub index: u64,
+    /// The length of the chunk, in bytes.
+    #[prost(uint64, tag = "3")]
+    pub length: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WasmChunkStoreMetadata {
+    #[prost(message, repeated, tag = "1")]
+    pub chunks: ::prost::alloc::vec::Vec<WasmChunkData>,
+    /// The total length of all stored chunks, in bytes.
+    #[prost(uint64, tag = "2")]
+    pub size: u64,
+}
+/// Query statistics of a canister, aggregated over all epochs. Each value is
+/// stored as u128::to_le_bytes().
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TotalQueryStats {
+    #[prost(bytes = "vec", tag = "1")]
+    pub num_calls: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub num_instructions: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub ingress_payload_size: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "4")]
+    pub egress_payload_size: ::prost::alloc::vec::Vec<u8>,
+}
+/// A snapshot of a canister's state, taken by a `take_canister_snapshot` call.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterSnapshotBits {
+    #[prost(bytes = "vec", tag = "1")]
+    pub snapshot_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub canister_id: ::core::option::Option<super::super::super::types::v1::CanisterId>,
+    #[prost(uint64, tag = "3")]
+    pub taken_at_timestamp: u64,
+    #[prost(uint64, tag = "4")]
+    pub canister_version: u64,
+    #[prost(bytes = "vec", tag = "5")]
+    pub binary_hash: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "6")]
+    pub certified_data: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, repeated, tag = "7")]
+    pub exported_globals: ::prost::alloc::vec::Vec<Global>,
+    #[prost(uint64, tag = "8")]
+    pub wasm_memory_size: u64,
+    #[prost(uint64, tag = "9")]
+    pub stable_memory_size: u64,
+    #[prost(uint64, tag = "10")]
+    pub total_size: u64,
+    /// Canister global timer, in nanoseconds since Unix epoch.
+    #[prost(uint64, optional, tag = "11")]
+    pub global_timer_nanos: ::core::option::Option<u64>,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -627,3 +739,24 @@ impl CyclesUseCase {
         }
     }
 }
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum LogVisibility {
+    Unspecified = 0,
+    Controllers = 1,
+    Public = 2,
+}
+impl LogVisibility {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            LogVisibility::Unspecified => "LOG_VISIBILITY_UNSPECIFIED",
+            LogVisibility::Controllers => "LOG_VISIBILITY_CONTROLLERS",
+            LogVisibility::Public => "LOG_VISIBILITY_PUBLIC",
+        }
+    }
+}
@@ -118,6 +118,52 @@ pub struct SignWithEcdsaContextTree {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SignWithSchnorrContext {
+    #[prost(message, optional, tag = "1")]
+    pub request: ::core::option::Option<super::super::queues::v1::Request>,
+    #[prost(message, optional, tag = "2")]
+    pub key_id: ::core::option::Option<super::super::super::registry::crypto::v1::SchnorrKeyId>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub message: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", repeated, tag = "4")]
+    pub derivation_path: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    #[prost(uint64, tag = "5")]
+    pub batch_time: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SignWithSchnorrContextTree {
+    #[prost(uint64, tag = "1")]
+    pub callback_id: u64,
+    #[prost(message, optional, tag = "2")]
+    pub context: ::core::option::Option<SignWithSchnorrContext>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VetKdContext {
+    #[prost(message, optional, tag = "1")]
+    pub request: ::core::option::Option<super::super::queues::v1::Request>,
+    #[prost(message, optional, tag = "2")]
+    pub key_id: ::core::option::Option<super::super::super::registry::crypto::v1::VetKdKeyId>,
+    #[prost(bytes = "vec", repeated, tag = "3")]
+    pub derivation_path: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    #[prost(bytes = "vec", tag = "4")]
+    pub derivation_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "5")]
+    pub encryption_public_key: ::prost::alloc::vec::Vec<u8>,
+    #[prost(uint64, tag = "6")]
+    pub batch_time: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VetKdContextTree {
+    #[prost(uint64, tag = "1")]
+    pub callback_id: u64,
+    #[prost(message, optional, tag = "2")]
+    pub context: ::core::option::Option<VetKdContext>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct HttpHeader {
     #[prost(string, tag = "1")]
     pub name: ::prost::alloc::string::String,
@@ -145,6 +191,9 @@ pub struct CanisterHttpRequestContext {
     pub max_response_bytes: ::core::option::Option<u64>,
     #[prost(message, optional, tag = "10")]
     pub transform_context: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    /// Set if the request is performed only by the specified node.
+    #[prost(message, optional, tag = "11")]
+    pub non_replicated_node: ::core::option::Option<super::super::super::types::v1::NodeId>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -249,6 +298,10 @@ pub struct SubnetCallContextManager {
         ::prost::alloc::vec::Vec<BitcoinSendTransactionInternalContextTree>,
     #[prost(message, repeated, tag = "10")]
     pub install_code_contexts: ::prost::alloc::vec::Vec<InstallCodeContextTree>,
+    #[prost(message, repeated, tag = "11")]
+    pub vetkd_contexts: ::prost::alloc::vec::Vec<VetKdContextTree>,
+    #[prost(message, repeated, tag = "12")]
+    pub sign_with_schnorr_contexts: ::prost::alloc::vec::Vec<SignWithSchnorrContextTree>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -332,6 +385,28 @@ pub struct SystemMetadata {
         ::prost::alloc::vec::Vec<BitcoinGetSuccessorsFollowUpResponses>,
     #[prost(message, repeated, tag = "19")]
     pub node_public_keys: ::prost::alloc::vec::Vec<NodePublicKeyEntry>,
+    #[prost(message, optional, tag = "20")]
+    pub epoch_query_stats: ::core::option::Option<RawQueryStats>,
+}
+/// Query statistics reported by a single node during the current epoch.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NodeQueryStats {
+    #[prost(message, optional, tag = "1")]
+    pub node_id: ::core::option::Option<super::super::super::types::v1::NodeId>,
+    #[prost(message, repeated, tag = "2")]
+    pub canister_stats:
+        ::prost::alloc::vec::Vec<super::super::super::types::v1::CanisterQueryStats>,
+}
+/// Query statistics received through consensus that have not been aggregated
+/// into the canister states yet.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RawQueryStats {
+    #[prost(uint64, optional, tag = "1")]
+    pub epoch: ::core::option::Option<u64>,
+    #[prost(message, repeated, tag = "2")]
+    pub node_stats: ::prost::alloc::vec::Vec<NodeQueryStats>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -355,6 +430,9 @@ pub enum HttpMethod {
     Get = 1,
     Post = 2,
     Head = 3,
+    Put = 4,
+    Delete = 5,
+    Patch = 6,
 }
 impl HttpMethod {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -367,6 +445,9 @@ impl HttpMethod {
             HttpMethod::Get => "HTTP_METHOD_GET",
             HttpMethod::Post => "HTTP_METHOD_POST",
             HttpMethod::Head => "HTTP_METHOD_HEAD",
+            HttpMethod::Put => "HTTP_METHOD_PUT",
+            HttpMethod::Delete => "HTTP_METHOD_DELETE",
+            HttpMethod::Patch => "HTTP_METHOD_PATCH",
         }
     }
 }
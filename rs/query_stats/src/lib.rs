@@ -0,0 +1,200 @@
+//! This crate implements the collection and aggregation of per-canister
+//! query statistics.
+//!
+//! Query calls are executed by single nodes and therefore do not go through
+//! consensus. To still make statistics about them available in the replicated
+//! state, every node collects the statistics of the queries it executed
+//! locally during an epoch (see [`QueryStatsCollector`]). Once the epoch is
+//! over, block makers include their locally collected statistics in the
+//! query stats section of the block payload (see
+//! [`QueryStatsPayloadBuilderImpl`]). Finally, the statistics reported by the
+//! different nodes are aggregated deterministically in the replicated state
+//! (see [`deliver_query_stats`]).
+
+mod metrics;
+mod payload_builder;
+mod state_machine;
+pub mod test_utils;
+
+use crate::metrics::QueryStatsCollectorMetrics;
+use ic_config::{execution_environment::Config, flag_status::FlagStatus};
+use ic_logger::{debug, ReplicaLogger};
+use ic_metrics::MetricsRegistry;
+use ic_types::{
+    batch::{epoch_from_height, QueryStats, QueryStatsEpoch},
+    CanisterId, Height,
+};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+pub use crate::{
+    payload_builder::{QueryStatsPayloadBuilderImpl, QueryStatsPayloadBuilderParams},
+    state_machine::deliver_query_stats,
+};
+
+/// Statistics collected locally by a node, shared between the
+/// [`QueryStatsCollector`] and the payload builder.
+#[derive(Default)]
+struct CollectorState {
+    /// The epoch for which statistics are currently being collected.
+    current_epoch: Option<QueryStatsEpoch>,
+    /// The statistics collected during `current_epoch`.
+    current_stats: BTreeMap<CanisterId, QueryStats>,
+    /// The statistics of the last completed epoch, which are reported to the
+    /// other nodes through the block payload.
+    previous_stats: Option<(QueryStatsEpoch, BTreeMap<CanisterId, QueryStats>)>,
+}
+
+/// Collects the statistics of the query calls executed on this node.
+#[derive(Clone)]
+pub struct QueryStatsCollector {
+    enabled: bool,
+    epoch_length: u64,
+    state: Arc<Mutex<CollectorState>>,
+    metrics: Arc<QueryStatsCollectorMetrics>,
+    log: ReplicaLogger,
+}
+
+impl QueryStatsCollector {
+    /// Updates the current epoch based on the height of the certified state
+    /// that queries are executed against.
+    ///
+    /// When a new epoch starts, the statistics collected so far are moved
+    /// aside, so that they can be included in the next block payloads.
+    pub fn set_epoch_from_height(&self, height: Height) {
+        if !self.enabled {
+            return;
+        }
+
+        let epoch = epoch_from_height(height, self.epoch_length);
+        let mut state = self.state.lock().unwrap();
+        match state.current_epoch {
+            Some(current_epoch) if current_epoch >= epoch => return,
+            Some(current_epoch) => {
+                let stats = std::mem::take(&mut state.current_stats);
+                debug!(
+                    self.log,
+                    "Completed query stats epoch {} with statistics for {} canisters",
+                    current_epoch,
+                    stats.len()
+                );
+                state.previous_stats = Some((current_epoch, stats));
+            }
+            None => {}
+        }
+        state.current_epoch = Some(epoch);
+        self.metrics.current_epoch.set(epoch.get() as i64);
+        self.metrics.num_canister_ids.set(0);
+    }
+
+    /// Records the statistics of queries executed on the given canister.
+    pub fn register_query_statistics(&self, canister_id: CanisterId, stats: &QueryStats) {
+        if !self.enabled {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state
+            .current_stats
+            .entry(canister_id)
+            .or_default()
+            .saturating_accumulate(stats);
+        self.metrics
+            .num_canister_ids
+            .set(state.current_stats.len() as i64);
+    }
+}
+
+/// Creates the [`QueryStatsCollector`] used by the query handler and the
+/// parameters to create the matching payload builder.
+pub fn init_query_stats(
+    log: ReplicaLogger,
+    config: &Config,
+    metrics_registry: &MetricsRegistry,
+) -> (QueryStatsCollector, QueryStatsPayloadBuilderParams) {
+    let state = Arc::new(Mutex::new(CollectorState::default()));
+    let epoch_length = config.query_stats_epoch_length;
+    (
+        QueryStatsCollector {
+            enabled: config.query_stats_aggregation == FlagStatus::Enabled,
+            epoch_length,
+            state: state.clone(),
+            metrics: Arc::new(QueryStatsCollectorMetrics::new(metrics_registry)),
+            log,
+        },
+        QueryStatsPayloadBuilderParams {
+            state,
+            epoch_length,
+            metrics_registry: metrics_registry.clone(),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_test_utilities::types::ids::canister_test_id;
+    use ic_test_utilities_logger::with_test_replica_logger;
+
+    fn stats(num_calls: u32) -> QueryStats {
+        QueryStats {
+            num_calls,
+            num_instructions: 100 * num_calls as u64,
+            ingress_payload_size: 10,
+            egress_payload_size: 20,
+        }
+    }
+
+    #[test]
+    fn collector_moves_stats_aside_on_new_epoch() {
+        with_test_replica_logger(|log| {
+            let config = Config {
+                query_stats_aggregation: FlagStatus::Enabled,
+                query_stats_epoch_length: 10,
+                ..Config::default()
+            };
+            let (collector, _) = init_query_stats(log, &config, &MetricsRegistry::new());
+
+            collector.set_epoch_from_height(Height::new(5));
+            collector.register_query_statistics(canister_test_id(1), &stats(1));
+            collector.register_query_statistics(canister_test_id(1), &stats(2));
+            // Still in epoch 0.
+            collector.set_epoch_from_height(Height::new(9));
+            assert!(collector.state.lock().unwrap().previous_stats.is_none());
+
+            collector.set_epoch_from_height(Height::new(10));
+            collector.register_query_statistics(canister_test_id(2), &stats(1));
+
+            let state = collector.state.lock().unwrap();
+            assert_eq!(state.current_epoch, Some(QueryStatsEpoch::from(1)));
+            let (epoch, previous_stats) = state.previous_stats.as_ref().unwrap();
+            assert_eq!(*epoch, QueryStatsEpoch::from(0));
+            assert_eq!(previous_stats.len(), 1);
+            assert_eq!(
+                previous_stats.get(&canister_test_id(1)).unwrap().num_calls,
+                3
+            );
+            assert_eq!(state.current_stats.len(), 1);
+        });
+    }
+
+    #[test]
+    fn disabled_collector_does_not_collect() {
+        with_test_replica_logger(|log| {
+            let config = Config {
+                query_stats_aggregation: FlagStatus::Disabled,
+                ..Config::default()
+            };
+            let (collector, _) = init_query_stats(log, &config, &MetricsRegistry::new());
+
+            collector.set_epoch_from_height(Height::new(0));
+            collector.register_query_statistics(canister_test_id(1), &stats(1));
+
+            let state = collector.state.lock().unwrap();
+            assert_eq!(state.current_epoch, None);
+            assert!(state.current_stats.is_empty());
+        });
+    }
+}
@@ -0,0 +1,53 @@
+//! This module contains the metrics of the query stats components.
+
+use ic_metrics::{buckets::decimal_buckets, MetricsRegistry};
+use prometheus::{HistogramVec, IntCounterVec, IntGauge};
+
+pub(crate) struct QueryStatsCollectorMetrics {
+    /// The epoch for which statistics are currently being collected.
+    pub current_epoch: IntGauge,
+    /// The number of canisters for which statistics were collected in the
+    /// current epoch.
+    pub num_canister_ids: IntGauge,
+}
+
+impl QueryStatsCollectorMetrics {
+    pub(crate) fn new(metrics_registry: &MetricsRegistry) -> Self {
+        Self {
+            current_epoch: metrics_registry.int_gauge(
+                "query_stats_collector_current_epoch",
+                "The epoch for which query statistics are currently being collected.",
+            ),
+            num_canister_ids: metrics_registry.int_gauge(
+                "query_stats_collector_num_canister_ids",
+                "The number of canisters for which query statistics were collected in the current epoch.",
+            ),
+        }
+    }
+}
+
+pub(crate) struct QueryStatsPayloadBuilderMetrics {
+    /// Records the time it took to perform an operation.
+    pub op_duration: HistogramVec,
+    /// The number of payloads that failed validation, by reason.
+    pub invalid_payloads: IntCounterVec,
+}
+
+impl QueryStatsPayloadBuilderMetrics {
+    pub(crate) fn new(metrics_registry: &MetricsRegistry) -> Self {
+        Self {
+            op_duration: metrics_registry.histogram_vec(
+                "query_stats_payload_builder_op_duration",
+                "The time it took the query stats payload builder to perform an operation",
+                // 0.1ms - 5s
+                decimal_buckets(-4, 0),
+                &["operation"],
+            ),
+            invalid_payloads: metrics_registry.int_counter_vec(
+                "query_stats_payload_builder_invalid_payloads",
+                "The number of query stats payloads that failed validation, by reason",
+                &["reason"],
+            ),
+        }
+    }
+}
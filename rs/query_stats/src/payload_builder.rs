@@ -0,0 +1,576 @@
+use crate::{metrics::QueryStatsPayloadBuilderMetrics, CollectorState};
+use ic_interfaces::{
+    batch_payload::{BatchPayloadBuilder, PastPayload, ProposalContext},
+    consensus::PayloadValidationError,
+    query_stats::{
+        QueryStatsPayloadValidationError, QueryStatsPermanentValidationError,
+        QueryStatsTransientValidationError,
+    },
+    validation::{ValidationError, ValidationResult},
+};
+use ic_interfaces_state_manager::{Labeled, StateReader};
+use ic_logger::{warn, ReplicaLogger};
+use ic_metrics::MetricsRegistry;
+use ic_replicated_state::ReplicatedState;
+use ic_types::{
+    batch::{
+        epoch_from_height, CanisterQueryStats, QueryStatsEpoch, QueryStatsPayload,
+        ValidationContext,
+    },
+    CanisterId, Height, NodeId, NumBytes,
+};
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+};
+
+/// The parameters returned by [`crate::init_query_stats`], from which the
+/// payload builder is created once all its dependencies are available.
+pub struct QueryStatsPayloadBuilderParams {
+    pub(crate) state: Arc<Mutex<CollectorState>>,
+    pub(crate) epoch_length: u64,
+    pub(crate) metrics_registry: MetricsRegistry,
+}
+
+impl QueryStatsPayloadBuilderParams {
+    pub fn into_payload_builder(
+        self,
+        state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+        node_id: NodeId,
+        log: ReplicaLogger,
+    ) -> QueryStatsPayloadBuilderImpl {
+        QueryStatsPayloadBuilderImpl {
+            state: self.state,
+            epoch_length: self.epoch_length,
+            state_reader,
+            node_id,
+            metrics: QueryStatsPayloadBuilderMetrics::new(&self.metrics_registry),
+            log,
+        }
+    }
+}
+
+/// Includes the statistics collected by this node during the last completed
+/// epoch in the block payload.
+///
+/// Every node reports the statistics of a canister at most once per epoch,
+/// and only in blocks it made itself. Validation therefore checks that the
+/// proposer in the payload is the block maker and a member of the subnet.
+/// The aggregation uses the median of the reported values, which limits the
+/// influence of any single node.
+pub struct QueryStatsPayloadBuilderImpl {
+    state: Arc<Mutex<CollectorState>>,
+    epoch_length: u64,
+    state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+    node_id: NodeId,
+    metrics: QueryStatsPayloadBuilderMetrics,
+    log: ReplicaLogger,
+}
+
+impl BatchPayloadBuilder for QueryStatsPayloadBuilderImpl {
+    fn build_payload(
+        &self,
+        _height: Height,
+        max_size: NumBytes,
+        past_payloads: &[PastPayload],
+        context: &ValidationContext,
+    ) -> Vec<u8> {
+        let _time = self
+            .metrics
+            .op_duration
+            .with_label_values(&["build"])
+            .start_timer();
+
+        let Some(epoch) = self.expected_epoch(context) else {
+            return vec![];
+        };
+
+        let stats = match &self.state.lock().unwrap().previous_stats {
+            Some((previous_epoch, stats)) if *previous_epoch == epoch => stats.clone(),
+            _ => return vec![],
+        };
+
+        let already_reported = match self.certified_state(context).map(|state| {
+            self.already_reported_canisters(self.node_id, epoch, past_payloads, state.get_ref())
+        }) {
+            Ok(already_reported) => already_reported,
+            Err(err) => {
+                warn!(self.log, "Failed to build query stats payload: {:?}", err);
+                return vec![];
+            }
+        };
+
+        QueryStatsPayload {
+            epoch,
+            proposer: self.node_id,
+            stats: stats
+                .into_iter()
+                .filter(|(canister_id, _)| !already_reported.contains(canister_id))
+                .map(|(canister_id, stats)| CanisterQueryStats { canister_id, stats })
+                .collect(),
+        }
+        .serialize_with_limit(max_size)
+    }
+
+    fn validate_payload(
+        &self,
+        _height: Height,
+        payload: &[u8],
+        past_payloads: &[PastPayload],
+        proposal_context: &ProposalContext,
+    ) -> ValidationResult<PayloadValidationError> {
+        let _time = self
+            .metrics
+            .op_duration
+            .with_label_values(&["validate"])
+            .start_timer();
+
+        // Empty payloads are always valid
+        if payload.is_empty() {
+            return Ok(());
+        }
+
+        self.validate_payload_impl(payload, past_payloads, proposal_context)
+            .map_err(|err| {
+                if let ValidationError::Permanent(err) = &err {
+                    let reason = match err {
+                        QueryStatsPermanentValidationError::DeserializationFailed(_) => {
+                            "deserialization_failed"
+                        }
+                        QueryStatsPermanentValidationError::EpochMismatch { .. } => {
+                            "epoch_mismatch"
+                        }
+                        QueryStatsPermanentValidationError::DuplicateCanisterId(_) => {
+                            "duplicate_canister_id"
+                        }
+                        QueryStatsPermanentValidationError::InvalidProposer { .. } => {
+                            "invalid_proposer"
+                        }
+                        QueryStatsPermanentValidationError::ProposerNotSubnetMember(_) => {
+                            "proposer_not_subnet_member"
+                        }
+                    };
+                    self.metrics
+                        .invalid_payloads
+                        .with_label_values(&[reason])
+                        .inc();
+                }
+                PayloadValidationError::from(err)
+            })
+    }
+}
+
+impl QueryStatsPayloadBuilderImpl {
+    /// Returns the epoch whose statistics are reported in payloads built under
+    /// the given context, i.e. the last epoch completed by the certified state.
+    fn expected_epoch(&self, context: &ValidationContext) -> Option<QueryStatsEpoch> {
+        epoch_from_height(context.certified_height, self.epoch_length)
+            .get()
+            .checked_sub(1)
+            .map(QueryStatsEpoch::from)
+    }
+
+    fn validate_payload_impl(
+        &self,
+        payload: &[u8],
+        past_payloads: &[PastPayload],
+        proposal_context: &ProposalContext,
+    ) -> Result<(), QueryStatsPayloadValidationError> {
+        let context = proposal_context.validation_context;
+        let payload = match QueryStatsPayload::deserialize(payload) {
+            Ok(Some(payload)) => payload,
+            Ok(None) => return Ok(()),
+            Err(err) => {
+                return Err(ValidationError::Permanent(
+                    QueryStatsPermanentValidationError::DeserializationFailed(err),
+                ))
+            }
+        };
+
+        let expected = self.expected_epoch(context);
+        if expected != Some(payload.epoch) {
+            return Err(ValidationError::Permanent(
+                QueryStatsPermanentValidationError::EpochMismatch {
+                    expected,
+                    received: payload.epoch,
+                },
+            ));
+        }
+
+        // The statistics are attributed to the proposer, so a block maker must
+        // not be able to report them on behalf of other nodes.
+        if payload.proposer != proposal_context.proposer {
+            return Err(ValidationError::Permanent(
+                QueryStatsPermanentValidationError::InvalidProposer {
+                    expected: proposal_context.proposer,
+                    received: payload.proposer,
+                },
+            ));
+        }
+
+        let state = self.certified_state(context)?;
+        let state = state.get_ref();
+        let is_member = state
+            .metadata
+            .network_topology
+            .subnets
+            .get(&state.metadata.own_subnet_id)
+            .map_or(false, |subnet| subnet.nodes.contains(&payload.proposer));
+        if !is_member {
+            return Err(ValidationError::Permanent(
+                QueryStatsPermanentValidationError::ProposerNotSubnetMember(payload.proposer),
+            ));
+        }
+
+        let mut reported =
+            self.already_reported_canisters(payload.proposer, payload.epoch, past_payloads, state);
+        for stats in &payload.stats {
+            if !reported.insert(stats.canister_id) {
+                return Err(ValidationError::Permanent(
+                    QueryStatsPermanentValidationError::DuplicateCanisterId(stats.canister_id),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the state at the certified height of the given context.
+    fn certified_state(
+        &self,
+        context: &ValidationContext,
+    ) -> Result<Labeled<Arc<ReplicatedState>>, QueryStatsPayloadValidationError> {
+        self.state_reader
+            .get_state_at(context.certified_height)
+            .map_err(|_| {
+                ValidationError::Transient(QueryStatsTransientValidationError::StateUnavailable(
+                    context.certified_height,
+                ))
+            })
+    }
+
+    /// Returns the canisters for which `proposer` already reported statistics
+    /// of the given epoch, either in the past payloads or in the certified
+    /// state.
+    fn already_reported_canisters(
+        &self,
+        proposer: NodeId,
+        epoch: QueryStatsEpoch,
+        past_payloads: &[PastPayload],
+        state: &ReplicatedState,
+    ) -> BTreeSet<CanisterId> {
+        let mut reported = BTreeSet::new();
+        let epoch_query_stats = &state.metadata.epoch_query_stats;
+        if epoch_query_stats.epoch == Some(epoch) {
+            reported.extend(
+                epoch_query_stats
+                    .stats
+                    .iter()
+                    .filter(|(_, stats_by_node)| stats_by_node.contains_key(&proposer))
+                    .map(|(canister_id, _)| *canister_id),
+            );
+        }
+
+        for past_payload in past_payloads {
+            // Past payloads have been validated, so they can be deserialized.
+            if let Ok(Some(past_payload)) = QueryStatsPayload::deserialize(past_payload.payload) {
+                if past_payload.epoch == epoch && past_payload.proposer == proposer {
+                    reported.extend(past_payload.stats.iter().map(|stats| stats.canister_id));
+                }
+            }
+        }
+
+        reported
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_query_stats;
+    use ic_config::{execution_environment::Config, flag_status::FlagStatus};
+    use ic_interfaces::consensus::PayloadPermanentError;
+    use ic_interfaces_state_manager_mocks::MockStateManager;
+    use ic_logger::replica_logger::no_op_logger;
+    use ic_registry_subnet_type::SubnetType;
+    use ic_replicated_state::metadata_state::SubnetTopology;
+    use ic_test_utilities::{
+        mock_time,
+        types::ids::{canister_test_id, node_test_id, subnet_test_id},
+    };
+    use ic_types::{batch::QueryStats, crypto::CryptoHash, crypto::CryptoHashOf, RegistryVersion};
+
+    const EPOCH_LENGTH: u64 = 10;
+
+    fn stats(num_calls: u32) -> QueryStats {
+        QueryStats {
+            num_calls,
+            num_instructions: 1_000 * num_calls as u64,
+            ingress_payload_size: 10,
+            egress_payload_size: 20,
+        }
+    }
+
+    fn context(certified_height: u64) -> ValidationContext {
+        ValidationContext {
+            registry_version: RegistryVersion::from(1),
+            certified_height: Height::new(certified_height),
+            time: mock_time(),
+        }
+    }
+
+    /// Returns the context of a block made by the given node.
+    fn proposal_context(proposer: u64, validation_context: &ValidationContext) -> ProposalContext {
+        ProposalContext {
+            proposer: node_test_id(proposer),
+            validation_context,
+        }
+    }
+
+    fn past_payload(payload: &[u8]) -> PastPayload {
+        PastPayload {
+            height: Height::new(0),
+            time: mock_time(),
+            block_hash: CryptoHashOf::from(CryptoHash(vec![])),
+            payload,
+        }
+    }
+
+    /// Sets up a payload builder for node 1, whose collector has completed
+    /// epoch 0 with statistics for canisters 1 to 3.
+    fn setup(state: ReplicatedState) -> QueryStatsPayloadBuilderImpl {
+        let config = Config {
+            query_stats_aggregation: FlagStatus::Enabled,
+            query_stats_epoch_length: EPOCH_LENGTH,
+            ..Config::default()
+        };
+        let (collector, params) =
+            init_query_stats(no_op_logger(), &config, &MetricsRegistry::new());
+        collector.set_epoch_from_height(Height::new(0));
+        for i in 1..=3 {
+            collector.register_query_statistics(canister_test_id(i), &stats(i as u32));
+        }
+        collector.set_epoch_from_height(Height::new(EPOCH_LENGTH));
+
+        let mut state_manager = MockStateManager::new();
+        state_manager
+            .expect_get_state_at()
+            .return_const(Ok(Labeled::new(Height::new(0), Arc::new(state))));
+
+        params.into_payload_builder(Arc::new(state_manager), node_test_id(1), no_op_logger())
+    }
+
+    /// Returns a state of subnet 1, whose members are nodes 1 to 4.
+    fn empty_state() -> ReplicatedState {
+        let mut state = ReplicatedState::new(subnet_test_id(1), SubnetType::Application);
+        state.metadata.network_topology.subnets.insert(
+            subnet_test_id(1),
+            SubnetTopology {
+                nodes: (1..=4).map(node_test_id).collect(),
+                ..SubnetTopology::default()
+            },
+        );
+        state
+    }
+
+    /// Returns a payload of node `proposer` reporting epoch 0 statistics of
+    /// canister 1.
+    fn payload_of(proposer: u64) -> Vec<u8> {
+        QueryStatsPayload {
+            epoch: QueryStatsEpoch::from(0),
+            proposer: node_test_id(proposer),
+            stats: vec![CanisterQueryStats {
+                canister_id: canister_test_id(1),
+                stats: stats(1),
+            }],
+        }
+        .serialize_with_limit(NumBytes::new(1024))
+    }
+
+    fn reported_canisters(payload: &[u8]) -> Vec<CanisterId> {
+        QueryStatsPayload::deserialize(payload)
+            .unwrap()
+            .map(|payload| payload.stats.iter().map(|s| s.canister_id).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn builds_payload_for_previous_epoch() {
+        let builder = setup(empty_state());
+
+        // The certified state is still in epoch 0.
+        let payload = builder.build_payload(
+            Height::new(1),
+            NumBytes::new(1024),
+            &[],
+            &context(EPOCH_LENGTH - 1),
+        );
+        assert!(payload.is_empty());
+
+        let context = context(EPOCH_LENGTH);
+        let payload = builder.build_payload(Height::new(1), NumBytes::new(1024), &[], &context);
+        let decoded = QueryStatsPayload::deserialize(&payload).unwrap().unwrap();
+        assert_eq!(decoded.epoch, QueryStatsEpoch::from(0));
+        assert_eq!(decoded.proposer, node_test_id(1));
+        assert_eq!(
+            reported_canisters(&payload),
+            vec![
+                canister_test_id(1),
+                canister_test_id(2),
+                canister_test_id(3)
+            ]
+        );
+        assert!(builder
+            .validate_payload(
+                Height::new(1),
+                &payload,
+                &[],
+                &proposal_context(1, &context)
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn does_not_report_canisters_twice() {
+        let mut state = empty_state();
+        state.metadata.epoch_query_stats.epoch = Some(QueryStatsEpoch::from(0));
+        state
+            .metadata
+            .epoch_query_stats
+            .stats
+            .entry(canister_test_id(1))
+            .or_default()
+            .insert(node_test_id(1), stats(1));
+        let builder = setup(state);
+        let context = context(EPOCH_LENGTH);
+
+        let past = QueryStatsPayload {
+            epoch: QueryStatsEpoch::from(0),
+            proposer: node_test_id(1),
+            stats: vec![CanisterQueryStats {
+                canister_id: canister_test_id(2),
+                stats: stats(2),
+            }],
+        }
+        .serialize_with_limit(NumBytes::new(1024));
+
+        let payload = builder.build_payload(
+            Height::new(2),
+            NumBytes::new(1024),
+            &[past_payload(&past)],
+            &context,
+        );
+        assert_eq!(reported_canisters(&payload), vec![canister_test_id(3)]);
+
+        // A payload reporting canister 2 again is rejected.
+        assert!(matches!(
+            builder.validate_payload(
+                Height::new(2),
+                &past,
+                &[past_payload(&past)],
+                &proposal_context(1, &context)
+            ),
+            Err(ValidationError::Permanent(
+                PayloadPermanentError::QueryStatsPayloadValidationError(
+                    QueryStatsPermanentValidationError::DuplicateCanisterId(canister_id)
+                )
+            )) if canister_id == canister_test_id(2)
+        ));
+    }
+
+    #[test]
+    fn rejects_payload_with_wrong_epoch() {
+        let builder = setup(empty_state());
+        let payload = QueryStatsPayload {
+            epoch: QueryStatsEpoch::from(1),
+            proposer: node_test_id(2),
+            stats: vec![CanisterQueryStats {
+                canister_id: canister_test_id(1),
+                stats: stats(1),
+            }],
+        }
+        .serialize_with_limit(NumBytes::new(1024));
+
+        assert!(matches!(
+            builder.validate_payload(
+                Height::new(1),
+                &payload,
+                &[],
+                &proposal_context(2, &context(EPOCH_LENGTH))
+            ),
+            Err(ValidationError::Permanent(
+                PayloadPermanentError::QueryStatsPayloadValidationError(
+                    QueryStatsPermanentValidationError::EpochMismatch { .. }
+                )
+            ))
+        ));
+    }
+
+    #[test]
+    fn rejects_garbage_payload() {
+        let builder = setup(empty_state());
+        assert!(matches!(
+            builder.validate_payload(
+                Height::new(1),
+                &[0xff; 8],
+                &[],
+                &proposal_context(1, &context(EPOCH_LENGTH))
+            ),
+            Err(ValidationError::Permanent(
+                PayloadPermanentError::QueryStatsPayloadValidationError(
+                    QueryStatsPermanentValidationError::DeserializationFailed(_)
+                )
+            ))
+        ));
+    }
+
+    #[test]
+    fn rejects_payload_not_proposed_by_block_maker() {
+        let builder = setup(empty_state());
+        let context = context(EPOCH_LENGTH);
+
+        assert!(builder
+            .validate_payload(
+                Height::new(1),
+                &payload_of(2),
+                &[],
+                &proposal_context(2, &context)
+            )
+            .is_ok());
+
+        // Node 1 must not be able to report statistics on behalf of node 2.
+        assert!(matches!(
+            builder.validate_payload(
+                Height::new(1),
+                &payload_of(2),
+                &[],
+                &proposal_context(1, &context)
+            ),
+            Err(ValidationError::Permanent(
+                PayloadPermanentError::QueryStatsPayloadValidationError(
+                    QueryStatsPermanentValidationError::InvalidProposer { expected, received }
+                )
+            )) if expected == node_test_id(1) && received == node_test_id(2)
+        ));
+    }
+
+    #[test]
+    fn rejects_payload_of_non_member() {
+        let builder = setup(empty_state());
+        let context = context(EPOCH_LENGTH);
+
+        assert!(matches!(
+            builder.validate_payload(
+                Height::new(1),
+                &payload_of(5),
+                &[],
+                &proposal_context(5, &context)
+            ),
+            Err(ValidationError::Permanent(
+                PayloadPermanentError::QueryStatsPayloadValidationError(
+                    QueryStatsPermanentValidationError::ProposerNotSubnetMember(node_id)
+                )
+            )) if node_id == node_test_id(5)
+        ));
+    }
+}
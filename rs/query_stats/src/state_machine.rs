@@ -0,0 +1,195 @@
+use ic_logger::{info, ReplicaLogger};
+use ic_replicated_state::ReplicatedState;
+use ic_types::{
+    batch::{QueryStats, QueryStatsPayload},
+    NodeId,
+};
+use std::collections::BTreeMap;
+
+/// Returns the median of the given values, choosing the lower of the two
+/// middle values if the number of values is even.
+fn median<T: Copy + Ord>(mut values: Vec<T>) -> T {
+    values.sort_unstable();
+    values[(values.len() - 1) / 2]
+}
+
+/// Aggregates the statistics of a single canister reported by different nodes.
+///
+/// Each field is aggregated separately as the median of the reported values,
+/// which bounds the influence of nodes that report bogus statistics.
+fn aggregate_node_stats(stats_by_node: &BTreeMap<NodeId, QueryStats>) -> QueryStats {
+    let field = |f: fn(&QueryStats) -> u64| median(stats_by_node.values().map(f).collect());
+    QueryStats {
+        num_calls: field(|stats| stats.num_calls as u64) as u32,
+        num_instructions: field(|stats| stats.num_instructions),
+        ingress_payload_size: field(|stats| stats.ingress_payload_size),
+        egress_payload_size: field(|stats| stats.egress_payload_size),
+    }
+}
+
+/// Applies a query stats payload to the replicated state.
+///
+/// The statistics reported for an epoch are kept in the system metadata until
+/// the first report for a later epoch is delivered. At that point the
+/// statistics of the completed epoch are aggregated over all nodes, scaled by
+/// the size of the subnet (as every node only executes a share of the
+/// queries), and added to the totals of the respective canisters.
+///
+/// The proposer of the payload has been validated to be the maker of the
+/// block, which is a member of the subnet. Statistics are stored under the
+/// proposer, so that every node contributes at most one value per canister
+/// and epoch to the median.
+pub fn deliver_query_stats(
+    payload: &QueryStatsPayload,
+    state: &mut ReplicatedState,
+    log: &ReplicaLogger,
+) {
+    let current_epoch = *state
+        .metadata
+        .epoch_query_stats
+        .epoch
+        .get_or_insert(payload.epoch);
+
+    if payload.epoch < current_epoch {
+        info!(
+            log,
+            "Received query stats of epoch {} while aggregating epoch {}, ignoring",
+            payload.epoch,
+            current_epoch
+        );
+        return;
+    }
+
+    if payload.epoch > current_epoch {
+        let subnet_size = state
+            .metadata
+            .network_topology
+            .get_subnet_size(&state.metadata.own_subnet_id)
+            .unwrap_or(1) as u128;
+        let epoch_stats = std::mem::take(&mut state.metadata.epoch_query_stats.stats);
+        for (canister_id, stats_by_node) in epoch_stats {
+            if let Some(canister) = state.canister_state_mut(&canister_id) {
+                canister
+                    .scheduler_state
+                    .total_query_stats
+                    .saturating_accumulate(&aggregate_node_stats(&stats_by_node), subnet_size);
+            }
+        }
+        state.metadata.epoch_query_stats.epoch = Some(payload.epoch);
+    }
+
+    let epoch_stats = &mut state.metadata.epoch_query_stats.stats;
+    for stats in &payload.stats {
+        epoch_stats
+            .entry(stats.canister_id)
+            .or_default()
+            .entry(payload.proposer)
+            .or_insert_with(|| stats.stats.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_logger::replica_logger::no_op_logger;
+    use ic_replicated_state::{metadata_state::SubnetTopology, TotalQueryStats};
+    use ic_test_utilities::{
+        state::{CanisterStateBuilder, ReplicatedStateBuilder},
+        types::ids::{canister_test_id, node_test_id, subnet_test_id},
+    };
+    use ic_types::batch::{CanisterQueryStats, QueryStatsEpoch};
+
+    fn stats(num_calls: u32) -> QueryStats {
+        QueryStats {
+            num_calls,
+            num_instructions: 1_000 * num_calls as u64,
+            ingress_payload_size: 10 * num_calls as u64,
+            egress_payload_size: 20 * num_calls as u64,
+        }
+    }
+
+    fn payload(epoch: u64, node: u64, num_calls: u32) -> QueryStatsPayload {
+        QueryStatsPayload {
+            epoch: QueryStatsEpoch::from(epoch),
+            proposer: node_test_id(node),
+            stats: vec![CanisterQueryStats {
+                canister_id: canister_test_id(1),
+                stats: stats(num_calls),
+            }],
+        }
+    }
+
+    fn state_with_subnet_of_size(subnet_size: u64) -> ReplicatedState {
+        let subnet_id = subnet_test_id(1);
+        let mut state = ReplicatedStateBuilder::new()
+            .with_subnet_id(subnet_id)
+            .with_canister(
+                CanisterStateBuilder::new()
+                    .with_canister_id(canister_test_id(1))
+                    .build(),
+            )
+            .build();
+        state.metadata.network_topology.subnets.insert(
+            subnet_id,
+            SubnetTopology {
+                nodes: (1..=subnet_size).map(node_test_id).collect(),
+                ..Default::default()
+            },
+        );
+        state
+    }
+
+    fn total_query_stats(state: &ReplicatedState) -> TotalQueryStats {
+        state
+            .canister_state(&canister_test_id(1))
+            .unwrap()
+            .scheduler_state
+            .total_query_stats
+            .clone()
+    }
+
+    #[test]
+    fn median_picks_lower_middle_value() {
+        assert_eq!(median(vec![3]), 3);
+        assert_eq!(median(vec![5, 1, 3]), 3);
+        assert_eq!(median(vec![4, 1, 3, 2]), 2);
+    }
+
+    #[test]
+    fn stats_are_aggregated_once_next_epoch_starts() {
+        let log = no_op_logger();
+        let mut state = state_with_subnet_of_size(4);
+
+        deliver_query_stats(&payload(0, 1, 1), &mut state, &log);
+        deliver_query_stats(&payload(0, 2, 100), &mut state, &log);
+        deliver_query_stats(&payload(0, 3, 3), &mut state, &log);
+        // Duplicate reports of the same node are ignored.
+        deliver_query_stats(&payload(0, 3, 50), &mut state, &log);
+        assert_eq!(total_query_stats(&state), TotalQueryStats::default());
+
+        deliver_query_stats(&payload(1, 1, 7), &mut state, &log);
+        // The median of [1, 3, 100] scaled by the subnet size.
+        assert_eq!(
+            total_query_stats(&state),
+            TotalQueryStats {
+                num_calls: 3 * 4,
+                num_instructions: 3_000 * 4,
+                ingress_payload_size: 30 * 4,
+                egress_payload_size: 60 * 4,
+            }
+        );
+        let epoch_query_stats = &state.metadata.epoch_query_stats;
+        assert_eq!(epoch_query_stats.epoch, Some(QueryStatsEpoch::from(1)));
+        assert_eq!(
+            epoch_query_stats.stats[&canister_test_id(1)][&node_test_id(1)],
+            stats(7)
+        );
+
+        // Late reports for a completed epoch are dropped.
+        deliver_query_stats(&payload(0, 4, 1), &mut state, &log);
+        assert_eq!(
+            state.metadata.epoch_query_stats.stats[&canister_test_id(1)].len(),
+            1
+        );
+    }
+}
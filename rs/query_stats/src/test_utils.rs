@@ -0,0 +1,38 @@
+use ic_interfaces::{
+    batch_payload::{BatchPayloadBuilder, PastPayload, ProposalContext},
+    consensus::PayloadValidationError,
+};
+use ic_types::{batch::ValidationContext, Height, NumBytes};
+
+/// A query stats payload builder that always builds empty payloads and
+/// accepts any payload.
+#[derive(Default)]
+pub struct FakeQueryStatsPayloadBuilder;
+
+impl FakeQueryStatsPayloadBuilder {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl BatchPayloadBuilder for FakeQueryStatsPayloadBuilder {
+    fn build_payload(
+        &self,
+        _height: Height,
+        _max_size: NumBytes,
+        _past_payloads: &[PastPayload],
+        _context: &ValidationContext,
+    ) -> Vec<u8> {
+        vec![]
+    }
+
+    fn validate_payload(
+        &self,
+        _height: Height,
+        _payload: &[u8],
+        _past_payloads: &[PastPayload],
+        _proposal_context: &ProposalContext,
+    ) -> Result<(), PayloadValidationError> {
+        Ok(())
+    }
+}
@@ -75,6 +75,10 @@ pub enum SubCommand {
 
     /// Verify the signature of a CUP from a subnet
     VerifySubnetCUP(VerifySubnetCUPCmd),
+
+    /// Execute a query against a checkpoint without replaying any blocks and
+    /// print the Candid-decoded reply.
+    Query(QueryCmd),
 }
 
 #[derive(Clone, Parser)]
@@ -176,3 +180,20 @@ pub struct VerifySubnetCUPCmd {
     /// File wih the content of the public key
     pub public_key_file: PathBuf,
 }
+
+#[derive(Clone, Parser, Debug)]
+pub struct QueryCmd {
+    /// The canister to query.
+    pub canister_id: CanisterId,
+    /// The name of the query method.
+    pub method_name: String,
+    /// Arguments in Candid text format, e.g. `(record { limit = 10 : nat32 })`.
+    #[clap(default_value = "()")]
+    pub arg: String,
+    /// The principal that sends the query. Defaults to the anonymous principal.
+    #[clap(long)]
+    pub caller: Option<PrincipalId>,
+    /// Height of the checkpoint to query. Defaults to the latest state.
+    #[clap(long)]
+    pub height: Option<u64>,
+}
@@ -12,18 +12,24 @@
 //! state (after all past blocks have been executed). All of them are meant to
 //! help recover NNS subnet where the registry canister resides.
 //!
+//! The `query` sub-command executes a query against a past checkpoint without
+//! replaying any blocks, e.g. to inspect governance proposals during an
+//! incident.
+//!
 //! Use `ic-replay --help` to find out more.
 
 use crate::cmd::{ReplayToolArgs, SubCommand};
 use crate::ingress::*;
 use crate::player::{Player, ReplayResult};
 
+use candid::IDLArgs;
 use ic_canister_client::{Agent, Sender};
 use ic_config::{Config, ConfigSource};
 use ic_nns_constants::GOVERNANCE_CANISTER_ID;
 use ic_protobuf::registry::subnet::v1::InitialNiDkgTranscriptRecord;
 use ic_types::consensus::CatchUpPackage;
-use ic_types::ReplicaVersion;
+use ic_types::ingress::WasmResult;
+use ic_types::{Height, PrincipalId, ReplicaVersion};
 use prost::Message;
 use std::cell::RefCell;
 use std::convert::{TryFrom, TryInto};
@@ -32,6 +38,7 @@ use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 use std::rc::Rc;
+use std::str::FromStr;
 
 mod backup;
 pub mod cmd;
@@ -157,6 +164,14 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
                 return;
             }
 
+            if let Some(SubCommand::Query(cmd)) = subcmd {
+                if let Err(err) = cmd_query(&player, cmd) {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+                return;
+            }
+
             let extra = move |player: &Player, time| -> Vec<IngressWithPrinter> {
                 // Use a dummy URL here because we don't send any outgoing ingress.
                 // The agent is only used to construct ingress messages.
@@ -309,6 +324,29 @@ fn cmd_get_recovery_cup(
     Ok(())
 }
 
+// Executes a query against the state at the requested height, without
+// replaying any blocks, and prints the Candid-decoded reply.
+fn cmd_query(player: &Player, cmd: &crate::cmd::QueryCmd) -> Result<(), String> {
+    let payload = IDLArgs::from_str(&cmd.arg)
+        .and_then(|args| args.to_bytes())
+        .map_err(|err| format!("Illegal Candid arguments {}: {}", cmd.arg, err))?;
+    let caller = cmd.caller.unwrap_or_else(PrincipalId::new_anonymous);
+    match player.query(
+        caller,
+        cmd.canister_id,
+        cmd.method_name.clone(),
+        payload,
+        cmd.height.map(Height::from),
+    )? {
+        WasmResult::Reply(bytes) => match IDLArgs::from_bytes(&bytes) {
+            Ok(args) => println!("{}", args),
+            Err(_) => println!("Reply (not Candid): 0x{}", hex::encode(bytes)),
+        },
+        WasmResult::Reject(msg) => return Err(format!("Query rejected: {}", msg)),
+    }
+    Ok(())
+}
+
 fn verify_cup_signature(cup_file: &Path, public_key_file: &Path) -> Result<(), Box<dyn Error>> {
     let mut file = File::open(cup_file)?;
     let mut buffer = Vec::new();
@@ -1,4 +1,5 @@
 use ic_interfaces::{
+    batch_payload::ProposalContext,
     consensus::{PayloadBuilder, PayloadValidationError},
     validation::ValidationResult,
 };
@@ -28,7 +29,7 @@ impl PayloadBuilder for MockPayloadBuilder {
         _height: Height,
         _payload: &Payload,
         _past_payloads: &[(Height, Time, Payload)],
-        _context: &ValidationContext,
+        _proposal_context: &ProposalContext,
     ) -> ValidationResult<PayloadValidationError> {
         Ok(())
     }
@@ -57,8 +57,8 @@ use ic_types::{
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::UserQuery,
     time::current_time,
-    CryptoHashOfState, Height, PrincipalId, Randomness, RegistryVersion, ReplicaVersion, SubnetId,
-    Time, UserId,
+    CanisterId, CryptoHashOfState, Height, PrincipalId, Randomness, RegistryVersion,
+    ReplicaVersion, SubnetId, Time, UserId,
 };
 use ic_types::{
     consensus::CatchUpContentProtobufBytes,
@@ -714,6 +714,8 @@ impl Player {
             // Use a fake randomness here since we don't have random tape for extra messages
             randomness,
             ecdsa_subnet_public_keys: BTreeMap::new(),
+            schnorr_subnet_public_keys: BTreeMap::new(),
+            vetkd_subnet_public_keys: BTreeMap::new(),
             registry_version,
             time,
             consensus_responses: Vec::new(),
@@ -784,6 +786,33 @@ impl Player {
         }
     }
 
+    /// Executes a query against the state at the given height (or the latest
+    /// state if no height is given) without modifying it. States that are no
+    /// longer in memory are loaded from the corresponding checkpoint.
+    pub fn query(
+        &self,
+        caller: PrincipalId,
+        canister_id: CanisterId,
+        method_name: String,
+        method_payload: Vec<u8>,
+        height: Option<Height>,
+    ) -> Result<WasmResult, String> {
+        let query = UserQuery {
+            source: UserId::from(caller),
+            receiver: canister_id,
+            method_name,
+            method_payload,
+            ingress_expiry: current_time().as_nanos_since_unix_epoch(),
+            nonce: None,
+        };
+        query_at_height(
+            self.state_manager.as_ref(),
+            self.http_query_handler.as_ref(),
+            query,
+            height,
+        )
+    }
+
     /// Return the latest registry version by querying the registry canister.
     pub fn get_latest_registry_version(
         &self,
@@ -1292,10 +1321,35 @@ fn get_state_hash<T>(
     None
 }
 
+// Executes the query against the state at the given height, or the latest
+// state if no height is given.
+fn query_at_height(
+    state_reader: &dyn StateReader<State = ReplicatedState>,
+    query_handler: &dyn QueryHandler<State = ReplicatedState>,
+    query: UserQuery,
+    height: Option<Height>,
+) -> Result<WasmResult, String> {
+    let state = match height {
+        Some(height) => state_reader
+            .get_state_at(height)
+            .map_err(|err| format!("Failed to load state at height {}: {:?}", height, err))?,
+        None => state_reader.get_latest_state(),
+    };
+    query_handler
+        .query(query, state.take(), Vec::new())
+        .map_err(|err| format!("Failed to run query: {:?}", err))
+}
+
 #[cfg(test)]
 mod tests {
+    use ic_error_types::UserError;
+    use ic_interfaces_state_manager::CertificationScope;
     use ic_logger::replica_logger::no_op_logger;
-    use ic_test_utilities::{consensus::fake::FakeSigner, types::ids::node_test_id};
+    use ic_test_utilities::{
+        consensus::fake::FakeSigner,
+        state_manager::FakeStateManager,
+        types::ids::{canister_test_id, node_test_id, subnet_test_id, user_test_id},
+    };
     use ic_types::{
         artifact::CertificationMessage,
         consensus::certification::{CertificationContent, CertificationShare},
@@ -1315,6 +1369,83 @@ mod tests {
         })
     }
 
+    /// Replies with the ID of the subnet of the state the query is executed
+    /// on and records the executed query.
+    #[derive(Default)]
+    struct FakeQueryHandler {
+        queries: std::sync::Mutex<Vec<UserQuery>>,
+    }
+
+    impl QueryHandler for FakeQueryHandler {
+        type State = ReplicatedState;
+
+        fn query(
+            &self,
+            query: UserQuery,
+            state: Arc<ReplicatedState>,
+            _data_certificate: Vec<u8>,
+        ) -> Result<WasmResult, UserError> {
+            self.queries.lock().unwrap().push(query);
+            Ok(WasmResult::Reply(
+                state.metadata.own_subnet_id.get().to_vec(),
+            ))
+        }
+    }
+
+    fn user_query() -> UserQuery {
+        UserQuery {
+            source: user_test_id(1),
+            receiver: canister_test_id(2),
+            method_name: "read".to_string(),
+            method_payload: vec![1, 2, 3],
+            ingress_expiry: 0,
+            nonce: None,
+        }
+    }
+
+    #[test]
+    fn test_query_at_height() {
+        // The initial state at height 0 belongs to subnet 1, while the state
+        // committed at height 1 is the tip, which belongs to subnet 169.
+        let state_manager = FakeStateManager::new();
+        let (_, tip) = state_manager.take_tip();
+        state_manager.commit_and_certify(tip, Height::from(1), CertificationScope::Full);
+        let query_handler = FakeQueryHandler::default();
+
+        let reply_from = |subnet| WasmResult::Reply(subnet_test_id(subnet).get().to_vec());
+        assert_eq!(
+            query_at_height(&state_manager, &query_handler, user_query(), None),
+            Ok(reply_from(169))
+        );
+        assert_eq!(
+            query_at_height(
+                &state_manager,
+                &query_handler,
+                user_query(),
+                Some(Height::from(0))
+            ),
+            Ok(reply_from(1))
+        );
+        assert_eq!(
+            *query_handler.queries.lock().unwrap(),
+            vec![user_query(); 2]
+        );
+
+        let err = query_at_height(
+            &state_manager,
+            &query_handler,
+            user_query(),
+            Some(Height::from(5)),
+        )
+        .unwrap_err();
+        assert!(
+            err.starts_with("Failed to load state at height 5"),
+            "unexpected error: {}",
+            err
+        );
+        assert_eq!(query_handler.queries.lock().unwrap().len(), 2);
+    }
+
     #[test]
     fn test_get_share_certified_hashes() {
         let tmp = tempfile::tempdir().expect("Could not create a temp dir");
@@ -117,6 +117,8 @@ fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -
         },
         randomness: Randomness::from([0; 32]),
         ecdsa_subnet_public_keys: BTreeMap::new(),
+        schnorr_subnet_public_keys: BTreeMap::new(),
+        vetkd_subnet_public_keys: BTreeMap::new(),
         registry_version: RegistryVersion::from(1),
         time: mock_time(),
         consensus_responses: vec![],
@@ -35,6 +35,7 @@ use ic_interfaces::{
         AdvertBroadcaster, ArtifactClient, ArtifactManager, ArtifactProcessor, JoinGuard,
     },
     artifact_pool::UnvalidatedArtifact,
+    batch_payload::BatchPayloadBuilder,
     crypto::IngressSigVerifier,
     execution_environment::IngressHistoryReader,
     messaging::{MessageRouting, XNetPayloadBuilder},
@@ -128,6 +129,7 @@ pub fn setup_consensus_and_p2p(
     state_sync_client: P2PStateSyncClient,
     xnet_payload_builder: Arc<dyn XNetPayloadBuilder>,
     self_validating_payload_builder: Arc<dyn SelfValidatingPayloadBuilder>,
+    query_stats_payload_builder: Arc<dyn BatchPayloadBuilder>,
     message_router: Arc<dyn MessageRouting>,
     consensus_crypto: Arc<dyn ConsensusCrypto + Send + Sync>,
     certifier_crypto: Arc<dyn CertificationCrypto + Send + Sync>,
@@ -177,6 +179,7 @@ pub fn setup_consensus_and_p2p(
         state_sync_client,
         xnet_payload_builder,
         self_validating_payload_builder,
+        query_stats_payload_builder,
         message_router,
         ingress_history_reader,
         artifact_pools,
@@ -241,6 +244,7 @@ fn setup_artifact_manager(
     state_sync_client: P2PStateSyncClient,
     xnet_payload_builder: Arc<dyn XNetPayloadBuilder>,
     self_validating_payload_builder: Arc<dyn SelfValidatingPayloadBuilder>,
+    query_stats_payload_builder: Arc<dyn BatchPayloadBuilder>,
     message_router: Arc<dyn MessageRouting>,
     ingress_history_reader: Box<dyn IngressHistoryReader>,
     artifact_pools: ArtifactPools,
@@ -365,6 +369,7 @@ fn setup_artifact_manager(
                 xnet_payload_builder,
                 self_validating_payload_builder,
                 canister_http_payload_builder,
+                query_stats_payload_builder,
                 Arc::clone(&artifact_pools.dkg_pool) as Arc<_>,
                 Arc::clone(&artifact_pools.ecdsa_pool) as Arc<_>,
                 Arc::clone(&dkg_key_manager) as Arc<_>,
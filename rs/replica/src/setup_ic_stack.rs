@@ -248,6 +248,12 @@ pub fn construct_ic_stack(
         log.clone(),
         subnet_type,
     );
+    // ---------- QUERY STATS DEPS FOLLOW ----------
+    let query_stats_payload_builder = Arc::new(
+        execution_services
+            .query_stats_payload_builder
+            .into_payload_builder(state_manager.clone(), node_id, log.clone()),
+    );
     // ---------- CONSENSUS AND P2P DEPS FOLLOW ----------
     let state_sync = StateSync::new(state_manager.clone(), log.clone());
     let sev_handshake = Arc::new(Sev::new(node_id, registry.clone()));
@@ -273,6 +279,7 @@ pub fn construct_ic_stack(
         P2PStateSyncClient::Client(state_sync),
         xnet_payload_builder,
         self_validating_payload_builder,
+        query_stats_payload_builder,
         message_router,
         // TODO(SCL-213)
         Arc::clone(&crypto) as Arc<_>,
@@ -300,6 +307,7 @@ pub fn construct_ic_stack(
         registry,
         Arc::clone(&crypto) as Arc<_>,
         Arc::clone(&crypto) as Arc<_>,
+        Arc::clone(&crypto) as Arc<_>,
         node_id,
         subnet_id,
         root_subnet_id,
@@ -710,6 +710,11 @@ fn can_get_canister_information() {
                 ComputeAllocation::default().as_percent(),
                 None,
                 2592000,
+                None,
+                0u128,
+                0u128,
+                0u128,
+                0u128,
                 0u128,
             )
         );
@@ -761,6 +766,11 @@ fn can_get_canister_information() {
                     ComputeAllocation::default().as_percent(),
                     None,
                     259200,
+                    None,
+                    0u128,
+                    0u128,
+                    0u128,
+                    0u128,
                     0u128,
                 ),
                 CanisterStatusResultV2::decode(&res).unwrap(),
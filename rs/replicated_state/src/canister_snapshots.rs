@@ -0,0 +1,436 @@
+use crate::{
+    canister_state::execution_state::{Global, Memory},
+    num_bytes_try_from, CanisterState, PageMap,
+};
+use ic_types::{CanisterId, CanisterTimer, NumBytes, Time};
+use ic_wasm_types::CanisterModule;
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+/// The number of bytes used to encode the local part of a `SnapshotId`.
+const LOCAL_SNAPSHOT_ID_BYTES: usize = 8;
+
+/// A unique identifier of a canister snapshot.
+///
+/// It consists of the ID of the canister the snapshot belongs to and a local
+/// ID that is unique among all snapshots ever taken of that canister.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SnapshotId {
+    canister_id: CanisterId,
+    local_id: u64,
+}
+
+impl SnapshotId {
+    pub fn new(canister_id: CanisterId, local_id: u64) -> Self {
+        Self {
+            canister_id,
+            local_id,
+        }
+    }
+
+    /// Returns the ID of the canister this snapshot belongs to.
+    pub fn get_canister_id(&self) -> CanisterId {
+        self.canister_id
+    }
+
+    pub fn local_id(&self) -> u64 {
+        self.local_id
+    }
+
+    /// Returns the byte representation of the snapshot ID: the big-endian
+    /// encoded local ID followed by the raw bytes of the canister ID.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut bytes = self.local_id.to_be_bytes().to_vec();
+        bytes.extend_from_slice(self.canister_id.get_ref().as_slice());
+        bytes
+    }
+}
+
+impl std::fmt::Display for SnapshotId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.canister_id, self.local_id)
+    }
+}
+
+impl TryFrom<&[u8]> for SnapshotId {
+    type Error = String;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        if bytes.len() <= LOCAL_SNAPSHOT_ID_BYTES {
+            return Err(format!(
+                "Snapshot ID must be longer than {} bytes, got {} bytes",
+                LOCAL_SNAPSHOT_ID_BYTES,
+                bytes.len()
+            ));
+        }
+        let (local_id, canister_id) = bytes.split_at(LOCAL_SNAPSHOT_ID_BYTES);
+        let local_id = u64::from_be_bytes(local_id.try_into().unwrap());
+        let canister_id = ic_base_types::PrincipalId::try_from(canister_id)
+            .map_err(|err| format!("Invalid canister ID in snapshot ID: {}", err))
+            .and_then(|principal_id| {
+                CanisterId::new(principal_id)
+                    .map_err(|err| format!("Invalid canister ID in snapshot ID: {}", err))
+            })?;
+        Ok(Self {
+            canister_id,
+            local_id,
+        })
+    }
+}
+
+/// A snapshot of the state of a canister that can be restored later.
+///
+/// A snapshot contains everything needed to bring the canister back to the
+/// moment the snapshot was taken: the Wasm module, the contents of the Wasm
+/// and stable memories, the exported globals, the certified data and the
+/// global timer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CanisterSnapshot {
+    canister_id: CanisterId,
+    /// The time at which the snapshot was taken.
+    taken_at_timestamp: Time,
+    /// The version of the canister when the snapshot was taken.
+    canister_version: u64,
+    certified_data: Vec<u8>,
+    global_timer: CanisterTimer,
+    wasm_binary: CanisterModule,
+    wasm_memory: Memory,
+    stable_memory: Memory,
+    exported_globals: Vec<Global>,
+    /// The total size of the snapshot, in bytes.
+    size: NumBytes,
+}
+
+impl CanisterSnapshot {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        canister_id: CanisterId,
+        taken_at_timestamp: Time,
+        canister_version: u64,
+        certified_data: Vec<u8>,
+        global_timer: CanisterTimer,
+        wasm_binary: CanisterModule,
+        wasm_memory: Memory,
+        stable_memory: Memory,
+        exported_globals: Vec<Global>,
+        size: NumBytes,
+    ) -> Self {
+        Self {
+            canister_id,
+            taken_at_timestamp,
+            canister_version,
+            certified_data,
+            global_timer,
+            wasm_binary,
+            wasm_memory,
+            stable_memory,
+            exported_globals,
+            size,
+        }
+    }
+
+    /// Captures the current state of the given canister.
+    ///
+    /// Returns `None` if the canister is empty, i.e. has no Wasm module
+    /// installed.
+    pub fn from_canister(canister: &CanisterState, taken_at_timestamp: Time) -> Option<Self> {
+        let execution_state = canister.execution_state.as_ref()?;
+        let wasm_memory = Memory::new(
+            execution_state.wasm_memory.page_map.clone(),
+            execution_state.wasm_memory.size,
+        );
+        let stable_memory = Memory::new(
+            execution_state.stable_memory.page_map.clone(),
+            execution_state.stable_memory.size,
+        );
+        let certified_data = canister.system_state.certified_data.clone();
+        let size = Self::compute_size(
+            &execution_state.wasm_binary.binary,
+            &wasm_memory,
+            &stable_memory,
+            &execution_state.exported_globals,
+            &certified_data,
+        );
+        Some(Self {
+            canister_id: canister.canister_id(),
+            taken_at_timestamp,
+            canister_version: canister.system_state.canister_version,
+            certified_data,
+            global_timer: canister.system_state.global_timer,
+            wasm_binary: execution_state.wasm_binary.binary.clone(),
+            wasm_memory,
+            stable_memory,
+            exported_globals: execution_state.exported_globals.clone(),
+            size,
+        })
+    }
+
+    /// Returns the number of bytes a snapshot of the given canister would
+    /// take, or zero if the canister is empty.
+    pub fn estimated_size(canister: &CanisterState) -> NumBytes {
+        match canister.execution_state.as_ref() {
+            Some(execution_state) => Self::compute_size(
+                &execution_state.wasm_binary.binary,
+                &execution_state.wasm_memory,
+                &execution_state.stable_memory,
+                &execution_state.exported_globals,
+                &canister.system_state.certified_data,
+            ),
+            None => NumBytes::from(0),
+        }
+    }
+
+    fn compute_size(
+        wasm_binary: &CanisterModule,
+        wasm_memory: &Memory,
+        stable_memory: &Memory,
+        exported_globals: &[Global],
+        certified_data: &[u8],
+    ) -> NumBytes {
+        // We use 8 bytes per global.
+        let globals_size_bytes = 8 * exported_globals.len() as u64;
+        num_bytes_try_from(wasm_memory.size)
+            .expect("could not convert from wasm memory number of pages to bytes")
+            + num_bytes_try_from(stable_memory.size)
+                .expect("could not convert from stable memory number of pages to bytes")
+            + NumBytes::from(wasm_binary.len() as u64)
+            + NumBytes::from(globals_size_bytes)
+            + NumBytes::from(certified_data.len() as u64)
+    }
+
+    pub fn canister_id(&self) -> CanisterId {
+        self.canister_id
+    }
+
+    pub fn taken_at_timestamp(&self) -> Time {
+        self.taken_at_timestamp
+    }
+
+    pub fn canister_version(&self) -> u64 {
+        self.canister_version
+    }
+
+    pub fn certified_data(&self) -> &Vec<u8> {
+        &self.certified_data
+    }
+
+    pub fn global_timer(&self) -> CanisterTimer {
+        self.global_timer
+    }
+
+    pub fn wasm_binary(&self) -> &CanisterModule {
+        &self.wasm_binary
+    }
+
+    pub fn wasm_memory(&self) -> &Memory {
+        &self.wasm_memory
+    }
+
+    pub fn wasm_memory_mut(&mut self) -> &mut Memory {
+        &mut self.wasm_memory
+    }
+
+    pub fn stable_memory(&self) -> &Memory {
+        &self.stable_memory
+    }
+
+    pub fn stable_memory_mut(&mut self) -> &mut Memory {
+        &mut self.stable_memory
+    }
+
+    pub fn exported_globals(&self) -> &Vec<Global> {
+        &self.exported_globals
+    }
+
+    /// Returns the total size of the snapshot.
+    pub fn size(&self) -> NumBytes {
+        self.size
+    }
+
+    /// Returns the page maps of the snapshot's Wasm and stable memories.
+    pub fn page_maps(&self) -> (&PageMap, &PageMap) {
+        (&self.wasm_memory.page_map, &self.stable_memory.page_map)
+    }
+}
+
+/// An operation on canister snapshots that has to be mirrored by the files
+/// backing the snapshots on disk.
+///
+/// The state manager applies these operations to the tip directory, in order,
+/// before flushing the unflushed deltas of the involved page maps.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnapshotOperation {
+    /// The memory files of the canister are copied to the snapshot.
+    Backup(CanisterId, SnapshotId),
+    /// The memory files of the snapshot are copied to the canister.
+    Restore(CanisterId, SnapshotId),
+}
+
+/// All canister snapshots held by a subnet.
+#[derive(Clone, Debug, Default)]
+pub struct CanisterSnapshots {
+    snapshots: BTreeMap<SnapshotId, Arc<CanisterSnapshot>>,
+    /// Operations on snapshots since the last time the tip was flushed.
+    unflushed_changes: Vec<SnapshotOperation>,
+}
+
+// The unflushed changes only describe how the on-disk representation has to be
+// updated, so they are not relevant for equality.
+impl PartialEq for CanisterSnapshots {
+    fn eq(&self, other: &Self) -> bool {
+        self.snapshots == other.snapshots
+    }
+}
+
+impl CanisterSnapshots {
+    pub fn new(snapshots: BTreeMap<SnapshotId, Arc<CanisterSnapshot>>) -> Self {
+        Self {
+            snapshots,
+            unflushed_changes: Vec::new(),
+        }
+    }
+
+    /// Adds a new snapshot and records that the canister's memory files have
+    /// to be backed up on disk.
+    pub fn push(&mut self, snapshot_id: SnapshotId, snapshot: Arc<CanisterSnapshot>) {
+        self.unflushed_changes.push(SnapshotOperation::Backup(
+            snapshot.canister_id(),
+            snapshot_id,
+        ));
+        self.snapshots.insert(snapshot_id, snapshot);
+    }
+
+    pub fn get(&self, snapshot_id: SnapshotId) -> Option<&Arc<CanisterSnapshot>> {
+        self.snapshots.get(&snapshot_id)
+    }
+
+    pub fn get_mut(&mut self, snapshot_id: SnapshotId) -> Option<&mut CanisterSnapshot> {
+        self.snapshots.get_mut(&snapshot_id).map(Arc::make_mut)
+    }
+
+    /// Removes the snapshot with the given ID and returns it.
+    pub fn remove(&mut self, snapshot_id: SnapshotId) -> Option<Arc<CanisterSnapshot>> {
+        self.snapshots.remove(&snapshot_id)
+    }
+
+    /// Removes all snapshots of the given canister.
+    pub fn delete_snapshots(&mut self, canister_id: CanisterId) {
+        self.snapshots
+            .retain(|snapshot_id, _| snapshot_id.get_canister_id() != canister_id);
+    }
+
+    /// Records that the given snapshot was loaded into the canister, so that
+    /// the snapshot's memory files have to be copied to the canister on disk.
+    pub fn add_restore_operation(&mut self, canister_id: CanisterId, snapshot_id: SnapshotId) {
+        self.unflushed_changes
+            .push(SnapshotOperation::Restore(canister_id, snapshot_id));
+    }
+
+    /// Returns the snapshots of the given canister, in the order they were
+    /// taken.
+    pub fn list_snapshots(
+        &self,
+        canister_id: CanisterId,
+    ) -> Vec<(SnapshotId, Arc<CanisterSnapshot>)> {
+        self.snapshots
+            .range(SnapshotId::new(canister_id, 0)..=SnapshotId::new(canister_id, u64::MAX))
+            .map(|(snapshot_id, snapshot)| (*snapshot_id, Arc::clone(snapshot)))
+            .collect()
+    }
+
+    /// Returns the number of snapshots of the given canister.
+    pub fn count_by_canister(&self, canister_id: &CanisterId) -> usize {
+        self.snapshots
+            .range(SnapshotId::new(*canister_id, 0)..=SnapshotId::new(*canister_id, u64::MAX))
+            .count()
+    }
+
+    /// Returns the IDs of all snapshots.
+    pub fn snapshot_ids(&self) -> BTreeSet<SnapshotId> {
+        self.snapshots.keys().copied().collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&SnapshotId, &Arc<CanisterSnapshot>)> {
+        self.snapshots.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Retains only the snapshots of the canisters satisfying the predicate.
+    pub fn retain_canisters<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&CanisterId) -> bool,
+    {
+        self.snapshots
+            .retain(|snapshot_id, _| f(&snapshot_id.get_canister_id()));
+    }
+
+    /// Takes the snapshot operations recorded since the last call.
+    pub fn take_unflushed_changes(&mut self) -> Vec<SnapshotOperation> {
+        std::mem::take(&mut self.unflushed_changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_test_utilities::types::ids::canister_test_id;
+
+    #[test]
+    fn snapshot_id_roundtrips_through_bytes() {
+        let snapshot_id = SnapshotId::new(canister_test_id(42), 7);
+        let bytes = snapshot_id.to_vec();
+        assert_eq!(SnapshotId::try_from(bytes.as_slice()), Ok(snapshot_id));
+    }
+
+    #[test]
+    fn snapshot_id_rejects_short_input() {
+        assert!(SnapshotId::try_from(&[0_u8; LOCAL_SNAPSHOT_ID_BYTES][..]).is_err());
+    }
+
+    #[test]
+    fn list_snapshots_only_returns_snapshots_of_the_canister() {
+        let mut snapshots = CanisterSnapshots::default();
+        for (canister, local_id) in [(1, 0), (2, 0), (1, 1), (3, 0)] {
+            let canister_id = canister_test_id(canister);
+            snapshots.push(
+                SnapshotId::new(canister_id, local_id),
+                Arc::new(CanisterSnapshot::new(
+                    canister_id,
+                    Time::from_nanos_since_unix_epoch(0),
+                    0,
+                    vec![],
+                    CanisterTimer::Inactive,
+                    CanisterModule::new(vec![]),
+                    Memory::new_for_testing(),
+                    Memory::new_for_testing(),
+                    vec![],
+                    NumBytes::from(0),
+                )),
+            );
+        }
+
+        let canister_id = canister_test_id(1);
+        let ids: Vec<_> = snapshots
+            .list_snapshots(canister_id)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                SnapshotId::new(canister_id, 0),
+                SnapshotId::new(canister_id, 1)
+            ]
+        );
+        assert_eq!(snapshots.count_by_canister(&canister_id), 2);
+
+        snapshots.delete_snapshots(canister_id);
+        assert_eq!(snapshots.count_by_canister(&canister_id), 0);
+        assert_eq!(snapshots.take_unflushed_changes().len(), 4);
+        assert!(snapshots.take_unflushed_changes().is_empty());
+    }
+}
@@ -10,7 +10,9 @@ use crate::{InputQueueType, StateError};
 pub use execution_state::{EmbedderCache, ExecutionState, ExportedFunctions, Global};
 use ic_ic00_types::CanisterStatusType;
 use ic_interfaces::messages::CanisterMessage;
+use ic_protobuf::{proxy::ProxyDecodeError, state::canister_state_bits::v1 as pb};
 use ic_registry_subnet_type::SubnetType;
+use ic_types::batch::QueryStats;
 use ic_types::methods::SystemMethod;
 use ic_types::time::UNIX_EPOCH;
 use ic_types::{
@@ -23,7 +25,7 @@ use ic_types::{LongExecutionMode, NumInstructions};
 use phantom_newtype::AmountOf;
 pub use queues::{CanisterQueues, DEFAULT_QUEUE_CAPACITY};
 use std::collections::BTreeSet;
-use std::convert::From;
+use std::convert::{From, TryFrom, TryInto};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -72,6 +74,9 @@ pub struct SchedulerState {
     /// needed to calculate how much time should be considered when charging
     /// occurs.
     pub time_of_last_allocation_charge: Time,
+
+    /// Query statistics of the canister, aggregated through consensus.
+    pub total_query_stats: TotalQueryStats,
 }
 
 impl Default for SchedulerState {
@@ -85,6 +90,7 @@ impl Default for SchedulerState {
             heap_delta_debit: 0.into(),
             install_code_debit: 0.into(),
             time_of_last_allocation_charge: UNIX_EPOCH,
+            total_query_stats: TotalQueryStats::default(),
         }
     }
 }
@@ -98,6 +104,66 @@ impl SchedulerState {
     }
 }
 
+/// Query statistics of a canister, aggregated over all epochs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TotalQueryStats {
+    pub num_calls: u128,
+    pub num_instructions: u128,
+    pub ingress_payload_size: u128,
+    pub egress_payload_size: u128,
+}
+
+impl TotalQueryStats {
+    /// Adds `scale` times the given statistics of a single epoch, saturating
+    /// at `u128::MAX`.
+    pub fn saturating_accumulate(&mut self, stats: &QueryStats, scale: u128) {
+        let add =
+            |total: u128, value: u64| total.saturating_add((value as u128).saturating_mul(scale));
+        self.num_calls = add(self.num_calls, stats.num_calls as u64);
+        self.num_instructions = add(self.num_instructions, stats.num_instructions);
+        self.ingress_payload_size = add(self.ingress_payload_size, stats.ingress_payload_size);
+        self.egress_payload_size = add(self.egress_payload_size, stats.egress_payload_size);
+    }
+}
+
+impl From<&TotalQueryStats> for pb::TotalQueryStats {
+    fn from(stats: &TotalQueryStats) -> Self {
+        Self {
+            num_calls: stats.num_calls.to_le_bytes().to_vec(),
+            num_instructions: stats.num_instructions.to_le_bytes().to_vec(),
+            ingress_payload_size: stats.ingress_payload_size.to_le_bytes().to_vec(),
+            egress_payload_size: stats.egress_payload_size.to_le_bytes().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<pb::TotalQueryStats> for TotalQueryStats {
+    type Error = ProxyDecodeError;
+
+    fn try_from(stats: pb::TotalQueryStats) -> Result<Self, Self::Error> {
+        fn u128_from_le_bytes(bytes: Vec<u8>) -> Result<u128, ProxyDecodeError> {
+            if bytes.is_empty() {
+                return Ok(0);
+            }
+            let bytes: [u8; 16] =
+                bytes
+                    .try_into()
+                    .map_err(|bytes: Vec<u8>| ProxyDecodeError::ValueOutOfRange {
+                        typ: "TotalQueryStats",
+                        err: format!("expected 16 bytes, got {}", bytes.len()),
+                    })?;
+            Ok(u128::from_le_bytes(bytes))
+        }
+
+        Ok(Self {
+            num_calls: u128_from_le_bytes(stats.num_calls)?,
+            num_instructions: u128_from_le_bytes(stats.num_instructions)?,
+            ingress_payload_size: u128_from_le_bytes(stats.ingress_payload_size)?,
+            egress_payload_size: u128_from_le_bytes(stats.egress_payload_size)?,
+        })
+    }
+}
+
 /// The full state of a single canister.
 #[derive(Clone, Debug, PartialEq)]
 pub struct CanisterState {
@@ -383,10 +449,14 @@ impl CanisterState {
 
     /// The amount of memory currently being used by the canister.
     ///
-    /// This only includes execution memory (heap, stable, globals, Wasm)
-    /// and canister history memory.
+    /// This only includes execution memory (heap, stable, globals, Wasm),
+    /// canister history memory, the memory taken by canister snapshots and
+    /// the Wasm chunk store.
     pub fn memory_usage(&self) -> NumBytes {
-        self.raw_memory_usage() + self.canister_history_memory_usage()
+        self.raw_memory_usage()
+            + self.canister_history_memory_usage()
+            + self.snapshots_memory_usage()
+            + self.wasm_chunk_store_memory_usage()
     }
 
     /// Returns the amount of raw memory currently used by the canister in bytes.
@@ -416,6 +486,16 @@ impl CanisterState {
         self.system_state.canister_history_memory_usage()
     }
 
+    /// Returns the memory used by the snapshots of this canister.
+    pub fn snapshots_memory_usage(&self) -> NumBytes {
+        self.system_state.snapshots_memory_usage
+    }
+
+    /// Returns the memory used by the Wasm chunk store of this canister.
+    pub fn wasm_chunk_store_memory_usage(&self) -> NumBytes {
+        self.system_state.wasm_chunk_store.memory_usage()
+    }
+
     /// Sets the (transient) size in bytes of responses from this canister
     /// routed into streams and not yet garbage collected.
     pub(super) fn set_stream_responses_size_bytes(&mut self, size_bytes: usize) {
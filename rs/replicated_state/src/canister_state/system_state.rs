@@ -1,4 +1,5 @@
 mod call_context_manager;
+mod wasm_chunk_store;
 
 use super::queues::can_push;
 pub use super::queues::memory_required_to_push_request;
@@ -6,7 +7,7 @@ pub use crate::canister_state::queues::CanisterOutputQueuesIterator;
 use crate::{CanisterQueues, CanisterState, InputQueueType, StateError};
 pub use call_context_manager::{CallContext, CallContextAction, CallContextManager, CallOrigin};
 use ic_base_types::NumSeconds;
-use ic_ic00_types::{CanisterChange, CanisterChangeDetails, CanisterChangeOrigin};
+use ic_ic00_types::{CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, LogVisibility};
 use ic_interfaces::messages::{CanisterCall, CanisterMessage, CanisterMessageOrTask, CanisterTask};
 use ic_logger::{error, ReplicaLogger};
 use ic_protobuf::{
@@ -15,6 +16,8 @@ use ic_protobuf::{
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_types::{
+    canister_log::CanisterLog,
+    execution_profile::ExecutionProfile,
     messages::{Ingress, RejectContext, Request, RequestOrResponse, Response, StopCanisterContext},
     nominal_cycles::NominalCycles,
     CanisterId, CanisterTimer, Cycles, MemoryAllocation, NumBytes, PrincipalId, Time,
@@ -28,6 +31,9 @@ use std::{
 };
 use std::{collections::BTreeSet, sync::Arc};
 use std::{collections::VecDeque, str::FromStr};
+pub use wasm_chunk_store::{
+    WasmChunkHash, WasmChunkStore, WasmChunkStoreMetadata, CHUNK_SIZE, DEFAULT_MAX_NUM_CHUNKS,
+};
 
 lazy_static! {
     static ref DEFAULT_PRINCIPAL_MULTIPLE_CONTROLLERS: PrincipalId =
@@ -310,6 +316,33 @@ pub struct SystemState {
 
     /// Canister history.
     canister_history: CanisterHistory,
+
+    /// The local ID to be assigned to the next snapshot of this canister.
+    next_snapshot_id: u64,
+
+    /// The total size of all snapshots of this canister. Snapshots count
+    /// against the memory usage of the canister.
+    pub snapshots_memory_usage: NumBytes,
+
+    /// Store of Wasm chunks uploaded with `upload_chunk`, to be installed with
+    /// `install_chunked_code`. The chunks count against the memory usage of
+    /// the canister.
+    pub wasm_chunk_store: WasmChunkStore,
+
+    /// Who is allowed to fetch the canister logs.
+    pub log_visibility: LogVisibility,
+
+    /// Recent records written by `ic0.debug_print` and by traps.
+    pub canister_log: CanisterLog,
+
+    /// The maximum size of the Wasm memory. Update calls that grow the Wasm
+    /// memory beyond this limit trap. `None` means that there is no limit.
+    pub wasm_memory_limit: Option<NumBytes>,
+
+    /// The latest instruction profile of each entry point (e.g.
+    /// `canister_heartbeat`), recorded only if canister profiling is enabled.
+    /// Profiles are meant for tests and are not persisted in checkpoints.
+    pub execution_profiles: BTreeMap<String, ExecutionProfile>,
 }
 
 /// A wrapper around the different canister statuses.
@@ -694,6 +727,15 @@ impl SystemState {
             global_timer: CanisterTimer::Inactive,
             canister_version: 0,
             canister_history: CanisterHistory::default(),
+            next_snapshot_id: 0,
+            snapshots_memory_usage: NumBytes::from(0),
+            // Replaced by a store backed by the replica's page allocator when
+            // the canister manager creates a canister.
+            wasm_chunk_store: WasmChunkStore::new_for_testing(),
+            log_visibility: LogVisibility::default(),
+            canister_log: CanisterLog::default(),
+            wasm_memory_limit: None,
+            execution_profiles: BTreeMap::new(),
         }
     }
 
@@ -730,6 +772,12 @@ impl SystemState {
         global_timer: CanisterTimer,
         canister_version: u64,
         canister_history: CanisterHistory,
+        next_snapshot_id: u64,
+        snapshots_memory_usage: NumBytes,
+        wasm_chunk_store: WasmChunkStore,
+        log_visibility: LogVisibility,
+        canister_log: CanisterLog,
+        wasm_memory_limit: Option<NumBytes>,
     ) -> Self {
         Self {
             controllers,
@@ -747,6 +795,13 @@ impl SystemState {
             global_timer,
             canister_version,
             canister_history,
+            next_snapshot_id,
+            snapshots_memory_usage,
+            wasm_chunk_store,
+            log_visibility,
+            canister_log,
+            wasm_memory_limit,
+            execution_profiles: BTreeMap::new(),
         }
     }
 
@@ -754,6 +809,12 @@ impl SystemState {
         self.canister_id
     }
 
+    /// Replaces the profile of the entry point of the given profile.
+    pub fn add_execution_profile(&mut self, profile: ExecutionProfile) {
+        self.execution_profiles
+            .insert(profile.entry_point().to_string(), profile);
+    }
+
     /// Returns the amount of cycles that the balance holds.
     pub fn balance(&self) -> Cycles {
         self.cycles_balance
@@ -1330,6 +1391,20 @@ impl SystemState {
     pub fn get_canister_history(&self) -> &CanisterHistory {
         &self.canister_history
     }
+
+    /// Returns the local ID to be assigned to the next snapshot of this
+    /// canister, without allocating it.
+    pub fn next_snapshot_id(&self) -> u64 {
+        self.next_snapshot_id
+    }
+
+    /// Allocates a new local snapshot ID, unique among all snapshots ever
+    /// taken of this canister.
+    pub fn new_local_snapshot_id(&mut self) -> u64 {
+        let local_snapshot_id = self.next_snapshot_id;
+        self.next_snapshot_id += 1;
+        local_snapshot_id
+    }
 }
 
 /// Implements memory limits verification for pushing a canister-to-canister
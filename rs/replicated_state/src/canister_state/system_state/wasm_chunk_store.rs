@@ -0,0 +1,262 @@
+use crate::page_map::{Buffer, PageAllocatorFileDescriptor, PageMap};
+use ic_crypto_sha2::Sha256;
+use ic_protobuf::{proxy::ProxyDecodeError, state::canister_state_bits::v1 as pb};
+use ic_types::NumBytes;
+use std::{collections::BTreeMap, sync::Arc};
+
+/// The maximum size of a single chunk, in bytes.
+pub const CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// The maximum number of chunks a canister can keep in its chunk store.
+pub const DEFAULT_MAX_NUM_CHUNKS: usize = 100;
+
+/// The SHA-256 hash of a chunk, which is also its key in the chunk store.
+pub type WasmChunkHash = [u8; 32];
+
+/// The position of a chunk in the page map of the chunk store.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ChunkInfo {
+    /// The chunk occupies the `CHUNK_SIZE` bytes starting at
+    /// `index * CHUNK_SIZE`.
+    index: u64,
+    /// The actual length of the chunk, in bytes.
+    length: u64,
+}
+
+/// Describes which chunks are stored in the page map of a `WasmChunkStore`.
+/// It is persisted as part of the canister state bits, while the chunks
+/// themselves are persisted in their own file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WasmChunkStoreMetadata {
+    chunks: BTreeMap<WasmChunkHash, ChunkInfo>,
+    /// Sum over the lengths of all stored chunks. It is pre-computed, since
+    /// the memory usage of the canister is requested frequently.
+    size: NumBytes,
+}
+
+impl From<&WasmChunkStoreMetadata> for pb::WasmChunkStoreMetadata {
+    fn from(item: &WasmChunkStoreMetadata) -> Self {
+        Self {
+            chunks: item
+                .chunks
+                .iter()
+                .map(|(hash, info)| pb::WasmChunkData {
+                    hash: hash.to_vec(),
+                    index: info.index,
+                    length: info.length,
+                })
+                .collect(),
+            size: item.size.get(),
+        }
+    }
+}
+
+impl TryFrom<pb::WasmChunkStoreMetadata> for WasmChunkStoreMetadata {
+    type Error = ProxyDecodeError;
+
+    fn try_from(value: pb::WasmChunkStoreMetadata) -> Result<Self, Self::Error> {
+        let mut chunks = BTreeMap::new();
+        for chunk in value.chunks {
+            let hash = WasmChunkHash::try_from(chunk.hash.as_slice()).map_err(|_| {
+                ProxyDecodeError::InvalidDigestLength {
+                    expected: 32,
+                    actual: chunk.hash.len(),
+                }
+            })?;
+            chunks.insert(
+                hash,
+                ChunkInfo {
+                    index: chunk.index,
+                    length: chunk.length,
+                },
+            );
+        }
+        Ok(Self {
+            chunks,
+            size: NumBytes::from(value.size),
+        })
+    }
+}
+
+/// A per-canister store of Wasm chunks, addressed by their hash.
+///
+/// Chunks are uploaded with `upload_chunk` and later concatenated into a
+/// Wasm module by `install_chunked_code`. This allows installing modules that
+/// are larger than the ingress message size limit.
+///
+/// The chunks are kept in a `PageMap`, each one in its own `CHUNK_SIZE` slot,
+/// so that they are persisted incrementally like the canister memories.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WasmChunkStore {
+    data: PageMap,
+    metadata: WasmChunkStoreMetadata,
+}
+
+impl WasmChunkStore {
+    /// Creates an empty chunk store whose page map allocates through
+    /// `fd_factory`.
+    pub fn new(fd_factory: Arc<dyn PageAllocatorFileDescriptor>) -> Self {
+        Self {
+            data: PageMap::new(fd_factory),
+            metadata: WasmChunkStoreMetadata::default(),
+        }
+    }
+
+    /// Creates an empty chunk store for testing purposes.
+    pub fn new_for_testing() -> Self {
+        Self {
+            data: PageMap::new_for_testing(),
+            metadata: WasmChunkStoreMetadata::default(),
+        }
+    }
+
+    /// Restores a chunk store from the page map and metadata of a checkpoint.
+    pub fn from_checkpoint(data: PageMap, metadata: WasmChunkStoreMetadata) -> Self {
+        Self { data, metadata }
+    }
+
+    /// Checks whether the given chunk can be inserted into the store without
+    /// exceeding the size or count limits.
+    pub fn can_insert_chunk(&self, chunk: &[u8]) -> Result<(), String> {
+        if chunk.len() as u64 > CHUNK_SIZE {
+            return Err(format!(
+                "Wasm chunk size {} exceeds the maximum chunk size of {} bytes.",
+                chunk.len(),
+                CHUNK_SIZE
+            ));
+        }
+        if self.metadata.chunks.len() >= DEFAULT_MAX_NUM_CHUNKS
+            && !self.metadata.chunks.contains_key(&Sha256::hash(chunk))
+        {
+            return Err(format!(
+                "Wasm chunk store has already reached the maximum of {} chunks.",
+                DEFAULT_MAX_NUM_CHUNKS
+            ));
+        }
+        Ok(())
+    }
+
+    /// Inserts the chunk into the store and returns its hash. Inserting a
+    /// chunk that is already stored is a no-op.
+    ///
+    /// The caller is responsible for checking `can_insert_chunk()` first.
+    pub fn insert_chunk(&mut self, chunk: &[u8]) -> WasmChunkHash {
+        let hash = Sha256::hash(chunk);
+        if self.metadata.chunks.contains_key(&hash) {
+            return hash;
+        }
+
+        // Chunks are only ever removed all at once, so the slots in use are
+        // exactly `0..chunks.len()`.
+        let index = self.metadata.chunks.len() as u64;
+        let mut buffer = Buffer::new(self.data.clone());
+        buffer.write(chunk, (index * CHUNK_SIZE) as usize);
+        self.data.update(&buffer.dirty_pages().collect::<Vec<_>>());
+
+        let length = chunk.len() as u64;
+        self.metadata
+            .chunks
+            .insert(hash, ChunkInfo { index, length });
+        self.metadata.size += NumBytes::from(length);
+        hash
+    }
+
+    /// Returns the chunk with the given hash, if it is stored.
+    pub fn get_chunk(&self, hash: &WasmChunkHash) -> Option<Vec<u8>> {
+        let info = self.metadata.chunks.get(hash)?;
+        let mut chunk = vec![0; info.length as usize];
+        Buffer::new(self.data.clone()).read(&mut chunk, (info.index * CHUNK_SIZE) as usize);
+        Some(chunk)
+    }
+
+    /// Returns the hashes of all stored chunks, in ascending order.
+    pub fn keys(&self) -> impl Iterator<Item = &WasmChunkHash> {
+        self.metadata.chunks.keys()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.metadata.chunks.is_empty()
+    }
+
+    /// Returns the total size of the stored chunks.
+    pub fn memory_usage(&self) -> NumBytes {
+        self.metadata.size
+    }
+
+    pub fn metadata(&self) -> &WasmChunkStoreMetadata {
+        &self.metadata
+    }
+
+    pub fn page_map(&self) -> &PageMap {
+        &self.data
+    }
+
+    pub fn page_map_mut(&mut self) -> &mut PageMap {
+        &mut self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inserting_the_same_chunk_twice_is_charged_once() {
+        let mut store = WasmChunkStore::new_for_testing();
+        let hash = store.insert_chunk(&[1, 2, 3]);
+        assert_eq!(store.insert_chunk(&[1, 2, 3]), hash);
+        assert_eq!(store.memory_usage(), NumBytes::from(3));
+        assert_eq!(store.get_chunk(&hash), Some(vec![1, 2, 3]));
+        assert_eq!(store.keys().count(), 1);
+    }
+
+    #[test]
+    fn chunks_above_the_size_limit_are_rejected() {
+        let store = WasmChunkStore::new_for_testing();
+        assert!(store
+            .can_insert_chunk(&vec![0; CHUNK_SIZE as usize])
+            .is_ok());
+        assert!(store
+            .can_insert_chunk(&vec![0; CHUNK_SIZE as usize + 1])
+            .is_err());
+    }
+
+    #[test]
+    fn chunk_store_is_bounded() {
+        let mut store = WasmChunkStore::new_for_testing();
+        for i in 0..DEFAULT_MAX_NUM_CHUNKS {
+            let chunk = (i as u64).to_le_bytes();
+            store.can_insert_chunk(&chunk).unwrap();
+            store.insert_chunk(&chunk);
+        }
+        assert!(store.can_insert_chunk(&[42]).is_err());
+        // An already stored chunk can still be uploaded again.
+        assert!(store.can_insert_chunk(&0_u64.to_le_bytes()).is_ok());
+    }
+
+    #[test]
+    fn chunks_do_not_overlap_in_the_page_map() {
+        let mut store = WasmChunkStore::new_for_testing();
+        let full = vec![7; CHUNK_SIZE as usize];
+        let full_hash = store.insert_chunk(&full);
+        let small_hash = store.insert_chunk(&[1, 2]);
+        assert_eq!(store.get_chunk(&full_hash), Some(full));
+        assert_eq!(store.get_chunk(&small_hash), Some(vec![1, 2]));
+        assert_eq!(store.memory_usage(), NumBytes::from(CHUNK_SIZE + 2));
+    }
+
+    #[test]
+    fn metadata_round_trips_through_protobuf() {
+        let mut store = WasmChunkStore::new_for_testing();
+        let hash = store.insert_chunk(&[2, 3]);
+        store.insert_chunk(&[4]);
+
+        let pb_metadata = pb::WasmChunkStoreMetadata::from(store.metadata());
+        let metadata = WasmChunkStoreMetadata::try_from(pb_metadata).unwrap();
+        assert_eq!(&metadata, store.metadata());
+
+        let restored = WasmChunkStore::from_checkpoint(store.page_map().clone(), metadata);
+        assert_eq!(restored.memory_usage(), NumBytes::from(3));
+        assert_eq!(restored.get_chunk(&hash), Some(vec![2, 3]));
+    }
+}
@@ -1,4 +1,5 @@
 mod bitcoin;
+pub mod canister_snapshots;
 pub mod canister_state;
 pub(crate) mod hash;
 pub mod metadata_state;
@@ -9,6 +10,7 @@ pub mod testing {
     pub use super::canister_state::testing::CanisterQueuesTesting;
     pub use super::replicated_state::testing::ReplicatedStateTesting;
 }
+pub use canister_snapshots::{CanisterSnapshot, CanisterSnapshots, SnapshotId, SnapshotOperation};
 pub use canister_state::{
     execution_state::Memory,
     num_bytes_try_from,
@@ -17,10 +19,10 @@ pub use canister_state::{
         CallOrigin, CanisterMetrics, CanisterStatus, ExecutionTask, SystemState,
     },
     CanisterQueues, CanisterState, EmbedderCache, ExecutionState, ExportedFunctions, Global,
-    NumWasmPages, SchedulerState,
+    NumWasmPages, SchedulerState, TotalQueryStats,
 };
 pub use metadata_state::{
-    IngressHistoryState, NetworkTopology, Stream, SubnetTopology, SystemMetadata,
+    IngressHistoryState, NetworkTopology, RawQueryStats, Stream, SubnetTopology, SystemMetadata,
 };
 pub use page_map::{PageIndex, PageMap};
 pub use replicated_state::{InputQueueType, NextInputQueue, ReplicatedState, StateError};
@@ -29,6 +29,7 @@ use ic_registry_routing_table::{
 use ic_registry_subnet_features::SubnetFeatures;
 use ic_registry_subnet_type::SubnetType;
 use ic_types::{
+    batch::{CanisterQueryStats, QueryStats, QueryStatsEpoch},
     crypto::CryptoHash,
     ingress::{IngressState, IngressStatus},
     messages::{is_subnet_id, MessageId, RequestOrResponse},
@@ -156,6 +157,71 @@ pub struct SystemMetadata {
     /// response limit. To work around this limitation, large responses are paginated
     /// and are stored here temporarily until they're fetched by the calling canister.
     pub bitcoin_get_successors_follow_up_responses: BTreeMap<CanisterId, Vec<BlockBlob>>,
+
+    /// Query statistics received through consensus for the current epoch, that
+    /// have not been aggregated into the canister states yet.
+    pub epoch_query_stats: RawQueryStats,
+}
+
+/// Query statistics reported by the nodes of the subnet during a single epoch.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RawQueryStats {
+    /// The epoch of the statistics; `None` if no statistics have been received
+    /// yet.
+    pub epoch: Option<QueryStatsEpoch>,
+    /// The statistics reported by each node, by canister.
+    pub stats: BTreeMap<CanisterId, BTreeMap<NodeId, QueryStats>>,
+}
+
+impl From<&RawQueryStats> for pb_metadata::RawQueryStats {
+    fn from(item: &RawQueryStats) -> Self {
+        let mut node_stats = BTreeMap::<NodeId, Vec<pb_types::CanisterQueryStats>>::new();
+        for (canister_id, stats_by_node) in &item.stats {
+            for (node_id, stats) in stats_by_node {
+                node_stats
+                    .entry(*node_id)
+                    .or_default()
+                    .push(pb_types::CanisterQueryStats::from(&CanisterQueryStats {
+                        canister_id: *canister_id,
+                        stats: stats.clone(),
+                    }));
+            }
+        }
+
+        Self {
+            epoch: item.epoch.map(|epoch| epoch.get()),
+            node_stats: node_stats
+                .into_iter()
+                .map(|(node_id, canister_stats)| pb_metadata::NodeQueryStats {
+                    node_id: Some(node_id_into_protobuf(node_id)),
+                    canister_stats,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<pb_metadata::RawQueryStats> for RawQueryStats {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: pb_metadata::RawQueryStats) -> Result<Self, Self::Error> {
+        let mut stats = BTreeMap::<CanisterId, BTreeMap<NodeId, QueryStats>>::new();
+        for entry in item.node_stats {
+            let node_id = node_id_try_from_option(entry.node_id)?;
+            for canister_stats in entry.canister_stats {
+                let canister_stats = CanisterQueryStats::try_from(canister_stats)?;
+                stats
+                    .entry(canister_stats.canister_id)
+                    .or_default()
+                    .insert(node_id, canister_stats.stats);
+            }
+        }
+
+        Ok(Self {
+            epoch: item.epoch.map(QueryStatsEpoch::from),
+            stats,
+        })
+    }
 }
 
 /// Full description of the IC network toplogy.
@@ -509,6 +575,7 @@ impl From<&SystemMetadata> for pb_metadata::SystemMetadata {
                     public_key: public_key.clone(),
                 })
                 .collect(),
+            epoch_query_stats: Some((&item.epoch_query_stats).into()),
         }
     }
 }
@@ -616,6 +683,10 @@ impl TryFrom<pb_metadata::SystemMetadata> for SystemMetadata {
             },
             expected_compiled_wasms: BTreeSet::new(),
             bitcoin_get_successors_follow_up_responses,
+            epoch_query_stats: match item.epoch_query_stats {
+                Some(epoch_query_stats) => epoch_query_stats.try_into()?,
+                None => RawQueryStats::default(),
+            },
         })
     }
 }
@@ -651,6 +722,7 @@ impl SystemMetadata {
             subnet_metrics: Default::default(),
             expected_compiled_wasms: BTreeSet::new(),
             bitcoin_get_successors_follow_up_responses: BTreeMap::default(),
+            epoch_query_stats: RawQueryStats::default(),
         }
     }
 
@@ -910,6 +982,7 @@ impl SystemMetadata {
             subnet_metrics,
             expected_compiled_wasms,
             bitcoin_get_successors_follow_up_responses,
+            mut epoch_query_stats,
         } = self;
 
         let split_from = split_from.expect("Not a state resulting from a subnet split");
@@ -925,6 +998,11 @@ impl SystemMetadata {
                 || split_from == own_subnet_id && is_subnet_id(*canister_id, own_subnet_id)
         });
 
+        // Drop the query stats of canisters that are no longer hosted by this subnet.
+        epoch_query_stats
+            .stats
+            .retain(|canister_id, _| is_local_canister(canister_id));
+
         SystemMetadata {
             ingress_history,
             streams,
@@ -946,6 +1024,7 @@ impl SystemMetadata {
             subnet_metrics,
             expected_compiled_wasms,
             bitcoin_get_successors_follow_up_responses,
+            epoch_query_stats,
         }
     }
 }
@@ -1,5 +1,5 @@
 use ic_btc_types_internal::{GetSuccessorsRequestInitial, SendTransactionRequest};
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, SchnorrKeyId, VetKdKeyId};
 use ic_logger::{info, ReplicaLogger};
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
@@ -24,6 +24,8 @@ pub enum SubnetCallContext {
     BitcoinGetSuccessors(BitcoinGetSuccessorsContext),
     BitcoinSendTransactionInternal(BitcoinSendTransactionInternalContext),
     InstallCode(InstallCodeContext),
+    SignWithSchnorr(SignWithSchnorrContext),
+    VetKd(VetKdContext),
 }
 
 impl SubnetCallContext {
@@ -36,6 +38,8 @@ impl SubnetCallContext {
             SubnetCallContext::BitcoinGetSuccessors(context) => &context.request,
             SubnetCallContext::BitcoinSendTransactionInternal(context) => &context.request,
             SubnetCallContext::InstallCode(context) => &context.request,
+            SubnetCallContext::SignWithSchnorr(context) => &context.request,
+            SubnetCallContext::VetKd(context) => &context.request,
         }
     }
 
@@ -48,6 +52,8 @@ impl SubnetCallContext {
             SubnetCallContext::BitcoinGetSuccessors(context) => context.time,
             SubnetCallContext::BitcoinSendTransactionInternal(context) => context.time,
             SubnetCallContext::InstallCode(context) => context.time,
+            SubnetCallContext::SignWithSchnorr(context) => context.batch_time,
+            SubnetCallContext::VetKd(context) => context.batch_time,
         }
     }
 }
@@ -63,6 +69,8 @@ pub struct SubnetCallContextManager {
     pub bitcoin_send_transaction_internal_contexts:
         BTreeMap<CallbackId, BitcoinSendTransactionInternalContext>,
     pub install_code_contexts: BTreeMap<CallbackId, InstallCodeContext>,
+    pub sign_with_schnorr_contexts: BTreeMap<CallbackId, SignWithSchnorrContext>,
+    pub vetkd_contexts: BTreeMap<CallbackId, VetKdContext>,
 }
 
 impl SubnetCallContextManager {
@@ -95,6 +103,12 @@ impl SubnetCallContextManager {
             SubnetCallContext::InstallCode(context) => {
                 self.install_code_contexts.insert(callback_id, context);
             }
+            SubnetCallContext::SignWithSchnorr(context) => {
+                self.sign_with_schnorr_contexts.insert(callback_id, context);
+            }
+            SubnetCallContext::VetKd(context) => {
+                self.vetkd_contexts.insert(callback_id, context);
+            }
         };
         callback_id
     }
@@ -179,6 +193,30 @@ impl SubnetCallContextManager {
                         SubnetCallContext::BitcoinSendTransactionInternal(context)
                     })
             })
+            .or_else(|| {
+                self.sign_with_schnorr_contexts
+                    .remove(&callback_id)
+                    .map(|context| {
+                        info!(
+                            logger,
+                            "Received the response for SignWithSchnorr request with key_id {:?} from {:?}",
+                            context.key_id,
+                            context.request.sender
+                        );
+                        SubnetCallContext::SignWithSchnorr(context)
+                    })
+            })
+            .or_else(|| {
+                self.vetkd_contexts.remove(&callback_id).map(|context| {
+                    info!(
+                        logger,
+                        "Received the response for VetKdDeriveEncryptedKey request with key_id {:?} from {:?}",
+                        context.key_id,
+                        context.request.sender
+                    );
+                    SubnetCallContext::VetKd(context)
+                })
+            })
     }
 }
 
@@ -256,6 +294,24 @@ impl From<&SubnetCallContextManager> for pb_metadata::SubnetCallContextManager {
                     },
                 )
                 .collect(),
+            sign_with_schnorr_contexts: item
+                .sign_with_schnorr_contexts
+                .iter()
+                .map(
+                    |(callback_id, context)| pb_metadata::SignWithSchnorrContextTree {
+                        callback_id: callback_id.get(),
+                        context: Some(context.into()),
+                    },
+                )
+                .collect(),
+            vetkd_contexts: item
+                .vetkd_contexts
+                .iter()
+                .map(|(callback_id, context)| pb_metadata::VetKdContextTree {
+                    callback_id: callback_id.get(),
+                    context: Some(context.into()),
+                })
+                .collect(),
         }
     }
 }
@@ -327,6 +383,20 @@ impl TryFrom<(Time, pb_metadata::SubnetCallContextManager)> for SubnetCallContex
             install_code_contexts.insert(CallbackId::new(entry.callback_id), context);
         }
 
+        let mut sign_with_schnorr_contexts = BTreeMap::<CallbackId, SignWithSchnorrContext>::new();
+        for entry in item.sign_with_schnorr_contexts {
+            let context: SignWithSchnorrContext =
+                try_from_option_field(entry.context, "SystemMetadata::SignWithSchnorrContext")?;
+            sign_with_schnorr_contexts.insert(CallbackId::new(entry.callback_id), context);
+        }
+
+        let mut vetkd_contexts = BTreeMap::<CallbackId, VetKdContext>::new();
+        for entry in item.vetkd_contexts {
+            let context: VetKdContext =
+                try_from_option_field(entry.context, "SystemMetadata::VetKdContext")?;
+            vetkd_contexts.insert(CallbackId::new(entry.callback_id), context);
+        }
+
         Ok(Self {
             next_callback_id: item.next_callback_id,
             setup_initial_dkg_contexts,
@@ -336,6 +406,8 @@ impl TryFrom<(Time, pb_metadata::SubnetCallContextManager)> for SubnetCallContex
             bitcoin_get_successors_contexts,
             bitcoin_send_transaction_internal_contexts,
             install_code_contexts,
+            sign_with_schnorr_contexts,
+            vetkd_contexts,
         })
     }
 }
@@ -449,6 +521,77 @@ impl TryFrom<pb_metadata::SignWithEcdsaContext> for SignWithEcdsaContext {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignWithSchnorrContext {
+    pub request: Request,
+    pub key_id: SchnorrKeyId,
+    pub message: Vec<u8>,
+    pub derivation_path: Vec<Vec<u8>>,
+    pub batch_time: Time,
+}
+
+impl From<&SignWithSchnorrContext> for pb_metadata::SignWithSchnorrContext {
+    fn from(context: &SignWithSchnorrContext) -> Self {
+        pb_metadata::SignWithSchnorrContext {
+            request: Some((&context.request).into()),
+            key_id: Some((&context.key_id).into()),
+            message: context.message.clone(),
+            derivation_path: context.derivation_path.clone(),
+            batch_time: context.batch_time.as_nanos_since_unix_epoch(),
+        }
+    }
+}
+
+impl TryFrom<pb_metadata::SignWithSchnorrContext> for SignWithSchnorrContext {
+    type Error = ProxyDecodeError;
+    fn try_from(context: pb_metadata::SignWithSchnorrContext) -> Result<Self, Self::Error> {
+        Ok(SignWithSchnorrContext {
+            request: try_from_option_field(context.request, "SignWithSchnorrContext::request")?,
+            key_id: try_from_option_field(context.key_id, "SignWithSchnorrContext::key_id")?,
+            message: context.message,
+            derivation_path: context.derivation_path,
+            batch_time: Time::from_nanos_since_unix_epoch(context.batch_time),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VetKdContext {
+    pub request: Request,
+    pub key_id: VetKdKeyId,
+    pub derivation_path: Vec<Vec<u8>>,
+    pub derivation_id: Vec<u8>,
+    pub encryption_public_key: Vec<u8>,
+    pub batch_time: Time,
+}
+
+impl From<&VetKdContext> for pb_metadata::VetKdContext {
+    fn from(context: &VetKdContext) -> Self {
+        pb_metadata::VetKdContext {
+            request: Some((&context.request).into()),
+            key_id: Some((&context.key_id).into()),
+            derivation_path: context.derivation_path.clone(),
+            derivation_id: context.derivation_id.clone(),
+            encryption_public_key: context.encryption_public_key.clone(),
+            batch_time: context.batch_time.as_nanos_since_unix_epoch(),
+        }
+    }
+}
+
+impl TryFrom<pb_metadata::VetKdContext> for VetKdContext {
+    type Error = ProxyDecodeError;
+    fn try_from(context: pb_metadata::VetKdContext) -> Result<Self, Self::Error> {
+        Ok(VetKdContext {
+            request: try_from_option_field(context.request, "VetKdContext::request")?,
+            key_id: try_from_option_field(context.key_id, "VetKdContext::key_id")?,
+            derivation_path: context.derivation_path,
+            derivation_id: context.derivation_id,
+            encryption_public_key: context.encryption_public_key,
+            batch_time: Time::from_nanos_since_unix_epoch(context.batch_time),
+        })
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct EcdsaDealingsContext {
     pub request: Request,
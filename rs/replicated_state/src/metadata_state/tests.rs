@@ -1,11 +1,13 @@
 use super::*;
 use crate::metadata_state::subnet_call_context_manager::{
-    SubnetCallContext, SubnetCallContextManager,
+    SignWithSchnorrContext, SubnetCallContext, SubnetCallContextManager, VetKdContext,
 };
 use assert_matches::assert_matches;
 use ic_constants::MAX_INGRESS_TTL;
 use ic_error_types::{ErrorCode, UserError};
-use ic_ic00_types::{EcdsaCurve, IC_00};
+use ic_ic00_types::{
+    EcdsaCurve, SchnorrAlgorithm, SchnorrKeyId, VetKdCurve, VetKdKeyId, IC_00,
+};
 use ic_registry_routing_table::CanisterIdRange;
 use ic_test_utilities::{
     mock_time,
@@ -20,7 +22,7 @@ use ic_test_utilities::{
 };
 use ic_types::{canister_http::Transform, time::current_time};
 use ic_types::{
-    canister_http::{CanisterHttpMethod, CanisterHttpRequestContext},
+    canister_http::{CanisterHttpMethod, CanisterHttpRequestContext, Replication},
     ingress::WasmResult,
     messages::{CallbackId, Payload},
 };
@@ -644,6 +646,7 @@ fn subnet_call_contexts_deserialization() {
         http_method: CanisterHttpMethod::GET,
         transform: Some(transform.clone()),
         time: mock_time(),
+        replication: Replication::FullyReplicated,
     };
     system_call_context_manager.push_context(SubnetCallContext::CanisterHttpRequest(
         canister_http_request,
@@ -673,6 +676,119 @@ fn subnet_call_contexts_deserialization() {
     assert_eq!(deserialized_http_request_context.transform, Some(transform));
 }
 
+#[test]
+fn non_replicated_canister_http_request_context_roundtrip_encoding() {
+    let mut subnet_call_context_manager = SubnetCallContextManager::default();
+    let context = CanisterHttpRequestContext {
+        request: RequestBuilder::default()
+            .sender(canister_test_id(1))
+            .receiver(IC_00)
+            .build(),
+        url: "https://example.com".to_string(),
+        max_response_bytes: None,
+        headers: Vec::new(),
+        body: Some(vec![1, 2, 3]),
+        http_method: CanisterHttpMethod::PUT,
+        transform: None,
+        time: mock_time(),
+        replication: Replication::NonReplicated(node_test_id(3)),
+    };
+    subnet_call_context_manager.push_context(SubnetCallContext::CanisterHttpRequest(context));
+
+    let proto: ic_protobuf::state::system_metadata::v1::SubnetCallContextManager =
+        (&subnet_call_context_manager).into();
+    let deserialized = SubnetCallContextManager::try_from((mock_time(), proto)).unwrap();
+
+    assert_eq!(deserialized, subnet_call_context_manager);
+}
+
+#[test]
+fn sign_with_schnorr_contexts_roundtrip_encoding() {
+    let mut subnet_call_context_manager = SubnetCallContextManager::default();
+    let context = SignWithSchnorrContext {
+        request: RequestBuilder::default()
+            .sender(canister_test_id(1))
+            .receiver(IC_00)
+            .build(),
+        key_id: SchnorrKeyId {
+            algorithm: SchnorrAlgorithm::Ed25519,
+            name: "test_key_1".to_string(),
+        },
+        message: vec![1, 2, 3],
+        derivation_path: vec![vec![4, 5, 6], vec![]],
+        batch_time: mock_time(),
+    };
+    let callback_id = subnet_call_context_manager
+        .push_context(SubnetCallContext::SignWithSchnorr(context.clone()));
+
+    let proto: ic_protobuf::state::system_metadata::v1::SubnetCallContextManager =
+        (&subnet_call_context_manager).into();
+    let deserialized = SubnetCallContextManager::try_from((mock_time(), proto)).unwrap();
+
+    assert_eq!(deserialized, subnet_call_context_manager);
+    assert_eq!(
+        deserialized.sign_with_schnorr_contexts.get(&callback_id),
+        Some(&context)
+    );
+}
+
+#[test]
+fn vetkd_contexts_roundtrip_encoding() {
+    let mut subnet_call_context_manager = SubnetCallContextManager::default();
+    let context = VetKdContext {
+        request: RequestBuilder::default()
+            .sender(canister_test_id(1))
+            .receiver(IC_00)
+            .build(),
+        key_id: VetKdKeyId {
+            curve: VetKdCurve::Bls12_381_G2,
+            name: "test_key_1".to_string(),
+        },
+        derivation_path: vec![vec![1, 2, 3], vec![]],
+        derivation_id: vec![4, 5, 6],
+        encryption_public_key: vec![7; 48],
+        batch_time: mock_time(),
+    };
+    let callback_id =
+        subnet_call_context_manager.push_context(SubnetCallContext::VetKd(context.clone()));
+
+    let proto: ic_protobuf::state::system_metadata::v1::SubnetCallContextManager =
+        (&subnet_call_context_manager).into();
+    let deserialized = SubnetCallContextManager::try_from((mock_time(), proto)).unwrap();
+
+    assert_eq!(deserialized, subnet_call_context_manager);
+    assert_eq!(
+        deserialized.vetkd_contexts.get(&callback_id),
+        Some(&context)
+    );
+}
+
+#[test]
+fn raw_query_stats_roundtrip_encoding() {
+    let stats = |num_calls| QueryStats {
+        num_calls,
+        num_instructions: 1_000 * num_calls as u64,
+        ingress_payload_size: 10,
+        egress_payload_size: 20,
+    };
+    let raw_query_stats = RawQueryStats {
+        epoch: Some(QueryStatsEpoch::from(3)),
+        stats: btreemap! {
+            canister_test_id(1) => btreemap! {
+                node_test_id(1) => stats(1),
+                node_test_id(2) => stats(2),
+            },
+            canister_test_id(2) => btreemap! {
+                node_test_id(2) => stats(3),
+            },
+        },
+    };
+
+    let proto = pb_metadata::RawQueryStats::from(&raw_query_stats);
+    assert_eq!(proto.node_stats.len(), 2);
+    assert_eq!(RawQueryStats::try_from(proto).unwrap(), raw_query_stats);
+}
+
 #[test]
 fn empty_network_topology() {
     let network_topology = NetworkTopology {
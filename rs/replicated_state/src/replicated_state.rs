@@ -3,6 +3,7 @@ use super::{
     metadata_state::{IngressHistoryState, Stream, Streams, SystemMetadata},
 };
 use crate::{
+    canister_snapshots::CanisterSnapshots,
     canister_state::queues::CanisterQueuesLoopDetector,
     canister_state::system_state::{push_input, CanisterOutputQueuesIterator},
     metadata_state::StreamMap,
@@ -368,6 +369,9 @@ pub struct ReplicatedState {
     /// The queue is, therefore, emptied at the end of every round.
     // TODO(EXE-109): Move this queue into `subnet_queues`
     pub consensus_queue: Vec<Response>,
+
+    /// Snapshots of canister states, taken via `take_canister_snapshot`.
+    pub canister_snapshots: CanisterSnapshots,
 }
 
 impl ReplicatedState {
@@ -378,6 +382,7 @@ impl ReplicatedState {
             metadata: SystemMetadata::new(own_subnet_id, own_subnet_type),
             subnet_queues: CanisterQueues::default(),
             consensus_queue: Vec::new(),
+            canister_snapshots: CanisterSnapshots::default(),
         }
     }
 
@@ -386,12 +391,14 @@ impl ReplicatedState {
         canister_states: BTreeMap<CanisterId, CanisterState>,
         metadata: SystemMetadata,
         subnet_queues: CanisterQueues,
+        canister_snapshots: CanisterSnapshots,
     ) -> Self {
         let mut res = Self {
             canister_states,
             metadata,
             subnet_queues,
             consensus_queue: Vec::new(),
+            canister_snapshots,
         };
         res.update_stream_responses_size_bytes();
         res
@@ -843,6 +850,7 @@ impl ReplicatedState {
             metadata,
             mut subnet_queues,
             consensus_queue,
+            mut canister_snapshots,
         } = self;
 
         // Consensus queue is always empty at the end of the round.
@@ -854,6 +862,10 @@ impl ReplicatedState {
         canister_states
             .retain(|canister_id, _| routing_table.route(canister_id.get()) == Some(subnet_id));
 
+        // Snapshots follow the canisters they belong to.
+        canister_snapshots
+            .retain_canisters(|canister_id| canister_states.contains_key(canister_id));
+
         // All subnet messages (ingress and canister) only remain on subnet A' because:
         //
         //  * Message Routing would drop a response from subnet B to a request it had
@@ -877,6 +889,7 @@ impl ReplicatedState {
             metadata,
             subnet_queues,
             consensus_queue,
+            canister_snapshots,
         })
     }
 
@@ -896,6 +909,7 @@ impl ReplicatedState {
             mut metadata,
             subnet_queues,
             consensus_queue,
+            canister_snapshots,
         } = self;
 
         metadata
@@ -921,6 +935,7 @@ impl ReplicatedState {
             metadata,
             subnet_queues,
             consensus_queue,
+            canister_snapshots,
         };
         res.update_stream_responses_size_bytes();
         res
@@ -15,6 +15,7 @@ use ic_ledger_canister_core::{
     archive::{Archive, ArchiveOptions},
     ledger::{
         apply_transaction, archive_blocks, block_locations, find_block_in_archive, LedgerAccess,
+        TransferError as CoreTransferError,
     },
     range_utils,
 };
@@ -37,6 +38,7 @@ use icrc_ledger_types::icrc1::account::Account;
 use icrc_ledger_types::icrc1::transfer::TransferArg;
 use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
 use icrc_ledger_types::{
     icrc::generic_metadata_value::MetadataValue as Value, icrc3::archive::QueryArchiveFn,
 };
@@ -237,14 +239,17 @@ async fn send(
     Ok(height)
 }
 
+// Mints and burns are never performed on behalf of a spender, callers must
+// reject them before passing a `spender_account`.
 async fn icrc1_send(
     memo: Option<icrc_ledger_types::icrc1::transfer::Memo>,
     amount: Tokens,
     fee: Option<Nat>,
     from_account: Account,
     to: AccountIdentifier,
+    spender_account: Option<Account>,
     created_at_time: Option<TimeStamp>,
-) -> Result<BlockIndex, icrc_ledger_types::icrc1::transfer::TransferError> {
+) -> Result<BlockIndex, CoreTransferError<Tokens>> {
     let from = AccountIdentifier::from(from_account);
     let minting_acc = LEDGER
         .read()
@@ -254,43 +259,39 @@ async fn icrc1_send(
     let now = TimeStamp::from_nanos_since_unix_epoch(time_nanos());
     let (operation, effective_fee) = if to == minting_acc {
         if fee.is_some() && fee.as_ref() != Some(&Nat::from(0u64)) {
-            return Err(icrc_ledger_types::icrc1::transfer::TransferError::BadFee {
-                expected_fee: Nat::from(0u64),
+            return Err(CoreTransferError::BadFee {
+                expected_fee: Tokens::ZERO,
             });
         }
         let ledger = LEDGER.read().unwrap();
         let balance = ledger.balances.account_balance(&from);
         let min_burn_amount = ledger.transfer_fee.min(balance);
         if amount < min_burn_amount {
-            return Err(icrc_ledger_types::icrc1::transfer::TransferError::BadBurn {
-                min_burn_amount: Nat::from(min_burn_amount.get_e8s()),
-            });
+            return Err(CoreTransferError::BadBurn { min_burn_amount });
         }
         if amount == Tokens::ZERO {
-            return Err(icrc_ledger_types::icrc1::transfer::TransferError::BadBurn {
-                min_burn_amount: Nat::from(ledger.transfer_fee.get_e8s()),
+            return Err(CoreTransferError::BadBurn {
+                min_burn_amount: ledger.transfer_fee,
             });
         }
         (Operation::Burn { from, amount }, Tokens::ZERO)
     } else if from == minting_acc {
         if fee.is_some() && fee.as_ref() != Some(&Nat::from(0u64)) {
-            return Err(icrc_ledger_types::icrc1::transfer::TransferError::BadFee {
-                expected_fee: Nat::from(0u64),
+            return Err(CoreTransferError::BadFee {
+                expected_fee: Tokens::ZERO,
             });
         }
         (Operation::Mint { to, amount }, Tokens::ZERO)
     } else {
         let expected_fee = LEDGER.read().unwrap().transfer_fee;
         if fee.is_some() && fee.as_ref() != Some(&Nat::from(expected_fee.get_e8s())) {
-            return Err(icrc_ledger_types::icrc1::transfer::TransferError::BadFee {
-                expected_fee: Nat::from(expected_fee.get_e8s()),
-            });
+            return Err(CoreTransferError::BadFee { expected_fee });
         }
         (
             Operation::Transfer {
                 from,
                 to,
-                spender: None,
+                spender: spender_account.map(AccountIdentifier::from),
                 amount,
                 fee: expected_fee,
             },
@@ -306,15 +307,7 @@ async fn icrc1_send(
             icrc1_memo: memo.map(|x| x.0),
             created_at_time,
         };
-        let (block_index, hash) = apply_transaction(&mut *ledger, tx, now, effective_fee)
-            .map_err(convert_transfer_error)
-            .map_err(|err| {
-                let err: icrc_ledger_types::icrc1::transfer::TransferError = match err.try_into() {
-                    Ok(err) => err,
-                    Err(err) => trap_with(&err),
-                };
-                err
-            })?;
+        let (block_index, hash) = apply_transaction(&mut *ledger, tx, now, effective_fee)?;
 
         set_certified_data(&hash.into_bytes());
 
@@ -868,9 +861,25 @@ async fn icrc1_transfer(
     let created_at_time = arg
         .created_at_time
         .map(TimeStamp::from_nanos_since_unix_epoch);
-    Ok(Nat::from(
-        icrc1_send(arg.memo, amount, arg.fee, from_account, to, created_at_time).await?,
-    ))
+    icrc1_send(
+        arg.memo,
+        amount,
+        arg.fee,
+        from_account,
+        to,
+        None,
+        created_at_time,
+    )
+    .await
+    .map(Nat::from)
+    .map_err(convert_transfer_error)
+    .map_err(|err| {
+        let err: icrc_ledger_types::icrc1::transfer::TransferError = match err.try_into() {
+            Ok(err) => err,
+            Err(err) => trap_with(&err),
+        };
+        err
+    })
 }
 
 #[export_name = "canister_update transfer"]
@@ -1392,6 +1401,81 @@ fn icrc2_approve_candid() {
     })
 }
 
+#[candid_method(update, rename = "icrc2_transfer_from")]
+async fn icrc2_transfer_from(arg: TransferFromArgs) -> Result<Nat, TransferFromError> {
+    if !LEDGER.read().unwrap().feature_flags.icrc2 {
+        trap_with("ICRC-2 features are not enabled on the ledger.");
+    }
+    let spender_account = Account {
+        owner: caller().into(),
+        subaccount: arg.spender_subaccount,
+    };
+    let to = AccountIdentifier::from(arg.to);
+    let minting_acc = LEDGER
+        .read()
+        .unwrap()
+        .minting_account_id
+        .expect("Minting canister id not initialized");
+    if to == minting_acc {
+        return Err(TransferFromError::GenericError {
+            error_code: Nat::from(0u64),
+            message: "the minting account cannot be the recipient of transfer_from".to_string(),
+        });
+    }
+    if AccountIdentifier::from(arg.from) == minting_acc {
+        return Err(TransferFromError::GenericError {
+            error_code: Nat::from(0u64),
+            message: "the minter account cannot delegate mints".to_string(),
+        });
+    }
+    match arg.memo.as_ref() {
+        Some(memo) if memo.0.len() > MEMO_SIZE_BYTES => trap_with("the memo field is too large"),
+        _ => {}
+    };
+    let amount = match arg.amount.0.to_u64() {
+        Some(n) => Tokens::from_e8s(n),
+        None => {
+            // No one can have so many tokens
+            let balance = Nat::from(account_balance(AccountIdentifier::from(arg.from)).get_e8s());
+            assert!(balance < arg.amount);
+            return Err(TransferFromError::InsufficientFunds { balance });
+        }
+    };
+    let created_at_time = arg
+        .created_at_time
+        .map(TimeStamp::from_nanos_since_unix_epoch);
+    icrc1_send(
+        arg.memo,
+        amount,
+        arg.fee,
+        arg.from,
+        to,
+        Some(spender_account),
+        created_at_time,
+    )
+    .await
+    .map(Nat::from)
+    .map_err(convert_transfer_error)
+    .map_err(|err| {
+        let err: TransferFromError = match err.try_into() {
+            Ok(err) => err,
+            Err(err) => trap_with(&err),
+        };
+        err
+    })
+}
+
+#[export_name = "canister_update icrc2_transfer_from"]
+fn icrc2_transfer_from_candid() {
+    over_async_may_reject(candid_one, |arg: TransferFromArgs| async {
+        if !LEDGER.read().unwrap().can_send(&caller()) {
+            return Err("Anonymous principal cannot transfer tokens on the ledger.".to_string());
+        }
+
+        Ok(icrc2_transfer_from(arg).await)
+    })
+}
+
 #[candid_method(query, rename = "icrc2_allowance")]
 fn icrc2_allowance(arg: AllowanceArgs) -> Allowance {
     if !LEDGER.read().unwrap().feature_flags.icrc2 {
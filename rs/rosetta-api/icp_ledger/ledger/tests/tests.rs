@@ -3,8 +3,8 @@ use candid::{Decode, Encode, Nat};
 use dfn_protobuf::ProtoBuf;
 use ic_base_types::CanisterId;
 use ic_icrc1_ledger_sm_tests::{
-    default_approve_args, expect_icrc2_disabled, get_allowance, send_approval, supported_standards,
-    transfer, MINTER,
+    balance_of, default_approve_args, default_transfer_from_args, expect_icrc2_disabled,
+    get_allowance, send_approval, supported_standards, transfer, MINTER,
 };
 use ic_ledger_core::{block::BlockType, Tokens};
 use ic_state_machine_tests::{ErrorCode, PrincipalId, StateMachine, UserError};
@@ -18,6 +18,7 @@ use icrc_ledger_types::icrc1::{
     transfer::{Memo, TransferArg, TransferError},
 };
 use icrc_ledger_types::icrc2::allowance::AllowanceArgs;
+use icrc_ledger_types::icrc2::transfer_from::TransferFromError;
 use on_wire::{FromWire, IntoWire};
 use serde_bytes::ByteBuf;
 use std::collections::{HashMap, HashSet};
@@ -534,6 +535,73 @@ fn test_approve_from_minter() {
     ic_icrc1_ledger_sm_tests::test_approve_from_minter(ledger_wasm(), encode_init_args);
 }
 
+#[test]
+fn test_transfer_from_smoke() {
+    ic_icrc1_ledger_sm_tests::test_transfer_from_smoke(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_transfer_from_self() {
+    ic_icrc1_ledger_sm_tests::test_transfer_from_self(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_transfer_from_minter() {
+    // Unlike the ICRC-1 ledger, the ICP ledger neither mints nor burns on
+    // behalf of a spender.
+    let from = PrincipalId::new_user_test_id(1);
+    let spender = PrincipalId::new_user_test_id(2);
+    let to = PrincipalId::new_user_test_id(3);
+
+    let (env, canister_id) = ic_icrc1_ledger_sm_tests::setup(
+        ledger_wasm(),
+        encode_init_args,
+        vec![(Account::from(from.0), 100_000)],
+    );
+    send_approval(
+        &env,
+        canister_id,
+        from.0,
+        &default_approve_args(spender.0, 150_000),
+    )
+    .expect("approval failed");
+    let from_balance = balance_of(&env, canister_id, from.0);
+
+    let transfer_from = |from: Account, to: Account| {
+        let mut args = default_transfer_from_args(from, to, 30_000);
+        args.fee = None;
+        Decode!(
+            &env.execute_ingress_as(
+                spender,
+                canister_id,
+                "icrc2_transfer_from",
+                Encode!(&args).unwrap()
+            )
+            .expect("failed to call icrc2_transfer_from")
+            .bytes(),
+            Result<Nat, TransferFromError>
+        )
+        .expect("failed to decode transfer_from response")
+    };
+
+    assert_eq!(
+        transfer_from(MINTER, to.0.into()),
+        Err(TransferFromError::GenericError {
+            error_code: Nat::from(0u64),
+            message: "the minter account cannot delegate mints".to_string(),
+        })
+    );
+    assert_eq!(
+        transfer_from(from.0.into(), MINTER),
+        Err(TransferFromError::GenericError {
+            error_code: Nat::from(0u64),
+            message: "the minting account cannot be the recipient of transfer_from".to_string(),
+        })
+    );
+    assert_eq!(balance_of(&env, canister_id, to.0), 0);
+    assert_eq!(balance_of(&env, canister_id, from.0), from_balance);
+}
+
 #[test]
 fn test_feature_flags() {
     let ledger_wasm = ledger_wasm();
@@ -567,6 +635,7 @@ fn test_feature_flags() {
         account: from.0.into(),
         spender: spender.0.into(),
     };
+    let transfer_from_args = default_transfer_from_args(from.0, spender.0, 10_000);
 
     expect_icrc2_disabled(
         &env,
@@ -574,7 +643,7 @@ fn test_feature_flags() {
         canister_id,
         &approve_args,
         &allowance_args,
-        None,
+        Some(&transfer_from_args),
     );
 
     env.upgrade_canister(
@@ -595,7 +664,7 @@ fn test_feature_flags() {
         canister_id,
         &approve_args,
         &allowance_args,
-        None,
+        Some(&transfer_from_args),
     );
 
     env.upgrade_canister(
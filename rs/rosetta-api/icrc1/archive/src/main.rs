@@ -1,4 +1,4 @@
-use candid::{candid_method, Principal};
+use candid::{candid_method, Nat, Principal};
 use ic_canisters_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
 use ic_cdk_macros::{init, post_upgrade, query, update};
 use ic_icrc1::{blocks::encoded_block_to_generic_block, Block};
@@ -8,8 +8,12 @@ use ic_stable_structures::{
     cell::Cell as StableCell, log::Log as StableLog, memory_manager::MemoryManager,
     DefaultMemoryImpl, RestrictedMemory, Storable,
 };
-use icrc_ledger_types::icrc3::blocks::BlockRange;
+use icrc_ledger_types::icrc3::archive::{GetArchivesArgs, GetArchivesResult};
 use icrc_ledger_types::icrc3::blocks::GenericBlock as IcrcBlock;
+use icrc_ledger_types::icrc3::blocks::{
+    BlockRange, BlockWithId, GetBlocksRequest, GetBlocksResult, ICRC3DataCertificate,
+    SupportedBlockType,
+};
 
 use icrc_ledger_types::icrc3::transactions::Transaction;
 use icrc_ledger_types::icrc3::transactions::{GetTransactionsRequest, TransactionRange};
@@ -271,6 +275,66 @@ fn get_blocks(req: GetTransactionsRequest) -> BlockRange {
     BlockRange { blocks }
 }
 
+/// Returns the blocks stored in this archive that fall into the requested
+/// ranges. Unlike [get_blocks], this endpoint ignores the parts of the ranges
+/// that this archive does not serve instead of trapping.
+#[query]
+#[candid_method(query)]
+fn icrc3_get_blocks(reqs: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    let (offset, max_length) =
+        with_archive_opts(|opts| (opts.block_index_offset, opts.max_transactions_per_response));
+    let num_blocks = with_blocks(|blocks| blocks.len());
+
+    let mut blocks = vec![];
+    for req in reqs {
+        let (start, length) = match req.as_start_and_length() {
+            Ok(start_and_length) => start_and_length,
+            Err(_) => continue,
+        };
+        let end = start
+            .saturating_add(length)
+            .min(offset.saturating_add(num_blocks));
+        let start = start.max(offset);
+        let remaining = max_length.saturating_sub(blocks.len() as u64);
+        let end = end.min(start.saturating_add(remaining));
+        with_blocks(|log| {
+            for id in start..end {
+                let block = log.get(id - offset).unwrap();
+                blocks.push(BlockWithId {
+                    id: Nat::from(id),
+                    block: decode_icrc1_block(id, block),
+                });
+            }
+        });
+    }
+
+    GetBlocksResult {
+        log_length: Nat::from(offset.saturating_add(num_blocks)),
+        blocks,
+        archived_blocks: vec![],
+    }
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc3_get_archives(_args: GetArchivesArgs) -> GetArchivesResult {
+    vec![]
+}
+
+/// Archives do not certify their blocks, clients should use the certificate
+/// of the ledger instead.
+#[query]
+#[candid_method(query)]
+fn icrc3_get_tip_certificate() -> Option<ICRC3DataCertificate> {
+    None
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc3_supported_block_types() -> Vec<SupportedBlockType> {
+    ic_icrc1::endpoints::icrc3_supported_block_types()
+}
+
 #[query]
 fn __get_candid_interface_tmp_hack() -> &'static str {
     include_str!(env!("ARCHIVE_DID_PATH"))
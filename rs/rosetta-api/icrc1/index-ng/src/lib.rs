@@ -70,6 +70,68 @@ pub struct ListSubaccountsArgs {
     pub start: Option<Subaccount>,
 }
 
+#[derive(CandidType, Debug, Deserialize, PartialEq, Eq)]
+pub struct ListAllowancesArgs {
+    pub from_account: Account,
+    // If set then only the allowances granted to accounts
+    // of this principal are returned.
+    pub spender: Option<Principal>,
+    // The last spender seen by the client for the given account.
+    // This spender is excluded in the result.
+    // If None then the results will start from the first
+    // in natural order.
+    pub start: Option<Account>,
+    // Maximum number of allowances to fetch.
+    pub max_results: Option<Nat>,
+}
+
+#[derive(CandidType, Debug, Deserialize, PartialEq, Eq)]
+pub struct ListReceivedAllowancesArgs {
+    pub spender: Account,
+    // If set then only the allowances granted by accounts
+    // of this principal are returned.
+    pub from_owner: Option<Principal>,
+    // The last granting account seen by the client for the given spender.
+    // This account is excluded in the result.
+    // If None then the results will start from the first
+    // in natural order.
+    pub start: Option<Account>,
+    // Maximum number of allowances to fetch.
+    pub max_results: Option<Nat>,
+}
+
+#[derive(CandidType, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct AllowanceWithAccounts {
+    pub from_account: Account,
+    pub spender: Account,
+    pub allowance: Nat,
+    // The expiration of the allowance in nanoseconds since the Unix Epoch.
+    pub expires_at: Option<u64>,
+}
+
+#[derive(CandidType, Debug, Deserialize, PartialEq, Eq)]
+pub struct GetApprovalHistoryArgs {
+    pub from_account: Account,
+    pub spender: Account,
+    // The txid of the last approval seen by the client.
+    // If None then the results will start from the most recent
+    // approval. If set then the results will start from the next
+    // most recent approval after start (start won't be included).
+    pub start: Option<BlockIndex>,
+    // Maximum number of approvals to fetch.
+    pub max_results: Nat,
+}
+
+#[derive(CandidType, Debug, Deserialize, PartialEq, Eq)]
+pub struct GetApprovalHistoryResponse {
+    // The current allowance, zero if there is none or if it has expired.
+    pub allowance: Nat,
+    // The expiration of the current allowance in nanoseconds since the Unix Epoch.
+    pub expires_at: Option<u64>,
+    // The approve transactions from the most recent to the oldest.
+    pub approvals: Vec<TransactionWithId>,
+}
+
 #[derive(CandidType, Debug, Deserialize, PartialEq, Eq)]
 pub struct Status {
     pub num_blocks_synced: BlockIndex,
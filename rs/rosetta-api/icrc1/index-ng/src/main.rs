@@ -9,9 +9,10 @@ use ic_crypto_sha2::Sha256;
 use ic_icrc1::blocks::{encoded_block_to_generic_block, generic_block_to_encoded_block};
 use ic_icrc1::{Block, Operation};
 use ic_icrc1_index_ng::{
-    FeeCollectorRanges, GetAccountTransactionsArgs, GetAccountTransactionsResponse,
-    GetAccountTransactionsResult, IndexArg, ListSubaccountsArgs, Log, LogEntry, Status,
-    TransactionWithId, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
+    AllowanceWithAccounts, FeeCollectorRanges, GetAccountTransactionsArgs,
+    GetAccountTransactionsResponse, GetAccountTransactionsResult, GetApprovalHistoryArgs,
+    GetApprovalHistoryResponse, IndexArg, ListAllowancesArgs, ListReceivedAllowancesArgs,
+    ListSubaccountsArgs, Log, LogEntry, Status, TransactionWithId, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
 };
 use ic_ledger_core::block::{BlockIndex as BlockIndex64, BlockType, EncodedBlock};
 use ic_ledger_core::tokens::{CheckedAdd, CheckedSub, Zero};
@@ -21,7 +22,7 @@ use ic_stable_structures::{
     memory_manager::MemoryManager, BoundedStorable, DefaultMemoryImpl, StableBTreeMap, StableCell,
     StableLog, Storable,
 };
-use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use icrc_ledger_types::icrc1::account::{Account, Subaccount, DEFAULT_SUBACCOUNT};
 use icrc_ledger_types::icrc3::archive::{ArchivedRange, QueryBlockArchiveFn};
 use icrc_ledger_types::icrc3::blocks::{
     BlockRange, GenericBlock, GetBlocksRequest, GetBlocksResponse,
@@ -37,7 +38,7 @@ use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt::Debug;
 use std::hash::Hash;
-use std::ops::Bound::{Excluded, Included};
+use std::ops::Bound::{Excluded, Included, Unbounded};
 use std::ops::Range;
 use std::time::Duration;
 
@@ -50,10 +51,17 @@ const BLOCK_LOG_INDEX_MEMORY_ID: MemoryId = MemoryId::new(1);
 const BLOCK_LOG_DATA_MEMORY_ID: MemoryId = MemoryId::new(2);
 const ACCOUNT_BLOCK_IDS_MEMORY_ID: MemoryId = MemoryId::new(3);
 const ACCOUNT_DATA_MEMORY_ID: MemoryId = MemoryId::new(4);
+const ALLOWANCES_MEMORY_ID: MemoryId = MemoryId::new(5);
+const RECEIVED_ALLOWANCES_MEMORY_ID: MemoryId = MemoryId::new(6);
+const APPROVAL_BLOCK_IDS_MEMORY_ID: MemoryId = MemoryId::new(7);
 
 const DEFAULT_MAX_WAIT_TIME: Duration = Duration::from_secs(2);
 const DEFAULT_RETRY_WAIT_TIME: Duration = Duration::from_secs(1);
 
+// The number of blocks processed by a single [rebuild_allowances] call, so
+// that each call stays well below the instruction limit.
+const MAX_BLOCKS_PER_ALLOWANCES_REBUILD: u64 = 5_000;
+
 #[cfg(not(feature = "u256-tokens"))]
 type Tokens = ic_icrc1_tokens_u64::U64;
 
@@ -68,11 +76,30 @@ type BlockLog = StableLog<Vec<u8>, VM, VM>;
 type AccountBlockIdsMapKey = ([u8; Sha256::DIGEST_LEN], Reverse<u64>);
 type AccountBlockIdsMap = StableBTreeMap<AccountBlockIdsMapKey, (), VM>;
 
-// The second element of this tuple is the account represented
-// as principal of type Blob<29> and the effective subaccount
-type AccountDataMapKey = (AccountDataType, (Blob<29>, [u8; 32]));
+// An account represented as principal of type Blob<29>
+// and the effective subaccount
+type AccountKey = (Blob<29>, [u8; 32]);
+
+type AccountDataMapKey = (AccountDataType, AccountKey);
 type AccountDataMap = StableBTreeMap<AccountDataMapKey, Tokens, VM>;
 
+// The allowances are keyed by the account that granted them and the spender.
+// The value is the amount and the expiration in nanoseconds, where
+// NO_EXPIRATION means that the allowance never expires.
+type AllowancesMapKey = (AccountKey, AccountKey);
+type AllowancesMap = StableBTreeMap<AllowancesMapKey, (Tokens, u64), VM>;
+
+// The allowances keyed by the spender and the account that granted them.
+type ReceivedAllowancesMap = StableBTreeMap<AllowancesMapKey, (), VM>;
+
+// The block indexes of the approvals of a pair (account, spender). The
+// pair is hashed to save space and the block indexes are stored in reverse
+// order like in [AccountBlockIdsMap].
+type ApprovalBlockIdsMapKey = ([u8; Sha256::DIGEST_LEN], Reverse<u64>);
+type ApprovalBlockIdsMap = StableBTreeMap<ApprovalBlockIdsMapKey, (), VM>;
+
+const NO_EXPIRATION: u64 = u64::MAX;
+
 thread_local! {
     /// Static memory manager to manage the memory available for stable structures.
     static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
@@ -100,6 +127,21 @@ thread_local! {
         RefCell::new(AccountDataMap::init(memory_manager.get(ACCOUNT_DATA_MEMORY_ID)))
     });
 
+    /// Map that contains the current allowances.
+    static ALLOWANCES: RefCell<AllowancesMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(AllowancesMap::init(memory_manager.get(ALLOWANCES_MEMORY_ID)))
+    });
+
+    /// Map that contains the keys of [ALLOWANCES] with the spender first.
+    static RECEIVED_ALLOWANCES: RefCell<ReceivedAllowancesMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(ReceivedAllowancesMap::init(memory_manager.get(RECEIVED_ALLOWANCES_MEMORY_ID)))
+    });
+
+    /// Map that contains the block ids of the approvals of an (account, spender) pair.
+    static APPROVAL_BLOCK_IDS: RefCell<ApprovalBlockIdsMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(ApprovalBlockIdsMap::init(memory_manager.get(APPROVAL_BLOCK_IDS_MEMORY_ID)))
+    });
+
     /// Profiling data to understand cycles usage
     static PROFILING_DATA: RefCell<SpanStats> = RefCell::new(SpanStats::default());
 }
@@ -120,6 +162,16 @@ struct State {
 
     // The fees collectors with the ranges of blocks for which they collected the fee.
     fee_collectors: HashMap<Account, Vec<Range<BlockIndex64>>>,
+
+    // Equals to `true` if the allowances of all the blocks in the log are indexed.
+    // The field is missing in the state of indexes that did not index allowances.
+    #[serde(default)]
+    allowances_indexed: bool,
+
+    // The index of the next block processed by [rebuild_allowances] while
+    // the allowances are not indexed.
+    #[serde(default)]
+    allowances_rebuild_cursor: u64,
 }
 
 // NOTE: the default configuration is dysfunctional, but it's convenient to have
@@ -132,6 +184,8 @@ impl Default for State {
             max_blocks_per_response: DEFAULT_MAX_BLOCKS_PER_RESPONSE,
             last_wait_time: Duration::from_secs(0),
             fee_collectors: Default::default(),
+            allowances_indexed: true,
+            allowances_rebuild_cursor: 0,
         }
     }
 }
@@ -226,6 +280,21 @@ fn with_account_data<R>(f: impl FnOnce(&mut AccountDataMap) -> R) -> R {
     ACCOUNT_DATA.with(|cell| f(&mut cell.borrow_mut()))
 }
 
+/// A helper function to access the allowances.
+fn with_allowances<R>(f: impl FnOnce(&mut AllowancesMap) -> R) -> R {
+    ALLOWANCES.with(|cell| f(&mut cell.borrow_mut()))
+}
+
+/// A helper function to access the allowances keyed by spender.
+fn with_received_allowances<R>(f: impl FnOnce(&mut ReceivedAllowancesMap) -> R) -> R {
+    RECEIVED_ALLOWANCES.with(|cell| f(&mut cell.borrow_mut()))
+}
+
+/// A helper function to access the approval block ids.
+fn with_approval_block_ids<R>(f: impl FnOnce(&mut ApprovalBlockIdsMap) -> R) -> R {
+    APPROVAL_BLOCK_IDS.with(|cell| f(&mut cell.borrow_mut()))
+}
+
 /// A helper function that returns a decoded block stored in the
 /// block log at the given index or None if there is no block at that index.
 /// This function can trap if the index at the given block cannot be decoded
@@ -258,12 +327,54 @@ fn change_balance(account: Account, f: impl FnOnce(Tokens) -> Tokens) {
     }
 }
 
-fn balance_key(account: Account) -> (AccountDataType, (Blob<29>, [u8; 32])) {
+fn account_key(account: Account) -> AccountKey {
     let owner = Blob::try_from(account.owner.as_slice()).unwrap();
-    (
-        AccountDataType::Balance,
-        (owner, *account.effective_subaccount()),
-    )
+    (owner, *account.effective_subaccount())
+}
+
+fn account_from_key((owner, subaccount): AccountKey) -> Account {
+    Account {
+        owner: Principal::from_slice(owner.as_slice()),
+        subaccount: Some(subaccount).filter(|subaccount| subaccount != DEFAULT_SUBACCOUNT),
+    }
+}
+
+fn balance_key(account: Account) -> (AccountDataType, AccountKey) {
+    (AccountDataType::Balance, account_key(account))
+}
+
+/// A helper function to access the allowance of `spender` on `from`.
+/// Returns the amount and the expiration or None if there is no allowance.
+/// Expired allowances are returned too.
+fn get_allowance(from: Account, spender: Account) -> Option<(Tokens, Option<u64>)> {
+    with_allowances(|allowances| allowances.get(&(account_key(from), account_key(spender))))
+        .map(|(amount, expires_at)| (amount, stored_expiration(expires_at)))
+}
+
+/// Converts an expiration stored in [ALLOWANCES] into an optional expiration.
+fn stored_expiration(expires_at: u64) -> Option<u64> {
+    Some(expires_at).filter(|expires_at| *expires_at != NO_EXPIRATION)
+}
+
+/// A helper function to change the allowance of `spender` on `from`.
+/// It removes the allowance if the amount is 0.
+fn set_allowance(from: Account, spender: Account, amount: Tokens, expires_at: Option<u64>) {
+    let (from_key, spender_key) = (account_key(from), account_key(spender));
+    if Tokens::is_zero(&amount) {
+        with_allowances(|allowances| allowances.remove(&(from_key, spender_key)));
+        with_received_allowances(|allowances| allowances.remove(&(spender_key, from_key)));
+    } else {
+        let expires_at = expires_at.unwrap_or(NO_EXPIRATION);
+        with_allowances(|allowances| {
+            allowances.insert((from_key, spender_key), (amount, expires_at))
+        });
+        with_received_allowances(|allowances| allowances.insert((spender_key, from_key), ()));
+    }
+}
+
+/// Returns true if an allowance with the given expiration has expired at `now`.
+fn is_expired(expires_at: Option<u64>, now: u64) -> bool {
+    expires_at.map_or(false, |expires_at| expires_at <= now)
 }
 
 #[init]
@@ -285,6 +396,13 @@ fn init(index_arg: Option<IndexArg>) {
 
 #[post_upgrade]
 fn post_upgrade() {
+    // The allowances of an index that did not index them are rebuilt from
+    // the block log in batches, because the log can be too large to process
+    // within the upgrade.
+    if !with_state(|state| state.allowances_indexed) {
+        set_rebuild_allowances_timer(Duration::from_secs(0));
+    }
+
     // set the first build_index to be called after init
     set_build_index_timer(Duration::from_secs(0));
 }
@@ -455,6 +573,12 @@ fn append_block(block_index: BlockIndex64, block: GenericBlock) {
 
         // change the balance of the involved accounts
         process_balance_changes(block_index, &decoded_block);
+
+        // change the allowances of the involved accounts, unless they are
+        // being rebuilt, in which case the rebuild processes the block
+        if with_state(|state| state.allowances_indexed) {
+            process_allowance_changes(block_index, &decoded_block);
+        }
     });
 }
 
@@ -542,6 +666,141 @@ fn process_balance_changes(block_index: BlockIndex64, block: &Block<Tokens>) {
     );
 }
 
+fn process_allowance_changes(block_index: BlockIndex64, block: &Block<Tokens>) {
+    measure_span(
+        &PROFILING_DATA,
+        "append_blocks.process_allowance_changes",
+        move || match block.transaction.operation {
+            Operation::Approve {
+                from,
+                spender,
+                amount,
+                expires_at,
+                ..
+            } => {
+                with_approval_block_ids(|approval_block_ids| {
+                    approval_block_ids
+                        .insert(approval_block_ids_key(from, spender, block_index), ())
+                });
+                set_allowance(
+                    from,
+                    spender,
+                    amount,
+                    expires_at.map(|exp| exp.as_nanos_since_unix_epoch()),
+                );
+            }
+            Operation::Transfer {
+                from,
+                spender: Some(spender),
+                amount,
+                fee,
+                ..
+            } if from != spender => {
+                let fee = block.effective_fee.or(fee).unwrap_or_else(|| {
+                    ic_cdk::trap(&format!(
+                        "Block {} is of type Transfer but has no fee or effective fee!",
+                        block_index
+                    ))
+                });
+                let used_allowance = amount.checked_add(&fee).unwrap_or_else(|| {
+                    ic_cdk::trap(&format!(
+                        "token amount overflow while indexing block {block_index}"
+                    ))
+                });
+                use_allowance(block_index, from, spender, used_allowance);
+            }
+            Operation::Burn {
+                from,
+                spender: Some(spender),
+                amount,
+            } if from != spender => use_allowance(block_index, from, spender, amount),
+            _ => {}
+        },
+    );
+}
+
+/// Rebuilds the allowances and the approval block ids of at most
+/// `max_blocks` blocks of the block log, starting at
+/// [State::allowances_rebuild_cursor]. An index upgraded from a version that
+/// did not index allowances has Approve blocks in its log that it never
+/// processed. Returns `true` once all the blocks in the log are processed.
+fn rebuild_allowances(max_blocks: u64) -> bool {
+    if with_state(|state| state.allowances_indexed) {
+        return true;
+    }
+    let start = with_state(|state| state.allowances_rebuild_cursor);
+    if start == 0 {
+        with_allowances(clear_map);
+        with_received_allowances(clear_map);
+        with_approval_block_ids(clear_map);
+    }
+    let num_blocks = with_blocks(|blocks| blocks.len());
+    let end = num_blocks.min(start.saturating_add(max_blocks));
+    for block_index in start..end {
+        let block = get_decoded_block(block_index).unwrap_or_else(|| {
+            trap(&format!(
+                "Block {} is missing while rebuilding the allowances",
+                block_index
+            ))
+        });
+        process_allowance_changes(block_index, &block);
+    }
+    let done = end == num_blocks;
+    mutate_state(|state| {
+        state.allowances_indexed = done;
+        state.allowances_rebuild_cursor = if done { 0 } else { end };
+    });
+    if done {
+        log!(
+            P0,
+            "[rebuild_allowances] rebuilt the allowances of {} blocks",
+            num_blocks
+        );
+    }
+    done
+}
+
+fn set_rebuild_allowances_timer(after: Duration) -> TimerId {
+    ic_cdk_timers::set_timer(after, || {
+        if !rebuild_allowances(MAX_BLOCKS_PER_ALLOWANCES_REBUILD) {
+            set_rebuild_allowances_timer(Duration::from_secs(0));
+        }
+    })
+}
+
+/// Traps if the allowances are still being rebuilt after an upgrade, because
+/// they would be incomplete.
+fn check_allowances_indexed() {
+    if !with_state(|state| state.allowances_indexed) {
+        trap("The allowances are not ready yet, they are being rebuilt from the block log. Try again later.");
+    }
+}
+
+fn clear_map<K, V>(map: &mut StableBTreeMap<K, V, VM>)
+where
+    K: BoundedStorable + Ord + Clone,
+    V: BoundedStorable,
+{
+    let keys: Vec<K> = map.iter().map(|(key, _)| key).collect();
+    for key in keys {
+        map.remove(&key);
+    }
+}
+
+fn use_allowance(block_index: BlockIndex64, from: Account, spender: Account, amount: Tokens) {
+    let (allowance, expires_at) = get_allowance(from, spender).unwrap_or_else(|| {
+        ic_cdk::trap(&format!(
+            "Block {} uses the allowance of {} on {} but there is no allowance",
+            block_index, spender, from
+        ))
+    });
+    let allowance = allowance.checked_sub(&amount).unwrap_or_else(|| {
+        ic_cdk::trap(&format!("Block {} caused an underflow for the allowance of {} on {} when calculating allowance {} - amount {}",
+            block_index, spender, from, allowance, amount))
+    });
+    set_allowance(from, spender, allowance, expires_at);
+}
+
 fn debit(block_index: BlockIndex64, account: Account, amount: Tokens) {
     change_balance(account, |balance| {
         balance.checked_sub(&amount).unwrap_or_else(|| {
@@ -617,6 +876,21 @@ fn account_block_ids_key(account: Account, block_index: BlockIndex64) -> Account
     (account_sha256(account), Reverse(block_index))
 }
 
+fn approval_sha256(from: Account, spender: Account) -> [u8; Sha256::DIGEST_LEN] {
+    let mut hasher = Sha256::new();
+    from.hash(&mut hasher);
+    spender.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn approval_block_ids_key(
+    from: Account,
+    spender: Account,
+    block_index: BlockIndex64,
+) -> ApprovalBlockIdsMapKey {
+    (approval_sha256(from, spender), Reverse(block_index))
+}
+
 fn decode_icrc1_block(_txid: u64, bytes: Vec<u8>) -> GenericBlock {
     let encoded_block = EncodedBlock::from(bytes);
     encoded_block_to_generic_block(&encoded_block)
@@ -668,7 +942,6 @@ fn get_account_transactions(arg: GetAccountTransactionsArgs) -> GetAccountTransa
         .start
         .map_or(u64::MAX, |n| n.0.to_u64().expect("start must be a u64!"));
     let key = account_block_ids_key(arg.account, start);
-    let mut transactions = vec![];
     let indices = with_account_block_ids(|account_block_ids| {
         account_block_ids
             .range(key..)
@@ -678,22 +951,7 @@ fn get_account_transactions(arg: GetAccountTransactionsArgs) -> GetAccountTransa
             .map(|(k, _)| k.1 .0)
             .collect::<Vec<BlockIndex64>>()
     });
-    for id in indices {
-        let block = with_blocks(|blocks| {
-            blocks.get(id).unwrap_or_else(|| {
-                trap(&format!(
-                    "Block {} not found in the block log, account blocks map is corrupted!",
-                    id
-                ))
-            })
-        });
-        let transaction = encoded_block_bytes_to_flat_transaction(id, block);
-        let transaction_with_idx = TransactionWithId {
-            id: id.into(),
-            transaction,
-        };
-        transactions.push(transaction_with_idx);
-    }
+    let transactions = get_transactions_with_id(indices, "account blocks");
     let oldest_tx_id = get_oldest_tx_id(arg.account).map(|tx_id| tx_id.into());
     let balance = get_balance(arg.account).into();
     Ok(GetAccountTransactionsResponse {
@@ -703,6 +961,29 @@ fn get_account_transactions(arg: GetAccountTransactionsArgs) -> GetAccountTransa
     })
 }
 
+/// Returns the transactions at the given block indices. `map_name` is the
+/// name of the map the indices come from, which is corrupted if a block
+/// is missing.
+fn get_transactions_with_id(indices: Vec<BlockIndex64>, map_name: &str) -> Vec<TransactionWithId> {
+    indices
+        .into_iter()
+        .map(|id| {
+            let block = with_blocks(|blocks| {
+                blocks.get(id).unwrap_or_else(|| {
+                    trap(&format!(
+                        "Block {} not found in the block log, {} map is corrupted!",
+                        id, map_name
+                    ))
+                })
+            });
+            TransactionWithId {
+                id: id.into(),
+                transaction: encoded_block_bytes_to_flat_transaction(id, block),
+            }
+        })
+        .collect()
+}
+
 fn encoded_block_bytes_to_flat_transaction(
     block_index: BlockIndex64,
     block: Vec<u8>,
@@ -838,6 +1119,138 @@ fn list_subaccounts(args: ListSubaccountsArgs) -> Vec<Subaccount> {
     })
 }
 
+/// Returns the maximum number of results of a list request, which is
+/// `max_results` capped to [State::max_blocks_per_response].
+fn capped_max_results(max_results: Option<Nat>) -> usize {
+    let max_blocks_per_response = with_state(|opts| opts.max_blocks_per_response);
+    max_results
+        .map_or(max_blocks_per_response, |n| {
+            n.0.to_u64().expect("max_results must be a u64!")
+        })
+        .min(max_blocks_per_response)
+        .min(usize::MAX as u64) as usize
+}
+
+/// Returns the active allowances in `map`, whose keys are pairs of accounts
+/// starting with `account`, in natural order. If `owner` is set then only the
+/// keys whose second account is of `owner` are considered. If `start` is set
+/// then the results start after the key (`account`, `start`), or at the first
+/// key of `owner` if `start` precedes it.
+fn list_active_allowances<V: BoundedStorable>(
+    map: &StableBTreeMap<AllowancesMapKey, V, VM>,
+    account: Account,
+    owner: Option<Principal>,
+    start: Option<Account>,
+    max_results: usize,
+    to_allowance: impl Fn(AllowancesMapKey, V) -> AllowanceWithAccounts,
+) -> Vec<AllowanceWithAccounts> {
+    let owner_key = owner.map(|owner| account_key(Account::from(owner)).0);
+    let key = account_key(account);
+    let first_key = (
+        key,
+        (
+            owner_key.unwrap_or_else(|| Blob::try_from(&[] as &[u8]).unwrap()),
+            [0; 32],
+        ),
+    );
+    let lower_bound = match start.map(|start| (key, account_key(start))) {
+        Some(start_key) if start_key >= first_key => Excluded(start_key),
+        _ => Included(first_key),
+    };
+    let now = ic_cdk::api::time();
+    map.range((lower_bound, Unbounded))
+        .take_while(|((first, second), _)| {
+            *first == key && owner_key.map_or(true, |owner_key| second.0 == owner_key)
+        })
+        .map(|(key, value)| to_allowance(key, value))
+        .filter(|allowance| !is_expired(allowance.expires_at, now))
+        .take(max_results)
+        .collect()
+}
+
+#[query]
+#[candid_method(query)]
+fn list_allowances(args: ListAllowancesArgs) -> Vec<AllowanceWithAccounts> {
+    check_allowances_indexed();
+    let max_results = capped_max_results(args.max_results);
+    with_allowances(|allowances| {
+        list_active_allowances(
+            allowances,
+            args.from_account,
+            args.spender,
+            args.start,
+            max_results,
+            |(from, spender), (amount, expires_at)| AllowanceWithAccounts {
+                from_account: account_from_key(from),
+                spender: account_from_key(spender),
+                allowance: amount.into(),
+                expires_at: stored_expiration(expires_at),
+            },
+        )
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn list_received_allowances(args: ListReceivedAllowancesArgs) -> Vec<AllowanceWithAccounts> {
+    check_allowances_indexed();
+    let max_results = capped_max_results(args.max_results);
+    with_received_allowances(|received_allowances| {
+        list_active_allowances(
+            received_allowances,
+            args.spender,
+            args.from_owner,
+            args.start,
+            max_results,
+            |(spender, from), ()| {
+                let (from, spender) = (account_from_key(from), account_from_key(spender));
+                let (amount, expires_at) = get_allowance(from, spender).unwrap_or_else(|| {
+                    trap(&format!(
+                        "Allowance of {} on {} not found, received allowances map is corrupted!",
+                        spender, from
+                    ))
+                });
+                AllowanceWithAccounts {
+                    from_account: from,
+                    spender,
+                    allowance: amount.into(),
+                    expires_at,
+                }
+            },
+        )
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn get_approval_history(args: GetApprovalHistoryArgs) -> GetApprovalHistoryResponse {
+    check_allowances_indexed();
+    let length = capped_max_results(Some(args.max_results));
+    let start = args
+        .start
+        .map_or(u64::MAX, |n| n.0.to_u64().expect("start must be a u64!"));
+    let key = approval_block_ids_key(args.from_account, args.spender, start);
+    let indices = with_approval_block_ids(|approval_block_ids| {
+        approval_block_ids
+            .range(key..)
+            // old approvals of the requested pair and skip the start index
+            .take_while(|(k, _)| k.0 == key.0)
+            .filter(|(k, _)| k.1 .0 != start)
+            .take(length)
+            .map(|(k, _)| k.1 .0)
+            .collect::<Vec<BlockIndex64>>()
+    });
+    let approvals = get_transactions_with_id(indices, "approval blocks");
+    let (allowance, expires_at) = get_allowance(args.from_account, args.spender)
+        .filter(|(_, expires_at)| !is_expired(*expires_at, ic_cdk::api::time()))
+        .unwrap_or_else(|| (Tokens::zero(), None));
+    GetApprovalHistoryResponse {
+        allowance: allowance.into(),
+        expires_at,
+        approvals,
+    }
+}
+
 #[candid_method(query)]
 #[query]
 fn http_request(req: HttpRequest) -> HttpResponse {
@@ -978,3 +1391,92 @@ fn compute_wait_time_test() {
     assert_eq!(wait_time(25), compute_wait_time(blocks(75)));
     assert_eq!(wait_time(0), compute_wait_time(blocks(100)));
 }
+
+#[test]
+fn rebuild_allowances_test() {
+    const FEE: u64 = 10_000;
+
+    fn tokens(n: u64) -> Tokens {
+        Tokens::try_from(Nat::from(n)).unwrap()
+    }
+
+    fn block(operation: Operation<Tokens>) -> GenericBlock {
+        let block = Block {
+            parent_hash: None,
+            transaction: ic_icrc1::Transaction {
+                operation,
+                created_at_time: None,
+                memo: None,
+            },
+            effective_fee: Some(tokens(FEE)),
+            timestamp: 0,
+            fee_collector: None,
+            fee_collector_block_index: None,
+        };
+        encoded_block_to_generic_block(&block.encode())
+    }
+
+    let from = Account::from(Principal::from_slice(&[1]));
+    let spender = Account::from(Principal::from_slice(&[2]));
+    let to = Account::from(Principal::from_slice(&[3]));
+    append_blocks(vec![
+        block(Operation::Mint {
+            to: from,
+            amount: tokens(1_000_000),
+        }),
+        block(Operation::Approve {
+            from,
+            spender,
+            amount: tokens(100_000),
+            expected_allowance: None,
+            expires_at: None,
+            fee: None,
+        }),
+    ]);
+
+    // An index that did not index allowances has the Approve block in its
+    // log but no allowances after the upgrade.
+    with_allowances(clear_map);
+    with_received_allowances(clear_map);
+    with_approval_block_ids(clear_map);
+    mutate_state(|state| state.allowances_indexed = false);
+
+    // The rebuild processes one block per call.
+    assert!(!rebuild_allowances(1));
+    assert_eq!(with_state(|state| state.allowances_rebuild_cursor), 1);
+    assert!(!with_state(|state| state.allowances_indexed));
+
+    // A block appended during the rebuild is processed by the rebuild only.
+    append_blocks(vec![block(Operation::Transfer {
+        from,
+        to,
+        spender: Some(spender),
+        amount: tokens(40_000),
+        fee: None,
+    })]);
+    assert_eq!(get_allowance(from, spender), None);
+    assert!(!rebuild_allowances(1));
+    assert_eq!(get_allowance(from, spender), Some((tokens(100_000), None)));
+    assert!(rebuild_allowances(1));
+    assert!(with_state(|state| state.allowances_indexed));
+    assert_eq!(with_state(|state| state.allowances_rebuild_cursor), 0);
+    assert_eq!(get_allowance(from, spender), Some((tokens(50_000), None)));
+    assert_eq!(with_approval_block_ids(|ids| ids.len()), 1);
+
+    // Blocks appended after the rebuild change the allowances directly.
+    append_blocks(vec![block(Operation::Transfer {
+        from,
+        to,
+        spender: Some(spender),
+        amount: tokens(10_000),
+        fee: None,
+    })]);
+    assert_eq!(get_allowance(from, spender), Some((tokens(30_000), None)));
+
+    // Rebuilding the allowances again does not use the approval twice.
+    mutate_state(|state| state.allowances_indexed = false);
+    assert!(rebuild_allowances(MAX_BLOCKS_PER_ALLOWANCES_REBUILD));
+    assert_eq!(get_allowance(from, spender), Some((tokens(30_000), None)));
+    assert_eq!(with_received_allowances(|allowances| allowances.len()), 1);
+    assert_eq!(with_approval_block_ids(|ids| ids.len()), 1);
+}
@@ -2,12 +2,14 @@ use candid::{Decode, Encode, Nat};
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_canisters_http_types::{HttpRequest, HttpResponse};
 use ic_icrc1_index_ng::{
-    FeeCollectorRanges, GetAccountTransactionsArgs, GetAccountTransactionsResponse,
-    GetAccountTransactionsResult, GetBlocksResponse, IndexArg, InitArg as IndexInitArg,
-    ListSubaccountsArgs, Log, Status, TransactionWithId, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
+    AllowanceWithAccounts, FeeCollectorRanges, GetAccountTransactionsArgs,
+    GetAccountTransactionsResponse, GetAccountTransactionsResult, GetApprovalHistoryArgs,
+    GetApprovalHistoryResponse, GetBlocksResponse, IndexArg, InitArg as IndexInitArg,
+    ListAllowancesArgs, ListReceivedAllowancesArgs, ListSubaccountsArgs, Log, Status,
+    TransactionWithId, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
 };
 use ic_icrc1_ledger::{
-    ChangeFeeCollector, InitArgsBuilder as LedgerInitArgsBuilder, LedgerArgument,
+    ChangeFeeCollector, FeatureFlags, InitArgsBuilder as LedgerInitArgsBuilder, LedgerArgument,
     UpgradeArgs as LedgerUpgradeArgs,
 };
 use ic_icrc1_test_utils::{valid_transactions_strategy, CallerTransferArg};
@@ -15,6 +17,8 @@ use ic_ledger_canister_core::archive::ArchiveOptions;
 use ic_state_machine_tests::{StateMachine, WasmResult};
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use icrc_ledger_types::icrc1::transfer::{BlockIndex, TransferArg, TransferError};
+use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
 use icrc_ledger_types::icrc3::blocks::{BlockRange, GenericBlock, GetBlocksRequest};
 use icrc_ledger_types::icrc3::transactions::{Mint, Transaction, Transfer};
 use num_traits::cast::ToPrimitive;
@@ -22,7 +26,7 @@ use proptest::test_runner::{Config as TestRunnerConfig, TestRunner};
 use std::collections::HashSet;
 use std::convert::TryInto;
 use std::path::PathBuf;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 const FEE: u64 = 10_000;
 const ARCHIVE_TRIGGER_THRESHOLD: u64 = 10;
@@ -98,7 +102,8 @@ fn install_ledger(
         .with_metadata_entry(INT_META_KEY, INT_META_VALUE)
         .with_metadata_entry(TEXT_META_KEY, TEXT_META_VALUE)
         .with_metadata_entry(BLOB_META_KEY, BLOB_META_VALUE)
-        .with_archive_options(archive_options);
+        .with_archive_options(archive_options)
+        .with_feature_flags(FeatureFlags { icrc2: true });
     if let Some(fee_collector_account) = fee_collector_account {
         builder = builder.with_fee_collector_account(fee_collector_account);
     }
@@ -352,6 +357,61 @@ fn transfer(
     icrc1_transfer(env, ledger_id, owner.into(), req)
 }
 
+fn approve(
+    env: &StateMachine,
+    ledger_id: CanisterId,
+    from: Account,
+    spender: Account,
+    amount: u64,
+    expires_at: Option<u64>,
+) -> BlockIndex {
+    let req = ApproveArgs {
+        from_subaccount: from.subaccount,
+        spender,
+        amount: amount.into(),
+        expected_allowance: None,
+        expires_at,
+        fee: None,
+        memo: None,
+        created_at_time: None,
+    };
+    let req = Encode!(&req).expect("Failed to encode ApproveArgs");
+    let res = env
+        .execute_ingress_as(from.owner.into(), ledger_id, "icrc2_approve", req)
+        .expect("Failed to approve")
+        .bytes();
+    Decode!(&res, Result<BlockIndex, ApproveError>)
+        .expect("Failed to decode Result<BlockIndex, ApproveError>")
+        .expect("Failed to approve")
+}
+
+fn transfer_from(
+    env: &StateMachine,
+    ledger_id: CanisterId,
+    spender: Account,
+    from: Account,
+    to: Account,
+    amount: u64,
+) -> BlockIndex {
+    let req = TransferFromArgs {
+        spender_subaccount: spender.subaccount,
+        from,
+        to,
+        amount: amount.into(),
+        fee: None,
+        memo: None,
+        created_at_time: None,
+    };
+    let req = Encode!(&req).expect("Failed to encode TransferFromArgs");
+    let res = env
+        .execute_ingress_as(spender.owner.into(), ledger_id, "icrc2_transfer_from", req)
+        .expect("Failed to transfer tokens")
+        .bytes();
+    Decode!(&res, Result<BlockIndex, TransferFromError>)
+        .expect("Failed to decode Result<BlockIndex, TransferFromError>")
+        .expect("Failed to transfer tokens")
+}
+
 // Same as get_account_transactions but with the old index interface
 fn old_get_account_transactions(
     env: &StateMachine,
@@ -420,6 +480,86 @@ fn list_subaccounts(
     .expect("failed to decode list_subaccounts response")
 }
 
+fn list_allowances(
+    env: &StateMachine,
+    index: CanisterId,
+    from_account: Account,
+    spender: Option<PrincipalId>,
+    start: Option<Account>,
+    max_results: Option<u64>,
+) -> Vec<AllowanceWithAccounts> {
+    Decode!(
+        &env.execute_ingress(
+            index,
+            "list_allowances",
+            Encode!(&ListAllowancesArgs {
+                from_account,
+                spender: spender.map(|spender| spender.0),
+                start,
+                max_results: max_results.map(Nat::from),
+            })
+            .unwrap()
+        )
+        .expect("failed to list_allowances")
+        .bytes(),
+        Vec<AllowanceWithAccounts>
+    )
+    .expect("failed to decode list_allowances response")
+}
+
+fn list_received_allowances(
+    env: &StateMachine,
+    index: CanisterId,
+    spender: Account,
+    from_owner: Option<PrincipalId>,
+    start: Option<Account>,
+) -> Vec<AllowanceWithAccounts> {
+    Decode!(
+        &env.execute_ingress(
+            index,
+            "list_received_allowances",
+            Encode!(&ListReceivedAllowancesArgs {
+                spender,
+                from_owner: from_owner.map(|from_owner| from_owner.0),
+                start,
+                max_results: None,
+            })
+            .unwrap()
+        )
+        .expect("failed to list_received_allowances")
+        .bytes(),
+        Vec<AllowanceWithAccounts>
+    )
+    .expect("failed to decode list_received_allowances response")
+}
+
+fn get_approval_history(
+    env: &StateMachine,
+    index: CanisterId,
+    from_account: Account,
+    spender: Account,
+    start: Option<u64>,
+    max_results: u64,
+) -> GetApprovalHistoryResponse {
+    Decode!(
+        &env.execute_ingress(
+            index,
+            "get_approval_history",
+            Encode!(&GetApprovalHistoryArgs {
+                from_account,
+                spender,
+                start: start.map(|n| n.into()),
+                max_results: max_results.into(),
+            })
+            .unwrap()
+        )
+        .expect("failed to get_approval_history")
+        .bytes(),
+        GetApprovalHistoryResponse
+    )
+    .expect("failed to decode get_approval_history response")
+}
+
 fn get_fee_collectors_ranges(env: &StateMachine, index: CanisterId) -> FeeCollectorRanges {
     Decode!(
         &env.execute_ingress(index, "get_fee_collectors_ranges", Encode!(&()).unwrap())
@@ -1198,3 +1338,237 @@ fn test_upgrade_index_to_index_ng() {
         )
         .unwrap();
 }
+
+fn system_time_to_nanos(t: SystemTime) -> u64 {
+    t.duration_since(SystemTime::UNIX_EPOCH).unwrap().as_nanos() as u64
+}
+
+fn allowance(
+    from_account: Account,
+    spender: Account,
+    allowance: u64,
+    expires_at: Option<u64>,
+) -> AllowanceWithAccounts {
+    AllowanceWithAccounts {
+        from_account,
+        spender,
+        allowance: allowance.into(),
+        expires_at,
+    }
+}
+
+fn approval_ids(history: &GetApprovalHistoryResponse) -> Vec<Nat> {
+    history.approvals.iter().map(|tx| tx.id.clone()).collect()
+}
+
+#[test]
+fn test_allowances() {
+    let env = &StateMachine::new();
+    let ledger_id = install_ledger(
+        env,
+        vec![(account(1, 0), 10_000_000), (account(2, 0), 10_000_000)], // txid: 0, 1
+        default_archive_options(),
+        None,
+    );
+    let index_id = install_index_ng(env, ledger_id);
+
+    let one_day = Duration::from_secs(24 * 60 * 60).as_nanos() as u64;
+    let expiration = system_time_to_nanos(env.time()) + one_day;
+
+    approve(env, ledger_id, account(1, 0), account(3, 0), 100_000, None); // txid: 2
+    approve(
+        env,
+        ledger_id,
+        account(1, 0),
+        account(3, 1),
+        200_000,
+        Some(expiration),
+    ); // txid: 3
+    approve(env, ledger_id, account(1, 0), account(4, 0), 300_000, None); // txid: 4
+    approve(env, ledger_id, account(2, 0), account(3, 0), 400_000, None); // txid: 5
+    approve(env, ledger_id, account(1, 0), account(3, 0), 500_000, None); // txid: 6
+    transfer_from(
+        env,
+        ledger_id,
+        account(3, 0),
+        account(1, 0),
+        account(5, 0),
+        100_000,
+    ); // txid: 7
+    approve(env, ledger_id, account(1, 0), account(5, 0), 600_000, None); // txid: 8
+    approve(env, ledger_id, account(1, 0), account(5, 0), 0, None); // txid: 9
+
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    // transfer_from uses the amount plus the fee of the allowance and
+    // an approval of 0 tokens removes the allowance
+    let allowance_1_3_0 = allowance(account(1, 0), account(3, 0), 500_000 - 100_000 - FEE, None);
+    let allowance_1_3_1 = allowance(account(1, 0), account(3, 1), 200_000, Some(expiration));
+    let allowance_1_4_0 = allowance(account(1, 0), account(4, 0), 300_000, None);
+    let allowance_2_3_0 = allowance(account(2, 0), account(3, 0), 400_000, None);
+
+    let granted = list_allowances(env, index_id, account(1, 0), None, None, None);
+    assert_eq!(3, granted.len());
+    assert!(granted.contains(&allowance_1_3_0));
+    assert!(granted.contains(&allowance_1_3_1));
+    assert!(granted.contains(&allowance_1_4_0));
+
+    // pagination
+    assert_eq!(
+        granted[..2].to_vec(),
+        list_allowances(env, index_id, account(1, 0), None, None, Some(2))
+    );
+    assert_eq!(
+        granted[2..].to_vec(),
+        list_allowances(
+            env,
+            index_id,
+            account(1, 0),
+            None,
+            Some(granted[1].spender),
+            Some(2)
+        )
+    );
+
+    // filter by spender
+    let spender_3 = PrincipalId::new_user_test_id(3);
+    assert_eq!(
+        vec![allowance_1_3_0.clone(), allowance_1_3_1.clone()],
+        list_allowances(env, index_id, account(1, 0), Some(spender_3), None, None)
+    );
+    assert_eq!(
+        vec![allowance_1_3_1],
+        list_allowances(
+            env,
+            index_id,
+            account(1, 0),
+            Some(spender_3),
+            Some(account(3, 0)),
+            None
+        )
+    );
+    // a start of another spender that precedes the filtered spender does
+    // not skip the allowances of the filtered spender
+    assert_eq!(
+        vec![allowance_1_4_0],
+        list_allowances(
+            env,
+            index_id,
+            account(1, 0),
+            Some(PrincipalId::new_user_test_id(4)),
+            Some(account(3, 0)),
+            None
+        )
+    );
+
+    // allowances received by account(3, 0)
+    let received = list_received_allowances(env, index_id, account(3, 0), None, None);
+    assert_eq!(2, received.len());
+    assert!(received.contains(&allowance_1_3_0));
+    assert!(received.contains(&allowance_2_3_0));
+    assert_eq!(
+        vec![allowance_2_3_0.clone()],
+        list_received_allowances(
+            env,
+            index_id,
+            account(3, 0),
+            Some(PrincipalId::new_user_test_id(2)),
+            None
+        )
+    );
+    assert!(list_received_allowances(env, index_id, account(5, 0), None, None).is_empty());
+
+    // approval history
+    let history = get_approval_history(env, index_id, account(1, 0), account(3, 0), None, 10);
+    assert_eq!(allowance_1_3_0.allowance, history.allowance);
+    assert_eq!(None, history.expires_at);
+    assert_eq!(
+        vec![Nat::from(6u64), Nat::from(2u64)],
+        approval_ids(&history)
+    );
+    assert!(history
+        .approvals
+        .iter()
+        .all(|tx| tx.transaction.kind == "approve"));
+    let history = get_approval_history(env, index_id, account(1, 0), account(3, 0), Some(6), 10);
+    assert_eq!(vec![Nat::from(2u64)], approval_ids(&history));
+    let history = get_approval_history(env, index_id, account(1, 0), account(5, 0), None, 10);
+    assert_eq!(Nat::from(0u64), history.allowance);
+    assert_eq!(
+        vec![Nat::from(9u64), Nat::from(8u64)],
+        approval_ids(&history)
+    );
+
+    // expired allowances are not listed
+    let short_expiration =
+        system_time_to_nanos(env.time()) + Duration::from_secs(10 * 60).as_nanos() as u64;
+    approve(
+        env,
+        ledger_id,
+        account(2, 0),
+        account(4, 0),
+        700_000,
+        Some(short_expiration),
+    ); // txid: 10
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+    assert_eq!(
+        2,
+        list_allowances(env, index_id, account(2, 0), None, None, None).len()
+    );
+    env.advance_time(Duration::from_secs(60 * 60));
+    assert_eq!(
+        vec![allowance_2_3_0],
+        list_allowances(env, index_id, account(2, 0), None, None, None)
+    );
+    let history = get_approval_history(env, index_id, account(2, 0), account(4, 0), None, 10);
+    assert_eq!(Nat::from(0u64), history.allowance);
+    assert_eq!(vec![Nat::from(10u64)], approval_ids(&history));
+}
+
+#[test]
+fn test_allowances_after_upgrade() {
+    let env = &StateMachine::new();
+    let ledger_id = install_ledger(
+        env,
+        vec![(account(1, 0), 10_000_000)], // txid: 0
+        default_archive_options(),
+        None,
+    );
+    let index_id = install_index_ng(env, ledger_id);
+
+    approve(env, ledger_id, account(1, 0), account(3, 0), 500_000, None); // txid: 1
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    env.upgrade_canister(index_id, index_ng_wasm(), vec![])
+        .unwrap();
+
+    // the approval indexed before the upgrade can be used after it
+    transfer_from(
+        env,
+        ledger_id,
+        account(3, 0),
+        account(1, 0),
+        account(5, 0),
+        100_000,
+    ); // txid: 2
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+    assert_ledger_index_parity(env, ledger_id, index_id);
+
+    // upgrading again does not use the approval twice
+    env.upgrade_canister(index_id, index_ng_wasm(), vec![])
+        .unwrap();
+    approve(env, ledger_id, account(1, 0), account(4, 0), 300_000, None); // txid: 3
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    let granted = list_allowances(env, index_id, account(1, 0), None, None, None);
+    assert_eq!(2, granted.len());
+    assert!(granted.contains(&allowance(
+        account(1, 0),
+        account(3, 0),
+        500_000 - 100_000 - FEE,
+        None
+    )));
+    assert!(granted.contains(&allowance(account(1, 0), account(4, 0), 300_000, None)));
+    let history = get_approval_history(env, index_id, account(1, 0), account(3, 0), None, 10);
+    assert_eq!(vec![Nat::from(1u64)], approval_ids(&history));
+}
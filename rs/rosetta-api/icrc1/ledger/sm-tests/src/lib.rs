@@ -1,5 +1,6 @@
 use candid::{CandidType, Decode, Encode, Nat, Principal};
 use ic_base_types::PrincipalId;
+use ic_crypto_tree_hash::{LookupStatus, MixedHashTree};
 use ic_error_types::UserError;
 use ic_icrc1::{endpoints::StandardRecord, hash::Hash, Block, Operation, Transaction};
 use ic_icrc1_ledger::FeatureFlags;
@@ -13,10 +14,12 @@ use icrc_ledger_types::icrc1::transfer::{Memo, TransferArg, TransferError};
 use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
 use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
-use icrc_ledger_types::icrc3::archive::ArchiveInfo;
+use icrc_ledger_types::icrc3::archive::{ArchiveInfo, GetArchivesArgs, GetArchivesResult};
 use icrc_ledger_types::icrc3::blocks::BlockRange;
 use icrc_ledger_types::icrc3::blocks::GenericBlock as IcrcBlock;
-use icrc_ledger_types::icrc3::blocks::GetBlocksResponse;
+use icrc_ledger_types::icrc3::blocks::{
+    DataCertificate, GetBlocksRequest, GetBlocksResponse, GetBlocksResult, ICRC3DataCertificate,
+};
 use icrc_ledger_types::icrc3::transactions::GetTransactionsRequest;
 use icrc_ledger_types::icrc3::transactions::GetTransactionsResponse;
 use icrc_ledger_types::icrc3::transactions::Transaction as Tx;
@@ -312,6 +315,63 @@ fn get_archive_blocks(
     get_transactions_as(env, archive, start, length, "get_blocks".to_string())
 }
 
+fn icrc3_get_blocks(
+    env: &StateMachine,
+    canister_id: Principal,
+    args: Vec<GetBlocksRequest>,
+) -> GetBlocksResult {
+    let canister_id =
+        CanisterId::new(canister_id.into()).expect("failed to convert Principal to CanisterId");
+    Decode!(
+        &env.query(canister_id, "icrc3_get_blocks", Encode!(&args).unwrap())
+            .expect("failed to query icrc3_get_blocks")
+            .bytes(),
+        GetBlocksResult
+    )
+    .expect("failed to decode icrc3_get_blocks response")
+}
+
+fn icrc3_get_archives(
+    env: &StateMachine,
+    ledger: CanisterId,
+    from: Option<Principal>,
+) -> GetArchivesResult {
+    Decode!(
+        &env.query(
+            ledger,
+            "icrc3_get_archives",
+            Encode!(&GetArchivesArgs { from }).unwrap()
+        )
+        .expect("failed to query icrc3_get_archives")
+        .bytes(),
+        GetArchivesResult
+    )
+    .expect("failed to decode icrc3_get_archives response")
+}
+
+fn icrc3_get_tip_certificate(
+    env: &StateMachine,
+    ledger: CanisterId,
+) -> Option<ICRC3DataCertificate> {
+    Decode!(
+        &env.query(ledger, "icrc3_get_tip_certificate", Encode!().unwrap())
+            .expect("failed to query icrc3_get_tip_certificate")
+            .bytes(),
+        Option<ICRC3DataCertificate>
+    )
+    .expect("failed to decode icrc3_get_tip_certificate response")
+}
+
+fn get_data_certificate(env: &StateMachine, ledger: CanisterId) -> DataCertificate {
+    Decode!(
+        &env.query(ledger, "get_data_certificate", Encode!().unwrap())
+            .expect("failed to query get_data_certificate")
+            .bytes(),
+        DataCertificate
+    )
+    .expect("failed to decode get_data_certificate response")
+}
+
 fn get_phash(block: &IcrcBlock) -> Result<Option<Hash>, String> {
     match block {
         IcrcBlock::Map(map) => {
@@ -750,7 +810,7 @@ where
         standards.push(standard.name);
     }
     standards.sort();
-    assert_eq!(standards, vec!["ICRC-1", "ICRC-2"]);
+    assert_eq!(standards, vec!["ICRC-1", "ICRC-2", "ICRC-3"]);
 }
 
 pub fn test_total_supply<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
@@ -1343,6 +1403,336 @@ where
     assert_eq!(0, missing_blocks_reply.archived_blocks.len());
 }
 
+pub fn test_icrc3_get_blocks<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
+where
+    T: CandidType,
+{
+    let p1 = PrincipalId::new_user_test_id(1);
+    let p2 = PrincipalId::new_user_test_id(2);
+
+    let (env, canister_id) = setup(
+        ledger_wasm,
+        encode_init_args,
+        vec![(Account::from(p1.0), 10_000_000)],
+    );
+
+    for i in 0..ARCHIVE_TRIGGER_THRESHOLD {
+        transfer(&env, canister_id, p1.0, p2.0, 10_000 + i * 10_000).expect("transfer failed");
+    }
+
+    env.run_until_completion(/*max_ticks=*/ 10);
+
+    let log_length = ARCHIVE_TRIGGER_THRESHOLD + 1;
+    let resp = icrc3_get_blocks(
+        &env,
+        canister_id.get().0,
+        vec![GetBlocksRequest {
+            start: Nat::from(0),
+            length: Nat::from(1_000_000),
+        }],
+    );
+    assert_eq!(resp.log_length, Nat::from(log_length));
+    let ids: Vec<_> = resp.blocks.iter().map(|b| b.id.clone()).collect();
+    let expected_ids: Vec<_> = (NUM_BLOCKS_TO_ARCHIVE..log_length).map(Nat::from).collect();
+    assert_eq!(ids, expected_ids);
+
+    // The local blocks are the same as the ones served by get_blocks.
+    let legacy_blocks = get_blocks(&env, canister_id.get().0, 0, 1_000_000).blocks;
+    let blocks: Vec<_> = resp.blocks.iter().map(|b| b.block.clone()).collect();
+    assert_eq!(blocks, legacy_blocks);
+
+    let archives = icrc3_get_archives(&env, canister_id, None);
+    assert_eq!(archives.len(), 1);
+    assert_eq!(archives[0].start, Nat::from(0));
+    assert_eq!(archives[0].end, Nat::from(NUM_BLOCKS_TO_ARCHIVE - 1));
+    let archive_principal = archives[0].canister_id;
+    assert_eq!(
+        icrc3_get_archives(&env, canister_id, Some(archive_principal)),
+        vec![]
+    );
+
+    assert_eq!(resp.archived_blocks.len(), 1);
+    let archived = &resp.archived_blocks[0];
+    assert_eq!(archived.callback.canister_id, archive_principal);
+    assert_eq!(archived.callback.method, "icrc3_get_blocks");
+    assert_eq!(
+        archived.args,
+        vec![GetBlocksRequest {
+            start: Nat::from(0),
+            length: Nat::from(NUM_BLOCKS_TO_ARCHIVE),
+        }]
+    );
+
+    let archived_resp = icrc3_get_blocks(&env, archive_principal, archived.args.clone());
+    assert!(archived_resp.archived_blocks.is_empty());
+    let ids: Vec<_> = archived_resp.blocks.iter().map(|b| b.id.clone()).collect();
+    let expected_ids: Vec<_> = (0..NUM_BLOCKS_TO_ARCHIVE).map(Nat::from).collect();
+    assert_eq!(ids, expected_ids);
+
+    // Check that the hash chain built from generic block hashes is correct.
+    let mut prev_hash = None;
+    for block in archived_resp
+        .blocks
+        .into_iter()
+        .chain(resp.blocks.into_iter())
+    {
+        assert_eq!(
+            prev_hash,
+            get_phash(&block.block).expect("cannot get the hash of the previous block")
+        );
+        prev_hash = Some(block.block.hash());
+    }
+
+    // Requesting several ranges, including invalid ones, returns only existing blocks.
+    let resp = icrc3_get_blocks(
+        &env,
+        canister_id.get().0,
+        vec![
+            GetBlocksRequest {
+                start: Nat::from(log_length - 1),
+                length: Nat::from(5),
+            },
+            GetBlocksRequest {
+                start: Nat::from(100),
+                length: Nat::from(5),
+            },
+        ],
+    );
+    assert_eq!(resp.blocks.len(), 1);
+    assert_eq!(resp.blocks[0].id, Nat::from(log_length - 1));
+    assert!(resp.archived_blocks.is_empty());
+
+    // The tip certificate uses the ICRC-3 labels and encodings.
+    let certificate =
+        icrc3_get_tip_certificate(&env, canister_id).expect("the ledger must certify its tip");
+    let hash_tree: MixedHashTree = ciborium::de::from_reader(certificate.hash_tree.as_slice())
+        .expect("failed to decode the hash tree");
+    let last_block_index = match hash_tree.lookup(&[b"last_block_index"]) {
+        LookupStatus::Found(MixedHashTree::Leaf(bytes)) => {
+            leb128::read::unsigned(&mut bytes.as_slice()).expect("invalid LEB128 encoding")
+        }
+        status => panic!(
+            "unexpected lookup result for last_block_index: {:?}",
+            status
+        ),
+    };
+    assert_eq!(last_block_index, log_length - 1);
+    let tip = icrc3_get_blocks(
+        &env,
+        canister_id.get().0,
+        vec![GetBlocksRequest {
+            start: Nat::from(last_block_index),
+            length: Nat::from(1),
+        }],
+    );
+    let tip_hash = tip.blocks[0].block.hash();
+    for label in [&b"last_block_hash"[..], &b"tip_hash"[..]] {
+        match hash_tree.lookup(&[label]) {
+            LookupStatus::Found(MixedHashTree::Leaf(bytes)) => {
+                assert_eq!(bytes.as_slice(), tip_hash.as_slice())
+            }
+            status => panic!("unexpected lookup result for {:?}: {:?}", label, status),
+        }
+    }
+}
+
+/// Decodes the certificate of `get_data_certificate` the way clients did
+/// before the ledger certified an ICRC-3 shaped tree, i.e. with
+/// `last_block_index` as a big-endian u64 and the hash under `tip_hash`.
+/// The hash is still found, but the index is misread: this is the breaking
+/// change recorded in the ledger CHANGELOG.
+pub fn test_get_data_certificate_with_pre_icrc3_client<T>(
+    ledger_wasm: Vec<u8>,
+    encode_init_args: fn(InitArgs) -> T,
+) where
+    T: CandidType,
+{
+    let p1 = PrincipalId::new_user_test_id(1);
+    let p2 = PrincipalId::new_user_test_id(2);
+
+    let (env, canister_id) = setup(
+        ledger_wasm,
+        encode_init_args,
+        vec![(Account::from(p1.0), 10_000_000)],
+    );
+    for i in 0..3 {
+        transfer(&env, canister_id, p1.0, p2.0, 10_000 + i).expect("transfer failed");
+    }
+    let tip_index = 3;
+
+    let certificate = get_data_certificate(&env, canister_id);
+    assert!(certificate.certificate.is_some());
+    let hash_tree: MixedHashTree = ciborium::de::from_reader(certificate.hash_tree.as_slice())
+        .expect("failed to decode the hash tree");
+
+    // The pre-ICRC-3 client logic.
+    let old_last_block_index = match hash_tree.lookup(&[b"last_block_index"]) {
+        LookupStatus::Found(MixedHashTree::Leaf(l)) => {
+            let mut bytes: [u8; 8] = [0u8; 8];
+            for (i, e) in l.iter().enumerate() {
+                bytes[i] = *e;
+            }
+            u64::from_be_bytes(bytes)
+        }
+        status => panic!(
+            "unexpected lookup result for last_block_index: {:?}",
+            status
+        ),
+    };
+    let old_last_block_hash = match hash_tree.lookup(&[b"tip_hash"]) {
+        LookupStatus::Found(MixedHashTree::Leaf(l)) => l.clone(),
+        status => panic!("unexpected lookup result for tip_hash: {:?}", status),
+    };
+
+    let tip = icrc3_get_blocks(
+        &env,
+        canister_id.get().0,
+        vec![GetBlocksRequest {
+            start: Nat::from(tip_index),
+            length: Nat::from(1),
+        }],
+    );
+    assert_eq!(
+        old_last_block_hash.as_slice(),
+        tip.blocks[0].block.hash().as_slice()
+    );
+    // The LEB128 encoding of 3 is the single byte 0x03, which the old client
+    // reads as 0x03 << 56.
+    assert_eq!(old_last_block_index, tip_index << 56);
+
+    // Clients that detect the ICRC-3 tree by its last_block_hash label read
+    // the right index.
+    assert!(hash_tree.lookup(&[b"last_block_hash"]).is_found());
+    match hash_tree.lookup(&[b"last_block_index"]) {
+        LookupStatus::Found(MixedHashTree::Leaf(l)) => assert_eq!(
+            leb128::read::unsigned(&mut l.as_slice()).expect("invalid LEB128 encoding"),
+            tip_index
+        ),
+        status => panic!(
+            "unexpected lookup result for last_block_index: {:?}",
+            status
+        ),
+    }
+}
+
+pub fn test_icrc3_get_archives<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
+where
+    T: CandidType,
+{
+    let p1 = PrincipalId::new_user_test_id(1);
+    let p2 = PrincipalId::new_user_test_id(2);
+
+    // Archive nodes fit only a few blocks so that the ledger has to spawn several of them.
+    let mut args = init_args(vec![(Account::from(p1.0), 10_000_000)]);
+    args.archive_options.node_max_memory_size_bytes = Some(1024);
+    let env = StateMachine::new();
+    let canister_id = env
+        .install_canister(ledger_wasm, Encode!(&encode_init_args(args)).unwrap(), None)
+        .unwrap();
+
+    for i in 0..5 * ARCHIVE_TRIGGER_THRESHOLD {
+        transfer(&env, canister_id, p1.0, p2.0, 10_000 + i).expect("transfer failed");
+        env.run_until_completion(/*max_ticks=*/ 10);
+    }
+    let log_length = 5 * ARCHIVE_TRIGGER_THRESHOLD + 1;
+
+    let archives = icrc3_get_archives(&env, canister_id, None);
+    assert!(
+        archives.len() > 1,
+        "expected several archives, got {:?}",
+        archives
+    );
+    // The archives cover consecutive ranges starting from the first block.
+    assert_eq!(archives[0].start, Nat::from(0));
+    for (prev, next) in archives.iter().zip(archives.iter().skip(1)) {
+        assert_eq!(next.start, prev.end.clone() + Nat::from(1));
+    }
+    let first_local_block = archives.last().unwrap().end.clone() + Nat::from(1);
+
+    // The from argument skips all the archives up to and including the given one.
+    for (i, archive) in archives.iter().enumerate() {
+        assert_eq!(
+            icrc3_get_archives(&env, canister_id, Some(archive.canister_id)),
+            archives[i + 1..].to_vec()
+        );
+    }
+    // An unknown principal does not filter out anything.
+    assert_eq!(icrc3_get_archives(&env, canister_id, Some(p1.0)), archives);
+
+    // Requesting the whole log returns one archived range per archive.
+    let resp = icrc3_get_blocks(
+        &env,
+        canister_id.get().0,
+        vec![GetBlocksRequest {
+            start: Nat::from(0),
+            length: Nat::from(log_length),
+        }],
+    );
+    assert_eq!(resp.log_length, Nat::from(log_length));
+    assert_eq!(
+        resp.blocks.first().map(|b| b.id.clone()),
+        Some(first_local_block)
+    );
+    assert_eq!(resp.archived_blocks.len(), archives.len());
+
+    let mut blocks = vec![];
+    for archive in &archives {
+        let archived = resp
+            .archived_blocks
+            .iter()
+            .find(|archived| archived.callback.canister_id == archive.canister_id)
+            .expect("missing archived range for an archive");
+        assert_eq!(archived.callback.method, "icrc3_get_blocks");
+        assert_eq!(
+            archived.args,
+            vec![GetBlocksRequest {
+                start: archive.start.clone(),
+                length: archive.end.clone() - archive.start.clone() + Nat::from(1),
+            }]
+        );
+
+        let archived_resp = icrc3_get_blocks(&env, archive.canister_id, archived.args.clone());
+        assert!(archived_resp.archived_blocks.is_empty());
+        assert_eq!(archived_resp.log_length, archive.end.clone() + Nat::from(1));
+        blocks.extend(archived_resp.blocks);
+
+        // Archives serve only the blocks they store.
+        let archived_resp = icrc3_get_blocks(
+            &env,
+            archive.canister_id,
+            vec![GetBlocksRequest {
+                start: Nat::from(0),
+                length: Nat::from(log_length),
+            }],
+        );
+        let ids: Vec<_> = archived_resp.blocks.iter().map(|b| b.id.clone()).collect();
+        let expected_ids: Vec<_> = (archive.start.0.to_u64().unwrap()
+            ..=archive.end.0.to_u64().unwrap())
+            .map(Nat::from)
+            .collect();
+        assert_eq!(ids, expected_ids);
+
+        // Archives do not have archives of their own nor certify their tip.
+        let archive_id = CanisterId::new(archive.canister_id.into()).unwrap();
+        assert_eq!(icrc3_get_archives(&env, archive_id, None), vec![]);
+        assert_eq!(icrc3_get_tip_certificate(&env, archive_id), None);
+    }
+    blocks.extend(resp.blocks);
+
+    // The archived and local blocks form the whole hash chain.
+    let ids: Vec<_> = blocks.iter().map(|b| b.id.clone()).collect();
+    let expected_ids: Vec<_> = (0..log_length).map(Nat::from).collect();
+    assert_eq!(ids, expected_ids);
+    let mut prev_hash = None;
+    for block in blocks {
+        assert_eq!(
+            prev_hash,
+            get_phash(&block.block).expect("cannot get the hash of the previous block")
+        );
+        prev_hash = Some(block.block.hash());
+    }
+}
+
 // Generate random blocks and check that their CBOR encoding complies with the CDDL spec.
 pub fn block_encoding_agrees_with_the_schema() {
     use std::path::PathBuf;
@@ -1962,7 +2352,7 @@ pub fn default_approve_args(spender: impl Into<Account>, amount: u64) -> Approve
     }
 }
 
-fn default_transfer_from_args(
+pub fn default_transfer_from_args(
     from: impl Into<Account>,
     to: impl Into<Account>,
     amount: u64,
@@ -2421,8 +2811,8 @@ pub fn expect_icrc2_disabled(
         );
     }
     let standards = supported_standards(env, canister_id);
-    assert_eq!(standards.len(), 1);
-    assert_eq!(standards[0].name, "ICRC-1");
+    assert!(standards.iter().any(|standard| standard.name == "ICRC-1"));
+    assert!(!standards.iter().any(|standard| standard.name == "ICRC-2"));
 }
 
 pub fn test_feature_flags<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
@@ -2494,7 +2884,7 @@ where
         standards.push(standard.name);
     }
     standards.sort();
-    assert_eq!(standards, vec!["ICRC-1", "ICRC-2"]);
+    assert_eq!(standards, vec!["ICRC-1", "ICRC-2", "ICRC-3"]);
 
     let block_index =
         send_approval(&env, canister_id, from.0, &approve_args).expect("approval failed");
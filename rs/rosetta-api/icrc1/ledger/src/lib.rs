@@ -28,10 +28,15 @@ use ic_ledger_core::{
 use ic_ledger_hash_of::HashOf;
 use icrc_ledger_types::icrc1::account::Account;
 use icrc_ledger_types::icrc3::transactions::Transaction as Tx;
-use icrc_ledger_types::icrc3::{blocks::GetBlocksResponse, transactions::GetTransactionsResponse};
+use icrc_ledger_types::icrc3::{
+    blocks::{ArchivedBlocks, BlockWithId, GetBlocksRequest, GetBlocksResponse, GetBlocksResult},
+    transactions::GetTransactionsResponse,
+};
 use icrc_ledger_types::{
     icrc::generic_metadata_value::MetadataValue as Value,
-    icrc3::archive::{ArchivedRange, QueryBlockArchiveFn, QueryTxArchiveFn},
+    icrc3::archive::{
+        ArchivedRange, ICRC3QueryBlockArchiveFn, QueryBlockArchiveFn, QueryTxArchiveFn,
+    },
 };
 use serde::{Deserialize, Serialize};
 use serde_bytes::ByteBuf;
@@ -609,15 +614,29 @@ impl<Tokens: TokensType> Ledger<Tokens> {
         self.construct_hash_tree().digest().0
     }
 
+    /// Returns the certified tree of the ledger tip in the shape required by ICRC-3:
+    /// `last_block_index` holds the LEB128-encoded index of the tip and `last_block_hash` its
+    /// hash. The tree also keeps the `tip_hash` label of the previous version so that existing
+    /// clients can still find the tip hash. Clients can tell the versions apart by the presence
+    /// of `last_block_hash`: older ledgers encode `last_block_index` as a big-endian u64.
     pub fn construct_hash_tree(&self) -> MixedHashTree {
         match self.blockchain().last_hash {
             Some(hash) => {
                 let last_block_index = self.blockchain().chain_length().checked_sub(1).unwrap();
+                let mut last_block_index_encoded = vec![];
+                leb128::write::unsigned(&mut last_block_index_encoded, last_block_index)
+                    .expect("failed to encode the last block index");
                 MixedHashTree::Fork(Box::new((
-                    MixedHashTree::Labeled(
-                        Label::from("last_block_index"),
-                        Box::new(MixedHashTree::Leaf(last_block_index.to_be_bytes().to_vec())),
-                    ),
+                    MixedHashTree::Fork(Box::new((
+                        MixedHashTree::Labeled(
+                            Label::from("last_block_hash"),
+                            Box::new(MixedHashTree::Leaf(hash.as_slice().to_vec())),
+                        ),
+                        MixedHashTree::Labeled(
+                            Label::from("last_block_index"),
+                            Box::new(MixedHashTree::Leaf(last_block_index_encoded)),
+                        ),
+                    ))),
                     MixedHashTree::Labeled(
                         Label::from("tip_hash"),
                         Box::new(MixedHashTree::Leaf(hash.as_slice().to_vec())),
@@ -698,4 +717,59 @@ impl<Tokens: TokensType> Ledger<Tokens> {
             archived_blocks,
         }
     }
+
+    /// Returns the blocks in the specified ranges following the ICRC-3
+    /// standard. Ranges that cannot be represented as `u64` are ignored.
+    /// At most [MAX_TRANSACTIONS_PER_REQUEST] blocks are returned by the
+    /// ledger itself, archived ranges are grouped by archive canister.
+    pub fn icrc3_get_blocks(&self, args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+        let mut blocks = vec![];
+        let mut archived: BTreeMap<Principal, Vec<GetBlocksRequest>> = BTreeMap::new();
+        for arg in args {
+            let (start, length) = match arg.as_start_and_length() {
+                Ok(start_and_length) => start_and_length,
+                Err(_) => continue,
+            };
+            let length = length.min(usize::MAX as u64) as usize;
+            let locations = block_locations(self, start, length);
+
+            let remaining = MAX_TRANSACTIONS_PER_REQUEST.saturating_sub(blocks.len());
+            let local_blocks_range = range_utils::take(&locations.local_blocks, remaining);
+            for (offset, encoded_block) in self
+                .blockchain
+                .block_slice(local_blocks_range.clone())
+                .iter()
+                .enumerate()
+            {
+                blocks.push(BlockWithId {
+                    id: Nat::from(local_blocks_range.start + offset as u64),
+                    block: encoded_block_to_generic_block(encoded_block),
+                });
+            }
+
+            for (canister_id, slice) in locations.archived_blocks {
+                archived
+                    .entry(canister_id.get().0)
+                    .or_default()
+                    .push(GetBlocksRequest {
+                        start: Nat::from(slice.start),
+                        length: Nat::from(range_utils::range_len(&slice)),
+                    });
+            }
+        }
+
+        let archived_blocks = archived
+            .into_iter()
+            .map(|(canister_id, args)| ArchivedBlocks {
+                args,
+                callback: ICRC3QueryBlockArchiveFn::new(canister_id, "icrc3_get_blocks"),
+            })
+            .collect();
+
+        GetBlocksResult {
+            log_length: Nat::from(self.blockchain.chain_length()),
+            blocks,
+            archived_blocks,
+        }
+    }
 }
@@ -21,8 +21,11 @@ use icrc_ledger_types::icrc3::blocks::DataCertificate;
 use icrc_ledger_types::{
     icrc::generic_metadata_value::MetadataValue as Value,
     icrc3::{
-        archive::ArchiveInfo,
-        blocks::{GetBlocksRequest, GetBlocksResponse},
+        archive::{ArchiveInfo, GetArchivesArgs, GetArchivesResult, ICRC3ArchiveInfo},
+        blocks::{
+            GetBlocksRequest, GetBlocksResponse, GetBlocksResult, ICRC3DataCertificate,
+            SupportedBlockType,
+        },
         transactions::{GetTransactionsRequest, GetTransactionsResponse},
     },
 };
@@ -472,10 +475,16 @@ fn archives() -> Vec<ArchiveInfo> {
 #[query(name = "icrc1_supported_standards")]
 #[candid_method(query, rename = "icrc1_supported_standards")]
 fn supported_standards() -> Vec<StandardRecord> {
-    let mut standards = vec![StandardRecord {
-        name: "ICRC-1".to_string(),
-        url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-1".to_string(),
-    }];
+    let mut standards = vec![
+        StandardRecord {
+            name: "ICRC-1".to_string(),
+            url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-1".to_string(),
+        },
+        StandardRecord {
+            name: "ICRC-3".to_string(),
+            url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-3".to_string(),
+        },
+    ];
     let icrc2 = Access::with_ledger(|ledger| ledger.feature_flags().icrc2);
     if icrc2 {
         standards.push(StandardRecord {
@@ -504,6 +513,12 @@ fn get_blocks(req: GetBlocksRequest) -> GetBlocksResponse {
     Access::with_ledger(|ledger| ledger.get_blocks(start, length as usize))
 }
 
+/// Returns the certificate of the ledger tip.
+///
+/// The certified tree is the ICRC-3 tip tree: `last_block_index` is LEB128
+/// encoded and `last_block_hash` holds the tip hash. The hash is also kept
+/// under the legacy `tip_hash` label, but clients that decode
+/// `last_block_index` as a big-endian u64 must switch to LEB128.
 #[query]
 #[candid_method(query)]
 fn get_data_certificate() -> DataCertificate {
@@ -516,6 +531,65 @@ fn get_data_certificate() -> DataCertificate {
     }
 }
 
+#[query]
+#[candid_method(query)]
+fn icrc3_get_blocks(args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    Access::with_ledger(|ledger| ledger.icrc3_get_blocks(args))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc3_get_archives(args: GetArchivesArgs) -> GetArchivesResult {
+    let mut archives: Vec<ICRC3ArchiveInfo> = Access::with_ledger(|ledger| {
+        ledger
+            .blockchain()
+            .archive
+            .read()
+            .unwrap()
+            .as_ref()
+            .iter()
+            .flat_map(|archive| {
+                archive
+                    .index()
+                    .into_iter()
+                    .map(|((start, end), canister_id)| ICRC3ArchiveInfo {
+                        canister_id: canister_id.get().0,
+                        start: Nat::from(start),
+                        end: Nat::from(end),
+                    })
+            })
+            .collect()
+    });
+    archives.sort_by(|a, b| a.start.cmp(&b.start));
+    match args.from {
+        // Skip all the archives up to and including the one the client saw last.
+        Some(from) => match archives.iter().position(|a| a.canister_id == from) {
+            Some(pos) => archives.split_off(pos + 1),
+            None => archives,
+        },
+        None => archives,
+    }
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc3_get_tip_certificate() -> Option<ICRC3DataCertificate> {
+    let certificate = ByteBuf::from(ic_cdk::api::data_certificate()?);
+    let hash_tree = Access::with_ledger(|ledger| ledger.construct_hash_tree());
+    let mut tree_buf = vec![];
+    ciborium::ser::into_writer(&hash_tree, &mut tree_buf).unwrap();
+    Some(ICRC3DataCertificate {
+        certificate,
+        hash_tree: ByteBuf::from(tree_buf),
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc3_supported_block_types() -> Vec<SupportedBlockType> {
+    ic_icrc1::endpoints::icrc3_supported_block_types()
+}
+
 #[update]
 #[candid_method(update)]
 async fn icrc2_approve(arg: ApproveArgs) -> Result<Nat, ApproveError> {
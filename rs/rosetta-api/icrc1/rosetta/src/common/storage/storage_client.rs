@@ -1,7 +1,11 @@
-use super::{storage_operations, types::RosettaBlock};
+use super::{
+    storage_operations,
+    types::{BlockSearchFilter, RosettaBlock},
+};
 use anyhow::Result;
-use ic_icrc1::Transaction;
+use ic_icrc1::{Block, Transaction};
 use ic_icrc1_tokens_u64::U64;
+use icrc_ledger_types::icrc1::account::Account;
 use rusqlite::Connection;
 use serde_bytes::ByteBuf;
 use std::{path::Path, sync::Mutex};
@@ -103,6 +107,61 @@ impl StorageClient {
         storage_operations::get_transaction_at_idx(&open_connection, block_idx)
     }
 
+    // Gets all the blocks that contain a transaction with a certain hash, ordered by block index.
+    pub fn get_blocks_by_transaction_hash(
+        &self,
+        hash: ByteBuf,
+    ) -> anyhow::Result<Vec<RosettaBlock>> {
+        let open_connection = self.storage_connection.lock().unwrap();
+        storage_operations::get_blocks_by_transaction_hash(&open_connection, hash)
+    }
+
+    /// Returns at most `limit` blocks matching the filter, starting from the highest block index
+    /// and skipping the first `offset` matches, together with the total number of matches.
+    pub fn search_blocks(
+        &self,
+        filter: &BlockSearchFilter,
+        offset: u64,
+        limit: u64,
+    ) -> anyhow::Result<(Vec<RosettaBlock>, u64)> {
+        let open_connection = self.storage_connection.lock().unwrap();
+        storage_operations::search_blocks(&open_connection, filter, offset, limit)
+    }
+
+    /// Computes the account balances for all stored blocks that follow the highest block in the
+    /// account balances table without a gap.
+    pub fn update_account_balances(&self) -> anyhow::Result<()> {
+        let open_connection = self.storage_connection.lock().unwrap();
+        storage_operations::update_account_balances(&open_connection)
+    }
+
+    // Gets the balance of an account right after the block with the given index was applied.
+    // Returns None if the account had no balance changes up to that block.
+    pub fn get_account_balance_at_block_idx(
+        &self,
+        account: &Account,
+        block_idx: u64,
+    ) -> anyhow::Result<Option<Tokens>> {
+        let open_connection = self.storage_connection.lock().unwrap();
+        storage_operations::get_account_balance_at_block_idx(&open_connection, account, block_idx)
+    }
+
+    // Gets the highest block index for which the account balances were computed.
+    // Returns None if no balances were computed yet.
+    pub fn get_highest_block_idx_in_account_balance_table(&self) -> anyhow::Result<Option<u64>> {
+        let open_connection = self.storage_connection.lock().unwrap();
+        storage_operations::get_highest_block_idx_in_account_balance_table(&open_connection)
+    }
+
+    // Gets the account collecting the fees of a block, if any.
+    pub fn get_fee_collector_from_block(
+        &self,
+        block: &Block<Tokens>,
+    ) -> anyhow::Result<Option<Account>> {
+        let open_connection = self.storage_connection.lock().unwrap();
+        storage_operations::get_fee_collector_from_block(&open_connection, block)
+    }
+
     fn create_tables(&self) -> Result<(), rusqlite::Error> {
         let open_connection = self.storage_connection.lock().unwrap();
         open_connection.execute(
@@ -136,6 +195,19 @@ impl StorageClient {
             "#,
             [],
         )?;
+        open_connection.execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS account_balances (
+                block_idx INTEGER NOT NULL,
+                principal BLOB NOT NULL,
+                subaccount BLOB NOT NULL,
+                amount INTEGER NOT NULL,
+                PRIMARY KEY(principal, subaccount, block_idx),
+                FOREIGN KEY(block_idx) REFERENCES blocks(idx)
+            )
+            "#,
+            [],
+        )?;
         Ok(())
     }
 
@@ -149,13 +221,256 @@ impl StorageClient {
 mod tests {
     use super::*;
     use crate::common::utils::unit_test_utils::create_tmp_dir;
-    use ic_icrc1::Block;
+    use candid::Principal;
+    use ic_icrc1::Operation;
     use ic_icrc1_test_utils::{
         arb_small_amount, blocks_strategy, valid_blockchain_with_gaps_strategy,
     };
     use ic_ledger_core::block::BlockType;
     use proptest::prelude::*;
 
+    fn account(id: u64, subaccount: Option<[u8; 32]>) -> Account {
+        Account {
+            owner: Principal::from_slice(&id.to_be_bytes()),
+            subaccount,
+        }
+    }
+
+    fn block(operation: Operation<Tokens>, effective_fee: Option<u64>) -> Block<Tokens> {
+        Block {
+            parent_hash: None,
+            transaction: Transaction {
+                operation,
+                created_at_time: None,
+                memo: None,
+            },
+            effective_fee: effective_fee.map(Tokens::new),
+            timestamp: 0,
+            fee_collector: None,
+            fee_collector_block_index: None,
+        }
+    }
+
+    // Mints to A, transfers from A to B with C as fee collector, burns from B and approves from A.
+    fn store_test_blockchain(storage_client: &StorageClient) -> (Account, Account, Account) {
+        let (a, b, c) = (
+            account(1, None),
+            account(2, Some([1; 32])),
+            account(3, None),
+        );
+        let mut transfer = block(
+            Operation::Transfer {
+                from: a,
+                to: b,
+                spender: None,
+                amount: Tokens::new(100),
+                fee: None,
+            },
+            Some(10),
+        );
+        transfer.fee_collector = Some(c);
+        let blocks = vec![
+            block(
+                Operation::Mint {
+                    to: a,
+                    amount: Tokens::new(1_000),
+                },
+                None,
+            ),
+            transfer,
+            block(
+                Operation::Burn {
+                    from: b,
+                    spender: None,
+                    amount: Tokens::new(50),
+                },
+                None,
+            ),
+        ];
+        storage_client
+            .store_blocks(
+                blocks
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, block)| {
+                        RosettaBlock::from_icrc_ledger_block(block, idx as u64).unwrap()
+                    })
+                    .collect(),
+            )
+            .unwrap();
+        (a, b, c)
+    }
+
+    #[test]
+    fn test_account_balances() {
+        let storage_client = StorageClient::new_in_memory().unwrap();
+        let (a, b, c) = store_test_blockchain(&storage_client);
+        assert_eq!(
+            storage_client
+                .get_highest_block_idx_in_account_balance_table()
+                .unwrap(),
+            None
+        );
+        storage_client.update_account_balances().unwrap();
+        assert_eq!(
+            storage_client
+                .get_highest_block_idx_in_account_balance_table()
+                .unwrap(),
+            Some(2)
+        );
+
+        let balance = |account: &Account, block_idx: u64| {
+            storage_client
+                .get_account_balance_at_block_idx(account, block_idx)
+                .unwrap()
+                .map(Tokens::to_u64)
+        };
+        assert_eq!(balance(&a, 0), Some(1_000));
+        assert_eq!(balance(&a, 1), Some(890));
+        assert_eq!(balance(&a, 2), Some(890));
+        assert_eq!(balance(&b, 0), None);
+        assert_eq!(balance(&b, 1), Some(100));
+        assert_eq!(balance(&b, 2), Some(50));
+        assert_eq!(balance(&c, 1), Some(10));
+        // The default subaccount can be given explicitly.
+        assert_eq!(balance(&account(1, Some([0; 32])), 2), Some(890));
+
+        // Blocks after a gap are not considered until the gap is filled.
+        let approve = block(
+            Operation::Approve {
+                from: a,
+                spender: b,
+                amount: Tokens::new(1_000),
+                expected_allowance: None,
+                expires_at: None,
+                fee: Some(Tokens::new(20)),
+            },
+            None,
+        );
+        let burn = block(
+            Operation::Burn {
+                from: a,
+                spender: None,
+                amount: Tokens::new(70),
+            },
+            None,
+        );
+        storage_client
+            .store_blocks(vec![RosettaBlock::from_icrc_ledger_block(burn, 4).unwrap()])
+            .unwrap();
+        storage_client.update_account_balances().unwrap();
+        assert_eq!(
+            storage_client
+                .get_highest_block_idx_in_account_balance_table()
+                .unwrap(),
+            Some(2)
+        );
+        let approve = RosettaBlock::from_icrc_ledger_block(approve, 3).unwrap();
+        // Approvals cannot be stored in the transactions table yet, store the block only.
+        storage_client
+            .storage_connection
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO blocks (idx, hash, serialized_block) VALUES (?1, ?2, ?3)",
+                rusqlite::params![
+                    approve.index,
+                    approve.block_hash.to_vec(),
+                    approve.encoded_block.clone().into_vec()
+                ],
+            )
+            .unwrap();
+        storage_client.update_account_balances().unwrap();
+        assert_eq!(
+            storage_client
+                .get_highest_block_idx_in_account_balance_table()
+                .unwrap(),
+            Some(4)
+        );
+        assert_eq!(balance(&a, 3), Some(870));
+        assert_eq!(balance(&a, 4), Some(800));
+    }
+
+    #[test]
+    fn test_search_blocks() {
+        let storage_client = StorageClient::new_in_memory().unwrap();
+        let (a, b, _c) = store_test_blockchain(&storage_client);
+        let search = |filter: BlockSearchFilter, offset: u64, limit: u64| {
+            let (blocks, total_count) = storage_client
+                .search_blocks(&filter, offset, limit)
+                .unwrap();
+            (
+                blocks
+                    .into_iter()
+                    .map(|block| block.index)
+                    .collect::<Vec<_>>(),
+                total_count,
+            )
+        };
+
+        assert_eq!(
+            search(BlockSearchFilter::default(), 0, 10),
+            (vec![2, 1, 0], 3)
+        );
+        assert_eq!(search(BlockSearchFilter::default(), 1, 1), (vec![1], 3));
+        let by_principal = |account: &Account, subaccount| BlockSearchFilter {
+            principal: Some(account.owner),
+            subaccount,
+            ..Default::default()
+        };
+        assert_eq!(search(by_principal(&a, None), 0, 10), (vec![1, 0], 2));
+        assert_eq!(
+            search(by_principal(&a, Some([0; 32])), 0, 10),
+            (vec![1, 0], 2)
+        );
+        assert_eq!(
+            search(by_principal(&b, Some([1; 32])), 0, 10),
+            (vec![2, 1], 2)
+        );
+        assert_eq!(search(by_principal(&b, Some([0; 32])), 0, 10), (vec![], 0));
+        assert_eq!(
+            search(
+                BlockSearchFilter {
+                    operation_type: Some("burn".to_string()),
+                    ..Default::default()
+                },
+                0,
+                10
+            ),
+            (vec![2], 1)
+        );
+        assert_eq!(
+            search(
+                BlockSearchFilter {
+                    max_block_idx: Some(1),
+                    ..by_principal(&b, Some([1; 32]))
+                },
+                0,
+                10
+            ),
+            (vec![1], 1)
+        );
+
+        let block = storage_client.get_block_at_idx(1).unwrap().unwrap();
+        assert_eq!(
+            search(
+                BlockSearchFilter {
+                    transaction_hash: Some(block.transaction_hash.clone()),
+                    ..Default::default()
+                },
+                0,
+                10
+            ),
+            (vec![1], 1)
+        );
+        assert_eq!(
+            storage_client
+                .get_blocks_by_transaction_hash(block.transaction_hash.clone())
+                .unwrap(),
+            vec![block]
+        );
+    }
+
     #[test]
     fn smoke_test() {
         let storage_client_memory = StorageClient::new_in_memory();
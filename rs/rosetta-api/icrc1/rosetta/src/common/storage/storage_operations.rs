@@ -1,17 +1,21 @@
-use crate::common::storage::types::RosettaBlock;
+use crate::common::storage::types::{BlockSearchFilter, RosettaBlock};
 use anyhow::{anyhow, bail};
 use candid::Principal;
-use ic_icrc1::{Operation, Transaction};
+use ic_icrc1::{Block, Operation, Transaction};
 use ic_icrc1_tokens_u64::U64;
 use ic_ledger_core::block::EncodedBlock;
-use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use icrc_ledger_types::icrc1::transfer::Memo;
-use rusqlite::{params, Params};
+use rusqlite::{params, params_from_iter, OptionalExtension, Params};
 use rusqlite::{Connection, Statement, ToSql};
 use serde_bytes::ByteBuf;
+use std::collections::HashMap;
 
 type Tokens = U64;
 
+/// The number of blocks loaded at once when updating the account balances.
+const ACCOUNT_BALANCES_BATCH_SIZE: u64 = 10_000;
+
 // Stores a batch of RosettaBlocks
 pub fn store_blocks(
     connection: &Connection,
@@ -189,7 +193,257 @@ pub fn get_blockchain_gaps(
         .collect())
 }
 
-// Returns a icrc1 Transaction if the block index exists in the database, else returns None.
+// Returns all the blocks whose transaction has the given hash, ordered by block index.
+pub fn get_blocks_by_transaction_hash(
+    connection: &Connection,
+    hash: ByteBuf,
+) -> anyhow::Result<Vec<RosettaBlock>> {
+    let mut stmt = connection.prepare(
+        "SELECT b.idx,b.serialized_block FROM blocks b JOIN transactions t ON b.idx = t.block_idx WHERE t.tx_hash = ?1 ORDER BY b.idx ASC",
+    )?;
+    read_blocks(&mut stmt, params![hash.as_slice().to_vec()])
+}
+
+// Returns the blocks matching the filter, ordered from the highest to the lowest block index,
+// skipping the first `offset` blocks and returning at most `limit` blocks.
+// Also returns the total number of blocks matching the filter.
+pub fn search_blocks(
+    connection: &Connection,
+    filter: &BlockSearchFilter,
+    offset: u64,
+    limit: u64,
+) -> anyhow::Result<(Vec<RosettaBlock>, u64)> {
+    let mut conditions: Vec<String> = vec![];
+    let mut values: Vec<Box<dyn ToSql>> = vec![];
+    if let Some(max_block_idx) = filter.max_block_idx {
+        values.push(Box::new(max_block_idx));
+        conditions.push(format!("b.idx <= ?{}", values.len()));
+    }
+    if let Some(hash) = &filter.transaction_hash {
+        values.push(Box::new(hash.as_slice().to_vec()));
+        conditions.push(format!("t.tx_hash = ?{}", values.len()));
+    }
+    if let Some(operation_type) = &filter.operation_type {
+        values.push(Box::new(operation_type.clone()));
+        conditions.push(format!("t.operation_type = ?{}", values.len()));
+    }
+    if let Some(principal) = filter.principal {
+        values.push(Box::new(principal.as_slice().to_vec()));
+        let principal_param = values.len();
+        match filter.subaccount {
+            Some(subaccount) => {
+                values.push(Box::new(subaccount));
+                let subaccount_param = values.len();
+                // Transactions store the default subaccount either as NULL or as 32 zero bytes.
+                conditions.push(format!(
+                    "((t.from_principal = ?{p} AND COALESCE(t.from_subaccount, zeroblob(32)) = ?{s}) OR (t.to_principal = ?{p} AND COALESCE(t.to_subaccount, zeroblob(32)) = ?{s}))",
+                    p = principal_param,
+                    s = subaccount_param
+                ));
+            }
+            None => conditions.push(format!(
+                "(t.from_principal = ?{p} OR t.to_principal = ?{p})",
+                p = principal_param
+            )),
+        }
+    }
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+
+    let command = format!(
+        "SELECT COUNT(*) FROM blocks b JOIN transactions t ON b.idx = t.block_idx {}",
+        where_clause
+    );
+    let total_count: u64 = connection.query_row(
+        &command,
+        params_from_iter(values.iter().map(|v| v.as_ref())),
+        |row| row.get(0),
+    )?;
+
+    let command = format!(
+        "SELECT b.idx,b.serialized_block FROM blocks b JOIN transactions t ON b.idx = t.block_idx {} ORDER BY b.idx DESC LIMIT {} OFFSET {}",
+        where_clause, limit, offset
+    );
+    let mut stmt = connection.prepare(&command)?;
+    let blocks = read_blocks(
+        &mut stmt,
+        params_from_iter(values.iter().map(|v| v.as_ref())),
+    )?;
+    Ok((blocks, total_count))
+}
+
+// Returns the balance of the account right after the block with the given index was applied.
+// Returns None if the account had no balance changes up to that block.
+pub fn get_account_balance_at_block_idx(
+    connection: &Connection,
+    account: &Account,
+    block_idx: u64,
+) -> anyhow::Result<Option<Tokens>> {
+    let amount: Option<u64> = connection
+        .query_row(
+            "SELECT amount FROM account_balances WHERE principal = ?1 AND subaccount = ?2 AND block_idx <= ?3 ORDER BY block_idx DESC LIMIT 1",
+            params![
+                account.owner.as_slice().to_vec(),
+                account.effective_subaccount(),
+                block_idx
+            ],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(amount.map(Tokens::new))
+}
+
+// Returns the highest block index whose balance changes are stored in the account_balances table.
+pub fn get_highest_block_idx_in_account_balance_table(
+    connection: &Connection,
+) -> anyhow::Result<Option<u64>> {
+    Ok(
+        connection.query_row("SELECT MAX(block_idx) FROM account_balances", [], |row| {
+            row.get(0)
+        })?,
+    )
+}
+
+// Returns the fee collector of the block, which is either set directly in the block or in the
+// block referenced by the fee_collector_block_index field.
+pub fn get_fee_collector_from_block(
+    connection: &Connection,
+    block: &Block<Tokens>,
+) -> anyhow::Result<Option<Account>> {
+    if let Some(fee_collector) = block.fee_collector {
+        return Ok(Some(fee_collector));
+    }
+    match block.fee_collector_block_index {
+        Some(fee_collector_block_idx) => {
+            let fee_collector_block = get_block_at_idx(connection, fee_collector_block_idx)?
+                .ok_or_else(|| {
+                    anyhow!(
+                        "the fee collector block {} is not in the database",
+                        fee_collector_block_idx
+                    )
+                })?
+                .get_block()?;
+            Ok(fee_collector_block.fee_collector)
+        }
+        None => Ok(None),
+    }
+}
+
+// Applies the balance changes of all blocks that come after the highest block in the
+// account_balances table, up to the first gap in the blockchain.
+pub fn update_account_balances(connection: &Connection) -> anyhow::Result<()> {
+    let mut next_block_idx =
+        get_highest_block_idx_in_account_balance_table(connection)?.map_or(0, |idx| idx + 1);
+
+    connection.execute_batch("BEGIN TRANSACTION;")?;
+    match apply_balance_changes(connection, &mut next_block_idx) {
+        Ok(()) => {
+            connection.execute_batch("COMMIT TRANSACTION;")?;
+            Ok(())
+        }
+        Err(e) => {
+            connection.execute_batch("ROLLBACK TRANSACTION;")?;
+            Err(e)
+        }
+    }
+}
+
+fn apply_balance_changes(connection: &Connection, next_block_idx: &mut u64) -> anyhow::Result<()> {
+    let mut insert_stmt = connection.prepare(
+        "INSERT INTO account_balances (block_idx,principal,subaccount,amount) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    // The latest balances of the accounts touched so far.
+    let mut balances: HashMap<(Principal, Subaccount), u64> = HashMap::new();
+
+    loop {
+        let mut blocks = get_blocks_by_index_range(
+            connection,
+            *next_block_idx,
+            next_block_idx.saturating_add(ACCOUNT_BALANCES_BATCH_SIZE - 1),
+        )?;
+        blocks.sort_by_key(|block| block.index);
+        let num_fetched = blocks.len() as u64;
+        for rosetta_block in blocks {
+            // Balances can only be computed for a consecutive sequence of blocks.
+            if rosetta_block.index != *next_block_idx {
+                return Ok(());
+            }
+            let block = rosetta_block.get_block()?;
+            let block_fee = |fee: Option<Tokens>| -> anyhow::Result<i128> {
+                Ok(fee
+                    .or(block.effective_fee)
+                    .ok_or_else(|| anyhow!("the fee of block {} is not set", rosetta_block.index))?
+                    .to_u64() as i128)
+            };
+            let mut changes: Vec<(Account, i128)> = vec![];
+            match &block.transaction.operation {
+                Operation::Mint { to, amount } => changes.push((*to, amount.to_u64() as i128)),
+                Operation::Burn { from, amount, .. } => {
+                    changes.push((*from, -(amount.to_u64() as i128)))
+                }
+                Operation::Transfer {
+                    from,
+                    to,
+                    amount,
+                    fee,
+                    ..
+                } => {
+                    let fee = block_fee(*fee)?;
+                    let amount = amount.to_u64() as i128;
+                    changes.push((*from, -(amount + fee)));
+                    changes.push((*to, amount));
+                    if let Some(fee_collector) = get_fee_collector_from_block(connection, &block)? {
+                        changes.push((fee_collector, fee));
+                    }
+                }
+                Operation::Approve { from, fee, .. } => {
+                    // Approval fees are burned.
+                    changes.push((*from, -block_fee(*fee)?));
+                }
+            }
+
+            let mut touched_accounts = vec![];
+            for (account, change) in changes {
+                let key = (account.owner, *account.effective_subaccount());
+                let balance = match balances.get(&key) {
+                    Some(balance) => *balance,
+                    None => {
+                        get_account_balance_at_block_idx(connection, &account, rosetta_block.index)?
+                            .map_or(0, Tokens::to_u64)
+                    }
+                };
+                let new_balance = u64::try_from(balance as i128 + change).map_err(|_| {
+                    anyhow!(
+                        "the balance of account {} overflows at block {}",
+                        account,
+                        rosetta_block.index
+                    )
+                })?;
+                balances.insert(key, new_balance);
+                if !touched_accounts.contains(&key) {
+                    touched_accounts.push(key);
+                }
+            }
+            // Store one entry per touched account with the balance right after the block.
+            for key in touched_accounts {
+                insert_stmt.execute(params![
+                    rosetta_block.index,
+                    key.0.as_slice().to_vec(),
+                    key.1,
+                    balances[&key]
+                ])?;
+            }
+            *next_block_idx += 1;
+        }
+        if num_fetched < ACCOUNT_BALANCES_BATCH_SIZE {
+            return Ok(());
+        }
+    }
+}
+
 // Returns an Error if the query fails.
 pub fn get_transaction_at_idx(
     connection: &Connection,
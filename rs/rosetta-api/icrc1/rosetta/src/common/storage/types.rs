@@ -1,9 +1,11 @@
 use candid::Deserialize;
+use candid::Principal;
 use ic_icrc1::blocks::{generic_block_to_encoded_block, generic_transaction_from_generic_block};
 use ic_icrc1::{Block, Transaction};
 use ic_icrc1_tokens_u64::U64;
 use ic_ledger_canister_core::ledger::LedgerTransaction;
 use ic_ledger_core::block::{BlockType, EncodedBlock};
+use icrc_ledger_types::icrc1::account::Subaccount;
 use icrc_ledger_types::icrc3::blocks::GenericBlock;
 use serde::Serialize;
 use serde_bytes::ByteBuf;
@@ -64,9 +66,26 @@ impl RosettaBlock {
         )
     }
 
+    pub fn get_block(&self) -> anyhow::Result<Block<Tokens>> {
+        Block::decode(self.encoded_block.clone()).map_err(anyhow::Error::msg)
+    }
+
     pub fn get_transaction(&self) -> anyhow::Result<Transaction<Tokens>> {
-        Ok(Block::decode(self.encoded_block.clone())
-            .map_err(anyhow::Error::msg)?
-            .transaction)
+        Ok(self.get_block()?.transaction)
     }
 }
+
+/// Restricts the blocks returned by a search to those whose transaction
+/// matches all the fields that are set.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockSearchFilter {
+    pub transaction_hash: Option<ByteBuf>,
+    /// The principal that sends or receives tokens in the transaction.
+    pub principal: Option<Principal>,
+    /// The subaccount of [principal]. All subaccounts match if not set.
+    pub subaccount: Option<Subaccount>,
+    /// The operation type as stored in the transactions table, e.g., "transfer".
+    pub operation_type: Option<String>,
+    /// The highest block index to consider.
+    pub max_block_idx: Option<u64>,
+}
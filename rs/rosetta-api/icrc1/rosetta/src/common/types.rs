@@ -1,7 +1,9 @@
 use axum::{http::StatusCode, response::IntoResponse, Json};
 use candid::Deserialize;
 
+use candid::Principal;
 use ic_base_types::CanisterId;
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use serde::Serialize;
 
 // Generated from the [Rosetta API specification v1.4.13](https://github.com/coinbase/rosetta-specifications/blob/v1.4.13/api.json)
@@ -169,6 +171,21 @@ pub struct Error {
 }
 
 const ERROR_CODE_INVALID_NETWORK_ID: u32 = 1;
+const ERROR_CODE_UNABLE_TO_FIND_BLOCK: u32 = 2;
+const ERROR_CODE_INVALID_BLOCK_IDENTIFIER: u32 = 3;
+const ERROR_CODE_FAILED_TO_BUILD_BLOCK_RESPONSE: u32 = 4;
+const ERROR_CODE_INVALID_TRANSACTION_IDENTIFIER: u32 = 5;
+const ERROR_CODE_UNABLE_TO_FIND_ACCOUNT_BALANCE: u32 = 6;
+const ERROR_CODE_PARSING_ERROR: u32 = 7;
+const ERROR_CODE_INVALID_SEARCH_TRANSACTIONS_REQUEST: u32 = 8;
+const ERROR_CODE_STORAGE_ERROR: u32 = 9;
+const ERROR_CODE_INVALID_PUBLIC_KEY: u32 = 10;
+const ERROR_CODE_INVALID_OPERATIONS: u32 = 11;
+const ERROR_CODE_INVALID_METADATA: u32 = 12;
+const ERROR_CODE_INVALID_TRANSACTION: u32 = 13;
+const ERROR_CODE_LEDGER_COMMUNICATION_ERROR: u32 = 14;
+const ERROR_CODE_TRANSACTION_REJECTED: u32 = 15;
+const ERROR_CODE_TRANSACTION_EXPIRED: u32 = 16;
 
 impl IntoResponse for Error {
     fn into_response(self) -> axum::response::Response {
@@ -189,6 +206,151 @@ impl Error {
             details: None,
         }
     }
+
+    fn with_description(code: u32, message: &str, description: String, retriable: bool) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            description: Some(description),
+            retriable,
+            details: None,
+        }
+    }
+
+    pub fn unable_to_find_block(description: String) -> Self {
+        Self::with_description(
+            ERROR_CODE_UNABLE_TO_FIND_BLOCK,
+            "Unable to find block",
+            description,
+            true,
+        )
+    }
+
+    pub fn invalid_block_identifier(description: String) -> Self {
+        Self::with_description(
+            ERROR_CODE_INVALID_BLOCK_IDENTIFIER,
+            "Invalid block identifier",
+            description,
+            false,
+        )
+    }
+
+    pub fn failed_to_build_block_response(description: String) -> Self {
+        Self::with_description(
+            ERROR_CODE_FAILED_TO_BUILD_BLOCK_RESPONSE,
+            "Failed to build block response",
+            description,
+            false,
+        )
+    }
+
+    pub fn invalid_transaction_identifier(description: String) -> Self {
+        Self::with_description(
+            ERROR_CODE_INVALID_TRANSACTION_IDENTIFIER,
+            "Invalid transaction identifier",
+            description,
+            false,
+        )
+    }
+
+    pub fn unable_to_find_account_balance(description: String) -> Self {
+        Self::with_description(
+            ERROR_CODE_UNABLE_TO_FIND_ACCOUNT_BALANCE,
+            "Unable to find account balance",
+            description,
+            true,
+        )
+    }
+
+    pub fn parsing_unsuccessful(description: String) -> Self {
+        Self::with_description(
+            ERROR_CODE_PARSING_ERROR,
+            "Failed to parse in the backend",
+            description,
+            false,
+        )
+    }
+
+    pub fn invalid_search_transactions_request(description: String) -> Self {
+        Self::with_description(
+            ERROR_CODE_INVALID_SEARCH_TRANSACTIONS_REQUEST,
+            "Invalid search transactions request",
+            description,
+            false,
+        )
+    }
+
+    pub fn storage_error(description: String) -> Self {
+        Self::with_description(
+            ERROR_CODE_STORAGE_ERROR,
+            "Unable to access the storage",
+            description,
+            true,
+        )
+    }
+
+    pub fn invalid_public_key(description: String) -> Self {
+        Self::with_description(
+            ERROR_CODE_INVALID_PUBLIC_KEY,
+            "Invalid public key",
+            description,
+            false,
+        )
+    }
+
+    pub fn invalid_operations(description: String) -> Self {
+        Self::with_description(
+            ERROR_CODE_INVALID_OPERATIONS,
+            "Invalid operations",
+            description,
+            false,
+        )
+    }
+
+    pub fn invalid_metadata(description: String) -> Self {
+        Self::with_description(
+            ERROR_CODE_INVALID_METADATA,
+            "Invalid metadata",
+            description,
+            false,
+        )
+    }
+
+    pub fn invalid_transaction(description: String) -> Self {
+        Self::with_description(
+            ERROR_CODE_INVALID_TRANSACTION,
+            "Invalid transaction",
+            description,
+            false,
+        )
+    }
+
+    pub fn ledger_communication_error(description: String) -> Self {
+        Self::with_description(
+            ERROR_CODE_LEDGER_COMMUNICATION_ERROR,
+            "Unable to communicate with the ledger",
+            description,
+            true,
+        )
+    }
+
+    pub fn transaction_rejected(description: String) -> Self {
+        Self::with_description(
+            ERROR_CODE_TRANSACTION_REJECTED,
+            "Transaction rejected by the ledger",
+            description,
+            false,
+        )
+    }
+
+    pub fn transaction_expired(description: String) -> Self {
+        Self::with_description(
+            ERROR_CODE_TRANSACTION_EXPIRED,
+            "Transaction expired",
+            description,
+            false,
+        )
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -198,3 +360,518 @@ pub struct NetworkRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<serde_json::Value>,
 }
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlockIdentifier {
+    pub index: u64,
+
+    pub hash: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PartialBlockIdentifier {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TransactionIdentifier {
+    pub hash: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct OperationIdentifier {
+    pub index: u64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network_index: Option<i64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AccountIdentifier {
+    /// The textual representation of the principal owning the account.
+    pub address: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_account: Option<SubAccountIdentifier>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SubAccountIdentifier {
+    /// The hex encoded subaccount.
+    pub address: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+impl From<Account> for AccountIdentifier {
+    fn from(account: Account) -> Self {
+        Self {
+            address: account.owner.to_text(),
+            sub_account: account.subaccount.map(|subaccount| SubAccountIdentifier {
+                address: hex::encode(subaccount),
+                metadata: None,
+            }),
+            metadata: None,
+        }
+    }
+}
+
+impl TryFrom<&AccountIdentifier> for Account {
+    type Error = String;
+
+    fn try_from(account_identifier: &AccountIdentifier) -> Result<Self, Self::Error> {
+        let owner = Principal::from_text(&account_identifier.address)
+            .map_err(|e| format!("Invalid principal {}: {}", account_identifier.address, e))?;
+        let subaccount = match &account_identifier.sub_account {
+            Some(sub_account) => {
+                let bytes = hex::decode(&sub_account.address)
+                    .map_err(|e| format!("Invalid subaccount {}: {}", sub_account.address, e))?;
+                Some(Subaccount::try_from(bytes.as_slice()).map_err(|_| {
+                    format!(
+                        "Invalid subaccount {}: expected 32 bytes, got {}",
+                        sub_account.address,
+                        bytes.len()
+                    )
+                })?)
+            }
+            None => None,
+        };
+        Ok(Account { owner, subaccount })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Amount {
+    /// The amount in the smallest unit of the currency, negative for debits.
+    pub value: String,
+
+    pub currency: Currency,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Operation {
+    pub operation_identifier: OperationIdentifier,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub related_operations: Option<Vec<OperationIdentifier>>,
+
+    #[serde(rename = "type")]
+    pub type_: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<AccountIdentifier>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<Amount>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// The metadata of an APPROVE operation.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ApproveMetadata {
+    pub spender: AccountIdentifier,
+
+    pub allowance: Amount,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_allowance: Option<Amount>,
+
+    /// The expiration of the approval in nanoseconds since the Unix Epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Transaction {
+    pub transaction_identifier: TransactionIdentifier,
+
+    pub operations: Vec<Operation>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Block {
+    pub block_identifier: BlockIdentifier,
+
+    pub parent_block_identifier: BlockIdentifier,
+
+    /// The timestamp of the block in milliseconds since the Unix Epoch.
+    pub timestamp: u64,
+
+    pub transactions: Vec<Transaction>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlockTransaction {
+    pub block_identifier: BlockIdentifier,
+
+    pub transaction: Transaction,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SyncStatus {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_index: Option<i64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_index: Option<i64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stage: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub synced: Option<bool>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Peer {
+    pub peer_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct NetworkStatusResponse {
+    pub current_block_identifier: BlockIdentifier,
+
+    pub current_block_timestamp: u64,
+
+    pub genesis_block_identifier: BlockIdentifier,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oldest_block_identifier: Option<BlockIdentifier>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sync_status: Option<SyncStatus>,
+
+    pub peers: Vec<Peer>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlockRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    pub block_identifier: PartialBlockIdentifier,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlockResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block: Option<Block>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub other_transactions: Option<Vec<TransactionIdentifier>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlockTransactionRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    pub block_identifier: BlockIdentifier,
+
+    pub transaction_identifier: TransactionIdentifier,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlockTransactionResponse {
+    pub transaction: Transaction,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AccountBalanceRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    pub account_identifier: AccountIdentifier,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_identifier: Option<PartialBlockIdentifier>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currencies: Option<Vec<Currency>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AccountBalanceResponse {
+    pub block_identifier: BlockIdentifier,
+
+    pub balances: Vec<Amount>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MempoolResponse {
+    pub transaction_identifiers: Vec<TransactionIdentifier>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Operator {
+    Or,
+    And,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SearchTransactionsRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator: Option<Operator>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_block: Option<i64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<i64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_identifier: Option<TransactionIdentifier>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_identifier: Option<AccountIdentifier>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<Currency>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub success: Option<bool>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SearchTransactionsResponse {
+    pub transactions: Vec<BlockTransaction>,
+
+    pub total_count: i64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<i64>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum CurveType {
+    #[serde(rename = "secp256k1")]
+    Secp256K1,
+    #[serde(rename = "secp256r1")]
+    Secp256R1,
+    #[serde(rename = "edwards25519")]
+    Edwards25519,
+    #[serde(rename = "tweedle")]
+    Tweedle,
+    #[serde(rename = "pallas")]
+    Pallas,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum SignatureType {
+    #[serde(rename = "ecdsa")]
+    Ecdsa,
+    #[serde(rename = "ecdsa_recovery")]
+    EcdsaRecovery,
+    #[serde(rename = "ed25519")]
+    Ed25519,
+    #[serde(rename = "schnorr_1")]
+    Schnorr1,
+    #[serde(rename = "schnorr_poseidon")]
+    SchnorrPoseidon,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublicKey {
+    pub hex_bytes: String,
+
+    pub curve_type: CurveType,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SigningPayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_identifier: Option<AccountIdentifier>,
+
+    pub hex_bytes: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature_type: Option<SignatureType>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Signature {
+    pub signing_payload: SigningPayload,
+
+    pub public_key: PublicKey,
+
+    pub signature_type: SignatureType,
+
+    pub hex_bytes: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionDeriveRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    pub public_key: PublicKey,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionDeriveResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_identifier: Option<AccountIdentifier>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionPreprocessRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    pub operations: Vec<Operation>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionPreprocessResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<serde_json::Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required_public_keys: Option<Vec<AccountIdentifier>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionMetadataRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<serde_json::Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_keys: Option<Vec<PublicKey>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionMetadataResponse {
+    pub metadata: serde_json::Value,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggested_fee: Option<Vec<Amount>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionPayloadsRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    pub operations: Vec<Operation>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_keys: Option<Vec<PublicKey>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionPayloadsResponse {
+    /// The hex encoded CBOR serialization of the unsigned transaction.
+    pub unsigned_transaction: String,
+
+    pub payloads: Vec<SigningPayload>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionCombineRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    pub unsigned_transaction: String,
+
+    pub signatures: Vec<Signature>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionCombineResponse {
+    /// The hex encoded CBOR serialization of the signed transaction.
+    pub signed_transaction: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionParseRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    pub signed: bool,
+
+    /// Either the unsigned transaction returned by /construction/payloads or
+    /// the signed transaction returned by /construction/combine.
+    pub transaction: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionParseResponse {
+    pub operations: Vec<Operation>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_identifier_signers: Option<Vec<AccountIdentifier>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionHashRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    pub signed_transaction: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionSubmitRequest {
+    pub network_identifier: NetworkIdentifier,
+
+    pub signed_transaction: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TransactionIdentifierResponse {
+    pub transaction_identifier: TransactionIdentifier,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
@@ -0,0 +1,268 @@
+use crate::common::storage::types::RosettaBlock;
+use crate::common::types::{
+    AccountIdentifier, Amount, ApproveMetadata, Block, BlockIdentifier, Currency, Operation,
+    OperationIdentifier, Transaction, TransactionIdentifier,
+};
+use ic_icrc1::{Operation as IcrcOperation, Transaction as IcrcTransaction};
+use ic_icrc1_tokens_u64::U64;
+use icrc_ledger_types::icrc1::account::Account;
+use serde_json::json;
+use std::str::FromStr;
+
+type Tokens = U64;
+
+/// The status of all operations, as only applied transactions are stored in the ledger.
+pub const STATUS_COMPLETED: &str = "COMPLETED";
+
+/// The types of the Rosetta operations that ICRC-1 transactions consist of.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OperationType {
+    Mint,
+    Burn,
+    Transfer,
+    Approve,
+    Fee,
+}
+
+impl OperationType {
+    pub const ALL: [OperationType; 5] = [
+        OperationType::Mint,
+        OperationType::Burn,
+        OperationType::Transfer,
+        OperationType::Approve,
+        OperationType::Fee,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OperationType::Mint => "MINT",
+            OperationType::Burn => "BURN",
+            OperationType::Transfer => "TRANSFER",
+            OperationType::Approve => "APPROVE",
+            OperationType::Fee => "FEE",
+        }
+    }
+}
+
+impl FromStr for OperationType {
+    type Err = String;
+
+    fn from_str(operation_type: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|ty| ty.as_str() == operation_type)
+            .ok_or_else(|| format!("Unknown operation type {}", operation_type))
+    }
+}
+
+/// Converts a timestamp in nanoseconds since the Unix Epoch to milliseconds.
+pub fn convert_timestamp_to_millis(timestamp_nanos: u64) -> u64 {
+    timestamp_nanos / 1_000_000
+}
+
+pub fn rosetta_block_identifier(rosetta_block: &RosettaBlock) -> BlockIdentifier {
+    BlockIdentifier {
+        index: rosetta_block.index,
+        hash: hex::encode(&rosetta_block.block_hash),
+    }
+}
+
+/// Returns the identifier of the parent block. By the Rosetta convention the
+/// genesis block is its own parent.
+pub fn rosetta_parent_block_identifier(rosetta_block: &RosettaBlock) -> BlockIdentifier {
+    match &rosetta_block.parent_hash {
+        Some(parent_hash) => BlockIdentifier {
+            index: rosetta_block.index.saturating_sub(1),
+            hash: hex::encode(parent_hash),
+        },
+        None => rosetta_block_identifier(rosetta_block),
+    }
+}
+
+fn amount(value: Tokens, debit: bool, currency: &Currency) -> Amount {
+    Amount {
+        value: if debit {
+            format!("-{}", value)
+        } else {
+            value.to_string()
+        },
+        currency: currency.clone(),
+        metadata: None,
+    }
+}
+
+/// Converts an ICRC-1 operation into Rosetta operations with the given status.
+/// `effective_fee` is the fee charged by the ledger if the operation does not
+/// specify one, and `fee_collector` is the account that collects the fee, if any.
+pub fn icrc1_rosetta_operations(
+    operation: IcrcOperation<Tokens>,
+    effective_fee: Option<Tokens>,
+    fee_collector: Option<Account>,
+    status: Option<&str>,
+    currency: &Currency,
+) -> Vec<Operation> {
+    let mut operations = vec![];
+    let mut push_operation = |type_: OperationType,
+                              account: Account,
+                              amount: Option<Amount>,
+                              metadata: Option<serde_json::Value>| {
+        operations.push(Operation {
+            operation_identifier: OperationIdentifier {
+                index: operations.len() as u64,
+                network_index: None,
+            },
+            related_operations: None,
+            type_: type_.as_str().to_string(),
+            status: status.map(str::to_string),
+            account: Some(AccountIdentifier::from(account)),
+            amount,
+            metadata,
+        })
+    };
+
+    match operation {
+        IcrcOperation::Mint { to, amount: value } => push_operation(
+            OperationType::Mint,
+            to,
+            Some(amount(value, false, currency)),
+            None,
+        ),
+        IcrcOperation::Burn {
+            from,
+            spender,
+            amount: value,
+        } => push_operation(
+            OperationType::Burn,
+            from,
+            Some(amount(value, true, currency)),
+            spender.map(|spender| json!({ "spender": AccountIdentifier::from(spender) })),
+        ),
+        IcrcOperation::Transfer {
+            from,
+            to,
+            spender,
+            amount: value,
+            fee,
+        } => {
+            push_operation(
+                OperationType::Transfer,
+                from,
+                Some(amount(value, true, currency)),
+                spender.map(|spender| json!({ "spender": AccountIdentifier::from(spender) })),
+            );
+            push_operation(
+                OperationType::Transfer,
+                to,
+                Some(amount(value, false, currency)),
+                None,
+            );
+            if let Some(fee) = fee.or(effective_fee) {
+                push_operation(
+                    OperationType::Fee,
+                    from,
+                    Some(amount(fee, true, currency)),
+                    None,
+                );
+                if let Some(fee_collector) = fee_collector {
+                    push_operation(
+                        OperationType::Fee,
+                        fee_collector,
+                        Some(amount(fee, false, currency)),
+                        None,
+                    );
+                }
+            }
+        }
+        IcrcOperation::Approve {
+            from,
+            spender,
+            amount: allowance,
+            expected_allowance,
+            expires_at,
+            fee,
+        } => {
+            let metadata = ApproveMetadata {
+                spender: AccountIdentifier::from(spender),
+                allowance: amount(allowance, false, currency),
+                expected_allowance: expected_allowance
+                    .map(|expected_allowance| amount(expected_allowance, false, currency)),
+                expires_at: expires_at.map(|expires_at| expires_at.as_nanos_since_unix_epoch()),
+            };
+            push_operation(
+                OperationType::Approve,
+                from,
+                None,
+                Some(serde_json::to_value(metadata).expect("failed to serialize approve metadata")),
+            );
+            // Approval fees are burned.
+            if let Some(fee) = fee.or(effective_fee) {
+                push_operation(
+                    OperationType::Fee,
+                    from,
+                    Some(amount(fee, true, currency)),
+                    None,
+                );
+            }
+        }
+    }
+    operations
+}
+
+/// Returns the memo and creation time of an ICRC-1 transaction as Rosetta metadata.
+pub fn icrc1_transaction_metadata(
+    transaction: &IcrcTransaction<Tokens>,
+) -> Option<serde_json::Value> {
+    let mut metadata = serde_json::Map::new();
+    if let Some(memo) = &transaction.memo {
+        metadata.insert("memo".to_string(), json!(hex::encode(&memo.0)));
+    }
+    if let Some(created_at_time) = transaction.created_at_time {
+        metadata.insert("created_at_time".to_string(), json!(created_at_time));
+    }
+    (!metadata.is_empty()).then_some(serde_json::Value::Object(metadata))
+}
+
+/// Converts the transaction of a stored block into a Rosetta transaction.
+/// `fee_collector` is the account that collects the fees of the block, if any.
+pub fn icrc1_rosetta_transaction(
+    rosetta_block: &RosettaBlock,
+    fee_collector: Option<Account>,
+    currency: &Currency,
+) -> anyhow::Result<Transaction> {
+    let block = rosetta_block.get_block()?;
+    let metadata = icrc1_transaction_metadata(&block.transaction);
+    Ok(Transaction {
+        transaction_identifier: TransactionIdentifier {
+            hash: hex::encode(&rosetta_block.transaction_hash),
+        },
+        operations: icrc1_rosetta_operations(
+            block.transaction.operation,
+            block.effective_fee,
+            fee_collector,
+            Some(STATUS_COMPLETED),
+            currency,
+        ),
+        metadata,
+    })
+}
+
+/// Converts a stored block into a Rosetta block.
+/// `fee_collector` is the account that collects the fees of the block, if any.
+pub fn icrc1_rosetta_block(
+    rosetta_block: &RosettaBlock,
+    fee_collector: Option<Account>,
+    currency: &Currency,
+) -> anyhow::Result<Block> {
+    let timestamp = rosetta_block.get_block()?.timestamp;
+    Ok(Block {
+        block_identifier: rosetta_block_identifier(rosetta_block),
+        parent_block_identifier: rosetta_parent_block_identifier(rosetta_block),
+        timestamp: convert_timestamp_to_millis(timestamp),
+        transactions: vec![icrc1_rosetta_transaction(
+            rosetta_block,
+            fee_collector,
+            currency,
+        )?],
+        metadata: None,
+    })
+}
@@ -1 +1,2 @@
+pub mod conversions;
 pub mod unit_test_utils;
@@ -0,0 +1,3 @@
+pub mod services;
+pub mod types;
+pub mod utils;
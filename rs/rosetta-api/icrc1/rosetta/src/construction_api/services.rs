@@ -0,0 +1,1295 @@
+use crate::common::types::{
+    AccountIdentifier, Amount, ConstructionCombineResponse, ConstructionDeriveResponse,
+    ConstructionMetadataResponse, ConstructionParseResponse, ConstructionPayloadsResponse,
+    ConstructionPreprocessResponse, Currency, Error, Operation, PublicKey, Signature,
+    SigningPayload, TransactionIdentifier, TransactionIdentifierResponse,
+};
+use crate::common::utils::conversions::{icrc1_rosetta_operations, icrc1_transaction_metadata};
+use crate::construction_api::types::{
+    ConstructionPayloadsRequestMetadata, EnvelopePair, LedgerCall, SignedTransaction,
+    UnsignedTransaction,
+};
+use crate::construction_api::utils::{
+    der_encode_public_key, ingress_expiries, ingress_validity, ledger_call_from_operations,
+    make_read_state_from_update, make_sig_data, principal_from_public_key, signature_type,
+};
+use candid::{Decode, Nat, Principal};
+use ic_agent::agent::{Replied, RequestStatusResponse};
+use ic_base_types::CanisterId;
+use ic_icrc1::Transaction as IcrcTransaction;
+use ic_icrc1_tokens_u64::U64;
+use ic_ledger_canister_core::ledger::LedgerTransaction;
+use ic_types::messages::{
+    Blob, HttpCallContent, HttpCanisterUpdate, HttpReadStateContent, HttpRequestEnvelope,
+    MessageId, SignedRequestBytes,
+};
+use ic_types::time::{current_time, Time};
+use icrc_ledger_agent::{CallMode, Icrc1Agent};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::{Memo, TransferError};
+use icrc_ledger_types::icrc2::approve::ApproveError;
+use icrc_ledger_types::icrc2::transfer_from::TransferFromError;
+use num_traits::ToPrimitive;
+use serde_json::json;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+type Tokens = U64;
+
+// Exponential backoff from 100ms to 10s with a multiplier of 1.3 while
+// polling for the result of a submitted transaction.
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(10);
+const POLL_INTERVAL_MULTIPLIER: f32 = 1.3;
+const SUBMIT_TIMEOUT: Duration = Duration::from_secs(20);
+
+fn decode_unsigned_transaction(transaction: &str) -> Result<UnsignedTransaction, Error> {
+    UnsignedTransaction::from_str(transaction).map_err(Error::invalid_transaction)
+}
+
+fn decode_signed_transaction(transaction: &str) -> Result<SignedTransaction, Error> {
+    SignedTransaction::from_str(transaction).map_err(Error::invalid_transaction)
+}
+
+/// Decodes the ledger call of the update and returns it together with its caller.
+fn decode_update(update: &HttpCanisterUpdate) -> Result<(Principal, LedgerCall), Error> {
+    let caller = Principal::try_from_slice(&update.sender.0)
+        .map_err(|e| Error::invalid_transaction(format!("Invalid sender: {}", e)))?;
+    let ledger_call = LedgerCall::decode(&update.method_name, &update.arg.0)
+        .map_err(Error::invalid_transaction)?;
+    Ok((caller, ledger_call))
+}
+
+fn ledger_transaction(update: &HttpCanisterUpdate) -> Result<IcrcTransaction<Tokens>, Error> {
+    let (caller, ledger_call) = decode_update(update)?;
+    ledger_call
+        .to_transaction(caller)
+        .map_err(Error::invalid_transaction)
+}
+
+fn transaction_identifier(update: &HttpCanisterUpdate) -> Result<TransactionIdentifier, Error> {
+    let transaction = ledger_transaction(update)?;
+    Ok(TransactionIdentifier {
+        hash: hex::encode(
+            <IcrcTransaction<Tokens> as LedgerTransaction>::hash(&transaction).as_slice(),
+        ),
+    })
+}
+
+pub fn construction_derive(public_key: &PublicKey) -> Result<ConstructionDeriveResponse, Error> {
+    let principal = principal_from_public_key(public_key).map_err(Error::invalid_public_key)?;
+    Ok(ConstructionDeriveResponse {
+        account_identifier: Some(AccountIdentifier::from(Account::from(principal))),
+        metadata: None,
+    })
+}
+
+pub fn construction_preprocess(
+    operations: &[Operation],
+    currency: &Currency,
+) -> Result<ConstructionPreprocessResponse, Error> {
+    let (caller, _) = ledger_call_from_operations(operations, None, None, currency)
+        .map_err(Error::invalid_operations)?;
+    Ok(ConstructionPreprocessResponse {
+        options: None,
+        required_public_keys: Some(vec![AccountIdentifier::from(Account::from(caller.owner))]),
+    })
+}
+
+pub async fn construction_metadata(
+    icrc1_agent: &Icrc1Agent,
+    currency: &Currency,
+) -> Result<ConstructionMetadataResponse, Error> {
+    let fee = icrc1_agent
+        .fee(CallMode::Query)
+        .await
+        .map_err(|e| Error::ledger_communication_error(format!("{:?}", e)))?;
+    Ok(ConstructionMetadataResponse {
+        metadata: json!({}),
+        suggested_fee: Some(vec![Amount {
+            value: fee.0.to_string(),
+            currency: currency.clone(),
+            metadata: None,
+        }]),
+    })
+}
+
+pub fn construction_payloads(
+    ledger_id: CanisterId,
+    operations: &[Operation],
+    metadata: &Option<serde_json::Value>,
+    public_keys: &[PublicKey],
+    currency: &Currency,
+) -> Result<ConstructionPayloadsResponse, Error> {
+    let metadata: ConstructionPayloadsRequestMetadata = match metadata {
+        Some(metadata) => serde_json::from_value(metadata.clone())
+            .map_err(|e| Error::invalid_metadata(e.to_string()))?,
+        None => ConstructionPayloadsRequestMetadata::default(),
+    };
+    let memo = metadata
+        .memo
+        .as_ref()
+        .map(|memo| {
+            hex::decode(memo)
+                .map(Memo::from)
+                .map_err(|e| Error::invalid_metadata(format!("Invalid memo {}: {}", memo, e)))
+        })
+        .transpose()?;
+    let created_at_time = metadata
+        .created_at_time
+        .unwrap_or_else(|| current_time().as_nanos_since_unix_epoch());
+
+    let (caller, ledger_call) =
+        ledger_call_from_operations(operations, memo, Some(created_at_time), currency)
+            .map_err(Error::invalid_operations)?;
+    let public_key = public_keys
+        .iter()
+        .find(|public_key| principal_from_public_key(public_key).ok() == Some(caller.owner))
+        .ok_or_else(|| {
+            Error::invalid_public_key(format!("Missing the public key of {}", caller.owner))
+        })?;
+    let signature_type =
+        signature_type(public_key.curve_type).map_err(Error::invalid_public_key)?;
+    let account_identifier = AccountIdentifier::from(Account::from(caller.owner));
+
+    let update = HttpCanisterUpdate {
+        canister_id: Blob(ledger_id.get().into_vec()),
+        method_name: ledger_call.method_name().to_string(),
+        arg: Blob(
+            ledger_call
+                .encode_arg()
+                .map_err(Error::invalid_operations)?,
+        ),
+        sender: Blob(caller.owner.as_slice().to_vec()),
+        ingress_expiry: 0,
+        nonce: None,
+    };
+    let ingress_expiries = ingress_expiries(metadata.ingress_start, metadata.ingress_end);
+    if ingress_expiries.is_empty() {
+        return Err(Error::invalid_metadata(
+            "The ingress end must be after the ingress start".to_string(),
+        ));
+    }
+
+    let mut payloads = vec![];
+    for ingress_expiry in &ingress_expiries {
+        let mut update = update.clone();
+        update.ingress_expiry = *ingress_expiry;
+        let read_state = make_read_state_from_update(&update);
+        for message_id in [
+            update.id(),
+            MessageId::from(read_state.representation_independent_hash()),
+        ] {
+            payloads.push(SigningPayload {
+                account_identifier: Some(account_identifier.clone()),
+                hex_bytes: hex::encode(make_sig_data(&message_id)),
+                signature_type: Some(signature_type),
+            });
+        }
+    }
+
+    Ok(ConstructionPayloadsResponse {
+        unsigned_transaction: UnsignedTransaction {
+            update,
+            ingress_expiries,
+        }
+        .to_hex(),
+        payloads,
+    })
+}
+
+pub fn construction_combine(
+    unsigned_transaction: &str,
+    signatures: &[Signature],
+) -> Result<ConstructionCombineResponse, Error> {
+    let unsigned_transaction = decode_unsigned_transaction(unsigned_transaction)?;
+    let signatures_by_payload: HashMap<&str, &Signature> = signatures
+        .iter()
+        .map(|signature| (signature.signing_payload.hex_bytes.as_str(), signature))
+        .collect();
+
+    let sign = |message_id: &MessageId| -> Result<(Blob, Blob), Error> {
+        let payload = hex::encode(make_sig_data(message_id));
+        let signature = signatures_by_payload.get(payload.as_str()).ok_or_else(|| {
+            Error::invalid_transaction(format!("Missing the signature of payload {}", payload))
+        })?;
+        if signature_type(signature.public_key.curve_type).ok() != Some(signature.signature_type) {
+            return Err(Error::invalid_transaction(format!(
+                "Signature type {:?} does not match curve type {:?}",
+                signature.signature_type, signature.public_key.curve_type
+            )));
+        }
+        let public_key =
+            der_encode_public_key(&signature.public_key).map_err(Error::invalid_public_key)?;
+        let signature = hex::decode(&signature.hex_bytes).map_err(|e| {
+            Error::invalid_transaction(format!("Invalid signature {}: {}", signature.hex_bytes, e))
+        })?;
+        Ok((Blob(public_key), Blob(signature)))
+    };
+
+    let mut envelope_pairs = vec![];
+    for ingress_expiry in &unsigned_transaction.ingress_expiries {
+        let mut update = unsigned_transaction.update.clone();
+        update.ingress_expiry = *ingress_expiry;
+        let read_state = make_read_state_from_update(&update);
+
+        let (update_public_key, update_signature) = sign(&update.id())?;
+        let (read_state_public_key, read_state_signature) = sign(&MessageId::from(
+            read_state.representation_independent_hash(),
+        ))?;
+        envelope_pairs.push(EnvelopePair {
+            call_envelope: HttpRequestEnvelope {
+                content: HttpCallContent::Call { update },
+                sender_pubkey: Some(update_public_key),
+                sender_sig: Some(update_signature),
+                sender_delegation: None,
+            },
+            read_state_envelope: HttpRequestEnvelope {
+                content: HttpReadStateContent::ReadState { read_state },
+                sender_pubkey: Some(read_state_public_key),
+                sender_sig: Some(read_state_signature),
+                sender_delegation: None,
+            },
+        });
+    }
+
+    Ok(ConstructionCombineResponse {
+        signed_transaction: SignedTransaction { envelope_pairs }.to_hex(),
+    })
+}
+
+pub fn construction_parse(
+    transaction: &str,
+    signed: bool,
+    currency: &Currency,
+) -> Result<ConstructionParseResponse, Error> {
+    let update = if signed {
+        decode_signed_transaction(transaction)?.envelope_pairs[0]
+            .update()
+            .clone()
+    } else {
+        decode_unsigned_transaction(transaction)?.update
+    };
+    let (caller, _) = decode_update(&update)?;
+    let transaction = ledger_transaction(&update)?;
+    let metadata = icrc1_transaction_metadata(&transaction);
+    Ok(ConstructionParseResponse {
+        operations: icrc1_rosetta_operations(transaction.operation, None, None, None, currency),
+        account_identifier_signers: signed
+            .then(|| vec![AccountIdentifier::from(Account::from(caller))]),
+        metadata,
+    })
+}
+
+pub fn construction_hash(signed_transaction: &str) -> Result<TransactionIdentifierResponse, Error> {
+    let signed_transaction = decode_signed_transaction(signed_transaction)?;
+    Ok(TransactionIdentifierResponse {
+        transaction_identifier: transaction_identifier(
+            signed_transaction.envelope_pairs[0].update(),
+        )?,
+        metadata: None,
+    })
+}
+
+/// Decodes the reply of the ledger to a submitted call.
+/// Returns the index of the block that records the transaction.
+fn decode_reply(ledger_call: &LedgerCall, reply: &[u8]) -> Result<Nat, Error> {
+    let decode_error = |e: candid::Error| {
+        Error::ledger_communication_error(format!("Failed to decode the ledger reply: {}", e))
+    };
+    // A duplicate means that the same transaction has already been applied.
+    match ledger_call {
+        LedgerCall::Transfer(_) => {
+            match Decode!(reply, Result<Nat, TransferError>).map_err(decode_error)? {
+                Ok(block_index)
+                | Err(TransferError::Duplicate {
+                    duplicate_of: block_index,
+                }) => Ok(block_index),
+                Err(e) => Err(Error::transaction_rejected(format!("{:?}", e))),
+            }
+        }
+        LedgerCall::Approve(_) => {
+            match Decode!(reply, Result<Nat, ApproveError>).map_err(decode_error)? {
+                Ok(block_index)
+                | Err(ApproveError::Duplicate {
+                    duplicate_of: block_index,
+                }) => Ok(block_index),
+                Err(e) => Err(Error::transaction_rejected(format!("{:?}", e))),
+            }
+        }
+        LedgerCall::TransferFrom(_) => {
+            match Decode!(reply, Result<Nat, TransferFromError>).map_err(decode_error)? {
+                Ok(block_index)
+                | Err(TransferFromError::Duplicate {
+                    duplicate_of: block_index,
+                }) => Ok(block_index),
+                Err(e) => Err(Error::transaction_rejected(format!("{:?}", e))),
+            }
+        }
+    }
+}
+
+pub async fn construction_submit(
+    icrc1_agent: &Icrc1Agent,
+    signed_transaction: &str,
+) -> Result<TransactionIdentifierResponse, Error> {
+    let signed_transaction = decode_signed_transaction(signed_transaction)?;
+
+    // Pick the envelope pair whose ingress expiry is currently valid.
+    let now = current_time();
+    let envelope_pair = signed_transaction
+        .envelope_pairs
+        .into_iter()
+        .find(|envelope_pair| {
+            let ingress_expiry =
+                Time::from_nanos_since_unix_epoch(envelope_pair.update().ingress_expiry);
+            ingress_expiry.saturating_sub_duration(ingress_validity()) <= now
+                && ingress_expiry > now
+        })
+        .ok_or_else(|| {
+            Error::transaction_expired(
+                "None of the ingress expiries of the transaction is currently valid".to_string(),
+            )
+        })?;
+
+    let transaction_identifier = transaction_identifier(envelope_pair.update())?;
+    let (_, ledger_call) = decode_update(envelope_pair.update())?;
+    let ledger_id = Principal::try_from_slice(&envelope_pair.update().canister_id.0)
+        .map_err(|e| Error::invalid_transaction(format!("Invalid canister id: {}", e)))?;
+    if ledger_id != icrc1_agent.ledger_canister_id {
+        return Err(Error::invalid_transaction(format!(
+            "The transaction is for canister {}, not for the ledger {}",
+            ledger_id, icrc1_agent.ledger_canister_id
+        )));
+    }
+
+    let serialize = |e: serde_cbor::Error| {
+        Error::invalid_transaction(format!("Failed to serialize the envelope: {}", e))
+    };
+    let call_bytes: Vec<u8> = SignedRequestBytes::try_from(envelope_pair.call_envelope)
+        .map_err(serialize)?
+        .into();
+    let read_state_bytes: Vec<u8> = SignedRequestBytes::try_from(envelope_pair.read_state_envelope)
+        .map_err(serialize)?
+        .into();
+
+    let request_id = icrc1_agent
+        .agent
+        .update_signed(ledger_id, call_bytes)
+        .await
+        .map_err(|e| Error::ledger_communication_error(e.to_string()))?;
+
+    // Poll the status of the request until the ledger replies.
+    let deadline = Instant::now() + SUBMIT_TIMEOUT;
+    let mut poll_interval = MIN_POLL_INTERVAL;
+    while Instant::now() + poll_interval < deadline {
+        tokio::time::sleep(poll_interval).await;
+        match icrc1_agent
+            .agent
+            .request_status_signed(&request_id, ledger_id, read_state_bytes.clone(), false)
+            .await
+            .map_err(|e| Error::ledger_communication_error(e.to_string()))?
+        {
+            RequestStatusResponse::Unknown
+            | RequestStatusResponse::Received
+            | RequestStatusResponse::Processing => {}
+            RequestStatusResponse::Replied {
+                reply: Replied::CallReplied(reply),
+            } => {
+                let block_index = decode_reply(&ledger_call, &reply)?;
+                return Ok(TransactionIdentifierResponse {
+                    transaction_identifier,
+                    metadata: Some(json!({ "block_index": block_index.0.to_u64() })),
+                });
+            }
+            status => {
+                return Err(Error::transaction_rejected(format!(
+                    "The ledger did not reply to the transaction: {:?}",
+                    status
+                )))
+            }
+        }
+        poll_interval = poll_interval
+            .mul_f32(POLL_INTERVAL_MULTIPLIER)
+            .min(MAX_POLL_INTERVAL);
+    }
+
+    Err(Error::ledger_communication_error(format!(
+        "The transaction {} was submitted but its result is not known after {:?}",
+        transaction_identifier.hash, SUBMIT_TIMEOUT
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::types::{ApproveMetadata, CurveType, OperationIdentifier, SignatureType};
+    use crate::common::utils::conversions::OperationType;
+    use candid::Encode;
+    use ic_agent::agent::http_transport::ReqwestHttpReplicaV2Transport;
+    use ic_agent::Agent;
+    use ic_canister_client_sender::{Ed25519KeyPair, Secp256k1KeyPair};
+    use ic_icrc1::Operation as IcrcOperation;
+    use ic_ledger_core::timestamp::TimeStamp;
+    use icrc_ledger_types::icrc1::transfer::TransferArg;
+    use icrc_ledger_types::icrc2::approve::ApproveArgs;
+    use icrc_ledger_types::icrc2::transfer_from::TransferFromArgs;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    const CREATED_AT_TIME: u64 = 1_000_000_000;
+    const MEMO: [u8; 4] = [1, 2, 3, 4];
+
+    enum KeyPair {
+        Ed25519(Ed25519KeyPair),
+        Secp256k1(Secp256k1KeyPair),
+    }
+
+    impl KeyPair {
+        fn public_key(&self) -> PublicKey {
+            match self {
+                KeyPair::Ed25519(key_pair) => PublicKey {
+                    hex_bytes: hex::encode(key_pair.public_key),
+                    curve_type: CurveType::Edwards25519,
+                },
+                KeyPair::Secp256k1(key_pair) => PublicKey {
+                    hex_bytes: hex::encode(key_pair.get_public_key().serialize_sec1(false)),
+                    curve_type: CurveType::Secp256K1,
+                },
+            }
+        }
+
+        fn principal(&self) -> Principal {
+            match self {
+                KeyPair::Ed25519(key_pair) => Principal::self_authenticating(
+                    ic_canister_client_sender::ed25519_public_key_to_der(
+                        key_pair.public_key.to_vec(),
+                    ),
+                ),
+                KeyPair::Secp256k1(key_pair) => {
+                    Principal::self_authenticating(key_pair.get_public_key().serialize_der())
+                }
+            }
+        }
+
+        fn signature_type(&self) -> SignatureType {
+            match self {
+                KeyPair::Ed25519(_) => SignatureType::Ed25519,
+                KeyPair::Secp256k1(_) => SignatureType::Ecdsa,
+            }
+        }
+
+        fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+            match self {
+                KeyPair::Ed25519(key_pair) => ring::signature::UnparsedPublicKey::new(
+                    &ring::signature::ED25519,
+                    key_pair.public_key,
+                )
+                .verify(message, signature)
+                .is_ok(),
+                KeyPair::Secp256k1(key_pair) => key_pair
+                    .get_public_key()
+                    .verify_signature(message, signature),
+            }
+        }
+
+        fn sign(&self, payload: &SigningPayload) -> Signature {
+            let bytes = hex::decode(&payload.hex_bytes).unwrap();
+            let signature = match self {
+                KeyPair::Ed25519(key_pair) => key_pair.sign(&bytes).to_vec(),
+                KeyPair::Secp256k1(key_pair) => key_pair.sign(&bytes),
+            };
+            Signature {
+                signing_payload: payload.clone(),
+                public_key: self.public_key(),
+                signature_type: self.signature_type(),
+                hex_bytes: hex::encode(signature),
+            }
+        }
+    }
+
+    fn ed25519_key_pair(seed: u64) -> KeyPair {
+        KeyPair::Ed25519(Ed25519KeyPair::generate(&mut StdRng::seed_from_u64(seed)))
+    }
+
+    fn secp256k1_key_pair(seed: u64) -> KeyPair {
+        KeyPair::Secp256k1(Secp256k1KeyPair::generate(&mut StdRng::seed_from_u64(seed)))
+    }
+
+    fn currency() -> Currency {
+        Currency {
+            symbol: "XTST".to_string(),
+            decimals: 8,
+            metadata: None,
+        }
+    }
+
+    fn amount(value: i128) -> Amount {
+        Amount {
+            value: value.to_string(),
+            currency: currency(),
+            metadata: None,
+        }
+    }
+
+    fn operation(
+        index: u64,
+        type_: OperationType,
+        account: Account,
+        value: Option<i128>,
+        metadata: Option<serde_json::Value>,
+    ) -> Operation {
+        Operation {
+            operation_identifier: OperationIdentifier {
+                index,
+                network_index: None,
+            },
+            related_operations: None,
+            type_: type_.as_str().to_string(),
+            status: None,
+            account: Some(AccountIdentifier::from(account)),
+            amount: value.map(amount),
+            metadata,
+        }
+    }
+
+    fn account(principal: Principal, subaccount: Option<u8>) -> Account {
+        Account {
+            owner: principal,
+            subaccount: subaccount.map(|subaccount| [subaccount; 32]),
+        }
+    }
+
+    fn other_principal(id: u64) -> Principal {
+        CanisterId::from_u64(id).get().0
+    }
+
+    // Checks that the result is an error built by the given constructor.
+    fn assert_error<T: std::fmt::Debug>(result: Result<T, Error>, expected: fn(String) -> Error) {
+        let error = result.unwrap_err();
+        let expected = expected(String::new());
+        assert_eq!(
+            (error.code, &error.message),
+            (expected.code, &expected.message),
+            "unexpected error {:?}",
+            error
+        );
+    }
+
+    fn transfer_operations(from: Account, to: Account) -> Vec<Operation> {
+        vec![
+            operation(0, OperationType::Transfer, from, Some(-1_000), None),
+            operation(1, OperationType::Transfer, to, Some(1_000), None),
+        ]
+    }
+
+    fn payloads_with_metadata(
+        key_pair: &KeyPair,
+        metadata: serde_json::Value,
+    ) -> Result<ConstructionPayloadsResponse, Error> {
+        construction_payloads(
+            CanisterId::from_u64(1),
+            &transfer_operations(
+                account(key_pair.principal(), None),
+                account(other_principal(2), None),
+            ),
+            &Some(metadata),
+            &[key_pair.public_key()],
+            &currency(),
+        )
+    }
+
+    fn sign_all(key_pair: &KeyPair, payloads: &ConstructionPayloadsResponse) -> Vec<Signature> {
+        payloads
+            .payloads
+            .iter()
+            .map(|payload| key_pair.sign(payload))
+            .collect()
+    }
+
+    fn test_agent(ledger_id: CanisterId) -> Icrc1Agent {
+        // The agent is never used to send requests, so the URL does not matter.
+        let transport = ReqwestHttpReplicaV2Transport::create("http://localhost:1").unwrap();
+        Icrc1Agent {
+            agent: Agent::builder().with_transport(transport).build().unwrap(),
+            ledger_canister_id: ledger_id.get().0,
+        }
+    }
+
+    /// Runs the construction flow for `operations` signed by `key_pair` and
+    /// checks that the transaction hash is the hash of `expected_transaction`.
+    fn check_construction_flow(
+        key_pair: &KeyPair,
+        operations: Vec<Operation>,
+        expected_transaction: IcrcTransaction<Tokens>,
+    ) {
+        let currency = currency();
+        let signer = AccountIdentifier::from(Account::from(key_pair.principal()));
+
+        let derived = construction_derive(&key_pair.public_key()).unwrap();
+        assert_eq!(derived.account_identifier, Some(signer.clone()));
+
+        let preprocessed = construction_preprocess(&operations, &currency).unwrap();
+        assert_eq!(
+            preprocessed.required_public_keys,
+            Some(vec![signer.clone()])
+        );
+
+        let metadata = json!({
+            "memo": hex::encode(MEMO),
+            "created_at_time": CREATED_AT_TIME,
+        });
+        let payloads = construction_payloads(
+            CanisterId::from_u64(1),
+            &operations,
+            &Some(metadata.clone()),
+            &[key_pair.public_key()],
+            &currency,
+        )
+        .unwrap();
+        let unsigned_transaction =
+            UnsignedTransaction::from_str(&payloads.unsigned_transaction).unwrap();
+        assert_eq!(
+            UnsignedTransaction::from_str(&unsigned_transaction.to_hex()).unwrap(),
+            unsigned_transaction
+        );
+        let update = &unsigned_transaction.update;
+        assert_eq!(update.canister_id.0, CanisterId::from_u64(1).get().to_vec());
+        assert_eq!(update.sender.0, key_pair.principal().as_slice());
+        assert_eq!(ledger_transaction(update).unwrap(), expected_transaction);
+
+        // One payload for the update call and one for the read_state request per ingress expiry.
+        assert_eq!(
+            payloads.payloads.len(),
+            2 * unsigned_transaction.ingress_expiries.len()
+        );
+        assert!(payloads
+            .payloads
+            .iter()
+            .all(|payload| payload.account_identifier == Some(signer.clone())
+                && payload.signature_type == Some(key_pair.signature_type())));
+        for (ingress_expiry, payloads) in unsigned_transaction
+            .ingress_expiries
+            .iter()
+            .zip(payloads.payloads.chunks(2))
+        {
+            let mut update = update.clone();
+            update.ingress_expiry = *ingress_expiry;
+            let read_state = make_read_state_from_update(&update);
+            assert_eq!(
+                payloads[0].hex_bytes,
+                hex::encode(make_sig_data(&update.id()))
+            );
+            assert_eq!(
+                payloads[1].hex_bytes,
+                hex::encode(make_sig_data(&MessageId::from(
+                    read_state.representation_independent_hash()
+                )))
+            );
+        }
+
+        let parsed = construction_parse(&payloads.unsigned_transaction, false, &currency).unwrap();
+        assert_eq!(parsed.operations, operations);
+        assert_eq!(parsed.account_identifier_signers, None);
+        assert_eq!(parsed.metadata, Some(metadata.clone()));
+
+        let signatures: Vec<_> = payloads
+            .payloads
+            .iter()
+            .map(|payload| key_pair.sign(payload))
+            .collect();
+        assert!(construction_combine(&payloads.unsigned_transaction, &signatures[1..]).is_err());
+        let combined = construction_combine(&payloads.unsigned_transaction, &signatures).unwrap();
+        let signed_transaction = SignedTransaction::from_str(&combined.signed_transaction).unwrap();
+        assert_eq!(
+            SignedTransaction::from_str(&signed_transaction.to_hex()).unwrap(),
+            signed_transaction
+        );
+        assert_eq!(
+            signed_transaction.envelope_pairs.len(),
+            unsigned_transaction.ingress_expiries.len()
+        );
+        // Every envelope is signed by the sender of the update for its ingress expiry.
+        let sender_pubkey = der_encode_public_key(&key_pair.public_key()).unwrap();
+        for (envelope_pair, ingress_expiry) in signed_transaction
+            .envelope_pairs
+            .iter()
+            .zip(&unsigned_transaction.ingress_expiries)
+        {
+            let mut expected_update = unsigned_transaction.update.clone();
+            expected_update.ingress_expiry = *ingress_expiry;
+            assert_eq!(envelope_pair.update(), &expected_update);
+
+            let call_envelope = &envelope_pair.call_envelope;
+            assert_eq!(
+                call_envelope.sender_pubkey,
+                Some(Blob(sender_pubkey.clone()))
+            );
+            assert_eq!(
+                Principal::self_authenticating(&sender_pubkey).as_slice(),
+                expected_update.sender.0
+            );
+            assert!(key_pair.verify(
+                &make_sig_data(&expected_update.id()),
+                &call_envelope.sender_sig.as_ref().unwrap().0
+            ));
+
+            let read_state_envelope = &envelope_pair.read_state_envelope;
+            let HttpReadStateContent::ReadState { read_state } = &read_state_envelope.content;
+            assert_eq!(read_state, &make_read_state_from_update(&expected_update));
+            assert_eq!(
+                read_state_envelope.sender_pubkey,
+                Some(Blob(sender_pubkey.clone()))
+            );
+            assert!(key_pair.verify(
+                &make_sig_data(&MessageId::from(
+                    read_state.representation_independent_hash()
+                )),
+                &read_state_envelope.sender_sig.as_ref().unwrap().0
+            ));
+        }
+
+        let parsed = construction_parse(&combined.signed_transaction, true, &currency).unwrap();
+        assert_eq!(parsed.operations, operations);
+        assert_eq!(parsed.account_identifier_signers, Some(vec![signer]));
+        assert_eq!(parsed.metadata, Some(metadata));
+
+        let hash = construction_hash(&combined.signed_transaction).unwrap();
+        assert_eq!(
+            hash.transaction_identifier.hash,
+            hex::encode(
+                <IcrcTransaction<Tokens> as LedgerTransaction>::hash(&expected_transaction)
+                    .as_slice()
+            )
+        );
+    }
+
+    #[test]
+    fn test_construction_derive() {
+        for key_pair in [ed25519_key_pair(0), secp256k1_key_pair(0)] {
+            assert_eq!(
+                construction_derive(&key_pair.public_key())
+                    .unwrap()
+                    .account_identifier,
+                Some(AccountIdentifier::from(Account::from(key_pair.principal())))
+            );
+        }
+
+        let unsupported = PublicKey {
+            hex_bytes: hex::encode([0; 32]),
+            curve_type: CurveType::Tweedle,
+        };
+        assert!(construction_derive(&unsupported).is_err());
+        let too_short = PublicKey {
+            hex_bytes: hex::encode([0; 31]),
+            curve_type: CurveType::Edwards25519,
+        };
+        assert!(construction_derive(&too_short).is_err());
+    }
+
+    #[test]
+    fn test_construction_transfer() {
+        let key_pair = ed25519_key_pair(1);
+        let from = account(key_pair.principal(), Some(1));
+        let to = account(other_principal(2), Some(2));
+        let operations = vec![
+            operation(0, OperationType::Transfer, from, Some(-1_000), None),
+            operation(1, OperationType::Transfer, to, Some(1_000), None),
+            operation(2, OperationType::Fee, from, Some(-10), None),
+        ];
+        check_construction_flow(
+            &key_pair,
+            operations,
+            IcrcTransaction {
+                operation: IcrcOperation::Transfer {
+                    from,
+                    to,
+                    spender: None,
+                    amount: Tokens::new(1_000),
+                    fee: Some(Tokens::new(10)),
+                },
+                created_at_time: Some(CREATED_AT_TIME),
+                memo: Some(Memo::from(MEMO.to_vec())),
+            },
+        );
+    }
+
+    #[test]
+    fn test_construction_approve() {
+        let key_pair = secp256k1_key_pair(2);
+        let from = account(key_pair.principal(), None);
+        let spender = account(other_principal(3), Some(3));
+        let metadata = ApproveMetadata {
+            spender: AccountIdentifier::from(spender),
+            allowance: amount(5_000),
+            expected_allowance: Some(amount(0)),
+            expires_at: Some(2_000_000_000),
+        };
+        let operations = vec![operation(
+            0,
+            OperationType::Approve,
+            from,
+            None,
+            Some(serde_json::to_value(metadata).unwrap()),
+        )];
+        check_construction_flow(
+            &key_pair,
+            operations,
+            IcrcTransaction {
+                operation: IcrcOperation::Approve {
+                    from,
+                    spender,
+                    amount: Tokens::new(5_000),
+                    expected_allowance: Some(Tokens::new(0)),
+                    expires_at: Some(TimeStamp::from_nanos_since_unix_epoch(2_000_000_000)),
+                    fee: None,
+                },
+                created_at_time: Some(CREATED_AT_TIME),
+                memo: Some(Memo::from(MEMO.to_vec())),
+            },
+        );
+    }
+
+    #[test]
+    fn test_construction_transfer_from() {
+        let key_pair = secp256k1_key_pair(3);
+        let spender = account(key_pair.principal(), Some(4));
+        let from = account(other_principal(4), None);
+        let to = account(other_principal(5), None);
+        let operations = vec![
+            operation(
+                0,
+                OperationType::Transfer,
+                from,
+                Some(-300),
+                Some(json!({ "spender": AccountIdentifier::from(spender) })),
+            ),
+            operation(1, OperationType::Transfer, to, Some(300), None),
+            operation(2, OperationType::Fee, from, Some(-10), None),
+        ];
+        check_construction_flow(
+            &key_pair,
+            operations,
+            IcrcTransaction {
+                operation: IcrcOperation::Transfer {
+                    from,
+                    to,
+                    spender: Some(spender),
+                    amount: Tokens::new(300),
+                    fee: Some(Tokens::new(10)),
+                },
+                created_at_time: Some(CREATED_AT_TIME),
+                memo: Some(Memo::from(MEMO.to_vec())),
+            },
+        );
+    }
+
+    #[test]
+    fn test_construction_invalid_operations() {
+        let currency = currency();
+        let key_pair = ed25519_key_pair(4);
+        let from = account(key_pair.principal(), None);
+        let to = account(other_principal(6), None);
+
+        // The amounts of the debit and the credit differ.
+        let mismatched = vec![
+            operation(0, OperationType::Transfer, from, Some(-1_000), None),
+            operation(1, OperationType::Transfer, to, Some(999), None),
+        ];
+        assert!(construction_preprocess(&mismatched, &currency).is_err());
+
+        // The fee is not paid by the sender.
+        let wrong_fee_payer = vec![
+            operation(0, OperationType::Transfer, from, Some(-1_000), None),
+            operation(1, OperationType::Transfer, to, Some(1_000), None),
+            operation(2, OperationType::Fee, to, Some(-10), None),
+        ];
+        assert!(construction_preprocess(&wrong_fee_payer, &currency).is_err());
+
+        // Mints cannot be constructed.
+        let mint = vec![operation(0, OperationType::Mint, to, Some(1_000), None)];
+        assert!(construction_preprocess(&mint, &currency).is_err());
+
+        // The public key of the sender is missing.
+        let transfer = vec![
+            operation(0, OperationType::Transfer, from, Some(-1_000), None),
+            operation(1, OperationType::Transfer, to, Some(1_000), None),
+        ];
+        assert!(construction_preprocess(&transfer, &currency).is_ok());
+        assert!(construction_payloads(
+            CanisterId::from_u64(1),
+            &transfer,
+            &None,
+            &[ed25519_key_pair(5).public_key()],
+            &currency,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_construction_payloads_ingress_window() {
+        let key_pair = ed25519_key_pair(6);
+        let interval = ingress_validity() - Duration::from_secs(120);
+        let ingress_start = current_time().as_nanos_since_unix_epoch();
+        let ingress_end = ingress_start + 3 * interval.as_nanos() as u64;
+        let payloads = payloads_with_metadata(
+            &key_pair,
+            json!({
+                "ingress_start": ingress_start,
+                "ingress_end": ingress_end,
+            }),
+        )
+        .unwrap();
+        let unsigned_transaction =
+            UnsignedTransaction::from_str(&payloads.unsigned_transaction).unwrap();
+        let expected: Vec<_> = (0..3)
+            .map(|i| {
+                ingress_start
+                    + i * interval.as_nanos() as u64
+                    + ingress_validity().as_nanos() as u64
+            })
+            .collect();
+        assert_eq!(unsigned_transaction.ingress_expiries, expected);
+        assert_eq!(payloads.payloads.len(), 6);
+
+        let combined = construction_combine(
+            &payloads.unsigned_transaction,
+            &sign_all(&key_pair, &payloads),
+        )
+        .unwrap();
+        let signed_transaction = SignedTransaction::from_str(&combined.signed_transaction).unwrap();
+        let ingress_expiries: Vec<_> = signed_transaction
+            .envelope_pairs
+            .iter()
+            .map(|envelope_pair| envelope_pair.update().ingress_expiry)
+            .collect();
+        assert_eq!(ingress_expiries, expected);
+
+        assert_error(
+            payloads_with_metadata(
+                &key_pair,
+                json!({
+                    "ingress_start": ingress_start,
+                    "ingress_end": ingress_start,
+                }),
+            ),
+            Error::invalid_metadata,
+        );
+    }
+
+    #[test]
+    fn test_construction_payloads_errors() {
+        let currency = currency();
+        let key_pair = ed25519_key_pair(7);
+        let operations = transfer_operations(
+            account(key_pair.principal(), None),
+            account(other_principal(2), None),
+        );
+
+        assert_error(
+            payloads_with_metadata(&key_pair, json!({ "memo": "not hex" })),
+            Error::invalid_metadata,
+        );
+        assert_error(
+            payloads_with_metadata(&key_pair, json!({ "created_at_time": "yesterday" })),
+            Error::invalid_metadata,
+        );
+        assert_error(
+            payloads_with_metadata(&key_pair, json!("not an object")),
+            Error::invalid_metadata,
+        );
+        assert_error(
+            construction_payloads(
+                CanisterId::from_u64(1),
+                &operations[..1],
+                &None,
+                &[key_pair.public_key()],
+                &currency,
+            ),
+            Error::invalid_operations,
+        );
+        // The public key of the sender is missing.
+        assert_error(
+            construction_payloads(CanisterId::from_u64(1), &operations, &None, &[], &currency),
+            Error::invalid_public_key,
+        );
+        // Amounts must be in the currency of the ledger.
+        let mut other_currency = operations.clone();
+        for operation in other_currency.iter_mut() {
+            operation.amount.as_mut().unwrap().currency.symbol = "ICP".to_string();
+        }
+        assert_error(
+            construction_payloads(
+                CanisterId::from_u64(1),
+                &other_currency,
+                &None,
+                &[key_pair.public_key()],
+                &currency,
+            ),
+            Error::invalid_operations,
+        );
+    }
+
+    #[test]
+    fn test_construction_combine_errors() {
+        let key_pair = secp256k1_key_pair(8);
+        let payloads = payloads_with_metadata(&key_pair, json!({})).unwrap();
+        let signatures = sign_all(&key_pair, &payloads);
+
+        assert_error(
+            construction_combine("not hex", &signatures),
+            Error::invalid_transaction,
+        );
+        assert_error(
+            construction_combine(&payloads.unsigned_transaction, &[]),
+            Error::invalid_transaction,
+        );
+
+        let mut wrong_signature_type = signatures.clone();
+        wrong_signature_type[0].signature_type = SignatureType::Ed25519;
+        assert_error(
+            construction_combine(&payloads.unsigned_transaction, &wrong_signature_type),
+            Error::invalid_transaction,
+        );
+
+        let mut invalid_signature = signatures.clone();
+        invalid_signature[0].hex_bytes = "not hex".to_string();
+        assert_error(
+            construction_combine(&payloads.unsigned_transaction, &invalid_signature),
+            Error::invalid_transaction,
+        );
+
+        let mut invalid_public_key = signatures;
+        invalid_public_key[0].public_key.hex_bytes = hex::encode([1; 10]);
+        assert_error(
+            construction_combine(&payloads.unsigned_transaction, &invalid_public_key),
+            Error::invalid_public_key,
+        );
+    }
+
+    #[test]
+    fn test_construction_parse_and_hash_errors() {
+        let currency = currency();
+        let key_pair = ed25519_key_pair(9);
+        let payloads = payloads_with_metadata(&key_pair, json!({})).unwrap();
+        let combined = construction_combine(
+            &payloads.unsigned_transaction,
+            &sign_all(&key_pair, &payloads),
+        )
+        .unwrap();
+
+        for signed in [false, true] {
+            assert_error(
+                construction_parse("not hex", signed, &currency),
+                Error::invalid_transaction,
+            );
+            assert_error(
+                construction_parse("0badc0de", signed, &currency),
+                Error::invalid_transaction,
+            );
+        }
+        // The transactions must be parsed as what they are.
+        assert_error(
+            construction_parse(&payloads.unsigned_transaction, true, &currency),
+            Error::invalid_transaction,
+        );
+        assert_error(
+            construction_parse(&combined.signed_transaction, false, &currency),
+            Error::invalid_transaction,
+        );
+
+        // Only signed transactions have a hash.
+        assert_error(
+            construction_hash(&payloads.unsigned_transaction),
+            Error::invalid_transaction,
+        );
+        assert_error(
+            construction_hash(
+                &SignedTransaction {
+                    envelope_pairs: vec![],
+                }
+                .to_hex(),
+            ),
+            Error::invalid_transaction,
+        );
+
+        // An update to an unsupported method cannot be parsed.
+        let mut unsigned_transaction =
+            UnsignedTransaction::from_str(&payloads.unsigned_transaction).unwrap();
+        unsigned_transaction.update.method_name = "icrc1_balance_of".to_string();
+        assert_error(
+            construction_parse(&unsigned_transaction.to_hex(), false, &currency),
+            Error::invalid_transaction,
+        );
+    }
+
+    #[test]
+    fn test_decode_reply() {
+        let transfer = LedgerCall::Transfer(TransferArg {
+            from_subaccount: None,
+            to: account(other_principal(1), None),
+            fee: None,
+            created_at_time: None,
+            memo: None,
+            amount: Nat::from(1),
+        });
+        let approve = LedgerCall::Approve(ApproveArgs {
+            from_subaccount: None,
+            spender: account(other_principal(1), None),
+            amount: Nat::from(1),
+            expected_allowance: None,
+            expires_at: None,
+            fee: None,
+            memo: None,
+            created_at_time: None,
+        });
+        let transfer_from = LedgerCall::TransferFrom(TransferFromArgs {
+            spender_subaccount: None,
+            from: account(other_principal(1), None),
+            to: account(other_principal(2), None),
+            amount: Nat::from(1),
+            fee: None,
+            memo: None,
+            created_at_time: None,
+        });
+
+        let ok = |block_index: u64| Ok::<Nat, TransferError>(Nat::from(block_index));
+        assert_eq!(
+            decode_reply(&transfer, &Encode!(&ok(5)).unwrap()).unwrap(),
+            Nat::from(5)
+        );
+        assert_eq!(
+            decode_reply(
+                &transfer,
+                &Encode!(&Err::<Nat, _>(TransferError::Duplicate {
+                    duplicate_of: Nat::from(3)
+                }))
+                .unwrap()
+            )
+            .unwrap(),
+            Nat::from(3)
+        );
+        assert_error(
+            decode_reply(
+                &transfer,
+                &Encode!(&Err::<Nat, _>(TransferError::TooOld)).unwrap(),
+            ),
+            Error::transaction_rejected,
+        );
+
+        assert_eq!(
+            decode_reply(
+                &approve,
+                &Encode!(&Ok::<Nat, ApproveError>(Nat::from(6))).unwrap()
+            )
+            .unwrap(),
+            Nat::from(6)
+        );
+        assert_eq!(
+            decode_reply(
+                &approve,
+                &Encode!(&Err::<Nat, _>(ApproveError::Duplicate {
+                    duplicate_of: Nat::from(4)
+                }))
+                .unwrap()
+            )
+            .unwrap(),
+            Nat::from(4)
+        );
+        assert_error(
+            decode_reply(
+                &approve,
+                &Encode!(&Err::<Nat, _>(ApproveError::AllowanceChanged {
+                    current_allowance: Nat::from(10)
+                }))
+                .unwrap(),
+            ),
+            Error::transaction_rejected,
+        );
+
+        assert_eq!(
+            decode_reply(
+                &transfer_from,
+                &Encode!(&Ok::<Nat, TransferFromError>(Nat::from(7))).unwrap()
+            )
+            .unwrap(),
+            Nat::from(7)
+        );
+        assert_eq!(
+            decode_reply(
+                &transfer_from,
+                &Encode!(&Err::<Nat, _>(TransferFromError::Duplicate {
+                    duplicate_of: Nat::from(2)
+                }))
+                .unwrap()
+            )
+            .unwrap(),
+            Nat::from(2)
+        );
+        assert_error(
+            decode_reply(
+                &transfer_from,
+                &Encode!(&Err::<Nat, _>(TransferFromError::InsufficientAllowance {
+                    allowance: Nat::from(0)
+                }))
+                .unwrap(),
+            ),
+            Error::transaction_rejected,
+        );
+
+        // The reply of one method cannot be decoded as the reply of another.
+        assert_error(
+            decode_reply(&transfer, &Encode!(&"not a reply").unwrap()),
+            Error::ledger_communication_error,
+        );
+    }
+
+    #[tokio::test]
+    async fn test_construction_submit_errors() {
+        let key_pair = ed25519_key_pair(10);
+
+        assert_error(
+            construction_submit(&test_agent(CanisterId::from_u64(1)), "not hex").await,
+            Error::invalid_transaction,
+        );
+
+        // None of the envelopes can be submitted after the ingress end.
+        let expired = payloads_with_metadata(
+            &key_pair,
+            json!({
+                "ingress_start": 1_000_000_000,
+                "ingress_end": 2_000_000_000,
+            }),
+        )
+        .unwrap();
+        let combined = construction_combine(
+            &expired.unsigned_transaction,
+            &sign_all(&key_pair, &expired),
+        )
+        .unwrap();
+        assert_error(
+            construction_submit(
+                &test_agent(CanisterId::from_u64(1)),
+                &combined.signed_transaction,
+            )
+            .await,
+            Error::transaction_expired,
+        );
+
+        // The transaction must be for the ledger that Rosetta serves.
+        let payloads = payloads_with_metadata(&key_pair, json!({})).unwrap();
+        let combined = construction_combine(
+            &payloads.unsigned_transaction,
+            &sign_all(&key_pair, &payloads),
+        )
+        .unwrap();
+        assert_error(
+            construction_submit(
+                &test_agent(CanisterId::from_u64(2)),
+                &combined.signed_transaction,
+            )
+            .await,
+            Error::invalid_transaction,
+        );
+    }
+}
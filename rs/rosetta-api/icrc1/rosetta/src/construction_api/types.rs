@@ -0,0 +1,223 @@
+use candid::{Decode, Encode, Nat, Principal};
+use ic_icrc1::{Operation as IcrcOperation, Transaction as IcrcTransaction};
+use ic_icrc1_tokens_u64::U64;
+use ic_ledger_core::timestamp::TimeStamp;
+use ic_types::messages::{
+    HttpCallContent, HttpCanisterUpdate, HttpReadStateContent, HttpRequestEnvelope,
+};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::TransferArg;
+use icrc_ledger_types::icrc2::approve::ApproveArgs;
+use icrc_ledger_types::icrc2::transfer_from::TransferFromArgs;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+type Tokens = U64;
+
+/// The metadata accepted by /construction/payloads.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConstructionPayloadsRequestMetadata {
+    /// The hex encoded memo of the transaction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+
+    /// The creation time of the transaction in nanoseconds since the Unix Epoch.
+    /// The current time is used by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at_time: Option<u64>,
+
+    /// The earliest time, in nanoseconds since the Unix Epoch, at which the
+    /// transaction can be submitted. The current time is used by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ingress_start: Option<u64>,
+
+    /// The latest time, in nanoseconds since the Unix Epoch, at which the
+    /// transaction can be submitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ingress_end: Option<u64>,
+}
+
+/// A call to one of the ledger endpoints that the Construction API supports.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LedgerCall {
+    Transfer(TransferArg),
+    Approve(ApproveArgs),
+    TransferFrom(TransferFromArgs),
+}
+
+fn tokens(amount: &Nat) -> Result<Tokens, String> {
+    Tokens::try_from(amount.clone()).map_err(|_| format!("Invalid amount {}", amount))
+}
+
+impl LedgerCall {
+    pub fn method_name(&self) -> &'static str {
+        match self {
+            LedgerCall::Transfer(_) => "icrc1_transfer",
+            LedgerCall::Approve(_) => "icrc2_approve",
+            LedgerCall::TransferFrom(_) => "icrc2_transfer_from",
+        }
+    }
+
+    pub fn encode_arg(&self) -> Result<Vec<u8>, String> {
+        match self {
+            LedgerCall::Transfer(arg) => Encode!(arg),
+            LedgerCall::Approve(arg) => Encode!(arg),
+            LedgerCall::TransferFrom(arg) => Encode!(arg),
+        }
+        .map_err(|e| {
+            format!(
+                "Failed to encode the argument of {}: {}",
+                self.method_name(),
+                e
+            )
+        })
+    }
+
+    pub fn decode(method_name: &str, arg: &[u8]) -> Result<Self, String> {
+        let decode_error =
+            |e: candid::Error| format!("Failed to decode the argument of {}: {}", method_name, e);
+        match method_name {
+            "icrc1_transfer" => Decode!(arg, TransferArg)
+                .map(LedgerCall::Transfer)
+                .map_err(decode_error),
+            "icrc2_approve" => Decode!(arg, ApproveArgs)
+                .map(LedgerCall::Approve)
+                .map_err(decode_error),
+            "icrc2_transfer_from" => Decode!(arg, TransferFromArgs)
+                .map(LedgerCall::TransferFrom)
+                .map_err(decode_error),
+            _ => Err(format!("Unsupported ledger method {}", method_name)),
+        }
+    }
+
+    /// Returns the transaction that the ledger records when `caller` makes this call.
+    ///
+    /// The ledger records approvals without an expiration with its default
+    /// expiration and transfers from or to the minting account as mints or
+    /// burns, so the transactions of such calls differ from the recorded ones.
+    pub fn to_transaction(&self, caller: Principal) -> Result<IcrcTransaction<Tokens>, String> {
+        let (operation, created_at_time, memo) = match self {
+            LedgerCall::Transfer(arg) => (
+                IcrcOperation::Transfer {
+                    from: Account {
+                        owner: caller,
+                        subaccount: arg.from_subaccount,
+                    },
+                    to: arg.to,
+                    spender: None,
+                    amount: tokens(&arg.amount)?,
+                    fee: arg.fee.as_ref().map(tokens).transpose()?,
+                },
+                arg.created_at_time,
+                arg.memo.clone(),
+            ),
+            LedgerCall::Approve(arg) => (
+                IcrcOperation::Approve {
+                    from: Account {
+                        owner: caller,
+                        subaccount: arg.from_subaccount,
+                    },
+                    spender: arg.spender,
+                    amount: tokens(&arg.amount)?,
+                    expected_allowance: arg.expected_allowance.as_ref().map(tokens).transpose()?,
+                    expires_at: arg.expires_at.map(TimeStamp::from_nanos_since_unix_epoch),
+                    fee: arg.fee.as_ref().map(tokens).transpose()?,
+                },
+                arg.created_at_time,
+                arg.memo.clone(),
+            ),
+            LedgerCall::TransferFrom(arg) => (
+                IcrcOperation::Transfer {
+                    from: arg.from,
+                    to: arg.to,
+                    spender: Some(Account {
+                        owner: caller,
+                        subaccount: arg.spender_subaccount,
+                    }),
+                    amount: tokens(&arg.amount)?,
+                    fee: arg.fee.as_ref().map(tokens).transpose()?,
+                },
+                arg.created_at_time,
+                arg.memo.clone(),
+            ),
+        };
+        Ok(IcrcTransaction {
+            operation,
+            created_at_time,
+            memo,
+        })
+    }
+}
+
+fn to_hex_cbor<T: Serialize>(value: &T) -> String {
+    hex::encode(serde_cbor::to_vec(value).expect("failed to serialize a transaction"))
+}
+
+fn from_hex_cbor<T: for<'a> Deserialize<'a>>(value: &str) -> Result<T, String> {
+    let bytes = hex::decode(value).map_err(|e| format!("Invalid hex encoding: {}", e))?;
+    serde_cbor::from_slice(&bytes).map_err(|e| format!("Invalid CBOR encoding: {}", e))
+}
+
+/// The transaction returned by /construction/payloads.
+/// The update call is signed once for every ingress expiry, so that the
+/// signed transaction can be submitted at any time between the ingress start
+/// and the ingress end.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct UnsignedTransaction {
+    pub update: HttpCanisterUpdate,
+    pub ingress_expiries: Vec<u64>,
+}
+
+impl UnsignedTransaction {
+    pub fn to_hex(&self) -> String {
+        to_hex_cbor(self)
+    }
+}
+
+impl FromStr for UnsignedTransaction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_hex_cbor(s)
+    }
+}
+
+/// A signed update call together with the signed read_state request used to
+/// poll for its result.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct EnvelopePair {
+    pub call_envelope: HttpRequestEnvelope<HttpCallContent>,
+    pub read_state_envelope: HttpRequestEnvelope<HttpReadStateContent>,
+}
+
+impl EnvelopePair {
+    pub fn update(&self) -> &HttpCanisterUpdate {
+        let HttpCallContent::Call { update } = &self.call_envelope.content;
+        update
+    }
+}
+
+/// The transaction returned by /construction/combine, one envelope pair per
+/// ingress expiry of the unsigned transaction.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SignedTransaction {
+    pub envelope_pairs: Vec<EnvelopePair>,
+}
+
+impl SignedTransaction {
+    pub fn to_hex(&self) -> String {
+        to_hex_cbor(self)
+    }
+}
+
+impl FromStr for SignedTransaction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let signed_transaction: Self = from_hex_cbor(s)?;
+        if signed_transaction.envelope_pairs.is_empty() {
+            return Err("The signed transaction contains no envelopes".to_string());
+        }
+        Ok(signed_transaction)
+    }
+}
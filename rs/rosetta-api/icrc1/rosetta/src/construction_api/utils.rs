@@ -0,0 +1,278 @@
+use crate::common::types::{
+    AccountIdentifier, Amount, ApproveMetadata, Currency, CurveType, Operation, PublicKey,
+    SignatureType,
+};
+use crate::common::utils::conversions::OperationType;
+use crate::construction_api::types::LedgerCall;
+use candid::{Nat, Principal};
+use ic_crypto_tree_hash::Path;
+use ic_types::crypto::DOMAIN_IC_REQUEST;
+use ic_types::messages::{HttpCanisterUpdate, HttpReadState, MessageId};
+use ic_types::time::{current_time, Time};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::{Memo, TransferArg};
+use icrc_ledger_types::icrc2::approve::ApproveArgs;
+use icrc_ledger_types::icrc2::transfer_from::TransferFromArgs;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Returns the DER encoding of the public key, which is the form the IC
+/// expects in request envelopes.
+pub fn der_encode_public_key(public_key: &PublicKey) -> Result<Vec<u8>, String> {
+    let bytes = hex::decode(&public_key.hex_bytes)
+        .map_err(|e| format!("Invalid public key {}: {}", public_key.hex_bytes, e))?;
+    match public_key.curve_type {
+        CurveType::Edwards25519 => {
+            if bytes.len() != 32 {
+                return Err(format!(
+                    "Invalid Ed25519 public key: expected 32 bytes, got {}",
+                    bytes.len()
+                ));
+            }
+            Ok(ic_canister_client_sender::ed25519_public_key_to_der(bytes))
+        }
+        CurveType::Secp256K1 => ic_crypto_ecdsa_secp256k1::PublicKey::deserialize_sec1(&bytes)
+            .map(|public_key| public_key.serialize_der())
+            .map_err(|e| format!("Invalid secp256k1 public key: {:?}", e)),
+        curve_type => Err(format!("Curve type {:?} is not supported", curve_type)),
+    }
+}
+
+/// Returns the self-authenticating principal of the public key.
+pub fn principal_from_public_key(public_key: &PublicKey) -> Result<Principal, String> {
+    der_encode_public_key(public_key).map(Principal::self_authenticating)
+}
+
+/// Returns the type of the signatures made with keys of the given curve.
+pub fn signature_type(curve_type: CurveType) -> Result<SignatureType, String> {
+    match curve_type {
+        CurveType::Edwards25519 => Ok(SignatureType::Ed25519),
+        CurveType::Secp256K1 => Ok(SignatureType::Ecdsa),
+        curve_type => Err(format!("Curve type {:?} is not supported", curve_type)),
+    }
+}
+
+/// Returns the bytes that the sender of a request signs.
+pub fn make_sig_data(message_id: &MessageId) -> Vec<u8> {
+    let mut sig_data = vec![];
+    sig_data.extend_from_slice(DOMAIN_IC_REQUEST);
+    sig_data.extend_from_slice(message_id.as_bytes());
+    sig_data
+}
+
+/// Returns the read_state request that polls for the result of the update call.
+pub fn make_read_state_from_update(update: &HttpCanisterUpdate) -> HttpReadState {
+    let path = Path::new(vec!["request_status".into(), update.id().into()]);
+    HttpReadState {
+        sender: update.sender.clone(),
+        paths: vec![path],
+        nonce: None,
+        ingress_expiry: update.ingress_expiry,
+    }
+}
+
+/// Returns the duration between the ingress expiry of a request and the
+/// earliest time at which the IC accepts it.
+pub fn ingress_validity() -> Duration {
+    ic_constants::MAX_INGRESS_TTL.saturating_sub(ic_constants::PERMITTED_DRIFT)
+}
+
+/// Returns ingress expiries such that one of them is valid at any time
+/// between `ingress_start` and `ingress_end`.
+pub fn ingress_expiries(ingress_start: Option<u64>, ingress_end: Option<u64>) -> Vec<u64> {
+    let interval = ingress_validity() - Duration::from_secs(120);
+    let ingress_start = ingress_start
+        .map(Time::from_nanos_since_unix_epoch)
+        .unwrap_or_else(current_time);
+    let ingress_end = ingress_end
+        .map(Time::from_nanos_since_unix_epoch)
+        .unwrap_or_else(|| ingress_start + interval);
+
+    let mut ingress_expiries = vec![];
+    let mut now = ingress_start;
+    while now < ingress_end {
+        ingress_expiries.push((now + ingress_validity()).as_nanos_since_unix_epoch());
+        now += interval;
+    }
+    ingress_expiries
+}
+
+fn operation_account(operation: &Operation) -> Result<Account, String> {
+    let account_identifier = operation.account.as_ref().ok_or_else(|| {
+        format!(
+            "Operation {} has no account",
+            operation.operation_identifier.index
+        )
+    })?;
+    Account::try_from(account_identifier)
+}
+
+/// Returns the value of the amount in the smallest unit of the currency.
+fn amount_value(amount: &Amount, currency: &Currency) -> Result<i128, String> {
+    if amount.currency != *currency {
+        return Err(format!(
+            "Expected an amount in {}, got {}",
+            currency.symbol, amount.currency.symbol
+        ));
+    }
+    amount
+        .value
+        .parse::<i128>()
+        .map_err(|e| format!("Invalid amount {}: {}", amount.value, e))
+}
+
+fn operation_amount(operation: &Operation, currency: &Currency) -> Result<i128, String> {
+    let amount = operation.amount.as_ref().ok_or_else(|| {
+        format!(
+            "Operation {} has no amount",
+            operation.operation_identifier.index
+        )
+    })?;
+    amount_value(amount, currency)
+}
+
+fn nat(value: i128) -> Result<Nat, String> {
+    u64::try_from(value)
+        .map(Nat::from)
+        .map_err(|_| format!("Amount {} is out of range", value))
+}
+
+/// Converts the operations of a Construction API request into a ledger call.
+/// Returns the account of the caller together with the call.
+///
+/// A transfer consists of a TRANSFER operation debiting the sender and one
+/// crediting the receiver. The debit may name a spender in its metadata, in
+/// which case the transfer is made with the spender's allowance. An approval
+/// consists of an APPROVE operation. Both can include a FEE operation debiting
+/// the sender to set the fee explicitly.
+pub fn ledger_call_from_operations(
+    operations: &[Operation],
+    memo: Option<Memo>,
+    created_at_time: Option<u64>,
+    currency: &Currency,
+) -> Result<(Account, LedgerCall), String> {
+    let mut transfers = vec![];
+    let mut approves = vec![];
+    let mut fees = vec![];
+    for operation in operations {
+        match OperationType::from_str(&operation.type_)? {
+            OperationType::Transfer => transfers.push(operation),
+            OperationType::Approve => approves.push(operation),
+            OperationType::Fee => fees.push(operation),
+            operation_type @ (OperationType::Mint | OperationType::Burn) => {
+                return Err(format!(
+                    "{} operations are not supported by the Construction API",
+                    operation_type.as_str()
+                ))
+            }
+        }
+    }
+
+    let fee = match fees.as_slice() {
+        [] => None,
+        [fee] => {
+            let value = operation_amount(fee, currency)?;
+            if value > 0 {
+                return Err("The FEE operation must debit the sender".to_string());
+            }
+            Some((operation_account(fee)?, nat(-value)?))
+        }
+        _ => return Err("At most one FEE operation is supported".to_string()),
+    };
+    let check_fee_payer = |from: &Account| match &fee {
+        Some((payer, _)) if payer != from => {
+            Err("The FEE operation must debit the sender".to_string())
+        }
+        _ => Ok(()),
+    };
+    let fee = fee.as_ref().map(|(_, fee)| fee.clone());
+
+    match (transfers.as_slice(), approves.as_slice()) {
+        ([first, second], []) => {
+            let (first_value, second_value) = (
+                operation_amount(first, currency)?,
+                operation_amount(second, currency)?,
+            );
+            let (debit, credit, value) = match (first_value, second_value) {
+                (debit, credit) if debit < 0 && debit == -credit => (first, second, credit),
+                (credit, debit) if debit < 0 && debit == -credit => (second, first, credit),
+                _ => {
+                    return Err(
+                        "Expected a TRANSFER debit and a TRANSFER credit of the same amount"
+                            .to_string(),
+                    )
+                }
+            };
+            let from = operation_account(debit)?;
+            let to = operation_account(credit)?;
+            check_fee_payer(&from)?;
+            let spender = match debit
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.get("spender"))
+            {
+                Some(spender) => {
+                    let spender: AccountIdentifier = serde_json::from_value(spender.clone())
+                        .map_err(|e| format!("Invalid spender: {}", e))?;
+                    Some(Account::try_from(&spender)?)
+                }
+                None => None,
+            };
+            Ok(match spender {
+                None => (
+                    from,
+                    LedgerCall::Transfer(TransferArg {
+                        from_subaccount: from.subaccount,
+                        to,
+                        fee,
+                        created_at_time,
+                        memo,
+                        amount: nat(value)?,
+                    }),
+                ),
+                Some(spender) => (
+                    spender,
+                    LedgerCall::TransferFrom(TransferFromArgs {
+                        spender_subaccount: spender.subaccount,
+                        from,
+                        to,
+                        amount: nat(value)?,
+                        fee,
+                        memo,
+                        created_at_time,
+                    }),
+                ),
+            })
+        }
+        ([], [approve]) => {
+            let from = operation_account(approve)?;
+            check_fee_payer(&from)?;
+            let metadata: ApproveMetadata = serde_json::from_value(
+                approve
+                    .metadata
+                    .clone()
+                    .ok_or_else(|| "The APPROVE operation has no metadata".to_string())?,
+            )
+            .map_err(|e| format!("Invalid APPROVE metadata: {}", e))?;
+            Ok((
+                from,
+                LedgerCall::Approve(ApproveArgs {
+                    from_subaccount: from.subaccount,
+                    spender: Account::try_from(&metadata.spender)?,
+                    amount: nat(amount_value(&metadata.allowance, currency)?)?,
+                    expected_allowance: metadata
+                        .expected_allowance
+                        .map(|expected_allowance| {
+                            amount_value(&expected_allowance, currency).and_then(nat)
+                        })
+                        .transpose()?,
+                    expires_at: metadata.expires_at,
+                    fee,
+                    memo,
+                    created_at_time,
+                }),
+            ))
+        }
+        _ => Err("Expected either two TRANSFER operations or one APPROVE operation".to_string()),
+    }
+}
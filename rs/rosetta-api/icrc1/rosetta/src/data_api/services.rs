@@ -0,0 +1,1008 @@
+use crate::common::storage::{
+    storage_client::StorageClient,
+    types::{BlockSearchFilter, RosettaBlock},
+};
+use crate::common::types::{
+    AccountBalanceResponse, AccountIdentifier, Amount, BlockIdentifier, BlockResponse,
+    BlockTransaction, BlockTransactionResponse, Currency, Error, NetworkStatusResponse, Operator,
+    PartialBlockIdentifier, SearchTransactionsRequest, SearchTransactionsResponse,
+    TransactionIdentifier,
+};
+use crate::common::utils::conversions::{
+    convert_timestamp_to_millis, icrc1_rosetta_block, icrc1_rosetta_transaction,
+    rosetta_block_identifier, OperationType, STATUS_COMPLETED,
+};
+use candid::Principal;
+use ic_icrc1_tokens_u64::U64;
+use icrc_ledger_types::icrc1::account::Account;
+use serde_bytes::ByteBuf;
+use std::str::FromStr;
+
+type Tokens = U64;
+
+/// The maximum number of transactions returned by a single /search/transactions request.
+pub const MAX_TRANSACTIONS_PER_SEARCH: u64 = 10_000;
+
+fn storage_error(e: anyhow::Error) -> Error {
+    Error::storage_error(e.to_string())
+}
+
+fn decode_hash(hash: &str) -> Result<ByteBuf, String> {
+    hex::decode(hash)
+        .map(ByteBuf::from)
+        .map_err(|e| format!("Invalid hash {}: {}", hash, e))
+}
+
+/// Finds the block matching the partial block identifier.
+/// Returns the block with the highest index if neither the index nor the hash is set.
+fn find_block(
+    storage_client: &StorageClient,
+    block_identifier: &PartialBlockIdentifier,
+) -> Result<RosettaBlock, Error> {
+    let block = match (block_identifier.index, &block_identifier.hash) {
+        (None, None) => storage_client
+            .get_block_with_highest_block_idx()
+            .map_err(storage_error)?,
+        (Some(index), None) => storage_client
+            .get_block_at_idx(index)
+            .map_err(storage_error)?,
+        (None, Some(hash)) => storage_client
+            .get_block_by_hash(decode_hash(hash).map_err(Error::invalid_block_identifier)?)
+            .map_err(storage_error)?,
+        (Some(index), Some(hash)) => {
+            let block = storage_client
+                .get_block_at_idx(index)
+                .map_err(storage_error)?;
+            if let Some(block) = &block {
+                if hex::encode(&block.block_hash) != hash.to_lowercase() {
+                    return Err(Error::invalid_block_identifier(format!(
+                        "The hash of block {} is {}, not {}",
+                        index,
+                        hex::encode(&block.block_hash),
+                        hash
+                    )));
+                }
+            }
+            block
+        }
+    };
+    block.ok_or_else(|| {
+        Error::unable_to_find_block(format!(
+            "No block matching {} was found",
+            serde_json::to_string(block_identifier).unwrap()
+        ))
+    })
+}
+
+fn rosetta_block_transaction(
+    storage_client: &StorageClient,
+    rosetta_block: &RosettaBlock,
+    currency: &Currency,
+) -> Result<BlockTransaction, Error> {
+    let fee_collector = rosetta_block
+        .get_block()
+        .and_then(|block| storage_client.get_fee_collector_from_block(&block))
+        .map_err(|e| Error::failed_to_build_block_response(e.to_string()))?;
+    let transaction = icrc1_rosetta_transaction(rosetta_block, fee_collector, currency)
+        .map_err(|e| Error::failed_to_build_block_response(e.to_string()))?;
+    Ok(BlockTransaction {
+        block_identifier: rosetta_block_identifier(rosetta_block),
+        transaction,
+    })
+}
+
+pub fn network_status(storage_client: &StorageClient) -> Result<NetworkStatusResponse, Error> {
+    let current_block = storage_client
+        .get_block_with_highest_block_idx()
+        .map_err(storage_error)?
+        .ok_or_else(|| Error::unable_to_find_block("No blocks have been synced yet".to_string()))?;
+    let genesis_block = storage_client
+        .get_block_at_idx(0)
+        .map_err(storage_error)?
+        .ok_or_else(|| {
+            Error::unable_to_find_block("The genesis block has not been synced yet".to_string())
+        })?;
+    let current_block_timestamp = current_block
+        .get_block()
+        .map_err(|e| Error::failed_to_build_block_response(e.to_string()))?
+        .timestamp;
+    Ok(NetworkStatusResponse {
+        current_block_identifier: rosetta_block_identifier(&current_block),
+        current_block_timestamp: convert_timestamp_to_millis(current_block_timestamp),
+        genesis_block_identifier: rosetta_block_identifier(&genesis_block),
+        oldest_block_identifier: Some(rosetta_block_identifier(&genesis_block)),
+        sync_status: None,
+        peers: vec![],
+    })
+}
+
+pub fn block(
+    storage_client: &StorageClient,
+    block_identifier: &PartialBlockIdentifier,
+    currency: &Currency,
+) -> Result<BlockResponse, Error> {
+    let rosetta_block = find_block(storage_client, block_identifier)?;
+    let fee_collector = rosetta_block
+        .get_block()
+        .and_then(|block| storage_client.get_fee_collector_from_block(&block))
+        .map_err(|e| Error::failed_to_build_block_response(e.to_string()))?;
+    let block = icrc1_rosetta_block(&rosetta_block, fee_collector, currency)
+        .map_err(|e| Error::failed_to_build_block_response(e.to_string()))?;
+    Ok(BlockResponse {
+        block: Some(block),
+        other_transactions: None,
+    })
+}
+
+pub fn block_transaction(
+    storage_client: &StorageClient,
+    block_identifier: &BlockIdentifier,
+    transaction_identifier: &TransactionIdentifier,
+    currency: &Currency,
+) -> Result<BlockTransactionResponse, Error> {
+    let rosetta_block = find_block(
+        storage_client,
+        &PartialBlockIdentifier {
+            index: Some(block_identifier.index),
+            hash: Some(block_identifier.hash.clone()),
+        },
+    )?;
+    if hex::encode(&rosetta_block.transaction_hash) != transaction_identifier.hash.to_lowercase() {
+        return Err(Error::invalid_transaction_identifier(format!(
+            "Block {} does not contain the transaction {}",
+            block_identifier.index, transaction_identifier.hash
+        )));
+    }
+    let BlockTransaction { transaction, .. } =
+        rosetta_block_transaction(storage_client, &rosetta_block, currency)?;
+    Ok(BlockTransactionResponse { transaction })
+}
+
+pub fn account_balance(
+    storage_client: &StorageClient,
+    account_identifier: &AccountIdentifier,
+    block_identifier: &Option<PartialBlockIdentifier>,
+    currency: &Currency,
+) -> Result<AccountBalanceResponse, Error> {
+    let account = Account::try_from(account_identifier).map_err(Error::parsing_unsuccessful)?;
+    let rosetta_block = find_block(
+        storage_client,
+        &block_identifier.clone().unwrap_or_default(),
+    )?;
+
+    let highest_balance_block_idx = storage_client
+        .get_highest_block_idx_in_account_balance_table()
+        .map_err(storage_error)?;
+    if highest_balance_block_idx.map_or(true, |idx| idx < rosetta_block.index) {
+        return Err(Error::unable_to_find_account_balance(format!(
+            "The account balances at block {} have not been computed yet",
+            rosetta_block.index
+        )));
+    }
+
+    let balance = storage_client
+        .get_account_balance_at_block_idx(&account, rosetta_block.index)
+        .map_err(storage_error)?
+        .unwrap_or(Tokens::new(0));
+    Ok(AccountBalanceResponse {
+        block_identifier: rosetta_block_identifier(&rosetta_block),
+        balances: vec![Amount {
+            value: balance.to_string(),
+            currency: currency.clone(),
+            metadata: None,
+        }],
+        metadata: None,
+    })
+}
+
+pub fn search_transactions(
+    storage_client: &StorageClient,
+    request: &SearchTransactionsRequest,
+    currency: &Currency,
+) -> Result<SearchTransactionsResponse, Error> {
+    let empty_response = SearchTransactionsResponse {
+        transactions: vec![],
+        total_count: 0,
+        next_offset: None,
+    };
+
+    if request.operator == Some(Operator::Or) {
+        return Err(Error::invalid_search_transactions_request(
+            "Only the and operator is supported".to_string(),
+        ));
+    }
+    let to_u64 = |value: Option<i64>, name: &str| -> Result<Option<u64>, Error> {
+        value
+            .map(|value| {
+                u64::try_from(value).map_err(|_| {
+                    Error::invalid_search_transactions_request(format!(
+                        "{} must not be negative, got {}",
+                        name, value
+                    ))
+                })
+            })
+            .transpose()
+    };
+    let max_block_idx = to_u64(request.max_block, "max_block")?;
+    let offset = to_u64(request.offset, "offset")?.unwrap_or(0);
+    let limit = to_u64(request.limit, "limit")?
+        .unwrap_or(MAX_TRANSACTIONS_PER_SEARCH)
+        .min(MAX_TRANSACTIONS_PER_SEARCH);
+
+    // All stored transactions succeeded and are denominated in the currency of the ledger.
+    if request.success == Some(false)
+        || request
+            .status
+            .as_ref()
+            .map_or(false, |status| status != STATUS_COMPLETED)
+        || request
+            .currency
+            .as_ref()
+            .map_or(false, |request_currency| request_currency != currency)
+    {
+        return Ok(empty_response);
+    }
+
+    let operation_type = match &request.type_ {
+        Some(type_) => match OperationType::from_str(type_)
+            .map_err(Error::invalid_search_transactions_request)?
+        {
+            OperationType::Mint => Some("mint".to_string()),
+            OperationType::Burn => Some("burn".to_string()),
+            OperationType::Transfer => Some("transfer".to_string()),
+            unsupported => {
+                return Err(Error::invalid_search_transactions_request(format!(
+                    "Searching for operations of type {} is not supported",
+                    unsupported.as_str()
+                )))
+            }
+        },
+        None => None,
+    };
+
+    let transaction_hash = request
+        .transaction_identifier
+        .as_ref()
+        .map(|transaction_identifier| decode_hash(&transaction_identifier.hash))
+        .transpose()
+        .map_err(Error::invalid_transaction_identifier)?;
+
+    let account = request
+        .account_identifier
+        .as_ref()
+        .map(Account::try_from)
+        .transpose()
+        .map_err(Error::parsing_unsuccessful)?;
+    let address = request
+        .address
+        .as_ref()
+        .map(|address| {
+            Principal::from_text(address).map_err(|e| {
+                Error::parsing_unsuccessful(format!("Invalid address {}: {}", address, e))
+            })
+        })
+        .transpose()?;
+    let (principal, subaccount) = match (account, address) {
+        (Some(account), Some(address)) if account.owner != address => return Ok(empty_response),
+        (Some(account), _) => (Some(account.owner), Some(*account.effective_subaccount())),
+        (None, address) => (address, None),
+    };
+
+    let filter = BlockSearchFilter {
+        transaction_hash,
+        principal,
+        subaccount,
+        operation_type,
+        max_block_idx,
+    };
+    let (rosetta_blocks, total_count) = storage_client
+        .search_blocks(&filter, offset, limit)
+        .map_err(storage_error)?;
+
+    let next_offset = offset + rosetta_blocks.len() as u64;
+    let transactions = rosetta_blocks
+        .iter()
+        .map(|rosetta_block| rosetta_block_transaction(storage_client, rosetta_block, currency))
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok(SearchTransactionsResponse {
+        transactions,
+        total_count: total_count as i64,
+        next_offset: (next_offset < total_count).then_some(next_offset as i64),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::types::{NetworkIdentifier, SubAccountIdentifier};
+    use ic_base_types::CanisterId;
+    use ic_icrc1::{Block, Operation, Transaction};
+    use ic_ledger_core::block::BlockType;
+
+    const SUBACCOUNT: [u8; 32] = [1; 32];
+
+    fn currency() -> Currency {
+        Currency {
+            symbol: "XTST".to_string(),
+            decimals: 8,
+            metadata: None,
+        }
+    }
+
+    fn account(id: u8) -> Account {
+        Account {
+            owner: Principal::from_slice(&[id]),
+            subaccount: None,
+        }
+    }
+
+    fn index(index: u64) -> PartialBlockIdentifier {
+        PartialBlockIdentifier {
+            index: Some(index),
+            hash: None,
+        }
+    }
+
+    fn hash(hash: &str) -> PartialBlockIdentifier {
+        PartialBlockIdentifier {
+            index: None,
+            hash: Some(hash.to_string()),
+        }
+    }
+
+    // Checks that the result is an error built by the given constructor.
+    fn assert_error<T: std::fmt::Debug>(result: Result<T, Error>, expected: fn(String) -> Error) {
+        let error = result.unwrap_err();
+        let expected = expected(String::new());
+        assert_eq!(
+            (error.code, &error.message),
+            (expected.code, &expected.message),
+            "unexpected error {:?}",
+            error
+        );
+    }
+
+    fn block_with_parent(
+        parent: Option<&Block<Tokens>>,
+        operation: Operation<Tokens>,
+        timestamp: u64,
+    ) -> Block<Tokens> {
+        Block::<Tokens> {
+            parent_hash: parent.map(|parent| Block::<Tokens>::block_hash(&parent.clone().encode())),
+            transaction: Transaction {
+                operation,
+                created_at_time: Some(timestamp),
+                memo: None,
+            },
+            effective_fee: None,
+            timestamp,
+            fee_collector: None,
+            fee_collector_block_index: None,
+        }
+    }
+
+    // Stores the following blocks:
+    //  0. a mint of 1_000 to account 1,
+    //  1. a transfer of 100 from account 1 to account 2 with a fee of 10,
+    //  2. a transfer of 50 from account 2 to a subaccount of account 2 with a fee of 10,
+    //  3. a burn of 20 from account 1.
+    fn store_blocks(storage_client: &StorageClient) {
+        let mint = block_with_parent(
+            None,
+            Operation::Mint {
+                to: account(1),
+                amount: Tokens::new(1_000),
+            },
+            1_000_000_000,
+        );
+        let transfer = block_with_parent(
+            Some(&mint),
+            Operation::Transfer {
+                from: account(1),
+                to: account(2),
+                spender: None,
+                amount: Tokens::new(100),
+                fee: Some(Tokens::new(10)),
+            },
+            2_000_000_000,
+        );
+        let transfer_to_subaccount = block_with_parent(
+            Some(&transfer),
+            Operation::Transfer {
+                from: account(2),
+                to: Account {
+                    subaccount: Some(SUBACCOUNT),
+                    ..account(2)
+                },
+                spender: None,
+                amount: Tokens::new(50),
+                fee: Some(Tokens::new(10)),
+            },
+            3_000_000_000,
+        );
+        let burn = block_with_parent(
+            Some(&transfer_to_subaccount),
+            Operation::Burn {
+                from: account(1),
+                spender: None,
+                amount: Tokens::new(20),
+            },
+            4_000_000_000,
+        );
+        storage_client
+            .store_blocks(
+                [mint, transfer, transfer_to_subaccount, burn]
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, block)| {
+                        RosettaBlock::from_icrc_ledger_block(block, idx as u64).unwrap()
+                    })
+                    .collect(),
+            )
+            .unwrap();
+    }
+
+    fn storage_with_blocks() -> StorageClient {
+        let storage_client = StorageClient::new_in_memory().unwrap();
+        store_blocks(&storage_client);
+        storage_client.update_account_balances().unwrap();
+        storage_client
+    }
+
+    fn get_block(
+        storage_client: &StorageClient,
+        block_identifier: PartialBlockIdentifier,
+    ) -> crate::common::types::Block {
+        block(storage_client, &block_identifier, &currency())
+            .unwrap()
+            .block
+            .unwrap()
+    }
+
+    #[test]
+    fn test_network_status() {
+        let storage_client = storage_with_blocks();
+        let status = network_status(&storage_client).unwrap();
+        assert_eq!(status.current_block_identifier.index, 3);
+        assert_eq!(status.current_block_timestamp, 4_000);
+        assert_eq!(status.genesis_block_identifier.index, 0);
+        assert_eq!(
+            status.oldest_block_identifier,
+            Some(status.genesis_block_identifier.clone())
+        );
+
+        let empty_storage = StorageClient::new_in_memory().unwrap();
+        assert_error(network_status(&empty_storage), Error::unable_to_find_block);
+    }
+
+    #[test]
+    fn test_block() {
+        let storage_client = storage_with_blocks();
+        let genesis = get_block(&storage_client, index(0));
+        assert_eq!(genesis.parent_block_identifier, genesis.block_identifier);
+        assert_eq!(genesis.timestamp, 1_000);
+
+        let transfer = get_block(&storage_client, index(1));
+        assert_eq!(transfer.block_identifier.index, 1);
+        assert_eq!(transfer.parent_block_identifier, genesis.block_identifier);
+        assert_eq!(transfer.transactions.len(), 1);
+        let operations: Vec<_> = transfer.transactions[0]
+            .operations
+            .iter()
+            .map(|op| {
+                (
+                    op.type_.as_str(),
+                    op.account.as_ref().unwrap().address.clone(),
+                    op.amount.as_ref().unwrap().value.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            operations,
+            vec![
+                ("TRANSFER", account(1).owner.to_text(), "-100"),
+                ("TRANSFER", account(2).owner.to_text(), "100"),
+                ("FEE", account(1).owner.to_text(), "-10"),
+            ]
+        );
+
+        // Without an index or a hash, the latest block is returned.
+        let latest = get_block(&storage_client, PartialBlockIdentifier::default());
+        assert_eq!(latest.block_identifier.index, 3);
+        assert_eq!(latest, get_block(&storage_client, index(3)));
+        let burn = &latest.transactions[0].operations;
+        assert_eq!(burn.len(), 1);
+        assert_eq!(burn[0].type_, "BURN");
+        assert_eq!(burn[0].amount.as_ref().unwrap().value, "-20");
+
+        // The hash can be given with or without the index and in any case.
+        assert_eq!(
+            get_block(&storage_client, hash(&transfer.block_identifier.hash)),
+            transfer
+        );
+        assert_eq!(
+            get_block(
+                &storage_client,
+                PartialBlockIdentifier {
+                    index: Some(1),
+                    hash: Some(transfer.block_identifier.hash.to_uppercase()),
+                }
+            ),
+            transfer
+        );
+    }
+
+    #[test]
+    fn test_block_errors() {
+        let storage_client = storage_with_blocks();
+        let transfer = get_block(&storage_client, index(1));
+
+        assert_error(
+            block(&storage_client, &index(4), &currency()),
+            Error::unable_to_find_block,
+        );
+        assert_error(
+            block(&storage_client, &hash(&"00".repeat(32)), &currency()),
+            Error::unable_to_find_block,
+        );
+        assert_error(
+            block(&storage_client, &hash("not a hash"), &currency()),
+            Error::invalid_block_identifier,
+        );
+        // The index and the hash must refer to the same block.
+        assert_error(
+            block(
+                &storage_client,
+                &PartialBlockIdentifier {
+                    index: Some(0),
+                    hash: Some(transfer.block_identifier.hash.clone()),
+                },
+                &currency(),
+            ),
+            Error::invalid_block_identifier,
+        );
+
+        let empty_storage = StorageClient::new_in_memory().unwrap();
+        assert_error(
+            block(
+                &empty_storage,
+                &PartialBlockIdentifier::default(),
+                &currency(),
+            ),
+            Error::unable_to_find_block,
+        );
+    }
+
+    #[test]
+    fn test_block_transaction() {
+        let storage_client = storage_with_blocks();
+        for idx in 0..4 {
+            let block = get_block(&storage_client, index(idx));
+            let transaction = block_transaction(
+                &storage_client,
+                &block.block_identifier,
+                &block.transactions[0].transaction_identifier,
+                &currency(),
+            )
+            .unwrap()
+            .transaction;
+            assert_eq!(transaction, block.transactions[0]);
+        }
+    }
+
+    #[test]
+    fn test_block_transaction_errors() {
+        let storage_client = storage_with_blocks();
+        let mint = get_block(&storage_client, index(0));
+        let transfer = get_block(&storage_client, index(1));
+
+        // The transaction is in a different block.
+        assert_error(
+            block_transaction(
+                &storage_client,
+                &transfer.block_identifier,
+                &mint.transactions[0].transaction_identifier,
+                &currency(),
+            ),
+            Error::invalid_transaction_identifier,
+        );
+        // The hash does not match the index of the block.
+        assert_error(
+            block_transaction(
+                &storage_client,
+                &BlockIdentifier {
+                    index: 0,
+                    hash: transfer.block_identifier.hash.clone(),
+                },
+                &transfer.transactions[0].transaction_identifier,
+                &currency(),
+            ),
+            Error::invalid_block_identifier,
+        );
+        assert_error(
+            block_transaction(
+                &storage_client,
+                &BlockIdentifier {
+                    index: 4,
+                    hash: transfer.block_identifier.hash.clone(),
+                },
+                &transfer.transactions[0].transaction_identifier,
+                &currency(),
+            ),
+            Error::unable_to_find_block,
+        );
+    }
+
+    #[test]
+    fn test_account_balance() {
+        let storage_client = storage_with_blocks();
+        let balance = |account: Account, block_idx: Option<u64>| {
+            let response = account_balance(
+                &storage_client,
+                &AccountIdentifier::from(account),
+                &block_idx.map(index),
+                &currency(),
+            )
+            .unwrap();
+            assert_eq!(response.block_identifier.index, block_idx.unwrap_or(3));
+            assert_eq!(response.balances.len(), 1);
+            assert_eq!(response.balances[0].currency, currency());
+            response.balances[0].value.clone()
+        };
+        let subaccount = Account {
+            subaccount: Some(SUBACCOUNT),
+            ..account(2)
+        };
+        assert_eq!(balance(account(1), Some(0)), "1000");
+        assert_eq!(balance(account(1), Some(1)), "890");
+        assert_eq!(balance(account(1), None), "870");
+        assert_eq!(balance(account(2), Some(0)), "0");
+        assert_eq!(balance(account(2), Some(1)), "100");
+        assert_eq!(balance(account(2), None), "40");
+        assert_eq!(balance(subaccount, Some(1)), "0");
+        assert_eq!(balance(subaccount, None), "50");
+        // Accounts that never had a transaction have no balance.
+        assert_eq!(balance(account(3), None), "0");
+
+        // The default subaccount can also be given explicitly.
+        let explicit_default_subaccount = Account {
+            subaccount: Some([0; 32]),
+            ..account(2)
+        };
+        assert_eq!(balance(explicit_default_subaccount, None), "40");
+
+        // The block can also be identified by its hash.
+        let transfer = get_block(&storage_client, index(1));
+        let response = account_balance(
+            &storage_client,
+            &AccountIdentifier::from(account(1)),
+            &Some(hash(&transfer.block_identifier.hash)),
+            &currency(),
+        )
+        .unwrap();
+        assert_eq!(response.block_identifier, transfer.block_identifier);
+        assert_eq!(response.balances[0].value, "890");
+    }
+
+    #[test]
+    fn test_account_balance_errors() {
+        let storage_client = storage_with_blocks();
+        let account_balance_at = |account_identifier: AccountIdentifier, block_idx: Option<u64>| {
+            account_balance(
+                &storage_client,
+                &account_identifier,
+                &block_idx.map(index),
+                &currency(),
+            )
+        };
+
+        assert_error(
+            account_balance_at(
+                AccountIdentifier {
+                    address: "not a principal".to_string(),
+                    sub_account: None,
+                    metadata: None,
+                },
+                None,
+            ),
+            Error::parsing_unsuccessful,
+        );
+        assert_error(
+            account_balance_at(
+                AccountIdentifier {
+                    sub_account: Some(SubAccountIdentifier {
+                        address: "0101".to_string(),
+                        metadata: None,
+                    }),
+                    ..AccountIdentifier::from(account(1))
+                },
+                None,
+            ),
+            Error::parsing_unsuccessful,
+        );
+        assert_error(
+            account_balance_at(AccountIdentifier::from(account(1)), Some(4)),
+            Error::unable_to_find_block,
+        );
+
+        // Balances are unknown for blocks that were not processed yet.
+        let storage_client = StorageClient::new_in_memory().unwrap();
+        store_blocks(&storage_client);
+        assert_error(
+            account_balance(
+                &storage_client,
+                &AccountIdentifier::from(account(1)),
+                &None,
+                &currency(),
+            ),
+            Error::unable_to_find_account_balance,
+        );
+    }
+
+    fn search_request() -> SearchTransactionsRequest {
+        SearchTransactionsRequest {
+            network_identifier: NetworkIdentifier::for_ledger_id(CanisterId::from_u64(1)),
+            operator: None,
+            max_block: None,
+            offset: None,
+            limit: None,
+            transaction_identifier: None,
+            account_identifier: None,
+            currency: None,
+            status: None,
+            type_: None,
+            address: None,
+            success: None,
+        }
+    }
+
+    // Returns the indices of the blocks found by the search.
+    fn search(storage_client: &StorageClient, request: SearchTransactionsRequest) -> Vec<u64> {
+        let response = search_transactions(storage_client, &request, &currency()).unwrap();
+        response
+            .transactions
+            .iter()
+            .map(|transaction| transaction.block_identifier.index)
+            .collect()
+    }
+
+    #[test]
+    fn test_search_transactions() {
+        let storage_client = storage_with_blocks();
+
+        // Transactions are returned from the newest to the oldest.
+        assert_eq!(search(&storage_client, search_request()), vec![3, 2, 1, 0]);
+
+        let request = SearchTransactionsRequest {
+            account_identifier: Some(AccountIdentifier::from(account(1))),
+            limit: Some(2),
+            ..search_request()
+        };
+        let response = search_transactions(&storage_client, &request, &currency()).unwrap();
+        assert_eq!(response.total_count, 3);
+        assert_eq!(response.next_offset, Some(2));
+        assert_eq!(response.transactions.len(), 2);
+        assert_eq!(response.transactions[0].block_identifier.index, 3);
+        assert_eq!(
+            response.transactions[0].transaction,
+            get_block(&storage_client, index(3)).transactions[0]
+        );
+
+        let response = search_transactions(
+            &storage_client,
+            &SearchTransactionsRequest {
+                offset: response.next_offset,
+                ..request.clone()
+            },
+            &currency(),
+        )
+        .unwrap();
+        assert_eq!(response.total_count, 3);
+        assert_eq!(response.next_offset, None);
+        assert_eq!(response.transactions.len(), 1);
+        assert_eq!(response.transactions[0].block_identifier.index, 0);
+    }
+
+    #[test]
+    fn test_search_transactions_filters() {
+        let storage_client = storage_with_blocks();
+        let transfer = get_block(&storage_client, index(1));
+        let subaccount = Account {
+            subaccount: Some(SUBACCOUNT),
+            ..account(2)
+        };
+
+        assert_eq!(
+            search(
+                &storage_client,
+                SearchTransactionsRequest {
+                    transaction_identifier: Some(
+                        transfer.transactions[0].transaction_identifier.clone()
+                    ),
+                    ..search_request()
+                }
+            ),
+            vec![1]
+        );
+        // Searching by account only matches the given subaccount.
+        assert_eq!(
+            search(
+                &storage_client,
+                SearchTransactionsRequest {
+                    account_identifier: Some(AccountIdentifier::from(account(2))),
+                    ..search_request()
+                }
+            ),
+            vec![2, 1]
+        );
+        assert_eq!(
+            search(
+                &storage_client,
+                SearchTransactionsRequest {
+                    account_identifier: Some(AccountIdentifier::from(subaccount)),
+                    ..search_request()
+                }
+            ),
+            vec![2]
+        );
+        // Searching by address matches all the subaccounts of the principal.
+        assert_eq!(
+            search(
+                &storage_client,
+                SearchTransactionsRequest {
+                    address: Some(account(2).owner.to_text()),
+                    ..search_request()
+                }
+            ),
+            vec![2, 1]
+        );
+        assert_eq!(
+            search(
+                &storage_client,
+                SearchTransactionsRequest {
+                    account_identifier: Some(AccountIdentifier::from(account(2))),
+                    address: Some(account(1).owner.to_text()),
+                    ..search_request()
+                }
+            ),
+            Vec::<u64>::new()
+        );
+        for (type_, expected) in [
+            ("MINT", vec![0]),
+            ("TRANSFER", vec![2, 1]),
+            ("BURN", vec![3]),
+        ] {
+            assert_eq!(
+                search(
+                    &storage_client,
+                    SearchTransactionsRequest {
+                        type_: Some(type_.to_string()),
+                        ..search_request()
+                    }
+                ),
+                expected
+            );
+        }
+        assert_eq!(
+            search(
+                &storage_client,
+                SearchTransactionsRequest {
+                    max_block: Some(1),
+                    ..search_request()
+                }
+            ),
+            vec![1, 0]
+        );
+        assert_eq!(
+            search(
+                &storage_client,
+                SearchTransactionsRequest {
+                    status: Some(STATUS_COMPLETED.to_string()),
+                    success: Some(true),
+                    currency: Some(currency()),
+                    ..search_request()
+                }
+            ),
+            vec![3, 2, 1, 0]
+        );
+
+        // Failed transactions and other currencies are never stored.
+        for request in [
+            SearchTransactionsRequest {
+                success: Some(false),
+                ..search_request()
+            },
+            SearchTransactionsRequest {
+                status: Some("FAILED".to_string()),
+                ..search_request()
+            },
+            SearchTransactionsRequest {
+                currency: Some(Currency {
+                    symbol: "ICP".to_string(),
+                    ..currency()
+                }),
+                ..search_request()
+            },
+        ] {
+            let response = search_transactions(&storage_client, &request, &currency()).unwrap();
+            assert_eq!(response.total_count, 0);
+            assert!(response.transactions.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_search_transactions_errors() {
+        let storage_client = storage_with_blocks();
+        let search_result = |request: SearchTransactionsRequest| {
+            search_transactions(&storage_client, &request, &currency())
+        };
+
+        assert_error(
+            search_result(SearchTransactionsRequest {
+                operator: Some(Operator::Or),
+                ..search_request()
+            }),
+            Error::invalid_search_transactions_request,
+        );
+        for request in [
+            SearchTransactionsRequest {
+                max_block: Some(-1),
+                ..search_request()
+            },
+            SearchTransactionsRequest {
+                offset: Some(-1),
+                ..search_request()
+            },
+            SearchTransactionsRequest {
+                limit: Some(-1),
+                ..search_request()
+            },
+        ] {
+            assert_error(
+                search_result(request),
+                Error::invalid_search_transactions_request,
+            );
+        }
+        assert_error(
+            search_result(SearchTransactionsRequest {
+                type_: Some("UNKNOWN".to_string()),
+                ..search_request()
+            }),
+            Error::invalid_search_transactions_request,
+        );
+        assert_error(
+            search_result(SearchTransactionsRequest {
+                type_: Some("FEE".to_string()),
+                ..search_request()
+            }),
+            Error::invalid_search_transactions_request,
+        );
+        assert_error(
+            search_result(SearchTransactionsRequest {
+                transaction_identifier: Some(TransactionIdentifier {
+                    hash: "not a hash".to_string(),
+                }),
+                ..search_request()
+            }),
+            Error::invalid_transaction_identifier,
+        );
+        assert_error(
+            search_result(SearchTransactionsRequest {
+                address: Some("not a principal".to_string()),
+                ..search_request()
+            }),
+            Error::parsing_unsuccessful,
+        );
+        assert_error(
+            search_result(SearchTransactionsRequest {
+                account_identifier: Some(AccountIdentifier {
+                    address: "not a principal".to_string(),
+                    sub_account: None,
+                    metadata: None,
+                }),
+                ..search_request()
+            }),
+            Error::parsing_unsuccessful,
+        );
+    }
+}
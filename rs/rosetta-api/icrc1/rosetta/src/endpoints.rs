@@ -2,10 +2,23 @@ use std::sync::Arc;
 
 use axum::{extract::State, http::StatusCode, response::Result, Json};
 use ic_icrc_rosetta::{
-    common::types::{
-        Allow, Error, MetadataRequest, NetworkIdentifier, NetworkListResponse,
-        NetworkOptionsResponse, NetworkRequest, Version,
+    common::{
+        types::{
+            AccountBalanceRequest, AccountBalanceResponse, Allow, BlockRequest, BlockResponse,
+            BlockTransactionRequest, BlockTransactionResponse, ConstructionCombineRequest,
+            ConstructionCombineResponse, ConstructionDeriveRequest, ConstructionDeriveResponse,
+            ConstructionHashRequest, ConstructionMetadataRequest, ConstructionMetadataResponse,
+            ConstructionParseRequest, ConstructionParseResponse, ConstructionPayloadsRequest,
+            ConstructionPayloadsResponse, ConstructionPreprocessRequest,
+            ConstructionPreprocessResponse, ConstructionSubmitRequest, Error, MempoolResponse,
+            MetadataRequest, NetworkIdentifier, NetworkListResponse, NetworkOptionsResponse,
+            NetworkRequest, NetworkStatusResponse, OperationStatus, SearchTransactionsRequest,
+            SearchTransactionsResponse, TransactionIdentifierResponse, Version,
+        },
+        utils::conversions::{OperationType, STATUS_COMPLETED},
     },
+    construction_api::services as construction_services,
+    data_api::services,
     AppState,
 };
 
@@ -46,11 +59,32 @@ pub async fn network_options(
             metadata: None,
         },
         allow: Allow {
-            operation_statuses: vec![],
-            operation_types: vec![],
-            errors: vec![Error::invalid_network_id(
-                &NetworkIdentifier::for_ledger_id(state.ledger_id),
-            )],
+            operation_statuses: vec![OperationStatus {
+                status: STATUS_COMPLETED.to_string(),
+                successful: true,
+            }],
+            operation_types: OperationType::ALL
+                .iter()
+                .map(|ty| ty.as_str().to_string())
+                .collect(),
+            errors: vec![
+                Error::invalid_network_id(&NetworkIdentifier::for_ledger_id(state.ledger_id)),
+                Error::unable_to_find_block("".to_string()),
+                Error::invalid_block_identifier("".to_string()),
+                Error::failed_to_build_block_response("".to_string()),
+                Error::invalid_transaction_identifier("".to_string()),
+                Error::unable_to_find_account_balance("".to_string()),
+                Error::parsing_unsuccessful("".to_string()),
+                Error::invalid_search_transactions_request("".to_string()),
+                Error::storage_error("".to_string()),
+                Error::invalid_public_key("".to_string()),
+                Error::invalid_operations("".to_string()),
+                Error::invalid_metadata("".to_string()),
+                Error::invalid_transaction("".to_string()),
+                Error::ledger_communication_error("".to_string()),
+                Error::transaction_rejected("".to_string()),
+                Error::transaction_expired("".to_string()),
+            ],
             historical_balance_lookup: true,
             timestamp_start_index: None,
             call_methods: vec![],
@@ -61,3 +95,165 @@ pub async fn network_options(
         },
     }))
 }
+
+pub async fn network_status(
+    State(state): State<Arc<AppState>>,
+    request: Json<NetworkRequest>,
+) -> Result<Json<NetworkStatusResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    Ok(Json(services::network_status(&state.storage)?))
+}
+
+pub async fn block(
+    State(state): State<Arc<AppState>>,
+    request: Json<BlockRequest>,
+) -> Result<Json<BlockResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    Ok(Json(services::block(
+        &state.storage,
+        &request.block_identifier,
+        &state.metadata.rosetta_currency(),
+    )?))
+}
+
+pub async fn block_transaction(
+    State(state): State<Arc<AppState>>,
+    request: Json<BlockTransactionRequest>,
+) -> Result<Json<BlockTransactionResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    Ok(Json(services::block_transaction(
+        &state.storage,
+        &request.block_identifier,
+        &request.transaction_identifier,
+        &state.metadata.rosetta_currency(),
+    )?))
+}
+
+pub async fn account_balance(
+    State(state): State<Arc<AppState>>,
+    request: Json<AccountBalanceRequest>,
+) -> Result<Json<AccountBalanceResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    Ok(Json(services::account_balance(
+        &state.storage,
+        &request.account_identifier,
+        &request.block_identifier,
+        &state.metadata.rosetta_currency(),
+    )?))
+}
+
+pub async fn mempool(
+    State(state): State<Arc<AppState>>,
+    request: Json<NetworkRequest>,
+) -> Result<Json<MempoolResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    // The ledger applies transactions right away, so there is never anything pending.
+    Ok(Json(MempoolResponse {
+        transaction_identifiers: vec![],
+    }))
+}
+
+pub async fn search_transactions(
+    State(state): State<Arc<AppState>>,
+    request: Json<SearchTransactionsRequest>,
+) -> Result<Json<SearchTransactionsResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    Ok(Json(services::search_transactions(
+        &state.storage,
+        &request,
+        &state.metadata.rosetta_currency(),
+    )?))
+}
+
+pub async fn construction_derive(
+    State(state): State<Arc<AppState>>,
+    request: Json<ConstructionDeriveRequest>,
+) -> Result<Json<ConstructionDeriveResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    Ok(Json(construction_services::construction_derive(
+        &request.public_key,
+    )?))
+}
+
+pub async fn construction_preprocess(
+    State(state): State<Arc<AppState>>,
+    request: Json<ConstructionPreprocessRequest>,
+) -> Result<Json<ConstructionPreprocessResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    Ok(Json(construction_services::construction_preprocess(
+        &request.operations,
+        &state.metadata.rosetta_currency(),
+    )?))
+}
+
+pub async fn construction_metadata(
+    State(state): State<Arc<AppState>>,
+    request: Json<ConstructionMetadataRequest>,
+) -> Result<Json<ConstructionMetadataResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    Ok(Json(
+        construction_services::construction_metadata(
+            &state.icrc1_agent,
+            &state.metadata.rosetta_currency(),
+        )
+        .await?,
+    ))
+}
+
+pub async fn construction_payloads(
+    State(state): State<Arc<AppState>>,
+    request: Json<ConstructionPayloadsRequest>,
+) -> Result<Json<ConstructionPayloadsResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    Ok(Json(construction_services::construction_payloads(
+        state.ledger_id,
+        &request.operations,
+        &request.metadata,
+        request.public_keys.as_deref().unwrap_or_default(),
+        &state.metadata.rosetta_currency(),
+    )?))
+}
+
+pub async fn construction_parse(
+    State(state): State<Arc<AppState>>,
+    request: Json<ConstructionParseRequest>,
+) -> Result<Json<ConstructionParseResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    Ok(Json(construction_services::construction_parse(
+        &request.transaction,
+        request.signed,
+        &state.metadata.rosetta_currency(),
+    )?))
+}
+
+pub async fn construction_combine(
+    State(state): State<Arc<AppState>>,
+    request: Json<ConstructionCombineRequest>,
+) -> Result<Json<ConstructionCombineResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    Ok(Json(construction_services::construction_combine(
+        &request.unsigned_transaction,
+        &request.signatures,
+    )?))
+}
+
+pub async fn construction_hash(
+    State(state): State<Arc<AppState>>,
+    request: Json<ConstructionHashRequest>,
+) -> Result<Json<TransactionIdentifierResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    Ok(Json(construction_services::construction_hash(
+        &request.signed_transaction,
+    )?))
+}
+
+pub async fn construction_submit(
+    State(state): State<Arc<AppState>>,
+    request: Json<ConstructionSubmitRequest>,
+) -> Result<Json<TransactionIdentifierResponse>> {
+    verify_network_id(&request.network_identifier, &state)?;
+    Ok(Json(
+        construction_services::construction_submit(&state.icrc1_agent, &request.signed_transaction)
+            .await?,
+    ))
+}
@@ -148,6 +148,9 @@ pub async fn sync_from_the_tip(
         )
         .await?;
     }
+
+    // Keep the historical account balances in line with the newly stored blocks
+    storage_client.update_account_balances()?;
     Ok(())
 }
 
@@ -405,9 +408,16 @@ async fn fetch_blockchain_tip_data(agent: Arc<Icrc1Agent>) -> anyhow::Result<(u6
     let hash_tree: MixedHashTree = serde_cbor::from_slice(&data_certificate.hash_tree)
         .map_err(|err| anyhow::Error::msg(err.to_string()))?;
 
-    // Extract the last block index from the hash tree
+    // Extract the last block index from the hash tree. Ledgers that certify an ICRC-3 tree
+    // (i.e., a tree with a last_block_hash label) encode it as LEB128, older ledgers as a
+    // big-endian u64.
+    let is_icrc3_tree = hash_tree.lookup(&[b"last_block_hash"]).is_found();
     let last_block_index = match hash_tree.lookup(&[b"last_block_index"]) {
         Found(x) => match x {
+            MixedHashTree::Leaf(l) if is_icrc3_tree => leb128::read::unsigned(&mut l.as_slice())
+                .map_err(|err| {
+                    anyhow::Error::msg(format!("Could not decode last block index: {}", err))
+                }),
             MixedHashTree::Leaf(l) => {
                 let mut bytes: [u8; 8] = [0u8; 8];
                 for (i, e) in l.iter().enumerate() {
@@ -1,12 +1,37 @@
 use common::storage::storage_client::StorageClient;
+use common::types::Currency;
 use ic_base_types::CanisterId;
+use icrc_ledger_agent::Icrc1Agent;
 use std::sync::Arc;
 
 pub mod common;
 
+pub mod construction_api;
+
+pub mod data_api;
+
 pub mod ledger_blocks_synchronization;
 
+/// Metadata of the ledger that Rosetta serves.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Metadata {
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+impl Metadata {
+    pub fn rosetta_currency(&self) -> Currency {
+        Currency {
+            symbol: self.symbol.clone(),
+            decimals: self.decimals as i32,
+            metadata: None,
+        }
+    }
+}
+
 pub struct AppState {
+    pub icrc1_agent: Arc<Icrc1Agent>,
     pub ledger_id: CanisterId,
-    pub _storage: Arc<StorageClient>,
+    pub storage: Arc<StorageClient>,
+    pub metadata: Metadata,
 }
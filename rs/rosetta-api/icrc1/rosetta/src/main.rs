@@ -5,7 +5,12 @@ use axum::{
     Router,
 };
 use clap::{Parser, ValueEnum};
-use endpoints::{health, network_list, network_options};
+use endpoints::{
+    account_balance, block, block_transaction, construction_combine, construction_derive,
+    construction_hash, construction_metadata, construction_parse, construction_payloads,
+    construction_preprocess, construction_submit, health, mempool, network_list, network_options,
+    network_status, search_transactions,
+};
 use http::Request;
 use ic_agent::{
     agent::http_transport::ReqwestHttpReplicaV2Transport, identity::AnonymousIdentity, Agent,
@@ -13,16 +18,19 @@ use ic_agent::{
 use ic_base_types::CanisterId;
 use ic_icrc_rosetta::{
     common::storage::storage_client::StorageClient,
-    ledger_blocks_synchronization::blocks_synchronizer::start_synching_blocks, AppState,
+    ledger_blocks_synchronization::blocks_synchronizer::{
+        start_synching_blocks, sync_from_the_tip,
+    },
+    AppState, Metadata,
 };
-use icrc_ledger_agent::Icrc1Agent;
+use icrc_ledger_agent::{CallMode, Icrc1Agent};
 use lazy_static::lazy_static;
-use std::{net::TcpListener, sync::Arc};
+use std::{net::TcpListener, sync::Arc, time::Duration};
 use std::{path::PathBuf, process};
 use tower_http::classify::{ServerErrorsAsFailures, SharedClassifier};
 use tower_http::trace::TraceLayer;
 use tower_request_id::{RequestId, RequestIdLayer};
-use tracing::{debug, error_span, info, Level, Span};
+use tracing::{debug, error, error_span, info, Level, Span};
 use url::Url;
 mod endpoints;
 
@@ -30,6 +38,8 @@ lazy_static! {
     static ref MAINNET_DEFAULT_URL: &'static str = "https://ic0.app";
     static ref TESTNET_DEFAULT_URL: &'static str = "https://exchanges.testnet.dfinity.network";
     static ref MAXIMUM_BLOCKS_PER_REQUEST: u64 = 2000;
+    static ref OFFLINE_DEFAULT_SYMBOL: &'static str = "ICRC1";
+    static ref OFFLINE_DEFAULT_DECIMALS: u8 = 8;
 }
 
 #[derive(Clone, Debug, ValueEnum)]
@@ -88,6 +98,16 @@ struct Args {
     /// Set this option to only run the rosetta server, no block synchronization will be performed and no transactions can be submitted in this mode.
     #[arg(long)]
     offline: bool,
+
+    /// The symbol of the token. If not set then it is fetched from the ledger,
+    /// or set to ICRC1 in offline mode.
+    #[arg(long)]
+    symbol: Option<String>,
+
+    /// The number of decimals of the token. If not set then it is fetched from
+    /// the ledger, or set to 8 in offline mode.
+    #[arg(long)]
+    decimals: Option<u8>,
 }
 
 impl Args {
@@ -162,11 +182,6 @@ async fn main() -> Result<()> {
         StoreType::File => StorageClient::new_persistent(&args.store_file)?,
     });
 
-    let shared_state = Arc::new(AppState {
-        ledger_id: args.ledger_id,
-        _storage: storage.clone(),
-    });
-
     let network_url = args.effective_network_url();
 
     let ic_agent = Agent::builder()
@@ -195,6 +210,34 @@ async fn main() -> Result<()> {
         ledger_canister_id: args.ledger_id.into(),
     });
 
+    let metadata = Metadata {
+        symbol: match &args.symbol {
+            Some(symbol) => symbol.clone(),
+            None if args.offline => (*OFFLINE_DEFAULT_SYMBOL).to_string(),
+            None => icrc1_agent
+                .symbol(CallMode::Query)
+                .await
+                .map_err(|e| anyhow::Error::msg(format!("{:?}", e)))
+                .context("Failed to fetch the token symbol from the ledger")?,
+        },
+        decimals: match args.decimals {
+            Some(decimals) => decimals,
+            None if args.offline => *OFFLINE_DEFAULT_DECIMALS,
+            None => icrc1_agent
+                .decimals(CallMode::Query)
+                .await
+                .map_err(|e| anyhow::Error::msg(format!("{:?}", e)))
+                .context("Failed to fetch the token decimals from the ledger")?,
+        },
+    };
+
+    let shared_state = Arc::new(AppState {
+        icrc1_agent: icrc1_agent.clone(),
+        ledger_id: args.ledger_id,
+        storage: storage.clone(),
+        metadata,
+    });
+
     if !args.offline {
         info!("Starting to sync blocks");
         start_synching_blocks(
@@ -210,10 +253,45 @@ async fn main() -> Result<()> {
         process::exit(0);
     }
 
+    // Keep following the tip of the ledger while the server is running
+    if !args.offline {
+        let icrc1_agent = icrc1_agent.clone();
+        let storage = storage.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(1));
+            loop {
+                interval.tick().await;
+                if let Err(e) = sync_from_the_tip(
+                    icrc1_agent.clone(),
+                    storage.clone(),
+                    *MAXIMUM_BLOCKS_PER_REQUEST,
+                )
+                .await
+                {
+                    error!("Error while syncing blocks: {}", e);
+                }
+            }
+        });
+    }
+
     let app = Router::new()
         .route("/health", get(health))
         .route("/network/list", post(network_list))
         .route("/network/options", post(network_options))
+        .route("/network/status", post(network_status))
+        .route("/block", post(block))
+        .route("/block/transaction", post(block_transaction))
+        .route("/account/balance", post(account_balance))
+        .route("/mempool", post(mempool))
+        .route("/search/transactions", post(search_transactions))
+        .route("/construction/derive", post(construction_derive))
+        .route("/construction/preprocess", post(construction_preprocess))
+        .route("/construction/metadata", post(construction_metadata))
+        .route("/construction/payloads", post(construction_payloads))
+        .route("/construction/parse", post(construction_parse))
+        .route("/construction/combine", post(construction_combine))
+        .route("/construction/hash", post(construction_hash))
+        .route("/construction/submit", post(construction_submit))
         // This layer creates a span for each http request and attaches
         // the request_id, HTTP Method and path to it.
         .layer(add_request_span())
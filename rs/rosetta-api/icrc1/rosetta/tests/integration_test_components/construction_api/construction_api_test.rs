@@ -0,0 +1,275 @@
+use crate::common::local_replica;
+use candid::Nat;
+use ic_base_types::{CanisterId, PrincipalId};
+use ic_canister_client_sender::Ed25519KeyPair;
+use ic_icrc1_ledger::{FeatureFlags, InitArgsBuilder};
+use ic_icrc1_test_utils::DEFAULT_TRANSFER_FEE;
+use ic_icrc_rosetta::common::storage::storage_client::StorageClient;
+use ic_icrc_rosetta::common::types::{
+    AccountIdentifier, Amount, ApproveMetadata, Currency, CurveType, Operation,
+    OperationIdentifier, PartialBlockIdentifier, PublicKey, Signature, SignatureType,
+};
+use ic_icrc_rosetta::common::utils::conversions::OperationType;
+use ic_icrc_rosetta::construction_api::services;
+use ic_icrc_rosetta::data_api::services as data_api_services;
+use ic_icrc_rosetta::ledger_blocks_synchronization::blocks_synchronizer;
+use icrc_ledger_agent::{CallMode, Icrc1Agent};
+use icrc_ledger_types::icrc1::account::Account;
+use rand::{rngs::StdRng, SeedableRng};
+use serde_json::json;
+use std::sync::Arc;
+
+const INITIAL_BALANCE: u64 = 1_000_000_000_000;
+
+fn currency() -> Currency {
+    Currency {
+        symbol: "XTST".to_string(),
+        decimals: 8,
+        metadata: None,
+    }
+}
+
+fn amount(value: i128) -> Amount {
+    Amount {
+        value: value.to_string(),
+        currency: currency(),
+        metadata: None,
+    }
+}
+
+fn operation(
+    index: u64,
+    type_: OperationType,
+    account: Account,
+    value: Option<i128>,
+    metadata: Option<serde_json::Value>,
+) -> Operation {
+    Operation {
+        operation_identifier: OperationIdentifier {
+            index,
+            network_index: None,
+        },
+        related_operations: None,
+        type_: type_.as_str().to_string(),
+        status: None,
+        account: Some(AccountIdentifier::from(account)),
+        amount: value.map(amount),
+        metadata,
+    }
+}
+
+fn public_key(key_pair: &Ed25519KeyPair) -> PublicKey {
+    PublicKey {
+        hex_bytes: hex::encode(key_pair.public_key),
+        curve_type: CurveType::Edwards25519,
+    }
+}
+
+fn receiver() -> Account {
+    Account {
+        owner: PrincipalId::new_user_test_id(1).0,
+        subaccount: None,
+    }
+}
+
+/// Runs the construction flow from derive through submit and returns the
+/// signed transaction, its hash and the index of the block it was recorded in.
+async fn construct_and_submit(
+    agent: &Icrc1Agent,
+    ledger_id: CanisterId,
+    key_pair: &Ed25519KeyPair,
+    operations: Vec<Operation>,
+) -> (String, String, u64) {
+    let currency = currency();
+    let signer = services::construction_derive(&public_key(key_pair))
+        .unwrap()
+        .account_identifier
+        .unwrap();
+
+    let preprocessed = services::construction_preprocess(&operations, &currency).unwrap();
+    assert_eq!(
+        preprocessed.required_public_keys,
+        Some(vec![signer.clone()])
+    );
+
+    let payloads = services::construction_payloads(
+        ledger_id,
+        &operations,
+        &Some(json!({})),
+        &[public_key(key_pair)],
+        &currency,
+    )
+    .unwrap();
+    let parsed =
+        services::construction_parse(&payloads.unsigned_transaction, false, &currency).unwrap();
+    assert_eq!(parsed.operations, operations);
+
+    let signatures: Vec<_> = payloads
+        .payloads
+        .iter()
+        .map(|payload| Signature {
+            signing_payload: payload.clone(),
+            public_key: public_key(key_pair),
+            signature_type: SignatureType::Ed25519,
+            hex_bytes: hex::encode(key_pair.sign(&hex::decode(&payload.hex_bytes).unwrap())),
+        })
+        .collect();
+    let signed_transaction =
+        services::construction_combine(&payloads.unsigned_transaction, &signatures)
+            .unwrap()
+            .signed_transaction;
+    let parsed = services::construction_parse(&signed_transaction, true, &currency).unwrap();
+    assert_eq!(parsed.operations, operations);
+    assert_eq!(parsed.account_identifier_signers, Some(vec![signer]));
+
+    let hash = services::construction_hash(&signed_transaction)
+        .unwrap()
+        .transaction_identifier
+        .hash;
+    let submitted = services::construction_submit(agent, &signed_transaction)
+        .await
+        .unwrap();
+    assert_eq!(submitted.transaction_identifier.hash, hash);
+    let block_index = submitted.metadata.unwrap()["block_index"].as_u64().unwrap();
+    (signed_transaction, hash, block_index)
+}
+
+#[tokio::test]
+async fn test_construction_api_on_local_replica() {
+    let sender_key_pair = Ed25519KeyPair::generate(&mut StdRng::seed_from_u64(1));
+    let spender_key_pair = Ed25519KeyPair::generate(&mut StdRng::seed_from_u64(2));
+    let account_of = |key_pair: &Ed25519KeyPair| {
+        let account_identifier = services::construction_derive(&public_key(key_pair))
+            .unwrap()
+            .account_identifier
+            .unwrap();
+        Account::try_from(&account_identifier).unwrap()
+    };
+    let sender = account_of(&sender_key_pair);
+    let spender = account_of(&spender_key_pair);
+
+    let replica_context = local_replica::start_new_local_replica().await;
+    let ledger_id = local_replica::deploy_icrc_ledger_with_custom_args(
+        &replica_context,
+        InitArgsBuilder::for_tests()
+            .with_minting_account(Account {
+                owner: PrincipalId::new_user_test_id(0).0,
+                subaccount: None,
+            })
+            .with_initial_balance(sender, INITIAL_BALANCE)
+            .with_transfer_fee(DEFAULT_TRANSFER_FEE)
+            .with_feature_flags(FeatureFlags { icrc2: true })
+            .build(),
+    )
+    .await;
+    let agent = Arc::new(Icrc1Agent {
+        agent: local_replica::get_testing_agent(&replica_context).await,
+        ledger_canister_id: ledger_id.into(),
+    });
+
+    let metadata = services::construction_metadata(&agent, &currency())
+        .await
+        .unwrap();
+    let fee: i128 = metadata.suggested_fee.unwrap()[0].value.parse().unwrap();
+    assert_eq!(fee, DEFAULT_TRANSFER_FEE as i128);
+
+    // Block 0 mints the initial balance of the sender.
+    let (transfer, transfer_hash, transfer_index) = construct_and_submit(
+        &agent,
+        ledger_id,
+        &sender_key_pair,
+        vec![
+            operation(0, OperationType::Transfer, sender, Some(-1_000_000), None),
+            operation(
+                1,
+                OperationType::Transfer,
+                receiver(),
+                Some(1_000_000),
+                None,
+            ),
+            operation(2, OperationType::Fee, sender, Some(-fee), None),
+        ],
+    )
+    .await;
+    assert_eq!(transfer_index, 1);
+
+    let approve_metadata = ApproveMetadata {
+        spender: AccountIdentifier::from(spender),
+        allowance: amount(500_000),
+        expected_allowance: None,
+        expires_at: None,
+    };
+    let (_, approve_hash, approve_index) = construct_and_submit(
+        &agent,
+        ledger_id,
+        &sender_key_pair,
+        vec![operation(
+            0,
+            OperationType::Approve,
+            sender,
+            None,
+            Some(serde_json::to_value(approve_metadata).unwrap()),
+        )],
+    )
+    .await;
+    assert_eq!(approve_index, 2);
+
+    let (_, transfer_from_hash, transfer_from_index) = construct_and_submit(
+        &agent,
+        ledger_id,
+        &spender_key_pair,
+        vec![
+            operation(
+                0,
+                OperationType::Transfer,
+                sender,
+                Some(-200_000),
+                Some(json!({ "spender": AccountIdentifier::from(spender) })),
+            ),
+            operation(1, OperationType::Transfer, receiver(), Some(200_000), None),
+            operation(2, OperationType::Fee, sender, Some(-fee), None),
+        ],
+    )
+    .await;
+    assert_eq!(transfer_from_index, 3);
+
+    // Submitting a transaction again returns the block it was already recorded in.
+    let resubmitted = services::construction_submit(&agent, &transfer)
+        .await
+        .unwrap();
+    assert_eq!(resubmitted.transaction_identifier.hash, transfer_hash);
+    assert_eq!(resubmitted.metadata.unwrap()["block_index"], json!(1));
+
+    let fee = fee as u64;
+    let sender_balance = agent.balance_of(sender, CallMode::Query).await.unwrap();
+    assert_eq!(
+        sender_balance,
+        Nat::from(INITIAL_BALANCE - 1_200_000 - 3 * fee)
+    );
+    let receiver_balance = agent.balance_of(receiver(), CallMode::Query).await.unwrap();
+    assert_eq!(receiver_balance, Nat::from(1_200_000u64));
+
+    // The hashes of the constructed transactions are the ones of the synced blocks.
+    let storage_client = Arc::new(StorageClient::new_in_memory().unwrap());
+    blocks_synchronizer::start_synching_blocks(agent.clone(), storage_client.clone(), 10)
+        .await
+        .unwrap();
+    for (index, hash) in [
+        (transfer_index, transfer_hash),
+        (approve_index, approve_hash),
+        (transfer_from_index, transfer_from_hash),
+    ] {
+        let block = data_api_services::block(
+            &storage_client,
+            &PartialBlockIdentifier {
+                index: Some(index),
+                hash: None,
+            },
+            &currency(),
+        )
+        .unwrap()
+        .block
+        .unwrap();
+        assert_eq!(block.transactions[0].transaction_identifier.hash, hash);
+    }
+}
@@ -0,0 +1 @@
+pub mod construction_api_test;
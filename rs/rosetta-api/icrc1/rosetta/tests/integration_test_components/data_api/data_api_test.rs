@@ -0,0 +1,250 @@
+use crate::common::local_replica;
+use crate::common::local_replica::test_identity;
+use candid::Nat;
+use ic_agent::Identity;
+use ic_base_types::{CanisterId, PrincipalId};
+use ic_icrc1_ledger::InitArgsBuilder;
+use ic_icrc1_test_utils::DEFAULT_TRANSFER_FEE;
+use ic_icrc_rosetta::common::storage::storage_client::StorageClient;
+use ic_icrc_rosetta::common::types::{
+    AccountIdentifier, Currency, Error, NetworkIdentifier, PartialBlockIdentifier,
+    SearchTransactionsRequest, TransactionIdentifier,
+};
+use ic_icrc_rosetta::data_api::services;
+use ic_icrc_rosetta::ledger_blocks_synchronization::blocks_synchronizer;
+use ic_ledger_canister_core::archive::ArchiveOptions;
+use icrc_ledger_agent::{CallMode, Icrc1Agent};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::TransferArg;
+use std::sync::Arc;
+
+const NUM_TRANSFERS: u64 = 12;
+
+fn currency() -> Currency {
+    Currency {
+        symbol: "XTST".to_string(),
+        decimals: 8,
+        metadata: None,
+    }
+}
+
+fn receiver(n: u64) -> Account {
+    Account {
+        owner: PrincipalId::new_user_test_id(n).0,
+        subaccount: None,
+    }
+}
+
+fn search_request(ledger_id: CanisterId) -> SearchTransactionsRequest {
+    SearchTransactionsRequest {
+        network_identifier: NetworkIdentifier::for_ledger_id(ledger_id),
+        operator: None,
+        max_block: None,
+        offset: None,
+        limit: None,
+        transaction_identifier: None,
+        account_identifier: None,
+        currency: None,
+        status: None,
+        type_: None,
+        address: None,
+        success: None,
+    }
+}
+
+#[tokio::test]
+async fn test_data_api_on_synced_blocks() {
+    let test_account: Account = test_identity().sender().unwrap().into();
+    let replica_context = local_replica::start_new_local_replica().await;
+
+    // Archive some of the blocks so that the synchronizer has to fetch them from the archive.
+    let ledger_id = local_replica::deploy_icrc_ledger_with_custom_args(
+        &replica_context,
+        InitArgsBuilder::for_tests()
+            .with_minting_account(receiver(0))
+            .with_initial_balance(test_account, 1_000_000_000_000u64)
+            .with_transfer_fee(DEFAULT_TRANSFER_FEE)
+            .with_archive_options(ArchiveOptions {
+                trigger_threshold: 10,
+                num_blocks_to_archive: 5,
+                node_max_memory_size_bytes: None,
+                max_message_size_bytes: None,
+                controller_id: PrincipalId::new_user_test_id(100),
+                cycles_for_archive_creation: None,
+                max_transactions_per_response: None,
+            })
+            .build(),
+    )
+    .await;
+    let agent = Arc::new(Icrc1Agent {
+        agent: local_replica::get_testing_agent(&replica_context).await,
+        ledger_canister_id: ledger_id.into(),
+    });
+
+    // Block 0 mints the initial balance, the following blocks transfer to three receivers.
+    for i in 0..NUM_TRANSFERS {
+        agent
+            .transfer(TransferArg {
+                from_subaccount: None,
+                to: receiver(1 + i % 3),
+                fee: None,
+                created_at_time: None,
+                memo: None,
+                amount: Nat::from(1_000_000 + i),
+            })
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    let storage_client = Arc::new(StorageClient::new_in_memory().unwrap());
+    blocks_synchronizer::start_synching_blocks(agent.clone(), storage_client.clone(), 10)
+        .await
+        .unwrap();
+
+    let status = services::network_status(&storage_client).unwrap();
+    assert_eq!(status.current_block_identifier.index, NUM_TRANSFERS);
+    assert_eq!(status.genesis_block_identifier.index, 0);
+
+    // Every block and its transaction can be looked up by index and by hash.
+    for idx in 0..=NUM_TRANSFERS {
+        let block = services::block(
+            &storage_client,
+            &PartialBlockIdentifier {
+                index: Some(idx),
+                hash: None,
+            },
+            &currency(),
+        )
+        .unwrap()
+        .block
+        .unwrap();
+        assert_eq!(block.block_identifier.index, idx);
+        assert_eq!(block.parent_block_identifier.index, idx.saturating_sub(1));
+        let by_hash = services::block(
+            &storage_client,
+            &PartialBlockIdentifier {
+                index: None,
+                hash: Some(block.block_identifier.hash.clone()),
+            },
+            &currency(),
+        )
+        .unwrap()
+        .block
+        .unwrap();
+        assert_eq!(by_hash, block);
+
+        let transaction = services::block_transaction(
+            &storage_client,
+            &block.block_identifier,
+            &block.transactions[0].transaction_identifier,
+            &currency(),
+        )
+        .unwrap()
+        .transaction;
+        assert_eq!(transaction, block.transactions[0]);
+    }
+    let err = services::block(
+        &storage_client,
+        &PartialBlockIdentifier {
+            index: Some(NUM_TRANSFERS + 1),
+            hash: None,
+        },
+        &currency(),
+    )
+    .unwrap_err();
+    assert_eq!(err.code, Error::unable_to_find_block(String::new()).code);
+    let err = services::block_transaction(
+        &storage_client,
+        &status.current_block_identifier,
+        &TransactionIdentifier {
+            hash: "00".repeat(32),
+        },
+        &currency(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.code,
+        Error::invalid_transaction_identifier(String::new()).code
+    );
+
+    // The balances computed from the synced blocks match the ones of the ledger.
+    for account in [
+        test_account,
+        receiver(1),
+        receiver(2),
+        receiver(3),
+        receiver(4),
+    ] {
+        let balance = services::account_balance(
+            &storage_client,
+            &AccountIdentifier::from(account),
+            &None,
+            &currency(),
+        )
+        .unwrap();
+        assert_eq!(balance.block_identifier, status.current_block_identifier);
+        let ledger_balance = agent.balance_of(account, CallMode::Query).await.unwrap();
+        assert_eq!(balance.balances[0].value, ledger_balance.0.to_string());
+    }
+    // Before the first transfer, only the initial balance was minted.
+    let balance = services::account_balance(
+        &storage_client,
+        &AccountIdentifier::from(test_account),
+        &Some(PartialBlockIdentifier {
+            index: Some(0),
+            hash: None,
+        }),
+        &currency(),
+    )
+    .unwrap();
+    assert_eq!(balance.balances[0].value, "1000000000000");
+
+    // The search finds the transactions of each receiver, from the newest to the oldest.
+    for n in 1..=3 {
+        let response = services::search_transactions(
+            &storage_client,
+            &SearchTransactionsRequest {
+                account_identifier: Some(AccountIdentifier::from(receiver(n))),
+                ..search_request(ledger_id)
+            },
+            &currency(),
+        )
+        .unwrap();
+        let indices: Vec<_> = response
+            .transactions
+            .iter()
+            .map(|transaction| transaction.block_identifier.index)
+            .collect();
+        let expected: Vec<_> = (1..=NUM_TRANSFERS)
+            .rev()
+            .filter(|idx| (idx - 1) % 3 == n - 1)
+            .collect();
+        assert_eq!(indices, expected);
+        assert_eq!(response.total_count, expected.len() as i64);
+    }
+    let response = services::search_transactions(
+        &storage_client,
+        &SearchTransactionsRequest {
+            type_: Some("MINT".to_string()),
+            ..search_request(ledger_id)
+        },
+        &currency(),
+    )
+    .unwrap();
+    assert_eq!(response.total_count, 1);
+    assert_eq!(response.transactions[0].block_identifier.index, 0);
+    let err = services::search_transactions(
+        &storage_client,
+        &SearchTransactionsRequest {
+            limit: Some(-1),
+            ..search_request(ledger_id)
+        },
+        &currency(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.code,
+        Error::invalid_search_transactions_request(String::new()).code
+    );
+}
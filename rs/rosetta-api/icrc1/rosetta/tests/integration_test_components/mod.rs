@@ -1,2 +1,4 @@
 pub mod blocks_synchronizer;
+pub mod construction_api;
+pub mod data_api;
 pub mod test_setup;
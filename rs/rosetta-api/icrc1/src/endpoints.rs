@@ -6,6 +6,7 @@ use ic_ledger_core::tokens::TokensType;
 use icrc_ledger_types::icrc1::transfer::TransferError;
 use icrc_ledger_types::icrc2::approve::ApproveError;
 use icrc_ledger_types::icrc2::transfer_from::TransferFromError;
+use icrc_ledger_types::icrc3::blocks::SupportedBlockType;
 use icrc_ledger_types::icrc3::transactions::{Approve, Burn, Mint, Transaction, Transfer};
 use serde::Deserialize;
 
@@ -147,6 +148,25 @@ pub struct StandardRecord {
     pub url: String,
 }
 
+/// Returns the block types produced by ICRC-1 ledgers, see the ICRC-3 standard.
+pub fn icrc3_supported_block_types() -> Vec<SupportedBlockType> {
+    const ICRC1_URL: &str = "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-1";
+    const ICRC2_URL: &str = "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-2";
+    [
+        ("1burn", ICRC1_URL),
+        ("1mint", ICRC1_URL),
+        ("1xfer", ICRC1_URL),
+        ("2approve", ICRC2_URL),
+        ("2xfer", ICRC2_URL),
+    ]
+    .into_iter()
+    .map(|(block_type, url)| SupportedBlockType {
+        block_type: block_type.to_string(),
+        url: url.to_string(),
+    })
+    .collect()
+}
+
 // Non-standard queries
 
 impl<Tokens: TokensType> From<Block<Tokens>> for Transaction {
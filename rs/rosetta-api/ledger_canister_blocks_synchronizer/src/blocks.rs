@@ -119,7 +119,32 @@ mod database_access {
                 })
                 .map_err(|e| BlockStoreError::Other(e.to_string()))?;
             }
-            Operation::Approve { .. } => todo!(),
+            Operation::Approve {
+                from,
+                spender,
+                allowance,
+                fee,
+                ..
+            } => {
+                let op_string: &str = operation_type.into();
+                let from_account = from.to_hex();
+                let tokens = allowance.get_e8s();
+                let to_account = spender.to_hex();
+                let fees = fee.get_e8s();
+                stmt.execute(named_params! {
+                    ":index": index,
+                    ":tx_hash": tx_hash,
+                    ":op": op_string,
+                    ":from": from_account,
+                    ":to": to_account,
+                    ":tokens": tokens,
+                    ":fee": fees,
+                    ":created_at_time": created_at_time,
+                    ":memo": memo,
+                    ":icrc1_memo": icrc1_memo,
+                })
+                .map_err(|e| BlockStoreError::Other(e.to_string()))?;
+            }
             Operation::Transfer {
                 from,
                 to,
@@ -11,23 +11,27 @@ use crate::request::transaction_operation_results::TransactionOperationResults;
 use crate::request::transaction_results::TransactionResults;
 use crate::request::Request;
 use crate::request_types::{
-    ChangeAutoStakeMaturityMetadata, DisburseMetadata, FollowMetadata, KeyMetadata,
-    MergeMaturityMetadata, NeuronIdentifierMetadata, NeuronInfoMetadata, PublicKeyOrPrincipal,
-    RegisterVoteMetadata, RequestResultMetadata, SetDissolveTimestampMetadata, SpawnMetadata,
-    StakeMaturityMetadata, Status, STATUS_COMPLETED,
+    ApproveMetadata, ChangeAutoStakeMaturityMetadata, DisburseMetadata, FollowMetadata,
+    KeyMetadata, MergeMaturityMetadata, NeuronIdentifierMetadata, NeuronInfoMetadata,
+    PublicKeyOrPrincipal, RegisterVoteMetadata, RequestResultMetadata,
+    SetDissolveTimestampMetadata, SpawnMetadata, StakeMaturityMetadata, Status, STATUS_COMPLETED,
 };
 use crate::transaction_id::TransactionIdentifier;
 use crate::{convert, errors};
+use candid::Nat;
+use dfn_candid::CandidOne;
 use dfn_protobuf::ProtoBuf;
 use ic_canister_client_sender::Ed25519KeyPair as EdKeypair;
 use ic_canister_client_sender::Secp256k1KeyPair;
 use ic_crypto_tree_hash::Path;
 use ic_ledger_canister_blocks_synchronizer::blocks::HashedBlock;
 use ic_ledger_core::block::BlockType;
+use ic_ledger_core::timestamp::TimeStamp;
 use ic_ledger_hash_of::HashOf;
 use ic_types::messages::{HttpCanisterUpdate, HttpReadState};
 use ic_types::{CanisterId, PrincipalId};
 use icp_ledger::{Block, BlockIndex, Operation as LedgerOperation, SendArgs, Subaccount, Tokens};
+use icrc_ledger_types::icrc2::approve::ApproveArgs;
 use on_wire::{FromWire, IntoWire};
 use serde_json::map::Map;
 use serde_json::{from_value, Number, Value};
@@ -250,6 +254,34 @@ pub fn operations_to_requests(
                 };
                 state.neuron_info(account, principal, neuron_index)?;
             }
+            OperationType::Approve => {
+                if o.amount.is_some() {
+                    return Err(op_error(
+                        o,
+                        "APPROVE operations cannot have an amount".into(),
+                    ));
+                }
+                let ApproveMetadata {
+                    spender,
+                    allowance,
+                    expected_allowance,
+                    expires_at,
+                    ..
+                } = o.metadata.clone().try_into()?;
+                let allowance =
+                    ledgeramount_from_amount(&allowance, token_name).map_err(|e| op_error(o, e))?;
+                let expected_allowance = expected_allowance
+                    .map(|amount| ledgeramount_from_amount(&amount, token_name))
+                    .transpose()
+                    .map_err(|e| op_error(o, e))?;
+                state.approve(
+                    account,
+                    spender,
+                    allowance,
+                    expected_allowance,
+                    expires_at.map(TimeStamp::from_nanos_since_unix_epoch),
+                )?;
+            }
             OperationType::Burn | OperationType::Mint => {
                 let msg = format!("Unsupported operation type: {:?}", o._type);
                 return Err(op_error(o, msg));
@@ -401,6 +433,41 @@ pub fn to_arg(args: SendArgs) -> Vec<u8> {
     ProtoBuf(args).into_bytes().expect("Serialization failed")
 }
 
+pub fn from_approve_arg(encoded: Vec<u8>) -> Result<ApproveArgs, ApiError> {
+    CandidOne::from_bytes(encoded)
+        .map_err(ApiError::internal_error)
+        .map(|CandidOne(c)| c)
+}
+
+pub fn to_approve_arg(args: ApproveArgs) -> Vec<u8> {
+    CandidOne(args).into_bytes().expect("Serialization failed")
+}
+
+/// Returns the operation that an `icrc2_approve` call made by `caller` with
+/// the given arguments records on the ledger.
+pub fn approve_operation(
+    caller: PrincipalId,
+    args: &ApproveArgs,
+) -> Result<LedgerOperation, ApiError> {
+    let tokens = |n: &Nat| {
+        u64::try_from(n.0.clone())
+            .map(Tokens::from_e8s)
+            .map_err(|_| ApiError::invalid_request(format!("{} does not fit into Tokens", n)))
+    };
+    let fee = args
+        .fee
+        .as_ref()
+        .ok_or_else(|| ApiError::invalid_request("The approval does not specify a fee"))?;
+    Ok(LedgerOperation::Approve {
+        from: icp_ledger::AccountIdentifier::new(caller, args.from_subaccount.map(Subaccount)),
+        spender: icp_ledger::AccountIdentifier::from(args.spender),
+        allowance: tokens(&args.amount)?,
+        expected_allowance: args.expected_allowance.as_ref().map(tokens).transpose()?,
+        expires_at: args.expires_at.map(TimeStamp::from_nanos_since_unix_epoch),
+        fee: tokens(fee)?,
+    })
+}
+
 pub fn from_hash<T>(hash: &HashOf<T>) -> String {
     format!("{}", *hash)
 }
@@ -6,17 +6,19 @@ use crate::request_types::{
     PublicKeyOrPrincipal, RegisterVote, RemoveHotKey, SetDissolveTimestamp, Spawn, Stake,
     StakeMaturity, StartDissolve, StopDissolve,
 };
+use ic_ledger_core::timestamp::TimeStamp;
 use ic_types::PrincipalId;
 use icp_ledger::{Operation, Tokens, DEFAULT_TRANSFER_FEE};
 
 /// Helper for `from_operations` that creates `Transfer`s from related
-/// debit/credit/fee operations.
+/// debit/credit/fee operations, and approvals from approve/fee operations.
 pub struct State {
     preprocessing: bool,
     pub(crate) actions: Vec<Request>,
     credit: Option<AccountTokens>,
     debit: Option<AccountTokens>,
     fee: Option<AccountTokens>,
+    approval: Option<Approval>,
 }
 
 impl State {
@@ -33,6 +35,7 @@ impl State {
             credit,
             debit,
             fee,
+            approval: None,
         }
     }
 
@@ -45,6 +48,10 @@ impl State {
             Err(err)
         };
 
+        if let Some(approval) = self.approval.take() {
+            return self.flush_approval(approval);
+        }
+
         if self.credit.is_none() && self.debit.is_none() && self.fee.is_none() {
             return Ok(());
         }
@@ -98,11 +105,58 @@ impl State {
         Ok(())
     }
 
+    /// Create an `Approve` from the approve/fee operations seen previously.
+    fn flush_approval(&mut self, approval: Approval) -> Result<(), ApiError> {
+        let Approval {
+            from,
+            spender,
+            allowance,
+            expected_allowance,
+            expires_at,
+        } = approval;
+
+        // If you're preprocessing just continue with the default fee
+        if self.preprocessing && self.fee.is_none() {
+            self.fee = Some(AccountTokens {
+                tokens: DEFAULT_TRANSFER_FEE,
+                account: from,
+            })
+        }
+
+        let AccountTokens {
+            account: fee_acc,
+            tokens: fee,
+        } = self.fee.take().ok_or_else(|| {
+            ApiError::InvalidTransaction(
+                false,
+                "Bad transaction: An approval must be followed by a fee operation".into(),
+            )
+        })?;
+        if fee_acc != from {
+            let msg = format!("Bad transaction: Fee should be taken from {}", from);
+            return Err(ApiError::InvalidTransaction(false, msg.into()));
+        }
+
+        self.actions.push(Request::Transfer(Operation::Approve {
+            from,
+            spender,
+            allowance,
+            expected_allowance,
+            expires_at,
+            fee,
+        }));
+
+        Ok(())
+    }
+
     pub fn transaction(
         &mut self,
         account: icp_ledger::AccountIdentifier,
         amount: i128,
     ) -> Result<(), ApiError> {
+        if self.approval.is_some() {
+            self.flush()?;
+        }
         if amount > 0 || self.debit.is_some() && amount == 0 {
             if self.credit.is_some() {
                 self.flush()?;
@@ -138,6 +192,25 @@ impl State {
         Ok(())
     }
 
+    pub fn approve(
+        &mut self,
+        from: icp_ledger::AccountIdentifier,
+        spender: icp_ledger::AccountIdentifier,
+        allowance: Tokens,
+        expected_allowance: Option<Tokens>,
+        expires_at: Option<TimeStamp>,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.approval = Some(Approval {
+            from,
+            spender,
+            allowance,
+            expected_allowance,
+            expires_at,
+        });
+        Ok(())
+    }
+
     pub fn stake(
         &mut self,
         account: icp_ledger::AccountIdentifier,
@@ -384,3 +457,12 @@ pub struct AccountTokens {
     account: icp_ledger::AccountIdentifier,
     tokens: Tokens,
 }
+
+/// An approval waiting for the fee operation that completes it.
+struct Approval {
+    from: icp_ledger::AccountIdentifier,
+    spender: icp_ledger::AccountIdentifier,
+    allowance: Tokens,
+    expected_allowance: Option<Tokens>,
+    expires_at: Option<TimeStamp>,
+}
@@ -1,10 +1,11 @@
 use super::*;
 use crate::models::amount::signed_amount;
 use crate::models::operation::{OperationIdentifier, OperationType};
-use crate::request_types::Stake;
+use crate::request_types::{RequestType, Stake};
 use crate::DEFAULT_TOKEN_SYMBOL;
 use icp_ledger::AccountIdentifier;
 use icp_ledger::Operation as LedgerOperation;
+use icp_ledger::DEFAULT_TRANSFER_FEE;
 
 struct OperationBuilder(Operation);
 impl OperationBuilder {
@@ -35,6 +36,13 @@ impl OperationBuilder {
         })
     }
 
+    fn metadata(self, metadata: impl Into<models::Object>) -> Self {
+        Self(Operation {
+            metadata: Some(metadata.into()),
+            ..self.0
+        })
+    }
+
     fn neuron_index(self, neuron_index: u64) -> Self {
         let mut metadata = self.0.metadata.unwrap_or_default();
         metadata.insert(
@@ -179,6 +187,144 @@ fn test_can_handle_multiple_transfers() {
     );
 }
 
+fn test_approve() -> LedgerOperation {
+    LedgerOperation::Approve {
+        from: test_account(1),
+        spender: test_account(2),
+        allowance: Tokens::from_e8s(100),
+        expected_allowance: Some(Tokens::from_e8s(50)),
+        expires_at: Some(TimeStamp::from_nanos_since_unix_epoch(1_000)),
+        fee: Tokens::from_e8s(10),
+    }
+}
+
+fn test_approve_operations() -> Vec<Operation> {
+    vec![
+        OperationBuilder::new(0, OperationType::Approve)
+            .account(test_account(1))
+            .metadata(ApproveMetadata {
+                spender: test_account(2),
+                allowance: signed_amount(100, DEFAULT_TOKEN_SYMBOL),
+                expected_allowance: Some(signed_amount(50, DEFAULT_TOKEN_SYMBOL)),
+                expires_at: Some(1_000),
+                spender_principal: None,
+                spender_subaccount: None,
+            })
+            .build(),
+        OperationBuilder::new(1, OperationType::Fee)
+            .account(test_account(1))
+            .amount(-10)
+            .build(),
+    ]
+}
+
+#[test]
+fn test_approve_requests_to_operations() {
+    assert_eq!(
+        Request::requests_to_operations(&[Request::Transfer(test_approve())], DEFAULT_TOKEN_SYMBOL),
+        Ok(test_approve_operations())
+    );
+}
+
+#[test]
+fn test_approve_operations_to_requests() {
+    assert_eq!(
+        operations_to_requests(&test_approve_operations(), false, DEFAULT_TOKEN_SYMBOL),
+        Ok(vec![Request::Transfer(test_approve())])
+    );
+
+    // An approval followed by a transfer.
+    let mut ops = test_approve_operations();
+    ops.extend([
+        OperationBuilder::new(2, OperationType::Transaction)
+            .account(test_account(3))
+            .amount(-200)
+            .build(),
+        OperationBuilder::new(3, OperationType::Transaction)
+            .account(test_account(4))
+            .amount(200)
+            .build(),
+        OperationBuilder::new(4, OperationType::Fee)
+            .account(test_account(3))
+            .amount(-20)
+            .build(),
+    ]);
+    assert_eq!(
+        operations_to_requests(&ops, false, DEFAULT_TOKEN_SYMBOL),
+        Ok(vec![
+            Request::Transfer(test_approve()),
+            Request::Transfer(LedgerOperation::Transfer {
+                from: test_account(3),
+                to: test_account(4),
+                spender: None,
+                amount: Tokens::from_e8s(200),
+                fee: Tokens::from_e8s(20),
+            }),
+        ])
+    );
+}
+
+#[test]
+fn test_approve_operations_to_requests_fee() {
+    let approve = test_approve_operations()[0].clone();
+
+    // Only preprocessing falls back to the default fee.
+    assert!(operations_to_requests(&[approve.clone()], false, DEFAULT_TOKEN_SYMBOL).is_err());
+    match operations_to_requests(&[approve.clone()], true, DEFAULT_TOKEN_SYMBOL)
+        .unwrap()
+        .as_slice()
+    {
+        [Request::Transfer(LedgerOperation::Approve { fee, .. })] => {
+            assert_eq!(*fee, DEFAULT_TRANSFER_FEE)
+        }
+        requests => panic!("Unexpected requests: {:?}", requests),
+    }
+
+    let fee_from_spender = OperationBuilder::new(1, OperationType::Fee)
+        .account(test_account(2))
+        .amount(-10)
+        .build();
+    assert!(operations_to_requests(
+        &[approve.clone(), fee_from_spender],
+        false,
+        DEFAULT_TOKEN_SYMBOL
+    )
+    .is_err());
+
+    let with_amount = Operation {
+        amount: Some(signed_amount(100, DEFAULT_TOKEN_SYMBOL)),
+        ..approve
+    };
+    assert!(operations_to_requests(
+        &[with_amount, test_approve_operations()[1].clone()],
+        false,
+        DEFAULT_TOKEN_SYMBOL
+    )
+    .is_err());
+}
+
+#[test]
+fn test_approve_request_type_and_serialization() {
+    let request = Request::Transfer(test_approve());
+    assert_eq!(request.request_type(), Ok(RequestType::Approve));
+
+    let json = serde_json::to_value(&request).unwrap();
+    assert_eq!(json["type"], "TRANSACTION");
+    assert_eq!(json["spender"], test_account(2).to_hex());
+    assert_eq!(serde_json::from_value::<Request>(json).unwrap(), request);
+
+    // Transfers still deserialize as transfers.
+    let transfer = Request::Transfer(LedgerOperation::Transfer {
+        from: test_account(1),
+        to: test_account(2),
+        spender: None,
+        amount: Tokens::from_e8s(100),
+        fee: Tokens::from_e8s(10),
+    });
+    let json = serde_json::to_value(&transfer).unwrap();
+    assert_eq!(serde_json::from_value::<Request>(json).unwrap(), transfer);
+}
+
 #[test]
 fn account_identifier_decode_test() {
     // a good address
@@ -0,0 +1,20 @@
+use crate::errors::ApiError;
+use crate::ledger_client::OperationOutput;
+use candid::Nat;
+use icrc_ledger_types::icrc2::approve::ApproveError;
+
+pub fn handle_approve(bytes: Vec<u8>) -> Result<Result<Option<OperationOutput>, ApiError>, String> {
+    let response: Result<Nat, ApproveError> = candid::decode_one(bytes.as_ref())
+        .map_err(|err| format!("Could not decode APPROVE response : {}", err))?;
+    match response {
+        Ok(block_index) => {
+            let block_index = u64::try_from(block_index.0)
+                .map_err(|err| format!("Invalid block index in APPROVE response: {}", err))?;
+            Ok(Ok(Some(OperationOutput::BlockIndex(block_index))))
+        }
+        Err(err) => Ok(Err(ApiError::TransactionRejected(
+            false,
+            format!("Could not approve: {:?}", err).into(),
+        ))),
+    }
+}
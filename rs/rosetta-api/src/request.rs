@@ -25,7 +25,7 @@ pub mod transaction_results;
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(tag = "type")]
 pub enum Request {
-    /// Contains `Send`, `Mint`, and `Burn` operations.
+    /// Contains `Send`, `Approve`, `Mint`, and `Burn` operations.
     /// Attempting to serialize or deserialize any Mint, or Burn will error.
     #[serde(rename = "TRANSACTION")]
     #[serde(with = "serde_transfer")]
@@ -112,9 +112,7 @@ impl Request {
             Request::Transfer(icp_ledger::Operation::Mint { .. }) => Err(
                 ApiError::invalid_request("Mint operations are not supported through Rosetta"),
             ),
-            Request::Transfer(icp_ledger::Operation::Approve { .. }) => Err(
-                ApiError::invalid_request("Approve operations are not supported through Rosetta"),
-            ),
+            Request::Transfer(icp_ledger::Operation::Approve { .. }) => Ok(RequestType::Approve),
             Request::Spawn(Spawn { neuron_index, .. }) => Ok(RequestType::Spawn {
                 neuron_index: *neuron_index,
             }),
@@ -253,6 +251,10 @@ impl TryFrom<&models::Request> for Request {
                     fee,
                 }))
             }
+            RequestType::Approve => {
+                let args = convert::from_approve_arg(payload.update_content().arg.0.clone())?;
+                Ok(Request::Transfer(convert::approve_operation(pid, &args)?))
+            }
             RequestType::Stake { neuron_index } => Ok(Request::Stake(Stake {
                 account,
                 neuron_index: *neuron_index,
@@ -1,20 +1,28 @@
+use ic_ledger_core::timestamp::TimeStamp;
 use icp_ledger::{AccountIdentifier, Operation, Tokens};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::convert::TryFrom;
 
 /// A helper for serializing `TransactionResults`
 pub fn deserialize<'d, D: Deserializer<'d>>(d: D) -> Result<Operation, D::Error> {
-    Send::deserialize(d)
+    Transfer::deserialize(d)
         .map(Operation::from)
         .map_err(D::Error::from)
 }
 
 pub fn serialize<S: Serializer>(t: &Operation, s: S) -> Result<S::Ok, S::Error> {
-    Send::try_from(t)
+    Transfer::try_from(t)
         .map_err(serde::ser::Error::custom)
         .and_then(|t| t.serialize(s))
 }
 
+#[derive(Copy, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+enum Transfer {
+    Send(Send),
+    Approve(Approve),
+}
+
 #[derive(Copy, Clone, Deserialize, Serialize)]
 struct Send {
     from: AccountIdentifier,
@@ -23,7 +31,21 @@ struct Send {
     fee: Tokens,
 }
 
-impl TryFrom<&Operation> for Send {
+#[derive(Copy, Clone, Deserialize, Serialize)]
+struct Approve {
+    from: AccountIdentifier,
+    spender: AccountIdentifier,
+    allowance: Tokens,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected_allowance: Option<Tokens>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<TimeStamp>,
+    fee: Tokens,
+}
+
+impl TryFrom<&Operation> for Transfer {
     type Error = String;
 
     fn try_from(transfer: &Operation) -> Result<Self, String> {
@@ -40,16 +62,28 @@ impl TryFrom<&Operation> for Send {
                         "Transfer from operations are not supported through Rosetta".to_string()
                     );
                 }
-                Ok(Send {
+                Ok(Transfer::Send(Send {
                     from,
                     to,
                     amount,
                     fee,
-                })
-            }
-            Operation::Approve { .. } => {
-                Err("Approve operations are not supported through Rosetta".to_string())
+                }))
             }
+            Operation::Approve {
+                from,
+                spender,
+                allowance,
+                expected_allowance,
+                expires_at,
+                fee,
+            } => Ok(Transfer::Approve(Approve {
+                from,
+                spender,
+                allowance,
+                expected_allowance,
+                expires_at,
+                fee,
+            })),
             Operation::Burn { .. } => {
                 Err("Burn operations are not supported through rosetta".to_owned())
             }
@@ -60,20 +94,36 @@ impl TryFrom<&Operation> for Send {
     }
 }
 
-impl From<Send> for Operation {
-    fn from(s: Send) -> Self {
-        let Send {
-            from,
-            to,
-            amount,
-            fee,
-        } = s;
-        Operation::Transfer {
-            from,
-            to,
-            spender: None,
-            amount,
-            fee,
+impl From<Transfer> for Operation {
+    fn from(t: Transfer) -> Self {
+        match t {
+            Transfer::Send(Send {
+                from,
+                to,
+                amount,
+                fee,
+            }) => Operation::Transfer {
+                from,
+                to,
+                spender: None,
+                amount,
+                fee,
+            },
+            Transfer::Approve(Approve {
+                from,
+                spender,
+                allowance,
+                expected_allowance,
+                expires_at,
+                fee,
+            }) => Operation::Approve {
+                from,
+                spender,
+                allowance,
+                expected_allowance,
+                expires_at,
+                fee,
+            },
         }
     }
 }
@@ -45,15 +45,16 @@ impl RosettaRequestHandler {
         let mut from_ai = vec![];
 
         for (request_type, HttpCanisterUpdate { arg, sender, .. }) in updates {
-            let from = PrincipalId::try_from(sender.0)
-                .map_err(|e| ApiError::internal_error(e.to_string()))?
-                .into();
+            let caller = PrincipalId::try_from(sender.0)
+                .map_err(|e| ApiError::internal_error(e.to_string()))?;
+            let from = caller.into();
             if msg.signed {
                 from_ai.push(from);
             }
 
             match request_type {
                 RequestType::Send => send(&mut requests, arg, from)?,
+                RequestType::Approve => approve(&mut requests, arg, caller)?,
                 RequestType::Stake { neuron_index } => {
                     stake(&mut requests, arg, from, neuron_index)?
                 }
@@ -129,6 +130,15 @@ fn send(requests: &mut Vec<Request>, arg: Blob, from: AccountIdentifier) -> Resu
     Ok(())
 }
 
+/// Handle APPROVE.
+fn approve(requests: &mut Vec<Request>, arg: Blob, caller: PrincipalId) -> Result<(), ApiError> {
+    let args = convert::from_approve_arg(arg.0)?;
+    requests.push(Request::Transfer(convert::approve_operation(
+        caller, &args,
+    )?));
+    Ok(())
+}
+
 /// Handle STAKE.
 fn stake(
     requests: &mut Vec<Request>,
@@ -1,8 +1,12 @@
+use candid::Nat;
 use dfn_candid::CandidOne;
+use ic_ledger_core::timestamp::TimeStamp;
 use ic_nns_common::pb::v1::NeuronId;
 use ic_types::messages::{Blob, HttpCanisterUpdate, MessageId};
 use ic_types::PrincipalId;
 use icp_ledger::{Memo, Operation, SendArgs, Tokens};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc2::approve::ApproveArgs;
 use on_wire::IntoWire;
 use rand::Rng;
 use std::collections::HashMap;
@@ -17,6 +21,7 @@ use ic_nns_governance::pb::v1::{
 use crate::convert::{make_read_state_from_update, to_arg, to_model_account_identifier};
 use crate::errors::ApiError;
 use crate::ledger_client::LedgerAccess;
+use crate::models::operation::OperationType;
 use crate::models::{
     AccountIdentifier, ConstructionPayloadsRequest, ConstructionPayloadsResponse, PublicKey,
     SignatureType, SigningPayload, UnsignedTransaction,
@@ -24,9 +29,9 @@ use crate::models::{
 use crate::request::Request;
 use crate::request_handler::{make_sig_data, verify_network_id, RosettaRequestHandler};
 use crate::request_types::{
-    AddHotKey, ChangeAutoStakeMaturity, Disburse, Follow, MergeMaturity, NeuronInfo,
-    PublicKeyOrPrincipal, RegisterVote, RemoveHotKey, RequestType, SetDissolveTimestamp, Spawn,
-    Stake, StakeMaturity, StartDissolve, StopDissolve,
+    AddHotKey, ApproveMetadata, ChangeAutoStakeMaturity, Disburse, Follow, MergeMaturity,
+    NeuronInfo, PublicKeyOrPrincipal, RegisterVote, RemoveHotKey, RequestType,
+    SetDissolveTimestamp, Spawn, Stake, StakeMaturity, StartDissolve, StopDissolve,
 };
 use crate::{convert, models};
 
@@ -99,6 +104,8 @@ impl RosettaRequestHandler {
             })
             .collect::<Result<HashMap<_, _>, ApiError>>()?;
 
+        let spenders = approve_spenders(&ops)?;
+
         for t in transactions {
             match t {
                 Request::Transfer(req) => handle_transfer(
@@ -109,6 +116,7 @@ impl RosettaRequestHandler {
                     &mut payloads,
                     &mut updates,
                     &pks_map,
+                    &spenders,
                     &ingress_expiries,
                 )?,
                 Request::NeuronInfo(req) => handle_neuron_info(
@@ -222,7 +230,39 @@ impl RosettaRequestHandler {
     }
 }
 
+/// The ICRC-1 accounts of the spenders of the APPROVE operations, keyed by
+/// their account identifiers. The ledger identifies a spender by principal
+/// and subaccount, which cannot be recovered from an account identifier.
+fn approve_spenders(
+    ops: &[models::Operation],
+) -> Result<HashMap<icp_ledger::AccountIdentifier, Account>, ApiError> {
+    let mut spenders = HashMap::new();
+    for op in ops.iter().filter(|op| op._type == OperationType::Approve) {
+        let ApproveMetadata {
+            spender,
+            spender_principal,
+            spender_subaccount,
+            ..
+        } = op.metadata.clone().try_into()?;
+        if let Some(principal) = spender_principal {
+            let account = Account {
+                owner: principal.0,
+                subaccount: spender_subaccount.map(|subaccount| subaccount.0),
+            };
+            if icp_ledger::AccountIdentifier::from(account) != spender {
+                return Err(ApiError::invalid_request(format!(
+                    "The spender principal and subaccount do not match the spender {}",
+                    spender
+                )));
+            }
+            spenders.insert(spender, account);
+        }
+    }
+    Ok(spenders)
+}
+
 /// Handle TRANSFER.
+#[allow(clippy::too_many_arguments)]
 fn handle_transfer(
     req: Operation,
     memo: Memo,
@@ -231,6 +271,7 @@ fn handle_transfer(
     payloads: &mut Vec<SigningPayload>,
     updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
     pks_map: &HashMap<icp_ledger::AccountIdentifier, &PublicKey>,
+    spenders: &HashMap<icp_ledger::AccountIdentifier, Account>,
     ingress_expiries: &[u64],
 ) -> Result<(), ApiError> {
     match req {
@@ -240,9 +281,36 @@ fn handle_transfer(
         Operation::Mint { .. } => Err(ApiError::invalid_request(
             "Mint operations are not supported through Rosetta.",
         )),
-        Operation::Approve { .. } => Err(ApiError::invalid_request(
-            "Approve operations are not supported through Rosetta.",
-        )),
+        Operation::Approve {
+            from,
+            spender,
+            allowance,
+            expected_allowance,
+            expires_at,
+            fee,
+        } => {
+            let spender = spenders.get(&spender).ok_or_else(|| {
+                ApiError::invalid_request(format!(
+                    "Approving {} requires the spender_principal in the APPROVE metadata.",
+                    spender
+                ))
+            })?;
+            handle_approve_operation(
+                from,
+                *spender,
+                allowance,
+                expected_allowance,
+                expires_at,
+                fee,
+                memo,
+                created_at_time,
+                ledger,
+                payloads,
+                updates,
+                pks_map,
+                ingress_expiries,
+            )
+        }
         Operation::Transfer {
             from,
             to,
@@ -324,6 +392,69 @@ fn handle_transfer_operation(
     Ok(())
 }
 
+/// Handle APPROVE.
+#[allow(clippy::too_many_arguments)]
+fn handle_approve_operation(
+    from: icp_ledger::AccountIdentifier,
+    spender: Account,
+    allowance: Tokens,
+    expected_allowance: Option<Tokens>,
+    expires_at: Option<TimeStamp>,
+    fee: Tokens,
+    memo: Memo,
+    created_at_time: TimeStamp,
+    ledger: &Arc<dyn LedgerAccess + Send + Sync>,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<icp_ledger::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let pk = pks_map.get(&from).ok_or_else(|| {
+        ApiError::internal_error(format!(
+            "Cannot find public key for account identifier {}",
+            from,
+        ))
+    })?;
+
+    // Without an explicit expiration the ledger records one relative to the
+    // time it applies the approval, so the transaction ID could not be
+    // computed in advance.
+    let expires_at = expires_at.ok_or_else(|| {
+        ApiError::invalid_request("Approvals constructed through Rosetta must set expires_at.")
+    })?;
+
+    let approve_args = ApproveArgs {
+        from_subaccount: None,
+        spender,
+        amount: Nat::from(allowance.get_e8s()),
+        expected_allowance: expected_allowance.map(|tokens| Nat::from(tokens.get_e8s())),
+        expires_at: Some(expires_at.as_nanos_since_unix_epoch()),
+        fee: Some(Nat::from(fee.get_e8s())),
+        memo: Some(memo.0.into()),
+        created_at_time: Some(created_at_time.as_nanos_since_unix_epoch()),
+    };
+
+    let update = HttpCanisterUpdate {
+        canister_id: Blob(ledger.ledger_canister_id().get().to_vec()),
+        method_name: "icrc2_approve".to_string(),
+        arg: Blob(convert::to_approve_arg(approve_args)),
+        // Like transfers, approvals are deduplicated by the ledger, so we
+        // never want two of them with identical tx IDs to land on chain.
+        nonce: None,
+        sender: Blob(convert::principal_id_from_public_key(pk)?.into_vec()),
+        ingress_expiry: 0,
+    };
+
+    add_payloads(
+        payloads,
+        ingress_expiries,
+        &convert::to_model_account_identifier(&from),
+        &update,
+    );
+    updates.push((RequestType::Approve, update));
+    Ok(())
+}
+
 /// Handle NEURON_INFO.
 fn handle_neuron_info(
     req: NeuronInfo,
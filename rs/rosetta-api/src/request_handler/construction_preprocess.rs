@@ -62,9 +62,7 @@ fn required_public_key(request: Request) -> Result<icp_ledger::AccountIdentifier
         Request::Transfer(Operation::Mint { .. }) => Err(ApiError::invalid_request(
             "Mint operations are not supported through rosetta",
         )),
-        Request::Transfer(Operation::Approve { .. }) => Err(ApiError::invalid_request(
-            "Approve operations are not supported through rosetta",
-        )),
+        Request::Transfer(Operation::Approve { from, .. }) => Ok(from),
         Request::Stake(Stake { account, .. })
         | Request::SetDissolveTimestamp(SetDissolveTimestamp { account, .. })
         | Request::ChangeAutoStakeMaturity(ChangeAutoStakeMaturity { account, .. })
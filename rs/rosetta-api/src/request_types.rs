@@ -1,4 +1,4 @@
-use crate::models::amount::{signed_amount, tokens_to_amount};
+use crate::models::amount::{signed_amount, tokens_to_amount, Amount};
 use crate::models::operation::{OperationIdentifier, OperationType};
 use crate::models::seconds::Seconds;
 use crate::{
@@ -7,8 +7,9 @@ use crate::{
     models::{self, operation::Operation, Object},
     transaction_id::TransactionIdentifier,
 };
+use ic_ledger_core::timestamp::TimeStamp;
 use ic_types::PrincipalId;
-use icp_ledger::{AccountIdentifier, BlockIndex, Operation as LedgerOperation, Tokens};
+use icp_ledger::{AccountIdentifier, BlockIndex, Operation as LedgerOperation, Subaccount, Tokens};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::convert::TryFrom;
@@ -36,6 +37,7 @@ pub const REGISTER_VOTE: &str = "REGISTER_VOTE";
 pub const STAKE_MATURITY: &str = "STAKE_MATURITY";
 pub const NEURON_INFO: &str = "NEURON_INFO";
 pub const FOLLOW: &str = "FOLLOW";
+pub const APPROVE: &str = "APPROVE";
 
 /// `RequestType` contains all supported values of `Operation.type`.
 /// Extra information, such as `neuron_index` should only be included
@@ -94,6 +96,9 @@ pub enum RequestType {
         neuron_index: u64,
         controller: Option<PublicKeyOrPrincipal>,
     },
+    #[serde(rename = "APPROVE")]
+    #[serde(alias = "Approve")]
+    Approve,
 }
 
 impl RequestType {
@@ -114,11 +119,14 @@ impl RequestType {
             RequestType::StakeMaturity { .. } => STAKE_MATURITY,
             RequestType::NeuronInfo { .. } => NEURON_INFO,
             RequestType::Follow { .. } => FOLLOW,
+            RequestType::Approve => APPROVE,
         }
     }
 
+    /// Whether the request results in a ledger transaction, and therefore
+    /// has a transaction identifier.
     pub const fn is_transfer(&self) -> bool {
-        matches!(self, RequestType::Send)
+        matches!(self, RequestType::Send | RequestType::Approve)
     }
 
     pub const fn is_neuron_management(&self) -> bool {
@@ -512,6 +520,55 @@ impl From<DisburseMetadata> for Object {
     }
 }
 
+/// The metadata of an APPROVE operation.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ApproveMetadata {
+    pub spender: AccountIdentifier,
+
+    pub allowance: Amount,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_allowance: Option<Amount>,
+
+    /// The expiration of the approval in nanoseconds since the Unix epoch.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+
+    /// The principal owning `spender`. The ledger identifies spenders by
+    /// principal and subaccount, so constructing an approval requires it.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spender_principal: Option<PrincipalId>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spender_subaccount: Option<Subaccount>,
+}
+
+impl TryFrom<Option<Object>> for ApproveMetadata {
+    type Error = ApiError;
+
+    fn try_from(o: Option<Object>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse APPROVE operation metadata from a JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl From<ApproveMetadata> for Object {
+    fn from(m: ApproveMetadata) -> Self {
+        match serde_json::to_value(m) {
+            Ok(Value::Object(o)) => o,
+            _ => unreachable!(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 pub struct KeyMetadata {
     #[serde(flatten)]
@@ -856,7 +913,7 @@ impl TransactionBuilder {
     }
 
     /// Add a `Request::Transfer` to the Transaction.
-    /// This handles `Send`, `Mint`, and `Burn`.
+    /// This handles `Send`, `Mint`, `Burn`, and `Approve`.
     pub fn transfer(
         &mut self,
         operation: &LedgerOperation,
@@ -884,11 +941,22 @@ impl TransactionBuilder {
                 push_op(OperationType::Mint, to, i128::from(amount.get_e8s()));
             }
             LedgerOperation::Approve {
-                from, spender, fee, ..
+                from,
+                spender,
+                allowance,
+                expected_allowance,
+                expires_at,
+                fee,
             } => {
-                push_op(OperationType::Transaction, from, 0);
-                push_op(OperationType::Transaction, spender, 0);
-                push_op(OperationType::Fee, from, -i128::from(fee.get_e8s()));
+                self.approve(
+                    from,
+                    spender,
+                    *allowance,
+                    *expected_allowance,
+                    *expires_at,
+                    *fee,
+                    token_name,
+                )?;
             }
             LedgerOperation::Transfer {
                 from,
@@ -906,6 +974,53 @@ impl TransactionBuilder {
         Ok(())
     }
 
+    /// An approval is an APPROVE operation on the approver's account,
+    /// followed by the FEE the approver pays.
+    fn approve(
+        &mut self,
+        from: &AccountIdentifier,
+        spender: &AccountIdentifier,
+        allowance: Tokens,
+        expected_allowance: Option<Tokens>,
+        expires_at: Option<TimeStamp>,
+        fee: Tokens,
+        token_name: &str,
+    ) -> Result<(), ApiError> {
+        let metadata = ApproveMetadata {
+            spender: *spender,
+            allowance: tokens_to_amount(allowance, token_name)?,
+            expected_allowance: expected_allowance
+                .map(|tokens| tokens_to_amount(tokens, token_name))
+                .transpose()?,
+            expires_at: expires_at.map(|t| t.as_nanos_since_unix_epoch()),
+            spender_principal: None,
+            spender_subaccount: None,
+        };
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::Approve,
+            status: None,
+            account: Some(to_model_account_identifier(from)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(metadata.into()),
+        });
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::Fee,
+            status: None,
+            account: Some(to_model_account_identifier(from)),
+            amount: Some(signed_amount(-i128::from(fee.get_e8s()), token_name)),
+            related_operations: None,
+            coin_change: None,
+            metadata: None,
+        });
+        Ok(())
+    }
+
     pub fn stake(&mut self, stake: &Stake) {
         let Stake {
             account,
@@ -1,12 +1,13 @@
 use std::{convert::TryFrom, str::FromStr};
 
 use ic_ledger_canister_core::ledger::LedgerTransaction;
+use ic_ledger_core::timestamp::TimeStamp;
 use ic_ledger_hash_of::HashOf;
 use ic_types::{
     messages::{HttpCallContent, HttpRequestEnvelope},
     PrincipalId,
 };
-use icp_ledger::{SendArgs, Transaction};
+use icp_ledger::{Memo, SendArgs, Transaction};
 use serde::{Deserialize, Serialize};
 
 use crate::{convert, errors::ApiError, request_types::RequestType};
@@ -65,6 +66,29 @@ impl TransactionIdentifier {
 
                 Ok(TransactionIdentifier::from(&hash))
             }
+            RequestType::Approve => {
+                let HttpCallContent::Call { update } = &signed_transaction.content;
+                let caller = PrincipalId::try_from(update.sender.clone().0)
+                    .map_err(|e| ApiError::internal_error(e.to_string()))?;
+                let args = convert::from_approve_arg(update.arg.clone().0)?;
+                let created_at_time = args.created_at_time.ok_or_else(|| ApiError::internal_error(
+                    "A transaction ID cannot be generated from a constructed approval without an explicit 'created_at_time'"
+                ))?;
+                if args.expires_at.is_none() {
+                    return Err(ApiError::internal_error(
+                        "A transaction ID cannot be generated from a constructed approval without an explicit 'expires_at'"
+                    ));
+                }
+
+                let transaction = Transaction {
+                    operation: convert::approve_operation(caller, &args)?,
+                    memo: Memo(0),
+                    created_at_time: Some(TimeStamp::from_nanos_since_unix_epoch(created_at_time)),
+                    icrc1_memo: args.memo.map(|memo| memo.0),
+                };
+
+                Ok(TransactionIdentifier::from(&transaction))
+            }
             RequestType::Stake { .. }
             | RequestType::StartDissolve { .. }
             | RequestType::StopDissolve { .. }
@@ -6,18 +6,25 @@ use ic_ledger_core::block::BlockType;
 use ic_ledger_core::tokens::CheckedAdd;
 use ic_rosetta_api::convert::{block_id, from_hash, to_hash};
 use ic_rosetta_api::ledger_client::LedgerAccess;
-use ic_rosetta_api::models::amount::{tokens_to_amount, Amount};
+use ic_rosetta_api::models::amount::{signed_amount, tokens_to_amount, Amount};
+use ic_rosetta_api::models::operation::{OperationIdentifier, OperationType};
 use ic_rosetta_api::models::{
     AccountBalanceResponse, BlockIdentifier, BlockRequest, BlockTransaction,
     BlockTransactionRequest, ConstructionDeriveRequest, ConstructionDeriveResponse,
-    ConstructionMetadataRequest, ConstructionMetadataResponse, Currency, CurveType,
-    MempoolResponse, MempoolTransactionRequest, MetadataRequest, NetworkListResponse,
-    NetworkRequest, NetworkStatusResponse, SearchTransactionsRequest, SearchTransactionsResponse,
-    SyncStatus,
+    ConstructionMetadataRequest, ConstructionMetadataResponse, ConstructionParseRequest,
+    ConstructionPayloadsRequest, ConstructionPayloadsRequestMetadata,
+    ConstructionPreprocessRequest, Currency, CurveType, MempoolResponse, MempoolTransactionRequest,
+    MetadataRequest, NetworkListResponse, NetworkRequest, NetworkStatusResponse,
+    SearchTransactionsRequest, SearchTransactionsResponse, SyncStatus, UnsignedTransaction,
 };
 use ic_rosetta_api::request_handler::RosettaRequestHandler;
+use ic_rosetta_api::request_types::ApproveMetadata;
 use ic_rosetta_api::transaction_id::TransactionIdentifier;
 use ic_rosetta_api::{models, API_VERSION, NODE_VERSION};
+use ic_types::messages::{HttpCallContent, HttpRequestEnvelope};
+use icp_ledger::{Memo, Subaccount, Transaction};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc2::approve::ApproveArgs;
 use std::sync::Arc;
 
 #[actix_rt::test]
@@ -794,3 +801,182 @@ async fn store_batch_test() {
     blocks.set_hashed_block_to_verified(&last_idx).unwrap();
     verify_balances(&scribe, &blocks, 0);
 }
+
+#[actix_rt::test]
+async fn approve_construction_test() {
+    init_test_logger();
+
+    let ledger = Arc::new(TestLedger::default());
+    let req_handler = RosettaRequestHandler::new_with_default_blockchain(ledger);
+
+    let (from, _from_kp, from_pk, _from_pid) = ic_rosetta_test_utils::make_user_ed25519(1);
+    let (spender, _spender_kp, _spender_pk, spender_pid) =
+        ic_rosetta_test_utils::make_user_ed25519(2);
+    let (_other, _other_kp, _other_pk, other_pid) = ic_rosetta_test_utils::make_user_ed25519(3);
+    let allowance = Tokens::from_e8s(100_000);
+    let expires_at = 1_000_000_000;
+    let created_at_time = 500;
+    let memo = 7;
+
+    let metadata = ApproveMetadata {
+        spender,
+        allowance: tokens_to_amount(allowance, DEFAULT_TOKEN_SYMBOL).unwrap(),
+        expected_allowance: None,
+        expires_at: Some(expires_at),
+        spender_principal: Some(spender_pid),
+        spender_subaccount: None,
+    };
+    let approve_ops = |metadata: ApproveMetadata| {
+        vec![
+            models::Operation {
+                operation_identifier: OperationIdentifier::new(0),
+                _type: OperationType::Approve,
+                status: None,
+                account: Some(to_model_account_identifier(&from)),
+                amount: None,
+                related_operations: None,
+                coin_change: None,
+                metadata: Some(metadata.into()),
+            },
+            models::Operation {
+                operation_identifier: OperationIdentifier::new(1),
+                _type: OperationType::Fee,
+                status: None,
+                account: Some(to_model_account_identifier(&from)),
+                amount: Some(signed_amount(
+                    -i128::from(DEFAULT_TRANSFER_FEE.get_e8s()),
+                    DEFAULT_TOKEN_SYMBOL,
+                )),
+                related_operations: None,
+                coin_change: None,
+                metadata: None,
+            },
+        ]
+    };
+    let payloads_request = |metadata: ApproveMetadata| {
+        let mut msg =
+            ConstructionPayloadsRequest::new(req_handler.network_id(), approve_ops(metadata));
+        msg.public_keys = Some(vec![from_pk.clone()]);
+        msg.metadata = Some(ConstructionPayloadsRequestMetadata {
+            memo: Some(memo),
+            created_at_time: Some(created_at_time),
+            ..Default::default()
+        });
+        msg
+    };
+    let approve = Operation::Approve {
+        from,
+        spender,
+        allowance,
+        expected_allowance: None,
+        expires_at: Some(TimeStamp::from_nanos_since_unix_epoch(expires_at)),
+        fee: DEFAULT_TRANSFER_FEE,
+    };
+
+    let res = req_handler
+        .construction_preprocess(ConstructionPreprocessRequest::new(
+            req_handler.network_id(),
+            approve_ops(metadata.clone()),
+        ))
+        .unwrap();
+    assert_eq!(
+        res.required_public_keys,
+        Some(vec![to_model_account_identifier(&from)])
+    );
+    assert_eq!(
+        res.options.unwrap().request_types,
+        vec![RequestType::Approve]
+    );
+
+    let res = req_handler
+        .construction_payloads(payloads_request(metadata.clone()))
+        .unwrap();
+    let unsigned: UnsignedTransaction =
+        serde_cbor::from_slice(&hex::decode(&res.unsigned_transaction).unwrap()).unwrap();
+    let (request_type, update) = match unsigned.updates.as_slice() {
+        [update] => update.clone(),
+        updates => panic!("Expected a single update, got {:?}", updates),
+    };
+    assert_eq!(request_type, RequestType::Approve);
+    assert_eq!(update.method_name, "icrc2_approve");
+    let args: ApproveArgs = candid::decode_one(&update.arg.0).unwrap();
+    assert_eq!(
+        args.spender,
+        Account {
+            owner: spender_pid.0,
+            subaccount: None,
+        }
+    );
+    assert_eq!(args.amount, candid::Nat::from(allowance.get_e8s()));
+    assert_eq!(args.expected_allowance, None);
+    assert_eq!(args.expires_at, Some(expires_at));
+    assert_eq!(
+        args.fee,
+        Some(candid::Nat::from(DEFAULT_TRANSFER_FEE.get_e8s()))
+    );
+    assert_eq!(args.created_at_time, Some(created_at_time));
+
+    // Parsing the unsigned transaction gives back the approval.
+    let res = req_handler
+        .construction_parse(ConstructionParseRequest::new(
+            req_handler.network_id(),
+            false,
+            res.unsigned_transaction,
+        ))
+        .unwrap();
+    assert_eq!(
+        res.operations,
+        Request::requests_to_operations(
+            &[Request::Transfer(approve.clone())],
+            DEFAULT_TOKEN_SYMBOL
+        )
+        .unwrap()
+    );
+
+    // The transaction identifier is the hash of the transaction recorded by
+    // the ledger.
+    let envelope = HttpRequestEnvelope {
+        content: HttpCallContent::Call { update },
+        sender_pubkey: None,
+        sender_sig: None,
+        sender_delegation: None,
+    };
+    let transaction = Transaction {
+        operation: approve,
+        memo: Memo(0),
+        created_at_time: Some(TimeStamp::from_nanos_since_unix_epoch(created_at_time)),
+        icrc1_memo: args.memo.map(|memo| memo.0),
+    };
+    assert_eq!(
+        TransactionIdentifier::try_from_envelope(RequestType::Approve, &envelope).unwrap(),
+        TransactionIdentifier::from(&transaction)
+    );
+
+    // The spender principal is required and must match the spender.
+    for metadata in [
+        ApproveMetadata {
+            spender_principal: None,
+            ..metadata.clone()
+        },
+        ApproveMetadata {
+            spender_principal: Some(other_pid),
+            ..metadata.clone()
+        },
+        ApproveMetadata {
+            spender_subaccount: Some(Subaccount([1; 32])),
+            ..metadata.clone()
+        },
+        ApproveMetadata {
+            expires_at: None,
+            ..metadata.clone()
+        },
+    ] {
+        let res = req_handler.construction_payloads(payloads_request(metadata.clone()));
+        assert!(
+            matches!(res, Err(ApiError::InvalidRequest(..))),
+            "Expected {:?} to be rejected, got {:?}",
+            metadata,
+            res
+        );
+    }
+}
@@ -2,6 +2,7 @@ use crate::error::LayoutError;
 use crate::utils::do_copy;
 
 use ic_base_types::{NumBytes, NumSeconds};
+use ic_ic00_types::LogVisibility;
 use ic_logger::{error, info, ReplicaLogger};
 use ic_metrics::{buckets::decimal_buckets, MetricsRegistry};
 use ic_protobuf::{
@@ -14,14 +15,16 @@ use ic_protobuf::{
 use ic_replicated_state::{
     canister_state::{
         execution_state::{NextScheduledMethod, WasmMetadata},
-        system_state::{CanisterHistory, CyclesUseCase},
+        system_state::{CanisterHistory, CyclesUseCase, WasmChunkStoreMetadata},
     },
     CallContextManager, CanisterStatus, ExecutionTask, ExportedFunctions, Global, NumWasmPages,
+    SnapshotId, TotalQueryStats,
 };
 use ic_sys::mmap::ScopedMmap;
 use ic_types::{
-    nominal_cycles::NominalCycles, AccumulatedPriority, CanisterId, ComputeAllocation, Cycles,
-    ExecutionRound, Height, MemoryAllocation, NumInstructions, PrincipalId,
+    canister_log::CanisterLog, nominal_cycles::NominalCycles, AccumulatedPriority, CanisterId,
+    CanisterTimer, ComputeAllocation, Cycles, ExecutionRound, Height, MemoryAllocation,
+    NumInstructions, PrincipalId, Time,
 };
 use ic_utils::fs::sync_path;
 use ic_utils::thread::parallel_map;
@@ -46,6 +49,8 @@ pub const CHECKPOINTS_DIR: &str = "checkpoints";
 pub const CANISTER_STATES_DIR: &str = "canister_states";
 pub const QUEUES_FILE: &str = "queues.pbuf";
 pub const CANISTER_FILE: &str = "canister.pbuf";
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+pub const SNAPSHOT_FILE: &str = "snapshot.pbuf";
 pub const INGRESS_HISTORY_FILE: &str = "ingress_history.pbuf";
 pub const SPLIT_MARKER_FILE: &str = "split_from.pbuf";
 pub const SUBNET_QUEUES_FILE: &str = "subnet_queues.pbuf";
@@ -155,6 +160,30 @@ pub struct CanisterStateBits {
     pub canister_version: u64,
     pub consumed_cycles_since_replica_started_by_use_cases: BTreeMap<CyclesUseCase, NominalCycles>,
     pub canister_history: CanisterHistory,
+    pub next_snapshot_id: u64,
+    pub snapshots_memory_usage: NumBytes,
+    pub wasm_chunk_store_metadata: WasmChunkStoreMetadata,
+    pub log_visibility: LogVisibility,
+    pub canister_log: CanisterLog,
+    pub wasm_memory_limit: Option<NumBytes>,
+    pub total_query_stats: TotalQueryStats,
+}
+
+/// This struct contains bits of the `CanisterSnapshot` that are not already
+/// covered somewhere else and are too small to be serialized separately.
+#[derive(Debug)]
+pub struct CanisterSnapshotBits {
+    pub snapshot_id: SnapshotId,
+    pub canister_id: CanisterId,
+    pub taken_at_timestamp: Time,
+    pub canister_version: u64,
+    pub binary_hash: Option<WasmHash>,
+    pub certified_data: Vec<u8>,
+    pub exported_globals: Vec<Global>,
+    pub wasm_memory_size: NumWasmPages,
+    pub stable_memory_size: NumWasmPages,
+    pub total_size: NumBytes,
+    pub global_timer: CanisterTimer,
 }
 
 #[derive(Clone)]
@@ -210,6 +239,13 @@ struct CheckpointRefData {
 /// │   │       ├── queues.pbuf
 /// │   │       ├── software.wasm
 /// │   │       ├── stable_memory.bin
+/// │   │       ├── vmemory_0.bin
+/// │   │       └── wasm_chunk_store.bin
+/// │   ├── snapshots
+/// │   │   └── <hex(snapshot_id)>
+/// │   │       ├── snapshot.pbuf
+/// │   │       ├── software.wasm
+/// │   │       ├── stable_memory.bin
 /// │   │       └── vmemory_0.bin
 /// │   ├── ingress_history.pbuf
 /// │   ├── split_from.pbuf
@@ -224,6 +260,13 @@ struct CheckpointRefData {
 /// │      │       ├── queues.pbuf
 /// │      │       ├── software.wasm
 /// │      │       ├── stable_memory.bin
+/// │      │       ├── vmemory_0.bin
+/// │      │       └── wasm_chunk_store.bin
+/// │      ├── snapshots
+/// │      │   └── <hex(snapshot_id)>
+/// │      │       ├── snapshot.pbuf
+/// │      │       ├── software.wasm
+/// │      │       ├── stable_memory.bin
 /// │      │       └── vmemory_0.bin
 /// │      ├── ingress_history.pbuf
 /// │      ├── split_from.pbuf
@@ -375,6 +418,27 @@ impl TipHandler {
         }
         Ok(())
     }
+
+    /// Filters snapshots in tip. Removes the ones not present in the set.
+    pub fn filter_tip_snapshots(
+        &mut self,
+        height: Height,
+        ids: &BTreeSet<SnapshotId>,
+    ) -> Result<(), LayoutError> {
+        let tip = self.tip(height)?;
+        let snapshots_on_disk = tip.snapshot_ids()?;
+        for id in snapshots_on_disk {
+            if !ids.contains(&id) {
+                let snapshot_path = tip.snapshot(&id)?.raw_path();
+                std::fs::remove_dir_all(&snapshot_path).map_err(|err| LayoutError::IoError {
+                    path: snapshot_path,
+                    message: "Cannot remove snapshot.".to_string(),
+                    io_err: err,
+                })?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl StateLayout {
@@ -590,6 +654,34 @@ impl StateLayout {
         Ok(())
     }
 
+    /// Copies the checkpoint with the given height into the checkpoints
+    /// directory of another state layout, e.g. to export a state into a
+    /// fresh state root from which a state manager can be started.
+    pub fn copy_checkpoint_to(
+        &self,
+        height: Height,
+        target: &StateLayout,
+    ) -> Result<(), LayoutError> {
+        let src = self.checkpoints().join(Self::checkpoint_name(height));
+        if !src.exists() {
+            return Err(LayoutError::NotFound(height));
+        }
+        let dst = target.checkpoints().join(Self::checkpoint_name(height));
+        target
+            .copy_and_sync_checkpoint(&Self::checkpoint_name(height), &src, &dst, None)
+            .map_err(|io_err| {
+                if is_already_exists_err(&io_err) {
+                    LayoutError::AlreadyExists(height)
+                } else {
+                    LayoutError::IoError {
+                        path: dst,
+                        message: format!("Failed to copy checkpoint {}", height),
+                        io_err,
+                    }
+                }
+            })
+    }
+
     /// Returns the layout of the checkpoint with the given height (if
     /// there is one).
     pub fn checkpoint(&self, height: Height) -> Result<CheckpointLayout<ReadOnly>, LayoutError> {
@@ -1170,6 +1262,19 @@ fn parse_canister_id(hex: &str) -> Result<CanisterId, String> {
     .map_err(|err| format!("failed to create canister ID: {}", err))
 }
 
+/// Helper for parsing hex representations of snapshot IDs, used for the
+/// directory names under `snapshots`).
+fn parse_snapshot_id(hex: &str) -> Result<SnapshotId, String> {
+    let blob = hex::decode(hex).map_err(|err| {
+        format!(
+            "failed to convert directory name {} into a snapshot ID: {}",
+            hex, err
+        )
+    })?;
+
+    SnapshotId::try_from(&blob[..])
+}
+
 /// Parses the canister ID from a relative path, if it is the path of a canister
 /// state file (e.g. `canister_states/00000000000000010101/queues.pbuf`).
 /// Returns `None` if the path is not under `canister_states`; or if parsing
@@ -1309,6 +1414,23 @@ impl<Permissions: AccessPolicy> CheckpointLayout<Permissions> {
         )
     }
 
+    pub fn snapshot_ids(&self) -> Result<Vec<SnapshotId>, LayoutError> {
+        let snapshots_dir = self.root.join(SNAPSHOTS_DIR);
+        Permissions::check_dir(&snapshots_dir)?;
+        collect_subdirs(snapshots_dir.as_path(), parse_snapshot_id)
+    }
+
+    pub fn snapshot(
+        &self,
+        snapshot_id: &SnapshotId,
+    ) -> Result<SnapshotLayout<Permissions>, LayoutError> {
+        SnapshotLayout::new(
+            self.root
+                .join(SNAPSHOTS_DIR)
+                .join(hex::encode(snapshot_id.to_vec())),
+        )
+    }
+
     pub fn height(&self) -> Height {
         self.height
     }
@@ -1357,6 +1479,47 @@ impl<Permissions: AccessPolicy> CanisterLayout<Permissions> {
     pub fn stable_memory_blob(&self) -> PathBuf {
         self.canister_root.join("stable_memory.bin")
     }
+
+    pub fn wasm_chunk_store(&self) -> PathBuf {
+        self.canister_root.join("wasm_chunk_store.bin")
+    }
+}
+
+pub struct SnapshotLayout<Permissions: AccessPolicy> {
+    snapshot_root: PathBuf,
+    permissions_tag: PhantomData<Permissions>,
+}
+
+impl<Permissions: AccessPolicy> SnapshotLayout<Permissions> {
+    pub fn new(snapshot_root: PathBuf) -> Result<Self, LayoutError> {
+        Permissions::check_dir(&snapshot_root)?;
+        Ok(Self {
+            snapshot_root,
+            permissions_tag: PhantomData,
+        })
+    }
+
+    pub fn raw_path(&self) -> PathBuf {
+        self.snapshot_root.clone()
+    }
+
+    pub fn snapshot(
+        &self,
+    ) -> ProtoFileWith<pb_canister_state_bits::CanisterSnapshotBits, Permissions> {
+        self.snapshot_root.join(SNAPSHOT_FILE).into()
+    }
+
+    pub fn wasm(&self) -> WasmFile<Permissions> {
+        self.snapshot_root.join("software.wasm").into()
+    }
+
+    pub fn vmemory_0(&self) -> PathBuf {
+        self.snapshot_root.join("vmemory_0.bin")
+    }
+
+    pub fn stable_memory_blob(&self) -> PathBuf {
+        self.snapshot_root.join("stable_memory.bin")
+    }
 }
 
 fn open_for_write(path: &Path) -> Result<std::fs::File, LayoutError> {
@@ -1623,6 +1786,20 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
                 })
                 .collect(),
             canister_history: Some((&item.canister_history).into()),
+            next_snapshot_id: item.next_snapshot_id,
+            snapshots_memory_usage: item.snapshots_memory_usage.get(),
+            log_visibility: pb_canister_state_bits::LogVisibility::from(&item.log_visibility)
+                .into(),
+            canister_log_records: item
+                .canister_log
+                .records()
+                .iter()
+                .map(|record| record.into())
+                .collect(),
+            next_canister_log_record_idx: item.canister_log.next_idx(),
+            wasm_memory_limit: item.wasm_memory_limit.map(|limit| limit.get()),
+            total_query_stats: Some((&item.total_query_stats).into()),
+            wasm_chunk_store_metadata: Some((&item.wasm_chunk_store_metadata).into()),
         }
     }
 }
@@ -1733,6 +1910,98 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
                 "CanisterStateBits::canister_history",
             )
             .unwrap_or_default(),
+            next_snapshot_id: value.next_snapshot_id,
+            snapshots_memory_usage: NumBytes::from(value.snapshots_memory_usage),
+            wasm_chunk_store_metadata: match value.wasm_chunk_store_metadata {
+                Some(wasm_chunk_store_metadata) => wasm_chunk_store_metadata.try_into()?,
+                None => WasmChunkStoreMetadata::default(),
+            },
+            log_visibility: pb_canister_state_bits::LogVisibility::from_i32(value.log_visibility)
+                .unwrap_or_default()
+                .into(),
+            canister_log: CanisterLog::new(
+                value.next_canister_log_record_idx,
+                value
+                    .canister_log_records
+                    .into_iter()
+                    .map(|record| record.into())
+                    .collect(),
+            ),
+            wasm_memory_limit: value.wasm_memory_limit.map(NumBytes::from),
+            total_query_stats: match value.total_query_stats {
+                Some(total_query_stats) => total_query_stats.try_into()?,
+                None => TotalQueryStats::default(),
+            },
+        })
+    }
+}
+
+impl From<CanisterSnapshotBits> for pb_canister_state_bits::CanisterSnapshotBits {
+    fn from(item: CanisterSnapshotBits) -> Self {
+        Self {
+            snapshot_id: item.snapshot_id.to_vec(),
+            canister_id: Some(item.canister_id.into()),
+            taken_at_timestamp: item.taken_at_timestamp.as_nanos_since_unix_epoch(),
+            canister_version: item.canister_version,
+            binary_hash: item
+                .binary_hash
+                .as_ref()
+                .map(|h| h.to_vec())
+                .unwrap_or_default(),
+            certified_data: item.certified_data,
+            exported_globals: item
+                .exported_globals
+                .iter()
+                .map(|global| global.into())
+                .collect(),
+            wasm_memory_size: item.wasm_memory_size.get() as u64,
+            stable_memory_size: item.stable_memory_size.get() as u64,
+            total_size: item.total_size.get(),
+            global_timer_nanos: item.global_timer.to_nanos_since_unix_epoch(),
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::CanisterSnapshotBits> for CanisterSnapshotBits {
+    type Error = ProxyDecodeError;
+
+    fn try_from(value: pb_canister_state_bits::CanisterSnapshotBits) -> Result<Self, Self::Error> {
+        let snapshot_id = SnapshotId::try_from(&value.snapshot_id[..]).map_err(|err| {
+            ProxyDecodeError::ValueOutOfRange {
+                typ: "SnapshotId",
+                err,
+            }
+        })?;
+        let canister_id =
+            try_from_option_field(value.canister_id, "CanisterSnapshotBits::canister_id")?;
+        let binary_hash =
+            if value.binary_hash.is_empty() {
+                None
+            } else {
+                let hash: [u8; 32] = value.binary_hash.try_into().map_err(|e| {
+                    ProxyDecodeError::ValueOutOfRange {
+                        typ: "BinaryHash",
+                        err: format!("Expected a 32-byte long module hash, got {:?}", e),
+                    }
+                })?;
+                Some(hash.into())
+            };
+        let mut exported_globals = Vec::with_capacity(value.exported_globals.len());
+        for global in value.exported_globals.into_iter() {
+            exported_globals.push(global.try_into()?);
+        }
+        Ok(Self {
+            snapshot_id,
+            canister_id,
+            taken_at_timestamp: Time::from_nanos_since_unix_epoch(value.taken_at_timestamp),
+            canister_version: value.canister_version,
+            binary_hash,
+            certified_data: value.certified_data,
+            exported_globals,
+            wasm_memory_size: NumWasmPages::from(value.wasm_memory_size as usize),
+            stable_memory_size: NumWasmPages::from(value.stable_memory_size as usize),
+            total_size: NumBytes::from(value.total_size),
+            global_timer: CanisterTimer::from_nanos_since_unix_epoch(value.global_timer_nanos),
         })
     }
 }
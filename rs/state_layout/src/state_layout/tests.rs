@@ -1,10 +1,13 @@
 use super::*;
 
 use ic_ic00_types::{
-    CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallMode, IC_00,
+    CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallMode,
+    LogVisibility, IC_00,
 };
 use ic_interfaces::messages::{CanisterCall, CanisterMessage, CanisterMessageOrTask};
-use ic_replicated_state::canister_state::system_state::CanisterHistory;
+use ic_replicated_state::canister_state::system_state::{
+    CanisterHistory, WasmChunkStore, WasmChunkStoreMetadata,
+};
 use ic_test_utilities::types::ids::user_test_id;
 use ic_test_utilities::{
     mock_time,
@@ -46,6 +49,13 @@ fn default_canister_state_bits() -> CanisterStateBits {
         canister_version: 0,
         consumed_cycles_since_replica_started_by_use_cases: BTreeMap::new(),
         canister_history: CanisterHistory::default(),
+        next_snapshot_id: 0,
+        snapshots_memory_usage: NumBytes::from(0),
+        wasm_chunk_store_metadata: WasmChunkStoreMetadata::default(),
+        log_visibility: LogVisibility::default(),
+        canister_log: CanisterLog::default(),
+        wasm_memory_limit: None,
+        total_query_stats: TotalQueryStats::default(),
     }
 }
 
@@ -106,6 +116,80 @@ fn test_encode_decode_non_empty_controllers() {
     assert_eq!(canister_state_bits.controllers, expected_controllers);
 }
 
+#[test]
+fn test_encode_decode_wasm_chunk_store_metadata() {
+    let mut wasm_chunk_store = WasmChunkStore::new_for_testing();
+    wasm_chunk_store.insert_chunk(&[1, 2, 3]);
+    wasm_chunk_store.insert_chunk(&[4, 5]);
+
+    let canister_state_bits = CanisterStateBits {
+        wasm_chunk_store_metadata: wasm_chunk_store.metadata().clone(),
+        ..default_canister_state_bits()
+    };
+
+    let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+    let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+    assert_eq!(
+        &canister_state_bits.wasm_chunk_store_metadata,
+        wasm_chunk_store.metadata()
+    );
+}
+
+#[test]
+fn test_encode_decode_canister_log() {
+    let mut canister_log = CanisterLog::new(7, vec![]);
+    canister_log.add_record(100, b"hello".to_vec());
+    canister_log.add_record(200, b"world".to_vec());
+
+    let canister_state_bits = CanisterStateBits {
+        log_visibility: LogVisibility::Public,
+        canister_log: canister_log.clone(),
+        ..default_canister_state_bits()
+    };
+
+    let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+    let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+    assert_eq!(canister_state_bits.log_visibility, LogVisibility::Public);
+    assert_eq!(canister_state_bits.canister_log, canister_log);
+    assert_eq!(canister_state_bits.canister_log.next_idx(), 9);
+}
+
+#[test]
+fn test_encode_decode_wasm_memory_limit() {
+    for wasm_memory_limit in [None, Some(NumBytes::from(1 << 30))] {
+        let canister_state_bits = CanisterStateBits {
+            wasm_memory_limit,
+            ..default_canister_state_bits()
+        };
+
+        let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+        let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+        assert_eq!(canister_state_bits.wasm_memory_limit, wasm_memory_limit);
+    }
+}
+
+#[test]
+fn test_encode_decode_total_query_stats() {
+    let total_query_stats = TotalQueryStats {
+        num_calls: 5,
+        num_instructions: u64::MAX as u128 + 1,
+        ingress_payload_size: 1000,
+        egress_payload_size: u128::MAX,
+    };
+    let canister_state_bits = CanisterStateBits {
+        total_query_stats: total_query_stats.clone(),
+        ..default_canister_state_bits()
+    };
+
+    let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+    let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+    assert_eq!(canister_state_bits.total_query_stats, total_query_stats);
+}
+
 #[test]
 fn test_encode_decode_empty_history() {
     let canister_history = CanisterHistory::default();
@@ -316,6 +400,57 @@ fn test_last_removal_panics_in_debug() {
     });
 }
 
+#[test]
+fn test_copy_checkpoint_to_other_layout() {
+    with_test_replica_logger(|log| {
+        let metrics_registry = ic_metrics::MetricsRegistry::new();
+        let src_dir = tmpdir("state_layout_src");
+        let src_layout =
+            StateLayout::try_new(log.clone(), src_dir.path().to_path_buf(), &metrics_registry)
+                .unwrap();
+        let dst_dir = tmpdir("state_layout_dst");
+        let dst_layout =
+            StateLayout::try_new(log, dst_dir.path().to_path_buf(), &metrics_registry).unwrap();
+
+        let scratchpad_dir = tmpdir("scratchpad");
+        let scratchpad = CheckpointLayout::<RwPolicy<()>>::new_untracked(
+            scratchpad_dir.path().to_path_buf().join("1"),
+            Height::new(1),
+        )
+        .unwrap();
+        std::fs::write(
+            scratchpad.raw_path().join(SYSTEM_METADATA_FILE),
+            b"metadata",
+        )
+        .unwrap();
+        let _cp1 = src_layout
+            .scratchpad_to_checkpoint(scratchpad, Height::new(1), None)
+            .unwrap();
+
+        src_layout
+            .copy_checkpoint_to(Height::new(1), &dst_layout)
+            .unwrap();
+        assert_eq!(
+            vec![Height::new(1)],
+            dst_layout.checkpoint_heights().unwrap(),
+        );
+        let copied = dst_layout.checkpoint(Height::new(1)).unwrap();
+        assert_eq!(
+            std::fs::read(copied.raw_path().join(SYSTEM_METADATA_FILE)).unwrap(),
+            b"metadata",
+        );
+
+        assert!(matches!(
+            src_layout.copy_checkpoint_to(Height::new(1), &dst_layout),
+            Err(LayoutError::AlreadyExists(_))
+        ));
+        assert!(matches!(
+            src_layout.copy_checkpoint_to(Height::new(2), &dst_layout),
+            Err(LayoutError::NotFound(_))
+        ));
+    });
+}
+
 #[test]
 fn test_canister_id_from_path() {
     assert_eq!(
@@ -4,20 +4,33 @@ use ic_config::{execution_environment::Config as HypervisorConfig, subnet_config
 use ic_constants::{MAX_INGRESS_TTL, PERMITTED_DRIFT, SMALL_APP_SUBNET_MAX_SIZE};
 use ic_crypto_ecdsa_secp256k1::{PrivateKey, PublicKey};
 use ic_crypto_extended_bip32::{DerivationIndex, DerivationPath};
+use ic_crypto_internal_bls12_381_vetkd::{
+    DerivationPath as VetKdDerivationPath, DerivedPublicKey, EncryptedKey, EncryptedKeyShare,
+    G2Affine, Scalar, TransportPublicKey,
+};
 use ic_crypto_internal_seed::Seed;
 use ic_crypto_internal_threshold_sig_bls12381::api::{
     combine_signatures, combined_public_key, generate_threshold_key, sign_message,
 };
 use ic_crypto_internal_threshold_sig_bls12381::types::SecretKeyBytes;
-use ic_crypto_internal_types::sign::threshold_sig::public_key::CspThresholdSigPublicKey;
-use ic_crypto_tree_hash::{flatmap, Label, LabeledTree, LabeledTree::SubTree};
+use ic_crypto_internal_types::sign::threshold_sig::public_key::{
+    bls12_381::PublicKeyBytes, CspThresholdSigPublicKey,
+};
+use ic_crypto_tree_hash::{
+    flatmap, sparse_labeled_tree_from_paths, Label, LabeledTree, LabeledTree::SubTree,
+    Path as LabelPath,
+};
+use ic_crypto_tschnorr::{derive_tschnorr_public_key, test_utils::MasterSchnorrTestKey};
 use ic_cycles_account_manager::CyclesAccountManager;
+use ic_embedders::wasm_utils::{decoding::decode_wasm, function_names};
 pub use ic_error_types::{ErrorCode, UserError};
 use ic_execution_environment::ExecutionServices;
 use ic_ic00_types::{self as ic00, CanisterIdRecord, InstallCodeArgs, Method, Payload};
 pub use ic_ic00_types::{
     CanisterHttpResponsePayload, CanisterInstallMode, CanisterSettingsArgs, ECDSAPublicKeyResponse,
-    EcdsaCurve, EcdsaKeyId, HttpHeader, HttpMethod, SignWithECDSAReply, UpdateSettingsArgs,
+    EcdsaCurve, EcdsaKeyId, HttpHeader, HttpMethod, SchnorrAlgorithm, SchnorrKeyId,
+    SchnorrPublicKeyResponse, SignWithECDSAReply, SignWithSchnorrReply, UpdateSettingsArgs,
+    VetKdCurve, VetKdDeriveEncryptedKeyResult, VetKdKeyId, VetKdPublicKeyResult,
 };
 use ic_interfaces::{
     certification::{Verifier, VerifierError},
@@ -54,13 +67,15 @@ use ic_registry_routing_table::{
 use ic_registry_subnet_features::{EcdsaConfig, SubnetFeatures, DEFAULT_ECDSA_MAX_QUEUE_SIZE};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::canister_state::system_state::CyclesUseCase;
-use ic_replicated_state::metadata_state::subnet_call_context_manager::SignWithEcdsaContext;
+use ic_replicated_state::metadata_state::subnet_call_context_manager::{
+    SignWithEcdsaContext, SignWithSchnorrContext, VetKdContext,
+};
 use ic_replicated_state::page_map::Buffer;
 use ic_replicated_state::{
     canister_state::{NumWasmPages, WASM_PAGE_SIZE_IN_BYTES},
     Memory, PageMap, ReplicatedState,
 };
-use ic_state_layout::{CheckpointLayout, RwPolicy};
+use ic_state_layout::{CheckpointLayout, RwPolicy, StateLayout};
 use ic_state_manager::StateManagerImpl;
 use ic_test_utilities_metrics::{
     fetch_histogram_stats, fetch_int_counter, fetch_int_gauge, fetch_int_gauge_vec, Labels,
@@ -73,11 +88,12 @@ use ic_types::consensus::certification::CertificationContent;
 use ic_types::crypto::threshold_sig::ni_dkg::{NiDkgId, NiDkgTag, NiDkgTargetSubnet};
 pub use ic_types::crypto::threshold_sig::ThresholdSigPublicKey;
 use ic_types::crypto::{
-    canister_threshold_sig::MasterEcdsaPublicKey, AlgorithmId, CombinedThresholdSig,
-    CombinedThresholdSigOf, Signable, Signed,
+    canister_threshold_sig::{ExtendedDerivationPath, MasterEcdsaPublicKey},
+    AlgorithmId, CombinedThresholdSig, CombinedThresholdSigOf, Signable, Signed,
 };
+pub use ic_types::execution_profile::ExecutionProfile;
 use ic_types::malicious_flags::MaliciousFlags;
-use ic_types::messages::{CallbackId, Certificate, Response};
+use ic_types::messages::{CallbackId, Certificate, RejectContext, Response};
 use ic_types::signature::ThresholdSignature;
 use ic_types::time::GENESIS;
 use ic_types::{
@@ -97,6 +113,8 @@ pub use ic_types::{
     CanisterId, CryptoHashOfState, Cycles, PrincipalId, SubnetId, UserId,
 };
 use maplit::btreemap;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
 use serde::Serialize;
 pub use slog::Level;
 use std::io::stderr;
@@ -286,6 +304,8 @@ pub struct StateMachine {
     public_key: ThresholdSigPublicKey,
     secret_key: SecretKeyBytes,
     ecdsa_secret_key: PrivateKey,
+    schnorr_secret_keys: BTreeMap<SchnorrKeyId, MasterSchnorrTestKey>,
+    vetkd_secret_key: Scalar,
     registry_data_provider: Arc<ProtoRegistryDataProvider>,
     registry_client: Arc<FakeRegistryClient>,
     pub state_manager: Arc<StateManagerImpl>,
@@ -299,6 +319,8 @@ pub struct StateMachine {
     nonce: std::sync::atomic::AtomicU64,
     time: std::sync::atomic::AtomicU64,
     ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+    schnorr_subnet_public_keys: BTreeMap<SchnorrKeyId, MasterEcdsaPublicKey>,
+    vetkd_subnet_public_keys: BTreeMap<VetKdKeyId, ThresholdSigPublicKey>,
 }
 
 impl Default for StateMachine {
@@ -329,7 +351,10 @@ pub struct StateMachineBuilder {
     routing_table: RoutingTable,
     use_cost_scaling_flag: bool,
     ecdsa_keys: Vec<EcdsaKeyId>,
+    schnorr_keys: Vec<SchnorrKeyId>,
+    vetkd_keys: Vec<VetKdKeyId>,
     features: SubnetFeatures,
+    canister_profiling: bool,
 }
 
 impl StateMachineBuilder {
@@ -351,10 +376,13 @@ impl StateMachineBuilder {
                 curve: EcdsaCurve::Secp256k1,
                 name: "master_ecdsa_public_key".to_string(),
             }],
+            schnorr_keys: vec![],
+            vetkd_keys: vec![],
             features: SubnetFeatures {
                 http_requests: true,
                 ..SubnetFeatures::default()
             },
+            canister_profiling: false,
         }
     }
 
@@ -362,14 +390,44 @@ impl StateMachineBuilder {
         Self { state_dir, ..self }
     }
 
-    fn with_nonce(self, nonce: u64) -> Self {
+    pub fn with_nonce(self, nonce: u64) -> Self {
         Self { nonce, ..self }
     }
 
-    fn with_time(self, time: Time) -> Self {
+    pub fn with_time(self, time: Time) -> Self {
         Self { time, ..self }
     }
 
+    /// Initializes the state directory with the latest checkpoint found in
+    /// `checkpoint_dir`, a state root in the `state_layout` format such as
+    /// one written by [StateMachine::checkpoint_to]. The state machine
+    /// resumes from that checkpoint; the checkpoints in `checkpoint_dir` are
+    /// not modified.
+    pub fn with_checkpoint_dir(self, checkpoint_dir: &Path) -> Result<Self, String> {
+        let metrics_registry = MetricsRegistry::new();
+        let source = StateLayout::try_new(
+            replica_logger(),
+            checkpoint_dir.to_path_buf(),
+            &metrics_registry,
+        )
+        .map_err(|err| err.to_string())?;
+        let height = source
+            .checkpoint_heights()
+            .map_err(|err| err.to_string())?
+            .pop()
+            .ok_or_else(|| format!("No checkpoint found in {}", checkpoint_dir.display()))?;
+        let target = StateLayout::try_new(
+            replica_logger(),
+            self.state_dir.path().to_path_buf(),
+            &metrics_registry,
+        )
+        .map_err(|err| err.to_string())?;
+        source
+            .copy_checkpoint_to(height, &target)
+            .map_err(|err| err.to_string())?;
+        Ok(self)
+    }
+
     pub fn with_config(self, config: Option<StateMachineConfig>) -> Self {
         Self { config, ..self }
     }
@@ -455,10 +513,35 @@ impl StateMachineBuilder {
         Self { ecdsa_keys, ..self }
     }
 
+    pub fn with_schnorr_key(self, key: SchnorrKeyId) -> Self {
+        let mut schnorr_keys = self.schnorr_keys;
+        schnorr_keys.push(key);
+        Self {
+            schnorr_keys,
+            ..self
+        }
+    }
+
+    pub fn with_vetkd_key(self, key: VetKdKeyId) -> Self {
+        let mut vetkd_keys = self.vetkd_keys;
+        vetkd_keys.push(key);
+        Self { vetkd_keys, ..self }
+    }
+
     pub fn with_features(self, features: SubnetFeatures) -> Self {
         Self { features, ..self }
     }
 
+    /// Records an instruction profile of every canister execution, see
+    /// [StateMachine::execution_profile]. Profiling slows down execution
+    /// considerably.
+    pub fn with_canister_profiling(self) -> Self {
+        Self {
+            canister_profiling: true,
+            ..self
+        }
+    }
+
     pub fn build(self) -> StateMachine {
         StateMachine::setup_from_dir(
             self.state_dir,
@@ -473,7 +556,10 @@ impl StateMachineBuilder {
             self.routing_table,
             self.use_cost_scaling_flag,
             self.ecdsa_keys,
+            self.schnorr_keys,
+            self.vetkd_keys,
             self.features,
+            self.canister_profiling,
         )
     }
 }
@@ -513,7 +599,10 @@ impl StateMachine {
         routing_table: RoutingTable,
         use_cost_scaling_flag: bool,
         ecdsa_keys: Vec<EcdsaKeyId>,
+        schnorr_keys: Vec<SchnorrKeyId>,
+        vetkd_keys: Vec<VetKdKeyId>,
         features: SubnetFeatures,
+        canister_profiling: bool,
     ) -> Self {
         let replica_logger = replica_logger();
 
@@ -545,6 +634,12 @@ impl StateMachine {
             hypervisor_config.canister_sandboxing_flag = FlagStatus::Disabled;
             hypervisor_config.deterministic_time_slicing = FlagStatus::Disabled;
         }
+        if canister_profiling {
+            hypervisor_config
+                .embedders_config
+                .feature_flags
+                .canister_profiling = FlagStatus::Enabled;
+        }
 
         let mut cycles_account_manager = CyclesAccountManager::new(
             subnet_config.scheduler_config.max_instructions_per_message,
@@ -620,16 +715,6 @@ impl StateMachine {
             combined_public_key(&public_coefficients).unwrap(),
         ));
 
-        let mut ecdsa_subnet_public_keys = BTreeMap::new();
-        for ecdsa_key in ecdsa_keys {
-            ecdsa_subnet_public_keys.insert(
-                ecdsa_key,
-                MasterEcdsaPublicKey {
-                    algorithm_id: AlgorithmId::EcdsaSecp256k1,
-                    public_key: b"master_ecdsa_public_key".to_vec(),
-                },
-            );
-        }
         // The following key has been randomly generated using:
         // https://sourcegraph.com/github.com/dfinity/ic/-/blob/rs/crypto/ecdsa_secp256k1/src/lib.rs
         // It's the sec1 representation of the key in a hex string.
@@ -644,22 +729,67 @@ impl StateMachine {
         let ecdsa_secret_key: PrivateKey =
             PrivateKey::deserialize_sec1(private_key_bytes.as_slice()).unwrap();
 
-        ecdsa_subnet_public_keys.insert(
-            EcdsaKeyId {
-                curve: EcdsaCurve::Secp256k1,
-                name: "master_ecdsa_public_key".to_string(),
-            },
-            MasterEcdsaPublicKey {
-                algorithm_id: AlgorithmId::EcdsaSecp256k1,
-                public_key: ecdsa_secret_key.public_key().serialize_sec1(true),
-            },
-        );
+        // All ECDSA keys share the same secret key, which is used to answer
+        // `sign_with_ecdsa` requests on every tick, so that the signatures
+        // verify against the public key of whichever key was requested.
+        let mut ecdsa_subnet_public_keys = BTreeMap::new();
+        for ecdsa_key in ecdsa_keys.into_iter().chain(std::iter::once(EcdsaKeyId {
+            curve: EcdsaCurve::Secp256k1,
+            name: "master_ecdsa_public_key".to_string(),
+        })) {
+            ecdsa_subnet_public_keys.insert(
+                ecdsa_key,
+                MasterEcdsaPublicKey {
+                    algorithm_id: AlgorithmId::EcdsaSecp256k1,
+                    public_key: ecdsa_secret_key.public_key().serialize_sec1(true),
+                },
+            );
+        }
+
+        // Every Schnorr key has a fixed master secret key derived from its
+        // name, which is used to answer `sign_with_schnorr` requests on every
+        // tick. Please do not use these keys anywhere.
+        let schnorr_secret_keys: BTreeMap<_, _> = schnorr_keys
+            .into_iter()
+            .map(|key_id| {
+                let algorithm_id = match key_id.algorithm {
+                    SchnorrAlgorithm::Bip340Secp256k1 => AlgorithmId::ThresholdSchnorrBip340,
+                    SchnorrAlgorithm::Ed25519 => AlgorithmId::ThresholdEd25519,
+                };
+                let secret_key = MasterSchnorrTestKey::new(algorithm_id, key_id.name.as_bytes());
+                (key_id, secret_key)
+            })
+            .collect();
+        let schnorr_subnet_public_keys = schnorr_secret_keys
+            .iter()
+            .map(|(key_id, secret_key)| (key_id.clone(), secret_key.public_key()))
+            .collect();
+
+        // All vetKD keys share the same fixed master secret key, so that the
+        // derived keys are deterministic. Please do not use this key anywhere.
+        let vetkd_secret_key = Scalar::deserialize(
+            &hex::decode("1c3e4b28f1de5d6a07f3a21e8b3f6c5a9d0e7b41c2a8f5e36d19b7c4a0f2e8d5")
+                .unwrap(),
+        )
+        .unwrap();
+        let vetkd_public_key = G2Affine::from(G2Affine::generator() * &vetkd_secret_key);
+        let vetkd_subnet_public_keys = vetkd_keys
+            .into_iter()
+            .map(|key_id| {
+                (
+                    key_id,
+                    ThresholdSigPublicKey::from(PublicKeyBytes(vetkd_public_key.serialize())),
+                )
+            })
+            .collect();
 
         Self {
             subnet_id,
             secret_key: secret_key_bytes.get(0).unwrap().clone(),
             public_key,
             ecdsa_secret_key,
+            schnorr_secret_keys,
+            vetkd_secret_key,
             registry_data_provider,
             registry_client,
             state_manager,
@@ -675,6 +805,8 @@ impl StateMachine {
             nonce: std::sync::atomic::AtomicU64::new(nonce),
             time: std::sync::atomic::AtomicU64::new(time.as_nanos_since_unix_epoch()),
             ecdsa_subnet_public_keys,
+            schnorr_subnet_public_keys,
+            vetkd_subnet_public_keys,
         }
     }
 
@@ -793,6 +925,42 @@ impl StateMachine {
                 response_payload: MsgPayload::Data(reply.encode()),
             });
         }
+        let sign_with_schnorr_contexts = state
+            .metadata
+            .subnet_call_context_manager
+            .sign_with_schnorr_contexts
+            .clone();
+        for (id, schnorr_context) in sign_with_schnorr_contexts {
+            let reply = SignWithSchnorrReply {
+                signature: self.sign_with_schnorr(&schnorr_context),
+            };
+
+            payload.consensus_responses.push(Response {
+                originator: CanisterId::ic_00(),
+                respondent: CanisterId::ic_00(),
+                originator_reply_callback: id,
+                refund: Cycles::zero(),
+                response_payload: MsgPayload::Data(reply.encode()),
+            });
+        }
+        let vetkd_contexts = state
+            .metadata
+            .subnet_call_context_manager
+            .vetkd_contexts
+            .clone();
+        for (id, vetkd_context) in vetkd_contexts {
+            let reply = VetKdDeriveEncryptedKeyResult {
+                encrypted_key: self.derive_encrypted_key(&vetkd_context),
+            };
+
+            payload.consensus_responses.push(Response {
+                originator: CanisterId::ic_00(),
+                respondent: CanisterId::ic_00(),
+                originator_reply_callback: id,
+                refund: Cycles::zero(),
+                response_payload: MsgPayload::Data(reply.encode()),
+            });
+        }
         self.execute_payload(payload)
     }
 
@@ -841,9 +1009,12 @@ impl StateMachine {
                 signed_ingress_msgs: payload.ingress_messages,
                 certified_stream_slices: payload.xnet_payload.stream_slices,
                 bitcoin_adapter_responses: vec![],
+                query_stats: None,
             },
             randomness: Randomness::from(seed),
             ecdsa_subnet_public_keys: self.ecdsa_subnet_public_keys.clone(),
+            schnorr_subnet_public_keys: self.schnorr_subnet_public_keys.clone(),
+            vetkd_subnet_public_keys: self.vetkd_subnet_public_keys.clone(),
             registry_version: self.registry_client.get_latest_version(),
             time: Time::from_nanos_since_unix_epoch(self.time.load(Ordering::Relaxed)),
             consensus_responses: payload.consensus_responses,
@@ -943,6 +1114,11 @@ impl StateMachine {
         );
     }
 
+    /// Returns the nonce that will be used for the next ingress message.
+    pub fn nonce(&self) -> u64 {
+        self.nonce.load(Ordering::Relaxed)
+    }
+
     /// Returns the current state machine time.
     pub fn time(&self) -> SystemTime {
         SystemTime::UNIX_EPOCH + Duration::from_nanos(self.time.load(Ordering::Relaxed))
@@ -1119,6 +1295,33 @@ impl StateMachine {
         self.state_manager.remove_states_below(h.increment());
     }
 
+    /// Writes a checkpoint of the latest state and copies it to `state_dir`,
+    /// which is initialized as a state root in the `state_layout` format if
+    /// needed. Returns the height of the checkpoint.
+    ///
+    /// A state machine can be started from the checkpoint using
+    /// [StateMachineBuilder::with_checkpoint_dir].
+    pub fn checkpoint_to(&self, state_dir: &Path) -> Result<Height, String> {
+        let cp_enabled = self.checkpoints_enabled.load(Ordering::Relaxed);
+        self.set_checkpoints_enabled(true);
+        self.tick();
+        self.set_checkpoints_enabled(cp_enabled);
+        self.state_manager.flush_tip_channel();
+
+        let height = self.state_manager.latest_state_height();
+        let target = StateLayout::try_new(
+            replica_logger(),
+            state_dir.to_path_buf(),
+            &MetricsRegistry::new(),
+        )
+        .map_err(|err| err.to_string())?;
+        self.state_manager
+            .state_layout()
+            .copy_checkpoint_to(height, &target)
+            .map_err(|err| err.to_string())?;
+        Ok(height)
+    }
+
     /// Removes states below the latest height.
     ///
     /// This is useful for testing behaviour after old states are dropped.
@@ -1397,12 +1600,7 @@ impl StateMachine {
         method: impl ToString,
         method_payload: Vec<u8>,
     ) -> Result<WasmResult, UserError> {
-        if self.state_manager.latest_state_height() > self.state_manager.latest_certified_height() {
-            let state_hashes = self.state_manager.list_state_hashes_to_certify();
-            let (height, hash) = state_hashes.last().unwrap();
-            self.state_manager
-                .deliver_state_certification(self.certify_hash(height, hash));
-        }
+        self.certify_latest_state();
 
         let path = SubTree(flatmap! {
             Label::from("canister") => SubTree(
@@ -1433,6 +1631,37 @@ impl StateMachine {
         )
     }
 
+    /// Returns a certificate for the given paths of the latest state, as
+    /// served by the `read_state` endpoint of a replica. The `time` path is
+    /// always included.
+    pub fn read_state(&self, paths: &[LabelPath]) -> Result<Certificate, String> {
+        self.certify_latest_state();
+
+        let mut paths = paths.to_vec();
+        paths.push(LabelPath::from(Label::from("time")));
+        let labeled_tree = sparse_labeled_tree_from_paths(&paths)
+            .map_err(|_| "Failed to parse requested paths: path is too long.".to_string())?;
+        let (_, tree, certification) = self
+            .state_manager
+            .read_certified_state(&labeled_tree)
+            .ok_or_else(|| "Certified state is not available.".to_string())?;
+        Ok(Certificate {
+            tree,
+            signature: Blob(certification.signed.signature.signature.get().0),
+            delegation: None,
+        })
+    }
+
+    /// Certifies the latest state if it is not certified yet.
+    fn certify_latest_state(&self) {
+        if self.state_manager.latest_state_height() > self.state_manager.latest_certified_height() {
+            let state_hashes = self.state_manager.list_state_hashes_to_certify();
+            let (height, hash) = state_hashes.last().unwrap();
+            self.state_manager
+                .deliver_state_certification(self.certify_hash(height, hash));
+        }
+    }
+
     fn certify_hash(&self, height: &Height, hash: &CryptoHashOfPartialState) -> Certification {
         let signature_bytes = Some(
             sign_message(
@@ -1476,6 +1705,57 @@ impl StateMachine {
         )
     }
 
+    /// Returns the latest instruction profile of the given entry point of the
+    /// specified canister, e.g. of `canister_update transfer`,
+    /// `canister_heartbeat` or `canister_post_upgrade`. Responses are profiled
+    /// as `callback`. Profiles are only recorded if the state machine was built
+    /// with [StateMachineBuilder::with_canister_profiling].
+    pub fn execution_profile(
+        &self,
+        canister_id: CanisterId,
+        entry_point: &str,
+    ) -> Option<ExecutionProfile> {
+        let state = self.state_manager.get_latest_state().take();
+        state
+            .canister_state(&canister_id)?
+            .system_state
+            .execution_profiles
+            .get(entry_point)
+            .cloned()
+    }
+
+    /// Renders the given profile of the specified canister in the folded stack
+    /// format, which can be turned into a flamegraph by `flamegraph.pl` or
+    /// `inferno-flamegraph`. Functions are named after the `name` section of
+    /// the canister module, if it has one.
+    pub fn folded_stacks(&self, canister_id: CanisterId, profile: &ExecutionProfile) -> String {
+        let state = self.state_manager.get_latest_state().take();
+        let function_names = state
+            .canister_state(&canister_id)
+            .and_then(|canister| canister.execution_state.as_ref())
+            .and_then(|execution_state| {
+                decode_wasm(execution_state.wasm_binary.binary.to_shared_vec()).ok()
+            })
+            .map(|wasm| function_names(wasm.as_slice()))
+            .unwrap_or_default();
+        profile.to_folded_stacks(&function_names)
+    }
+
+    /// Executes an ingress message like [execute_ingress_as] and returns its
+    /// result together with the latest profile of the called method.
+    pub fn execute_ingress_with_profile(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        method: impl ToString,
+        payload: Vec<u8>,
+    ) -> (Result<WasmResult, UserError>, Option<ExecutionProfile>) {
+        let method = method.to_string();
+        let result = self.execute_ingress_as(sender, canister_id, &method, payload);
+        let profile = self.execution_profile(canister_id, &format!("canister_update {}", method));
+        (result, profile)
+    }
+
     /// Executes an ingress message on the canister with the specified ID.
     ///
     /// This function is synchronous, it blocks until the result of the ingress
@@ -1531,6 +1811,17 @@ impl StateMachine {
         msg_id
     }
 
+    /// Submits an already signed ingress message, e.g. one received through
+    /// the HTTP interface, in a new block. The caller is responsible for
+    /// validating the message.
+    ///
+    /// The returned message ID can be awaited with [await_ingress].
+    pub fn submit_signed_ingress(&self, msg: SignedIngress) -> MessageId {
+        let msg_id = msg.id();
+        self.execute_payload(PayloadBuilder::new().signed_ingress(msg));
+        msg_id
+    }
+
     /// Returns the status of the ingress message with the specified ID.
     pub fn ingress_status(&self, msg_id: &MessageId) -> IngressStatus {
         (self.ingress_history_reader.get_latest_status())(msg_id)
@@ -1811,6 +2102,123 @@ impl StateMachine {
             .clone()
     }
 
+    /// Returns Schnorr signature contexts from internal subnet call context
+    /// manager.
+    pub fn sign_with_schnorr_contexts(&self) -> BTreeMap<CallbackId, SignWithSchnorrContext> {
+        let state = self.state_manager.get_latest_state().take();
+        state
+            .metadata
+            .subnet_call_context_manager
+            .sign_with_schnorr_contexts
+            .clone()
+    }
+
+    /// Returns the Schnorr public key of `canister_id` for the given
+    /// `derivation_path`, as `schnorr_public_key` would return it.
+    pub fn schnorr_public_key(
+        &self,
+        key_id: &SchnorrKeyId,
+        canister_id: CanisterId,
+        derivation_path: Vec<Vec<u8>>,
+    ) -> Option<SchnorrPublicKeyResponse> {
+        let master_public_key = self.schnorr_subnet_public_keys.get(key_id)?;
+        let public_key = derive_tschnorr_public_key(
+            master_public_key,
+            &ExtendedDerivationPath {
+                caller: canister_id.get(),
+                derivation_path,
+            },
+        )
+        .ok()?;
+        Some(SchnorrPublicKeyResponse {
+            public_key: public_key.public_key,
+            chain_code: public_key.chain_code,
+        })
+    }
+
+    /// Signs the message of `context` with the key derived from the master
+    /// secret key of the requested Schnorr key. A real subnet runs the
+    /// threshold signing protocol instead.
+    fn sign_with_schnorr(&self, context: &SignWithSchnorrContext) -> Vec<u8> {
+        self.schnorr_secret_keys
+            .get(&context.key_id)
+            .expect("Schnorr key is validated by execution")
+            .sign(
+                &ExtendedDerivationPath {
+                    caller: context.request.sender.get(),
+                    derivation_path: context.derivation_path.clone(),
+                },
+                &context.message,
+            )
+            .expect("failed to sign with the derived Schnorr key")
+    }
+
+    /// Returns vetKD contexts from internal subnet call context manager.
+    pub fn vetkd_contexts(&self) -> BTreeMap<CallbackId, VetKdContext> {
+        let state = self.state_manager.get_latest_state().take();
+        state
+            .metadata
+            .subnet_call_context_manager
+            .vetkd_contexts
+            .clone()
+    }
+
+    /// Returns the vetKD public key of `canister_id` for the given
+    /// `derivation_path`, as `vetkd_public_key` would return it.
+    pub fn vetkd_public_key(
+        &self,
+        key_id: &VetKdKeyId,
+        canister_id: CanisterId,
+        derivation_path: Vec<Vec<u8>>,
+    ) -> Option<VetKdPublicKeyResult> {
+        let master_public_key = self.vetkd_subnet_public_keys.get(key_id)?;
+        let master_public_key = G2Affine::deserialize(&master_public_key.into_bytes()).ok()?;
+        let derivation_path =
+            VetKdDerivationPath::new(canister_id.get_ref().as_slice(), &derivation_path);
+        let public_key =
+            DerivedPublicKey::compute_derived_key(&master_public_key, &derivation_path);
+        Some(VetKdPublicKeyResult {
+            public_key: public_key.serialize().to_vec(),
+        })
+    }
+
+    /// Computes the encrypted key requested by `context` using the vetKD
+    /// master secret key. A real subnet combines the encrypted key shares of
+    /// its nodes instead; here the single share is the whole key.
+    fn derive_encrypted_key(&self, context: &VetKdContext) -> Vec<u8> {
+        let master_public_key = G2Affine::from(G2Affine::generator() * &self.vetkd_secret_key);
+        let transport_public_key = TransportPublicKey::deserialize(&context.encryption_public_key)
+            .expect("transport public key is validated by execution");
+        let derivation_path = VetKdDerivationPath::new(
+            context.request.sender.get_ref().as_slice(),
+            &context.derivation_path,
+        );
+        // Derive the randomness from the callback id of the request so that
+        // the responses are deterministic.
+        let mut seed = [0u8; 32];
+        seed[..8].copy_from_slice(&context.request.sender_reply_callback.get().to_le_bytes());
+        let mut rng = ChaCha20Rng::from_seed(seed);
+        let share = EncryptedKeyShare::create(
+            &mut rng,
+            &master_public_key,
+            &self.vetkd_secret_key,
+            &transport_public_key,
+            &derivation_path,
+            &context.derivation_id,
+        );
+        EncryptedKey::combine(
+            &[(0, master_public_key.clone(), share)],
+            1,
+            &master_public_key,
+            &transport_public_key,
+            &derivation_path,
+            &context.derivation_id,
+        )
+        .expect("failed to combine the encrypted key share")
+        .serialize()
+        .to_vec()
+    }
+
     /// Returns canister HTTP request contexts from internal subnet call context manager.
     pub fn canister_http_request_contexts(
         &self,
@@ -1929,6 +2337,11 @@ impl PayloadBuilder {
         self
     }
 
+    pub fn signed_ingress(mut self, msg: SignedIngress) -> Self {
+        self.ingress_messages.push(msg);
+        self
+    }
+
     pub fn xnet_payload(mut self, xnet_payload: XNetPayload) -> Self {
         self.xnet_payload = xnet_payload;
         self
@@ -1945,6 +2358,17 @@ impl PayloadBuilder {
         self
     }
 
+    pub fn http_reject(mut self, id: CallbackId, reject: RejectContext) -> Self {
+        self.consensus_responses.push(Response {
+            originator: CanisterId::ic_00(),
+            respondent: CanisterId::ic_00(),
+            originator_reply_callback: id,
+            refund: Cycles::zero(),
+            response_payload: MsgPayload::Reject(reject),
+        });
+        self
+    }
+
     pub fn ingress_ids(&self) -> Vec<MessageId> {
         self.ingress_messages.iter().map(|i| i.id()).collect()
     }
@@ -7,10 +7,14 @@ use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::page_map::PageAllocatorFileDescriptor;
 use ic_replicated_state::Memory;
 use ic_replicated_state::{
-    canister_state::execution_state::WasmBinary, page_map::PageMap, CanisterMetrics, CanisterState,
-    ExecutionState, ReplicatedState, SchedulerState, SystemState,
+    canister_state::{execution_state::WasmBinary, system_state::WasmChunkStore},
+    page_map::PageMap,
+    CanisterMetrics, CanisterSnapshot, CanisterSnapshots, CanisterState, ExecutionState,
+    ReplicatedState, SchedulerState, SnapshotId, SystemState,
+};
+use ic_state_layout::{
+    CanisterLayout, CanisterSnapshotBits, CanisterStateBits, CheckpointLayout, ReadOnly, ReadPolicy,
 };
-use ic_state_layout::{CanisterLayout, CanisterStateBits, CheckpointLayout, ReadOnly, ReadPolicy};
 use ic_types::{CanisterTimer, Height, LongExecutionMode, Time};
 use ic_utils::thread::parallel_map;
 use std::collections::BTreeMap;
@@ -59,6 +63,13 @@ pub(crate) fn make_checkpoint(
         })
         .unwrap();
 
+    tip_channel
+        .send(TipRequest::FilterTipSnapshots {
+            height,
+            ids: state.canister_snapshots.snapshot_ids(),
+        })
+        .unwrap();
+
     let cp = {
         let _timer = metrics
             .make_checkpoint_step_duration
@@ -213,7 +224,31 @@ pub fn load_checkpoint<P: ReadPolicy + Send + Sync>(
         canister_states
     };
 
-    let state = ReplicatedState::new_from_checkpoint(canister_states, metadata, subnet_queues);
+    let canister_snapshots = {
+        let _timer = metrics
+            .load_checkpoint_step_duration
+            .with_label_values(&["canister_snapshots"])
+            .start_timer();
+
+        let mut canister_snapshots = BTreeMap::new();
+        for snapshot_id in checkpoint_layout.snapshot_ids()?.iter() {
+            let snapshot = load_snapshot_from_checkpoint(
+                checkpoint_layout,
+                snapshot_id,
+                Arc::clone(&fd_factory),
+            )?;
+            canister_snapshots.insert(*snapshot_id, Arc::new(snapshot));
+        }
+
+        CanisterSnapshots::new(canister_snapshots)
+    };
+
+    let state = ReplicatedState::new_from_checkpoint(
+        canister_states,
+        metadata,
+        subnet_queues,
+        canister_snapshots,
+    );
 
     Ok(state)
 }
@@ -313,6 +348,14 @@ pub fn load_canister_state<P: ReadPolicy>(
         None => None,
     };
 
+    let starting_time = Instant::now();
+    let wasm_chunk_store_data = PageMap::open(
+        &canister_layout.wasm_chunk_store(),
+        height,
+        Arc::clone(&fd_factory),
+    )?;
+    durations.insert("wasm_chunk_store", starting_time.elapsed());
+
     let starting_time = Instant::now();
     let queues =
         ic_replicated_state::CanisterQueues::try_from(canister_layout.queues().deserialize()?)
@@ -348,6 +391,15 @@ pub fn load_canister_state<P: ReadPolicy>(
         CanisterTimer::from_nanos_since_unix_epoch(canister_state_bits.global_timer_nanos),
         canister_state_bits.canister_version,
         canister_state_bits.canister_history,
+        canister_state_bits.next_snapshot_id,
+        canister_state_bits.snapshots_memory_usage,
+        WasmChunkStore::from_checkpoint(
+            wasm_chunk_store_data,
+            canister_state_bits.wasm_chunk_store_metadata,
+        ),
+        canister_state_bits.log_visibility,
+        canister_state_bits.canister_log,
+        canister_state_bits.wasm_memory_limit,
     );
 
     let canister_state = CanisterState {
@@ -366,6 +418,7 @@ pub fn load_canister_state<P: ReadPolicy>(
             time_of_last_allocation_charge: Time::from_nanos_since_unix_epoch(
                 canister_state_bits.time_of_last_allocation_charge_nanos,
             ),
+            total_query_stats: canister_state_bits.total_query_stats,
         },
     };
 
@@ -387,3 +440,52 @@ fn load_canister_state_from_checkpoint<P: ReadPolicy>(
         Arc::clone(&fd_factory),
     )
 }
+
+fn load_snapshot_from_checkpoint<P: ReadPolicy>(
+    checkpoint_layout: &CheckpointLayout<P>,
+    snapshot_id: &SnapshotId,
+    fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+) -> Result<CanisterSnapshot, CheckpointError> {
+    let snapshot_layout = checkpoint_layout.snapshot(snapshot_id)?;
+    let snapshot_bits: CanisterSnapshotBits = CanisterSnapshotBits::try_from(
+        snapshot_layout.snapshot().deserialize()?,
+    )
+    .map_err(|err| CheckpointError::ProtoError {
+        path: snapshot_layout.raw_path(),
+        field: format!("snapshots[{}]::snapshot_bits", snapshot_id),
+        proto_err: err.to_string(),
+    })?;
+
+    let wasm_memory = Memory::new(
+        PageMap::open(
+            &snapshot_layout.vmemory_0(),
+            checkpoint_layout.height(),
+            Arc::clone(&fd_factory),
+        )?,
+        snapshot_bits.wasm_memory_size,
+    );
+    let stable_memory = Memory::new(
+        PageMap::open(
+            &snapshot_layout.stable_memory_blob(),
+            checkpoint_layout.height(),
+            Arc::clone(&fd_factory),
+        )?,
+        snapshot_bits.stable_memory_size,
+    );
+    let wasm_binary = snapshot_layout
+        .wasm()
+        .deserialize(snapshot_bits.binary_hash)?;
+
+    Ok(CanisterSnapshot::new(
+        snapshot_bits.canister_id,
+        snapshot_bits.taken_at_timestamp,
+        snapshot_bits.canister_version,
+        snapshot_bits.certified_data,
+        snapshot_bits.global_timer,
+        wasm_binary,
+        wasm_memory,
+        stable_memory,
+        snapshot_bits.exported_globals,
+        snapshot_bits.total_size,
+    ))
+}
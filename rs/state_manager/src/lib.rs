@@ -40,7 +40,7 @@ use ic_protobuf::{messaging::xnet::v1, state::v1 as pb};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
     canister_state::execution_state::SandboxMemory, page_map::PersistenceError, PageIndex, PageMap,
-    ReplicatedState,
+    ReplicatedState, SnapshotId,
 };
 use ic_state_layout::{error::LayoutError, AccessPolicy, CheckpointLayout, ReadOnly, StateLayout};
 use ic_types::{
@@ -973,6 +973,9 @@ struct PopulatedMetadata {
 pub enum PageMapType {
     WasmMemory(CanisterId),
     StableMemory(CanisterId),
+    SnapshotWasmMemory(SnapshotId),
+    SnapshotStableMemory(SnapshotId),
+    WasmChunkStore(CanisterId),
 }
 
 impl PageMapType {
@@ -984,6 +987,11 @@ impl PageMapType {
                 result.push(Self::WasmMemory(id.to_owned()));
                 result.push(Self::StableMemory(id.to_owned()));
             }
+            result.push(Self::WasmChunkStore(id.to_owned()));
+        }
+        for (id, _snapshot) in state.canister_snapshots.iter() {
+            result.push(Self::SnapshotWasmMemory(id.to_owned()));
+            result.push(Self::SnapshotStableMemory(id.to_owned()));
         }
 
         result
@@ -997,6 +1005,9 @@ impl PageMapType {
         match &self {
             PageMapType::WasmMemory(id) => Ok(layout.canister(id)?.vmemory_0()),
             PageMapType::StableMemory(id) => Ok(layout.canister(id)?.stable_memory_blob()),
+            PageMapType::SnapshotWasmMemory(id) => Ok(layout.snapshot(id)?.vmemory_0()),
+            PageMapType::SnapshotStableMemory(id) => Ok(layout.snapshot(id)?.stable_memory_blob()),
+            PageMapType::WasmChunkStore(id) => Ok(layout.canister(id)?.wasm_chunk_store()),
         }
     }
 
@@ -1013,6 +1024,17 @@ impl PageMapType {
                     .as_ref()
                     .map(|ex| &ex.stable_memory.page_map)
             }),
+            PageMapType::SnapshotWasmMemory(id) => state
+                .canister_snapshots
+                .get(*id)
+                .map(|snapshot| &snapshot.wasm_memory().page_map),
+            PageMapType::SnapshotStableMemory(id) => state
+                .canister_snapshots
+                .get(*id)
+                .map(|snapshot| &snapshot.stable_memory().page_map),
+            PageMapType::WasmChunkStore(id) => state
+                .canister_state(id)
+                .map(|can| can.system_state.wasm_chunk_store.page_map()),
         }
     }
 
@@ -1029,6 +1051,17 @@ impl PageMapType {
                     .as_mut()
                     .map(|ex| &mut ex.stable_memory.page_map)
             }),
+            PageMapType::SnapshotWasmMemory(id) => state
+                .canister_snapshots
+                .get_mut(*id)
+                .map(|snapshot| &mut snapshot.wasm_memory_mut().page_map),
+            PageMapType::SnapshotStableMemory(id) => state
+                .canister_snapshots
+                .get_mut(*id)
+                .map(|snapshot| &mut snapshot.stable_memory_mut().page_map),
+            PageMapType::WasmChunkStore(id) => state
+                .canister_state_mut(id)
+                .map(|can| can.system_state.wasm_chunk_store.page_map_mut()),
         }
     }
 }
@@ -1056,6 +1089,24 @@ pub fn get_dirty_pages(
 ) -> DirtyPages {
     let mut result: DirtyPages = PageMapType::list_all(state)
         .into_iter()
+        // A canister snapshot taken since the previous checkpoint shares its page maps with
+        // the canister, so its dirty pages are relative to the canister's files.
+        // Only snapshots that were already part of the previous checkpoint can
+        // reuse its hashes.
+        .filter(|entry| match entry {
+            PageMapType::SnapshotWasmMemory(id) | PageMapType::SnapshotStableMemory(id) => {
+                previous_snapshot.map_or(false, |previous_snapshot| {
+                    previous_snapshot
+                        .state
+                        .canister_snapshots
+                        .get(*id)
+                        .is_some()
+                })
+            }
+            PageMapType::WasmMemory(_)
+            | PageMapType::StableMemory(_)
+            | PageMapType::WasmChunkStore(_) => true,
+        })
         .filter_map(|entry| {
             let page_map = entry.get(state)?;
             let height = page_map.base_height?;
@@ -1827,6 +1878,9 @@ impl StateManagerImpl {
     /// during execution from the last flush.
     fn flush_page_maps(&self, tip_state: &mut ReplicatedState, height: Height) {
         self.metrics.checkpoint_metrics.page_map_flushes.inc();
+        // Snapshot operations must be applied to the tip before the deltas are
+        // flushed, as they copy the files the deltas are applied to.
+        let snapshot_operations = tip_state.canister_snapshots.take_unflushed_changes();
         let mut pagemaps = Vec::new();
         for entry in PageMapType::list_all(tip_state) {
             if let Some(page_map) = entry.get_mut(tip_state) {
@@ -1857,9 +1911,13 @@ impl StateManagerImpl {
                 page_map.strip_unflushed_delta();
             }
         }
-        if !pagemaps.is_empty() {
+        if !pagemaps.is_empty() || !snapshot_operations.is_empty() {
             self.tip_channel
-                .send(TipRequest::FlushPageMapDelta { height, pagemaps })
+                .send(TipRequest::FlushPageMapDelta {
+                    height,
+                    pagemaps,
+                    snapshot_operations,
+                })
                 .unwrap();
             // We flush further when the tip_channel queue is not empty. Meaning we're blind
             // to a request being processed, so we send Noop to signal for the busy Tip Thread.
@@ -9,12 +9,12 @@ use ic_logger::{error, fatal, info, ReplicaLogger};
 use ic_protobuf::state::system_metadata::v1::{SplitFrom, SystemMetadata};
 #[allow(unused)]
 use ic_replicated_state::{
-    canister_state::execution_state::SandboxMemory, CanisterState, NumWasmPages, PageMap,
-    ReplicatedState,
+    canister_state::execution_state::SandboxMemory, CanisterSnapshot, CanisterState, NumWasmPages,
+    PageMap, ReplicatedState, SnapshotId, SnapshotOperation,
 };
 use ic_state_layout::{
-    error::LayoutError, CanisterStateBits, CheckpointLayout, ExecutionStateBits, ReadOnly,
-    RwPolicy, StateLayout, TipHandler,
+    error::LayoutError, CanisterSnapshotBits, CanisterStateBits, CheckpointLayout,
+    ExecutionStateBits, ReadOnly, RwPolicy, StateLayout, TipHandler,
 };
 use ic_types::state_sync::{
     FILE_GROUP_CHUNK_ID_OFFSET, MANIFEST_CHUNK_ID_OFFSET, MAX_SUPPORTED_STATE_SYNC_VERSION,
@@ -71,11 +71,18 @@ pub(crate) enum TipRequest {
         height: Height,
         ids: BTreeSet<CanisterId>,
     },
-    /// Flush PageMaps's unflushed delta on disc.
+    /// Filter snapshots in tip. Remove ones not present in the set.
+    /// State: !Empty
+    FilterTipSnapshots {
+        height: Height,
+        ids: BTreeSet<SnapshotId>,
+    },
+    /// Apply snapshot operations and flush PageMaps's unflushed delta on disc.
     /// State: ReadyForPageDeltas(h) -> ReadyForPageDeltas(height), height >= h
     FlushPageMapDelta {
         height: Height,
         pagemaps: Vec<PageMapToFlush>,
+        snapshot_operations: Vec<SnapshotOperation>,
     },
     /// Reset tip folder to the checkpoint with given height.
     /// State: * -> ReadyForPageDeltas(checkpoint_layout.height())
@@ -166,6 +173,21 @@ pub(crate) fn spawn_tip_thread(
                                     )
                                 });
                         }
+                        TipRequest::FilterTipSnapshots { height, ids } => {
+                            debug_assert_ne!(tip_state, TipState::Empty);
+
+                            let _timer = request_timer(&metrics, "filter_tip_snapshots");
+                            tip_handler
+                                .filter_tip_snapshots(height, &ids)
+                                .unwrap_or_else(|err| {
+                                    fatal!(
+                                        log,
+                                        "Failed to filter tip snapshots for height @{}: {}",
+                                        height,
+                                        err
+                                    )
+                                });
+                        }
                         TipRequest::TipToCheckpoint { height, sender } => {
                             debug_assert_eq!(tip_state, TipState::Serialized(height));
                             debug_assert!(have_latest_manifest);
@@ -218,7 +240,11 @@ pub(crate) fn spawn_tip_thread(
                                 });
                         }
 
-                        TipRequest::FlushPageMapDelta { height, pagemaps } => {
+                        TipRequest::FlushPageMapDelta {
+                            height,
+                            pagemaps,
+                            snapshot_operations,
+                        } => {
                             let _timer = request_timer(&metrics, "flush_unflushed_delta");
                             #[cfg(debug_assert)]
                             match tip_state {
@@ -226,6 +252,28 @@ pub(crate) fn spawn_tip_thread(
                                 _ => panic!("Unexpected tip state: {:?}", tip_state),
                             }
                             tip_state = TipState::ReadyForPageDeltas(height);
+                            for operation in snapshot_operations {
+                                apply_snapshot_operation(
+                                    &log,
+                                    &tip_handler.tip(height).unwrap_or_else(|err| {
+                                        fatal!(
+                                            log,
+                                            "Failed to get tip @{} to apply snapshot operation: {}",
+                                            height,
+                                            err
+                                        );
+                                    }),
+                                    &operation,
+                                )
+                                .unwrap_or_else(|err| {
+                                    fatal!(
+                                        log,
+                                        "Failed to apply snapshot operation {:?}: {}",
+                                        operation,
+                                        err
+                                    );
+                                });
+                            }
                             parallel_map(
                                 &mut thread_pool,
                                 pagemaps.into_iter().map(
@@ -411,6 +459,112 @@ fn serialize_to_tip(
         result?;
     }
 
+    let results = parallel_map(
+        thread_pool,
+        state.canister_snapshots.iter(),
+        |(snapshot_id, snapshot)| serialize_snapshot_to_tip(log, snapshot_id, snapshot, tip),
+    );
+
+    for result in results.into_iter() {
+        result?;
+    }
+
+    Ok(())
+}
+
+fn serialize_snapshot_to_tip(
+    log: &ReplicaLogger,
+    snapshot_id: &SnapshotId,
+    snapshot: &CanisterSnapshot,
+    tip: &CheckpointLayout<RwPolicy<TipHandler>>,
+) -> Result<(), CheckpointError> {
+    let snapshot_layout = tip.snapshot(snapshot_id)?;
+    let wasm = snapshot_layout.wasm();
+    // Snapshot IDs are never reused, so an existing Wasm file always belongs to
+    // this snapshot.
+    if !wasm.raw_path().exists() {
+        match snapshot.wasm_binary().file() {
+            Some(path) => {
+                ic_state_layout::utils::do_copy(log, path, wasm.raw_path()).map_err(|io_err| {
+                    CheckpointError::IoError {
+                        path: path.to_path_buf(),
+                        message: "failed to copy Wasm file".to_string(),
+                        io_err: io_err.to_string(),
+                    }
+                })?;
+            }
+            None => wasm.serialize(snapshot.wasm_binary())?,
+        }
+    }
+
+    // The deltas have been flushed already, this only makes sure the files exist.
+    snapshot
+        .wasm_memory()
+        .page_map
+        .persist_delta(&snapshot_layout.vmemory_0())?;
+    snapshot
+        .stable_memory()
+        .page_map
+        .persist_delta(&snapshot_layout.stable_memory_blob())?;
+
+    snapshot_layout.snapshot().serialize(
+        CanisterSnapshotBits {
+            snapshot_id: *snapshot_id,
+            canister_id: snapshot.canister_id(),
+            taken_at_timestamp: snapshot.taken_at_timestamp(),
+            canister_version: snapshot.canister_version(),
+            binary_hash: Some(snapshot.wasm_binary().module_hash().into()),
+            certified_data: snapshot.certified_data().clone(),
+            exported_globals: snapshot.exported_globals().clone(),
+            wasm_memory_size: snapshot.wasm_memory().size,
+            stable_memory_size: snapshot.stable_memory().size,
+            total_size: snapshot.size(),
+            global_timer: snapshot.global_timer(),
+        }
+        .into(),
+    )?;
+    Ok(())
+}
+
+/// Mirrors a snapshot operation in the tip by copying the memory files
+/// between the canister and the snapshot. The unflushed deltas of the page
+/// maps involved are applied on top of the copied files afterwards.
+fn apply_snapshot_operation(
+    log: &ReplicaLogger,
+    tip: &CheckpointLayout<RwPolicy<TipHandler>>,
+    operation: &SnapshotOperation,
+) -> Result<(), CheckpointError> {
+    let (canister_id, snapshot_id) = match operation {
+        SnapshotOperation::Backup(canister_id, snapshot_id)
+        | SnapshotOperation::Restore(canister_id, snapshot_id) => (canister_id, snapshot_id),
+    };
+    let canister_layout = tip.canister(canister_id)?;
+    let snapshot_layout = tip.snapshot(snapshot_id)?;
+    let files = [
+        (canister_layout.vmemory_0(), snapshot_layout.vmemory_0()),
+        (
+            canister_layout.stable_memory_blob(),
+            snapshot_layout.stable_memory_blob(),
+        ),
+    ];
+    for (canister_file, snapshot_file) in files {
+        let (src, dst) = match operation {
+            SnapshotOperation::Backup(..) => (canister_file, snapshot_file),
+            SnapshotOperation::Restore(..) => (snapshot_file, canister_file),
+        };
+        if src.exists() {
+            ic_state_layout::utils::do_copy_overwrite(log, &src, &dst).map_err(|io_err| {
+                CheckpointError::IoError {
+                    path: src.clone(),
+                    message: "failed to copy memory file".to_string(),
+                    io_err: io_err.to_string(),
+                }
+            })?;
+        } else {
+            // Nothing was flushed for this memory yet, so the copy is empty.
+            truncate_path(log, &dst);
+        }
+    }
     Ok(())
 }
 
@@ -475,6 +629,11 @@ fn serialize_canister_to_tip(
             None
         }
     };
+    canister_state
+        .system_state
+        .wasm_chunk_store
+        .page_map()
+        .persist_delta(&canister_layout.wasm_chunk_store())?;
     // Priority credit must be zero at this point
     assert_eq!(canister_state.scheduler_state.priority_credit.get(), 0);
     canister_layout.canister().serialize(
@@ -537,6 +696,17 @@ fn serialize_canister_to_tip(
                 .get_consumed_cycles_since_replica_started_by_use_cases()
                 .clone(),
             canister_history: canister_state.system_state.get_canister_history().clone(),
+            next_snapshot_id: canister_state.system_state.next_snapshot_id(),
+            snapshots_memory_usage: canister_state.system_state.snapshots_memory_usage,
+            wasm_chunk_store_metadata: canister_state
+                .system_state
+                .wasm_chunk_store
+                .metadata()
+                .clone(),
+            log_visibility: canister_state.system_state.log_visibility,
+            canister_log: canister_state.system_state.canister_log.clone(),
+            wasm_memory_limit: canister_state.system_state.wasm_memory_limit,
+            total_query_stats: canister_state.scheduler_state.total_query_stats.clone(),
         }
         .into(),
     )?;
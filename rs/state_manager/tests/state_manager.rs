@@ -552,6 +552,33 @@ fn stable_memory_is_persisted() {
     });
 }
 
+#[test]
+fn wasm_chunk_store_is_persisted() {
+    state_manager_restart_test(|state_manager, restart_fn| {
+        let (_height, mut state) = state_manager.take_tip();
+        insert_dummy_canister(&mut state, canister_test_id(100));
+        let canister_state = state.canister_state_mut(&canister_test_id(100)).unwrap();
+        let hash = canister_state
+            .system_state
+            .wasm_chunk_store
+            .insert_chunk(&[1; 5000]);
+        state_manager.commit_and_certify(state, height(1), CertificationScope::Full);
+
+        let state_manager = restart_fn(state_manager, None);
+
+        let recovered = state_manager.get_latest_state();
+        assert_eq!(height(1), recovered.height());
+        let state = recovered.take();
+        let wasm_chunk_store = &state
+            .canister_state(&canister_test_id(100))
+            .unwrap()
+            .system_state
+            .wasm_chunk_store;
+        assert_eq!(wasm_chunk_store.get_chunk(&hash), Some(vec![1; 5000]));
+        assert_eq!(wasm_chunk_store.memory_usage(), NumBytes::from(5000));
+    });
+}
+
 #[test]
 fn missing_stable_memory_file_is_handled() {
     use ic_state_layout::{CheckpointLayout, RwPolicy};
@@ -2001,14 +2028,16 @@ fn can_state_sync_from_cache() {
             //           2 |         18 |     1     | canister_states/00000000000000640101/software.wasm
             //           3 |       4096 |     2     | canister_states/00000000000000640101/stable_memory.bin
             //           4 |       4096 |     3     | canister_states/00000000000000640101/vmemory_0.bin
-            //           5 |        221 |     4     | canister_states/00000000000000c80101/canister.pbuf
-            //           6 |          0 |    N/A    | canister_states/00000000000000c80101/queues.pbuf
-            //           7 |         18 |     5     | canister_states/00000000000000c80101/software.wasm
-            //           8 |          0 |    N/A    | canister_states/00000000000000c80101/stable_memory.bin
-            //           9 |          0 |    N/A    | canister_states/00000000000000c80101/vmemory_0.bin
-            //          10 |          0 |    N/A    | ingress_history.pbuf
-            //          11 |          0 |    N/A    | subnet_queues.pbuf
-            //          12 |         86 |     6     | system_metadata.pbuf
+            //           5 |          0 |    N/A    | canister_states/00000000000000640101/wasm_chunk_store.bin
+            //           6 |        221 |     4     | canister_states/00000000000000c80101/canister.pbuf
+            //           7 |          0 |    N/A    | canister_states/00000000000000c80101/queues.pbuf
+            //           8 |         18 |     5     | canister_states/00000000000000c80101/software.wasm
+            //           9 |          0 |    N/A    | canister_states/00000000000000c80101/stable_memory.bin
+            //          10 |          0 |    N/A    | canister_states/00000000000000c80101/vmemory_0.bin
+            //          11 |          0 |    N/A    | canister_states/00000000000000c80101/wasm_chunk_store.bin
+            //          12 |          0 |    N/A    | ingress_history.pbuf
+            //          13 |          0 |    N/A    | subnet_queues.pbuf
+            //          14 |         86 |     6     | system_metadata.pbuf
             //
             // Given the current state layout, the chunk for `system_metadata.pbuf` is the last one in the chunk table.
             // If there are changes to the state layout and it changes the position of `system_metadata.pbuf` in the chunk table,
@@ -3071,6 +3100,21 @@ fn can_get_dirty_pages() {
                 file_type: FileType::PageMap(PageMapType::StableMemory(canister_test_id(100))),
                 page_delta_indices: vec![],
             },
+            DirtyPageMap {
+                height: height(1),
+                file_type: FileType::PageMap(PageMapType::WasmChunkStore(canister_test_id(80))),
+                page_delta_indices: vec![],
+            },
+            DirtyPageMap {
+                height: height(1),
+                file_type: FileType::PageMap(PageMapType::WasmChunkStore(canister_test_id(90))),
+                page_delta_indices: vec![],
+            },
+            DirtyPageMap {
+                height: height(1),
+                file_type: FileType::PageMap(PageMapType::WasmChunkStore(canister_test_id(100))),
+                page_delta_indices: vec![],
+            },
             DirtyPageMap {
                 height: height(1),
                 file_type: FileType::WasmBinary(canister_test_id(80)),
@@ -3132,6 +3176,21 @@ fn can_get_dirty_pages() {
                 file_type: FileType::PageMap(PageMapType::StableMemory(canister_test_id(100))),
                 page_delta_indices: vec![PageIndex::new(1), PageIndex::new(300)],
             },
+            DirtyPageMap {
+                height: height(2),
+                file_type: FileType::PageMap(PageMapType::WasmChunkStore(canister_test_id(80))),
+                page_delta_indices: vec![],
+            },
+            DirtyPageMap {
+                height: height(2),
+                file_type: FileType::PageMap(PageMapType::WasmChunkStore(canister_test_id(90))),
+                page_delta_indices: vec![],
+            },
+            DirtyPageMap {
+                height: height(2),
+                file_type: FileType::PageMap(PageMapType::WasmChunkStore(canister_test_id(100))),
+                page_delta_indices: vec![],
+            },
             DirtyPageMap {
                 height: height(2),
                 file_type: FileType::WasmBinary(canister_test_id(80)),
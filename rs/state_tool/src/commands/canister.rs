@@ -0,0 +1,243 @@
+//! Extracts and displays the state of individual canisters in a checkpoint.
+
+use ic_replicated_state::{
+    canister_state::{execution_state::Memory, num_bytes_try_from},
+    page_map::{TestPageAllocatorFileDescriptorImpl, PAGE_SIZE},
+    CanisterState, PageIndex,
+};
+use ic_state_layout::CompleteCheckpointLayout;
+use ic_state_manager::checkpoint::load_canister_state as load_canister_state_from_layout;
+use ic_types::{CanisterId, Height};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// File name of the dumped Wasm heap, relative to the output directory.
+const HEAP_FILE: &str = "heap.bin";
+/// File name of the dumped stable memory, relative to the output directory.
+const STABLE_MEMORY_FILE: &str = "stable_memory.bin";
+
+/// Loads the state of `canister_id` from the checkpoint at `path`.
+///
+/// Only the files of the canister itself are read, so neither the rest of the
+/// checkpoint nor the type of the subnet it belongs to is needed.
+fn load_canister_state(path: PathBuf, canister_id: CanisterId) -> Result<CanisterState, String> {
+    let unused_height = Height::from(0);
+    let layout = CompleteCheckpointLayout::new_untracked(path.clone(), unused_height)
+        .map_err(|e| format!("failed to open checkpoint {}: {}", path.display(), e))?;
+    let canister_ids = layout
+        .canister_ids()
+        .map_err(|e| format!("failed to list canisters in {}: {}", path.display(), e))?;
+    if !canister_ids.contains(&canister_id) {
+        return Err(format!(
+            "canister {} not found in checkpoint {}",
+            canister_id,
+            path.display()
+        ));
+    }
+    let canister_layout = layout
+        .canister(&canister_id)
+        .map_err(|e| format!("failed to open canister {}: {}", canister_id, e))?;
+    let (canister, _metrics) = load_canister_state_from_layout(
+        &canister_layout,
+        &canister_id,
+        layout.height(),
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+    )
+    .map_err(|e| format!("failed to load canister {}: {}", canister_id, e))?;
+    Ok(canister)
+}
+
+/// Writes the contents of `memory` up to its current size into the file at
+/// `path`. Returns the number of bytes written.
+fn dump_memory(memory: &Memory, path: &Path) -> Result<usize, String> {
+    let size = num_bytes_try_from(memory.size)?.get() as usize;
+    let mut file = File::create(path)
+        .map_err(|e| format!("failed to create file {}: {}", path.display(), e))?;
+    for offset in (0..size).step_by(PAGE_SIZE) {
+        let page = memory
+            .page_map
+            .get_page(PageIndex::new((offset / PAGE_SIZE) as u64));
+        file.write_all(&page[..PAGE_SIZE.min(size - offset)])
+            .map_err(|e| format!("failed to write file {}: {}", path.display(), e))?;
+    }
+    Ok(size)
+}
+
+/// Writes the Wasm module of `canister_id` into the file at `output`.
+pub fn do_canister_wasm(
+    path: PathBuf,
+    canister_id: CanisterId,
+    output: PathBuf,
+) -> Result<(), String> {
+    let canister = load_canister_state(path, canister_id)?;
+    let execution_state = canister
+        .execution_state
+        .as_ref()
+        .ok_or_else(|| format!("canister {} has no Wasm module installed", canister_id))?;
+    let module = &execution_state.wasm_binary.binary;
+    std::fs::write(&output, module.as_slice())
+        .map_err(|e| format!("failed to write file {}: {}", output.display(), e))?;
+    println!(
+        "Wrote {} bytes (module hash {}) to {}",
+        module.len(),
+        hex::encode(module.module_hash()),
+        output.display()
+    );
+    Ok(())
+}
+
+/// Writes the Wasm heap and stable memory of `canister_id` into `heap.bin`
+/// and `stable_memory.bin` under the `output` directory.
+pub fn do_canister_memory(
+    path: PathBuf,
+    canister_id: CanisterId,
+    output: PathBuf,
+) -> Result<(), String> {
+    let canister = load_canister_state(path, canister_id)?;
+    let execution_state = canister
+        .execution_state
+        .as_ref()
+        .ok_or_else(|| format!("canister {} has no Wasm module installed", canister_id))?;
+    std::fs::create_dir_all(&output)
+        .map_err(|e| format!("failed to create directory {}: {}", output.display(), e))?;
+    for (file_name, memory) in [
+        (HEAP_FILE, &execution_state.wasm_memory),
+        (STABLE_MEMORY_FILE, &execution_state.stable_memory),
+    ] {
+        let file = output.join(file_name);
+        let size = dump_memory(memory, &file)?;
+        println!(
+            "Wrote {} bytes ({} pages with data) to {}",
+            size,
+            memory.page_map.host_pages_iter().count(),
+            file.display()
+        );
+    }
+    Ok(())
+}
+
+/// Displays the system state of `canister_id`: controllers, cycles, status,
+/// timers, queue statistics, task queue and canister history.
+pub fn do_canister_system_state(path: PathBuf, canister_id: CanisterId) -> Result<(), String> {
+    let canister = load_canister_state(path, canister_id)?;
+    let system_state = &canister.system_state;
+    let queues = system_state.queues();
+    let history = system_state.get_canister_history();
+
+    println!("Canister: {}", canister_id);
+    println!(
+        "Controllers: {}",
+        system_state.collect_controllers_as_string()
+    );
+    println!("Status: {}", system_state.status_string());
+    println!("Canister version: {}", system_state.canister_version);
+    println!("Balance: {}", system_state.balance());
+    println!("Reserved balance: {}", system_state.reserved_balance());
+    println!("Freezing threshold: {}", system_state.freeze_threshold);
+    println!("Memory allocation: {}", system_state.memory_allocation);
+    println!("Wasm memory limit: {:?}", system_state.wasm_memory_limit);
+    println!("Global timer: {:?}", system_state.global_timer);
+    println!(
+        "Module hash: {}",
+        canister
+            .execution_state
+            .as_ref()
+            .map(|es| hex::encode(es.wasm_binary.binary.module_hash()))
+            .unwrap_or_else(|| "<empty>".to_string())
+    );
+    println!(
+        "Queues: {} ingress, {} input ({} responses, {} reservations), {} output",
+        queues.ingress_queue_message_count(),
+        queues.input_queues_message_count(),
+        queues.input_queues_response_count(),
+        queues.input_queues_reservation_count(),
+        queues.output_queues_message_count()
+    );
+    println!(
+        "Call context manager: {:#?}",
+        system_state.call_context_manager()
+    );
+    println!("Task queue: {:#?}", system_state.task_queue);
+    println!(
+        "Canister history ({} changes in total):",
+        history.get_total_num_changes()
+    );
+    for change in history.get_changes(usize::MAX) {
+        println!("{:#?}", change);
+    }
+    Ok(())
+}
+
+/// Pretty-prints the ingress, input and output queues of `canister_id`.
+pub fn do_canister_queues(path: PathBuf, canister_id: CanisterId) -> Result<(), String> {
+    let canister = load_canister_state(path, canister_id)?;
+    println!("{:#?}", canister.system_state.queues());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_protobuf::state::canister_state_bits::v1 as pb;
+    use ic_replicated_state::CanisterStatus;
+    use ic_state_layout::{CheckpointLayout, RwPolicy};
+    use ic_types::Cycles;
+    use tempfile::TempDir;
+
+    /// Writes a minimal, stopped canister with the given balance into the
+    /// checkpoint at `root`.
+    fn write_canister(root: &Path, canister_id: CanisterId, balance: Cycles) {
+        let layout =
+            CheckpointLayout::<RwPolicy<()>>::new_untracked(root.into(), Height::new(0)).unwrap();
+        layout
+            .canister(&canister_id)
+            .unwrap()
+            .canister()
+            .serialize(pb::CanisterStateBits {
+                cycles_balance: Some(balance.into()),
+                canister_status: Some((&CanisterStatus::Stopped).into()),
+                time_of_last_allocation_charge_nanos: Some(0),
+                ..Default::default()
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn loads_only_the_requested_canister() {
+        let checkpoint = TempDir::new().unwrap();
+        let canister_id = CanisterId::from_u64(1);
+        write_canister(checkpoint.path(), canister_id, Cycles::new(42));
+
+        // Another canister whose state is corrupted, which must not be read.
+        let other_canister_id = CanisterId::from_u64(2);
+        write_canister(checkpoint.path(), other_canister_id, Cycles::new(1));
+        let other_canister_layout = CheckpointLayout::<RwPolicy<()>>::new_untracked(
+            checkpoint.path().into(),
+            Height::new(0),
+        )
+        .unwrap()
+        .canister(&other_canister_id)
+        .unwrap();
+        std::fs::write(other_canister_layout.canister().raw_path(), b"garbage").unwrap();
+
+        let canister = load_canister_state(checkpoint.path().into(), canister_id).unwrap();
+        assert_eq!(canister.canister_id(), canister_id);
+        assert_eq!(canister.system_state.balance(), Cycles::new(42));
+        assert_eq!(canister.system_state.status_string(), "Stopped");
+        assert!(canister.execution_state.is_none());
+
+        assert!(load_canister_state(checkpoint.path().into(), other_canister_id).is_err());
+    }
+
+    #[test]
+    fn fails_on_unknown_canister() {
+        let checkpoint = TempDir::new().unwrap();
+        write_canister(checkpoint.path(), CanisterId::from_u64(1), Cycles::new(42));
+
+        let err =
+            load_canister_state(checkpoint.path().into(), CanisterId::from_u64(3)).unwrap_err();
+        assert!(err.contains("not found"), "unexpected error: {}", err);
+    }
+}
@@ -2,12 +2,12 @@
 //!
 //! A command-line tool to manage Internet Computer replicated states (decode
 //! persisted state files, diff checkpoints, compute partial state hashes and
-//! checkpoint manifests, import state trees).
+//! checkpoint manifests, import state trees, inspect individual canisters).
 
 use clap::Parser;
 use ic_registry_routing_table::CanisterIdRange;
 use ic_registry_subnet_type::SubnetType;
-use ic_types::{PrincipalId, Time};
+use ic_types::{CanisterId, PrincipalId, Time};
 use std::path::PathBuf;
 
 mod commands;
@@ -105,6 +105,59 @@ enum Opt {
         canister: String,
     },
 
+    /// Writes the Wasm module of a canister in a checkpoint to a file.
+    #[clap(name = "canister_wasm")]
+    CanisterWasm {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+        /// The canister to extract the Wasm module of.
+        #[clap(long = "canister")]
+        canister_id: CanisterId,
+        /// Path to the file to write the Wasm module to.
+        #[clap(long = "output")]
+        output: PathBuf,
+    },
+
+    /// Writes the Wasm heap and stable memory of a canister in a checkpoint
+    /// to `heap.bin` and `stable_memory.bin` in the output directory.
+    #[clap(name = "canister_memory")]
+    CanisterMemory {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+        /// The canister to extract the memories of.
+        #[clap(long = "canister")]
+        canister_id: CanisterId,
+        /// Path to the directory to write the memory dumps to.
+        #[clap(long = "output")]
+        output: PathBuf,
+    },
+
+    /// Displays the system state (controllers, cycles, timers, queues, task
+    /// queue, history) of a canister in a checkpoint.
+    #[clap(name = "canister_system_state")]
+    CanisterSystemState {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+        /// The canister to display the system state of.
+        #[clap(long = "canister")]
+        canister_id: CanisterId,
+    },
+
+    /// Displays a pretty-printed view of the input and output queues of a
+    /// canister in a checkpoint.
+    #[clap(name = "canister_queues")]
+    CanisterQueues {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+        /// The canister to display the queues of.
+        #[clap(long = "canister")]
+        canister_id: CanisterId,
+    },
+
     /// Enumerates persisted states.
     #[clap(name = "list")]
     ListStates {
@@ -209,6 +262,22 @@ fn main() {
         Opt::CanisterHash { file, canister } => {
             commands::verify_manifest::do_canister_hash(&file, &canister)
         }
+        Opt::CanisterWasm {
+            path,
+            canister_id,
+            output,
+        } => commands::canister::do_canister_wasm(path, canister_id, output),
+        Opt::CanisterMemory {
+            path,
+            canister_id,
+            output,
+        } => commands::canister::do_canister_memory(path, canister_id, output),
+        Opt::CanisterSystemState { path, canister_id } => {
+            commands::canister::do_canister_system_state(path, canister_id)
+        }
+        Opt::CanisterQueues { path, canister_id } => {
+            commands::canister::do_canister_queues(path, canister_id)
+        }
         Opt::ListStates { config } => commands::list::do_list(config),
         Opt::Decode { file } => commands::decode::do_decode(file),
         Opt::CanisterIdToHex { canister_id } => {
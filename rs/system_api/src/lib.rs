@@ -23,6 +23,7 @@ use ic_replicated_state::{
 };
 use ic_sys::PageBytes;
 use ic_types::{
+    canister_log::{CanisterLog, MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE},
     ingress::WasmResult,
     messages::{CallContextId, RejectContext, Request, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES},
     methods::{SystemMethod, WasmClosure},
@@ -559,6 +560,23 @@ impl ApiType {
             ApiType::Cleanup { .. } => "cleanup",
         }
     }
+
+    /// Returns the time at which the message is executed.
+    pub fn time(&self) -> Time {
+        match self {
+            ApiType::Start { time }
+            | ApiType::Init { time, .. }
+            | ApiType::SystemTask { time, .. }
+            | ApiType::Update { time, .. }
+            | ApiType::Cleanup { time, .. }
+            | ApiType::NonReplicatedQuery { time, .. }
+            | ApiType::ReplicatedQuery { time, .. }
+            | ApiType::PreUpgrade { time, .. }
+            | ApiType::ReplyCallback { time, .. }
+            | ApiType::RejectCallback { time, .. }
+            | ApiType::InspectMessage { time, .. } => *time,
+        }
+    }
 }
 
 // This type is potentially serialized and exposed to the external world.  We
@@ -805,6 +823,10 @@ pub struct SystemApiImpl {
 
     /// Tracks the complexity accumulated during the message execution.
     execution_complexity: ExecutionComplexity,
+
+    /// Log records written during the message execution. They are appended
+    /// to the canister log once the execution finishes.
+    canister_log: CanisterLog,
 }
 
 impl SystemApiImpl {
@@ -843,7 +865,48 @@ impl SystemApiImpl {
             current_slice_instruction_limit: i64::try_from(slice_limit).unwrap_or(i64::MAX),
             instructions_executed_before_current_slice: 0,
             execution_complexity: ExecutionComplexity::default(),
+            canister_log: CanisterLog::default(),
+        }
+    }
+
+    /// Returns the log records written during the message execution.
+    pub fn take_canister_log(&mut self) -> CanisterLog {
+        std::mem::take(&mut self.canister_log)
+    }
+
+    /// Returns `Err(HypervisorError::WasmMemoryLimitExceeded)` if the canister
+    /// has a Wasm memory limit and growing the Wasm memory to the given size
+    /// would exceed it.
+    ///
+    /// The limit applies only to replicated executions that persist their
+    /// changes to the Wasm memory, so that queries keep working and a canister
+    /// that reached its limit can still be upgraded.
+    fn check_wasm_memory_limit(&self, new_size_in_pages: u64) -> HypervisorResult<()> {
+        let applies = match &self.api_type {
+            ApiType::Update { .. } | ApiType::SystemTask { .. } => true,
+            ApiType::ReplyCallback { execution_mode, .. }
+            | ApiType::RejectCallback { execution_mode, .. } => {
+                *execution_mode == ExecutionMode::Replicated
+            }
+            ApiType::Start { .. }
+            | ApiType::Init { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::InspectMessage { .. } => false,
+        };
+        if !applies {
+            return Ok(());
+        }
+        if let Some(limit) = self.sandbox_safe_system_state.wasm_memory_limit() {
+            let bytes =
+                NumBytes::new(new_size_in_pages.saturating_mul(WASM_PAGE_SIZE_IN_BYTES as u64));
+            if bytes > limit {
+                return Err(HypervisorError::WasmMemoryLimitExceeded { bytes, limit });
+            }
         }
+        Ok(())
     }
 
     /// Gets the result of execution, assuming there is no error from
@@ -884,6 +947,23 @@ impl SystemApiImpl {
             .cloned()
             .or_else(|| self.execution_error.take())
         {
+            // An aborted execution is resumed later, so it is not a trap.
+            let trap_message = match &err {
+                HypervisorError::Aborted => None,
+                HypervisorError::CalledTrap(message) => Some(message.clone()),
+                _ => Some(
+                    err.clone()
+                        .into_user_error(&self.sandbox_safe_system_state.canister_id)
+                        .description()
+                        .to_string(),
+                ),
+            };
+            if let Some(message) = trap_message {
+                self.canister_log.add_record(
+                    self.api_type.time().as_nanos_since_unix_epoch(),
+                    format!("[TRAP]: {}", message).into_bytes(),
+                );
+            }
             // Return allocated memory in case of failed message execution.
             self.memory_usage.deallocate_memory(
                 self.memory_usage.allocated_execution_memory,
@@ -1336,6 +1416,18 @@ impl SystemApi for SystemApiImpl {
         self.execution_parameters.subnet_type
     }
 
+    fn save_log_message(&mut self, src: u32, size: u32, heap: &[u8]) {
+        // Longer messages would be truncated by the canister log anyway.
+        let size = size.min(MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE as u32);
+        let content = match valid_subslice("save_log_message", src, size, heap) {
+            Ok(bytes) => bytes.to_vec(),
+            // Like `ic0.debug_print`, logging never traps.
+            Err(_) => b"(debug message out of memory bounds)".to_vec(),
+        };
+        self.canister_log
+            .add_record(self.api_type.time().as_nanos_since_unix_epoch(), content);
+    }
+
     fn message_instruction_limit(&self) -> NumInstructions {
         self.execution_parameters.instruction_limits.message()
     }
@@ -2361,6 +2453,37 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
+    fn try_grow_wasm_memory(
+        &mut self,
+        native_memory_grow_res: i64,
+        additional_wasm_pages: u64,
+    ) -> HypervisorResult<()> {
+        let result = {
+            if native_memory_grow_res == -1 {
+                return Ok(());
+            }
+            let new_size_in_pages = (native_memory_grow_res as u64)
+                .checked_add(additional_wasm_pages)
+                .ok_or(HypervisorError::OutOfMemory)?;
+            self.check_wasm_memory_limit(new_size_in_pages)
+                .and_then(|()| {
+                    self.update_available_memory(
+                        native_memory_grow_res,
+                        additional_wasm_pages,
+                        WASM_PAGE_SIZE_IN_BYTES as u64,
+                    )
+                })
+        };
+        trace_syscall!(
+            self,
+            try_grow_wasm_memory,
+            result,
+            native_memory_grow_res,
+            additional_wasm_pages
+        );
+        result
+    }
+
     fn try_grow_stable_memory(
         &mut self,
         current_size: u64,
@@ -13,6 +13,7 @@ use ic_config::{
     subnet_config::SubnetConfig,
 };
 use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
+use ic_crypto_internal_types::sign::threshold_sig::public_key::bls12_381::PublicKeyBytes;
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_embedders::{wasm_utils::compile, WasmtimeEmbedder};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
@@ -24,8 +25,9 @@ use ic_execution_environment::{
 };
 use ic_ic00_types::{
     CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgs, CanisterSettingsArgsBuilder,
-    CanisterStatusType, EcdsaKeyId, EmptyBlob, InstallCodeArgs, Method, Payload,
-    ProvisionalCreateCanisterWithCyclesArgs, UpdateSettingsArgs,
+    CanisterStatusType, EcdsaKeyId, EmptyBlob, InstallCodeArgs, LogVisibility, Method, Payload,
+    ProvisionalCreateCanisterWithCyclesArgs, SchnorrAlgorithm, SchnorrKeyId, UpdateSettingsArgs,
+    VetKdKeyId,
 };
 use ic_interfaces::{
     execution_environment::{
@@ -52,7 +54,10 @@ use ic_replicated_state::{
 use ic_replicated_state::{page_map::TestPageAllocatorFileDescriptorImpl, PageMap};
 use ic_system_api::InstructionLimits;
 use ic_types::{
-    crypto::{canister_threshold_sig::MasterEcdsaPublicKey, AlgorithmId},
+    crypto::{
+        canister_threshold_sig::MasterEcdsaPublicKey, threshold_sig::ThresholdSigPublicKey,
+        AlgorithmId,
+    },
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{
         AnonymousQuery, CallbackId, MessageId, RequestOrResponse, Response, UserQuery,
@@ -137,6 +142,41 @@ pub fn test_registry_settings() -> RegistryExecutionSettings {
     }
 }
 
+/// Returns the vetKD master public key used for all vetKD keys held by the
+/// test subnet. It is the BLS12-381 G2 generator multiplied by 2, so that it
+/// is a valid threshold public key for which no secret key needs to be known.
+pub fn vetkd_master_public_key() -> ThresholdSigPublicKey {
+    let mut bytes = [0; ThresholdSigPublicKey::SIZE];
+    hex::decode_to_slice(
+        "aa4edef9c1ed7f729f520e47730a124fd70662a904ba1074728114d1031e1572c6c886f6b57ec72a6178288c47c335771638533957d540a9d2370f17cc7ed5863bc0b995b8825e0ee1ea1e1e4d00dbae81f14b0bf3611b78c952aacab827a053",
+        &mut bytes,
+    )
+    .unwrap();
+    ThresholdSigPublicKey::from(PublicKeyBytes(bytes))
+}
+
+/// Returns the master public key used for all Schnorr keys of `algorithm`
+/// held by the test subnet. It is the generator of the respective curve, so
+/// that it is a valid point for which no secret key needs to be known.
+pub fn schnorr_master_public_key(algorithm: SchnorrAlgorithm) -> MasterEcdsaPublicKey {
+    match algorithm {
+        SchnorrAlgorithm::Bip340Secp256k1 => MasterEcdsaPublicKey {
+            algorithm_id: AlgorithmId::ThresholdSchnorrBip340,
+            public_key: hex::decode(
+                "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            )
+            .unwrap(),
+        },
+        SchnorrAlgorithm::Ed25519 => MasterEcdsaPublicKey {
+            algorithm_id: AlgorithmId::ThresholdEd25519,
+            public_key: hex::decode(
+                "5866666666666666666666666666666666666666666666666666666666666666",
+            )
+            .unwrap(),
+        },
+    }
+}
+
 /// When a universal canister is installed, but the serialized module has been
 /// cached, the test setup thinks the canister was only charged for the reduced
 /// compilation cost amount, when it was really charged for the full amount
@@ -192,6 +232,8 @@ pub struct ExecutionTest {
     manual_execution: bool,
     caller_canister_id: Option<CanisterId>,
     ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+    schnorr_subnet_public_keys: BTreeMap<SchnorrKeyId, MasterEcdsaPublicKey>,
+    vetkd_subnet_public_keys: BTreeMap<VetKdKeyId, ThresholdSigPublicKey>,
 
     // The actual implementation.
     exec_env: ExecutionEnvironment,
@@ -375,6 +417,18 @@ impl ExecutionTest {
         )
     }
 
+    pub fn http_request_fee_non_replicated(
+        &self,
+        request_size: NumBytes,
+        response_size_limit: Option<NumBytes>,
+    ) -> Cycles {
+        self.cycles_account_manager.http_request_fee_non_replicated(
+            request_size,
+            response_size_limit,
+            self.subnet_size(),
+        )
+    }
+
     pub fn reduced_wasm_compilation_fee(&self, wasm: &[u8]) -> Cycles {
         let cost = wasm_compilation_cost(wasm);
         self.cycles_account_manager()
@@ -572,6 +626,38 @@ impl ExecutionTest {
         self.subnet_message(Method::UpdateSettings, payload)
     }
 
+    pub fn set_log_visibility(
+        &mut self,
+        canister_id: CanisterId,
+        log_visibility: LogVisibility,
+    ) -> Result<WasmResult, UserError> {
+        let payload = UpdateSettingsArgs {
+            canister_id: canister_id.into(),
+            settings: CanisterSettingsArgsBuilder::new()
+                .with_log_visibility(log_visibility)
+                .build(),
+            sender_canister_version: None,
+        }
+        .encode();
+        self.subnet_message(Method::UpdateSettings, payload)
+    }
+
+    pub fn set_wasm_memory_limit(
+        &mut self,
+        canister_id: CanisterId,
+        wasm_memory_limit: NumBytes,
+    ) -> Result<WasmResult, UserError> {
+        let payload = UpdateSettingsArgs {
+            canister_id: canister_id.into(),
+            settings: CanisterSettingsArgsBuilder::new()
+                .with_wasm_memory_limit(wasm_memory_limit.get())
+                .build(),
+            sender_canister_version: None,
+        }
+        .encode();
+        self.subnet_message(Method::UpdateSettings, payload)
+    }
+
     /// Sets the controller of the canister to the given principal.
     pub fn set_controller(
         &mut self,
@@ -1026,6 +1112,8 @@ impl ExecutionTest {
             self.install_code_instruction_limits.clone(),
             &mut mock_random_number_generator(),
             &self.ecdsa_subnet_public_keys,
+            &self.schnorr_subnet_public_keys,
+            &self.vetkd_subnet_public_keys,
             &self.registry_settings,
             &mut round_limits,
         );
@@ -1411,6 +1499,8 @@ pub struct ExecutionTestBuilder {
     caller_canister_id: Option<CanisterId>,
     ecdsa_signature_fee: Option<Cycles>,
     ecdsa_key: Option<EcdsaKeyId>,
+    schnorr_key: Option<SchnorrKeyId>,
+    vetkd_key: Option<VetKdKeyId>,
     instruction_limit: NumInstructions,
     slice_instruction_limit: NumInstructions,
     install_code_instruction_limit: NumInstructions,
@@ -1466,6 +1556,8 @@ impl Default for ExecutionTestBuilder {
             caller_canister_id: None,
             ecdsa_signature_fee: None,
             ecdsa_key: None,
+            schnorr_key: None,
+            vetkd_key: None,
             instruction_limit: scheduler_config.max_instructions_per_message,
             slice_instruction_limit: scheduler_config.max_instructions_per_slice,
             install_code_instruction_limit: scheduler_config.max_instructions_per_install_code,
@@ -1567,6 +1659,20 @@ impl ExecutionTestBuilder {
         }
     }
 
+    pub fn with_schnorr_key(self, key_id: SchnorrKeyId) -> Self {
+        Self {
+            schnorr_key: Some(key_id),
+            ..self
+        }
+    }
+
+    pub fn with_vetkd_key(self, key_id: VetKdKeyId) -> Self {
+        Self {
+            vetkd_key: Some(key_id),
+            ..self
+        }
+    }
+
     pub fn with_instruction_limit(self, limit: u64) -> Self {
         Self {
             instruction_limit: NumInstructions::from(limit),
@@ -1837,6 +1943,19 @@ impl ExecutionTestBuilder {
                 )
             })
             .collect();
+        let schnorr_subnet_public_keys = self
+            .schnorr_key
+            .into_iter()
+            .map(|key_id| {
+                let master_public_key = schnorr_master_public_key(key_id.algorithm);
+                (key_id, master_public_key)
+            })
+            .collect();
+        let vetkd_subnet_public_keys = self
+            .vetkd_key
+            .into_iter()
+            .map(|key_id| (key_id, vetkd_master_public_key()))
+            .collect();
         let cycles_account_manager = Arc::new(CyclesAccountManager::new(
             self.instruction_limit,
             self.subnet_type,
@@ -1928,6 +2047,8 @@ impl ExecutionTestBuilder {
             config.clone(),
             Arc::clone(&cycles_account_manager),
         );
+        let (query_stats_collector, _) =
+            ic_query_stats::init_query_stats(self.log.clone(), &config, &metrics_registry);
         let query_handler = InternalHttpQueryHandler::new(
             self.log.clone(),
             hypervisor,
@@ -1936,6 +2057,7 @@ impl ExecutionTestBuilder {
             &metrics_registry,
             self.instruction_limit_without_dts,
             Arc::clone(&cycles_account_manager),
+            query_stats_collector,
         );
         ExecutionTest {
             state: Some(state),
@@ -1972,6 +2094,8 @@ impl ExecutionTestBuilder {
             ingress_history_writer,
             manual_execution: self.manual_execution,
             ecdsa_subnet_public_keys,
+            schnorr_subnet_public_keys,
+            vetkd_subnet_public_keys,
             log: self.log,
             checkpoint_files: vec![],
         }
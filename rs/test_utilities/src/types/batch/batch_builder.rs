@@ -20,6 +20,8 @@ impl Default for BatchBuilder {
                 messages: BatchMessages::default(),
                 randomness: Randomness::from([0; 32]),
                 ecdsa_subnet_public_keys: BTreeMap::new(),
+                schnorr_subnet_public_keys: BTreeMap::new(),
+                vetkd_subnet_public_keys: BTreeMap::new(),
                 registry_version: RegistryVersion::from(1),
                 time: mock_time(),
                 consensus_responses: vec![],
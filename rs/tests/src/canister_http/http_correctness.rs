@@ -96,6 +96,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        replication: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -123,6 +124,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        replication: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -153,6 +155,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        replication: None,
                     },
                     cycles: 0,
                 },
@@ -175,6 +178,7 @@ pub fn test(env: TestEnv) {
                 context: vec![0, 1, 2],
             }),
             max_response_bytes: None,
+            replication: None,
         };
         test_results.push(
             test_canister_http_property(
@@ -225,6 +229,7 @@ pub fn test(env: TestEnv) {
                 context: vec![0, 1, 2],
             }),
             max_response_bytes: Some(16384),
+            replication: None,
         };
         test_results.push(
             test_canister_http_property(
@@ -280,6 +285,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: Some(4 * 1024 * 1024),
+                        replication: None,
                     },
                     cycles: 0,
                 },
@@ -307,6 +313,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        replication: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -334,6 +341,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        replication: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -364,6 +372,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        replication: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -391,6 +400,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: Some(8 * 1024),
+                        replication: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -419,6 +429,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        replication: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -446,6 +457,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        replication: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -473,6 +485,7 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        replication: None,
                     },
                     cycles: 500_000_000_000,
                 },
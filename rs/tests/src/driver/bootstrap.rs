@@ -6,6 +6,7 @@ use std::{collections::BTreeMap, fs::File, io, net::SocketAddr, path::PathBuf, p
 
 use crate::driver::farm::FarmResult;
 use crate::driver::ic::{InternetComputer, Node};
+use crate::driver::local_process::LocalProcessBackend;
 use crate::driver::test_env::{HasIcPrepDir, TestEnv};
 use crate::driver::test_env_api::{
     HasDependencies, HasIcDependencies, HasTopologySnapshot, IcNodeContainer, NodesInfo,
@@ -71,7 +72,12 @@ pub fn init_ic(
     // only as a placeholder: Updating individual binaries (replica/orchestrator)
     // is not supported anymore.
     let dummy_hash = "60958ccac3e5dfa6ae74aa4f8d6206fd33a5fc9546b8abaad65e3f1c4023c5bf".to_string();
-    let initial_replica_version = test_env.get_initial_replica_version()?;
+    let local_processes = LocalProcessBackend::is_enabled(test_env);
+    let initial_replica_version = if local_processes {
+        LocalProcessBackend::replica_version(test_env)
+    } else {
+        test_env.get_initial_replica_version()?
+    };
     info!(
         logger,
         "Replica Version that is passed is: {:?}", &initial_replica_version
@@ -145,20 +151,23 @@ pub fn init_ic(
     }
 
     let whitelist = ProvisionalWhitelist::All;
+    // Local processes are never upgraded, so they need no update image.
     let (ic_os_update_img_sha256, ic_os_update_img_url) = {
-        if ic.has_malicious_behaviours() {
+        if local_processes {
+            (None, None)
+        } else if ic.has_malicious_behaviours() {
             warn!(
                 logger,
                 "Using malicious guestos update image for IC config."
             );
             (
-                test_env.get_malicious_ic_os_update_img_sha256()?,
-                test_env.get_malicious_ic_os_update_img_url()?,
+                Some(test_env.get_malicious_ic_os_update_img_sha256()?),
+                Some(test_env.get_malicious_ic_os_update_img_url()?),
             )
         } else {
             (
-                test_env.get_ic_os_update_img_sha256()?,
-                test_env.get_ic_os_update_img_url()?,
+                Some(test_env.get_ic_os_update_img_sha256()?),
+                Some(test_env.get_ic_os_update_img_url()?),
             )
         }
     };
@@ -173,8 +182,8 @@ pub fn init_ic(
         /* generate_subnet_records= */
         true,
         nns_subnet_idx,
-        Some(ic_os_update_img_url),
-        Some(ic_os_update_img_sha256),
+        ic_os_update_img_url,
+        ic_os_update_img_sha256,
         Some(whitelist),
         ic.node_operator,
         ic.node_provider,
@@ -383,10 +392,17 @@ pub fn create_config_disk_image(
 }
 
 fn node_to_config(node: &Node) -> NodeConfiguration {
-    let ipv6_addr = IpAddr::V6(node.ipv6.expect("missing ip_addr"));
-    let public_api = SocketAddr::new(ipv6_addr, AddrType::PublicApi.into());
-    let xnet_api = SocketAddr::new(ipv6_addr, AddrType::Xnet.into());
-    let p2p_addr = SocketAddr::new(ipv6_addr, AddrType::P2P.into());
+    let (public_api, xnet_api, p2p_addr) = match node.local_addrs {
+        Some(addrs) => (addrs.public_api, addrs.xnet_api, addrs.p2p_addr),
+        None => {
+            let ipv6_addr = IpAddr::V6(node.ipv6.expect("missing ip_addr"));
+            (
+                SocketAddr::new(ipv6_addr, AddrType::PublicApi.into()),
+                SocketAddr::new(ipv6_addr, AddrType::Xnet.into()),
+                SocketAddr::new(ipv6_addr, AddrType::P2P.into()),
+            )
+        }
+    };
     NodeConfiguration {
         xnet_api,
         public_api,
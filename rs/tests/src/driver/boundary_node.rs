@@ -15,6 +15,7 @@ use crate::{
             ImageLocation, VMCreateResponse, VmType,
         },
         ic::{AmountOfMemoryKiB, NrOfVCPUs, VmAllocationStrategy, VmResources},
+        local_process::{LocalBoundaryNode, LocalProcessBackend},
         log_events,
         resource::{DiskImage, ImageType},
         test_env::{HasIcPrepDir, TestEnv, TestEnvAttribute},
@@ -358,6 +359,12 @@ impl BoundaryNode {
     }
 
     pub fn allocate_vm(self, env: &TestEnv) -> Result<BoundaryNodeWithVm> {
+        if LocalProcessBackend::is_enabled(env) {
+            bail!(
+                "boundary node {} cannot get a VM when running local processes, use start_local",
+                self.name
+            );
+        }
         let farm = Farm::from_test_env(env, "boundary node");
         let pot_setup = GroupSetup::read_attribute(env);
         let boundary_node_img_url = env.get_boundary_node_img_url()?;
@@ -417,6 +424,21 @@ impl BoundaryNode {
         })
     }
 
+    /// Starts the boundary node as a local `ic-boundary` process in front of
+    /// the IC with the given name, instead of allocating a VM for it. Only
+    /// available when running local processes, see
+    /// [crate::driver::local_process]. The started boundary node is obtained
+    /// with [crate::driver::local_process::HasLocalBoundaryNode].
+    pub fn start_local(self, env: &TestEnv, ic_name: &str) -> Result<()> {
+        if !LocalProcessBackend::is_enabled(env) {
+            bail!(
+                "boundary node {} can only run as a local process with --local-processes",
+                self.name
+            );
+        }
+        LocalBoundaryNode::new(self.name).start(env, ic_name)
+    }
+
     pub fn with_snp_boot_img(mut self, env: &TestEnv) -> Self {
         let boundary_node_snp_img_url = env.get_boundary_node_snp_img_url().unwrap();
         let boundary_node_snp_img_sha256 = env.get_boundary_node_snp_img_sha256().unwrap();
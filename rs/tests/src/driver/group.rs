@@ -3,6 +3,7 @@
 use walkdir::WalkDir;
 use crate::driver::{
     farm::{Farm, HostFeature},
+    local_process::{stop_local_processes, LocalProcessBackend},
     resource::AllocatedVm,
     task_scheduler::TaskScheduler,
     test_env_api::{FarmBaseUrl, HasGroupSetup},
@@ -120,6 +121,24 @@ pub struct CliArgs {
         value_parser = CliArgs::parse_host_feature
     )]
     pub required_host_features: Option<Vec<HostFeature>>,
+
+    #[clap(
+        long = "local-processes",
+        help = "If set, the nodes of the system under test run as local processes instead of Farm VMs."
+    )]
+    pub local_processes: bool,
+
+    #[clap(
+        long = "replica-bin",
+        help = "The replica binary started with --local-processes. Defaults to the replica dependency."
+    )]
+    pub replica_bin: Option<PathBuf>,
+
+    #[clap(
+        long = "ic-boundary-bin",
+        help = "The ic-boundary binary started with --local-processes. Defaults to the ic-boundary dependency."
+    )]
+    pub ic_boundary_bin: Option<PathBuf>,
 }
 
 impl CliArgs {
@@ -393,6 +412,7 @@ pub struct SystemTestGroup {
     timeout_per_test: Option<Duration>,
     overall_timeout: Option<Duration>,
     with_farm: bool,
+    with_local_processes: bool,
 }
 
 impl Default for SystemTestGroup {
@@ -418,6 +438,7 @@ impl SystemTestGroup {
             timeout_per_test: None,
             overall_timeout: None,
             with_farm: true,
+            with_local_processes: false,
         }
     }
 
@@ -430,6 +451,13 @@ impl SystemTestGroup {
         self
     }
 
+    /// Runs the nodes of the group as local processes, as if the group was
+    /// started with `--local-processes`.
+    pub fn with_local_processes(mut self) -> Self {
+        self.with_local_processes = true;
+        self
+    }
+
     pub fn with_overall_timeout(mut self, overall_timeout: Duration) -> Self {
         self.overall_timeout = Some(overall_timeout);
         self
@@ -776,6 +804,7 @@ impl SystemTestGroup {
         // 1. CLI arguments are sane
         // 2. Test / setup functions are not specified more than once in the group
         let args = CliArgs::parse().validate()?;
+        let local_processes = args.local_processes || self.with_local_processes;
         let is_parent_process = matches!(args.action, SystemTestsSubcommand::Run);
 
         let group_ctx = GroupContext::new(
@@ -783,7 +812,8 @@ impl SystemTestGroup {
             args.subproc_id(),
             args.filter_tests,
             args.debug_keepalive,
-            args.no_farm_keepalive,
+            // There is no Farm group to keep alive when running local processes.
+            args.no_farm_keepalive || local_processes,
             args.group_base_name,
         )?;
         if is_parent_process {
@@ -792,12 +822,20 @@ impl SystemTestGroup {
             if let Some(required_args) = args.required_host_features {
                 required_args.write_attribute(&root_env);
             }
-            if self.with_farm {
+            if local_processes {
+                LocalProcessBackend {
+                    pid_dir: root_env.get_path("local_processes"),
+                    replica_bin: args.replica_bin,
+                    ic_boundary_bin: args.ic_boundary_bin,
+                }
+                .write_attribute(&root_env);
+                GroupSetup::new(group_ctx.group_base_name.clone()).write_attribute(&root_env);
+            } else if self.with_farm {
                 root_env.create_group_setup(group_ctx.group_base_name.clone());
             }
             debug!(group_ctx.log(), "Created group context: {:?}", group_ctx);
         }
-        let with_farm = self.with_farm;
+        let with_farm = self.with_farm && !local_processes;
 
         // create the runtime that lives until this variable is dropped.
         // Note: having only a runtime handle does not guarantee that the runtime is alive.
@@ -896,6 +934,11 @@ impl SystemTestGroup {
                 if with_farm && !args.no_delete_farm_group {
                     Self::delete_farm_group(group_ctx.clone());
                 }
+                if local_processes {
+                    info!(group_ctx.log(), "Stopping local processes.");
+                    let root_env = group_ctx.get_root_env().unwrap();
+                    stop_local_processes(&root_env, group_ctx.log());
+                }
                 if report.failure.is_empty() {
                     Ok(Outcome::FromParentProcess(report))
                 } else {
@@ -1,6 +1,9 @@
 use crate::driver::{
     bootstrap::{init_ic, setup_and_start_vms},
     farm::{Farm, HostFeature},
+    local_process::{
+        allocate_local_node_addrs, start_local_nodes, LocalNodeAddrs, LocalProcessBackend,
+    },
     node_software_version::NodeSoftwareVersion,
     resource::{allocate_resources, get_resource_request, ResourceGroup},
     test_env::{TestEnv, TestEnvAttribute},
@@ -8,6 +11,7 @@ use crate::driver::{
     test_setup::GroupSetup,
 };
 use anyhow::Result;
+use ic_prep_lib::internet_computer::InitializedIc;
 use ic_prep_lib::node::NodeSecretKeyStore;
 use ic_prep_lib::prep_state_directory::IcPrepStateDir;
 use ic_protobuf::registry::subnet::v1::GossipConfig;
@@ -176,6 +180,9 @@ impl InternetComputer {
     }
 
     pub fn setup_and_start(&mut self, env: &TestEnv) -> Result<()> {
+        if LocalProcessBackend::is_enabled(env) {
+            return self.setup_and_start_local_processes(env);
+        }
         // propagate required host features and resource settings to all vms
         let farm = Farm::from_test_env(env, "Internet Computer");
         for subnet in self.subnets.iter_mut() {
@@ -212,7 +219,38 @@ impl InternetComputer {
             &env.logger(),
             self.use_specified_ids_allocation_range,
         )?;
+        self.save_initial_registry_snapshot(env, &init_ic)?;
+        setup_and_start_vms(&init_ic, self, env, &farm, &group_name)?;
+        Ok(())
+    }
 
+    /// Like `setup_and_start`, but runs the nodes as local processes, see
+    /// [crate::driver::local_process].
+    fn setup_and_start_local_processes(&mut self, env: &TestEnv) -> Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        self.create_secret_key_stores(tempdir.path())?;
+        let nodes = self
+            .subnets
+            .iter_mut()
+            .flat_map(|s| s.nodes.iter_mut())
+            .chain(self.unassigned_nodes.iter_mut())
+            .collect::<Vec<_>>();
+        let (addrs, mut reserved_ports) = allocate_local_node_addrs(nodes.len())?;
+        for (node, addrs) in nodes.into_iter().zip(addrs) {
+            node.ipv6 = Some(Ipv6Addr::LOCALHOST);
+            node.local_addrs = Some(addrs);
+        }
+        let init_ic = init_ic(
+            self,
+            env,
+            &env.logger(),
+            self.use_specified_ids_allocation_range,
+        )?;
+        self.save_initial_registry_snapshot(env, &init_ic)?;
+        start_local_nodes(&init_ic, self, env, &mut reserved_ports)
+    }
+
+    fn save_initial_registry_snapshot(&self, env: &TestEnv, init_ic: &InitializedIc) -> Result<()> {
         // save initial registry snapshot for this pot
         let local_store_path = env
             .registry_local_store_path(&self.name)
@@ -231,7 +269,6 @@ impl InternetComputer {
         info!(env.logger(), "{topology_snapshot}");
         // Emit a json log event, to be consumed by log post-processing tools.
         topology_snapshot.emit_log_event(&env.logger());
-        setup_and_start_vms(&init_ic, self, env, &farm, &group_name)?;
         Ok(())
     }
 
@@ -599,6 +636,8 @@ pub struct Node {
     pub required_host_features: Vec<HostFeature>,
     pub secret_key_store: Option<NodeSecretKeyStore>,
     pub ipv6: Option<Ipv6Addr>,
+    /// The addresses of a node that runs as a local process.
+    pub local_addrs: Option<LocalNodeAddrs>,
     pub malicious_behaviour: Option<MaliciousBehaviour>,
 }
 
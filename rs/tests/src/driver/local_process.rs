@@ -0,0 +1,514 @@
+//! A driver backend that runs the system under test as local processes
+//! instead of Farm VMs.
+//!
+//! Every node of an [InternetComputer] is started as a `replica` process
+//! listening on its own ports on the IPv6 loopback address. There is no
+//! orchestrator: the replicas are started from the registry local store and
+//! the crypto state generated by `ic-prep`, just like `ic-starter` does for a
+//! single node. Each replica gets its own copy of the local store, which it
+//! keeps up to date with the NNS on its own.
+//!
+//! The backend is selected by running a [crate::driver::group::SystemTestGroup]
+//! with `--local-processes`. The topology, the registry and all node
+//! information are written to the [TestEnv] in the same way as for Farm VMs,
+//! so that the [TestEnv] and [crate::driver::test_env_api::IcNodeSnapshot]
+//! APIs work unchanged. Features that need a VM, like SSH access or
+//! upgrading the GuestOS, are not available.
+//!
+//! All started processes are recorded in a directory shared by all test
+//! environments of a group and are terminated in the tear down of the group.
+
+use crate::driver::config::NODES_INFO;
+use crate::driver::ic::InternetComputer;
+use crate::driver::test_env::{HasIcPrepDir, TestEnv, TestEnvAttribute};
+use crate::driver::test_env_api::{
+    HasDependencies, HasIcDependencies, HasPublicApiUrl, HasRegistryLocalStore, HasTestEnv,
+    HasTopologySnapshot, IcNodeContainer, NodesInfo,
+};
+use crate::util::create_agent;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use ic_agent::{Agent, AgentError};
+use ic_config::{
+    artifact_pool::ArtifactPoolTomlConfig,
+    crypto::CryptoConfig,
+    execution_environment::Config as HypervisorConfig,
+    flag_status::FlagStatus,
+    http_handler::Config as HttpHandlerConfig,
+    logger::Config as LoggerConfig,
+    message_routing::Config as MessageRoutingConfig,
+    metrics::{Config as MetricsConfig, Exporter},
+    registry_client::Config as RegistryClientConfig,
+    state_manager::Config as StateManagerConfig,
+    transport::TransportConfig,
+    ConfigOptional as ReplicaConfig,
+};
+use ic_prep_lib::{internet_computer::InitializedIc, node::InitializedNode};
+use ic_types::ReplicaVersion;
+use nix::{
+    sys::signal::{kill, Signal},
+    unistd::Pid,
+};
+use serde::{Deserialize, Serialize};
+use slog::{info, warn, Logger};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::net::{Ipv6Addr, SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use url::Url;
+
+const REPLICA_BIN: &str = "rs/replica/replica";
+const IC_BOUNDARY_BIN: &str = "rs/boundary_node/ic_boundary/ic-boundary";
+const REPLICA_CONFIG_FILE: &str = "ic.json5";
+const LOCAL_STORE_DIR: &str = "ic_registry_local_store";
+const LOCAL_BOUNDARY_NODES_DIR: &str = "local_boundary_nodes";
+
+/// Selects the local process backend. The attribute is written to the root
+/// environment of a group and thus inherited by all test environments.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LocalProcessBackend {
+    /// The directory in which the IDs of all started processes are recorded.
+    pub pid_dir: PathBuf,
+    /// The `replica` binary; the `rs/replica/replica` dependency by default.
+    pub replica_bin: Option<PathBuf>,
+    /// The `ic-boundary` binary; the `rs/boundary_node/ic_boundary/ic-boundary`
+    /// dependency by default.
+    pub ic_boundary_bin: Option<PathBuf>,
+}
+
+impl TestEnvAttribute for LocalProcessBackend {
+    fn attribute_name() -> String {
+        "local_process_backend".to_string()
+    }
+}
+
+impl LocalProcessBackend {
+    pub fn is_enabled(env: &TestEnv) -> bool {
+        Self::try_read_attribute(env).is_ok()
+    }
+
+    fn replica_bin(&self, env: &TestEnv) -> PathBuf {
+        self.replica_bin
+            .clone()
+            .unwrap_or_else(|| env.get_dependency_path(REPLICA_BIN))
+    }
+
+    fn ic_boundary_bin(&self, env: &TestEnv) -> PathBuf {
+        self.ic_boundary_bin
+            .clone()
+            .unwrap_or_else(|| env.get_dependency_path(IC_BOUNDARY_BIN))
+    }
+
+    /// The version of the started replicas. Outside of CI, where the version
+    /// file is not available, the version the replica is built with is used.
+    pub fn replica_version(env: &TestEnv) -> ReplicaVersion {
+        env.get_initial_replica_version().unwrap_or_default()
+    }
+
+    /// Starts a process whose output is written to `log_path` and records it,
+    /// so that it is terminated in the tear down of the group.
+    fn spawn(&self, mut cmd: Command, log_path: &Path) -> Result<u32> {
+        let log_file = File::create(log_path)
+            .with_context(|| format!("Failed to create log file {}", log_path.display()))?;
+        let child = cmd
+            .stdin(Stdio::null())
+            .stdout(log_file.try_clone()?)
+            .stderr(log_file)
+            .spawn()
+            .with_context(|| format!("Failed to spawn {:?}", cmd))?;
+        let pid = child.id();
+        fs::create_dir_all(&self.pid_dir)?;
+        fs::write(
+            self.pid_dir.join(pid.to_string()),
+            log_path.to_string_lossy().as_bytes(),
+        )?;
+        Ok(pid)
+    }
+}
+
+/// Terminates all processes started by the local process backend.
+pub fn stop_local_processes(env: &TestEnv, log: &Logger) {
+    let backend = match LocalProcessBackend::try_read_attribute(env) {
+        Ok(backend) => backend,
+        Err(_) => return,
+    };
+    let entries = match fs::read_dir(&backend.pid_dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let pid = match entry.file_name().to_string_lossy().parse::<i32>() {
+            Ok(pid) => pid,
+            Err(_) => continue,
+        };
+        match kill(Pid::from_raw(pid), Signal::SIGTERM) {
+            Ok(()) => info!(log, "Terminated local process {}.", pid),
+            Err(err) => warn!(log, "Failed to terminate local process {}: {}", pid, err),
+        }
+        let _ = fs::remove_file(entry.path());
+    }
+}
+
+/// The addresses a node listens on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LocalNodeAddrs {
+    pub public_api: SocketAddr,
+    pub xnet_api: SocketAddr,
+    pub p2p_addr: SocketAddr,
+    pub metrics_addr: SocketAddr,
+}
+
+impl LocalNodeAddrs {
+    fn ports(&self) -> [u16; 4] {
+        [
+            self.public_api.port(),
+            self.xnet_api.port(),
+            self.p2p_addr.port(),
+            self.metrics_addr.port(),
+        ]
+    }
+}
+
+/// Allocates distinct free ports on the loopback address for `count` nodes.
+/// The ports stay reserved until they are released from the returned
+/// [ReservedPorts], right before the node that binds them is started.
+pub fn allocate_local_node_addrs(count: usize) -> Result<(Vec<LocalNodeAddrs>, ReservedPorts)> {
+    let reserved = ReservedPorts::allocate(4 * count)?;
+    let addrs = reserved
+        .ports()
+        .chunks(4)
+        .map(|ports| LocalNodeAddrs {
+            public_api: loopback_addr(ports[0]),
+            xnet_api: loopback_addr(ports[1]),
+            p2p_addr: loopback_addr(ports[2]),
+            metrics_addr: loopback_addr(ports[3]),
+        })
+        .collect();
+    Ok((addrs, reserved))
+}
+
+/// Free ports on the loopback address that are reserved by listening on them,
+/// so that no port is handed out twice and no other process takes them before
+/// the process they are meant for is started.
+#[derive(Debug, Default)]
+pub struct ReservedPorts {
+    listeners: Vec<TcpListener>,
+}
+
+impl ReservedPorts {
+    /// Reserves `count` distinct free ports.
+    pub fn allocate(count: usize) -> Result<Self> {
+        let listeners = (0..count)
+            .map(|_| TcpListener::bind(loopback_addr(0)))
+            .collect::<std::io::Result<Vec<_>>>()
+            .context("Failed to allocate a port on the loopback address")?;
+        Ok(Self { listeners })
+    }
+
+    /// The reserved ports, in the order in which they were allocated.
+    pub fn ports(&self) -> Vec<u16> {
+        self.listeners
+            .iter()
+            .map(|listener| {
+                listener
+                    .local_addr()
+                    .expect("a bound listener has a local address")
+                    .port()
+            })
+            .collect()
+    }
+
+    /// Releases the given ports, so that they can be bound by the process that
+    /// is started next.
+    pub fn release(&mut self, ports: &[u16]) {
+        self.listeners.retain(|listener| {
+            listener
+                .local_addr()
+                .map_or(true, |addr| !ports.contains(&addr.port()))
+        });
+    }
+}
+
+fn loopback_addr(port: u16) -> SocketAddr {
+    SocketAddr::new(Ipv6Addr::LOCALHOST.into(), port)
+}
+
+/// Starts a replica for every node of the given, initialized IC.
+///
+/// The addresses of the nodes are taken from the `local_addrs` of the nodes
+/// of `ic`, whose ports are released from `reserved_ports` right before the
+/// respective replica is started.
+pub fn start_local_nodes(
+    initialized_ic: &InitializedIc,
+    ic: &InternetComputer,
+    env: &TestEnv,
+    reserved_ports: &mut ReservedPorts,
+) -> Result<()> {
+    let backend = LocalProcessBackend::read_attribute(env);
+    let replica_bin = backend.replica_bin(env);
+    if !replica_bin.is_file() {
+        bail!(
+            "replica binary {} not found, use --replica-bin to specify it",
+            replica_bin.display()
+        );
+    }
+    let replica_version = ic
+        .initial_version
+        .as_ref()
+        .map(|version| version.replica_version.clone())
+        .unwrap_or_else(|| LocalProcessBackend::replica_version(env));
+    let local_store_path = env
+        .registry_local_store_path(&ic.name())
+        .expect("corrupted ic-prep directory structure");
+    let debug_overrides = env.get_replica_log_debug_overrides()?;
+    let local_addrs: BTreeMap<_, _> = ic
+        .subnets
+        .iter()
+        .flat_map(|subnet| subnet.nodes.iter())
+        .chain(ic.unassigned_nodes.iter())
+        .filter_map(|node| Some((node.id(), node.local_addrs?)))
+        .collect();
+
+    let nodes = initialized_ic
+        .initialized_topology
+        .values()
+        .flat_map(|subnet| subnet.initialized_nodes.values())
+        .chain(initialized_ic.unassigned_nodes.values());
+    let mut nodes_info = NodesInfo::new();
+    for node in nodes {
+        let malicious_behaviour = ic.get_malicious_behavior_of_node(node.node_id);
+        nodes_info.insert(node.node_id, malicious_behaviour.clone());
+
+        let node_path = PathBuf::from(&node.node_path);
+        let node_local_store_path = node_path.join(LOCAL_STORE_DIR);
+        copy_dir(&local_store_path, &node_local_store_path)?;
+        let addrs = local_addrs
+            .get(&node.node_id)
+            .with_context(|| format!("node {} has no local addresses", node.node_id))?;
+        let mut config = replica_config(node, node_local_store_path, addrs.metrics_addr);
+        config.logger = Some(LoggerConfig {
+            debug_overrides: debug_overrides.clone(),
+            ..LoggerConfig::default()
+        });
+        config.malicious_behaviour = malicious_behaviour;
+        let config_path = node_path.join(REPLICA_CONFIG_FILE);
+        fs::write(&config_path, serde_json::to_string_pretty(&config)?)?;
+
+        let mut cmd = Command::new(&replica_bin);
+        cmd.arg("--replica-version")
+            .arg(replica_version.to_string())
+            .arg("--config-file")
+            .arg(&config_path);
+        reserved_ports.release(&addrs.ports());
+        let pid = backend.spawn(cmd, &node_path.join("replica.log"))?;
+        info!(
+            env.logger(),
+            "Started replica of node {} (pid {}) listening on {}",
+            node.node_id,
+            pid,
+            node.node_config.public_api
+        );
+    }
+    // In the tests we may need to identify, which node/s have malicious behavior.
+    env.write_json_object(NODES_INFO, &nodes_info)?;
+    Ok(())
+}
+
+fn replica_config(
+    node: &InitializedNode,
+    local_store: PathBuf,
+    metrics_addr: SocketAddr,
+) -> ReplicaConfig {
+    let node_path = PathBuf::from(&node.node_path);
+    let node_config = &node.node_config;
+    ReplicaConfig {
+        registry_client: Some(RegistryClientConfig { local_store }),
+        transport: Some(TransportConfig {
+            node_ip: node_config.p2p_addr.ip().to_string(),
+            listening_port: node_config.p2p_addr.port(),
+            ..TransportConfig::default()
+        }),
+        state_manager: Some(StateManagerConfig::new(node_path.join("state"))),
+        // The sandbox binaries are not deployed next to a local replica.
+        hypervisor: Some(HypervisorConfig {
+            canister_sandboxing_flag: FlagStatus::Disabled,
+            ..HypervisorConfig::default()
+        }),
+        http_handler: Some(HttpHandlerConfig {
+            listen_addr: node_config.public_api,
+            ..HttpHandlerConfig::default()
+        }),
+        metrics: Some(MetricsConfig {
+            exporter: Exporter::Http(metrics_addr),
+            ..MetricsConfig::default()
+        }),
+        artifact_pool: Some(ArtifactPoolTomlConfig::new(
+            node_path.join("consensus_pool"),
+            None,
+        )),
+        crypto: Some(CryptoConfig::new(node.crypto_path())),
+        message_routing: Some(MessageRoutingConfig {
+            xnet_ip_addr: node_config.xnet_api.ip().to_string(),
+            xnet_port: node_config.xnet_api.port(),
+        }),
+        ..ReplicaConfig::default()
+    }
+}
+
+fn copy_dir(src: &Path, dst: &Path) -> Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let target = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// A boundary node that runs `ic-boundary` as a local process in front of the
+/// nodes of a local IC.
+pub struct LocalBoundaryNode {
+    pub name: String,
+}
+
+impl LocalBoundaryNode {
+    pub fn new(name: String) -> Self {
+        Self { name }
+    }
+
+    /// Starts `ic-boundary` for the IC with the given name, which must have a
+    /// root subnet.
+    pub fn start(&self, env: &TestEnv, ic_name: &str) -> Result<()> {
+        let backend = LocalProcessBackend::read_attribute(env);
+        let prep_dir = env
+            .prep_dir(ic_name)
+            .with_context(|| format!("no IC named '{}'", ic_name))?;
+        let nns_urls = env
+            .topology_snapshot_by_name(ic_name)
+            .root_subnet()
+            .nodes()
+            .map(|node| node.get_public_url().to_string())
+            .collect::<Vec<_>>();
+
+        let dir = env.get_path(LOCAL_BOUNDARY_NODES_DIR).join(&self.name);
+        fs::create_dir_all(&dir)?;
+        let reserved_ports = ReservedPorts::allocate(2)?;
+        let ports = reserved_ports.ports();
+        let mut cmd = Command::new(backend.ic_boundary_bin(env));
+        cmd.arg("--nns-urls")
+            .arg(nns_urls.join(","))
+            .arg("--nns-pub-key-pem")
+            .arg(prep_dir.root_public_key_path())
+            .arg("--local-store-path")
+            .arg(dir.join(LOCAL_STORE_DIR))
+            .arg("--http-port")
+            .arg(ports[0].to_string())
+            .arg("--metrics-addr")
+            .arg(loopback_addr(ports[1]).to_string())
+            .arg("--nftables-system-replicas-path")
+            .arg(dir.join("system_replicas.ruleset"));
+        drop(reserved_ports);
+        let pid = backend.spawn(cmd, &dir.join("ic-boundary.log"))?;
+        info!(
+            env.logger(),
+            "Started boundary node {} (pid {}) listening on port {}", self.name, pid, ports[0]
+        );
+        env.write_json_object(
+            Path::new(LOCAL_BOUNDARY_NODES_DIR)
+                .join(&self.name)
+                .join("addr.json"),
+            &loopback_addr(ports[0]),
+        )?;
+        Ok(())
+    }
+}
+
+pub trait HasLocalBoundaryNode {
+    fn get_local_boundary_node(&self, name: &str) -> Result<LocalBoundaryNodeSnapshot>;
+}
+
+impl HasLocalBoundaryNode for TestEnv {
+    fn get_local_boundary_node(&self, name: &str) -> Result<LocalBoundaryNodeSnapshot> {
+        let addr = self.read_json_object(
+            Path::new(LOCAL_BOUNDARY_NODES_DIR)
+                .join(name)
+                .join("addr.json"),
+        )?;
+        Ok(LocalBoundaryNodeSnapshot {
+            env: self.clone(),
+            addr,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct LocalBoundaryNodeSnapshot {
+    env: TestEnv,
+    addr: SocketAddr,
+}
+
+impl HasTestEnv for LocalBoundaryNodeSnapshot {
+    fn test_env(&self) -> TestEnv {
+        self.env.clone()
+    }
+}
+
+#[async_trait]
+impl HasPublicApiUrl for LocalBoundaryNodeSnapshot {
+    fn get_public_url(&self) -> Url {
+        Url::parse(&format!("http://{}/", self.addr)).expect("Could not parse Url")
+    }
+
+    fn get_public_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    async fn try_build_default_agent_async(&self) -> Result<Agent, AgentError> {
+        create_agent(self.get_public_url().as_ref()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn reserved_ports_cannot_be_bound_until_released() {
+        let mut reserved = ReservedPorts::allocate(4).unwrap();
+        let ports = reserved.ports();
+        assert_eq!(ports.iter().collect::<BTreeSet<_>>().len(), 4);
+        for port in &ports {
+            assert!(TcpListener::bind(loopback_addr(*port)).is_err());
+        }
+
+        reserved.release(&ports[..2]);
+        assert_eq!(reserved.ports(), ports[2..].to_vec());
+        for port in &ports[..2] {
+            assert!(TcpListener::bind(loopback_addr(*port)).is_ok());
+        }
+        for port in &ports[2..] {
+            assert!(TcpListener::bind(loopback_addr(*port)).is_err());
+        }
+    }
+
+    #[test]
+    fn local_node_addrs_are_distinct_and_reserved() {
+        let (addrs, mut reserved) = allocate_local_node_addrs(3).unwrap();
+        assert_eq!(addrs.len(), 3);
+        let ports: BTreeSet<_> = addrs.iter().flat_map(|addrs| addrs.ports()).collect();
+        assert_eq!(ports.len(), 12);
+        assert_eq!(reserved.ports().into_iter().collect::<BTreeSet<_>>(), ports);
+
+        // Starting the first node releases exactly its ports.
+        reserved.release(&addrs[0].ports());
+        assert!(TcpListener::bind(addrs[0].metrics_addr).is_ok());
+        assert!(TcpListener::bind(addrs[1].metrics_addr).is_err());
+        assert_eq!(reserved.ports().len(), 8);
+    }
+}
@@ -235,9 +235,16 @@ pub fn test(env: TestEnv) {
         use LookupStatus::Found;
         let hash_tree: MixedHashTree = serde_cbor::from_slice(&data_certificate.hash_tree).unwrap();
 
+        let mut last_block_index = vec![];
+        leb128::write::unsigned(&mut last_block_index, 1).unwrap();
         assert_eq!(
             hash_tree.lookup(&[b"last_block_index"]),
-            Found(&mleaf((1_u64).to_be_bytes()))
+            Found(&mleaf(last_block_index))
+        );
+
+        assert_eq!(
+            hash_tree.lookup(&[b"last_block_hash"]),
+            Found(&mleaf(blocks_response.blocks[1].hash()))
         );
 
         assert_eq!(
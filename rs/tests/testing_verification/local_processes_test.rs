@@ -0,0 +1,91 @@
+/* tag::catalog[]
+Title:: Local processes backend
+
+Goal:: Ensure that a system test can run its IC and a boundary node as local
+processes instead of Farm VMs.
+
+Runbook::
+. Start a system and an application subnet with one node each as local replicas.
+. Start a local ic-boundary process in front of them.
+. Install the counter canister through a replica.
+. Query the canister through the boundary node.
+
+Success:: The query through the boundary node returns the initial counter value.
+
+end::catalog[] */
+
+use anyhow::{bail, Result};
+use ic_registry_subnet_type::SubnetType;
+use ic_tests::driver::{
+    boundary_node::BoundaryNode,
+    group::SystemTestGroup,
+    ic::{InternetComputer, Subnet},
+    local_process::HasLocalBoundaryNode,
+    test_env::TestEnv,
+    test_env_api::{
+        retry_async, HasPublicApiUrl, HasTopologySnapshot, IcNodeContainer, READY_WAIT_TIMEOUT,
+        RETRY_BACKOFF,
+    },
+};
+use ic_tests::systest;
+use ic_tests::util::block_on;
+use slog::info;
+
+const BOUNDARY_NODE_NAME: &str = "local-boundary-node";
+const COUNTER_CANISTER_WAT: &str = "rs/tests/src/counter.wat";
+
+fn setup(env: TestEnv) {
+    let mut ic = InternetComputer::new()
+        .add_subnet(Subnet::new(SubnetType::System).add_nodes(1))
+        .add_subnet(Subnet::new(SubnetType::Application).add_nodes(1));
+    ic.setup_and_start(&env)
+        .expect("failed to start the IC as local processes");
+    for subnet in env.topology_snapshot().subnets() {
+        for node in subnet.nodes() {
+            node.await_status_is_healthy()
+                .expect("replica did not become healthy");
+        }
+    }
+    BoundaryNode::new(BOUNDARY_NODE_NAME.to_string())
+        .start_local(&env, &ic.name())
+        .expect("failed to start the local boundary node");
+}
+
+fn test(env: TestEnv) {
+    let log = env.logger();
+    let app_node = env
+        .topology_snapshot()
+        .subnets()
+        .find(|subnet| subnet.subnet_type() == SubnetType::Application)
+        .expect("there is an application subnet")
+        .nodes()
+        .next()
+        .expect("the application subnet has a node");
+    let canister_id = app_node.create_and_install_canister_with_arg(COUNTER_CANISTER_WAT, None);
+    info!(log, "Installed the counter canister {}", canister_id);
+
+    let boundary_node = env
+        .get_local_boundary_node(BOUNDARY_NODE_NAME)
+        .expect("the local boundary node was started");
+    block_on(async {
+        retry_async(&log, READY_WAIT_TIMEOUT, RETRY_BACKOFF, || async {
+            let agent = boundary_node.try_build_default_agent_async().await?;
+            let reply = agent.query(&canister_id, "read").call().await?;
+            if reply != [0; 4] {
+                bail!("unexpected reply from the counter canister: {:?}", reply);
+            }
+            Ok(())
+        })
+        .await
+    })
+    .expect("querying the counter canister through the boundary node failed");
+}
+
+fn main() -> Result<()> {
+    SystemTestGroup::new()
+        .with_local_processes()
+        .with_setup(setup)
+        .add_test(systest!(test))
+        .execute_from_args()?;
+    Ok(())
+}
@@ -57,12 +57,13 @@ pub struct TransformContext {
 //     url : text;
 //     max_response_bytes: opt nat64;
 //     headers : vec http_header;
-//     method : variant { get; head; post };
+//     method : variant { get; head; post; put; delete; patch };
 //     body : opt blob;
 //     transform : opt record {
 //       function : func (record {response : http_response; context : blob}) -> (http_response) query;
 //       context : blob;
 //     };
+//     replication : opt variant { replicated; non_replicated };
 //   })`
 #[derive(CandidType, Deserialize, Debug, Clone)]
 pub struct CanisterHttpRequestArgs {
@@ -72,6 +73,7 @@ pub struct CanisterHttpRequestArgs {
     pub body: Option<Vec<u8>>,
     pub method: HttpMethod,
     pub transform: Option<TransformContext>,
+    pub replication: Option<HttpOutcallReplication>,
 }
 
 impl Payload<'_> for CanisterHttpRequestArgs {}
@@ -84,6 +86,29 @@ impl CanisterHttpRequestArgs {
             .as_ref()
             .map(|transform_context| PrincipalId::from(transform_context.function.0.principal))
     }
+
+    /// Returns true if the request should be performed by a single replica
+    /// rather than by the whole subnet.
+    pub fn is_non_replicated(&self) -> bool {
+        self.replication == Some(HttpOutcallReplication::NonReplicated)
+    }
+}
+
+/// Specifies which replicas perform a canister http request.
+///
+/// Struct used for encoding/decoding
+/// `variant { replicated; non_replicated }`
+#[derive(Clone, Copy, Debug, PartialEq, CandidType, Eq, Hash, Serialize, Deserialize)]
+pub enum HttpOutcallReplication {
+    /// Every replica of the subnet performs the request and the subnet
+    /// reaches consensus on the (transformed) response. This is the default.
+    #[serde(rename = "replicated")]
+    Replicated,
+    /// A single designated replica performs the request and its signed
+    /// response is delivered as is. Use this for requests that are not
+    /// idempotent or whose responses differ on every request.
+    #[serde(rename = "non_replicated")]
+    NonReplicated,
 }
 
 /// Struct used for encoding/decoding
@@ -107,6 +132,12 @@ pub enum HttpMethod {
     POST,
     #[serde(rename = "head")]
     HEAD,
+    #[serde(rename = "put")]
+    PUT,
+    #[serde(rename = "delete")]
+    DELETE,
+    #[serde(rename = "patch")]
+    PATCH,
 }
 
 /// Represents the response for a canister http request.
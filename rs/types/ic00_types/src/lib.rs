@@ -5,8 +5,8 @@ mod provisional;
 use arbitrary::{Arbitrary, Result as ArbitraryResult, Unstructured};
 use candid::{CandidType, Decode, Deserialize, Encode};
 pub use http::{
-    CanisterHttpRequestArgs, CanisterHttpResponsePayload, HttpHeader, HttpMethod, TransformArgs,
-    TransformContext, TransformFunc,
+    CanisterHttpRequestArgs, CanisterHttpResponsePayload, HttpHeader, HttpMethod,
+    HttpOutcallReplication, TransformArgs, TransformContext, TransformFunc,
 };
 use ic_base_types::{CanisterId, NodeId, NumBytes, PrincipalId, RegistryVersion, SubnetId};
 use ic_error_types::{ErrorCode, UserError};
@@ -60,6 +60,31 @@ pub enum Method {
     UpdateSettings,
     ComputeInitialEcdsaDealings,
 
+    // Canister snapshots.
+    TakeCanisterSnapshot,
+    LoadCanisterSnapshot,
+    ListCanisterSnapshots,
+    DeleteCanisterSnapshot,
+
+    // Chunked Wasm upload.
+    UploadChunk,
+    StoredChunks,
+    ClearChunkStore,
+    InstallChunkedCode,
+
+    // Canister logging.
+    FetchCanisterLogs,
+
+    // Threshold Schnorr signatures.
+    SchnorrPublicKey,
+    SignWithSchnorr,
+
+    // Verifiably encrypted threshold key derivation.
+    #[strum(serialize = "vetkd_public_key")]
+    VetKdPublicKey,
+    #[strum(serialize = "vetkd_derive_encrypted_key")]
+    VetKdDeriveEncryptedKey,
+
     // Bitcoin Interface.
     BitcoinGetBalance,
     BitcoinGetUtxos,
@@ -239,6 +264,36 @@ impl CanisterControllersChangeRecord {
     }
 }
 
+/// `CandidType` for `CanisterLoadSnapshotRecord`
+/// ```text
+/// record {
+///   canister_version : nat64;
+///   snapshot_id : blob;
+///   taken_at_timestamp : nat64;
+/// }
+/// ```
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CanisterLoadSnapshotRecord {
+    canister_version: u64,
+    #[serde(with = "serde_bytes")]
+    snapshot_id: Vec<u8>,
+    taken_at_timestamp: u64,
+}
+
+impl CanisterLoadSnapshotRecord {
+    pub fn canister_version(&self) -> u64 {
+        self.canister_version
+    }
+
+    pub fn snapshot_id(&self) -> &[u8] {
+        &self.snapshot_id
+    }
+
+    pub fn taken_at_timestamp(&self) -> u64 {
+        self.taken_at_timestamp
+    }
+}
+
 /// `CandidType` for `CanisterChangeDetails`
 /// ```text
 /// variant {
@@ -253,6 +308,11 @@ impl CanisterControllersChangeRecord {
 ///   controllers_change : record {
 ///     controllers : vec principal;
 ///   };
+///   load_snapshot : record {
+///     canister_version : nat64;
+///     snapshot_id : blob;
+///     taken_at_timestamp : nat64;
+///   };
 /// }
 /// ```
 #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -265,6 +325,8 @@ pub enum CanisterChangeDetails {
     CanisterCodeDeployment(CanisterCodeDeploymentRecord),
     #[serde(rename = "controllers_change")]
     CanisterControllersChange(CanisterControllersChangeRecord),
+    #[serde(rename = "load_snapshot")]
+    CanisterLoadSnapshot(CanisterLoadSnapshotRecord),
 }
 
 impl CanisterChangeDetails {
@@ -287,6 +349,18 @@ impl CanisterChangeDetails {
             controllers,
         })
     }
+
+    pub fn load_snapshot(
+        canister_version: u64,
+        snapshot_id: Vec<u8>,
+        taken_at_timestamp: u64,
+    ) -> CanisterChangeDetails {
+        CanisterChangeDetails::CanisterLoadSnapshot(CanisterLoadSnapshotRecord {
+            canister_version,
+            snapshot_id,
+            taken_at_timestamp,
+        })
+    }
 }
 
 /// Every canister change (canister creation, code uninstallation, code deployment, or controllers change) consists of
@@ -339,20 +413,24 @@ impl CanisterChange {
 
     /// Returns the number of bytes to represent a canister change in memory.
     /// The vector of controllers in `CanisterCreation` and `CanisterControllersChange`
-    /// is counted separately because the controllers are stored on heap
-    /// and thus not accounted for in `size_of::<CanisterChange>()`.
+    /// and the snapshot id in `CanisterLoadSnapshot` are counted separately
+    /// because they are stored on heap and thus not accounted for in
+    /// `size_of::<CanisterChange>()`.
     pub fn count_bytes(&self) -> NumBytes {
-        let controllers_memory_size = match &self.details {
+        let heap_memory_size = match &self.details {
             CanisterChangeDetails::CanisterCreation(canister_creation) => {
                 std::mem::size_of_val(canister_creation.controllers())
             }
             CanisterChangeDetails::CanisterControllersChange(canister_controllers_change) => {
                 std::mem::size_of_val(canister_controllers_change.controllers())
             }
+            CanisterChangeDetails::CanisterLoadSnapshot(canister_load_snapshot) => {
+                std::mem::size_of_val(canister_load_snapshot.snapshot_id())
+            }
             CanisterChangeDetails::CanisterCodeDeployment(_)
             | CanisterChangeDetails::CanisterCodeUninstall => 0,
         };
-        NumBytes::from((size_of::<CanisterChange>() + controllers_memory_size) as u64)
+        NumBytes::from((size_of::<CanisterChange>() + heap_memory_size) as u64)
     }
 }
 
@@ -523,6 +601,15 @@ impl From<&CanisterChangeDetails> for pb_canister_state_bits::canister_change::C
                     },
                 )
             }
+            CanisterChangeDetails::CanisterLoadSnapshot(canister_load_snapshot) => {
+                pb_canister_state_bits::canister_change::ChangeDetails::CanisterLoadSnapshot(
+                    pb_canister_state_bits::CanisterLoadSnapshot {
+                        canister_version: canister_load_snapshot.canister_version,
+                        snapshot_id: canister_load_snapshot.snapshot_id.clone(),
+                        taken_at_timestamp: canister_load_snapshot.taken_at_timestamp,
+                    },
+                )
+            }
         }
     }
 }
@@ -566,6 +653,13 @@ impl TryFrom<pb_canister_state_bits::canister_change::ChangeDetails> for Caniste
                     .map(TryInto::try_into)
                     .collect::<Result<Vec<PrincipalId>, _>>()?,
             )),
+            pb_canister_state_bits::canister_change::ChangeDetails::CanisterLoadSnapshot(
+                canister_load_snapshot,
+            ) => Ok(CanisterChangeDetails::load_snapshot(
+                canister_load_snapshot.canister_version,
+                canister_load_snapshot.snapshot_id,
+                canister_load_snapshot.taken_at_timestamp,
+            )),
         }
     }
 }
@@ -628,6 +722,7 @@ impl Payload<'_> for UninstallCodeArgs {}
 ///     controller : principal;
 ///     compute_allocation: nat;
 ///     memory_allocation: opt nat;
+///     wasm_memory_limit: nat;
 /// })`
 #[derive(CandidType, Deserialize, Debug, Eq, PartialEq)]
 pub struct DefiniteCanisterSettingsArgs {
@@ -636,6 +731,7 @@ pub struct DefiniteCanisterSettingsArgs {
     compute_allocation: candid::Nat,
     memory_allocation: candid::Nat,
     freezing_threshold: candid::Nat,
+    wasm_memory_limit: candid::Nat,
 }
 
 impl DefiniteCanisterSettingsArgs {
@@ -645,23 +741,33 @@ impl DefiniteCanisterSettingsArgs {
         compute_allocation: u64,
         memory_allocation: Option<u64>,
         freezing_threshold: u64,
+        wasm_memory_limit: Option<u64>,
     ) -> Self {
         let memory_allocation = match memory_allocation {
             None => candid::Nat::from(0),
             Some(memory) => candid::Nat::from(memory),
         };
+        let wasm_memory_limit = match wasm_memory_limit {
+            None => candid::Nat::from(0),
+            Some(limit) => candid::Nat::from(limit),
+        };
         Self {
             controller,
             controllers,
             compute_allocation: candid::Nat::from(compute_allocation),
             memory_allocation,
             freezing_threshold: candid::Nat::from(freezing_threshold),
+            wasm_memory_limit,
         }
     }
 
     pub fn controllers(&self) -> Vec<PrincipalId> {
         self.controllers.clone()
     }
+
+    pub fn wasm_memory_limit(&self) -> candid::Nat {
+        self.wasm_memory_limit.clone()
+    }
 }
 
 impl Payload<'_> for DefiniteCanisterSettingsArgs {}
@@ -731,6 +837,7 @@ impl Payload<'_> for CanisterStatusResult {}
 ///     memory_size: nat;
 ///     cycles: nat;
 ///     idle_cycles_burned_per_day: nat;
+///     query_stats: query_stats;
 /// })`
 #[derive(CandidType, Debug, Deserialize, Eq, PartialEq)]
 pub struct CanisterStatusResultV2 {
@@ -744,6 +851,22 @@ pub struct CanisterStatusResultV2 {
     balance: Vec<(Vec<u8>, candid::Nat)>,
     freezing_threshold: candid::Nat,
     idle_cycles_burned_per_day: candid::Nat,
+    query_stats: QueryStatsResult,
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     num_calls_total: nat;
+///     num_instructions_total: nat;
+///     request_payload_bytes_total: nat;
+///     response_payload_bytes_total: nat;
+/// })`
+#[derive(CandidType, Debug, Deserialize, Eq, PartialEq)]
+pub struct QueryStatsResult {
+    num_calls_total: candid::Nat,
+    num_instructions_total: candid::Nat,
+    request_payload_bytes_total: candid::Nat,
+    response_payload_bytes_total: candid::Nat,
 }
 
 impl CanisterStatusResultV2 {
@@ -758,7 +881,12 @@ impl CanisterStatusResultV2 {
         compute_allocation: u64,
         memory_allocation: Option<u64>,
         freezing_threshold: u64,
+        wasm_memory_limit: Option<u64>,
         idle_cycles_burned_per_day: u128,
+        query_num_calls_total: u128,
+        query_num_instructions_total: u128,
+        query_request_payload_bytes_total: u128,
+        query_response_payload_bytes_total: u128,
     ) -> Self {
         Self {
             status,
@@ -775,9 +903,16 @@ impl CanisterStatusResultV2 {
                 compute_allocation,
                 memory_allocation,
                 freezing_threshold,
+                wasm_memory_limit,
             ),
             freezing_threshold: candid::Nat::from(freezing_threshold),
             idle_cycles_burned_per_day: candid::Nat::from(idle_cycles_burned_per_day),
+            query_stats: QueryStatsResult {
+                num_calls_total: candid::Nat::from(query_num_calls_total),
+                num_instructions_total: candid::Nat::from(query_num_instructions_total),
+                request_payload_bytes_total: candid::Nat::from(query_request_payload_bytes_total),
+                response_payload_bytes_total: candid::Nat::from(query_response_payload_bytes_total),
+            },
         }
     }
 
@@ -809,9 +944,37 @@ impl CanisterStatusResultV2 {
         self.freezing_threshold.0.to_u64().unwrap()
     }
 
+    pub fn wasm_memory_limit(&self) -> u64 {
+        self.settings.wasm_memory_limit().0.to_u64().unwrap()
+    }
+
     pub fn idle_cycles_burned_per_day(&self) -> u128 {
         self.idle_cycles_burned_per_day.0.to_u128().unwrap()
     }
+
+    pub fn query_num_calls_total(&self) -> u128 {
+        self.query_stats.num_calls_total.0.to_u128().unwrap()
+    }
+
+    pub fn query_num_instructions_total(&self) -> u128 {
+        self.query_stats.num_instructions_total.0.to_u128().unwrap()
+    }
+
+    pub fn query_request_payload_bytes_total(&self) -> u128 {
+        self.query_stats
+            .request_payload_bytes_total
+            .0
+            .to_u128()
+            .unwrap()
+    }
+
+    pub fn query_response_payload_bytes_total(&self) -> u128 {
+        self.query_stats
+            .response_payload_bytes_total
+            .0
+            .to_u128()
+            .unwrap()
+    }
 }
 
 /// Indicates whether the canister is running, stopping, or stopped.
@@ -1110,12 +1273,51 @@ impl<'a> Arbitrary<'a> for UpdateSettingsArgs {
 
 impl Payload<'_> for UpdateSettingsArgs {}
 
+/// Determines who is allowed to fetch the logs of a canister.
+///
+/// `(variant {
+///     controllers;
+///     public;
+/// })`
+#[derive(Default, Copy, Clone, CandidType, Deserialize, Debug, PartialEq, Eq, Serialize)]
+pub enum LogVisibility {
+    #[default]
+    #[serde(rename = "controllers")]
+    Controllers,
+    #[serde(rename = "public")]
+    Public,
+}
+
+impl From<&LogVisibility> for pb_canister_state_bits::LogVisibility {
+    fn from(item: &LogVisibility) -> Self {
+        match item {
+            LogVisibility::Controllers => pb_canister_state_bits::LogVisibility::Controllers,
+            LogVisibility::Public => pb_canister_state_bits::LogVisibility::Public,
+        }
+    }
+}
+
+impl From<pb_canister_state_bits::LogVisibility> for LogVisibility {
+    fn from(item: pb_canister_state_bits::LogVisibility) -> Self {
+        match item {
+            // Checkpoints written before log visibility was introduced default
+            // to the most restrictive setting.
+            pb_canister_state_bits::LogVisibility::Unspecified
+            | pb_canister_state_bits::LogVisibility::Controllers => LogVisibility::Controllers,
+            pb_canister_state_bits::LogVisibility::Public => LogVisibility::Public,
+        }
+    }
+}
+
 /// Struct used for encoding/decoding
 /// `(record {
 ///     controller: opt principal;
 ///     controllers: opt vec principal;
 ///     compute_allocation: opt nat;
 ///     memory_allocation: opt nat;
+///     freezing_threshold: opt nat;
+///     log_visibility: opt log_visibility;
+///     wasm_memory_limit: opt nat;
 /// })`
 #[derive(Default, Clone, CandidType, Deserialize, Debug)]
 pub struct CanisterSettingsArgs {
@@ -1125,6 +1327,8 @@ pub struct CanisterSettingsArgs {
     pub compute_allocation: Option<candid::Nat>,
     pub memory_allocation: Option<candid::Nat>,
     pub freezing_threshold: Option<candid::Nat>,
+    pub log_visibility: Option<LogVisibility>,
+    pub wasm_memory_limit: Option<candid::Nat>,
 }
 
 impl Payload<'_> for CanisterSettingsArgs {}
@@ -1142,6 +1346,8 @@ impl CanisterSettingsArgs {
             compute_allocation: compute_allocation.map(candid::Nat::from),
             memory_allocation: memory_allocation.map(candid::Nat::from),
             freezing_threshold: freezing_threshold.map(candid::Nat::from),
+            log_visibility: None,
+            wasm_memory_limit: None,
         }
     }
 
@@ -1157,6 +1363,8 @@ pub struct CanisterSettingsArgsBuilder {
     compute_allocation: Option<candid::Nat>,
     memory_allocation: Option<candid::Nat>,
     freezing_threshold: Option<candid::Nat>,
+    log_visibility: Option<LogVisibility>,
+    wasm_memory_limit: Option<candid::Nat>,
 }
 
 #[allow(dead_code)]
@@ -1172,6 +1380,8 @@ impl CanisterSettingsArgsBuilder {
             compute_allocation: self.compute_allocation,
             memory_allocation: self.memory_allocation,
             freezing_threshold: self.freezing_threshold,
+            log_visibility: self.log_visibility,
+            wasm_memory_limit: self.wasm_memory_limit,
         }
     }
 
@@ -1215,6 +1425,23 @@ impl CanisterSettingsArgsBuilder {
             ..self
         }
     }
+
+    /// Sets who is allowed to fetch the canister logs.
+    pub fn with_log_visibility(self, log_visibility: LogVisibility) -> Self {
+        Self {
+            log_visibility: Some(log_visibility),
+            ..self
+        }
+    }
+
+    /// Sets the Wasm memory limit in bytes. Update calls that grow the Wasm
+    /// memory beyond the limit trap. Zero means that there is no limit.
+    pub fn with_wasm_memory_limit(self, wasm_memory_limit: u64) -> Self {
+        Self {
+            wasm_memory_limit: Some(candid::Nat::from(wasm_memory_limit)),
+            ..self
+        }
+    }
 }
 
 /// Struct used for encoding/decoding
@@ -1518,152 +1745,507 @@ fn ecdsa_key_id_round_trip() {
     }
 }
 
-#[derive(CandidType, Clone, Debug, PartialEq, Eq)]
-pub struct DerivationPath(Vec<Vec<u8>>);
+/// Types of Schnorr signature algorithms.
+/// ```text
+/// (variant { bip340secp256k1; ed25519 })
+/// ```
+#[derive(
+    CandidType,
+    Copy,
+    Clone,
+    Debug,
+    PartialOrd,
+    Ord,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Hash,
+    EnumIter,
+)]
+pub enum SchnorrAlgorithm {
+    #[serde(rename = "bip340secp256k1")]
+    Bip340Secp256k1,
+    #[serde(rename = "ed25519")]
+    Ed25519,
+}
 
-impl DerivationPath {
-    pub fn new(path: Vec<Vec<u8>>) -> Self {
-        Self(path)
-    }
+impl TryFrom<pb_registry_crypto::SchnorrAlgorithm> for SchnorrAlgorithm {
+    type Error = ProxyDecodeError;
 
-    pub fn get(&self) -> Vec<Vec<u8>> {
-        self.0.clone()
+    fn try_from(item: pb_registry_crypto::SchnorrAlgorithm) -> Result<Self, Self::Error> {
+        match item {
+            pb_registry_crypto::SchnorrAlgorithm::Bip340secp256k1 => {
+                Ok(SchnorrAlgorithm::Bip340Secp256k1)
+            }
+            pb_registry_crypto::SchnorrAlgorithm::Ed25519 => Ok(SchnorrAlgorithm::Ed25519),
+            pb_registry_crypto::SchnorrAlgorithm::Unspecified => {
+                Err(ProxyDecodeError::ValueOutOfRange {
+                    typ: "SchnorrAlgorithm",
+                    err: format!("Unable to convert {:?} to a SchnorrAlgorithm", item),
+                })
+            }
+        }
     }
 }
 
-impl<'de> Deserialize<'de> for DerivationPath {
-    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        let decoded: Vec<Vec<u8>> = Deserialize::deserialize(deserializer)?;
-        if decoded.len() > MAXIMUM_DERIVATION_PATH_LENGTH {
-            Err(serde::de::Error::custom(format!(
-                "Derivation path length {} exceeds maximum allowed {}",
-                decoded.len(),
-                MAXIMUM_DERIVATION_PATH_LENGTH
-            )))
-        } else {
-            Ok(DerivationPath::new(decoded))
+impl From<SchnorrAlgorithm> for pb_registry_crypto::SchnorrAlgorithm {
+    fn from(item: SchnorrAlgorithm) -> Self {
+        match item {
+            SchnorrAlgorithm::Bip340Secp256k1 => {
+                pb_registry_crypto::SchnorrAlgorithm::Bip340secp256k1
+            }
+            SchnorrAlgorithm::Ed25519 => pb_registry_crypto::SchnorrAlgorithm::Ed25519,
         }
     }
 }
 
-impl Payload<'_> for DerivationPath {}
-
-#[test]
-fn verify_max_derivation_path_length() {
-    for i in 0..=MAXIMUM_DERIVATION_PATH_LENGTH {
-        let path = DerivationPath::new(vec![vec![0_u8, 32]; i]);
-        let encoded = path.encode();
-        assert_eq!(DerivationPath::decode(&encoded).unwrap(), path);
+impl std::fmt::Display for SchnorrAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
 
-        let sign_with_ecdsa = SignWithECDSAArgs {
-            message_hash: [1; 32],
-            derivation_path: path.clone(),
-            key_id: EcdsaKeyId {
-                curve: EcdsaCurve::Secp256k1,
-                name: "test".to_string(),
-            },
-        };
+impl FromStr for SchnorrAlgorithm {
+    type Err = String;
 
-        let encoded = sign_with_ecdsa.encode();
-        assert_eq!(
-            SignWithECDSAArgs::decode(&encoded).unwrap(),
-            sign_with_ecdsa
-        );
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Bip340Secp256k1" => Ok(Self::Bip340Secp256k1),
+            "Ed25519" => Ok(Self::Ed25519),
+            _ => Err(format!("{} is not a recognized Schnorr algorithm", s)),
+        }
+    }
+}
 
-        let ecdsa_public_key = ECDSAPublicKeyArgs {
-            canister_id: None,
-            derivation_path: path,
-            key_id: EcdsaKeyId {
-                curve: EcdsaCurve::Secp256k1,
-                name: "test".to_string(),
-            },
-        };
+#[test]
+fn schnorr_algorithm_round_trip() {
+    use strum::IntoEnumIterator;
 
-        let encoded = ecdsa_public_key.encode();
+    for algorithm in SchnorrAlgorithm::iter() {
         assert_eq!(
-            ECDSAPublicKeyArgs::decode(&encoded).unwrap(),
-            ecdsa_public_key
+            format!("{}", algorithm)
+                .parse::<SchnorrAlgorithm>()
+                .unwrap(),
+            algorithm
         );
     }
-
-    for i in MAXIMUM_DERIVATION_PATH_LENGTH + 1..=MAXIMUM_DERIVATION_PATH_LENGTH + 100 {
-        let path = DerivationPath::new(vec![vec![0_u8, 32]; i]);
-        let encoded = path.encode();
-        let res = DerivationPath::decode(&encoded).unwrap_err();
-        assert_eq!(res.code(), ErrorCode::InvalidManagementPayload);
-        assert!(res.description().contains(&format!(
-            "Deserialize error: Derivation path length {} exceeds maximum allowed {}",
-            i, MAXIMUM_DERIVATION_PATH_LENGTH
-        )));
-
-        let sign_with_ecdsa = SignWithECDSAArgs {
-            message_hash: [1; 32],
-            derivation_path: path.clone(),
-            key_id: EcdsaKeyId {
-                curve: EcdsaCurve::Secp256k1,
-                name: "test".to_string(),
-            },
-        };
-
-        let encoded = sign_with_ecdsa.encode();
-        let res = SignWithECDSAArgs::decode(&encoded).unwrap_err();
-        assert_eq!(res.code(), ErrorCode::InvalidManagementPayload);
-        assert!(res.description().contains(&format!(
-            "Deserialize error: Derivation path length {} exceeds maximum allowed {}",
-            i, MAXIMUM_DERIVATION_PATH_LENGTH
-        )));
-
-        let ecsda_public_key = ECDSAPublicKeyArgs {
-            canister_id: None,
-            derivation_path: path,
-            key_id: EcdsaKeyId {
-                curve: EcdsaCurve::Secp256k1,
-                name: "test".to_string(),
-            },
-        };
-
-        let encoded = ecsda_public_key.encode();
-        let res = ECDSAPublicKeyArgs::decode(&encoded).unwrap_err();
-        assert_eq!(res.code(), ErrorCode::InvalidManagementPayload);
-        assert!(res.description().contains(&format!(
-            "Deserialize error: Derivation path length {} exceeds maximum allowed {}",
-            i, MAXIMUM_DERIVATION_PATH_LENGTH
-        )));
-    }
 }
 
-/// Represents the argument of the sign_with_ecdsa API.
+/// Unique identifier for a key that can be used for Schnorr signatures. The
+/// name is just a identifier, but it may be used to convey some information
+/// about the key (e.g. that the key is meant to be used for testing purposes).
 /// ```text
-/// (record {
-///   message_hash : blob;
-///   derivation_path : vec blob;
-///   key_id : ecdsa_key_id;
-/// })
+/// (record { algorithm: schnorr_algorithm; name: text})
 /// ```
-#[derive(CandidType, Deserialize, Debug, PartialEq, Eq)]
-pub struct SignWithECDSAArgs {
-    pub message_hash: [u8; 32],
-    pub derivation_path: DerivationPath,
-    pub key_id: EcdsaKeyId,
+#[derive(
+    CandidType, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize, Hash,
+)]
+pub struct SchnorrKeyId {
+    pub algorithm: SchnorrAlgorithm,
+    pub name: String,
 }
 
-impl Payload<'_> for SignWithECDSAArgs {}
+impl TryFrom<pb_registry_crypto::SchnorrKeyId> for SchnorrKeyId {
+    type Error = ProxyDecodeError;
+    fn try_from(item: pb_registry_crypto::SchnorrKeyId) -> Result<Self, Self::Error> {
+        Ok(Self {
+            algorithm: SchnorrAlgorithm::try_from(
+                pb_registry_crypto::SchnorrAlgorithm::from_i32(item.algorithm).ok_or(
+                    ProxyDecodeError::ValueOutOfRange {
+                        typ: "SchnorrKeyId",
+                        err: format!("Unable to convert {} to a SchnorrAlgorithm", item.algorithm),
+                    },
+                )?,
+            )?,
+            name: item.name,
+        })
+    }
+}
 
-/// Struct used to return an ECDSA signature.
-#[derive(CandidType, Deserialize, Debug)]
-pub struct SignWithECDSAReply {
-    pub signature: Vec<u8>,
+impl From<&SchnorrKeyId> for pb_registry_crypto::SchnorrKeyId {
+    fn from(item: &SchnorrKeyId) -> Self {
+        Self {
+            algorithm: pb_registry_crypto::SchnorrAlgorithm::from(item.algorithm) as i32,
+            name: item.name.clone(),
+        }
+    }
 }
 
-impl Payload<'_> for SignWithECDSAReply {}
+impl std::fmt::Display for SchnorrKeyId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.algorithm, self.name)
+    }
+}
 
-/// Represents the argument of the ecdsa_public_key API.
-/// ```text
-/// (record {
-///   canister_id : opt canister_id;
-///   derivation_path : vec blob;
-///   key_id : ecdsa_key_id;
-/// })
+impl FromStr for SchnorrKeyId {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (algorithm, name) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Schnorr key id {} does not contain a ':'", s))?;
+        Ok(SchnorrKeyId {
+            algorithm: algorithm.parse::<SchnorrAlgorithm>()?,
+            name: name.to_string(),
+        })
+    }
+}
+
+#[test]
+fn schnorr_key_id_round_trip() {
+    use strum::IntoEnumIterator;
+
+    for algorithm in SchnorrAlgorithm::iter() {
+        for name in ["Ed25519", "", "other_key", "other key", "other:key"] {
+            let key = SchnorrKeyId {
+                algorithm,
+                name: name.to_string(),
+            };
+            assert_eq!(format!("{}", key).parse::<SchnorrKeyId>().unwrap(), key);
+        }
+    }
+}
+
+/// Types of curves that can be used for vetKD key derivation.
+/// ```text
+/// (variant { bls12_381_g2; })
+/// ```
+#[derive(
+    CandidType,
+    Copy,
+    Clone,
+    Debug,
+    PartialOrd,
+    Ord,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Hash,
+    EnumIter,
+)]
+pub enum VetKdCurve {
+    #[serde(rename = "bls12_381_g2")]
+    #[allow(non_camel_case_types)]
+    Bls12_381_G2,
+}
+
+impl TryFrom<pb_registry_crypto::VetKdCurve> for VetKdCurve {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: pb_registry_crypto::VetKdCurve) -> Result<Self, Self::Error> {
+        match item {
+            pb_registry_crypto::VetKdCurve::Bls12381G2 => Ok(VetKdCurve::Bls12_381_G2),
+            pb_registry_crypto::VetKdCurve::Unspecified => Err(ProxyDecodeError::ValueOutOfRange {
+                typ: "VetKdCurve",
+                err: format!("Unable to convert {:?} to a VetKdCurve", item),
+            }),
+        }
+    }
+}
+
+impl From<VetKdCurve> for pb_registry_crypto::VetKdCurve {
+    fn from(item: VetKdCurve) -> Self {
+        match item {
+            VetKdCurve::Bls12_381_G2 => pb_registry_crypto::VetKdCurve::Bls12381G2,
+        }
+    }
+}
+
+impl std::fmt::Display for VetKdCurve {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for VetKdCurve {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Bls12_381_G2" => Ok(Self::Bls12_381_G2),
+            _ => Err(format!("{} is not a recognized vetKD curve", s)),
+        }
+    }
+}
+
+#[test]
+fn vetkd_curve_round_trip() {
+    use strum::IntoEnumIterator;
+
+    for curve in VetKdCurve::iter() {
+        assert_eq!(format!("{}", curve).parse::<VetKdCurve>().unwrap(), curve);
+    }
+}
+
+/// Unique identifier for a key that can be used for vetKD key derivation.
+/// ```text
+/// (record { curve: vetkd_curve; name: text})
+/// ```
+#[derive(
+    CandidType, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize, Hash,
+)]
+pub struct VetKdKeyId {
+    pub curve: VetKdCurve,
+    pub name: String,
+}
+
+impl TryFrom<pb_registry_crypto::VetKdKeyId> for VetKdKeyId {
+    type Error = ProxyDecodeError;
+    fn try_from(item: pb_registry_crypto::VetKdKeyId) -> Result<Self, Self::Error> {
+        Ok(Self {
+            curve: VetKdCurve::try_from(
+                pb_registry_crypto::VetKdCurve::from_i32(item.curve).ok_or(
+                    ProxyDecodeError::ValueOutOfRange {
+                        typ: "VetKdKeyId",
+                        err: format!("Unable to convert {} to a VetKdCurve", item.curve),
+                    },
+                )?,
+            )?,
+            name: item.name,
+        })
+    }
+}
+
+impl From<&VetKdKeyId> for pb_registry_crypto::VetKdKeyId {
+    fn from(item: &VetKdKeyId) -> Self {
+        Self {
+            curve: pb_registry_crypto::VetKdCurve::from(item.curve) as i32,
+            name: item.name.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for VetKdKeyId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.curve, self.name)
+    }
+}
+
+impl FromStr for VetKdKeyId {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (curve, name) = s
+            .split_once(':')
+            .ok_or_else(|| format!("vetKD key id {} does not contain a ':'", s))?;
+        Ok(VetKdKeyId {
+            curve: curve.parse::<VetKdCurve>()?,
+            name: name.to_string(),
+        })
+    }
+}
+
+#[test]
+fn vetkd_key_id_round_trip() {
+    for name in ["bls12_381_g2", "", "test_key_1", "other key", "other:key"] {
+        let key = VetKdKeyId {
+            curve: VetKdCurve::Bls12_381_G2,
+            name: name.to_string(),
+        };
+        assert_eq!(format!("{}", key).parse::<VetKdKeyId>().unwrap(), key);
+    }
+}
+
+/// Unique identifier for a threshold master public key, which is either an
+/// ECDSA key, a Schnorr key or a vetKD key.
+/// ```text
+/// (variant { ecdsa: ecdsa_key_id; schnorr: schnorr_key_id; vetkd: vetkd_key_id })
+/// ```
+#[derive(
+    CandidType, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize, Hash,
+)]
+pub enum MasterPublicKeyId {
+    #[serde(rename = "ecdsa")]
+    Ecdsa(EcdsaKeyId),
+    #[serde(rename = "schnorr")]
+    Schnorr(SchnorrKeyId),
+    #[serde(rename = "vetkd")]
+    VetKd(VetKdKeyId),
+}
+
+impl TryFrom<pb_registry_crypto::MasterPublicKeyId> for MasterPublicKeyId {
+    type Error = ProxyDecodeError;
+    fn try_from(item: pb_registry_crypto::MasterPublicKeyId) -> Result<Self, Self::Error> {
+        use pb_registry_crypto::master_public_key_id::KeyId;
+        match item.key_id {
+            Some(KeyId::Ecdsa(key_id)) => Ok(Self::Ecdsa(EcdsaKeyId::try_from(key_id)?)),
+            Some(KeyId::Schnorr(key_id)) => Ok(Self::Schnorr(SchnorrKeyId::try_from(key_id)?)),
+            Some(KeyId::Vetkd(key_id)) => Ok(Self::VetKd(VetKdKeyId::try_from(key_id)?)),
+            None => Err(ProxyDecodeError::MissingField("MasterPublicKeyId::key_id")),
+        }
+    }
+}
+
+impl From<&MasterPublicKeyId> for pb_registry_crypto::MasterPublicKeyId {
+    fn from(item: &MasterPublicKeyId) -> Self {
+        use pb_registry_crypto::master_public_key_id::KeyId;
+        let key_id = match item {
+            MasterPublicKeyId::Ecdsa(key_id) => KeyId::Ecdsa(key_id.into()),
+            MasterPublicKeyId::Schnorr(key_id) => KeyId::Schnorr(key_id.into()),
+            MasterPublicKeyId::VetKd(key_id) => KeyId::Vetkd(key_id.into()),
+        };
+        Self {
+            key_id: Some(key_id),
+        }
+    }
+}
+
+impl std::fmt::Display for MasterPublicKeyId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ecdsa(key_id) => write!(f, "ecdsa:{}", key_id),
+            Self::Schnorr(key_id) => write!(f, "schnorr:{}", key_id),
+            Self::VetKd(key_id) => write!(f, "vetkd:{}", key_id),
+        }
+    }
+}
+
+#[derive(CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct DerivationPath(Vec<Vec<u8>>);
+
+impl DerivationPath {
+    pub fn new(path: Vec<Vec<u8>>) -> Self {
+        Self(path)
+    }
+
+    pub fn get(&self) -> Vec<Vec<u8>> {
+        self.0.clone()
+    }
+}
+
+impl<'de> Deserialize<'de> for DerivationPath {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let decoded: Vec<Vec<u8>> = Deserialize::deserialize(deserializer)?;
+        if decoded.len() > MAXIMUM_DERIVATION_PATH_LENGTH {
+            Err(serde::de::Error::custom(format!(
+                "Derivation path length {} exceeds maximum allowed {}",
+                decoded.len(),
+                MAXIMUM_DERIVATION_PATH_LENGTH
+            )))
+        } else {
+            Ok(DerivationPath::new(decoded))
+        }
+    }
+}
+
+impl Payload<'_> for DerivationPath {}
+
+#[test]
+fn verify_max_derivation_path_length() {
+    for i in 0..=MAXIMUM_DERIVATION_PATH_LENGTH {
+        let path = DerivationPath::new(vec![vec![0_u8, 32]; i]);
+        let encoded = path.encode();
+        assert_eq!(DerivationPath::decode(&encoded).unwrap(), path);
+
+        let sign_with_ecdsa = SignWithECDSAArgs {
+            message_hash: [1; 32],
+            derivation_path: path.clone(),
+            key_id: EcdsaKeyId {
+                curve: EcdsaCurve::Secp256k1,
+                name: "test".to_string(),
+            },
+        };
+
+        let encoded = sign_with_ecdsa.encode();
+        assert_eq!(
+            SignWithECDSAArgs::decode(&encoded).unwrap(),
+            sign_with_ecdsa
+        );
+
+        let ecdsa_public_key = ECDSAPublicKeyArgs {
+            canister_id: None,
+            derivation_path: path,
+            key_id: EcdsaKeyId {
+                curve: EcdsaCurve::Secp256k1,
+                name: "test".to_string(),
+            },
+        };
+
+        let encoded = ecdsa_public_key.encode();
+        assert_eq!(
+            ECDSAPublicKeyArgs::decode(&encoded).unwrap(),
+            ecdsa_public_key
+        );
+    }
+
+    for i in MAXIMUM_DERIVATION_PATH_LENGTH + 1..=MAXIMUM_DERIVATION_PATH_LENGTH + 100 {
+        let path = DerivationPath::new(vec![vec![0_u8, 32]; i]);
+        let encoded = path.encode();
+        let res = DerivationPath::decode(&encoded).unwrap_err();
+        assert_eq!(res.code(), ErrorCode::InvalidManagementPayload);
+        assert!(res.description().contains(&format!(
+            "Deserialize error: Derivation path length {} exceeds maximum allowed {}",
+            i, MAXIMUM_DERIVATION_PATH_LENGTH
+        )));
+
+        let sign_with_ecdsa = SignWithECDSAArgs {
+            message_hash: [1; 32],
+            derivation_path: path.clone(),
+            key_id: EcdsaKeyId {
+                curve: EcdsaCurve::Secp256k1,
+                name: "test".to_string(),
+            },
+        };
+
+        let encoded = sign_with_ecdsa.encode();
+        let res = SignWithECDSAArgs::decode(&encoded).unwrap_err();
+        assert_eq!(res.code(), ErrorCode::InvalidManagementPayload);
+        assert!(res.description().contains(&format!(
+            "Deserialize error: Derivation path length {} exceeds maximum allowed {}",
+            i, MAXIMUM_DERIVATION_PATH_LENGTH
+        )));
+
+        let ecsda_public_key = ECDSAPublicKeyArgs {
+            canister_id: None,
+            derivation_path: path,
+            key_id: EcdsaKeyId {
+                curve: EcdsaCurve::Secp256k1,
+                name: "test".to_string(),
+            },
+        };
+
+        let encoded = ecsda_public_key.encode();
+        let res = ECDSAPublicKeyArgs::decode(&encoded).unwrap_err();
+        assert_eq!(res.code(), ErrorCode::InvalidManagementPayload);
+        assert!(res.description().contains(&format!(
+            "Deserialize error: Derivation path length {} exceeds maximum allowed {}",
+            i, MAXIMUM_DERIVATION_PATH_LENGTH
+        )));
+    }
+}
+
+/// Represents the argument of the sign_with_ecdsa API.
+/// ```text
+/// (record {
+///   message_hash : blob;
+///   derivation_path : vec blob;
+///   key_id : ecdsa_key_id;
+/// })
+/// ```
+#[derive(CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct SignWithECDSAArgs {
+    pub message_hash: [u8; 32],
+    pub derivation_path: DerivationPath,
+    pub key_id: EcdsaKeyId,
+}
+
+impl Payload<'_> for SignWithECDSAArgs {}
+
+/// Struct used to return an ECDSA signature.
+#[derive(CandidType, Deserialize, Debug)]
+pub struct SignWithECDSAReply {
+    pub signature: Vec<u8>,
+}
+
+impl Payload<'_> for SignWithECDSAReply {}
+
+/// Represents the argument of the ecdsa_public_key API.
+/// ```text
+/// (record {
+///   canister_id : opt canister_id;
+///   derivation_path : vec blob;
+///   key_id : ecdsa_key_id;
+/// })
 /// ```
 #[derive(CandidType, Deserialize, Debug, PartialEq, Eq)]
 pub struct ECDSAPublicKeyArgs {
@@ -1672,108 +2254,670 @@ pub struct ECDSAPublicKeyArgs {
     pub key_id: EcdsaKeyId,
 }
 
-impl Payload<'_> for ECDSAPublicKeyArgs {}
+impl Payload<'_> for ECDSAPublicKeyArgs {}
+
+/// Represents the response of the ecdsa_public_key API.
+/// ```text
+/// (record {
+///   public_key : blob;
+///   chain_code : blob;
+/// })
+/// ```
+#[derive(CandidType, Deserialize, Debug)]
+pub struct ECDSAPublicKeyResponse {
+    pub public_key: Vec<u8>,
+    pub chain_code: Vec<u8>,
+}
+
+impl Payload<'_> for ECDSAPublicKeyResponse {}
+
+/// Represents the argument of the sign_with_schnorr API.
+/// ```text
+/// (record {
+///   message : blob;
+///   derivation_path : vec blob;
+///   key_id : schnorr_key_id;
+/// })
+/// ```
+#[derive(CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct SignWithSchnorrArgs {
+    #[serde(with = "serde_bytes")]
+    pub message: Vec<u8>,
+    pub derivation_path: DerivationPath,
+    pub key_id: SchnorrKeyId,
+}
+
+impl Payload<'_> for SignWithSchnorrArgs {}
+
+/// Struct used to return a Schnorr signature.
+/// ```text
+/// (record {
+///   signature : blob;
+/// })
+/// ```
+#[derive(CandidType, Deserialize, Debug)]
+pub struct SignWithSchnorrReply {
+    #[serde(with = "serde_bytes")]
+    pub signature: Vec<u8>,
+}
+
+impl Payload<'_> for SignWithSchnorrReply {}
+
+/// Represents the argument of the schnorr_public_key API.
+/// ```text
+/// (record {
+///   canister_id : opt canister_id;
+///   derivation_path : vec blob;
+///   key_id : schnorr_key_id;
+/// })
+/// ```
+#[derive(CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct SchnorrPublicKeyArgs {
+    pub canister_id: Option<CanisterId>,
+    pub derivation_path: DerivationPath,
+    pub key_id: SchnorrKeyId,
+}
+
+impl Payload<'_> for SchnorrPublicKeyArgs {}
+
+/// Represents the response of the schnorr_public_key API.
+/// ```text
+/// (record {
+///   public_key : blob;
+///   chain_code : blob;
+/// })
+/// ```
+#[derive(CandidType, Deserialize, Debug)]
+pub struct SchnorrPublicKeyResponse {
+    #[serde(with = "serde_bytes")]
+    pub public_key: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    pub chain_code: Vec<u8>,
+}
+
+impl Payload<'_> for SchnorrPublicKeyResponse {}
+
+/// Represents the argument of the vetkd_public_key API.
+/// ```text
+/// (record {
+///   canister_id : opt canister_id;
+///   derivation_path : vec blob;
+///   key_id : vetkd_key_id;
+/// })
+/// ```
+#[derive(CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct VetKdPublicKeyArgs {
+    pub canister_id: Option<CanisterId>,
+    pub derivation_path: DerivationPath,
+    pub key_id: VetKdKeyId,
+}
+
+impl Payload<'_> for VetKdPublicKeyArgs {}
+
+/// Represents the response of the vetkd_public_key API.
+/// ```text
+/// (record {
+///   public_key : blob;
+/// })
+/// ```
+#[derive(CandidType, Deserialize, Debug)]
+pub struct VetKdPublicKeyResult {
+    #[serde(with = "serde_bytes")]
+    pub public_key: Vec<u8>,
+}
+
+impl Payload<'_> for VetKdPublicKeyResult {}
+
+/// Represents the argument of the vetkd_derive_encrypted_key API.
+/// ```text
+/// (record {
+///   derivation_id : blob;
+///   public_key_derivation_path : vec blob;
+///   key_id : vetkd_key_id;
+///   encryption_public_key : blob;
+/// })
+/// ```
+#[derive(CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct VetKdDeriveEncryptedKeyArgs {
+    #[serde(with = "serde_bytes")]
+    pub derivation_id: Vec<u8>,
+    pub public_key_derivation_path: DerivationPath,
+    pub key_id: VetKdKeyId,
+    #[serde(with = "serde_bytes")]
+    pub encryption_public_key: Vec<u8>,
+}
+
+impl Payload<'_> for VetKdDeriveEncryptedKeyArgs {}
+
+/// Represents the response of the vetkd_derive_encrypted_key API.
+/// ```text
+/// (record {
+///   encrypted_key : blob;
+/// })
+/// ```
+#[derive(CandidType, Deserialize, Debug)]
+pub struct VetKdDeriveEncryptedKeyResult {
+    #[serde(with = "serde_bytes")]
+    pub encrypted_key: Vec<u8>,
+}
+
+impl Payload<'_> for VetKdDeriveEncryptedKeyResult {}
+
+/// Argument of the compute_initial_ecdsa_dealings API.
+/// `(record {
+///     key_id: ecdsa_key_id;
+///     subnet_id: principal;
+///     nodes: vec principal;
+///     registry_version: nat;
+/// })`
+#[derive(CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub struct ComputeInitialEcdsaDealingsArgs {
+    pub key_id: EcdsaKeyId,
+    pub subnet_id: SubnetId,
+    nodes: Vec<PrincipalId>,
+    registry_version: u64,
+}
+
+impl ComputeInitialEcdsaDealingsArgs {
+    pub fn new(
+        key_id: EcdsaKeyId,
+        subnet_id: SubnetId,
+        nodes: BTreeSet<NodeId>,
+        registry_version: RegistryVersion,
+    ) -> Self {
+        Self {
+            key_id,
+            subnet_id,
+            nodes: nodes.iter().map(|id| id.get()).collect(),
+            registry_version: registry_version.get(),
+        }
+    }
+
+    pub fn get_set_of_nodes(&self) -> Result<BTreeSet<NodeId>, UserError> {
+        let mut set = BTreeSet::<NodeId>::new();
+        for node_id in self.nodes.iter() {
+            if !set.insert(NodeId::new(*node_id)) {
+                return Err(UserError::new(
+                    ErrorCode::CanisterContractViolation,
+                    format!(
+                        "Expected a set of NodeIds. The NodeId {} is repeated",
+                        node_id
+                    ),
+                ));
+            }
+        }
+        Ok(set)
+    }
+
+    pub fn get_registry_version(&self) -> RegistryVersion {
+        RegistryVersion::new(self.registry_version)
+    }
+}
+
+impl Payload<'_> for ComputeInitialEcdsaDealingsArgs {}
+
+/// Struct used to return the xnet initial dealings.
+#[derive(Debug)]
+pub struct ComputeInitialEcdsaDealingsResponse {
+    pub initial_dkg_dealings: InitialIDkgDealings,
+}
+
+impl ComputeInitialEcdsaDealingsResponse {
+    pub fn encode(&self) -> Vec<u8> {
+        let serde_encoded_transcript_records = self.encode_with_serde_cbor();
+        Encode!(&serde_encoded_transcript_records).unwrap()
+    }
+
+    fn encode_with_serde_cbor(&self) -> Vec<u8> {
+        let transcript_records = (&self.initial_dkg_dealings,);
+        serde_cbor::to_vec(&transcript_records).unwrap()
+    }
+
+    pub fn decode(blob: &[u8]) -> Result<Self, UserError> {
+        let serde_encoded_transcript_records =
+            Decode!(blob, Vec<u8>).map_err(candid_error_to_user_error)?;
+        match serde_cbor::from_slice::<(InitialIDkgDealings,)>(&serde_encoded_transcript_records) {
+            Err(err) => Err(UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!("Payload deserialization error: '{}'", err),
+            )),
+            Ok((initial_dkg_dealings,)) => Ok(Self {
+                initial_dkg_dealings,
+            }),
+        }
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id : principal;
+///     replace_snapshot : opt blob;
+/// })`
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TakeCanisterSnapshotArgs {
+    canister_id: PrincipalId,
+    replace_snapshot: Option<serde_bytes::ByteBuf>,
+}
+
+impl TakeCanisterSnapshotArgs {
+    pub fn new(canister_id: CanisterId, replace_snapshot: Option<Vec<u8>>) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            replace_snapshot: replace_snapshot.map(serde_bytes::ByteBuf::from),
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.canister_id).unwrap()
+    }
+
+    pub fn replace_snapshot(&self) -> Option<Vec<u8>> {
+        self.replace_snapshot
+            .as_ref()
+            .map(|snapshot_id| snapshot_id.to_vec())
+    }
+}
+
+impl Payload<'_> for TakeCanisterSnapshotArgs {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id : principal;
+///     snapshot_id : blob;
+///     sender_canister_version : opt nat64;
+/// })`
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LoadCanisterSnapshotArgs {
+    canister_id: PrincipalId,
+    #[serde(with = "serde_bytes")]
+    snapshot_id: Vec<u8>,
+    sender_canister_version: Option<u64>,
+}
+
+impl LoadCanisterSnapshotArgs {
+    pub fn new(
+        canister_id: CanisterId,
+        snapshot_id: Vec<u8>,
+        sender_canister_version: Option<u64>,
+    ) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            snapshot_id,
+            sender_canister_version,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.canister_id).unwrap()
+    }
+
+    pub fn snapshot_id(&self) -> &[u8] {
+        &self.snapshot_id
+    }
+
+    pub fn get_sender_canister_version(&self) -> Option<u64> {
+        self.sender_canister_version
+    }
+}
+
+impl Payload<'_> for LoadCanisterSnapshotArgs {}
+
+/// Struct used for encoding/decoding `(record {canister_id : principal})`.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ListCanisterSnapshotArgs {
+    canister_id: PrincipalId,
+}
+
+impl ListCanisterSnapshotArgs {
+    pub fn new(canister_id: CanisterId) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+        }
+    }
 
-/// Represents the response of the ecdsa_public_key API.
-/// ```text
-/// (record {
-///   public_key : blob;
-///   chain_code : blob;
-/// })
-/// ```
-#[derive(CandidType, Deserialize, Debug)]
-pub struct ECDSAPublicKeyResponse {
-    pub public_key: Vec<u8>,
-    pub chain_code: Vec<u8>,
+    pub fn get_canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.canister_id).unwrap()
+    }
 }
 
-impl Payload<'_> for ECDSAPublicKeyResponse {}
+impl Payload<'_> for ListCanisterSnapshotArgs {}
 
-/// Argument of the compute_initial_ecdsa_dealings API.
+/// Struct used for encoding/decoding
 /// `(record {
-///     key_id: ecdsa_key_id;
-///     subnet_id: principal;
-///     nodes: vec principal;
-///     registry_version: nat;
+///     canister_id : principal;
+///     snapshot_id : blob;
 /// })`
-#[derive(CandidType, Deserialize, Debug, Eq, PartialEq)]
-pub struct ComputeInitialEcdsaDealingsArgs {
-    pub key_id: EcdsaKeyId,
-    pub subnet_id: SubnetId,
-    nodes: Vec<PrincipalId>,
-    registry_version: u64,
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DeleteCanisterSnapshotArgs {
+    canister_id: PrincipalId,
+    #[serde(with = "serde_bytes")]
+    snapshot_id: Vec<u8>,
 }
 
-impl ComputeInitialEcdsaDealingsArgs {
+impl DeleteCanisterSnapshotArgs {
+    pub fn new(canister_id: CanisterId, snapshot_id: Vec<u8>) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            snapshot_id,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.canister_id).unwrap()
+    }
+
+    pub fn snapshot_id(&self) -> &[u8] {
+        &self.snapshot_id
+    }
+}
+
+impl Payload<'_> for DeleteCanisterSnapshotArgs {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     id : blob;
+///     taken_at_timestamp : nat64;
+///     total_size : nat64;
+/// })`
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CanisterSnapshotResponse {
+    #[serde(with = "serde_bytes")]
+    pub id: Vec<u8>,
+    pub taken_at_timestamp: u64,
+    pub total_size: u64,
+}
+
+impl CanisterSnapshotResponse {
+    pub fn new(id: Vec<u8>, taken_at_timestamp: u64, total_size: u64) -> Self {
+        Self {
+            id,
+            taken_at_timestamp,
+            total_size,
+        }
+    }
+
+    pub fn snapshot_id(&self) -> &[u8] {
+        &self.id
+    }
+}
+
+impl Payload<'_> for CanisterSnapshotResponse {}
+
+/// Struct used for encoding/decoding the reply of `list_canister_snapshots`,
+/// i.e. `(vec snapshot)`.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+pub struct ListCanisterSnapshotsResponse(pub Vec<CanisterSnapshotResponse>);
+
+impl Payload<'_> for ListCanisterSnapshotsResponse {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id : principal;
+///     chunk : blob;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug)]
+pub struct UploadChunkArgs {
+    pub canister_id: PrincipalId,
+    #[serde(with = "serde_bytes")]
+    pub chunk: Vec<u8>,
+}
+
+impl Payload<'_> for UploadChunkArgs {}
+
+impl UploadChunkArgs {
+    pub fn new(canister_id: CanisterId, chunk: Vec<u8>) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            chunk,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.canister_id).unwrap()
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     hash : blob;
+/// })`
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ChunkHash {
+    #[serde(with = "serde_bytes")]
+    pub hash: Vec<u8>,
+}
+
+impl Payload<'_> for ChunkHash {}
+
+/// Struct used for encoding/decoding the reply of `upload_chunk`, which is
+/// the hash of the uploaded chunk.
+pub type UploadChunkReply = ChunkHash;
+
+/// Struct used for encoding/decoding the reply of `stored_chunks`,
+/// i.e. `(vec record { hash : blob })`.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+pub struct StoredChunksReply(pub Vec<ChunkHash>);
+
+impl Payload<'_> for StoredChunksReply {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id : principal;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug)]
+pub struct ClearChunkStoreArgs {
+    pub canister_id: PrincipalId,
+}
+
+impl Payload<'_> for ClearChunkStoreArgs {}
+
+impl ClearChunkStoreArgs {
+    pub fn new(canister_id: CanisterId) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.canister_id).unwrap()
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id : principal;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug)]
+pub struct StoredChunksArgs {
+    pub canister_id: PrincipalId,
+}
+
+impl Payload<'_> for StoredChunksArgs {}
+
+impl StoredChunksArgs {
+    pub fn new(canister_id: CanisterId) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.canister_id).unwrap()
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     mode : variant { install; reinstall; upgrade };
+///     target_canister : principal;
+///     store_canister : opt principal;
+///     chunk_hashes_list : vec record { hash : blob };
+///     wasm_module_hash : blob;
+///     arg : blob;
+///     sender_canister_version : opt nat64;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug)]
+pub struct InstallChunkedCodeArgs {
+    pub mode: CanisterInstallMode,
+    pub target_canister: PrincipalId,
+    pub store_canister: Option<PrincipalId>,
+    pub chunk_hashes_list: Vec<ChunkHash>,
+    #[serde(with = "serde_bytes")]
+    pub wasm_module_hash: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    pub arg: Vec<u8>,
+    pub sender_canister_version: Option<u64>,
+}
+
+impl std::fmt::Display for InstallChunkedCodeArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "InstallChunkedCodeArgs {{")?;
+        writeln!(f, "  mode: {:?}", &self.mode)?;
+        writeln!(f, "  target_canister: {:?}", &self.target_canister)?;
+        writeln!(f, "  store_canister: {:?}", &self.store_canister)?;
+        writeln!(
+            f,
+            "  chunk_hashes_list: <{:?} chunks>",
+            self.chunk_hashes_list.len()
+        )?;
+        writeln!(f, "  wasm_module_hash: {:?}", &self.wasm_module_hash)?;
+        writeln!(f, "  arg: <{:?} bytes>", self.arg.len())?;
+        writeln!(f, "}}")
+    }
+}
+
+impl Payload<'_> for InstallChunkedCodeArgs {}
+
+impl InstallChunkedCodeArgs {
     pub fn new(
-        key_id: EcdsaKeyId,
-        subnet_id: SubnetId,
-        nodes: BTreeSet<NodeId>,
-        registry_version: RegistryVersion,
+        mode: CanisterInstallMode,
+        target_canister: CanisterId,
+        store_canister: Option<CanisterId>,
+        chunk_hashes_list: Vec<Vec<u8>>,
+        wasm_module_hash: Vec<u8>,
+        arg: Vec<u8>,
     ) -> Self {
         Self {
-            key_id,
-            subnet_id,
-            nodes: nodes.iter().map(|id| id.get()).collect(),
-            registry_version: registry_version.get(),
+            mode,
+            target_canister: target_canister.into(),
+            store_canister: store_canister.map(|canister_id| canister_id.into()),
+            chunk_hashes_list: chunk_hashes_list
+                .into_iter()
+                .map(|hash| ChunkHash { hash })
+                .collect(),
+            wasm_module_hash,
+            arg,
+            sender_canister_version: None,
         }
     }
 
-    pub fn get_set_of_nodes(&self) -> Result<BTreeSet<NodeId>, UserError> {
-        let mut set = BTreeSet::<NodeId>::new();
-        for node_id in self.nodes.iter() {
-            if !set.insert(NodeId::new(*node_id)) {
-                return Err(UserError::new(
-                    ErrorCode::CanisterContractViolation,
-                    format!(
-                        "Expected a set of NodeIds. The NodeId {} is repeated",
-                        node_id
-                    ),
-                ));
-            }
-        }
-        Ok(set)
+    pub fn get_target_canister(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.target_canister).unwrap()
     }
 
-    pub fn get_registry_version(&self) -> RegistryVersion {
-        RegistryVersion::new(self.registry_version)
+    /// Returns the canister holding the chunks, which defaults to the target
+    /// canister.
+    pub fn get_store_canister(&self) -> CanisterId {
+        self.store_canister
+            .map(|canister_id| CanisterId::new(canister_id).unwrap())
+            .unwrap_or_else(|| self.get_target_canister())
+    }
+
+    pub fn get_sender_canister_version(&self) -> Option<u64> {
+        self.sender_canister_version
     }
 }
 
-impl Payload<'_> for ComputeInitialEcdsaDealingsArgs {}
+/// `CandidType` for `FetchCanisterLogsRequest`
+/// ```text
+/// record {
+///     canister_id: principal;
+/// }
+/// ```
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct FetchCanisterLogsRequest {
+    pub canister_id: PrincipalId,
+}
 
-/// Struct used to return the xnet initial dealings.
-#[derive(Debug)]
-pub struct ComputeInitialEcdsaDealingsResponse {
-    pub initial_dkg_dealings: InitialIDkgDealings,
+impl Payload<'_> for FetchCanisterLogsRequest {}
+
+impl FetchCanisterLogsRequest {
+    pub fn new(canister_id: CanisterId) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.canister_id).unwrap()
+    }
 }
 
-impl ComputeInitialEcdsaDealingsResponse {
-    pub fn encode(&self) -> Vec<u8> {
-        let serde_encoded_transcript_records = self.encode_with_serde_cbor();
-        Encode!(&serde_encoded_transcript_records).unwrap()
+/// `CandidType` for `CanisterLogRecord`
+/// ```text
+/// record {
+///     idx: nat64;
+///     timestamp_nanos: nat64;
+///     content: blob;
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, CandidType, Deserialize, Serialize)]
+pub struct CanisterLogRecord {
+    pub idx: u64,
+    pub timestamp_nanos: u64,
+    #[serde(with = "serde_bytes")]
+    pub content: Vec<u8>,
+}
+
+impl CanisterLogRecord {
+    /// Returns the number of bytes the record occupies in a canister log.
+    pub fn data_size(&self) -> usize {
+        size_of::<u64>() * 2 + self.content.len()
     }
+}
 
-    fn encode_with_serde_cbor(&self) -> Vec<u8> {
-        let transcript_records = (&self.initial_dkg_dealings,);
-        serde_cbor::to_vec(&transcript_records).unwrap()
+impl From<&CanisterLogRecord> for pb_canister_state_bits::CanisterLogRecord {
+    fn from(item: &CanisterLogRecord) -> Self {
+        Self {
+            idx: item.idx,
+            timestamp_nanos: item.timestamp_nanos,
+            content: item.content.clone(),
+        }
     }
+}
 
-    pub fn decode(blob: &[u8]) -> Result<Self, UserError> {
-        let serde_encoded_transcript_records =
-            Decode!(blob, Vec<u8>).map_err(candid_error_to_user_error)?;
-        match serde_cbor::from_slice::<(InitialIDkgDealings,)>(&serde_encoded_transcript_records) {
-            Err(err) => Err(UserError::new(
-                ErrorCode::CanisterContractViolation,
-                format!("Payload deserialization error: '{}'", err),
-            )),
-            Ok((initial_dkg_dealings,)) => Ok(Self {
-                initial_dkg_dealings,
-            }),
+impl From<pb_canister_state_bits::CanisterLogRecord> for CanisterLogRecord {
+    fn from(item: pb_canister_state_bits::CanisterLogRecord) -> Self {
+        Self {
+            idx: item.idx,
+            timestamp_nanos: item.timestamp_nanos,
+            content: item.content,
         }
     }
 }
 
+/// `CandidType` for `FetchCanisterLogsResponse`
+/// ```text
+/// record {
+///     canister_log_records: vec canister_log_record;
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, CandidType, Deserialize, Serialize)]
+pub struct FetchCanisterLogsResponse {
+    pub canister_log_records: Vec<CanisterLogRecord>,
+}
+
+impl Payload<'_> for FetchCanisterLogsResponse {}
+
 // Export the bitcoin types.
 pub use ic_btc_interface::{
     GetBalanceRequest as BitcoinGetBalanceArgs,
@@ -3,11 +3,15 @@
 
 mod canister_http;
 mod ingress;
+mod query_stats;
 mod self_validating;
 mod xnet;
 
 pub use self::canister_http::{CanisterHttpPayload, MAX_CANISTER_HTTP_PAYLOAD_SIZE};
 pub use self::ingress::{IngressPayload, IngressPayloadError};
+pub use self::query_stats::{
+    epoch_from_height, CanisterQueryStats, QueryStats, QueryStatsEpoch, QueryStatsPayload,
+};
 pub use self::self_validating::{SelfValidatingPayload, MAX_BITCOIN_PAYLOAD_IN_BYTES};
 pub use self::xnet::XNetPayload;
 
@@ -17,10 +21,11 @@ use super::{
     Height, Randomness, RegistryVersion, SubnetId, Time,
 };
 use crate::crypto::canister_threshold_sig::MasterEcdsaPublicKey;
+use crate::crypto::threshold_sig::ThresholdSigPublicKey;
 use ic_btc_types_internal::BitcoinAdapterResponse;
 #[cfg(test)]
 use ic_exhaustive_derive::ExhaustiveSet;
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaKeyId, SchnorrKeyId, VetKdKeyId};
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeMap, convert::TryInto};
 
@@ -38,6 +43,10 @@ pub struct Batch {
     pub randomness: Randomness,
     /// The ECDSA public key of the subnet.
     pub ecdsa_subnet_public_keys: BTreeMap<EcdsaKeyId, MasterEcdsaPublicKey>,
+    /// The threshold Schnorr public keys of the subnet.
+    pub schnorr_subnet_public_keys: BTreeMap<SchnorrKeyId, MasterEcdsaPublicKey>,
+    /// The NI-DKG public keys of the subnet that back its vetKD keys.
+    pub vetkd_subnet_public_keys: BTreeMap<VetKdKeyId, ThresholdSigPublicKey>,
     /// The version of the registry to be referenced when processing the batch.
     pub registry_version: RegistryVersion,
     /// A clock time to be used for processing messages.
@@ -81,14 +90,17 @@ pub struct BatchPayload {
     pub xnet: XNetPayload,
     pub self_validating: SelfValidatingPayload,
     pub canister_http: Vec<u8>,
+    pub query_stats: Vec<u8>,
 }
 
-/// Return ingress messages, xnet messages, and responses from the bitcoin adapter.
+/// Return ingress messages, xnet messages, responses from the bitcoin adapter
+/// and query statistics.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct BatchMessages {
     pub signed_ingress_msgs: Vec<SignedIngress>,
     pub certified_stream_slices: BTreeMap<SubnetId, CertifiedStreamSlice>,
     pub bitcoin_adapter_responses: Vec<BitcoinAdapterResponse>,
+    pub query_stats: Option<QueryStatsPayload>,
 }
 
 impl BatchPayload {
@@ -101,6 +113,10 @@ impl BatchPayload {
             signed_ingress_msgs: self.ingress.try_into()?,
             certified_stream_slices: self.xnet.stream_slices,
             bitcoin_adapter_responses: self.self_validating.0,
+            // The query stats section has been validated, so it always deserializes.
+            query_stats: QueryStatsPayload::deserialize(&self.query_stats)
+                .ok()
+                .flatten(),
         })
     }
 
@@ -109,6 +125,7 @@ impl BatchPayload {
             && self.xnet.stream_slices.is_empty()
             && self.self_validating.is_empty()
             && self.canister_http.is_empty()
+            && self.query_stats.is_empty()
     }
 }
 #[cfg(test)]
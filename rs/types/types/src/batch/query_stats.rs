@@ -0,0 +1,223 @@
+use crate::{node_id_into_protobuf, node_id_try_from_option, CanisterId, Height, NodeId, NumBytes};
+use ic_protobuf::{
+    proxy::{try_from_option_field, ProxyDecodeError},
+    types::v1 as pb,
+};
+use phantom_newtype::AmountOf;
+use prost::{bytes::BufMut, Message};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+pub struct QueryStatsEpochTag {}
+/// The epoch in which query statistics are collected.
+///
+/// Epochs are consecutive ranges of `epoch_length` heights, see
+/// [`epoch_from_height`].
+pub type QueryStatsEpoch = AmountOf<QueryStatsEpochTag, u64>;
+
+/// Returns the epoch that contains the given height.
+pub fn epoch_from_height(height: Height, epoch_length: u64) -> QueryStatsEpoch {
+    QueryStatsEpoch::from(height.get() / epoch_length.max(1))
+}
+
+/// Statistics about the query calls executed on a single canister.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct QueryStats {
+    pub num_calls: u32,
+    pub num_instructions: u64,
+    pub ingress_payload_size: u64,
+    pub egress_payload_size: u64,
+}
+
+impl QueryStats {
+    /// Adds `other` to `self`, saturating at the numeric bounds.
+    pub fn saturating_accumulate(&mut self, other: &QueryStats) {
+        self.num_calls = self.num_calls.saturating_add(other.num_calls);
+        self.num_instructions = self.num_instructions.saturating_add(other.num_instructions);
+        self.ingress_payload_size = self
+            .ingress_payload_size
+            .saturating_add(other.ingress_payload_size);
+        self.egress_payload_size = self
+            .egress_payload_size
+            .saturating_add(other.egress_payload_size);
+    }
+}
+
+/// The [`QueryStats`] of a single canister, as collected by one node.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CanisterQueryStats {
+    pub canister_id: CanisterId,
+    pub stats: QueryStats,
+}
+
+/// The query stats section of a block.
+///
+/// Each block proposer reports the statistics it collected locally during
+/// `epoch`. The statistics are aggregated deterministically once all nodes
+/// had the chance to report them.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct QueryStatsPayload {
+    pub epoch: QueryStatsEpoch,
+    pub proposer: NodeId,
+    pub stats: Vec<CanisterQueryStats>,
+}
+
+impl QueryStatsPayload {
+    /// Serializes the payload, dropping canister entries that would make it
+    /// exceed `max_size`.
+    ///
+    /// Returns an empty vector if the payload does not contain any entries
+    /// after the truncation.
+    pub fn serialize_with_limit(&self, max_size: NumBytes) -> Vec<u8> {
+        let mut payload = pb::QueryStatsPayload {
+            epoch: self.epoch.get(),
+            proposer: Some(node_id_into_protobuf(self.proposer)),
+            canister_stats: vec![],
+        };
+        let max_size = max_size.get() as usize;
+        let mut size = payload.encoded_len();
+        for stats in &self.stats {
+            let stats = pb::CanisterQueryStats::from(stats);
+            let stats_size = prost::encoding::message::encoded_len(3, &stats);
+            if size + stats_size > max_size {
+                break;
+            }
+            size += stats_size;
+            payload.canister_stats.push(stats);
+        }
+
+        if payload.canister_stats.is_empty() {
+            return vec![];
+        }
+
+        let mut buffer = vec![].limit(max_size);
+        match payload.encode(&mut buffer) {
+            Ok(()) => buffer.into_inner(),
+            Err(_) => vec![],
+        }
+    }
+
+    /// Deserializes a payload. An empty slice corresponds to an empty payload.
+    pub fn deserialize(data: &[u8]) -> Result<Option<Self>, ProxyDecodeError> {
+        if data.is_empty() {
+            return Ok(None);
+        }
+        let payload = pb::QueryStatsPayload::decode(data).map_err(ProxyDecodeError::DecodeError)?;
+        Self::try_from(payload).map(Some)
+    }
+}
+
+impl From<&CanisterQueryStats> for pb::CanisterQueryStats {
+    fn from(stats: &CanisterQueryStats) -> Self {
+        Self {
+            canister_id: Some(pb::CanisterId::from(stats.canister_id)),
+            num_calls: stats.stats.num_calls,
+            num_instructions: stats.stats.num_instructions,
+            ingress_payload_size: stats.stats.ingress_payload_size,
+            egress_payload_size: stats.stats.egress_payload_size,
+        }
+    }
+}
+
+impl TryFrom<pb::CanisterQueryStats> for CanisterQueryStats {
+    type Error = ProxyDecodeError;
+
+    fn try_from(stats: pb::CanisterQueryStats) -> Result<Self, Self::Error> {
+        Ok(Self {
+            canister_id: try_from_option_field(
+                stats.canister_id,
+                "CanisterQueryStats::canister_id",
+            )?,
+            stats: QueryStats {
+                num_calls: stats.num_calls,
+                num_instructions: stats.num_instructions,
+                ingress_payload_size: stats.ingress_payload_size,
+                egress_payload_size: stats.egress_payload_size,
+            },
+        })
+    }
+}
+
+impl From<&QueryStatsPayload> for pb::QueryStatsPayload {
+    fn from(payload: &QueryStatsPayload) -> Self {
+        Self {
+            epoch: payload.epoch.get(),
+            proposer: Some(node_id_into_protobuf(payload.proposer)),
+            canister_stats: payload
+                .stats
+                .iter()
+                .map(pb::CanisterQueryStats::from)
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<pb::QueryStatsPayload> for QueryStatsPayload {
+    type Error = ProxyDecodeError;
+
+    fn try_from(payload: pb::QueryStatsPayload) -> Result<Self, Self::Error> {
+        Ok(Self {
+            epoch: QueryStatsEpoch::from(payload.epoch),
+            proposer: node_id_try_from_option(payload.proposer)?,
+            stats: payload
+                .canister_stats
+                .into_iter()
+                .map(CanisterQueryStats::try_from)
+                .collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_base_types::PrincipalId;
+
+    fn payload(num_canisters: u64) -> QueryStatsPayload {
+        QueryStatsPayload {
+            epoch: QueryStatsEpoch::from(7),
+            proposer: NodeId::from(PrincipalId::new_node_test_id(3)),
+            stats: (0..num_canisters)
+                .map(|i| CanisterQueryStats {
+                    canister_id: CanisterId::from_u64(i),
+                    stats: QueryStats {
+                        num_calls: i as u32,
+                        num_instructions: 1_000 * i,
+                        ingress_payload_size: 10 * i,
+                        egress_payload_size: 20 * i,
+                    },
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn query_stats_payload_round_trip() {
+        let payload = payload(10);
+        let bytes = payload.serialize_with_limit(NumBytes::new(1024 * 1024));
+        assert_eq!(
+            QueryStatsPayload::deserialize(&bytes).unwrap(),
+            Some(payload)
+        );
+    }
+
+    #[test]
+    fn empty_query_stats_payload_is_empty() {
+        let bytes = payload(0).serialize_with_limit(NumBytes::new(1024 * 1024));
+        assert!(bytes.is_empty());
+        assert_eq!(QueryStatsPayload::deserialize(&bytes).unwrap(), None);
+    }
+
+    #[test]
+    fn query_stats_payload_respects_size_limit() {
+        let payload = payload(1_000);
+        let max_size = NumBytes::new(1_000);
+        let bytes = payload.serialize_with_limit(max_size);
+        assert!(!bytes.is_empty());
+        assert!(bytes.len() as u64 <= max_size.get());
+
+        let truncated = QueryStatsPayload::deserialize(&bytes).unwrap().unwrap();
+        assert!(truncated.stats.len() < payload.stats.len());
+        assert_eq!(truncated.stats[..], payload.stats[..truncated.stats.len()]);
+    }
+}
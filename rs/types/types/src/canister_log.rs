@@ -0,0 +1,139 @@
+//! Bounded buffer of canister log records, filled by `ic0.debug_print` and
+//! trap messages and returned by the `fetch_canister_logs` management
+//! canister method.
+
+use ic_ic00_types::CanisterLogRecord;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// The maximum total size of the records kept in a canister log, in bytes.
+pub const MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE: usize = 4 * 1024;
+
+/// The size of the fixed part of a log record (its index and timestamp).
+const RECORD_OVERHEAD: usize = std::mem::size_of::<u64>() * 2;
+
+/// A ring buffer of canister log records.
+///
+/// The total size of the records is bounded by
+/// `MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE`: adding a record evicts the oldest
+/// records until the new one fits. Record indices grow monotonically and are
+/// never reused, so that callers can detect evicted records.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanisterLog {
+    next_idx: u64,
+    records: VecDeque<CanisterLogRecord>,
+    size: usize,
+}
+
+impl CanisterLog {
+    /// Creates a canister log from its persisted parts.
+    pub fn new(next_idx: u64, records: Vec<CanisterLogRecord>) -> Self {
+        let size = records.iter().map(|r| r.data_size()).sum();
+        Self {
+            next_idx,
+            records: records.into(),
+            size,
+        }
+    }
+
+    /// Returns the index that will be assigned to the next record.
+    pub fn next_idx(&self) -> u64 {
+        self.next_idx
+    }
+
+    /// Returns the stored records, oldest first.
+    pub fn records(&self) -> &VecDeque<CanisterLogRecord> {
+        &self.records
+    }
+
+    /// Returns the total size of the stored records, in bytes.
+    pub fn used_space(&self) -> usize {
+        self.size
+    }
+
+    /// Adds a new record, evicting the oldest records if the buffer is full.
+    /// Content that does not fit into an empty buffer is truncated.
+    pub fn add_record(&mut self, timestamp_nanos: u64, mut content: Vec<u8>) {
+        content.truncate(MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE - RECORD_OVERHEAD);
+        let record = CanisterLogRecord {
+            idx: self.next_idx,
+            timestamp_nanos,
+            content,
+        };
+        self.next_idx += 1;
+        self.size += record.data_size();
+        self.records.push_back(record);
+        while self.size > MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE {
+            match self.records.pop_front() {
+                Some(evicted) => self.size -= evicted.data_size(),
+                None => break,
+            }
+        }
+    }
+
+    /// Moves all records of `delta` into this log, assigning them new indices.
+    pub fn append_delta(&mut self, delta: &mut CanisterLog) {
+        for record in std::mem::take(&mut delta.records) {
+            self.add_record(record.timestamp_nanos, record.content);
+        }
+        delta.size = 0;
+    }
+
+    /// Removes all records. The record index keeps growing.
+    pub fn clear(&mut self) {
+        self.records.clear();
+        self.size = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_get_increasing_indices() {
+        let mut log = CanisterLog::default();
+        log.add_record(10, b"a".to_vec());
+        log.add_record(20, b"b".to_vec());
+        let indices: Vec<_> = log.records().iter().map(|r| r.idx).collect();
+        assert_eq!(indices, vec![0, 1]);
+        assert_eq!(log.next_idx(), 2);
+        assert_eq!(log.used_space(), 2 * (RECORD_OVERHEAD + 1));
+    }
+
+    #[test]
+    fn oldest_records_are_evicted_when_full() {
+        let mut log = CanisterLog::default();
+        let content_size = MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE / 4;
+        for i in 0..10 {
+            log.add_record(i, vec![0; content_size]);
+            assert!(log.used_space() <= MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE);
+        }
+        assert_eq!(log.records().len(), 3);
+        assert_eq!(log.records().front().unwrap().idx, 7);
+        assert_eq!(log.next_idx(), 10);
+    }
+
+    #[test]
+    fn oversized_content_is_truncated() {
+        let mut log = CanisterLog::default();
+        log.add_record(0, vec![0; 2 * MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE]);
+        assert_eq!(log.records().len(), 1);
+        assert_eq!(log.used_space(), MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE);
+    }
+
+    #[test]
+    fn append_delta_reassigns_indices() {
+        let mut log = CanisterLog::new(5, vec![]);
+        let mut delta = CanisterLog::default();
+        delta.add_record(1, b"x".to_vec());
+        delta.add_record(2, b"y".to_vec());
+
+        log.append_delta(&mut delta);
+
+        assert!(delta.records().is_empty());
+        assert_eq!(delta.used_space(), 0);
+        let indices: Vec<_> = log.records().iter().map(|r| r.idx).collect();
+        assert_eq!(indices, vec![5, 6]);
+    }
+}
@@ -1342,6 +1342,7 @@ impl From<&Block> for pb::Block {
             ingress_payload,
             self_validating_payload,
             canister_http_payload_bytes,
+            query_stats_payload_bytes,
             ecdsa_payload,
         ) = if payload.is_summary() {
             (
@@ -1350,6 +1351,7 @@ impl From<&Block> for pb::Block {
                 None,
                 None,
                 vec![],
+                vec![],
                 payload
                     .as_summary()
                     .ecdsa
@@ -1364,6 +1366,7 @@ impl From<&Block> for pb::Block {
                 Some(pb::IngressPayload::from(&batch.ingress)),
                 Some(pb::SelfValidatingPayload::from(&batch.self_validating)),
                 batch.canister_http.clone(),
+                batch.query_stats.clone(),
                 payload.as_data().ecdsa.as_ref().map(|ecdsa| ecdsa.into()),
             )
         };
@@ -1381,6 +1384,7 @@ impl From<&Block> for pb::Block {
             self_validating_payload,
             canister_http_payload: None,
             canister_http_payload_bytes,
+            query_stats_payload_bytes,
             ecdsa_payload,
             payload_hash: block.payload.get_hash().clone().get().0,
         }
@@ -1412,6 +1416,7 @@ impl TryFrom<pb::Block> for Block {
                 .transpose()?
                 .unwrap_or_default(),
             canister_http: block.canister_http_payload_bytes,
+            query_stats: block.query_stats_payload_bytes,
         };
         let payload = match dkg_payload {
             dkg::Payload::Summary(summary) => {
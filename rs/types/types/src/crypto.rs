@@ -145,6 +145,8 @@ pub enum AlgorithmId {
     RsaSha256 = 14,
     ThresholdEcdsaSecp256k1 = 15,
     MegaSecp256k1 = 16,
+    ThresholdSchnorrBip340 = 17,
+    ThresholdEd25519 = 18,
 }
 
 impl AlgorithmId {
@@ -201,6 +203,8 @@ impl From<i32> for AlgorithmId {
             14 => AlgorithmId::RsaSha256,
             15 => AlgorithmId::ThresholdEcdsaSecp256k1,
             16 => AlgorithmId::MegaSecp256k1,
+            17 => AlgorithmId::ThresholdSchnorrBip340,
+            18 => AlgorithmId::ThresholdEd25519,
             _ => AlgorithmId::Placeholder,
         }
     }
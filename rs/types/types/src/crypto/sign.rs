@@ -17,12 +17,13 @@ use crate::crypto::hash::{
     DOMAIN_RANDOM_BEACON_CONTENT, DOMAIN_RANDOM_TAPE_CONTENT, DOMAIN_SIGNED_IDKG_DEALING,
 };
 use crate::crypto::SignedBytesWithoutDomainSeparator;
-use crate::messages::{Delegation, MessageId, WebAuthnEnvelope};
+use crate::messages::{Delegation, MessageId, QueryResponseHash, WebAuthnEnvelope};
 use crate::onchain_observability::Report as OnchainObservabilityReport;
 use std::convert::TryFrom;
 
 const SIG_DOMAIN_IC_REQUEST_AUTH_DELEGATION: &str = "ic-request-auth-delegation";
 const SIG_DOMAIN_IC_REQUEST: &str = "ic-request";
+const SIG_DOMAIN_IC_RESPONSE: &str = "ic-response";
 
 /// `Signable` represents an object whose byte-vector representation
 /// can be signed using a digital signature scheme.
@@ -72,6 +73,7 @@ mod private {
     impl SignatureDomainSeal for Delegation {}
     impl SignatureDomainSeal for CanisterHttpResponseMetadata {}
     impl SignatureDomainSeal for MessageId {}
+    impl SignatureDomainSeal for QueryResponseHash {}
     impl SignatureDomainSeal for CertificationContent {}
     impl SignatureDomainSeal for CatchUpContent {}
     impl SignatureDomainSeal for CatchUpContentProtobufBytes {}
@@ -161,6 +163,12 @@ impl SignatureDomain for MessageId {
     }
 }
 
+impl SignatureDomain for QueryResponseHash {
+    fn domain(&self) -> Vec<u8> {
+        domain_with_prepended_length(SIG_DOMAIN_IC_RESPONSE)
+    }
+}
+
 impl SignatureDomain for CertificationContent {
     fn domain(&self) -> Vec<u8> {
         domain_with_prepended_length(DOMAIN_CERTIFICATION_CONTENT)
@@ -0,0 +1,122 @@
+//! Instruction profiles of canister executions, recorded when canister
+//! profiling is enabled in the embedder and rendered as folded stacks for
+//! flamegraph tools.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// The instructions spent by a single Wasm execution, broken down by call
+/// stack.
+///
+/// A call stack is a list of Wasm function indices, outermost first, as they
+/// appear in the function index space of the canister module (i.e. the indices
+/// used by its `name` section). The instructions recorded for a call stack are
+/// those spent in the innermost function itself, excluding its callees.
+/// Instructions spent outside of any function (e.g. by the system) are
+/// recorded for the empty call stack.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionProfile {
+    entry_point: String,
+    stacks: BTreeMap<Vec<u32>, u64>,
+}
+
+impl ExecutionProfile {
+    /// Creates an empty profile of the given entry point, e.g.
+    /// `canister_update transfer` or `canister_heartbeat`.
+    pub fn new(entry_point: String) -> Self {
+        Self {
+            entry_point,
+            stacks: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the entry point of the profiled execution.
+    pub fn entry_point(&self) -> &str {
+        &self.entry_point
+    }
+
+    /// Adds instructions spent in the innermost function of the given call
+    /// stack.
+    pub fn record(&mut self, stack: &[u32], instructions: u64) {
+        if instructions > 0 {
+            *self.stacks.entry(stack.to_vec()).or_default() += instructions;
+        }
+    }
+
+    /// Returns the instructions spent in each call stack.
+    pub fn stacks(&self) -> &BTreeMap<Vec<u32>, u64> {
+        &self.stacks
+    }
+
+    /// Returns the total number of instructions of the execution.
+    pub fn total_instructions(&self) -> u64 {
+        self.stacks.values().sum()
+    }
+
+    /// Returns the instructions spent in each function itself, excluding its
+    /// callees, summed over all call stacks.
+    pub fn instructions_per_function(&self) -> BTreeMap<u32, u64> {
+        let mut result = BTreeMap::new();
+        for (stack, instructions) in &self.stacks {
+            if let Some(function_index) = stack.last() {
+                *result.entry(*function_index).or_default() += instructions;
+            }
+        }
+        result
+    }
+
+    /// Renders the profile in the folded stack format understood by
+    /// `flamegraph.pl` and `inferno`: one line per call stack with the frames
+    /// separated by `;`, followed by the number of instructions. The entry
+    /// point is the root frame of all stacks. Functions without an entry in
+    /// `function_names` are rendered as `func[<index>]`.
+    pub fn to_folded_stacks(&self, function_names: &BTreeMap<u32, String>) -> String {
+        let mut result = String::new();
+        for (stack, instructions) in &self.stacks {
+            result.push_str(&frame_name(&self.entry_point));
+            for function_index in stack {
+                result.push(';');
+                match function_names.get(function_index) {
+                    Some(name) => result.push_str(&frame_name(name)),
+                    None => write!(result, "func[{}]", function_index).unwrap(),
+                }
+            }
+            writeln!(result, " {}", instructions).unwrap();
+        }
+        result
+    }
+}
+
+// Frame names must not contain the frame separator.
+fn frame_name(name: &str) -> String {
+    name.replace(';', ":")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folded_stacks_use_function_names() {
+        let mut profile = ExecutionProfile::new("canister_update transfer".to_string());
+        profile.record(&[3], 10);
+        profile.record(&[3, 5], 20);
+        profile.record(&[3, 5], 5);
+        profile.record(&[3, 7], 0);
+        profile.record(&[], 2);
+
+        let function_names = BTreeMap::from([(3, "transfer".to_string())]);
+        assert_eq!(
+            profile.to_folded_stacks(&function_names),
+            "canister_update transfer 2\n\
+             canister_update transfer;transfer 10\n\
+             canister_update transfer;transfer;func[5] 25\n"
+        );
+        assert_eq!(profile.total_instructions(), 37);
+        assert_eq!(
+            profile.instructions_per_function(),
+            BTreeMap::from([(3, 10), (5, 25)])
+        );
+    }
+}
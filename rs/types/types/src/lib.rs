@@ -67,9 +67,11 @@ pub mod artifact;
 pub mod artifact_kind;
 pub mod batch;
 pub mod canister_http;
+pub mod canister_log;
 pub mod chunkable;
 pub mod consensus;
 pub mod crypto;
+pub mod execution_profile;
 pub mod filetree_sync;
 pub mod funds;
 pub mod ingress;
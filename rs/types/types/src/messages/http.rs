@@ -18,7 +18,12 @@ use maplit::btreemap;
 #[cfg(test)]
 use proptest_derive::Arbitrary;
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeSet, convert::TryFrom, error::Error, fmt};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    error::Error,
+    fmt,
+};
 
 #[cfg(test)]
 mod tests;
@@ -580,6 +585,7 @@ pub enum RawHttpRequestVal {
     String(String),
     U64(u64),
     Array(Vec<RawHttpRequestVal>),
+    Map(BTreeMap<String, RawHttpRequestVal>),
 }
 
 /// The reply to an update call.
@@ -612,6 +618,76 @@ pub struct HttpQueryResponseReply {
     pub arg: Blob,
 }
 
+/// A query response together with the signatures of the replicas that
+/// executed the query, as defined in
+/// `<https://internetcomputer.org/docs/current/references/ic-interface-spec#http-query>`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HttpSignedQueryResponse {
+    #[serde(flatten)]
+    pub response: HttpQueryResponse,
+    pub signatures: Vec<NodeSignature>,
+}
+
+/// A signature of a replica over a [`QueryResponseHash`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NodeSignature {
+    /// The time at which the signature was created, in nanoseconds since
+    /// the UNIX epoch.
+    pub timestamp: u64,
+    /// The signature created with the node signing key.
+    pub signature: Blob,
+    /// The principal of the node that created the signature.
+    pub identity: Blob,
+}
+
+/// The representation-independent hash of a query response, the ID of the
+/// request it answers and the time at which it is signed. This is what a
+/// replica signs with its node signing key.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct QueryResponseHash([u8; 32]);
+
+impl QueryResponseHash {
+    pub fn new(response: &HttpQueryResponse, request_id: &MessageId, timestamp: Time) -> Self {
+        use RawHttpRequestVal::*;
+        let mut map = btreemap! {
+            "request_id".to_string() => Bytes(request_id.as_bytes().to_vec()),
+            "timestamp".to_string() => U64(timestamp.as_nanos_since_unix_epoch()),
+        };
+        match response {
+            HttpQueryResponse::Replied { reply } => {
+                map.insert("status".to_string(), String("replied".to_string()));
+                map.insert(
+                    "reply".to_string(),
+                    Map(btreemap! {
+                        "arg".to_string() => Bytes(reply.arg.0.clone()),
+                    }),
+                );
+            }
+            HttpQueryResponse::Rejected {
+                error_code,
+                reject_code,
+                reject_message,
+            } => {
+                map.insert("status".to_string(), String("rejected".to_string()));
+                map.insert("reject_code".to_string(), U64(*reject_code));
+                map.insert("reject_message".to_string(), String(reject_message.clone()));
+                map.insert("error_code".to_string(), String(error_code.clone()));
+            }
+        }
+        Self(hash_of_map(&map))
+    }
+
+    pub fn get_ref(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl SignedBytesWithoutDomainSeparator for QueryResponseHash {
+    fn as_signed_bytes_without_domain_separator(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+}
+
 /// The response to a `read_state` request.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct HttpReadStateResponse {
@@ -555,8 +555,9 @@ mod cbor_serialization {
 
     use crate::messages::http::btreemap;
     use crate::messages::{
-        Blob, Delegation, HttpQueryResponse, HttpQueryResponseReply, HttpStatusResponse,
-        ReplicaHealthStatus, SignedDelegation,
+        Blob, Delegation, HttpQueryResponse, HttpQueryResponseReply, HttpSignedQueryResponse,
+        HttpStatusResponse, MessageId, NodeSignature, QueryResponseHash, ReplicaHealthStatus,
+        SignedDelegation,
     };
     use crate::{time::UNIX_EPOCH, AmountOf};
     use pretty_assertions::assert_eq;
@@ -619,6 +620,75 @@ mod cbor_serialization {
         );
     }
 
+    #[test]
+    fn encoding_signed_query_response() {
+        assert_cbor_ser_equal(
+            &HttpSignedQueryResponse {
+                response: HttpQueryResponse::Replied {
+                    reply: HttpQueryResponseReply {
+                        arg: Blob(b"some_bytes".to_vec()),
+                    },
+                },
+                signatures: vec![NodeSignature {
+                    timestamp: 1,
+                    signature: Blob(b"signature".to_vec()),
+                    identity: Blob(b"node".to_vec()),
+                }],
+            },
+            Value::Map(btreemap! {
+                text("status") => text("replied"),
+                text("reply") => Value::Map(btreemap!{
+                    text("arg") => bytes(b"some_bytes")
+                }),
+                text("signatures") => Value::Array(vec![Value::Map(btreemap!{
+                    text("timestamp") => int(1),
+                    text("signature") => bytes(b"signature"),
+                    text("identity") => bytes(b"node"),
+                })]),
+            }),
+        );
+    }
+
+    #[test]
+    fn query_response_hash_depends_on_request_id_and_timestamp() {
+        let response = HttpQueryResponse::Replied {
+            reply: HttpQueryResponseReply {
+                arg: Blob(b"some_bytes".to_vec()),
+            },
+        };
+        let request_id = MessageId::from([1; 32]);
+        let hash = QueryResponseHash::new(&response, &request_id, UNIX_EPOCH);
+
+        assert_eq!(
+            hash,
+            QueryResponseHash::new(&response, &request_id, UNIX_EPOCH)
+        );
+        assert_ne!(
+            hash,
+            QueryResponseHash::new(&response, &MessageId::from([2; 32]), UNIX_EPOCH)
+        );
+        assert_ne!(
+            hash,
+            QueryResponseHash::new(
+                &response,
+                &request_id,
+                UNIX_EPOCH + std::time::Duration::from_nanos(1)
+            )
+        );
+        assert_ne!(
+            hash,
+            QueryResponseHash::new(
+                &HttpQueryResponse::Rejected {
+                    reject_code: 1,
+                    reject_message: "system error".to_string(),
+                    error_code: "IC500".to_string(),
+                },
+                &request_id,
+                UNIX_EPOCH
+            )
+        );
+    }
+
     #[test]
     fn encoding_status_without_root_key() {
         assert_cbor_ser_equal(
@@ -116,6 +116,13 @@ where
         self.cache.is_empty()
     }
 
+    /// Returns an iterator over the key-value pairs in the cache, ordered from
+    /// the most-recently used to the least-recently used. Iterating does not
+    /// change the order of the items.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.cache.iter()
+    }
+
     /// Evicts as many items as needed to restore the capacity guarantee.
     /// Returns the vector of evicted key-value pairs.
     fn evict(&mut self) -> Vec<(K, V)> {
@@ -395,4 +402,17 @@ mod tests {
         assert_eq!(0, lru.len());
         assert!(lru.is_empty());
     }
+
+    #[test]
+    fn lru_cache_iter() {
+        let mut lru = LruCache::<Key, ValueSize>::new(NumBytes::new(10));
+        lru.push(Key(0), ValueSize(0, 4));
+        lru.push(Key(1), ValueSize(1, 4));
+        lru.get(&Key(0));
+        let keys: Vec<_> = lru.iter().map(|(key, _)| key.0).collect();
+        assert_eq!(keys, vec![0, 1]);
+        lru.push(Key(2), ValueSize(2, 4));
+        let keys: Vec<_> = lru.iter().map(|(key, _)| key.0).collect();
+        assert_eq!(keys, vec![2, 0]);
+    }
 }